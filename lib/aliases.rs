@@ -0,0 +1,741 @@
+//! Printer aliases
+//!
+//! An alias is a logical printer name ("RECEIPT", "SHIPPING_LABEL") mapped to
+//! a concrete printer, with default job options applied to every job
+//! submitted through the alias. Aliases are accepted wherever a printer name
+//! is, so application code doesn't hardcode environment-specific names.
+//!
+//! When an alias file is set, the registry is loaded from it and every change
+//! is written back, so the mapping survives restarts. The file can also set
+//! the library-wide default job options, and default job options and tags
+//! of concrete printers. It is JSON:
+//!
+//! ```json
+//! {
+//!   "aliases": {
+//!     "RECEIPT": { "printer": "EPSON TM-T88V", "defaultOptions": { "copies": "2" } }
+//!   },
+//!   "printerDefaults": { "EPSON TM-T88V": { "media": "oe_roll-80mm_80x297mm" } },
+//!   "printerTags": { "HP LaserJet 4": ["3rd-floor", "duplex"] },
+//!   "defaultJobOptions": { "media": "A4" }
+//! }
+//! ```
+//!
+//! The file is re-read by `reload()`, or automatically when it changes on
+//! disk if it is watched, so long-running processes can be reconfigured
+//! without a restart. Every change is reported to the change callback.
+
+use crate::core::{LibraryConfig, PrinterCore};
+use crate::supervisor::{self, RestartPolicy, SupervisedTask};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// How often a watched alias file is checked for changes
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+lazy_static::lazy_static! {
+    static ref REGISTRY: RwLock<Registry> = RwLock::new(Registry::default());
+    static ref CHANGE_CALLBACK: Mutex<Option<ConfigChangeCallback>> = Mutex::new(None);
+    static ref WATCHER: Mutex<Option<SupervisedTask>> = Mutex::new(None);
+}
+
+#[derive(Default)]
+struct Registry {
+    /// Aliases and default job options, as persisted to the file
+    config: ConfigFile,
+    /// File the registry is persisted to, if any
+    file: Option<PathBuf>,
+    /// Modification time of the file when it was last read or written
+    file_modified: Option<SystemTime>,
+    /// Whether the file is re-read when it changes on disk
+    watch: bool,
+}
+
+/// A logical printer name mapped to a concrete printer
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrinterAlias {
+    pub name: String,
+    pub printer: String,
+    /// Raw job properties applied to jobs submitted through the alias,
+    /// taking precedence over the library-wide defaults
+    pub default_options: HashMap<String, String>,
+}
+
+/// Contents of an alias file
+#[derive(Clone, Debug, Default, PartialEq)]
+struct ConfigFile {
+    aliases: HashMap<String, PrinterAlias>,
+    /// Raw job properties by concrete printer name
+    printer_defaults: HashMap<String, HashMap<String, String>>,
+    /// Tags by concrete printer name, sorted
+    printer_tags: HashMap<String, Vec<String>>,
+    /// Library-wide default job options set by the file, if any
+    default_job_options: Option<HashMap<String, String>>,
+}
+
+/// What triggered a configuration change
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChangeSource {
+    /// An alias was defined or removed, or printer defaults were set
+    Api,
+    /// The alias file was set, or changed on disk while watched
+    File,
+    /// `reload()` was called
+    Reload,
+}
+
+impl ChangeSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeSource::Api => "api",
+            ChangeSource::File => "file",
+            ChangeSource::Reload => "reload",
+        }
+    }
+}
+
+/// A change to the alias configuration
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigChange {
+    pub source: ChangeSource,
+    /// Aliases that were added, removed or changed, sorted by name
+    pub aliases: Vec<String>,
+    /// Printers whose default job options or tags changed, sorted by name
+    pub printers: Vec<String>,
+    /// Whether the library-wide default job options changed
+    pub default_job_options: bool,
+    pub timestamp: SystemTime,
+}
+
+pub type ConfigChangeCallback = Arc<dyn Fn(&ConfigChange) + Send + Sync>;
+
+/// Read a string map, such as a set of job options
+fn string_map(
+    value: &Value,
+    describe: impl Fn() -> String,
+) -> Result<HashMap<String, String>, String> {
+    let object = value
+        .as_object()
+        .ok_or_else(|| format!("{} must be an object", describe()))?;
+    object
+        .iter()
+        .map(|(key, value)| match value.as_str() {
+            Some(value) => Ok((key.clone(), value.to_string())),
+            None => Err(format!(
+                "option '{}' of {} must be a string",
+                key,
+                describe()
+            )),
+        })
+        .collect()
+}
+
+/// Read an alias file
+fn load(path: &Path) -> Result<ConfigFile, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read alias file '{}': {}", path.display(), e))?;
+    let invalid = |reason: &str| format!("Invalid alias file '{}': {}", path.display(), reason);
+
+    let root: Value = serde_json::from_str(&contents).map_err(|e| invalid(&e.to_string()))?;
+    let mut config = ConfigFile::default();
+    if let Some(options) = root.get("defaultJobOptions") {
+        let options =
+            string_map(options, || "defaultJobOptions".to_string()).map_err(|e| invalid(&e))?;
+        config.default_job_options = Some(options);
+    }
+    match root.get("printerDefaults") {
+        None => {}
+        Some(Value::Object(entries)) => {
+            for (printer, options) in entries {
+                let options = string_map(options, || format!("printerDefaults of '{}'", printer))
+                    .map_err(|e| invalid(&e))?;
+                config.printer_defaults.insert(printer.clone(), options);
+            }
+        }
+        Some(_) => return Err(invalid("\"printerDefaults\" must be an object")),
+    }
+    match root.get("printerTags") {
+        None => {}
+        Some(Value::Object(entries)) => {
+            for (printer, tags) in entries {
+                let tags = tags
+                    .as_array()
+                    .and_then(|tags| tags.iter().map(Value::as_str).collect::<Option<Vec<_>>>())
+                    .ok_or_else(|| {
+                        invalid(&format!(
+                            "tags of '{}' must be an array of strings",
+                            printer
+                        ))
+                    })?;
+                let tags = normalize_tags(tags.into_iter()).map_err(|e| invalid(&e))?;
+                config.printer_tags.insert(printer.clone(), tags);
+            }
+        }
+        Some(_) => return Err(invalid("\"printerTags\" must be an object")),
+    }
+    let entries = match root.get("aliases") {
+        None => return Ok(config),
+        Some(Value::Object(entries)) => entries,
+        Some(_) => return Err(invalid("\"aliases\" must be an object")),
+    };
+
+    for (name, entry) in entries {
+        let printer = entry
+            .get("printer")
+            .and_then(Value::as_str)
+            .ok_or_else(|| invalid(&format!("alias '{}' has no printer", name)))?;
+        let default_options = match entry.get("defaultOptions") {
+            Some(options) => string_map(options, || format!("defaultOptions of '{}'", name))
+                .map_err(|e| invalid(&e))?,
+            None => HashMap::new(),
+        };
+        let alias = PrinterAlias {
+            name: name.clone(),
+            printer: printer.to_string(),
+            default_options,
+        };
+        validate(&alias).map_err(|e| invalid(&e))?;
+        config.aliases.insert(name.clone(), alias);
+    }
+    Ok(config)
+}
+
+/// Write an alias file, replacing it atomically
+fn save(path: &Path, config: &ConfigFile) -> Result<(), String> {
+    let mut entries: Vec<&PrinterAlias> = config.aliases.values().collect();
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    let entries: Map<String, Value> = entries
+        .into_iter()
+        .map(|alias| {
+            let entry = json!({
+                "printer": alias.printer,
+                "defaultOptions": alias.default_options,
+            });
+            (alias.name.clone(), entry)
+        })
+        .collect();
+    let mut root = json!({ "aliases": entries });
+    if !config.printer_defaults.is_empty() {
+        root["printerDefaults"] = json!(config.printer_defaults);
+    }
+    if !config.printer_tags.is_empty() {
+        root["printerTags"] = json!(config.printer_tags);
+    }
+    if let Some(options) = &config.default_job_options {
+        root["defaultJobOptions"] = json!(options);
+    }
+    let contents = serde_json::to_string_pretty(&root).map_err(|e| e.to_string())?;
+
+    let temp_path = path.with_extension("tmp");
+    fs::write(&temp_path, contents + "\n")
+        .and_then(|_| fs::rename(&temp_path, path))
+        .map_err(|e| format!("Failed to write alias file '{}': {}", path.display(), e))
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Trim tags, rejecting empty ones, and sort them without duplicates
+fn normalize_tags<'a>(tags: impl Iterator<Item = &'a str>) -> Result<Vec<String>, String> {
+    let mut normalized = Vec::new();
+    for tag in tags {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            return Err("Tags must not be empty".to_string());
+        }
+        normalized.push(tag.to_string());
+    }
+    normalized.sort();
+    normalized.dedup();
+    Ok(normalized)
+}
+
+fn validate(alias: &PrinterAlias) -> Result<(), String> {
+    if alias.name.trim().is_empty() {
+        return Err("Alias name must not be empty".to_string());
+    }
+    if alias.printer.trim().is_empty() {
+        return Err(format!("Alias '{}' must name a printer", alias.name));
+    }
+    Ok(())
+}
+
+/// Names whose entries differ between two maps, sorted
+fn changed_names<T: PartialEq>(old: &HashMap<String, T>, new: &HashMap<String, T>) -> Vec<String> {
+    let names: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    names
+        .into_iter()
+        .filter(|name| old.get(*name) != new.get(*name))
+        .cloned()
+        .collect()
+}
+
+/// Printers whose defaults or tags differ between two configurations, sorted
+fn changed_printers(old: &ConfigFile, new: &ConfigFile) -> Vec<String> {
+    let mut printers = changed_names(&old.printer_defaults, &new.printer_defaults);
+    printers.extend(changed_names(&old.printer_tags, &new.printer_tags));
+    printers.sort();
+    printers.dedup();
+    printers
+}
+
+/// Report a change to the change callback, if anything changed
+fn notify(
+    source: ChangeSource,
+    aliases: Vec<String>,
+    printers: Vec<String>,
+    default_job_options: bool,
+) {
+    if aliases.is_empty() && printers.is_empty() && !default_job_options {
+        return;
+    }
+    tracing::info!(
+        source = source.as_str(),
+        aliases = aliases.len(),
+        printers = printers.len(),
+        default_job_options,
+        "Printer alias configuration changed"
+    );
+    let change = ConfigChange {
+        source,
+        aliases,
+        printers,
+        default_job_options,
+        timestamp: SystemTime::now(),
+    };
+    let callback = CHANGE_CALLBACK.lock().unwrap().clone();
+    if let Some(callback) = callback {
+        callback(&change);
+    }
+}
+
+/// Update the registry, persisting the result before it takes effect
+fn update(change: impl FnOnce(&mut ConfigFile)) -> Result<(), String> {
+    let mut registry = REGISTRY.write().unwrap();
+    let mut config = registry.config.clone();
+    change(&mut config);
+    if let Some(path) = registry.file.clone() {
+        save(&path, &config)?;
+        // Our own write isn't a change on disk for the watcher to pick up
+        registry.file_modified = modified(&path);
+    }
+    let aliases = changed_names(&registry.config.aliases, &config.aliases);
+    let printers = changed_printers(&registry.config, &config);
+    registry.config = config;
+    drop(registry);
+
+    notify(ChangeSource::Api, aliases, printers, false);
+    Ok(())
+}
+
+/// Replace the registry with the contents of a file, returning whether
+/// anything changed. Default job options the file sets replace the
+/// library-wide ones.
+fn apply_file(path: &Path, source: ChangeSource) -> Result<bool, String> {
+    let config = load(path)?;
+    let mut registry = REGISTRY.write().unwrap();
+    let changed = changed_names(&registry.config.aliases, &config.aliases);
+    let printers = changed_printers(&registry.config, &config);
+    let defaults = config
+        .default_job_options
+        .clone()
+        .filter(|options| registry.config.default_job_options.as_ref() != Some(options));
+    registry.config = config;
+    registry.file_modified = modified(path);
+    drop(registry);
+
+    let defaults_changed = defaults.is_some();
+    if let Some(default_job_options) = defaults {
+        PrinterCore::configure(LibraryConfig {
+            default_job_options,
+            ..PrinterCore::get_config()
+        })?;
+    }
+    let anything_changed = !changed.is_empty() || !printers.is_empty() || defaults_changed;
+    notify(source, changed, printers, defaults_changed);
+    Ok(anything_changed)
+}
+
+/// Define an alias, replacing any existing one with the same name
+pub fn define(alias: PrinterAlias) -> Result<(), String> {
+    validate(&alias)?;
+    tracing::debug!(alias = %alias.name, printer = %alias.printer, "Defined printer alias");
+    update(|config| {
+        config.aliases.insert(alias.name.clone(), alias);
+    })
+}
+
+/// Remove an alias, returning whether it existed
+pub fn remove(name: &str) -> Result<bool, String> {
+    let mut removed = false;
+    update(|config| removed = config.aliases.remove(name).is_some())?;
+    Ok(removed)
+}
+
+pub fn get(name: &str) -> Option<PrinterAlias> {
+    REGISTRY.read().unwrap().config.aliases.get(name).cloned()
+}
+
+/// All aliases, sorted by name
+pub fn list() -> Vec<PrinterAlias> {
+    let mut aliases: Vec<PrinterAlias> = REGISTRY
+        .read()
+        .unwrap()
+        .config
+        .aliases
+        .values()
+        .cloned()
+        .collect();
+    aliases.sort_by(|a, b| a.name.cmp(&b.name));
+    aliases
+}
+
+/// Resolve a printer name that may be an alias to the concrete printer name
+pub fn resolve(name: &str) -> String {
+    get(name).map_or_else(|| name.to_string(), |alias| alias.printer)
+}
+
+/// Set the default job options of a printer (raw job properties), applied
+/// to its jobs beneath any alias defaults and the job's own options. Empty
+/// options remove them.
+pub fn set_printer_defaults(
+    printer_name: &str,
+    options: HashMap<String, String>,
+) -> Result<(), String> {
+    let printer_name = resolve(printer_name);
+    if printer_name.trim().is_empty() {
+        return Err("Printer name must not be empty".to_string());
+    }
+    tracing::debug!(printer = %printer_name, options = options.len(), "Set printer defaults");
+    update(|config| {
+        if options.is_empty() {
+            config.printer_defaults.remove(&printer_name);
+        } else {
+            config.printer_defaults.insert(printer_name, options);
+        }
+    })
+}
+
+/// Default job options of a printer, empty if none are set
+pub fn printer_defaults(printer_name: &str) -> HashMap<String, String> {
+    REGISTRY
+        .read()
+        .unwrap()
+        .config
+        .printer_defaults
+        .get(printer_name)
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Add tags to a printer, such as its floor or department, so jobs can be
+/// routed to printers by tag
+pub fn tag_printer(printer_name: &str, tags: &[String]) -> Result<(), String> {
+    let printer_name = resolve(printer_name);
+    if printer_name.trim().is_empty() {
+        return Err("Printer name must not be empty".to_string());
+    }
+    let tags = normalize_tags(tags.iter().map(String::as_str))?;
+    update(|config| {
+        let existing = config.printer_tags.entry(printer_name).or_default();
+        existing.extend(tags);
+        existing.sort();
+        existing.dedup();
+    })
+}
+
+/// Remove tags from a printer, or all of its tags if `tags` is None
+pub fn untag_printer(printer_name: &str, tags: Option<&[String]>) -> Result<(), String> {
+    let printer_name = resolve(printer_name);
+    update(|config| {
+        if let (Some(existing), Some(tags)) = (config.printer_tags.get_mut(&printer_name), tags) {
+            existing.retain(|tag| !tags.iter().any(|removed| removed.trim() == tag));
+        }
+        if tags.is_none()
+            || config
+                .printer_tags
+                .get(&printer_name)
+                .is_some_and(Vec::is_empty)
+        {
+            config.printer_tags.remove(&printer_name);
+        }
+    })
+}
+
+/// Tags of a printer, sorted
+pub fn printer_tags(printer_name: &str) -> Vec<String> {
+    REGISTRY
+        .read()
+        .unwrap()
+        .config
+        .printer_tags
+        .get(printer_name)
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Names of the printers with a tag, ignoring case, sorted
+pub fn tagged_printers(tag: &str) -> Vec<String> {
+    let tag = tag.trim();
+    let mut printers: Vec<String> = REGISTRY
+        .read()
+        .unwrap()
+        .config
+        .printer_tags
+        .iter()
+        .filter(|(_, tags)| tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+        .map(|(printer, _)| printer.clone())
+        .collect();
+    printers.sort();
+    printers
+}
+
+/// Persist aliases to a file. An existing file replaces the aliases defined
+/// so far; otherwise the file is created from them. `None` stops persisting
+/// and keeps the current aliases in memory. A watched file is re-read
+/// whenever it changes on disk.
+pub fn set_file(path: Option<PathBuf>, watch: bool) -> Result<(), String> {
+    let Some(path) = path else {
+        let mut registry = REGISTRY.write().unwrap();
+        registry.file = None;
+        registry.file_modified = None;
+        registry.watch = false;
+        return Ok(());
+    };
+
+    if path.exists() {
+        apply_file(&path, ChangeSource::File)?;
+        tracing::debug!(file = %path.display(), "Loaded printer aliases");
+    } else {
+        save(&path, &REGISTRY.read().unwrap().config)?;
+    }
+
+    let mut registry = REGISTRY.write().unwrap();
+    registry.file_modified = modified(&path);
+    registry.file = Some(path);
+    registry.watch = watch;
+    drop(registry);
+
+    if watch {
+        ensure_watcher();
+    }
+    Ok(())
+}
+
+/// File aliases are persisted to, if any
+pub fn file() -> Option<PathBuf> {
+    REGISTRY.read().unwrap().file.clone()
+}
+
+/// Re-read the alias file, returning whether anything changed
+pub fn reload() -> Result<bool, String> {
+    let path = file().ok_or("No alias file is set")?;
+    apply_file(&path, ChangeSource::Reload)
+}
+
+/// Receive configuration changes, replacing any previous callback
+pub fn set_change_callback(callback: Option<ConfigChangeCallback>) {
+    *CHANGE_CALLBACK.lock().unwrap() = callback;
+}
+
+/// Start the thread that re-reads a watched alias file when it changes. It
+/// exits once no file is watched.
+fn ensure_watcher() {
+    let mut watcher = WATCHER.lock().unwrap();
+    if watcher.as_ref().is_some_and(|task| !task.is_finished()) {
+        return;
+    }
+    *watcher = Some(supervisor::spawn(
+        "alias-watcher",
+        RestartPolicy::BACKGROUND,
+        || loop {
+            thread::sleep(WATCH_INTERVAL);
+            let (path, last_modified) = {
+                let registry = REGISTRY.read().unwrap();
+                match &registry.file {
+                    Some(path) if registry.watch => (path.clone(), registry.file_modified),
+                    _ => break,
+                }
+            };
+            // A deleted file keeps the current configuration
+            let current = modified(&path);
+            if current.is_none() || current == last_modified {
+                continue;
+            }
+            if let Err(e) = apply_file(&path, ChangeSource::File) {
+                tracing::warn!(file = %path.display(), "Failed to reload printer aliases: {}", e);
+                // Keep the current configuration until the file changes again
+                REGISTRY.write().unwrap().file_modified = current;
+            }
+        },
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+
+    /// Forget all aliases and stop persisting them
+    fn clear() {
+        let mut registry = REGISTRY.write().unwrap();
+        *registry = Registry::default();
+    }
+
+    fn receipt() -> PrinterAlias {
+        PrinterAlias {
+            name: "RECEIPT".to_string(),
+            printer: "Simulated Printer".to_string(),
+            default_options: HashMap::from([("copies".to_string(), "2".to_string())]),
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_printer_aliases() {
+        clear();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("aliases.json");
+
+        assert!(define(PrinterAlias {
+            printer: String::new(),
+            ..receipt()
+        })
+        .is_err());
+        define(receipt()).unwrap();
+        assert_eq!(resolve("RECEIPT"), "Simulated Printer");
+        assert_eq!(resolve("Other Printer"), "Other Printer");
+
+        // Setting a new file persists the aliases defined so far
+        set_file(Some(path.clone()), false).unwrap();
+        assert_eq!(load(&path).unwrap().aliases["RECEIPT"], receipt());
+        clear();
+        assert!(get("RECEIPT").is_none());
+
+        // Setting an existing file loads it, and changes are written back
+        set_file(Some(path.clone()), false).unwrap();
+        assert_eq!(list(), vec![receipt()]);
+        assert!(remove("RECEIPT").unwrap());
+        assert!(!remove("RECEIPT").unwrap());
+        assert!(load(&path).unwrap().aliases.is_empty());
+
+        fs::write(&path, r#"{"aliases": {"LABEL": {}}}"#).unwrap();
+        assert!(set_file(Some(path), false).is_err());
+        clear();
+    }
+
+    #[test]
+    #[serial]
+    fn test_reload_alias_file() {
+        clear();
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&changes);
+        set_change_callback(Some(Arc::new(move |change: &ConfigChange| {
+            sink.lock().unwrap().push(change.clone());
+        })));
+        assert!(reload().is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("aliases.json");
+        set_file(Some(path.clone()), false).unwrap();
+        define(receipt()).unwrap();
+
+        // Edit the file as an operator would and pick up the change
+        fs::write(
+            &path,
+            r#"{
+                "aliases": {"LABEL": {"printer": "Simulated Printer"}},
+                "defaultJobOptions": {"media": "A4"}
+            }"#,
+        )
+        .unwrap();
+        assert!(reload().unwrap());
+        assert!(!reload().unwrap());
+        assert_eq!(resolve("LABEL"), "Simulated Printer");
+        assert!(get("RECEIPT").is_none());
+        assert_eq!(PrinterCore::get_config().default_job_options["media"], "A4");
+
+        let changes = changes.lock().unwrap().clone();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].source, ChangeSource::Api);
+        assert_eq!(changes[0].aliases, vec!["RECEIPT"]);
+        assert_eq!(changes[1].source, ChangeSource::Reload);
+        assert_eq!(changes[1].aliases, vec!["LABEL", "RECEIPT"]);
+        assert!(changes[1].default_job_options);
+
+        // Aliases defined later keep the file's default job options
+        define(receipt()).unwrap();
+        assert!(load(&path).unwrap().default_job_options.is_some());
+
+        set_change_callback(None);
+        PrinterCore::configure(LibraryConfig::default()).unwrap();
+        clear();
+    }
+
+    #[test]
+    #[serial]
+    fn test_printer_defaults() {
+        clear();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("aliases.json");
+        set_file(Some(path.clone()), false).unwrap();
+        define(receipt()).unwrap();
+
+        // Defaults set through an alias belong to its printer
+        let media = HashMap::from([("media".to_string(), "A5".to_string())]);
+        set_printer_defaults("RECEIPT", media.clone()).unwrap();
+        assert_eq!(printer_defaults("Simulated Printer"), media);
+        assert!(printer_defaults("RECEIPT").is_empty());
+        assert_eq!(
+            load(&path).unwrap().printer_defaults["Simulated Printer"],
+            media
+        );
+
+        set_printer_defaults("Simulated Printer", HashMap::new()).unwrap();
+        assert!(printer_defaults("Simulated Printer").is_empty());
+        assert!(load(&path).unwrap().printer_defaults.is_empty());
+
+        fs::write(&path, r#"{"printerDefaults": {"Label": {"copies": 2}}}"#).unwrap();
+        assert!(reload().is_err());
+        clear();
+    }
+
+    #[test]
+    #[serial]
+    fn test_printer_tags() {
+        clear();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("aliases.json");
+        set_file(Some(path.clone()), false).unwrap();
+        define(receipt()).unwrap();
+        let tags = |tags: &[&str]| -> Vec<String> { tags.iter().map(|t| t.to_string()).collect() };
+
+        tag_printer("RECEIPT", &tags(&["3rd-floor", " front-desk "])).unwrap();
+        tag_printer("Simulated Printer", &tags(&["3rd-floor"])).unwrap();
+        assert!(tag_printer("Simulated Printer", &tags(&[" "])).is_err());
+        assert_eq!(
+            printer_tags("Simulated Printer"),
+            tags(&["3rd-floor", "front-desk"])
+        );
+        assert_eq!(tagged_printers("3RD-FLOOR"), tags(&["Simulated Printer"]));
+        assert_eq!(
+            load(&path).unwrap().printer_tags["Simulated Printer"],
+            tags(&["3rd-floor", "front-desk"])
+        );
+
+        untag_printer("Simulated Printer", Some(&tags(&["front-desk"]))).unwrap();
+        assert_eq!(printer_tags("Simulated Printer"), tags(&["3rd-floor"]));
+        untag_printer("Simulated Printer", None).unwrap();
+        assert!(tagged_printers("3rd-floor").is_empty());
+        assert!(load(&path).unwrap().printer_tags.is_empty());
+        clear();
+    }
+}
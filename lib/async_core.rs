@@ -0,0 +1,150 @@
+//! Async print functions on Tokio
+//!
+//! Async variants of the print functions for Rust consumers. The N-API
+//! `printFile` and `printBytes` promises are built on them too, so waiting
+//! for a job no longer holds a libuv threadpool thread for as long as the
+//! job runs.
+//!
+//! Submitting a job reads and spools the document, so it runs on Tokio's
+//! blocking pool. Waiting for a job checks its state on a timer instead of
+//! parking a thread. Jobs themselves still run on their own supervised
+//! threads, since spooler calls block inside the platform's print APIs.
+//!
+//! The futures must be run within a Tokio runtime with the time driver
+//! enabled.
+
+use crate::core::{
+    job_poll_interval, job_settled, JobId, PrintError, PrinterCore, PrinterJobOptions,
+};
+use std::time::Duration;
+use tokio::task::{self, JoinError};
+use tokio::time::{self, Instant};
+
+fn submission_panicked(e: JoinError) -> Result<JobId, PrintError> {
+    tracing::error!("Print job submission panicked: {}", e);
+    Err(PrintError::SpoolFailed)
+}
+
+impl PrinterCore {
+    /// Print a file with optional job properties, without blocking the
+    /// async runtime
+    pub async fn print_file_async(
+        printer_name: &str,
+        file_path: &str,
+        job_options: Option<PrinterJobOptions>,
+    ) -> Result<JobId, PrintError> {
+        let printer_name = printer_name.to_string();
+        let file_path = file_path.to_string();
+        task::spawn_blocking(move || Self::print_file(&printer_name, &file_path, job_options))
+            .await
+            .unwrap_or_else(submission_panicked)
+    }
+
+    /// Print raw bytes with optional job properties, without blocking the
+    /// async runtime
+    pub async fn print_bytes_async(
+        printer_name: &str,
+        data: Vec<u8>,
+        job_options: Option<PrinterJobOptions>,
+    ) -> Result<JobId, PrintError> {
+        let printer_name = printer_name.to_string();
+        task::spawn_blocking(move || Self::print_bytes(&printer_name, &data, job_options))
+            .await
+            .unwrap_or_else(submission_panicked)
+    }
+
+    /// Wait until a job completes, is cancelled, is held for approval or
+    /// is no longer tracked, checking its state at the job's poll interval.
+    /// Returns false if it is still running after `timeout`.
+    pub async fn wait_for_job(job_id: JobId, timeout: Option<Duration>) -> bool {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        while !job_settled(job_id) {
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return false;
+            }
+            time::sleep(job_poll_interval(job_id)).await;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{LibraryConfig, PrinterJobState, ShutdownMode};
+    use serial_test::serial;
+    use std::env;
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    #[serial]
+    fn test_print_async() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        PrinterCore::configure(LibraryConfig {
+            simulation_delay: Duration::from_millis(100),
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+
+        block_on(async {
+            let job_id =
+                PrinterCore::print_bytes_async("Simulated Printer", b"%!PS".to_vec(), None)
+                    .await
+                    .unwrap();
+            assert!(PrinterCore::wait_for_job(job_id, Some(Duration::from_secs(10))).await);
+            assert_eq!(
+                PrinterCore::get_job_status(job_id).unwrap().state,
+                PrinterJobState::COMPLETED
+            );
+
+            assert_eq!(
+                PrinterCore::print_file_async(
+                    "Simulated Printer",
+                    "/path/that/does_not_exist/file.pdf",
+                    None
+                )
+                .await,
+                Err(PrintError::FileNotFound)
+            );
+
+            // Rejections submitted on a blocking thread keep their details
+            crate::policy::set(vec![crate::policy::PolicyRule {
+                id: "pdf-only".to_string(),
+                allowed_formats: Some(vec!["application/pdf".to_string()]),
+                ..Default::default()
+            }])
+            .unwrap();
+            let result =
+                PrinterCore::print_bytes_async("Simulated Printer", b"%!PS".to_vec(), None).await;
+            crate::policy::set(Vec::new()).unwrap();
+            assert!(matches!(
+                result,
+                Err(PrintError::PolicyViolation(violation)) if violation.rule == "pdf-only"
+            ));
+        });
+
+        PrinterCore::configure(LibraryConfig {
+            simulation_delay: Duration::from_secs(30),
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+        block_on(async {
+            let job_id =
+                PrinterCore::print_bytes_async("Simulated Printer", b"%!PS".to_vec(), None)
+                    .await
+                    .unwrap();
+            assert!(!PrinterCore::wait_for_job(job_id, Some(Duration::from_millis(100))).await);
+        });
+
+        PrinterCore::configure(LibraryConfig::default()).unwrap();
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
+    }
+}
@@ -0,0 +1,277 @@
+//! Tamper-evident audit log of print jobs
+//!
+//! When an audit log is configured, every job submission, completion and
+//! failure is appended to it as a line of JSON recording who printed what,
+//! when, on which printer and how many pages. Each record holds the hash of
+//! the record before it and a SHA-256 hash of its own fields, so editing,
+//! inserting, removing or reordering records breaks the chain, which
+//! `verify` detects. Removing records from the end can't be detected from
+//! the log alone, so `verify` reports the last hash for keeping elsewhere.
+//!
+//! Records are written with the file opened for appending and synced before
+//! the job carries on. Only one process should write to a log.
+
+use crate::core::PrinterJob;
+use crate::crypto::{hex, sha256};
+use crate::webhooks::WebhookEvent;
+use serde_json::{json, Map, Value};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// `prev_hash` of the first record
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Hashed fields, in the order they are hashed
+const FIELDS: [&str; 11] = [
+    "seq",
+    "timestamp",
+    "event",
+    "job_id",
+    "job_name",
+    "printer",
+    "owner",
+    "pages",
+    "sheets",
+    "size_bytes",
+    "prev_hash",
+];
+
+lazy_static::lazy_static! {
+    /// Last record written, so appends don't reread the log
+    static ref TAIL: Mutex<Option<Tail>> = Mutex::new(None);
+}
+
+struct Tail {
+    path: PathBuf,
+    seq: u64,
+    hash: String,
+}
+
+/// Result of checking an audit log
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuditVerification {
+    /// Whether every record is intact and chained to the one before
+    pub valid: bool,
+    /// Records checked, up to and including the first invalid one
+    pub records: u64,
+    /// Line of the first record that failed verification
+    pub first_invalid_line: Option<u64>,
+    /// Why that record failed
+    pub error: Option<String>,
+    /// Hash of the last valid record
+    pub last_hash: Option<String>,
+}
+
+/// Hash of a record's fields, independent of how its JSON keys are ordered
+fn record_hash(record: &Map<String, Value>) -> String {
+    let fields: Vec<String> = FIELDS
+        .iter()
+        .map(|field| {
+            format!(
+                "{}:{}",
+                Value::from(*field),
+                record.get(*field).unwrap_or(&Value::Null)
+            )
+        })
+        .collect();
+    hex(&sha256(format!("{{{}}}", fields.join(",")).as_bytes()))
+}
+
+fn millis_since_epoch(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// Sequence number and hash of the last record in a log, or None if it is
+/// empty or missing
+fn read_tail(path: &Path) -> Result<Option<(u64, String)>, String> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(format!(
+                "Failed to read audit log '{}': {}",
+                path.display(),
+                e
+            ))
+        }
+    };
+    let Some(line) = contents.lines().rfind(|line| !line.trim().is_empty()) else {
+        return Ok(None);
+    };
+    let record: Value = serde_json::from_str(line)
+        .map_err(|e| format!("Last record of '{}' is malformed: {}", path.display(), e))?;
+    match (record["seq"].as_u64(), record["hash"].as_str()) {
+        (Some(seq), Some(hash)) => Ok(Some((seq, hash.to_string()))),
+        _ => Err(format!(
+            "Last record of '{}' has no sequence number or hash",
+            path.display()
+        )),
+    }
+}
+
+/// Append a job event to the log at `path`
+pub fn record(path: &Path, event: WebhookEvent, job: &PrinterJob) -> Result<(), String> {
+    let mut tail = TAIL.lock().unwrap();
+    if tail.as_ref().is_none_or(|tail| tail.path != path) {
+        let (seq, hash) = read_tail(path)?.unwrap_or((0, GENESIS_HASH.to_string()));
+        *tail = Some(Tail {
+            path: path.to_path_buf(),
+            seq,
+            hash,
+        });
+    }
+    let tail = tail.as_mut().unwrap();
+
+    let mut record = json!({
+        "seq": tail.seq + 1,
+        "timestamp": millis_since_epoch(SystemTime::now()),
+        "event": event.as_str(),
+        "job_id": job.id,
+        "job_name": job.name,
+        "printer": job.printer_name,
+        "owner": job.owner,
+        "pages": job.pages,
+        "sheets": job.sheets,
+        "size_bytes": job.size_bytes,
+        "prev_hash": tail.hash,
+    });
+    let hash = record_hash(record.as_object().unwrap());
+    record["hash"] = Value::from(hash.clone());
+
+    let mut line = record.to_string();
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| {
+            file.write_all(line.as_bytes())?;
+            file.sync_data()
+        })
+        .map_err(|e| format!("Failed to write audit log '{}': {}", path.display(), e))?;
+
+    tail.seq += 1;
+    tail.hash = hash;
+    Ok(())
+}
+
+/// Check every record of the log at `path`, stopping at the first that was
+/// altered or is out of sequence
+pub fn verify(path: &Path) -> Result<AuditVerification, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read audit log '{}': {}", path.display(), e))?;
+
+    let mut verification = AuditVerification {
+        valid: true,
+        records: 0,
+        first_invalid_line: None,
+        error: None,
+        last_hash: None,
+    };
+    let mut prev_hash = GENESIS_HASH.to_string();
+    for (index, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        verification.records += 1;
+        match verify_record(line, verification.records, &prev_hash) {
+            Ok(hash) => {
+                verification.last_hash = Some(hash.clone());
+                prev_hash = hash;
+            }
+            Err(error) => {
+                verification.valid = false;
+                verification.first_invalid_line = Some(index as u64 + 1);
+                verification.error = Some(error);
+                break;
+            }
+        }
+    }
+    Ok(verification)
+}
+
+/// Check one record, returning its hash
+fn verify_record(line: &str, seq: u64, prev_hash: &str) -> Result<String, String> {
+    let Ok(Value::Object(mut record)) = serde_json::from_str(line) else {
+        return Err("Record is not a JSON object".to_string());
+    };
+    let Some(Value::String(hash)) = record.remove("hash") else {
+        return Err("Record has no hash".to_string());
+    };
+    if let Some(field) = record.keys().find(|key| !FIELDS.contains(&key.as_str())) {
+        return Err(format!("Record has unexpected field '{}'", field));
+    }
+    if record_hash(&record) != hash {
+        return Err("Record does not match its hash".to_string());
+    }
+    if record.get("prev_hash").and_then(Value::as_str) != Some(prev_hash) {
+        return Err("Record does not follow the previous record".to_string());
+    }
+    if record.get("seq").and_then(Value::as_u64) != Some(seq) {
+        return Err(format!("Expected sequence number {}", seq));
+    }
+    Ok(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+
+    #[test]
+    #[serial]
+    fn test_audit_log_chain() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+        let mut job = PrinterJob::new(
+            1,
+            "Quarterly report".to_string(),
+            "application/pdf".to_string(),
+            "Finance Printer".to_string(),
+        );
+        job.owner = Some("alice".to_string());
+        job.pages = Some(12);
+        record(&path, WebhookEvent::Submitted, &job).unwrap();
+        record(&path, WebhookEvent::Completed, &job).unwrap();
+        job.id = 2;
+        record(&path, WebhookEvent::Submitted, &job).unwrap();
+
+        let verification = verify(&path).unwrap();
+        assert!(verification.valid, "{:?}", verification.error);
+        assert_eq!(verification.records, 3);
+        let last_hash = verification.last_hash.unwrap();
+
+        // Appending after a restart continues the chain
+        *TAIL.lock().unwrap() = None;
+        record(&path, WebhookEvent::Failed, &job).unwrap();
+        let verification = verify(&path).unwrap();
+        assert!(verification.valid);
+        assert_eq!(verification.records, 4);
+        assert_ne!(verification.last_hash.unwrap(), last_hash);
+
+        let contents = fs::read_to_string(&path).unwrap();
+        let tampered = contents.replacen("\"pages\":12", "\"pages\":2", 1);
+        fs::write(&path, tampered).unwrap();
+        let verification = verify(&path).unwrap();
+        assert!(!verification.valid);
+        assert_eq!(verification.first_invalid_line, Some(1));
+        assert_eq!(verification.records, 1);
+
+        // Removing a record breaks the chain after it
+        let mut lines: Vec<&str> = contents.lines().collect();
+        lines.remove(1);
+        fs::write(&path, lines.join("\n")).unwrap();
+        let verification = verify(&path).unwrap();
+        assert!(!verification.valid);
+        assert_eq!(verification.first_invalid_line, Some(2));
+        assert!(verification.error.unwrap().contains("previous record"));
+
+        *TAIL.lock().unwrap() = None;
+        assert!(verify(&dir.path().join("missing.log")).is_err());
+    }
+}
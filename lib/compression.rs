@@ -0,0 +1,224 @@
+//! Compression of document data sent over IPP
+//!
+//! IPP printers list the codings they accept for document data in
+//! `compression-supported`, and a Print-Job request names the one its data
+//! is in with the `compression` operation attribute (RFC 8011). Large raster
+//! jobs shrink considerably with deflate, which shortens transfers over slow
+//! links such as warehouse Wi-Fi. Printers that don't list a coding only
+//! accept uncompressed data.
+
+use std::fmt;
+#[cfg(feature = "compression")]
+use std::io::{Read, Write};
+
+/// Documents smaller than this aren't worth compressing automatically
+pub const MIN_AUTO_BYTES: usize = 4 * 1024;
+
+/// A coding of document data, by its IPP `compression` keyword
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    /// Raw DEFLATE (RFC 1951)
+    Deflate,
+    /// gzip (RFC 1952)
+    Gzip,
+}
+
+impl Compression {
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            Compression::None => "none",
+            Compression::Deflate => "deflate",
+            Compression::Gzip => "gzip",
+        }
+    }
+
+    /// Parse an IPP `compression` keyword. None for codings this library
+    /// can't produce or read, like `compress`.
+    pub fn from_keyword(keyword: &str) -> Option<Compression> {
+        match keyword.trim().to_lowercase().as_str() {
+            "none" => Some(Compression::None),
+            "deflate" => Some(Compression::Deflate),
+            "gzip" => Some(Compression::Gzip),
+            _ => None,
+        }
+    }
+
+    /// Codings this build can produce and read, for `compression-supported`
+    pub fn supported() -> &'static [Compression] {
+        if cfg!(feature = "compression") {
+            &[Compression::None, Compression::Deflate, Compression::Gzip]
+        } else {
+            &[Compression::None]
+        }
+    }
+
+    /// Best coding among those a printer lists, preferring gzip, whose
+    /// checksum catches data damaged in transit
+    pub fn negotiate<'a>(printer_supported: impl IntoIterator<Item = &'a str>) -> Compression {
+        let listed: Vec<Compression> = printer_supported
+            .into_iter()
+            .filter_map(Compression::from_keyword)
+            .collect();
+        [Compression::Gzip, Compression::Deflate]
+            .into_iter()
+            .find(|coding| listed.contains(coding) && Compression::supported().contains(coding))
+            .unwrap_or(Compression::None)
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.keyword())
+    }
+}
+
+/// How documents sent to a printer are compressed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CompressionMode {
+    /// Send documents uncompressed
+    #[default]
+    Off,
+    /// Compress documents of at least `MIN_AUTO_BYTES` with the best coding
+    /// the printer supports
+    Auto,
+    /// Always use this coding
+    Fixed(Compression),
+}
+
+impl CompressionMode {
+    /// Parse `"none"`, `"auto"`, `"gzip"` or `"deflate"`
+    pub fn parse(mode: &str) -> Result<CompressionMode, String> {
+        if mode.trim().eq_ignore_ascii_case("auto") {
+            return Ok(CompressionMode::Auto);
+        }
+        match Compression::from_keyword(mode) {
+            Some(Compression::None) => Ok(CompressionMode::Off),
+            Some(coding) if Compression::supported().contains(&coding) => {
+                Ok(CompressionMode::Fixed(coding))
+            }
+            Some(coding) => Err(format!(
+                "Compression '{}' requires the compression feature",
+                coding
+            )),
+            None => Err(format!(
+                "Unknown compression '{}', expected none, auto, gzip or deflate",
+                mode
+            )),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CompressionMode::Off => "none",
+            CompressionMode::Auto => "auto",
+            CompressionMode::Fixed(coding) => coding.keyword(),
+        }
+    }
+}
+
+/// Compress document data
+#[cfg(feature = "compression")]
+pub fn compress(data: &[u8], coding: Compression) -> Result<Vec<u8>, String> {
+    use flate2::write::{DeflateEncoder, GzEncoder};
+
+    let level = flate2::Compression::default();
+    let compressed = match coding {
+        Compression::None => return Ok(data.to_vec()),
+        Compression::Deflate => {
+            let mut encoder = DeflateEncoder::new(Vec::with_capacity(data.len() / 2), level);
+            encoder.write_all(data).and_then(|_| encoder.finish())
+        }
+        Compression::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::with_capacity(data.len() / 2), level);
+            encoder.write_all(data).and_then(|_| encoder.finish())
+        }
+    };
+    compressed.map_err(|e| format!("Failed to compress document with {}: {}", coding, e))
+}
+
+#[cfg(not(feature = "compression"))]
+pub fn compress(data: &[u8], coding: Compression) -> Result<Vec<u8>, String> {
+    match coding {
+        Compression::None => Ok(data.to_vec()),
+        coding => Err(format!(
+            "Compression '{}' requires the compression feature",
+            coding
+        )),
+    }
+}
+
+/// Decompress document data, failing if it would grow beyond `max_bytes`
+#[cfg(feature = "compression")]
+pub fn decompress(data: &[u8], coding: Compression, max_bytes: usize) -> Result<Vec<u8>, String> {
+    use flate2::read::{DeflateDecoder, GzDecoder};
+
+    let reader: Box<dyn Read + '_> = match coding {
+        Compression::None => return Ok(data.to_vec()),
+        Compression::Deflate => Box::new(DeflateDecoder::new(data)),
+        Compression::Gzip => Box::new(GzDecoder::new(data)),
+    };
+    let mut decompressed = Vec::new();
+    reader
+        .take((max_bytes as u64).saturating_add(1))
+        .read_to_end(&mut decompressed)
+        .map_err(|e| format!("Failed to decompress {} document: {}", coding, e))?;
+    if decompressed.len() > max_bytes {
+        return Err(format!(
+            "Decompressed document is larger than {} bytes",
+            max_bytes
+        ));
+    }
+    Ok(decompressed)
+}
+
+#[cfg(not(feature = "compression"))]
+pub fn decompress(data: &[u8], coding: Compression, _max_bytes: usize) -> Result<Vec<u8>, String> {
+    compress(data, coding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compression_modes() {
+        assert_eq!(CompressionMode::parse("auto"), Ok(CompressionMode::Auto));
+        assert_eq!(CompressionMode::parse("None"), Ok(CompressionMode::Off));
+        assert!(CompressionMode::parse("compress").is_err());
+        assert_eq!(
+            Compression::negotiate(["none", "compress"]),
+            Compression::None
+        );
+        assert_eq!(Compression::negotiate([]), Compression::None);
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_compress_round_trip() {
+        assert_eq!(
+            Compression::negotiate(["none", "deflate", "gzip"]),
+            Compression::Gzip
+        );
+        assert_eq!(Compression::negotiate(["deflate"]), Compression::Deflate);
+        assert_eq!(
+            CompressionMode::parse("gzip"),
+            Ok(CompressionMode::Fixed(Compression::Gzip))
+        );
+
+        let raster = [0u8, 0, 0, 255].repeat(16 * 1024);
+        for coding in [Compression::Deflate, Compression::Gzip] {
+            let compressed = compress(&raster, coding).unwrap();
+            assert!(compressed.len() < raster.len() / 10);
+            assert_eq!(
+                decompress(&compressed, coding, raster.len()).unwrap(),
+                raster
+            );
+            // Compression bombs are cut off
+            assert!(decompress(&compressed, coding, raster.len() - 1).is_err());
+        }
+        let gzip = compress(&raster, Compression::Gzip).unwrap();
+        assert_eq!(&gzip[..2], &[0x1f, 0x8b]);
+        assert!(decompress(&gzip[..gzip.len() / 2], Compression::Gzip, usize::MAX).is_err());
+    }
+}
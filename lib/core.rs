@@ -1,17 +1,22 @@
 use printers::common::base::printer::Printer;
-use printers::get_printer_by_name;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::env;
-use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::sync::{
-    atomic::{AtomicBool, Ordering},
-    Arc, Mutex,
+    atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    Arc, Condvar, Mutex, MutexGuard, PoisonError, RwLock,
 };
-use std::thread::{self, JoinHandle};
+use std::thread;
 use std::time::{Duration, Instant, SystemTime};
-use tempfile::NamedTempFile;
 use uuid::Uuid;
 
+use crate::export::ExportFormat;
+use crate::job_store::{JobLogEntry, JobStore};
+use crate::logging::LogLevel;
+use crate::supervisor::{self, RestartPolicy, SupervisedTask, TaskSet};
+use crate::webhooks::WebhookEvent;
+
 /// Print job options for configuring print jobs
 #[derive(Clone, Debug)]
 pub struct PrinterJobOptions {
@@ -19,6 +24,70 @@ pub struct PrinterJobOptions {
     pub name: Option<String>,
     /// Raw properties for CUPS/system-specific options
     pub raw_properties: HashMap<String, String>,
+    /// User the job is submitted on behalf of (IPP requesting-user-name)
+    pub requesting_user: Option<String>,
+    /// Fail the job if the spooler hasn't accepted it within this time
+    pub timeout: Option<Duration>,
+    /// Reject PDFs that fail structural validation before submission
+    pub validate_document: bool,
+    /// Keep the submitted payload in the spool directory after the job
+    /// finishes, until the job is removed from the history
+    pub keep_spool_file: bool,
+    /// Jobs that must complete successfully before this one is submitted
+    pub depends_on: Vec<JobId>,
+    /// Submissions with the same key within the dedupe window are duplicates
+    pub dedupe_key: Option<String>,
+    /// How long the key is held after submission (None = default window)
+    pub dedupe_window: Option<Duration>,
+    /// What happens to duplicate submissions
+    pub dedupe_mode: DedupeMode,
+    /// Hold the job until it is approved or rejected
+    pub require_approval: bool,
+    /// Caller-defined values stored with the job, such as an order ID, for
+    /// finding it again with `find_jobs`
+    pub metadata: HashMap<String, String>,
+    /// Replace the document name and file path of the tracked job with
+    /// hashes (None = the configured default)
+    pub redact: Option<bool>,
+    /// How often waiting for the job checks its state (None = the
+    /// configured default)
+    pub poll_interval: Option<Duration>,
+    /// Scale of the page content, in percent
+    pub scale: Option<f64>,
+    /// Scale the page content to fill the page within the margins
+    pub fit_to_page: bool,
+    /// Space to leave blank at the page edges; content is shrunk to fit
+    /// within them
+    pub margins: Option<crate::preprocess::Margins>,
+    /// Text or image stamped over every page of a PDF document
+    pub watermark: Option<crate::preprocess::Watermark>,
+    /// Line printed at the top and bottom of every page of a PDF document,
+    /// with `{{variable}}` placeholders filled from the metadata and the
+    /// page number
+    pub header: Option<String>,
+    pub footer: Option<String>,
+    /// Lay out the pages of a PDF document several to a sheet
+    pub layout: Option<crate::preprocess::Layout>,
+    /// Add a blank page to PDF documents with an odd number of sides when
+    /// several copies are printed on both sides of the sheet, so each copy
+    /// starts on its own sheet
+    pub pad_odd_pages: bool,
+    /// Colors to print in, asked of the driver through `print-color-mode`
+    pub color_mode: Option<crate::preprocess::ColorMode>,
+    /// Convert the document to gray before submission, set for gray jobs on
+    /// printers that don't report supporting their color mode
+    pub convert_to_gray: bool,
+    /// Print quality, asked of the driver through `print-quality`
+    pub quality: Option<JobQuality>,
+    /// Resolution in dots per inch, asked of the driver through
+    /// `printer-resolution`
+    pub dpi: Option<u32>,
+    /// Stapling, punching, folding and output bin, asked of the driver
+    /// through `finishings` and `output-bin`
+    pub finishing: Option<crate::finishing::Finishing>,
+    /// Fail the job if a raw property isn't one the printer lists, or asks
+    /// for a value it doesn't support (None = not strict)
+    pub strict_options: Option<bool>,
 }
 
 impl PrinterJobOptions {
@@ -27,18 +96,269 @@ impl PrinterJobOptions {
         PrinterJobOptions {
             name: None,
             raw_properties: HashMap::new(),
+            requesting_user: None,
+            timeout: None,
+            validate_document: false,
+            keep_spool_file: false,
+            depends_on: Vec::new(),
+            dedupe_key: None,
+            dedupe_window: None,
+            dedupe_mode: DedupeMode::Coalesce,
+            require_approval: false,
+            metadata: HashMap::new(),
+            redact: None,
+            poll_interval: None,
+            scale: None,
+            fit_to_page: false,
+            margins: None,
+            watermark: None,
+            header: None,
+            footer: None,
+            layout: None,
+            pad_odd_pages: false,
+            color_mode: None,
+            convert_to_gray: false,
+            quality: None,
+            dpi: None,
+            finishing: None,
+            strict_options: None,
         }
     }
 
     /// Create job options from raw properties map
     pub fn from_map(mut raw_properties: HashMap<String, String>) -> Self {
-        // Extract job name if present in raw properties
+        // Extract library options if present in raw properties
         let name = raw_properties.remove("job-name");
+        let requesting_user = raw_properties.remove("requesting-user-name");
+        let timeout = raw_properties
+            .remove("job-timeout-ms")
+            .and_then(|ms| ms.trim().parse::<u64>().ok())
+            .filter(|ms| *ms > 0)
+            .map(Duration::from_millis);
+        let validate_document = raw_properties
+            .remove("validate-document")
+            .is_some_and(|value| value == "true" || value == "1");
+        let keep_spool_file = raw_properties
+            .remove("keep-spool-file")
+            .is_some_and(|value| value == "true" || value == "1");
+        let depends_on = raw_properties
+            .remove("depends-on")
+            .map(|ids| {
+                ids.split(',')
+                    .filter_map(|id| id.trim().parse::<JobId>().ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let dedupe_key = raw_properties
+            .remove("dedupe-key")
+            .filter(|key| !key.is_empty());
+        let dedupe_window = raw_properties
+            .remove("dedupe-window-ms")
+            .and_then(|ms| ms.trim().parse::<u64>().ok())
+            .filter(|ms| *ms > 0)
+            .map(Duration::from_millis);
+        let dedupe_mode = raw_properties
+            .remove("dedupe-mode")
+            .and_then(|mode| DedupeMode::parse(&mode))
+            .unwrap_or(DedupeMode::Coalesce);
+        let require_approval = raw_properties
+            .remove("require-approval")
+            .is_some_and(|value| value == "true" || value == "1");
+        // Metadata is passed as one JSON object so its keys can't collide
+        // with job properties
+        let metadata = raw_properties
+            .remove("job-metadata")
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        let redact =
+            raw_properties
+                .remove("redact-job-data")
+                .and_then(|value| match value.as_str() {
+                    "true" | "1" => Some(true),
+                    "false" | "0" => Some(false),
+                    _ => None,
+                });
+        let poll_interval = raw_properties
+            .remove("job-poll-interval-ms")
+            .and_then(|ms| ms.trim().parse::<u64>().ok())
+            .filter(|ms| *ms > 0)
+            .map(Duration::from_millis);
+        let scale = raw_properties
+            .remove("job-scale")
+            .and_then(|scale| scale.trim().parse::<f64>().ok());
+        let fit_to_page = raw_properties
+            .remove("job-fit-to-page")
+            .is_some_and(|value| value == "true" || value == "1");
+        let margins = raw_properties
+            .remove("job-margins")
+            .and_then(|json| serde_json::from_str(&json).ok());
+        let watermark = raw_properties
+            .remove("job-watermark")
+            .and_then(|json| serde_json::from_str(&json).ok());
+        let header = raw_properties
+            .remove("job-header")
+            .filter(|text| !text.trim().is_empty());
+        let footer = raw_properties
+            .remove("job-footer")
+            .filter(|text| !text.trim().is_empty());
+        let layout = raw_properties
+            .remove("job-layout")
+            .and_then(|layout| crate::preprocess::Layout::parse(&layout));
+        let pad_odd_pages = raw_properties
+            .remove("job-pad-odd-pages")
+            .is_some_and(|value| value == "true" || value == "1");
+        let color_mode = raw_properties
+            .remove("job-color-mode")
+            .and_then(|mode| crate::preprocess::ColorMode::parse(&mode));
+        let convert_to_gray = raw_properties
+            .remove("job-convert-to-gray")
+            .is_some_and(|value| value == "true" || value == "1");
+        let quality = raw_properties
+            .remove("job-quality")
+            .and_then(|quality| JobQuality::parse(&quality));
+        let dpi = raw_properties
+            .remove("job-dpi")
+            .and_then(|dpi| dpi.trim().parse::<u32>().ok())
+            .filter(|dpi| *dpi > 0);
+        let finishing = raw_properties
+            .remove("job-finishing")
+            .and_then(|json| serde_json::from_str(&json).ok());
+        let strict_options = raw_properties
+            .remove("job-strict-options")
+            .and_then(|value| match value.as_str() {
+                "true" | "1" => Some(true),
+                "false" | "0" => Some(false),
+                _ => None,
+            });
 
         PrinterJobOptions {
             name,
             raw_properties,
+            requesting_user,
+            timeout,
+            validate_document,
+            keep_spool_file,
+            depends_on,
+            dedupe_key,
+            dedupe_window,
+            dedupe_mode,
+            require_approval,
+            metadata,
+            redact,
+            poll_interval,
+            scale,
+            fit_to_page,
+            margins,
+            watermark,
+            header,
+            footer,
+            layout,
+            pad_odd_pages,
+            color_mode,
+            convert_to_gray,
+            quality,
+            dpi,
+            finishing,
+            strict_options,
+        }
+    }
+
+    /// Raw properties map the options can be recreated from, the inverse of
+    /// `from_map`
+    pub fn to_map(&self) -> HashMap<String, String> {
+        let mut map = self.raw_properties.clone();
+        let flag = |set: bool| set.then(|| "true".to_string());
+        let entries = [
+            ("job-name", self.name.clone()),
+            ("requesting-user-name", self.requesting_user.clone()),
+            (
+                "job-timeout-ms",
+                self.timeout.map(|timeout| timeout.as_millis().to_string()),
+            ),
+            ("validate-document", flag(self.validate_document)),
+            ("keep-spool-file", flag(self.keep_spool_file)),
+            (
+                "depends-on",
+                (!self.depends_on.is_empty()).then(|| {
+                    let ids: Vec<String> = self.depends_on.iter().map(JobId::to_string).collect();
+                    ids.join(",")
+                }),
+            ),
+            ("dedupe-key", self.dedupe_key.clone()),
+            (
+                "dedupe-window-ms",
+                self.dedupe_window
+                    .map(|window| window.as_millis().to_string()),
+            ),
+            (
+                "dedupe-mode",
+                self.dedupe_key
+                    .as_ref()
+                    .map(|_| self.dedupe_mode.as_str().to_string()),
+            ),
+            ("require-approval", flag(self.require_approval)),
+            (
+                "job-metadata",
+                (!self.metadata.is_empty())
+                    .then(|| serde_json::to_string(&self.metadata).unwrap_or_default()),
+            ),
+            (
+                "redact-job-data",
+                self.redact.map(|redact| redact.to_string()),
+            ),
+            (
+                "job-poll-interval-ms",
+                self.poll_interval
+                    .map(|interval| interval.as_millis().to_string()),
+            ),
+            ("job-scale", self.scale.map(|scale| scale.to_string())),
+            ("job-fit-to-page", flag(self.fit_to_page)),
+            (
+                "job-margins",
+                self.margins
+                    .as_ref()
+                    .and_then(|margins| serde_json::to_string(margins).ok()),
+            ),
+            (
+                "job-watermark",
+                self.watermark
+                    .as_ref()
+                    .and_then(|watermark| serde_json::to_string(watermark).ok()),
+            ),
+            ("job-header", self.header.clone()),
+            ("job-footer", self.footer.clone()),
+            (
+                "job-layout",
+                self.layout.map(|layout| layout.as_str().to_string()),
+            ),
+            ("job-pad-odd-pages", flag(self.pad_odd_pages)),
+            (
+                "job-color-mode",
+                self.color_mode.map(|mode| mode.as_str().to_string()),
+            ),
+            ("job-convert-to-gray", flag(self.convert_to_gray)),
+            (
+                "job-quality",
+                self.quality.map(|quality| quality.as_str().to_string()),
+            ),
+            ("job-dpi", self.dpi.map(|dpi| dpi.to_string())),
+            (
+                "job-finishing",
+                self.finishing
+                    .as_ref()
+                    .and_then(|finishing| serde_json::to_string(finishing).ok()),
+            ),
+            (
+                "job-strict-options",
+                self.strict_options.map(|strict| strict.to_string()),
+            ),
+        ];
+        for (key, value) in entries {
+            if let Some(value) = value {
+                map.insert(key.to_string(), value);
+            }
         }
+        map
     }
 
     /// Create job options with name and properties
@@ -46,13 +366,78 @@ impl PrinterJobOptions {
         PrinterJobOptions {
             name: Some(name),
             raw_properties,
+            requesting_user: None,
+            timeout: None,
+            validate_document: false,
+            keep_spool_file: false,
+            depends_on: Vec::new(),
+            dedupe_key: None,
+            dedupe_window: None,
+            dedupe_mode: DedupeMode::Coalesce,
+            require_approval: false,
+            metadata: HashMap::new(),
+            redact: None,
+            poll_interval: None,
+            scale: None,
+            fit_to_page: false,
+            margins: None,
+            watermark: None,
+            header: None,
+            footer: None,
+            layout: None,
+            pad_odd_pages: false,
+            color_mode: None,
+            convert_to_gray: false,
+            quality: None,
+            dpi: None,
+            finishing: None,
+            strict_options: None,
+        }
+    }
+}
+
+/// Print quality of a job
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JobQuality {
+    Draft,
+    Normal,
+    High,
+}
+
+impl JobQuality {
+    pub fn parse(quality: &str) -> Option<JobQuality> {
+        match quality {
+            "draft" => Some(JobQuality::Draft),
+            "normal" => Some(JobQuality::Normal),
+            "high" => Some(JobQuality::High),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobQuality::Draft => "draft",
+            JobQuality::Normal => "normal",
+            JobQuality::High => "high",
+        }
+    }
+
+    /// Value of the IPP `print-quality` enum
+    pub fn ipp_value(&self) -> &'static str {
+        match self {
+            JobQuality::Draft => "3",
+            JobQuality::Normal => "4",
+            JobQuality::High => "5",
         }
     }
 }
 
-/// Error codes for the printing operations
+/// Error codes for the printing operations. The codes and names are
+/// stable, so callers can key their own messages, e.g. translations, on
+/// them. Errors with details carry them, so they reach the caller on
+/// whichever thread the error is handled.
 #[repr(i32)]
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum PrintError {
     InvalidParams = 1,
     InvalidPrinterName = 2,
@@ -62,11 +447,131 @@ pub enum PrintError {
     PrinterNotFound = 6,
     FileNotFound = 7,
     SimulatedFailure = 8,
+    ShuttingDown = 9,
+    InvalidDocument = 10,
+    SpoolQuotaExceeded = 11,
+    SpoolFailed = 12,
+    JobNotFound = 13,
+    NoPrinterAvailable = 14,
+    QueueFull = 15,
+    DuplicateJob = 16,
+    PrinterRemoved = 17,
+    /// Why the printer can't take the job's options
+    UnsupportedOption(String) = 18,
+    /// The policy rule the job broke
+    PolicyViolation(crate::policy::PolicyViolation) = 19,
 }
 
 impl PrintError {
-    pub fn as_i32(self) -> i32 {
-        self as i32
+    /// Every error, in order of code
+    pub const ALL: [PrintError; 19] = [
+        PrintError::InvalidParams,
+        PrintError::InvalidPrinterName,
+        PrintError::InvalidFilePath,
+        PrintError::InvalidJson,
+        PrintError::InvalidJsonEncoding,
+        PrintError::PrinterNotFound,
+        PrintError::FileNotFound,
+        PrintError::SimulatedFailure,
+        PrintError::ShuttingDown,
+        PrintError::InvalidDocument,
+        PrintError::SpoolQuotaExceeded,
+        PrintError::SpoolFailed,
+        PrintError::JobNotFound,
+        PrintError::NoPrinterAvailable,
+        PrintError::QueueFull,
+        PrintError::DuplicateJob,
+        PrintError::PrinterRemoved,
+        PrintError::UnsupportedOption(String::new()),
+        PrintError::PolicyViolation(crate::policy::PolicyViolation {
+            rule: String::new(),
+            reason: String::new(),
+        }),
+    ];
+
+    pub fn as_i32(&self) -> i32 {
+        match self {
+            PrintError::InvalidParams => 1,
+            PrintError::InvalidPrinterName => 2,
+            PrintError::InvalidFilePath => 3,
+            PrintError::InvalidJson => 4,
+            PrintError::InvalidJsonEncoding => 5,
+            PrintError::PrinterNotFound => 6,
+            PrintError::FileNotFound => 7,
+            PrintError::SimulatedFailure => 8,
+            PrintError::ShuttingDown => 9,
+            PrintError::InvalidDocument => 10,
+            PrintError::SpoolQuotaExceeded => 11,
+            PrintError::SpoolFailed => 12,
+            PrintError::JobNotFound => 13,
+            PrintError::NoPrinterAvailable => 14,
+            PrintError::QueueFull => 15,
+            PrintError::DuplicateJob => 16,
+            PrintError::PrinterRemoved => 17,
+            PrintError::UnsupportedOption(_) => 18,
+            PrintError::PolicyViolation(_) => 19,
+        }
+    }
+
+    pub fn from_i32(code: i32) -> Option<PrintError> {
+        Self::ALL.into_iter().find(|e| e.as_i32() == code)
+    }
+
+    /// Stable name of the error, matching the `PrintError` enum in
+    /// JavaScript
+    pub fn name(&self) -> &'static str {
+        match self {
+            PrintError::InvalidParams => "InvalidParams",
+            PrintError::InvalidPrinterName => "InvalidPrinterName",
+            PrintError::InvalidFilePath => "InvalidFilePath",
+            PrintError::InvalidJson => "InvalidJson",
+            PrintError::InvalidJsonEncoding => "InvalidJsonEncoding",
+            PrintError::PrinterNotFound => "PrinterNotFound",
+            PrintError::FileNotFound => "FileNotFound",
+            PrintError::SimulatedFailure => "SimulatedFailure",
+            PrintError::ShuttingDown => "ShuttingDown",
+            PrintError::InvalidDocument => "InvalidDocument",
+            PrintError::SpoolQuotaExceeded => "SpoolQuotaExceeded",
+            PrintError::SpoolFailed => "SpoolFailed",
+            PrintError::JobNotFound => "JobNotFound",
+            PrintError::NoPrinterAvailable => "NoPrinterAvailable",
+            PrintError::QueueFull => "QueueFull",
+            PrintError::DuplicateJob => "DuplicateJob",
+            PrintError::PrinterRemoved => "PrinterRemoved",
+            PrintError::UnsupportedOption(_) => "UnsupportedOption",
+            PrintError::PolicyViolation(_) => "PolicyViolation",
+        }
+    }
+
+    /// Default English message, written for the people using the printer
+    pub fn message(&self) -> &'static str {
+        match self {
+            PrintError::InvalidParams => "The print settings are not valid.",
+            PrintError::InvalidPrinterName => "The printer name is not valid.",
+            PrintError::InvalidFilePath => "The document location is not valid.",
+            PrintError::InvalidJson => "The print settings could not be read.",
+            PrintError::InvalidJsonEncoding => "The print settings could not be read.",
+            PrintError::PrinterNotFound => "The printer could not be found.",
+            PrintError::FileNotFound => "The document could not be found.",
+            PrintError::SimulatedFailure => "Printing failed (simulated).",
+            PrintError::ShuttingDown => "Printing is shutting down.",
+            PrintError::InvalidDocument => "The document is damaged and can't be printed.",
+            PrintError::SpoolQuotaExceeded => "There is no room for more print jobs right now.",
+            PrintError::SpoolFailed => "The document could not be prepared for printing.",
+            PrintError::JobNotFound => "The print job could not be found.",
+            PrintError::NoPrinterAvailable => "No printer is available.",
+            PrintError::QueueFull => "Too many documents are waiting to print.",
+            PrintError::DuplicateJob => "This document was already sent to print.",
+            PrintError::PrinterRemoved => "The printer was removed before the document printed.",
+            PrintError::UnsupportedOption(_) => "The printer doesn't support the print settings.",
+            PrintError::PolicyViolation(_) => "The print policy doesn't allow this document.",
+        }
+    }
+}
+
+impl std::fmt::Display for PrintError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.message())
     }
 }
 
@@ -76,32 +581,115 @@ const SIMULATION_VARIABLE_TIME_MS: u64 = 2000;
 
 // Type aliases for better readability
 pub type JobId = u64;
-type JobTracker = Arc<Mutex<HashMap<JobId, PrinterJob>>>;
+type JobTracker = Arc<JobStore>;
 type JobIdGenerator = Arc<Mutex<JobId>>;
 
 /// Check if we should use simulated printing (for testing)
-/// Only simulates when PRINTERS_JS_SIMULATE is explicitly set to "true" or "1"
+/// The `simulate` config setting takes precedence; otherwise only simulates
+/// when PRINTERS_JS_SIMULATE is explicitly set to "true" or "1"
 /// If unset or any other value, uses real printers
 pub fn should_simulate_printing() -> bool {
+    if let Some(simulate) = CONFIG.read().unwrap().simulate {
+        return simulate;
+    }
     match env::var("PRINTERS_JS_SIMULATE") {
         Ok(val) => val == "true" || val == "1",
         Err(_) => false, // If unset, use real printers
     }
 }
 
+/// Low bits of a job ID holding its sequence number within a second
+pub const JOB_ID_SEQUENCE_BITS: u32 = 20;
+
+/// Smallest job ID issued during the second `time` falls in
+fn job_id_floor(time: SystemTime) -> JobId {
+    let secs = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    secs << JOB_ID_SEQUENCE_BITS
+}
+
 /// Generate the next job ID
+///
+/// IDs are `(unix seconds << JOB_ID_SEQUENCE_BITS) + sequence`. They
+/// increase monotonically within a process and start above the IDs of
+/// earlier processes, so IDs in persisted histories and external systems
+/// don't collide after a restart. They stay below 2^53, so JavaScript
+/// numbers hold them exactly. With a job registry configured, they are
+/// taken from blocks reserved in it, so other processes using the registry
+/// don't issue the same IDs; such IDs decode to about when their block was
+/// reserved.
 fn generate_job_id() -> JobId {
     let mut next_id = NEXT_JOB_ID.lock().unwrap();
-    let id = *next_id;
-    *next_id += 1;
+    let floor = (*next_id).max(job_id_floor(SystemTime::now()));
+    let registry = CONFIG.read().unwrap().job_registry.clone();
+    let id = match registry {
+        Some(path) => registry_job_id(&path, *next_id, floor),
+        None => floor,
+    };
+    *next_id = id + 1;
     id
 }
 
+lazy_static::lazy_static! {
+    /// Registry and IDs of it reserved by this process that are still unused
+    static ref REGISTRY_BLOCK: Mutex<Option<(PathBuf, std::ops::Range<JobId>)>> = Mutex::new(None);
+}
+
+/// Take the next ID of the reserved block, at or above `next`, reserving a
+/// new one at or above `floor` once it is used up. Blocks are used up
+/// whatever the clock says, so the registry is only locked once every
+/// `BLOCK_SIZE` jobs. Falls back to `floor` if the registry can't be used.
+fn registry_job_id(path: &Path, next: JobId, floor: JobId) -> JobId {
+    let mut block = REGISTRY_BLOCK.lock().unwrap();
+    let reserved = block
+        .as_mut()
+        .filter(|(block_path, ids)| block_path == path && ids.start >= next && !ids.is_empty());
+    if let Some((_, ids)) = reserved {
+        let id = ids.start;
+        ids.start += 1;
+        return id;
+    }
+    match crate::registry::reserve(path, floor) {
+        Ok(mut ids) => {
+            let id = ids.start;
+            ids.start += 1;
+            *block = Some((path.to_path_buf(), ids));
+            id
+        }
+        Err(e) => {
+            tracing::warn!("{}, issuing a local job ID", e);
+            *block = None;
+            floor
+        }
+    }
+}
+
+impl PrinterCore {
+    /// Second a job ID was issued in, decoded from its high bits. IDs run
+    /// ahead of the clock once more than 2^20 are issued in a second.
+    pub fn job_id_time(job_id: JobId) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(job_id >> JOB_ID_SEQUENCE_BITS)
+    }
+
+    /// ID of the process that issued a job ID, looked up in the configured
+    /// job registry. None if the registry doesn't remember the ID.
+    pub fn job_process(job_id: JobId) -> Result<Option<u32>, String> {
+        let path = CONFIG
+            .read()
+            .unwrap()
+            .job_registry
+            .clone()
+            .ok_or_else(|| "No job registry is configured".to_string())?;
+        crate::registry::owner(&path, job_id)
+    }
+}
+
 /// Simulates a print job delay, checking for shutdown periodically.
 /// Returns true if completed normally, false if shutdown was requested.
 fn simulate_print_delay(shutdown_flag: &Arc<AtomicBool>) -> bool {
-    let duration_ms = SIMULATION_BASE_TIME_MS + SIMULATION_VARIABLE_TIME_MS / 2;
-    let duration = Duration::from_millis(duration_ms);
+    let duration = CONFIG.read().unwrap().simulation_delay;
     let start = Instant::now();
 
     while start.elapsed() < duration {
@@ -113,1464 +701,10439 @@ fn simulate_print_delay(shutdown_flag: &Arc<AtomicBool>) -> bool {
     true
 }
 
+/// Submit a job on behalf of a user. CUPS reads the requesting user and the
+/// printer's credentials from per-thread state before submission; Windows
+/// records the owner afterwards and applies the job's preferences to its
+/// DEVMODE, since the spooler doesn't understand IPP job options.
+#[cfg_attr(not(windows), allow(unused_variables))]
+fn submit_as_user(
+    printer_name: &str,
+    job_options: &PrinterJobOptions,
+    submit: impl FnOnce() -> Result<u64, String>,
+) -> Result<u64, String> {
+    let requesting_user = job_options.requesting_user.as_deref();
+
+    #[cfg(unix)]
+    if let Some(user) = requesting_user {
+        crate::spooler::set_thread_requesting_user(user)?;
+    }
+    #[cfg(unix)]
+    let credentials = crate::credentials::for_printer(printer_name);
+    #[cfg(unix)]
+    if let Some(credentials) = &credentials {
+        crate::spooler::set_thread_credentials(credentials)?;
+    }
+
+    let system_job_id = submit();
+    #[cfg(unix)]
+    let system_job_id = system_job_id
+        .map_err(|e| explain_authentication_failure(printer_name, credentials.as_ref(), e));
+    let system_job_id = system_job_id?;
+
+    #[cfg(windows)]
+    if let Some(user) = requesting_user {
+        // The job is already queued, so an owner the spooler rejects doesn't fail it
+        if let Err(e) = crate::spooler::set_job_owner(printer_name, system_job_id as u32, user) {
+            tracing::warn!(
+                printer = printer_name,
+                system_job_id,
+                "Failed to set job owner: {}",
+                e
+            );
+        }
+    }
+
+    #[cfg(windows)]
+    if let Some(preferences) =
+        crate::spooler::PrinterPreferences::from_job_properties(&job_options.raw_properties)
+    {
+        if let Err(e) =
+            crate::spooler::set_job_preferences(printer_name, system_job_id as u32, &preferences)
+        {
+            tracing::warn!(
+                printer = printer_name,
+                system_job_id,
+                "Failed to apply job preferences: {}",
+                e
+            );
+        }
+    }
+
+    Ok(system_job_id)
+}
+
+/// CUPS reports refused authentication as a generic print failure; say what
+/// went wrong instead
+#[cfg(unix)]
+fn explain_authentication_failure(
+    printer_name: &str,
+    credentials: Option<&crate::credentials::Credentials>,
+    error: String,
+) -> String {
+    if !crate::spooler::last_error_is_unauthorized() {
+        return error;
+    }
+    match credentials {
+        Some(credentials) => format!(
+            "Authentication ({}) failed for printer '{}': {}",
+            credentials.method(),
+            printer_name,
+            error
+        ),
+        None => format!(
+            "Printer '{}' requires authentication; set credentials with setPrinterCredentials",
+            printer_name
+        ),
+    }
+}
+
 /// Updates job state after completion (simulated or real).
 fn complete_job(job_tracker: &JobTracker, job_id: JobId, success: bool, error_msg: Option<String>) {
-    let mut tracker = job_tracker.lock().unwrap();
-    if let Some(job) = tracker.get_mut(&job_id) {
+    finish_job(job_tracker, job_id, success, error_msg, false);
+}
+
+fn finish_job(
+    job_tracker: &JobTracker,
+    job_id: JobId,
+    success: bool,
+    error_msg: Option<String>,
+    timed_out: bool,
+) {
+    let finished = job_tracker.update(job_id, |job| {
+        // Jobs cancelled in the meantime keep their cancellation
+        if job.state.is_terminal() {
+            return None;
+        }
+        let state = if success {
+            PrinterJobState::COMPLETED
+        } else if timed_out {
+            PrinterJobState::FAILED
+        } else {
+            PrinterJobState::CANCELLED
+        };
+        if let Err(e) = job.transition_to(state) {
+            tracing::warn!(job_id, "{}", e);
+            return None;
+        }
+        job.timed_out = timed_out;
         if success {
-            job.state = PrinterJobState::COMPLETED;
+            tracing::info!(job_id, printer = %job.printer_name, "Job completed");
         } else {
-            job.state = PrinterJobState::CANCELLED;
+            tracing::warn!(
+                job_id,
+                printer = %job.printer_name,
+                "Job failed: {}",
+                error_msg.as_deref().unwrap_or("unknown error")
+            );
             job.error_message = error_msg;
         }
-        job.completed_at = Some(SystemTime::now());
+        let completed_at = SystemTime::now();
+        job.completed_at = Some(completed_at);
+
+        let spool_latency = completed_at
+            .duration_since(job.created_at)
+            .unwrap_or_default();
+        crate::metrics::job_finished(success, spool_latency);
+        Some(job.clone())
+    });
+    if let Some(job) = finished.flatten() {
+        let event = if success {
+            job_tracker.log(job_id, LogLevel::Info, "Completed");
+            WebhookEvent::Completed
+        } else {
+            let error = job.error_message.as_deref().unwrap_or("unknown error");
+            job_tracker.log(job_id, LogLevel::Error, format!("Failed: {}", error));
+            WebhookEvent::Failed
+        };
+        notify_job_event(event, &job);
     }
+    evict_job_history(job_tracker);
+    crate::metrics::set_queue_depth(queue_depth(job_tracker));
 }
 
-// Global job tracking
-lazy_static::lazy_static! {
-    static ref JOB_TRACKER: JobTracker = Arc::new(Mutex::new(HashMap::new()));
-    static ref NEXT_JOB_ID: JobIdGenerator = Arc::new(Mutex::new(1000));
-    static ref SHUTDOWN_FLAG: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
-    static ref THREAD_HANDLES: Arc<Mutex<Vec<JoinHandle<()>>>> = Arc::new(Mutex::new(Vec::new()));
+/// Fail a job whose printer was removed before it started printing
+fn fail_printer_removed(job_tracker: &JobTracker, job_id: JobId, printer_name: &str) {
+    job_tracker.update(job_id, |job| {
+        if !job.state.is_terminal() {
+            job.printer_removed = true;
+        }
+    });
+    finish_job(
+        job_tracker,
+        job_id,
+        false,
+        Some(format!("Printer '{}' was removed", printer_name)),
+        false,
+    );
 }
 
-/// Job status enum matching upstream printers crate
-#[derive(Clone, Debug, PartialEq)]
-pub enum PrinterJobState {
-    PENDING,    // Job queued, waiting to be processed
-    PAUSED,     // Job temporarily halted
-    PROCESSING, // Job currently being printed
-    CANCELLED,  // Job cancelled by user or system
-    COMPLETED,  // Job finished successfully
-    UNKNOWN,    // Undetermined state
+/// Fail the jobs still queued for a printer that was removed, returning how
+/// many were failed
+fn fail_removed_printer_jobs(job_tracker: &JobTracker, printer_name: &str) -> usize {
+    let queued = job_tracker.filter(|job| {
+        job.printer_name == printer_name
+            && matches!(
+                job.state,
+                PrinterJobState::PENDING | PrinterJobState::PAUSED
+            )
+    });
+    for job in &queued {
+        fail_printer_removed(job_tracker, job.id, printer_name);
+    }
+    if !queued.is_empty() {
+        tracing::warn!(
+            printer = printer_name,
+            jobs = queued.len(),
+            "Printer removed, failing its queued jobs"
+        );
+    }
+    queued.len()
 }
 
-impl PrinterJobState {
-    pub fn as_string(&self) -> String {
-        match self {
-            PrinterJobState::PENDING => "pending".to_string(),
-            PrinterJobState::PAUSED => "paused".to_string(),
-            PrinterJobState::PROCESSING => "processing".to_string(),
-            PrinterJobState::CANCELLED => "cancelled".to_string(),
-            PrinterJobState::COMPLETED => "completed".to_string(),
-            PrinterJobState::UNKNOWN => "unknown".to_string(),
-        }
+/// Mark a queued job as processing, returning the printer it is queued for
+/// now, which `migrate_jobs` may have changed. None if the job was cancelled
+/// while queued, or was failed because its printer is gone.
+fn start_processing(job_tracker: &JobTracker, job_id: JobId) -> Option<String> {
+    let printer_name = job_tracker.inspect(job_id, |job| job.printer_name.clone())?;
+    if PrinterCore::find_printer_by_name(&printer_name).is_none() {
+        fail_printer_removed(job_tracker, job_id, &printer_name);
+        return None;
     }
+    let printer_name = job_tracker
+        .update(job_id, |job| {
+            job.transition_to(PrinterJobState::PROCESSING).ok()?;
+            job.processed_at = Some(SystemTime::now());
+            Some(job.printer_name.clone())
+        })
+        .flatten()?;
+    job_tracker.log(
+        job_id,
+        LogLevel::Info,
+        format!("Processing on '{}'", printer_name),
+    );
+    Some(printer_name)
 }
 
-/// Print job structure matching upstream printers crate
-#[derive(Clone, Debug)]
-pub struct PrinterJob {
-    pub id: JobId,                        // Unique job identifier
-    pub name: String,                     // Job title/description
-    pub state: PrinterJobState,           // Current job status
-    pub media_type: String,               // File type (e.g., "application/pdf")
-    pub created_at: SystemTime,           // Job creation timestamp
-    pub processed_at: Option<SystemTime>, // Processing start time (optional)
-    pub completed_at: Option<SystemTime>, // Job completion time (optional)
-    pub printer_name: String,             // Associated printer name
-    pub error_message: Option<String>,    // Error details if failed
-}
+/// Whether jobs for a printer travel over TLS: Some(true) for `ipps` and
+/// `https` destinations, Some(false) for other network destinations, and
+/// None for devices attached to this machine. Fails if it can't be told,
+/// e.g. for a CUPS queue whose device can't be looked up or a `dnssd` one.
+fn transport_encryption(printer_name: &str) -> Result<Option<bool>, String> {
+    if let Some(remote) = crate::remote::get(printer_name) {
+        return DeviceAddress::parse(&remote.url)
+            .map(|address| Some(address.is_secure()))
+            .ok_or_else(|| format!("Unknown destination '{}'", remote.url));
+    }
+    let printer = PrinterCore::find_printer_by_name(printer_name)
+        .ok_or_else(|| format!("Printer '{}' not found", printer_name))?;
+    if should_simulate_printing() && printer.name == "Simulated Printer" {
+        return Ok(None);
+    }
 
-/// Detect media type from file extension
-fn detect_media_type(file_path: &str) -> String {
-    if file_path.starts_with("<bytes:") {
-        return "application/vnd.cups-raw".to_string();
+    let device = device_uri(&printer)
+        .ok_or_else(|| format!("Can't look up the device of '{}'", printer_name))?;
+    if let Some(address) =
+        DeviceAddress::parse(&device).or_else(|| DeviceAddress::from_port_name(&printer.port_name))
+    {
+        return Ok(Some(address.is_secure()));
     }
+    if crate::device::is_attached(&device) || crate::device::is_attached(&printer.port_name) {
+        return Ok(None);
+    }
+    Err(format!("Can't tell how device '{}' is reached", device))
+}
 
-    let path = std::path::Path::new(file_path);
-    if let Some(extension) = path.extension() {
-        match extension.to_str().unwrap_or("").to_lowercase().as_str() {
-            "pdf" => "application/pdf".to_string(),
-            "ps" => "application/postscript".to_string(),
-            "txt" | "text" => "text/plain".to_string(),
-            "jpg" | "jpeg" => "image/jpeg".to_string(),
-            "png" => "image/png".to_string(),
-            "gif" => "image/gif".to_string(),
-            _ => "application/octet-stream".to_string(),
+/// Record whether a job's document travels encrypted, failing the job if
+/// its printer is reached in plaintext and `require_encrypted_transport`
+/// is set. Returns whether the job may be sent.
+fn check_transport(job_tracker: &JobTracker, job_id: JobId, printer_name: &str) -> bool {
+    let encrypted = transport_encryption(printer_name);
+    job_tracker.update(job_id, |job| {
+        job.encrypted_transport = encrypted.clone().ok().flatten()
+    });
+    let required = CONFIG.read().unwrap().require_encrypted_transport;
+    match encrypted {
+        Err(e) if required => {
+            complete_job(
+                job_tracker,
+                job_id,
+                false,
+                Some(format!(
+                    "Printer '{}' may be reached without encryption and encrypted transport is required: {}",
+                    printer_name, e
+                )),
+            );
+            return false;
         }
-    } else {
-        "application/octet-stream".to_string()
+        Err(e) => {
+            job_tracker.log(
+                job_id,
+                LogLevel::Warn,
+                format!("Can't tell whether the job is sent encrypted: {}", e),
+            );
+        }
+        Ok(Some(true)) => {
+            job_tracker.log(job_id, LogLevel::Info, "Sending over TLS");
+        }
+        Ok(Some(false)) if required => {
+            complete_job(
+                job_tracker,
+                job_id,
+                false,
+                Some(format!(
+                    "Printer '{}' is reached without encryption and encrypted transport is required",
+                    printer_name
+                )),
+            );
+            return false;
+        }
+        Ok(Some(false)) => {
+            job_tracker.log(job_id, LogLevel::Warn, "Sending without encryption");
+        }
+        Ok(None) => {}
     }
+    true
 }
 
-/// Create a JSON status object for a job
-pub fn create_status_json(_job_id: JobId, job: &PrinterJob) -> Option<String> {
-    let age_seconds = job
-        .created_at
-        .elapsed()
-        .unwrap_or(Duration::from_secs(0))
-        .as_secs();
+/// Start a new job's log with what was submitted
+fn log_submission(job: &PrinterJob) {
+    let mut message = format!(
+        "Submitted to '{}': {} bytes of {} (type from {})",
+        job.printer_name,
+        job.size_bytes,
+        job.media_type,
+        job.media_type_source.as_str()
+    );
+    if let Some(original) = job.reprint_of {
+        message.push_str(&format!(", reprinting job {}", original));
+    }
+    JOB_TRACKER.log(job.id, LogLevel::Info, message);
+}
 
-    let status_obj = serde_json::json!({
-        "id": job.id,
-        "printer_name": job.printer_name,
-        "name": job.name,
-        "state": job.state.as_string(),
-        "media_type": job.media_type,
-        "created_at": job.created_at.duration_since(SystemTime::UNIX_EPOCH).unwrap_or(Duration::from_secs(0)).as_secs(),
-        "processed_at": job.processed_at.map(|t| t.duration_since(SystemTime::UNIX_EPOCH).unwrap_or(Duration::from_secs(0)).as_secs()),
-        "completed_at": job.completed_at.map(|t| t.duration_since(SystemTime::UNIX_EPOCH).unwrap_or(Duration::from_secs(0)).as_secs()),
-        "error_message": job.error_message,
-        "age_seconds": age_seconds
+/// Fail the job if it hasn't finished once its timeout expires
+fn start_job_timeout(job_tracker: JobTracker, job_id: JobId, timeout: Duration) {
+    supervisor::spawn_once(format!("job-{}-timeout", job_id), move || {
+        let deadline = Instant::now() + timeout;
+        let interval = job_poll_interval(job_id).min(timeout);
+        while Instant::now() < deadline {
+            let finished = job_tracker
+                .inspect(job_id, |job| job.state.is_terminal())
+                .unwrap_or(true);
+            if finished {
+                return;
+            }
+            thread::sleep(interval);
+        }
+        finish_job(
+            &job_tracker,
+            job_id,
+            false,
+            Some(format!("Job timed out after {}ms", timeout.as_millis())),
+            true,
+        );
     });
+}
 
-    serde_json::to_string(&status_obj).ok()
+/// The spooler accepted a job after it timed out; remove it so it doesn't
+/// print after the caller was told it failed
+fn cancel_late_submission(printer_name: &str, job_id: JobId, system_job_id: u64) {
+    tracing::warn!(
+        job_id,
+        printer = printer_name,
+        system_job_id,
+        "Spooler accepted the job after it timed out, cancelling it"
+    );
+    JOB_TRACKER.log(
+        job_id,
+        LogLevel::Warn,
+        format!(
+            "Spooler accepted the job as job {} after it had failed, cancelling it",
+            system_job_id
+        ),
+    );
+    if let Err(e) = crate::spooler::cancel_job(printer_name, system_job_id as u32) {
+        tracing::warn!(
+            job_id,
+            printer = printer_name,
+            "Failed to cancel spooler job: {}",
+            e
+        );
+        JOB_TRACKER.log(
+            job_id,
+            LogLevel::Error,
+            format!("Failed to cancel spooler job {}: {}", system_job_id, e),
+        );
+    }
 }
 
-/// Core printer operations
-pub struct PrinterCore;
+/// Number of jobs waiting to print or printing
+fn queue_depth(tracker: &JobStore) -> u64 {
+    tracker.count(|job| job.state.is_queued()) as u64
+}
 
-impl PrinterCore {
-    /// Find a printer by name
-    pub fn find_printer_by_name(name: &str) -> Option<Printer> {
-        if should_simulate_printing() {
-            // In simulation mode, only return printer if name matches simulated printers
-            if name == "Simulated Printer" {
-                // Try to use a real printer as template, but with the requested name
-                if let Some(mut printer) = printers::get_printers().first().cloned() {
-                    printer.name = name.to_string();
-                    printer.is_default = true; // Always mark simulated printer as default
-                    Some(printer)
-                } else {
-                    // No real printers available - create a mock printer struct
-                    Some(Printer {
-                        name: name.to_string(),
-                        system_name: "Brother_MFC_J6955DW".to_string(),
-                        driver_name: "Brother MFC-J6955DW-AirPrint".to_string(),
-                        uri: "mock://printer".to_string(),
-                        location: "Test Location".to_string(),
-                        description: "Mock printer for testing".to_string(),
-                        port_name: "MOCK:".to_string(),
-                        processor: "Mock Processor".to_string(),
-                        data_type: "RAW".to_string(),
-                        is_shared: false,
-                        is_default: true,
-                        state: printers::common::base::printer::PrinterState::READY,
-                        state_reasons: Vec::new(),
-                    })
-                }
-            } else {
-                None
-            }
-        } else {
-            get_printer_by_name(name)
+/// Default of `LibraryConfig::job_poll_interval`
+pub const DEFAULT_JOB_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How often waiting for a job checks its state: the job's own interval,
+/// or the configured default
+pub fn job_poll_interval(job_id: JobId) -> Duration {
+    JOB_TRACKER
+        .inspect(job_id, |job| job.job_options.poll_interval)
+        .flatten()
+        .unwrap_or_else(|| CONFIG.read().unwrap().job_poll_interval)
+}
+
+/// Whether callers waiting on a job can stop: it completed, was cancelled,
+/// failed, is held for approval or is no longer tracked
+pub(crate) fn job_settled(job_id: JobId) -> bool {
+    JOB_TRACKER
+        .inspect(job_id, |job| {
+            matches!(
+                job.state,
+                PrinterJobState::COMPLETED
+                    | PrinterJobState::CANCELLED
+                    | PrinterJobState::FAILED
+                    | PrinterJobState::HELD_FOR_APPROVAL
+            )
+        })
+        .unwrap_or(true)
+}
+
+// Global job tracking
+lazy_static::lazy_static! {
+    static ref JOB_TRACKER: JobTracker = Arc::new(JobStore::new());
+    static ref NEXT_JOB_ID: JobIdGenerator = Arc::new(Mutex::new(0));
+    static ref SHUTDOWN_FLAG: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+    static ref JOB_TASKS: TaskSet = TaskSet::new();
+    static ref CONFIG: RwLock<LibraryConfig> = RwLock::new(LibraryConfig::default());
+    static ref WORKER_SLOTS: (Mutex<usize>, Condvar) = (Mutex::new(0), Condvar::new());
+}
+
+// ===== LIBRARY CONFIGURATION =====
+
+/// Library-wide settings, replacing environment variables and hardcoded defaults
+#[derive(Clone, Debug, PartialEq)]
+pub struct LibraryConfig {
+    /// Maximum number of jobs handed to the spooler concurrently (None = unlimited)
+    pub worker_pool_size: Option<usize>,
+    /// Default polling interval for printer state monitoring
+    pub monitoring_interval: Duration,
+    /// How often waiting for a job checks its state, unless the job sets
+    /// its own interval
+    pub job_poll_interval: Duration,
+    /// Directory for temporary spool files (None = system temp directory)
+    pub temp_dir: Option<PathBuf>,
+    /// Maximum bytes of print data held in spool files (None = unlimited)
+    pub spool_quota: Option<u64>,
+    /// Force simulation on or off (None = use PRINTERS_JS_SIMULATE)
+    pub simulate: Option<bool>,
+    /// How long a simulated job takes to print
+    pub simulation_delay: Duration,
+    /// Raw job properties applied to every job unless overridden
+    pub default_job_options: HashMap<String, String>,
+    /// Maximum number of completed/cancelled jobs kept (None = unlimited)
+    pub max_job_history: Option<usize>,
+    /// How long completed/cancelled jobs are kept (None = until removed)
+    pub history_ttl: Option<Duration>,
+    /// Jobs processing for longer than this are stalled (None = no watchdog)
+    pub stall_threshold: Option<Duration>,
+    /// What the watchdog does with stalled jobs
+    pub stall_action: StallAction,
+    /// Unfinished jobs a printer may have before submissions to it are
+    /// refused (None = unlimited)
+    pub max_queued_jobs_per_printer: Option<usize>,
+    /// Unfinished jobs across all printers before submissions are refused
+    /// (None = unlimited)
+    pub max_pending_jobs: Option<usize>,
+    /// How long a submission waits for room in a full queue (None = fail
+    /// immediately)
+    pub queue_full_timeout: Option<Duration>,
+    /// Directory jobs held for approval are persisted in (None =
+    /// `printers-js-held` under the temp directory)
+    pub held_jobs_dir: Option<PathBuf>,
+    /// File job events are appended to as a tamper-evident audit log (None =
+    /// no audit log)
+    pub audit_log: Option<PathBuf>,
+    /// Replace document names and file paths of tracked jobs with hashes,
+    /// unless a job sets `redact`
+    pub redact_job_data: bool,
+    /// File processes on this machine reserve job IDs from, so their IDs
+    /// don't collide (None = IDs are issued locally)
+    pub job_registry: Option<PathBuf>,
+    /// Ghostscript executable documents are rasterized with (None = `gs`
+    /// on the PATH)
+    pub ghostscript_path: Option<PathBuf>,
+    /// How long the list of system printers is reused before the system is
+    /// enumerated again (zero = enumerate on every lookup)
+    pub printer_cache_ttl: Duration,
+    /// Fail jobs for network printers reached without TLS, e.g. over
+    /// `ipp://` or `socket://`
+    pub require_encrypted_transport: bool,
+}
+
+impl Default for LibraryConfig {
+    fn default() -> Self {
+        LibraryConfig {
+            worker_pool_size: None,
+            monitoring_interval: Duration::from_secs(2),
+            job_poll_interval: DEFAULT_JOB_POLL_INTERVAL,
+            temp_dir: None,
+            spool_quota: None,
+            simulate: None,
+            simulation_delay: Duration::from_millis(
+                SIMULATION_BASE_TIME_MS + SIMULATION_VARIABLE_TIME_MS / 2,
+            ),
+            default_job_options: HashMap::new(),
+            max_job_history: None,
+            history_ttl: None,
+            stall_threshold: None,
+            stall_action: StallAction::Flag,
+            max_queued_jobs_per_printer: None,
+            max_pending_jobs: None,
+            queue_full_timeout: None,
+            held_jobs_dir: None,
+            audit_log: None,
+            redact_job_data: false,
+            job_registry: None,
+            ghostscript_path: None,
+            printer_cache_ttl: Duration::from_secs(2),
+            require_encrypted_transport: false,
         }
     }
+}
 
-    /// Check if a printer exists
-    pub fn printer_exists(name: &str) -> bool {
-        Self::find_printer_by_name(name).is_some()
+impl LibraryConfig {
+    /// Check settings before they are applied
+    pub fn validate(&self) -> Result<(), String> {
+        if self.worker_pool_size == Some(0) {
+            return Err("Worker pool size must be at least 1".to_string());
+        }
+        if self.monitoring_interval.is_zero() {
+            return Err("Monitoring interval must be greater than zero".to_string());
+        }
+        if self.job_poll_interval.is_zero() {
+            return Err("Job poll interval must be greater than zero".to_string());
+        }
+        if self.max_job_history == Some(0) {
+            return Err("Max job history must be at least 1".to_string());
+        }
+        if self.spool_quota == Some(0) {
+            return Err("Spool quota must be greater than zero".to_string());
+        }
+        if self.history_ttl.is_some_and(|ttl| ttl.is_zero()) {
+            return Err("History TTL must be greater than zero".to_string());
+        }
+        if self
+            .stall_threshold
+            .is_some_and(|threshold| threshold.is_zero())
+        {
+            return Err("Stall threshold must be greater than zero".to_string());
+        }
+        if self.max_queued_jobs_per_printer == Some(0) || self.max_pending_jobs == Some(0) {
+            return Err("Queue limits must be at least 1".to_string());
+        }
+        if let Some(dir) = &self.temp_dir {
+            if !dir.is_dir() {
+                return Err(format!("Temp directory '{}' does not exist", dir.display()));
+            }
+        }
+        if let Some(dir) = self
+            .audit_log
+            .as_deref()
+            .and_then(Path::parent)
+            .filter(|dir| !dir.as_os_str().is_empty())
+        {
+            if !dir.is_dir() {
+                return Err(format!(
+                    "Audit log directory '{}' does not exist",
+                    dir.display()
+                ));
+            }
+        }
+        if let Some(dir) = self
+            .job_registry
+            .as_deref()
+            .and_then(Path::parent)
+            .filter(|dir| !dir.as_os_str().is_empty())
+        {
+            if !dir.is_dir() {
+                return Err(format!(
+                    "Job registry directory '{}' does not exist",
+                    dir.display()
+                ));
+            }
+        }
+        Ok(())
     }
+}
 
-    /// Get all printer names
-    pub fn get_all_printer_names() -> Vec<String> {
-        if should_simulate_printing() {
-            vec!["Simulated Printer".to_string()]
-        } else {
-            printers::get_printers()
-                .into_iter()
-                .map(|p| p.name.clone())
-                .collect()
+impl PrinterJobOptions {
+    /// Fill in options the job doesn't set from configured defaults
+    fn with_defaults(mut self, defaults: &HashMap<String, String>) -> Self {
+        let defaults = PrinterJobOptions::from_map(defaults.clone());
+        for (key, value) in defaults.raw_properties {
+            // A job's own color mode, quality and resolution override the
+            // default driver settings
+            let overridden = match key.as_str() {
+                "print-color-mode" => self.color_mode.is_some(),
+                "print-quality" => self.quality.is_some(),
+                "printer-resolution" | "Resolution" => self.dpi.is_some(),
+                "finishings" | "output-bin" => self.finishing.is_some(),
+                _ => false,
+            };
+            if overridden {
+                continue;
+            }
+            self.raw_properties.entry(key).or_insert(value);
         }
+        self.name = self.name.or(defaults.name);
+        self.requesting_user = self.requesting_user.or(defaults.requesting_user);
+        self.redact = self.redact.or(defaults.redact);
+        // A job's own scale or fit to page overrides either default
+        if self.scale.is_none() && !self.fit_to_page {
+            self.scale = defaults.scale;
+            self.fit_to_page = defaults.fit_to_page;
+        }
+        self.margins = self.margins.or(defaults.margins);
+        self.watermark = self.watermark.or(defaults.watermark);
+        self.header = self.header.or(defaults.header);
+        self.footer = self.footer.or(defaults.footer);
+        self.layout = self.layout.or(defaults.layout);
+        self.pad_odd_pages |= defaults.pad_odd_pages;
+        self.color_mode = self.color_mode.or(defaults.color_mode);
+        self.quality = self.quality.or(defaults.quality);
+        self.dpi = self.dpi.or(defaults.dpi);
+        self.finishing = self.finishing.or(defaults.finishing);
+        self.strict_options = self.strict_options.or(defaults.strict_options);
+        self
     }
+}
 
-    /// Serialize printer to JSON (simplified)
-    pub fn printer_to_json(printer: &Printer) -> Option<String> {
-        let printer_obj = serde_json::json!({
-            "name": printer.name,
-            "system_name": printer.system_name,
-            "driver_name": printer.driver_name,
-            "is_default": printer.is_default,
-            "is_shared": printer.is_shared,
-        });
-
-        serde_json::to_string(&printer_obj).ok()
-    }
+impl PrinterCore {
+    /// Replace the library configuration
+    pub fn configure(config: LibraryConfig) -> Result<(), String> {
+        config.validate()?;
+        let monitoring_interval = config.monitoring_interval;
+        let held_jobs_moved = {
+            let mut current = CONFIG.write().unwrap();
+            let moved = (&current.held_jobs_dir, &current.temp_dir)
+                != (&config.held_jobs_dir, &config.temp_dir);
+            *current = config;
+            moved
+        };
+        if held_jobs_moved {
+            Self::restore_held_jobs();
+        }
 
-    /// Get printer state as string
-    pub fn get_printer_state(printer: &Printer) -> String {
-        // Convert the PrinterState enum to a string representation
-        use printers::common::base::printer::PrinterState;
+        // A larger pool may admit jobs that are waiting for a slot
+        WORKER_SLOTS.1.notify_all();
 
-        match printer.state {
-            PrinterState::READY => "idle".to_string(),
-            PrinterState::PRINTING => "printing".to_string(),
-            PrinterState::PAUSED => "paused".to_string(),
-            PrinterState::OFFLINE => "offline".to_string(),
-            PrinterState::UNKNOWN => "unknown".to_string(),
+        if let Some(monitor) = GLOBAL_STATE_MONITOR.lock().unwrap().as_mut() {
+            monitor.set_poll_interval(monitoring_interval);
         }
+
+        // Apply new limits right away rather than on the next completion
+        evict_job_history(&JOB_TRACKER);
+        ensure_history_sweeper();
+        ensure_stall_watchdog();
+        Ok(())
     }
 
-    /// Get printer state reasons as JSON array
-    pub fn get_printer_state_reasons(printer: &Printer) -> String {
-        serde_json::to_string(&printer.state_reasons).unwrap_or_else(|_| "[]".to_string())
+    /// Get the current library configuration
+    pub fn get_config() -> LibraryConfig {
+        CONFIG.read().unwrap().clone()
     }
+}
 
-    /// Print a file with optional job properties
-    pub fn print_file(
-        printer_name: &str,
-        file_path: &str,
-        job_options: Option<PrinterJobOptions>,
-    ) -> Result<JobId, PrintError> {
-        // Check if printer exists
-        let _printer =
-            Self::find_printer_by_name(printer_name).ok_or(PrintError::PrinterNotFound)?;
+/// Holds one of the configured worker slots until dropped
+struct WorkerSlot;
 
-        // Check if file exists
-        if should_simulate_printing() {
-            // In simulation mode, simulate different types of errors based on filename
-            if file_path.contains("nonexistent") || file_path.contains("does_not_exist") {
-                return Err(PrintError::FileNotFound);
-            } else if file_path.contains("fail-test") {
-                return Err(PrintError::SimulatedFailure);
-            }
-        } else if !std::path::Path::new(file_path).exists() {
-            return Err(PrintError::FileNotFound);
-        }
+impl Drop for WorkerSlot {
+    fn drop(&mut self) {
+        let (active, available) = &*WORKER_SLOTS;
+        *active.lock().unwrap() -= 1;
+        available.notify_one();
+    }
+}
 
-        // Generate job ID
-        let job_id = generate_job_id();
+/// Wait for a free worker slot. Returns None if shutdown was requested.
+fn acquire_worker_slot(shutdown_flag: &Arc<AtomicBool>) -> Option<WorkerSlot> {
+    let (active, available) = &*WORKER_SLOTS;
+    let mut active = active.lock().unwrap();
+    loop {
+        if shutdown_flag.load(Ordering::Relaxed) {
+            return None;
+        }
+        let limit = CONFIG.read().unwrap().worker_pool_size;
+        if limit.is_none_or(|limit| *active < limit) {
+            *active += 1;
+            return Some(WorkerSlot);
+        }
+        // Wake periodically to notice shutdown
+        active = available
+            .wait_timeout(active, Duration::from_millis(100))
+            .unwrap()
+            .0;
+    }
+}
 
-        // Extract job options
-        let job_options = job_options.unwrap_or_else(PrinterJobOptions::none);
+// ===== QUEUE LIMITS =====
 
-        // Detect media type from file extension
-        let media_type = detect_media_type(file_path);
+lazy_static::lazy_static! {
+    static ref ADMISSION: Mutex<()> = Mutex::new(());
+}
 
-        // Create job name from options or default to GUID
-        let job_name = job_options
-            .name
-            .clone()
-            .unwrap_or_else(|| Uuid::new_v4().simple().to_string());
+/// Held from the queue limit check until the job is tracked, so concurrent
+/// submissions can't both take the last place in a queue
+type Admission = MutexGuard<'static, ()>;
+
+/// Why the printer's queue or the global queue has no room, if it hasn't
+fn queue_full(printer_name: &str) -> Option<String> {
+    let (max_per_printer, max_pending) = {
+        let config = CONFIG.read().unwrap();
+        (config.max_queued_jobs_per_printer, config.max_pending_jobs)
+    };
+    if let Some(max) = max_pending {
+        let pending = queue_depth(&JOB_TRACKER) as usize;
+        if pending >= max {
+            return Some(format!("{} jobs pending", pending));
+        }
+    }
+    if let Some(max) = max_per_printer {
+        let queued =
+            JOB_TRACKER.count(|job| job.state.is_queued() && job.printer_name == printer_name);
+        if queued >= max {
+            return Some(format!("{} jobs queued for {}", queued, printer_name));
+        }
+    }
+    None
+}
 
-        // Create job status
-        let job_status = PrinterJob {
-            id: job_id,
-            name: job_name,
-            state: PrinterJobState::PENDING,
-            media_type,
-            created_at: SystemTime::now(),
-            processed_at: None,
-            completed_at: None,
-            printer_name: printer_name.to_string(),
-            error_message: None,
+/// Wait until the printer's queue and the global queue have room, for up to
+/// the configured timeout
+fn admit_job(printer_name: &str) -> Result<Admission, PrintError> {
+    let deadline = CONFIG
+        .read()
+        .unwrap()
+        .queue_full_timeout
+        .map(|timeout| Instant::now() + timeout);
+    loop {
+        let admission = ADMISSION.lock().unwrap();
+        let Some(reason) = queue_full(printer_name) else {
+            return Ok(admission);
         };
+        drop(admission);
 
-        // Store job in tracker
-        {
-            let mut tracker = JOB_TRACKER.lock().unwrap();
-            tracker.insert(job_id, job_status.clone());
+        if !ACCEPTING_JOBS.load(Ordering::Relaxed) {
+            return Err(PrintError::ShuttingDown);
         }
+        if deadline.is_none_or(|deadline| Instant::now() >= deadline) {
+            tracing::warn!(printer = printer_name, "Queue full: {}", reason);
+            return Err(PrintError::QueueFull);
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+}
 
-        // Spawn background thread to handle printing (simplified)
-        let printer_name_owned = printer_name.to_string();
-        let file_path_owned = file_path.to_string();
-        let job_options_owned = Some(job_options);
-        let shutdown_flag = SHUTDOWN_FLAG.clone();
-        let job_tracker = JOB_TRACKER.clone();
+// ===== JOB DEDUPLICATION =====
 
-        let handle = thread::spawn(move || {
-            Self::handle_print_job_simple(
-                job_id,
-                printer_name_owned,
-                file_path_owned,
-                job_options_owned,
-                shutdown_flag,
-                job_tracker,
-            );
-        });
+/// How long a dedupe key is held when the job doesn't set a window
+pub const DEFAULT_DEDUPE_WINDOW: Duration = Duration::from_secs(5);
 
-        // Store thread handle for cleanup
-        {
-            let mut handles = THREAD_HANDLES.lock().unwrap();
-            handles.push(handle);
+lazy_static::lazy_static! {
+    static ref DEDUPE_KEYS: Mutex<HashMap<String, DedupeEntry>> = Mutex::new(HashMap::new());
+}
+
+/// Jobs submitted under a dedupe key, and when the key is released
+struct DedupeEntry {
+    job_ids: Vec<JobId>,
+    expires: Instant,
+}
+
+/// What happens to a submission whose dedupe key is already held
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DedupeMode {
+    /// Return the ID of the job submitted first instead of a new one
+    Coalesce,
+    /// Fail with DuplicateJob
+    Reject,
+}
+
+impl DedupeMode {
+    pub fn parse(mode: &str) -> Option<DedupeMode> {
+        match mode {
+            "coalesce" => Some(DedupeMode::Coalesce),
+            "reject" => Some(DedupeMode::Reject),
+            _ => None,
         }
+    }
 
-        Ok(job_id)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DedupeMode::Coalesce => "coalesce",
+            DedupeMode::Reject => "reject",
+        }
     }
+}
 
-    /// Print raw bytes with optional job properties
-    pub fn print_bytes(
-        printer_name: &str,
-        data: &[u8],
-        job_options: Option<PrinterJobOptions>,
-    ) -> Result<JobId, PrintError> {
-        // Check if printer exists
-        let _printer =
-            Self::find_printer_by_name(printer_name).ok_or(PrintError::PrinterNotFound)?;
+/// A dedupe key held for a submission. Dropping it before `commit` releases
+/// the key, so a submission that fails doesn't turn retries into duplicates.
+struct DedupeClaim(Option<String>);
 
-        // Generate job ID
-        let job_id = generate_job_id();
+impl DedupeClaim {
+    /// Keep the key held for the rest of its window
+    fn commit(mut self) {
+        self.0 = None;
+    }
+}
 
-        // Extract job options
-        let job_options = job_options.unwrap_or_else(PrinterJobOptions::none);
+impl Drop for DedupeClaim {
+    fn drop(&mut self) {
+        if let Some(key) = self.0.take() {
+            DEDUPE_KEYS.lock().unwrap().remove(&key);
+        }
+    }
+}
 
-        // Create a temporary file path for tracking (since we're printing bytes)
-        let temp_file_path = format!("<bytes:{} bytes>", data.len());
+/// Hold the submission's dedupe key for its jobs, or return the jobs
+/// already submitted under the key within its window
+fn claim_dedupe_key(
+    job_options: &PrinterJobOptions,
+    job_ids: &[JobId],
+) -> Result<DedupeClaim, Vec<JobId>> {
+    let Some(key) = &job_options.dedupe_key else {
+        return Ok(DedupeClaim(None));
+    };
+    let now = Instant::now();
+    let mut keys = DEDUPE_KEYS.lock().unwrap();
+    keys.retain(|_, entry| entry.expires > now);
+    if let Some(entry) = keys.get(key) {
+        tracing::info!(
+            key = %key,
+            original = ?entry.job_ids,
+            mode = job_options.dedupe_mode.as_str(),
+            "Duplicate submission"
+        );
+        return Err(entry.job_ids.clone());
+    }
 
-        // Detect media type (raw bytes)
-        let media_type = detect_media_type(&temp_file_path);
+    let window = job_options.dedupe_window.unwrap_or(DEFAULT_DEDUPE_WINDOW);
+    keys.insert(
+        key.clone(),
+        DedupeEntry {
+            job_ids: job_ids.to_vec(),
+            expires: now + window,
+        },
+    );
+    Ok(DedupeClaim(Some(key.clone())))
+}
 
-        // Create job name from options or default
-        let job_name = job_options
-            .name
-            .clone()
-            .unwrap_or_else(|| "Raw Bytes Print Job".to_string());
+/// Outcome of a duplicate submission: the job submitted first, or
+/// DuplicateJob
+fn duplicate_job(
+    job_options: &PrinterJobOptions,
+    originals: &[JobId],
+) -> Result<JobId, PrintError> {
+    match (job_options.dedupe_mode, originals.first()) {
+        (DedupeMode::Coalesce, Some(original)) => Ok(*original),
+        _ => Err(PrintError::DuplicateJob),
+    }
+}
 
-        // Create job status
-        let job_status = PrinterJob {
-            id: job_id,
-            name: job_name,
-            state: PrinterJobState::PENDING,
-            media_type,
-            created_at: SystemTime::now(),
-            processed_at: None,
-            completed_at: None,
-            printer_name: printer_name.to_string(),
-            error_message: None,
-        };
+// ===== JOB DEPENDENCIES =====
 
-        // Store job in tracker
-        {
-            let mut tracker = JOB_TRACKER.lock().unwrap();
-            tracker.insert(job_id, job_status.clone());
+/// Reject dependencies on jobs that aren't tracked
+fn check_dependencies(depends_on: &[JobId]) -> Result<(), PrintError> {
+    match depends_on
+        .iter()
+        .find(|id| JOB_TRACKER.inspect(**id, |_| ()).is_none())
+    {
+        Some(id) => {
+            tracing::warn!(dependency = id, "Unknown dependency job");
+            Err(PrintError::JobNotFound)
         }
+        None => Ok(()),
+    }
+}
 
-        // Spawn background thread to handle printing
-        let printer_name_owned = printer_name.to_string();
-        let data_owned = data.to_vec();
-        let job_options_owned = Some(job_options);
-        let shutdown_flag = SHUTDOWN_FLAG.clone();
-        let job_tracker = JOB_TRACKER.clone();
+/// Wait until all dependencies have completed. If one fails, is cancelled or
+/// leaves the history before it was seen to complete, the job fails and
+/// false is returned; false is also returned if the job is cancelled while
+/// waiting or on shutdown. Dependencies are checked at the job's poll interval.
+fn await_dependencies(
+    job_tracker: &JobTracker,
+    job_id: JobId,
+    depends_on: &[JobId],
+    shutdown_flag: &Arc<AtomicBool>,
+) -> bool {
+    let mut remaining = depends_on.to_vec();
+    if !remaining.is_empty() {
+        let ids: Vec<String> = remaining.iter().map(JobId::to_string).collect();
+        job_tracker.log(
+            job_id,
+            LogLevel::Info,
+            format!("Waiting for jobs {} to complete", ids.join(", ")),
+        );
+    }
+    loop {
+        if shutdown_flag.load(Ordering::Relaxed)
+            || job_tracker.inspect(job_id, |job| job.state.is_terminal()) != Some(false)
+        {
+            return false;
+        }
 
-        let handle = thread::spawn(move || {
-            Self::handle_print_bytes_job(
-                job_id,
-                printer_name_owned,
-                data_owned,
-                job_options_owned,
-                shutdown_flag,
-                job_tracker,
-            );
+        let mut failed = None;
+        remaining.retain(|dependency| {
+            match job_tracker.inspect(*dependency, |job| job.state.clone()) {
+                Some(PrinterJobState::COMPLETED) => false,
+                Some(state) if !state.is_terminal() => true,
+                _ => {
+                    failed.get_or_insert(*dependency);
+                    true
+                }
+            }
         });
-
-        // Store thread handle for cleanup
-        {
-            let mut handles = THREAD_HANDLES.lock().unwrap();
-            handles.push(handle);
+        if let Some(dependency) = failed {
+            let error = format!("Dependency job {} did not complete", dependency);
+            complete_job(job_tracker, job_id, false, Some(error));
+            return false;
+        }
+        if remaining.is_empty() {
+            return true;
         }
+        thread::sleep(job_poll_interval(job_id));
+    }
+}
 
-        Ok(job_id)
+// ===== JOB QUERIES =====
+
+/// Order of the jobs returned by a query
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum JobSort {
+    CreatedAsc,
+    #[default]
+    CreatedDesc,
+    /// Jobs that haven't finished sort before finished ones
+    CompletedAsc,
+    /// Jobs that haven't finished sort after finished ones
+    CompletedDesc,
+}
+
+impl JobSort {
+    pub fn parse(sort: &str) -> Option<JobSort> {
+        match sort {
+            "created-asc" => Some(JobSort::CreatedAsc),
+            "created-desc" => Some(JobSort::CreatedDesc),
+            "completed-asc" => Some(JobSort::CompletedAsc),
+            "completed-desc" => Some(JobSort::CompletedDesc),
+            _ => None,
+        }
     }
 
-    /// Handle print job (file) - updated with real printing
-    fn handle_print_job_simple(
-        job_id: JobId,
-        printer_name: String,
-        file_path: String,
-        job_options: Option<PrinterJobOptions>,
-        shutdown_flag: Arc<AtomicBool>,
-        job_tracker: JobTracker,
-    ) {
-        // Update status to processing
-        {
-            let mut tracker = job_tracker.lock().unwrap();
-            if let Some(job) = tracker.get_mut(&job_id) {
-                job.state = PrinterJobState::PROCESSING;
-                job.processed_at = Some(SystemTime::now());
-            }
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobSort::CreatedAsc => "created-asc",
+            JobSort::CreatedDesc => "created-desc",
+            JobSort::CompletedAsc => "completed-asc",
+            JobSort::CompletedDesc => "completed-desc",
         }
+    }
+}
 
-        if should_simulate_printing() {
-            if simulate_print_delay(&shutdown_flag) {
-                complete_job(&job_tracker, job_id, true, None);
-            }
-        } else {
-            // Real printing using printers crate
-            let raw_options = job_options
-                .map(|opts| opts.raw_properties)
-                .unwrap_or_default();
-            let print_result =
-                Self::execute_real_print_job(&printer_name, &file_path, &raw_options);
+/// Which tracked jobs a query returns. Empty or unset criteria match every
+/// job.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct JobQuery {
+    pub printer_name: Option<String>,
+    pub states: Vec<PrinterJobState>,
+    /// Jobs created at or after this time
+    pub since: Option<SystemTime>,
+    /// Jobs created before this time
+    pub until: Option<SystemTime>,
+    /// Values the job's metadata must have
+    pub metadata: HashMap<String, String>,
+    pub sort: JobSort,
+    /// Matching jobs skipped before the first one returned
+    pub offset: usize,
+    /// Maximum number of jobs returned (None = all)
+    pub limit: Option<usize>,
+}
 
-            match print_result {
-                Ok(_) => complete_job(&job_tracker, job_id, true, None),
-                Err(error_msg) => complete_job(&job_tracker, job_id, false, Some(error_msg)),
-            }
+impl JobQuery {
+    /// Pending, processing and paused jobs, oldest first
+    pub fn active(printer_name: Option<&str>) -> JobQuery {
+        JobQuery {
+            printer_name: printer_name.map(str::to_string),
+            states: vec![
+                PrinterJobState::PENDING,
+                PrinterJobState::PROCESSING,
+                PrinterJobState::PAUSED,
+            ],
+            sort: JobSort::CreatedAsc,
+            ..JobQuery::default()
         }
     }
 
-    /// Execute actual printing using the printers crate
-    fn execute_real_print_job(
-        printer_name: &str,
-        file_path: &str,
-        job_options: &HashMap<String, String>,
-    ) -> Result<(), String> {
-        // Find the printer
-        let printer = get_printer_by_name(printer_name)
-            .ok_or_else(|| format!("Printer '{}' not found", printer_name))?;
-
-        // Check if file exists
-        if !std::path::Path::new(file_path).exists() {
-            return Err(format!("File '{}' not found", file_path));
+    /// Completed, cancelled and failed jobs, most recent first
+    pub fn history(printer_name: Option<&str>) -> JobQuery {
+        JobQuery {
+            printer_name: printer_name.map(str::to_string),
+            states: vec![
+                PrinterJobState::COMPLETED,
+                PrinterJobState::CANCELLED,
+                PrinterJobState::FAILED,
+            ],
+            sort: JobSort::CreatedDesc,
+            ..JobQuery::default()
         }
+    }
 
-        // Convert HashMap to upstream PrinterJobOptions
-        use printers::common::base::job::PrinterJobOptions as PrinterJobOpts;
-        // Execute print with proper lifetime management
-        let result = if job_options.is_empty() {
-            let job_opts = PrinterJobOpts::none();
-            match printer.print_file(file_path, job_opts) {
-                Ok(_) => Ok(()),
-                Err(e) => Err(format!("Print failed: {:?}", e)),
-            }
-        } else {
-            // Convert HashMap to slice of tuple references with proper lifetime
-            let properties: Vec<(&str, &str)> = job_options
+    fn matches(&self, job: &PrinterJob) -> bool {
+        self.printer_name
+            .as_ref()
+            .is_none_or(|name| job.printer_name == *name)
+            && (self.states.is_empty() || self.states.contains(&job.state))
+            && self.since.is_none_or(|since| job.created_at >= since)
+            && self.until.is_none_or(|until| job.created_at < until)
+            && self
+                .metadata
                 .iter()
-                .map(|(k, v)| (k.as_str(), v.as_str()))
-                .collect();
+                .all(|(key, value)| job.job_options.metadata.get(key) == Some(value))
+    }
+}
 
-            let job_opts = PrinterJobOpts {
-                name: job_options.get("job-name").map(|s| s.as_str()),
-                raw_properties: &properties,
-                converter: printers::common::converters::Converter::None,
-            };
+/// A page of query results
+#[derive(Clone, Debug)]
+pub struct JobPage {
+    pub jobs: Vec<PrinterJob>,
+    /// Number of jobs matching the query, ignoring offset and limit
+    pub total: usize,
+}
 
-            match printer.print_file(file_path, job_opts) {
-                Ok(_) => Ok(()),
-                Err(e) => Err(format!("Print failed: {:?}", e)),
-            }
+impl PrinterCore {
+    /// Filter, sort and page the tracked jobs. Jobs held for approval are
+    /// tracked too, so the query covers them as well.
+    pub fn query_jobs(query: &JobQuery) -> JobPage {
+        let query = JobQuery {
+            printer_name: query.printer_name.as_deref().map(crate::aliases::resolve),
+            ..query.clone()
         };
+        let matches = |job: &PrinterJob| query.matches(job);
+        let (jobs, total) = match query.sort {
+            JobSort::CreatedAsc | JobSort::CreatedDesc => JOB_TRACKER.page(
+                matches,
+                |job| job.created_at,
+                query.sort == JobSort::CreatedDesc,
+                query.offset,
+                query.limit,
+            ),
+            JobSort::CompletedAsc | JobSort::CompletedDesc => JOB_TRACKER.page(
+                matches,
+                |job| job.completed_at,
+                query.sort == JobSort::CompletedDesc,
+                query.offset,
+                query.limit,
+            ),
+        };
+        JobPage { jobs, total }
+    }
+}
+
+// ===== JOB HISTORY EXPORT =====
 
-        result
+impl PrinterCore {
+    /// Write the tracked jobs created at or after `since` to a file, oldest
+    /// first, returning how many were written. Held jobs are restored into
+    /// the tracker from disk, so they are exported too.
+    pub fn export_job_history(
+        path: &Path,
+        format: ExportFormat,
+        since: Option<SystemTime>,
+    ) -> Result<usize, String> {
+        let jobs = Self::query_jobs(&JobQuery {
+            since,
+            sort: JobSort::CreatedAsc,
+            ..JobQuery::default()
+        })
+        .jobs;
+        let written = crate::export::write_file(path, &jobs, format)?;
+        tracing::info!(
+            path = %path.display(),
+            format = format.as_str(),
+            jobs = written,
+            "Exported job history"
+        );
+        Ok(written)
     }
+}
 
-    /// Execute actual byte printing using the printers crate
-    fn execute_real_print_bytes(
-        printer_name: &str,
-        data: &[u8],
-        job_options: &HashMap<String, String>,
-    ) -> Result<(), String> {
-        // Find the printer
-        let printer = get_printer_by_name(printer_name)
-            .ok_or_else(|| format!("Printer '{}' not found", printer_name))?;
+// ===== JOB APPROVAL =====
 
-        // Execute the print job with raw bytes - use temp file approach
-        let mut temp_file = NamedTempFile::new().expect("Unable to create temp file");
-
-        let result = match temp_file.write_all(data) {
-            Ok(()) => {
-                // Convert HashMap to upstream PrinterJobOptions
-                use printers::common::base::job::PrinterJobOptions as PrinterJobOpts;
-                let properties: Vec<(&str, &str)>;
-                let job_opts = match job_options.is_empty() {
-                    true => PrinterJobOpts::none(),
-                    false => {
-                        // Convert HashMap to slice of tuple references with proper lifetime
-                        properties = job_options
-                            .iter()
-                            .map(|(k, v)| (k.as_str(), v.as_str()))
-                            .collect();
-
-                        PrinterJobOpts {
-                            name: job_options.get("job-name").map(|s| s.as_str()),
-                            raw_properties: &properties,
-                            converter: printers::common::converters::Converter::None,
-                        }
-                    }
-                };
-                match printer.print_file(
-                    temp_file
-                        .into_temp_path()
-                        .to_str()
-                        .expect("Unable to resolve temp file path"),
-                    job_opts,
-                ) {
-                    Ok(_) => Ok(()),
-                    Err(e) => Err(format!("Byte print failed: {:?}", e)),
-                }
-            }
-            Err(e) => Err(format!("Failed to write temp file: {}", e)),
-        };
+/// Directory jobs held for approval are persisted in
+fn held_jobs_dir() -> PathBuf {
+    let config = CONFIG.read().unwrap();
+    config.held_jobs_dir.clone().unwrap_or_else(|| {
+        config
+            .temp_dir
+            .clone()
+            .unwrap_or_else(env::temp_dir)
+            .join(crate::held::DEFAULT_DIR_NAME)
+    })
+}
 
-        result
-    }
+/// Persist a job that requires approval and track it as held, instead of
+/// starting it. Its timeout doesn't start until it is approved.
+fn hold_job(mut job: PrinterJob, data: &[u8]) -> Result<(), PrintError> {
+    job.transition_to(PrinterJobState::HELD_FOR_APPROVAL)
+        .map_err(|_| PrintError::InvalidParams)?;
+    crate::held::save(&held_jobs_dir(), &job, data).map_err(|e| {
+        tracing::warn!(job_id = job.id, printer = %job.printer_name, "Failed to hold job: {}", e);
+        PrintError::SpoolFailed
+    })?;
+    tracing::info!(job_id = job.id, printer = %job.printer_name, "Job held for approval");
+    notify_job_event(WebhookEvent::Submitted, &job);
+    let job_id = job.id;
+    JOB_TRACKER.insert(job.clone());
+    log_submission(&job);
+    JOB_TRACKER.log(job_id, LogLevel::Info, "Held for approval");
+    crate::metrics::job_submitted();
+    Ok(())
+}
 
-    /// Handle print bytes job
-    fn handle_print_bytes_job(
-        job_id: JobId,
-        printer_name: String,
-        data: Vec<u8>,
-        job_options: Option<PrinterJobOptions>,
-        shutdown_flag: Arc<AtomicBool>,
-        job_tracker: JobTracker,
-    ) {
-        // Update status to processing
-        {
-            let mut tracker = job_tracker.lock().unwrap();
-            if let Some(job) = tracker.get_mut(&job_id) {
-                job.state = PrinterJobState::PROCESSING;
-                job.processed_at = Some(SystemTime::now());
-            }
+impl PrinterCore {
+    /// Send a job held for approval to its printer
+    pub fn approve_job(job_id: JobId) -> Result<(), PrintError> {
+        if !ACCEPTING_JOBS.load(Ordering::Relaxed) {
+            return Err(PrintError::ShuttingDown);
         }
-
-        if should_simulate_printing() {
-            if simulate_print_delay(&shutdown_flag) {
-                complete_job(&job_tracker, job_id, true, None);
+        // Claiming the job first keeps a concurrent approval or rejection
+        // from seeing it as still held
+        let job = JOB_TRACKER
+            .update(job_id, |job| {
+                if job.state != PrinterJobState::HELD_FOR_APPROVAL {
+                    return None;
+                }
+                job.transition_to(PrinterJobState::PENDING).ok()?;
+                Some(job.clone())
+            })
+            .ok_or(PrintError::JobNotFound)?
+            .ok_or(PrintError::InvalidParams)?;
+
+        let payload = match crate::held::release(&held_jobs_dir(), job_id) {
+            Ok(Some(payload)) => payload,
+            Ok(None) => {
+                complete_job(
+                    &JOB_TRACKER,
+                    job_id,
+                    false,
+                    Some("Held job payload is missing".to_string()),
+                );
+                return Err(PrintError::FileNotFound);
             }
-        } else {
-            // Real printing using printers crate
-            let raw_options = job_options
-                .map(|opts| opts.raw_properties)
-                .unwrap_or_default();
-            let print_result = Self::execute_real_print_bytes(&printer_name, &data, &raw_options);
-
-            match print_result {
-                Ok(_) => complete_job(&job_tracker, job_id, true, None),
-                Err(error_msg) => complete_job(&job_tracker, job_id, false, Some(error_msg)),
+            Err(e) => {
+                // Keep the job held so it can be approved once the right
+                // encryption key is set, unless it was cancelled meanwhile
+                tracing::warn!(job_id, "Failed to release held job: {}", e);
+                JOB_TRACKER.update(job_id, |job| {
+                    let _ = job.transition_to(PrinterJobState::HELD_FOR_APPROVAL);
+                });
+                JOB_TRACKER.log(
+                    job_id,
+                    LogLevel::Warn,
+                    format!("Approval failed, still held: {}", e),
+                );
+                return Err(PrintError::SpoolFailed);
             }
+        };
+        tracing::info!(job_id, printer = %job.printer_name, "Job approved");
+        JOB_TRACKER.log(job_id, LogLevel::Info, "Approved");
+        crate::metrics::set_queue_depth(queue_depth(&JOB_TRACKER));
+        ensure_history_sweeper();
+        ensure_stall_watchdog();
+
+        if let Some(timeout) = job.job_options.timeout {
+            start_job_timeout(JOB_TRACKER.clone(), job_id, timeout);
         }
-    }
 
-    /// Get job status
-    pub fn get_job_status(job_id: JobId) -> Option<PrinterJob> {
-        let tracker = JOB_TRACKER.lock().unwrap();
-        tracker.get(&job_id).cloned()
-    }
-
-    /// Get all active jobs (pending or processing)
-    pub fn get_active_jobs() -> Vec<PrinterJob> {
-        let tracker = JOB_TRACKER.lock().unwrap();
-        tracker
-            .values()
-            .filter(|job| {
-                matches!(
-                    job.state,
-                    PrinterJobState::PENDING
-                        | PrinterJobState::PROCESSING
-                        | PrinterJobState::PAUSED
-                )
-            })
-            .cloned()
-            .collect()
-    }
-
-    /// Get active jobs for a specific printer
-    pub fn get_active_jobs_for_printer(printer_name: &str) -> Vec<PrinterJob> {
-        let tracker = JOB_TRACKER.lock().unwrap();
-        tracker
-            .values()
-            .filter(|job| {
-                job.printer_name == printer_name
-                    && matches!(
-                        job.state,
-                        PrinterJobState::PENDING
-                            | PrinterJobState::PROCESSING
-                            | PrinterJobState::PAUSED
-                    )
-            })
-            .cloned()
-            .collect()
+        let file_path = payload.path().to_string_lossy().into_owned();
+        let job_options = Some(job.job_options);
+        let shutdown_flag = SHUTDOWN_FLAG.clone();
+        let job_tracker = JOB_TRACKER.clone();
+        let task = supervisor::spawn_once(format!("job-{}", job_id), move || {
+            // The held payload is removed once the job is done
+            let _payload = payload;
+            Self::handle_print_job_simple(
+                job_id,
+                file_path,
+                job_options,
+                shutdown_flag,
+                job_tracker,
+            );
+        });
+        JOB_TASKS.push(task);
+        Ok(())
     }
 
-    /// Get job history (completed or cancelled jobs)
-    pub fn get_job_history() -> Vec<PrinterJob> {
-        let tracker = JOB_TRACKER.lock().unwrap();
-        tracker
-            .values()
-            .filter(|job| {
-                matches!(
-                    job.state,
-                    PrinterJobState::COMPLETED | PrinterJobState::CANCELLED
-                )
+    /// Cancel a job held for approval without printing it
+    pub fn reject_job(job_id: JobId, reason: Option<&str>) -> Result<(), PrintError> {
+        JOB_TRACKER
+            .update(job_id, |job| {
+                if job.state != PrinterJobState::HELD_FOR_APPROVAL
+                    || job.transition_to(PrinterJobState::CANCELLED).is_err()
+                {
+                    return false;
+                }
+                job.error_message = Some(reason.unwrap_or("Rejected").to_string());
+                job.completed_at = Some(SystemTime::now());
+                true
             })
-            .cloned()
-            .collect()
+            .ok_or(PrintError::JobNotFound)?
+            .then_some(())
+            .ok_or(PrintError::InvalidParams)?;
+
+        crate::held::remove(&held_jobs_dir(), job_id);
+        tracing::info!(job_id, "Job rejected");
+        JOB_TRACKER.log(
+            job_id,
+            LogLevel::Info,
+            format!("Rejected: {}", reason.unwrap_or("Rejected")),
+        );
+        evict_job_history(&JOB_TRACKER);
+        Ok(())
     }
 
-    /// Get job history for a specific printer
-    pub fn get_job_history_for_printer(printer_name: &str) -> Vec<PrinterJob> {
-        let tracker = JOB_TRACKER.lock().unwrap();
-        tracker
-            .values()
-            .filter(|job| {
-                job.printer_name == printer_name
-                    && matches!(
-                        job.state,
-                        PrinterJobState::COMPLETED | PrinterJobState::CANCELLED
-                    )
-            })
-            .cloned()
-            .collect()
+    /// Jobs waiting for approval, optionally only for one printer, oldest
+    /// first
+    pub fn get_held_jobs(printer_name: Option<&str>) -> Vec<PrinterJob> {
+        let printer_name = printer_name.map(crate::aliases::resolve);
+        let mut jobs = JOB_TRACKER.filter(|job| {
+            job.state == PrinterJobState::HELD_FOR_APPROVAL
+                && printer_name
+                    .as_ref()
+                    .is_none_or(|name| job.printer_name == *name)
+        });
+        jobs.sort_by_key(|job| job.id);
+        jobs
     }
 
-    /// Get all jobs for a specific printer
-    pub fn get_all_jobs_for_printer(printer_name: &str) -> Vec<PrinterJob> {
-        let tracker = JOB_TRACKER.lock().unwrap();
-        tracker
-            .values()
-            .filter(|job| job.printer_name == printer_name)
-            .cloned()
-            .collect()
+    /// Track the jobs persisted in the held jobs directory that aren't
+    /// tracked yet, returning how many were restored. Called when the
+    /// library loads and when the directory is configured.
+    pub fn restore_held_jobs() -> usize {
+        let mut restored = 0;
+        for job in crate::held::load_all(&held_jobs_dir()) {
+            // New jobs mustn't reuse the IDs of restored ones
+            {
+                let mut next_id = NEXT_JOB_ID.lock().unwrap();
+                *next_id = (*next_id).max(job.id + 1);
+            }
+            if let Some(state) = JOB_TRACKER.inspect(job.id, |tracked| tracked.state.clone()) {
+                if state != PrinterJobState::HELD_FOR_APPROVAL {
+                    tracing::warn!(
+                        job_id = job.id,
+                        "A different job has the ID of a held job, not restoring it"
+                    );
+                }
+                continue;
+            }
+            let job_id = job.id;
+            JOB_TRACKER.insert(job);
+            JOB_TRACKER.log(job_id, LogLevel::Info, "Restored as held for approval");
+            restored += 1;
+        }
+        if restored > 0 {
+            tracing::info!(restored, "Restored jobs held for approval");
+        }
+        restored
     }
+}
 
-    /// Clean up old completed/failed jobs
-    pub fn cleanup_old_jobs(max_age_seconds: u64) -> u32 {
-        let mut tracker = JOB_TRACKER.lock().unwrap();
-        let max_age = Duration::from_secs(max_age_seconds);
-        let mut removed_count = 0;
-
-        tracker.retain(|_, job| {
-            let should_keep = job.created_at.elapsed().unwrap_or(Duration::from_secs(0)) < max_age
-                || (job.state != PrinterJobState::COMPLETED
-                    && job.state != PrinterJobState::CANCELLED);
-            if !should_keep {
-                removed_count += 1;
-            }
-            should_keep
-        });
+// ===== JOB HISTORY RETENTION =====
 
-        removed_count
-    }
+lazy_static::lazy_static! {
+    static ref HISTORY_SWEEPER: Mutex<Option<(SupervisedTask, mpsc::Sender<()>)>> = Mutex::new(None);
+}
 
-    /// Clean up old completed/failed jobs for a specific printer
-    pub fn cleanup_old_jobs_for_printer(printer_name: &str, max_age_seconds: u64) -> u32 {
-        let mut tracker = JOB_TRACKER.lock().unwrap();
-        let max_age = Duration::from_secs(max_age_seconds);
-        let mut removed_count = 0;
-
-        tracker.retain(|_, job| {
-            let should_remove = job.printer_name == printer_name
-                && job.created_at.elapsed().unwrap_or(Duration::from_secs(0)) >= max_age
-                && (job.state == PrinterJobState::COMPLETED
-                    || job.state == PrinterJobState::CANCELLED);
-            if should_remove {
-                removed_count += 1;
-            }
-            !should_remove
+/// Remove completed/cancelled jobs past the configured TTL, then evict the
+/// least recently used ones beyond the configured history size
+fn evict_job_history(tracker: &JobStore) -> u32 {
+    let (max_history, ttl) = {
+        let config = CONFIG.read().unwrap();
+        (config.max_job_history, config.history_ttl)
+    };
+    let mut evicted = 0;
+
+    if let Some(ttl) = ttl {
+        evicted += tracker.retain(|job| {
+            !job.state.is_terminal()
+                || job
+                    .completed_at
+                    .is_some_and(|at| at.elapsed().unwrap_or_default() < ttl)
         });
+    }
 
-        removed_count
+    // Counting first avoids copying the history on every completion
+    if let Some(max_history) =
+        max_history.filter(|max| tracker.count(|job| job.state.is_terminal()) > *max)
+    {
+        let mut history: Vec<(SystemTime, JobId)> = tracker
+            .filter(|job| job.state.is_terminal())
+            .iter()
+            .map(|job| (job.last_used(), job.id))
+            .collect();
+        if history.len() > max_history {
+            history.sort_unstable();
+            for (_, job_id) in &history[..history.len() - max_history] {
+                if tracker.remove(*job_id).is_some() {
+                    evicted += 1;
+                }
+            }
+        }
     }
 
-    /// Shutdown the library and cleanup all background threads
-    pub fn shutdown_library() {
-        // Set shutdown flag
-        SHUTDOWN_FLAG.store(true, Ordering::Relaxed);
+    let evicted = evicted as u32;
+    if evicted > 0 {
+        tracing::debug!(evicted, "Evicted job history");
+        release_spool_files(tracker);
+    }
+    evicted
+}
 
-        // Wait for all threads to complete (with timeout)
-        let mut handles = THREAD_HANDLES.lock().unwrap();
-        let timeout = Duration::from_secs(5);
-        let start = Instant::now();
+/// Release payloads retained for jobs that are no longer tracked
+fn release_spool_files(tracker: &JobStore) {
+    let released = crate::spool::release_where(|job_id| tracker.inspect(job_id, |_| ()).is_none());
+    if released > 0 {
+        tracing::debug!(released, "Released retained spool files");
+    }
+}
 
-        while !handles.is_empty() && start.elapsed() < timeout {
-            // Take ownership of handles
-            let current_handles: Vec<_> = handles.drain(..).collect();
+/// How often the sweeper checks for expired jobs
+fn sweep_interval(ttl: Duration) -> Duration {
+    (ttl / 4).clamp(Duration::from_secs(1), Duration::from_secs(60))
+}
 
-            for handle in current_handles {
-                let _ = handle.join();
-            }
+/// Start the background sweeper if a history TTL is configured
+fn ensure_history_sweeper() {
+    let Some(ttl) = CONFIG.read().unwrap().history_ttl else {
+        return;
+    };
+    let mut sweeper = HISTORY_SWEEPER.lock().unwrap();
+    if sweeper.is_some() {
+        return;
+    }
 
-            if !handles.is_empty() {
-                thread::sleep(Duration::from_millis(100));
+    let (stop_sender, stop_receiver) = mpsc::channel();
+    let task = supervisor::spawn("history-sweeper", RestartPolicy::BACKGROUND, move || {
+        let mut interval = sweep_interval(ttl);
+        while let Err(mpsc::RecvTimeoutError::Timeout) = stop_receiver.recv_timeout(interval) {
+            evict_job_history(&JOB_TRACKER);
+            // Pick up TTL changes made through configure()
+            if let Some(ttl) = CONFIG.read().unwrap().history_ttl {
+                interval = sweep_interval(ttl);
             }
         }
+    });
+    *sweeper = Some((task, stop_sender));
+}
 
-        // Clear job tracker
-        let mut tracker = JOB_TRACKER.lock().unwrap();
-        tracker.clear();
-
-        // Reset shutdown flag for potential reuse
-        SHUTDOWN_FLAG.store(false, Ordering::Relaxed);
+/// Stop the background sweeper, if running
+fn stop_history_sweeper() {
+    if let Some((task, stop_sender)) = HISTORY_SWEEPER.lock().unwrap().take() {
+        let _ = stop_sender.send(());
+        let _ = task.join();
     }
 }
 
-/// Extended functionality for Printer objects
-pub trait PrinterJobTracking {
-    /// Get active jobs for this printer
-    fn get_active_jobs(&self) -> Vec<PrinterJob>;
-
-    /// Get job history for this printer
-    fn get_job_history(&self, limit: Option<usize>) -> Vec<PrinterJob>;
+// ===== JOB EVENTS =====
 
-    /// Get a specific job by ID (only if it belongs to this printer)
-    fn get_job(&self, job_id: JobId) -> Option<PrinterJob>;
+/// Receives job lifecycle events, the same events that are sent to the
+/// webhook
+pub type JobEventCallback = Arc<dyn Fn(WebhookEvent, &PrinterJob) + Send + Sync>;
 
-    /// Get all jobs for this printer
-    fn get_all_jobs(&self) -> Vec<PrinterJob>;
+static NEXT_JOB_EVENT_SUBSCRIPTION: AtomicUsize = AtomicUsize::new(1);
 
-    /// Clean up old jobs for this printer
-    fn cleanup_old_jobs(&self, max_age_seconds: u64) -> u32;
+lazy_static::lazy_static! {
+    static ref JOB_EVENT_CALLBACKS: RwLock<Vec<(usize, JobEventCallback)>> = RwLock::new(Vec::new());
 }
 
-impl PrinterJobTracking for Printer {
-    fn get_active_jobs(&self) -> Vec<PrinterJob> {
-        let tracker = JOB_TRACKER.lock().unwrap();
-        tracker
-            .values()
-            .filter(|job| {
-                job.printer_name == self.name
-                    && !matches!(
-                        job.state,
-                        PrinterJobState::COMPLETED | PrinterJobState::CANCELLED
-                    )
-            })
-            .cloned()
-            .collect()
-    }
-
-    fn get_job_history(&self, limit: Option<usize>) -> Vec<PrinterJob> {
-        let tracker = JOB_TRACKER.lock().unwrap();
-        let mut jobs: Vec<_> = tracker
-            .values()
-            .filter(|job| {
-                job.printer_name == self.name
-                    && matches!(
-                        job.state,
-                        PrinterJobState::COMPLETED | PrinterJobState::CANCELLED
-                    )
-            })
-            .cloned()
-            .collect();
-
-        // Sort by creation time (most recent first)
-        jobs.sort_by_key(|job| std::cmp::Reverse(job.created_at));
-
-        if let Some(limit) = limit {
-            jobs.truncate(limit);
+/// Send a job event to the webhook and subscribers
+fn notify_job_event(event: WebhookEvent, job: &PrinterJob) {
+    let audit_log = CONFIG.read().unwrap().audit_log.clone();
+    if let Some(path) = audit_log {
+        if let Err(e) = crate::audit::record(&path, event, job) {
+            tracing::warn!(job_id = job.id, "{}", e);
         }
-
-        jobs
     }
-
-    fn get_job(&self, job_id: JobId) -> Option<PrinterJob> {
-        let tracker = JOB_TRACKER.lock().unwrap();
-        tracker
-            .get(&job_id)
-            .filter(|job| job.printer_name == self.name)
-            .cloned()
+    crate::webhooks::notify(event, job);
+    let callbacks: Vec<JobEventCallback> = JOB_EVENT_CALLBACKS
+        .read()
+        .unwrap()
+        .iter()
+        .map(|(_, callback)| Arc::clone(callback))
+        .collect();
+    for callback in callbacks {
+        callback(event, job);
     }
+}
 
-    fn get_all_jobs(&self) -> Vec<PrinterJob> {
-        let tracker = JOB_TRACKER.lock().unwrap();
-        tracker
-            .values()
-            .filter(|job| job.printer_name == self.name)
-            .cloned()
-            .collect()
+impl PrinterCore {
+    /// Subscribe to job lifecycle events, returning the subscription ID.
+    /// Callbacks run on the thread that submitted or finished the job, so
+    /// they should return quickly.
+    pub fn subscribe_to_job_events(callback: JobEventCallback) -> usize {
+        let id = NEXT_JOB_EVENT_SUBSCRIPTION.fetch_add(1, Ordering::Relaxed);
+        JOB_EVENT_CALLBACKS.write().unwrap().push((id, callback));
+        id
     }
 
-    fn cleanup_old_jobs(&self, max_age_seconds: u64) -> u32 {
-        let mut tracker = JOB_TRACKER.lock().unwrap();
-        let max_age = Duration::from_secs(max_age_seconds);
-        let mut removed_count = 0;
-
-        tracker.retain(|_, job| {
-            let should_remove = job.printer_name == self.name
-                && job.created_at.elapsed().unwrap_or(Duration::from_secs(0)) >= max_age
-                && (job.state == PrinterJobState::COMPLETED
-                    || job.state == PrinterJobState::CANCELLED);
-
-            if should_remove {
-                removed_count += 1;
-                false
-            } else {
-                true
-            }
-        });
-
-        removed_count
+    /// Remove a job event subscription, returning whether it existed
+    pub fn unsubscribe_from_job_events(subscription_id: usize) -> bool {
+        let mut callbacks = JOB_EVENT_CALLBACKS.write().unwrap();
+        let before = callbacks.len();
+        callbacks.retain(|(id, _)| *id != subscription_id);
+        callbacks.len() != before
     }
 }
 
-// ===== PRINTER STATE MONITORING SYSTEM =====
-
-use std::collections::HashSet;
-use std::sync::mpsc::{self, Receiver, Sender};
+// ===== AUDIT LOG =====
 
-/// Printer state change event
-#[derive(Clone, Debug, PartialEq)]
-pub enum PrinterStateEvent {
-    /// Printer connected/appeared
-    Connected { name: String },
-    /// Printer disconnected/removed
-    Disconnected { name: String },
-    /// Printer state changed (idle -> printing, etc.)
-    StateChanged {
-        name: String,
-        old_state: String,
-        new_state: String,
-    },
-    /// Printer state reasons changed (error conditions, etc.)
-    StateReasonsChanged {
-        name: String,
-        old_reasons: Vec<String>,
-        new_reasons: Vec<String>,
-    },
+impl PrinterCore {
+    /// Check an audit log's hash chain, defaulting to the configured log
+    pub fn verify_audit_log(
+        path: Option<&Path>,
+    ) -> Result<crate::audit::AuditVerification, String> {
+        let configured = CONFIG.read().unwrap().audit_log.clone();
+        let path = path
+            .or(configured.as_deref())
+            .ok_or_else(|| "No audit log is configured".to_string())?;
+        crate::audit::verify(path)
+    }
 }
 
-/// Printer state snapshot for tracking changes
-#[derive(Clone, Debug, PartialEq)]
-struct PrinterStateSnapshot {
-    name: String,
-    state: String,
-    state_reasons: Vec<String>,
-    exists: bool,
+// ===== REMOTE PRINTERS =====
+
+/// Consecutive failed status checks after which a remote job is failed
+const REMOTE_STATUS_ATTEMPTS: u32 = 10;
+
+/// Forward a job to a remote printer and follow the remote job until it
+/// finishes. The worker slot is released once the document is submitted,
+/// as the remote end does the printing.
+fn print_remote(
+    remote: &crate::remote::RemotePrinter,
+    job_id: JobId,
+    document: &Path,
+    job_options: &PrinterJobOptions,
+    slot: WorkerSlot,
+    shutdown_flag: &Arc<AtomicBool>,
+    job_tracker: &JobTracker,
+) {
+    use crate::remote::RemoteJobState;
+
+    let job_name = job_tracker
+        .inspect(job_id, |job| job.name.clone())
+        .unwrap_or_default();
+    let submitted = std::fs::read(document)
+        .map_err(|e| format!("Failed to read document: {}", e))
+        .and_then(|data| remote.submit(&job_name, &data, job_options));
+    drop(slot);
+    let remote_job_id = match submitted {
+        Ok(remote_job_id) => remote_job_id,
+        Err(error_msg) => return complete_job(job_tracker, job_id, false, Some(error_msg)),
+    };
+    tracing::info!(
+        job_id,
+        printer = %remote.name,
+        url = %remote.url,
+        remote_job_id,
+        "Job forwarded to remote printer"
+    );
+    job_tracker.log(
+        job_id,
+        LogLevel::Info,
+        format!(
+            "Forwarded to remote printer '{}' as job {}",
+            remote.name, remote_job_id
+        ),
+    );
+
+    let mut failures = 0;
+    loop {
+        let start = Instant::now();
+        while start.elapsed() < remote.poll_interval {
+            if shutdown_flag.load(Ordering::Relaxed) {
+                return;
+            }
+            thread::sleep(Duration::from_millis(100).min(remote.poll_interval));
+        }
+        // Stop following jobs that finished locally, e.g. cancelled or timed out
+        if job_tracker.inspect(job_id, |job| job.state.is_terminal()) != Some(false) {
+            return;
+        }
+
+        match remote.job_state(remote_job_id) {
+            Ok(RemoteJobState::Completed) => return complete_job(job_tracker, job_id, true, None),
+            Ok(RemoteJobState::Failed(error_msg)) => {
+                return complete_job(job_tracker, job_id, false, Some(error_msg))
+            }
+            Ok(RemoteJobState::Queued | RemoteJobState::Processing) => failures = 0,
+            Err(e) => {
+                failures += 1;
+                tracing::warn!(job_id, remote_job_id, "Failed to check remote job: {}", e);
+                job_tracker.log(
+                    job_id,
+                    LogLevel::Warn,
+                    format!("Failed to check remote job: {}", e),
+                );
+                if failures >= REMOTE_STATUS_ATTEMPTS {
+                    let error_msg = format!("Lost contact with remote printer: {}", e);
+                    return complete_job(job_tracker, job_id, false, Some(error_msg));
+                }
+            }
+        }
+    }
 }
 
-/// Event subscription callback type
-pub type StateChangeCallback = Box<dyn Fn(PrinterStateEvent) + Send + Sync>;
+// ===== STALLED JOB WATCHDOG =====
 
-/// Printer state monitor with event subscription
-pub struct PrinterStateMonitor {
-    callbacks: Arc<Mutex<Vec<StateChangeCallback>>>,
-    monitoring_thread: Option<JoinHandle<()>>,
-    stop_sender: Option<Sender<()>>,
-    poll_interval: Duration,
+lazy_static::lazy_static! {
+    static ref STALL_WATCHDOG: Mutex<Option<(SupervisedTask, mpsc::Sender<()>)>> = Mutex::new(None);
+    static ref STALL_CALLBACK: Mutex<Option<JobStalledCallback>> = Mutex::new(None);
 }
 
-impl Default for PrinterStateMonitor {
-    fn default() -> Self {
-        Self::new()
-    }
+/// What the watchdog does with a job stuck in processing
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StallAction {
+    /// Report the job and leave it running
+    Flag,
+    /// Fail the job; the spooler job is cancelled if it is accepted later
+    Cancel,
+    /// Fail the job and resubmit it. A resubmitted job that stalls again is
+    /// cancelled rather than retried.
+    Retry,
 }
 
-impl PrinterStateMonitor {
-    /// Create a new printer state monitor
-    pub fn new() -> Self {
-        Self {
-            callbacks: Arc::new(Mutex::new(Vec::new())),
-            monitoring_thread: None,
-            stop_sender: None,
-            poll_interval: Duration::from_secs(2), // Default 2 second polling
+impl StallAction {
+    pub fn parse(action: &str) -> Option<StallAction> {
+        match action {
+            "flag" => Some(StallAction::Flag),
+            "cancel" => Some(StallAction::Cancel),
+            "retry" => Some(StallAction::Retry),
+            _ => None,
         }
     }
 
-    /// Set the polling interval for state monitoring
-    pub fn set_poll_interval(&mut self, interval: Duration) {
-        self.poll_interval = interval;
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StallAction::Flag => "flag",
+            StallAction::Cancel => "cancel",
+            StallAction::Retry => "retry",
+        }
     }
+}
 
-    /// Subscribe to printer state change events
-    pub fn subscribe<F>(&mut self, callback: F) -> usize
-    where
-        F: Fn(PrinterStateEvent) + Send + Sync + 'static,
-    {
-        let mut callbacks = self.callbacks.lock().unwrap();
-        callbacks.push(Box::new(callback));
-        callbacks.len() - 1 // Return subscription ID
-    }
+/// A job was processing for longer than the stall threshold
+#[derive(Clone, Debug, PartialEq)]
+pub struct JobStalledEvent {
+    pub job_id: JobId,
+    pub printer_name: String,
+    /// How long the job had been processing
+    pub processing_for: Duration,
+    /// Action taken, which is Cancel for a retry that stalled again
+    pub action: StallAction,
+    /// Job resubmitted in place of the stalled one
+    pub retry_job_id: Option<JobId>,
+    pub timestamp: SystemTime,
+}
 
-    /// Remove a subscription by ID
-    pub fn unsubscribe(&mut self, subscription_id: usize) -> bool {
-        let mut callbacks = self.callbacks.lock().unwrap();
-        if subscription_id < callbacks.len() {
-            let _removed = callbacks.remove(subscription_id);
-            true
-        } else {
-            false
-        }
-    }
+pub type JobStalledCallback = Arc<dyn Fn(&JobStalledEvent) + Send + Sync>;
 
-    /// Start monitoring printer state changes
-    pub fn start_monitoring(&mut self) -> Result<(), String> {
-        if self.monitoring_thread.is_some() {
-            return Err("Monitoring already started".to_string());
+/// Whether bytes jobs keep their payload so a stalled one can be retried
+fn retries_stalled_jobs() -> bool {
+    let config = CONFIG.read().unwrap();
+    config.stall_threshold.is_some() && config.stall_action == StallAction::Retry
+}
+
+/// Flag processing jobs that have reached the threshold and apply the action
+/// to each, returning one event per newly stalled job
+fn check_stalled_jobs(threshold: Duration, action: StallAction) -> Vec<JobStalledEvent> {
+    let now = SystemTime::now();
+    let mut stalled = Vec::new();
+    JOB_TRACKER.update_all(|job| {
+        if job.state != PrinterJobState::PROCESSING || job.stalled {
+            return;
         }
+        let processing_for = job
+            .processed_at
+            .and_then(|at| now.duration_since(at).ok())
+            .unwrap_or_default();
+        if processing_for >= threshold {
+            job.stalled = true;
+            stalled.push((
+                job.id,
+                job.printer_name.clone(),
+                processing_for,
+                job.reprint_of,
+            ));
+        }
+    });
 
-        let (stop_sender, stop_receiver) = mpsc::channel();
-        let callbacks = Arc::clone(&self.callbacks);
-        let poll_interval = self.poll_interval;
+    let mut events = Vec::new();
+    for (job_id, printer_name, processing_for, reprint_of) in stalled {
+        // Retry once: a retry that stalls again is only cancelled
+        let retried = reprint_of.and_then(|id| JOB_TRACKER.inspect(id, |job| job.stalled));
+        let action = match action {
+            StallAction::Retry if retried == Some(true) => StallAction::Cancel,
+            action => action,
+        };
+        tracing::warn!(
+            job_id,
+            printer = %printer_name,
+            action = action.as_str(),
+            "Job stalled after {}ms processing",
+            processing_for.as_millis()
+        );
+
+        JOB_TRACKER.log(
+            job_id,
+            LogLevel::Warn,
+            format!(
+                "Stalled after {}ms processing, action: {}",
+                processing_for.as_millis(),
+                action.as_str()
+            ),
+        );
+        if action != StallAction::Flag {
+            let error = format!(
+                "Job stalled after {}ms processing",
+                processing_for.as_millis()
+            );
+            finish_job(&JOB_TRACKER, job_id, false, Some(error), false);
+        }
+        let retry_job_id = match action {
+            StallAction::Retry => match PrinterCore::reprint_job(job_id) {
+                Ok(retry_job_id) => {
+                    JOB_TRACKER.log(
+                        job_id,
+                        LogLevel::Info,
+                        format!("Retried as job {}", retry_job_id),
+                    );
+                    Some(retry_job_id)
+                }
+                Err(e) => {
+                    tracing::warn!(job_id, "Failed to retry stalled job: {:?}", e);
+                    None
+                }
+            },
+            _ => None,
+        };
 
-        let handle = thread::spawn(move || {
-            Self::monitoring_loop(callbacks, stop_receiver, poll_interval);
+        events.push(JobStalledEvent {
+            job_id,
+            printer_name,
+            processing_for,
+            action,
+            retry_job_id,
+            timestamp: SystemTime::now(),
         });
+    }
+    events
+}
 
-        self.monitoring_thread = Some(handle);
-        self.stop_sender = Some(stop_sender);
-        Ok(())
+/// How often the watchdog checks for stalled jobs
+fn stall_check_interval(threshold: Duration) -> Duration {
+    (threshold / 4).clamp(Duration::from_millis(100), Duration::from_secs(10))
+}
+
+/// Start the background watchdog if a stall threshold is configured
+fn ensure_stall_watchdog() {
+    if CONFIG.read().unwrap().stall_threshold.is_none() {
+        return;
+    }
+    let mut watchdog = STALL_WATCHDOG.lock().unwrap();
+    if watchdog.is_some() {
+        return;
     }
 
-    /// Stop monitoring printer state changes
-    pub fn stop_monitoring(&mut self) -> Result<(), String> {
-        if let Some(sender) = self.stop_sender.take() {
-            let _ = sender.send(());
+    let (stop_sender, stop_receiver) = mpsc::channel();
+    let task = supervisor::spawn("stall-watchdog", RestartPolicy::BACKGROUND, move || loop {
+        // Pick up threshold and action changes made through configure()
+        let (threshold, action) = {
+            let config = CONFIG.read().unwrap();
+            (config.stall_threshold, config.stall_action)
+        };
+        let interval = threshold.map_or(Duration::from_secs(1), stall_check_interval);
+        match stop_receiver.recv_timeout(interval) {
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            _ => break,
         }
-
-        if let Some(handle) = self.monitoring_thread.take() {
-            match handle.join() {
-                Ok(_) => Ok(()),
-                Err(_) => Err("Failed to stop monitoring thread".to_string()),
+        let Some(threshold) = threshold else {
+            continue;
+        };
+        for event in check_stalled_jobs(threshold, action) {
+            let callback = STALL_CALLBACK.lock().unwrap().clone();
+            if let Some(callback) = callback {
+                callback(&event);
             }
-        } else {
-            Err("Monitoring not started".to_string())
         }
+    });
+    *watchdog = Some((task, stop_sender));
+}
+
+/// Stop the background watchdog, if running
+fn stop_stall_watchdog() {
+    if let Some((task, stop_sender)) = STALL_WATCHDOG.lock().unwrap().take() {
+        let _ = stop_sender.send(());
+        let _ = task.join();
     }
+}
 
-    /// Main monitoring loop
-    fn monitoring_loop(
-        callbacks: Arc<Mutex<Vec<StateChangeCallback>>>,
-        stop_receiver: Receiver<()>,
-        poll_interval: Duration,
-    ) {
-        let mut previous_states: HashMap<String, PrinterStateSnapshot> = HashMap::new();
+impl PrinterCore {
+    /// Receive stalled job events, replacing any previous callback
+    pub fn set_job_stalled_callback(callback: Option<JobStalledCallback>) {
+        *STALL_CALLBACK.lock().unwrap() = callback;
+    }
+}
 
-        loop {
-            // Check for stop signal with timeout
-            match stop_receiver.recv_timeout(poll_interval) {
-                Ok(_) => break, // Stop signal received
-                Err(mpsc::RecvTimeoutError::Timeout) => {
-                    // Continue monitoring
-                }
-                Err(mpsc::RecvTimeoutError::Disconnected) => break, // Channel closed
-            }
-
-            // Get current printer states
-            let current_states = Self::get_all_printer_states();
-            let current_names: HashSet<String> = current_states.keys().cloned().collect();
-            let previous_names: HashSet<String> = previous_states.keys().cloned().collect();
+/// Job status enum matching upstream printers crate
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum PrinterJobState {
+    PENDING,    // Job queued, waiting to be processed
+    PAUSED,     // Job temporarily halted
+    PROCESSING, // Job currently being printed
+    CANCELLED,  // Job cancelled by user or system
+    COMPLETED,  // Job finished successfully
+    FAILED,     // Job gave up, e.g. after its timeout
+    UNKNOWN,    // Undetermined state
+    #[serde(rename = "held-for-approval")]
+    #[allow(non_camel_case_types)]
+    HELD_FOR_APPROVAL, // Job spooled locally until approved or rejected
+}
 
-            // Check for new printers (connected)
-            for name in current_names.difference(&previous_names) {
-                let event = PrinterStateEvent::Connected { name: name.clone() };
-                Self::notify_subscribers(&callbacks, event);
-            }
+impl PrinterJobState {
+    /// Whether the job has finished (completed, cancelled or failed)
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            PrinterJobState::COMPLETED | PrinterJobState::CANCELLED | PrinterJobState::FAILED
+        )
+    }
 
-            // Check for removed printers (disconnected)
-            for name in previous_names.difference(&current_names) {
-                let event = PrinterStateEvent::Disconnected { name: name.clone() };
-                Self::notify_subscribers(&callbacks, event);
-            }
+    /// Whether the job is waiting to print or printing, as opposed to
+    /// finished or held for approval
+    pub fn is_queued(&self) -> bool {
+        !self.is_terminal() && *self != PrinterJobState::HELD_FOR_APPROVAL
+    }
 
-            // Check for state changes in existing printers
-            for (name, current_state) in &current_states {
-                if let Some(previous_state) = previous_states.get(name) {
-                    // Check for state change
-                    if current_state.state != previous_state.state {
-                        let event = PrinterStateEvent::StateChanged {
-                            name: name.clone(),
-                            old_state: previous_state.state.clone(),
-                            new_state: current_state.state.clone(),
-                        };
-                        Self::notify_subscribers(&callbacks, event);
-                    }
+    pub fn as_string(&self) -> String {
+        match self {
+            PrinterJobState::PENDING => "pending".to_string(),
+            PrinterJobState::PAUSED => "paused".to_string(),
+            PrinterJobState::PROCESSING => "processing".to_string(),
+            PrinterJobState::CANCELLED => "cancelled".to_string(),
+            PrinterJobState::COMPLETED => "completed".to_string(),
+            PrinterJobState::FAILED => "failed".to_string(),
+            PrinterJobState::UNKNOWN => "unknown".to_string(),
+            PrinterJobState::HELD_FOR_APPROVAL => "held-for-approval".to_string(),
+        }
+    }
 
-                    // Check for state reasons change
-                    if current_state.state_reasons != previous_state.state_reasons {
-                        let event = PrinterStateEvent::StateReasonsChanged {
-                            name: name.clone(),
-                            old_reasons: previous_state.state_reasons.clone(),
-                            new_reasons: current_state.state_reasons.clone(),
-                        };
-                        Self::notify_subscribers(&callbacks, event);
-                    }
-                }
-            }
+    /// Whether a job may move from this state to `to`. Finished jobs never
+    /// change state, and only jobs that started printing can complete.
+    pub fn can_transition_to(&self, to: &PrinterJobState) -> bool {
+        use PrinterJobState::*;
+        match (self, to) {
+            (from, to) if from == to => false,
+            (COMPLETED | CANCELLED | FAILED, _) => false,
+            (UNKNOWN, _) | (_, UNKNOWN) => true,
+            (PENDING, PAUSED | PROCESSING | CANCELLED | FAILED | HELD_FOR_APPROVAL) => true,
+            (PAUSED, PENDING | PROCESSING | CANCELLED | FAILED) => true,
+            (PROCESSING, PAUSED | COMPLETED | CANCELLED | FAILED) => true,
+            (HELD_FOR_APPROVAL, PENDING | CANCELLED) => true,
+            _ => false,
+        }
+    }
 
-            // Update previous states
-            previous_states = current_states;
+    pub fn parse(state: &str) -> Option<PrinterJobState> {
+        match state {
+            "pending" => Some(PrinterJobState::PENDING),
+            "paused" => Some(PrinterJobState::PAUSED),
+            "processing" => Some(PrinterJobState::PROCESSING),
+            "cancelled" => Some(PrinterJobState::CANCELLED),
+            "completed" => Some(PrinterJobState::COMPLETED),
+            "failed" => Some(PrinterJobState::FAILED),
+            "unknown" => Some(PrinterJobState::UNKNOWN),
+            "held-for-approval" => Some(PrinterJobState::HELD_FOR_APPROVAL),
+            _ => None,
         }
     }
+}
 
-    /// Get current state of all printers
-    fn get_all_printer_states() -> HashMap<String, PrinterStateSnapshot> {
-        let mut states = HashMap::new();
+/// A change of a job's state
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct JobTransition {
+    pub from: PrinterJobState,
+    pub to: PrinterJobState,
+    #[serde(rename = "timestamp", with = "unix_secs")]
+    #[cfg_attr(feature = "schema", schemars(with = "u64"))]
+    pub at: SystemTime,
+}
 
-        if should_simulate_printing() {
-            // In simulation mode, provide some mock printer states
-            states.insert(
-                "Simulated Printer".to_string(),
-                PrinterStateSnapshot {
-                    name: "Simulated Printer".to_string(),
-                    state: "idle".to_string(),
-                    state_reasons: vec![],
-                    exists: true,
-                },
-            );
-        } else {
-            // Get all real printers and their states
-            let printers = printers::get_printers();
-            for printer in printers {
-                let state = PrinterCore::get_printer_state(&printer);
-                states.insert(
-                    printer.name.clone(),
-                    PrinterStateSnapshot {
-                        name: printer.name.clone(),
-                        state,
-                        state_reasons: printer.state_reasons.clone(),
-                        exists: true,
-                    },
-                );
-            }
-        }
+/// How a job's media type was determined
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum MediaTypeSource {
+    Option,    // documentFormat job option (sent to CUPS as document-format)
+    Extension, // File extension
+    Content,   // Magic bytes, when the extension was missing or generic
+    Default,   // Nothing matched, or raw bytes
+}
 
-        states
+impl MediaTypeSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MediaTypeSource::Option => "option",
+            MediaTypeSource::Extension => "extension",
+            MediaTypeSource::Content => "content",
+            MediaTypeSource::Default => "default",
+        }
     }
 
-    /// Notify all subscribers of an event
-    fn notify_subscribers(
-        callbacks: &Arc<Mutex<Vec<StateChangeCallback>>>,
-        event: PrinterStateEvent,
-    ) {
-        let callbacks = callbacks.lock().unwrap();
-        for callback in callbacks.iter() {
-            callback(event.clone());
+    pub fn parse(source: &str) -> Option<MediaTypeSource> {
+        match source {
+            "option" => Some(MediaTypeSource::Option),
+            "extension" => Some(MediaTypeSource::Extension),
+            "content" => Some(MediaTypeSource::Content),
+            "default" => Some(MediaTypeSource::Default),
+            _ => None,
         }
     }
 }
 
-impl Drop for PrinterStateMonitor {
-    fn drop(&mut self) {
-        let _ = self.stop_monitoring();
-    }
+/// Print job structure matching upstream printers crate
+///
+/// Serializes to the JavaScript `PrinterJob`, with times as Unix timestamps
+/// in seconds. The file it was printed from, its last lookup and its options
+/// other than metadata are left out.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct PrinterJob {
+    /// Unique job identifier
+    pub id: JobId,
+    /// Job title/description
+    pub name: String,
+    /// Current job status
+    pub state: PrinterJobState,
+    /// File type (e.g., "application/pdf")
+    pub media_type: String,
+    /// How the file type was determined
+    #[serde(default = "default_media_type_source")]
+    pub media_type_source: MediaTypeSource,
+    /// Job creation timestamp
+    #[serde(with = "unix_secs")]
+    #[cfg_attr(feature = "schema", schemars(with = "u64"))]
+    pub created_at: SystemTime,
+    /// Processing start time (optional)
+    #[serde(default, with = "unix_secs::option")]
+    #[cfg_attr(feature = "schema", schemars(with = "Option<u64>"))]
+    pub processed_at: Option<SystemTime>,
+    /// Job completion time (optional)
+    #[serde(default, with = "unix_secs::option")]
+    #[cfg_attr(feature = "schema", schemars(with = "Option<u64>"))]
+    pub completed_at: Option<SystemTime>,
+    /// Associated printer name
+    pub printer_name: String,
+    /// Error details if failed
+    pub error_message: Option<String>,
+    /// Size of the submitted document
+    #[serde(default)]
+    pub size_bytes: u64,
+    /// Pages in the document, if known
+    pub total_pages: Option<u32>,
+    /// Pages printed including copies, if known
+    pub pages: Option<u32>,
+    /// Physical sheets used, if known
+    pub sheets: Option<u32>,
+    /// User the job was submitted for
+    pub owner: Option<String>,
+    /// Last status lookup, for history eviction
+    #[serde(skip)]
+    pub last_accessed: Option<SystemTime>,
+    /// Failed because its timeout expired
+    #[serde(default)]
+    pub timed_out: bool,
+    /// Processing past the stall threshold
+    #[serde(default)]
+    pub stalled: bool,
+    /// File the job was printed from (None for bytes)
+    #[serde(skip)]
+    pub source_path: Option<String>,
+    /// Options the job was submitted with
+    #[serde(
+        rename = "metadata",
+        default = "PrinterJobOptions::none",
+        with = "job_metadata"
+    )]
+    #[cfg_attr(feature = "schema", schemars(with = "HashMap<String, String>"))]
+    pub job_options: PrinterJobOptions,
+    /// Job this one reprints
+    pub reprint_of: Option<JobId>,
+    /// Failed because its printer was removed
+    #[serde(default)]
+    pub printer_removed: bool,
+    /// Whether the document went to a network printer over TLS (`ipps`
+    /// or `https`); None for local printers, or if it isn't known
+    #[serde(default)]
+    pub encrypted_transport: Option<bool>,
+    /// State changes, oldest first
+    #[serde(default)]
+    pub transitions: Vec<JobTransition>,
 }
 
-// Global state monitor instance
-lazy_static::lazy_static! {
-    static ref GLOBAL_STATE_MONITOR: Arc<Mutex<Option<PrinterStateMonitor>>> =
-        Arc::new(Mutex::new(None));
+fn default_media_type_source() -> MediaTypeSource {
+    MediaTypeSource::Default
 }
 
-/// Global functions for printer state monitoring
-impl PrinterCore {
-    /// Start global printer state monitoring
-    pub fn start_state_monitoring() -> Result<(), String> {
-        let mut monitor_guard = GLOBAL_STATE_MONITOR.lock().unwrap();
-
-        if monitor_guard.is_some() {
-            return Err("State monitoring already active".to_string());
-        }
+/// Times in serialized printers and jobs are Unix timestamps in seconds
+mod unix_secs {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::{Duration, SystemTime};
 
-        let mut monitor = PrinterStateMonitor::new();
-        monitor.start_monitoring()?;
-        *monitor_guard = Some(monitor);
-        Ok(())
+    fn secs(time: &SystemTime) -> u64 {
+        time.duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
     }
 
-    /// Stop global printer state monitoring
-    pub fn stop_state_monitoring() -> Result<(), String> {
-        let mut monitor_guard = GLOBAL_STATE_MONITOR.lock().unwrap();
+    pub fn serialize<S: Serializer>(time: &SystemTime, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(secs(time))
+    }
 
-        if let Some(mut monitor) = monitor_guard.take() {
-            monitor.stop_monitoring()?;
-            Ok(())
-        } else {
-            Err("State monitoring not active".to_string())
-        }
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SystemTime, D::Error> {
+        Ok(SystemTime::UNIX_EPOCH + Duration::from_secs(u64::deserialize(deserializer)?))
     }
 
-    /// Subscribe to printer state change events
-    pub fn subscribe_to_state_changes<F>(callback: F) -> Result<usize, String>
-    where
-        F: Fn(PrinterStateEvent) + Send + Sync + 'static,
-    {
-        let mut monitor_guard = GLOBAL_STATE_MONITOR.lock().unwrap();
+    pub mod option {
+        use super::*;
 
-        // Initialize monitor if not already done
-        if monitor_guard.is_none() {
-            let mut monitor = PrinterStateMonitor::new();
-            monitor
-                .start_monitoring()
-                .map_err(|e| format!("Failed to start monitoring: {}", e))?;
-            *monitor_guard = Some(monitor);
+        pub fn serialize<S: Serializer>(
+            time: &Option<SystemTime>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match time {
+                Some(time) => serializer.serialize_some(&secs(time)),
+                None => serializer.serialize_none(),
+            }
         }
 
-        if let Some(monitor) = monitor_guard.as_mut() {
-            Ok(monitor.subscribe(callback))
-        } else {
-            Err("Failed to initialize state monitoring".to_string())
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<SystemTime>, D::Error> {
+            let secs = Option::<u64>::deserialize(deserializer)?;
+            Ok(secs.map(|secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs)))
         }
     }
+}
 
-    /// Unsubscribe from printer state change events
-    pub fn unsubscribe_from_state_changes(subscription_id: usize) -> Result<bool, String> {
-        let mut monitor_guard = GLOBAL_STATE_MONITOR.lock().unwrap();
-
-        if let Some(monitor) = monitor_guard.as_mut() {
-            Ok(monitor.unsubscribe(subscription_id))
-        } else {
-            Err("State monitoring not active".to_string())
-        }
+/// A serialized job keeps only the metadata of its options
+mod job_metadata {
+    use super::PrinterJobOptions;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::HashMap;
+
+    pub fn serialize<S: Serializer>(
+        options: &PrinterJobOptions,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        options.metadata.serialize(serializer)
     }
 
-    /// Check if state monitoring is active
-    pub fn is_state_monitoring_active() -> bool {
-        let monitor_guard = GLOBAL_STATE_MONITOR.lock().unwrap();
-        monitor_guard.is_some()
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<PrinterJobOptions, D::Error> {
+        Ok(PrinterJobOptions {
+            metadata: HashMap::deserialize(deserializer)?,
+            ..PrinterJobOptions::none()
+        })
     }
+}
 
-    /// Set the polling interval for state monitoring (in seconds)
-    pub fn set_state_monitoring_interval(seconds: u64) -> Result<(), String> {
-        let mut monitor_guard = GLOBAL_STATE_MONITOR.lock().unwrap();
+/// Serialize a job to the JSON of the JavaScript `PrinterJob`
+pub fn printer_job_to_json(job: &PrinterJob) -> Result<String, String> {
+    serde_json::to_string(job).map_err(|e| format!("Failed to serialize job {}: {}", job.id, e))
+}
 
-        if let Some(monitor) = monitor_guard.as_mut() {
-            monitor.set_poll_interval(Duration::from_secs(seconds));
-            Ok(())
-        } else {
-            Err("State monitoring not active".to_string())
+/// Parse a job serialized with `printer_job_to_json`
+pub fn printer_job_from_json(json: &str) -> Result<PrinterJob, String> {
+    serde_json::from_str(json).map_err(|e| format!("Invalid job JSON: {}", e))
+}
+
+/// Printer information with the fields of the JavaScript `PrinterInfo`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct PrinterInfo {
+    pub name: String,
+    /// Hash of the system name and URI, stable across renames
+    pub printer_id: String,
+    pub system_name: String,
+    pub driver_name: String,
+    pub uri: String,
+    pub port_name: String,
+    pub processor: String,
+    pub data_type: String,
+    pub description: String,
+    pub location: String,
+    pub is_default: bool,
+    pub is_shared: bool,
+    /// "idle", "printing", "paused", "offline" or "unknown"
+    pub state: String,
+    pub state_reasons: Vec<String>,
+    pub normalized_state_reasons: Vec<String>,
+    /// When the printer was last seen going offline, if it still is
+    #[serde(default, with = "unix_secs::option")]
+    #[cfg_attr(feature = "schema", schemars(with = "Option<u64>"))]
+    pub offline_since: Option<SystemTime>,
+}
+
+impl From<&Printer> for PrinterInfo {
+    fn from(printer: &Printer) -> Self {
+        PrinterInfo {
+            name: printer.name.clone(),
+            printer_id: PrinterCore::printer_id(printer),
+            system_name: printer.system_name.clone(),
+            driver_name: printer.driver_name.clone(),
+            uri: printer.uri.clone(),
+            port_name: printer.port_name.clone(),
+            processor: printer.processor.clone(),
+            data_type: printer.data_type.clone(),
+            description: printer.description.clone(),
+            location: printer.location.clone(),
+            is_default: printer.is_default,
+            is_shared: printer.is_shared,
+            state: PrinterCore::get_printer_state(printer),
+            state_reasons: printer.state_reasons.clone(),
+            normalized_state_reasons: PrinterCore::get_normalized_state_reasons(printer)
+                .iter()
+                .map(|reason| reason.as_str().to_string())
+                .collect(),
+            offline_since: PrinterCore::get_offline_since(&printer.name),
         }
     }
+}
 
-    /// Get a snapshot of current printer states
-    pub fn get_printer_state_snapshot() -> HashMap<String, (String, Vec<String>)> {
-        let states = PrinterStateMonitor::get_all_printer_states();
-        states
-            .into_iter()
-            .map(|(name, snapshot)| (name, (snapshot.state, snapshot.state_reasons)))
-            .collect()
-    }
+/// Serialize printer information to the JSON of the JavaScript
+/// `PrinterInfo`
+pub fn printer_info_to_json(info: &PrinterInfo) -> Result<String, String> {
+    serde_json::to_string(info)
+        .map_err(|e| format!("Failed to serialize printer '{}': {}", info.name, e))
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use serial_test::serial;
-    use std::env;
+/// Parse printer information serialized with `printer_info_to_json`
+pub fn printer_info_from_json(json: &str) -> Result<PrinterInfo, String> {
+    serde_json::from_str(json).map_err(|e| format!("Invalid printer JSON: {}", e))
+}
 
-    #[test]
-    #[serial]
-    fn test_should_simulate_printing_when_env_var_is_true() {
-        env::set_var("PRINTERS_JS_SIMULATE", "true");
-        assert!(should_simulate_printing());
+impl PrinterJob {
+    /// Create a pending job with no timing or accounting information yet
+    pub fn new(id: JobId, name: String, media_type: String, printer_name: String) -> Self {
+        PrinterJob {
+            id,
+            name,
+            state: PrinterJobState::PENDING,
+            media_type,
+            media_type_source: MediaTypeSource::Default,
+            created_at: SystemTime::now(),
+            processed_at: None,
+            completed_at: None,
+            printer_name,
+            error_message: None,
+            size_bytes: 0,
+            total_pages: None,
+            pages: None,
+            sheets: None,
+            owner: None,
+            last_accessed: None,
+            timed_out: false,
+            stalled: false,
+            source_path: None,
+            job_options: PrinterJobOptions::none(),
+            reprint_of: None,
+            printer_removed: false,
+            encrypted_transport: None,
+            transitions: Vec::new(),
+        }
     }
 
-    #[test]
-    #[serial]
-    fn test_should_simulate_printing_when_env_var_is_false() {
-        // Store original value to restore later
-        let original = env::var("PRINTERS_JS_SIMULATE").ok();
-
-        env::set_var("PRINTERS_JS_SIMULATE", "false");
-        assert!(!should_simulate_printing());
-
-        // Restore original environment state
-        match original {
-            Some(val) => env::set_var("PRINTERS_JS_SIMULATE", val),
-            None => env::remove_var("PRINTERS_JS_SIMULATE"),
+    /// Move the job to `state`, recording the transition. Transitions the
+    /// state machine doesn't allow, like restarting a finished job, are
+    /// refused and leave the job as it was.
+    pub fn transition_to(&mut self, state: PrinterJobState) -> Result<(), String> {
+        if !self.state.can_transition_to(&state) {
+            return Err(format!(
+                "Job {} can't go from {} to {}",
+                self.id,
+                self.state.as_string(),
+                state.as_string()
+            ));
         }
+        let from = std::mem::replace(&mut self.state, state.clone());
+        self.transitions.push(JobTransition {
+            from,
+            to: state,
+            at: SystemTime::now(),
+        });
+        Ok(())
     }
 
-    #[test]
-    #[serial]
-    fn test_should_simulate_printing_when_env_var_is_missing() {
-        // Store original value to restore later
-        let original = env::var("PRINTERS_JS_SIMULATE").ok();
+    /// Whether the job was failed while it was still being handed to the
+    /// spooler, so a late submission must be cancelled
+    fn abandoned(&self) -> bool {
+        self.timed_out || (self.stalled && self.state == PrinterJobState::CANCELLED)
+    }
 
-        env::remove_var("PRINTERS_JS_SIMULATE");
-        assert!(!should_simulate_printing());
+    /// When the job was last completed or looked up, whichever is later
+    fn last_used(&self) -> SystemTime {
+        self.last_accessed
+            .into_iter()
+            .chain(self.completed_at)
+            .max()
+            .unwrap_or(self.created_at)
+    }
+}
 
-        // Restore original environment state
-        if let Some(val) = original {
-            env::set_var("PRINTERS_JS_SIMULATE", val);
-        }
+/// Detect media type from file extension
+fn detect_media_type(file_path: &str) -> String {
+    if file_path.starts_with("<bytes:") {
+        return "application/vnd.cups-raw".to_string();
     }
 
-    #[test]
-    #[serial]
-    fn test_get_all_printer_names_in_simulation_mode() {
-        env::set_var("PRINTERS_JS_SIMULATE", "true");
-        let names = PrinterCore::get_all_printer_names();
-        assert_eq!(names, vec!["Simulated Printer"]);
+    let path = std::path::Path::new(file_path);
+    if let Some(extension) = path.extension() {
+        match extension.to_str().unwrap_or("").to_lowercase().as_str() {
+            "pdf" => "application/pdf".to_string(),
+            "ps" => "application/postscript".to_string(),
+            "txt" | "text" => "text/plain".to_string(),
+            "jpg" | "jpeg" => "image/jpeg".to_string(),
+            "png" => "image/png".to_string(),
+            "gif" => "image/gif".to_string(),
+            _ => "application/octet-stream".to_string(),
+        }
+    } else {
+        "application/octet-stream".to_string()
+    }
+}
+
+/// Detect a file's media type from its extension, falling back to its
+/// content when the extension is missing or unknown
+fn detect_file_media_type(file_path: &str, data: Option<&[u8]>) -> (String, MediaTypeSource) {
+    let media_type = detect_media_type(file_path);
+    if media_type != "application/octet-stream" {
+        return (media_type, MediaTypeSource::Extension);
+    }
+    match data.and_then(crate::document::sniff_media_type) {
+        Some(sniffed) => (sniffed.to_string(), MediaTypeSource::Content),
+        None => (media_type, MediaTypeSource::Default),
+    }
+}
+
+/// Detect the media type of raw bytes from their content, falling back to
+/// raw data for the spooler
+fn detect_bytes_media_type(data: &[u8]) -> (String, MediaTypeSource) {
+    match crate::document::sniff_media_type(data) {
+        Some(sniffed) => (sniffed.to_string(), MediaTypeSource::Content),
+        None => (
+            detect_media_type(&format!("<bytes:{} bytes>", data.len())),
+            MediaTypeSource::Default,
+        ),
+    }
+}
+
+/// Explicit `document-format` job option. It stays in the raw properties so
+/// CUPS receives it and skips its own format detection.
+fn document_format(raw_properties: &HashMap<String, String>) -> Result<Option<String>, PrintError> {
+    let Some(format) = raw_properties.get("document-format") else {
+        return Ok(None);
+    };
+    if !is_mime_type(format) {
+        tracing::warn!("Invalid document-format: {:?}", format);
+        return Err(PrintError::InvalidParams);
+    }
+    Ok(Some(format.to_ascii_lowercase()))
+}
+
+/// Whether a value has the `type/subtype` shape of a MIME media type
+fn is_mime_type(value: &str) -> bool {
+    let is_token = |part: &str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "!#$&-^_.+".contains(c))
+    };
+    value
+        .split_once('/')
+        .is_some_and(|(kind, subtype)| is_token(kind) && is_token(subtype))
+}
+
+/// Check that a file to print exists
+fn check_file_exists(file_path: &str) -> Result<(), PrintError> {
+    if should_simulate_printing() {
+        // In simulation mode, simulate different types of errors based on filename
+        if file_path.contains("nonexistent") || file_path.contains("does_not_exist") {
+            return Err(PrintError::FileNotFound);
+        } else if file_path.contains("fail-test") {
+            return Err(PrintError::SimulatedFailure);
+        }
+    } else if !std::path::Path::new(file_path).exists() {
+        return Err(PrintError::FileNotFound);
+    }
+    Ok(())
+}
+
+/// Reject documents that are (or claim to be) PDFs but fail validation
+fn check_document(data: &[u8], media_type: &str) -> Result<(), PrintError> {
+    if media_type != "application/pdf" && !crate::document::is_pdf(data) {
+        return Ok(());
+    }
+    crate::document::validate_pdf(data)
+        .map(|_| ())
+        .map_err(|reason| {
+            tracing::warn!("Rejected invalid PDF: {}", reason);
+            PrintError::InvalidDocument
+        })
+}
+
+/// Apply a job's page options, like a watermark, to its document, returning
+/// the changed copy to print instead. Images placed on a page become PDFs,
+/// which the job is then sent as.
+fn preprocess_document(
+    data: &[u8],
+    job_options: &PrinterJobOptions,
+    media_type: &mut String,
+    media_type_source: &mut MediaTypeSource,
+) -> Result<Option<Vec<u8>>, PrintError> {
+    let processed = crate::preprocess::apply(data, job_options).map_err(|reason| {
+        tracing::warn!("Failed to pre-process document: {}", reason);
+        PrintError::InvalidDocument
+    })?;
+    if processed.is_some() && !crate::document::is_pdf(data) {
+        *media_type = "application/pdf".to_string();
+        *media_type_source = MediaTypeSource::Content;
+    }
+    Ok(processed)
+}
+
+/// Job a submission reprints
+struct Reprint<'a> {
+    original: &'a PrinterJob,
+    /// Retained payload the reprint prints from, if any
+    payload: Option<Arc<crate::spool::SpoolFile>>,
+}
+
+/// Jobs moved from one printer to another by `migrate_jobs`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct JobMigration {
+    /// Queued jobs now waiting for the new printer
+    pub moved: Vec<JobId>,
+    /// Failed jobs and the jobs reprinting them on the new printer
+    pub requeued: Vec<(JobId, JobId)>,
+    /// Failed jobs that couldn't be reprinted, e.g. because neither their
+    /// payload nor their file is left
+    pub unavailable: Vec<JobId>,
+}
+
+/// A file read and checked for submission, shared by the jobs printing it
+struct PreparedFile {
+    /// Path the jobs print from: the spooled copy if there is one
+    print_path: String,
+    /// Spooled copy of the file
+    payload: Option<Arc<crate::spool::SpoolFile>>,
+    source_path: Option<String>,
+    reprint_of: Option<JobId>,
+    media_type: String,
+    media_type_source: MediaTypeSource,
+    /// Size of the file, if it could be read
+    size_bytes: Option<u64>,
+    total_pages: Option<u32>,
+}
+
+/// Apply configured defaults to a job's options and check them
+/// Apply default options beneath a job's own, in order of precedence: the
+/// defaults of the alias it was submitted through, of its printer, then
+/// the library-wide defaults
+fn merge_job_options(
+    job_options: Option<PrinterJobOptions>,
+    printer_name: Option<&str>,
+    alias: Option<&crate::aliases::PrinterAlias>,
+) -> PrinterJobOptions {
+    let mut job_options = job_options.unwrap_or_else(PrinterJobOptions::none);
+    if let Some(alias) = alias {
+        job_options = job_options.with_defaults(&alias.default_options);
+    }
+    if let Some(printer_name) = printer_name {
+        job_options = job_options.with_defaults(&crate::aliases::printer_defaults(printer_name));
+    }
+    job_options.with_defaults(&CONFIG.read().unwrap().default_job_options)
+}
+
+fn prepare_job_options(
+    job_options: Option<PrinterJobOptions>,
+    printer_name: Option<&str>,
+    alias: Option<&crate::aliases::PrinterAlias>,
+) -> Result<PrinterJobOptions, PrintError> {
+    let job_options = merge_job_options(job_options, printer_name, alias);
+    if let Some(user) = &job_options.requesting_user {
+        crate::spooler::validate_user_name(user).map_err(|_| PrintError::InvalidParams)?;
+    }
+    check_dependencies(&job_options.depends_on)?;
+    crate::preprocess::validate(&job_options).map_err(|reason| {
+        tracing::warn!("Invalid page options: {}", reason);
+        PrintError::InvalidParams
+    })?;
+    if let Some(finishing) = &job_options.finishing {
+        finishing.validate().map_err(|reason| {
+            tracing::warn!("Invalid finishing: {}", reason);
+            PrintError::InvalidParams
+        })?;
+    }
+    Ok(job_options)
+}
+
+/// Check a job's options against what its printer reports it can do,
+/// asking the printer for its capabilities only if an option needs them
+fn check_printer_options(
+    printer_name: &str,
+    job_options: &mut PrinterJobOptions,
+) -> Result<(), PrintError> {
+    let booklet = job_options.layout == Some(crate::preprocess::Layout::Booklet);
+    let strict = job_options.strict_options == Some(true);
+    if !booklet
+        && !strict
+        && job_options.color_mode.is_none()
+        && job_options.quality.is_none()
+        && job_options.dpi.is_none()
+        && job_options.finishing.is_none()
+    {
+        return Ok(());
+    }
+    let capabilities = PrinterCore::get_printer_capabilities(printer_name)?;
+    if strict {
+        check_strict_options(printer_name, capabilities.as_ref(), job_options)?;
+    }
+    if booklet {
+        check_layout(printer_name, capabilities.as_ref(), job_options)?;
+    }
+    check_color_mode(printer_name, capabilities.as_ref(), job_options);
+    check_quality(printer_name, capabilities.as_ref(), job_options)?;
+    check_finishing(printer_name, capabilities.as_ref(), job_options)
+}
+
+/// Check a job against the policy rules, failing with the rule it broke
+fn check_policy(printer_name: &str, job: &crate::policy::PolicyJob<'_>) -> Result<(), PrintError> {
+    crate::policy::check(job).map_err(|violation| {
+        tracing::warn!(printer = %printer_name, "Job breaks print policy: {}", violation);
+        PrintError::PolicyViolation(violation)
+    })
+}
+
+/// Check a submitted document's format and text against the policy rules
+fn check_document_policy(
+    printer_name: &str,
+    media_type: &str,
+    data: Option<&[u8]>,
+) -> Result<(), PrintError> {
+    let job = crate::policy::PolicyJob {
+        media_type: Some(media_type),
+        data,
+        ..Default::default()
+    };
+    check_policy(printer_name, &job)
+}
+
+/// Check the pages a job prints, and its color for its user, against the
+/// policy rules. Its color is only worked out if a rule restricts color.
+fn check_job_policy(
+    printer_name: &str,
+    job_options: &PrinterJobOptions,
+    document_pages: Option<u32>,
+) -> Result<(), PrintError> {
+    let user = job_options
+        .requesting_user
+        .clone()
+        .or_else(crate::spooler::current_user);
+    let color = crate::policy::restricts_color().then(|| {
+        let capabilities = PrinterCore::get_printer_capabilities(printer_name)
+            .ok()
+            .flatten();
+        JobColor::of_job(
+            printer_name,
+            capabilities.as_ref(),
+            &job_options.raw_properties,
+        ) == JobColor::Color
+    });
+    let job = crate::policy::PolicyJob {
+        user: user.as_deref(),
+        pages: job_usage(document_pages, &job_options.raw_properties).0,
+        color,
+        ..Default::default()
+    };
+    check_policy(printer_name, &job)
+}
+
+/// Reject raw properties the printer doesn't list, and values it doesn't
+/// support, before the driver silently ignores them. Printers that report
+/// no capabilities can't be checked, so their jobs pass.
+fn check_strict_options(
+    printer_name: &str,
+    capabilities: Option<&PrinterCapabilities>,
+    job_options: &PrinterJobOptions,
+) -> Result<(), PrintError> {
+    let Some(capabilities) = capabilities else {
+        tracing::debug!(printer = %printer_name, "No capabilities to check options against");
+        return Ok(());
+    };
+    let raw_properties = &job_options.raw_properties;
+    let mut problems: Vec<String> = capabilities
+        .unknown_options(raw_properties)
+        .into_iter()
+        .map(|key| format!("unknown option '{}'", key))
+        .collect();
+    // The document format is checked against the document itself
+    problems.extend(
+        capabilities
+            .unsupported_options(raw_properties, "")
+            .into_iter()
+            .filter(|option| !option.starts_with("document-format=")),
+    );
+    if problems.is_empty() {
+        return Ok(());
+    }
+    Err(unsupported_option(
+        printer_name,
+        format!(
+            "{} (supported options: {})",
+            problems.join("; "),
+            capabilities.supported_options().join(", ")
+        ),
+    ))
+}
+
+/// Rejection of a job's options, logged with the reason it carries
+fn unsupported_option(printer_name: &str, reason: String) -> PrintError {
+    tracing::warn!(printer = %printer_name, "Rejected job options: {}", reason);
+    PrintError::UnsupportedOption(reason)
+}
+
+/// Check that a printer can print a booklet. Booklets are printed on both
+/// sides of the sheet, so they ask for duplex printing unless the job
+/// already does, and fail on printers that report they can't.
+fn check_layout(
+    printer_name: &str,
+    capabilities: Option<&PrinterCapabilities>,
+    job_options: &mut PrinterJobOptions,
+) -> Result<(), PrintError> {
+    // Pages side by side on landscape sheets turn over the short edge
+    let sides = job_options
+        .raw_properties
+        .entry("sides".to_string())
+        .or_insert_with(|| "two-sided-short-edge".to_string())
+        .clone();
+    if !sides.starts_with("two-sided") {
+        tracing::warn!("Booklets need two-sided printing, not sides={}", sides);
+        return Err(PrintError::InvalidParams);
+    }
+    if let Some(supported) =
+        capabilities.and_then(|capabilities| capabilities.supported.get("sides"))
+    {
+        if !supported.iter().any(|s| s.eq_ignore_ascii_case(&sides)) {
+            tracing::warn!(
+                printer = printer_name,
+                "Printer can't print booklets: sides={} isn't supported",
+                sides
+            );
+            return Err(PrintError::InvalidParams);
+        }
+    }
+    Ok(())
+}
+
+/// Ask the driver for a job's color mode, with the `print-color-mode`
+/// keyword the printer reports for it. Gray jobs for printers that don't
+/// report one are also converted to gray before submission, in case the
+/// driver ignores the request.
+fn check_color_mode(
+    printer_name: &str,
+    capabilities: Option<&PrinterCapabilities>,
+    job_options: &mut PrinterJobOptions,
+) {
+    let Some(mode) = job_options.color_mode else {
+        return;
+    };
+    let keyword = capabilities.and_then(|capabilities| capabilities.color_mode_keyword(mode));
+    job_options
+        .raw_properties
+        .entry("print-color-mode".to_string())
+        .or_insert_with(|| keyword.unwrap_or(mode.default_keyword()).to_string());
+    job_options.convert_to_gray = mode != crate::preprocess::ColorMode::Color && keyword.is_none();
+    if job_options.convert_to_gray {
+        tracing::info!(
+            printer = printer_name,
+            "Converting {} job to gray: the printer doesn't report the color mode",
+            mode.as_str()
+        );
+    }
+}
+
+/// Ask the driver for a job's quality and resolution, failing with
+/// UnsupportedOption if the printer reports it can't print them rather
+/// than letting it print at its default quality
+fn check_quality(
+    printer_name: &str,
+    capabilities: Option<&PrinterCapabilities>,
+    job_options: &mut PrinterJobOptions,
+) -> Result<(), PrintError> {
+    let supported =
+        |name: &str| capabilities.and_then(|capabilities| capabilities.supported.get(name));
+    if let Some(quality) = job_options.quality {
+        let value = quality.ipp_value();
+        if supported("print-quality").is_some_and(|supported| !supported.iter().any(|s| s == value))
+        {
+            return Err(unsupported_option(
+                printer_name,
+                format!(
+                    "Printer doesn't support {} quality (print-quality={})",
+                    quality.as_str(),
+                    value
+                ),
+            ));
+        }
+        job_options
+            .raw_properties
+            .insert("print-quality".to_string(), value.to_string());
+    }
+    if let Some(dpi) = job_options.dpi {
+        if supported("printer-resolution")
+            .is_some_and(|supported| !supported.iter().any(|s| resolution_dpi(s) == Some(dpi)))
+        {
+            return Err(unsupported_option(
+                printer_name,
+                format!("Printer doesn't support printing at {}dpi", dpi),
+            ));
+        }
+        let resolution = format!("{}dpi", dpi);
+        job_options
+            .raw_properties
+            .insert("printer-resolution".to_string(), resolution.clone());
+        // CUPS drivers take the resolution as the PPD option
+        job_options
+            .raw_properties
+            .insert("Resolution".to_string(), resolution);
+    }
+    Ok(())
+}
+
+/// Ask the driver for a job's finishing, failing with UnsupportedOption if
+/// the printer reports it can't staple, punch or fold that way or doesn't
+/// have the output bin
+fn check_finishing(
+    printer_name: &str,
+    capabilities: Option<&PrinterCapabilities>,
+    job_options: &mut PrinterJobOptions,
+) -> Result<(), PrintError> {
+    let Some(finishing) = &job_options.finishing else {
+        return Ok(());
+    };
+    let supported =
+        |name: &str| capabilities.and_then(|capabilities| capabilities.supported.get(name));
+    let values = finishing.values().map_err(|_| PrintError::InvalidParams)?;
+    if let Some(supported) = supported("finishings") {
+        if let Some(value) = values
+            .iter()
+            .find(|value| !supported.contains(&value.to_string()))
+        {
+            return Err(unsupported_option(
+                printer_name,
+                format!(
+                    "Printer doesn't support finishing {}",
+                    crate::finishing::name(*value).unwrap_or_default()
+                ),
+            ));
+        }
+    }
+    let output_bin = finishing.output_bin.clone();
+    if let (Some(bin), Some(supported)) = (&output_bin, supported("output-bin")) {
+        if !supported.iter().any(|s| s.eq_ignore_ascii_case(bin.trim())) {
+            return Err(unsupported_option(
+                printer_name,
+                format!("Printer has no output bin '{}'", bin),
+            ));
+        }
+    }
+    if !values.is_empty() {
+        let values: Vec<String> = values.iter().map(i32::to_string).collect();
+        job_options
+            .raw_properties
+            .insert("finishings".to_string(), values.join(","));
+    }
+    if let Some(bin) = output_bin {
+        job_options
+            .raw_properties
+            .insert("output-bin".to_string(), bin.trim().to_string());
+    }
+    Ok(())
+}
+
+/// Dots per inch of an IPP resolution like `600dpi` or `600x600dpi`, if it
+/// is the same in both directions
+fn resolution_dpi(resolution: &str) -> Option<u32> {
+    let dimensions = resolution.trim().strip_suffix("dpi")?;
+    let (cross_feed, feed) = dimensions
+        .split_once('x')
+        .unwrap_or((dimensions, dimensions));
+    let dpi = cross_feed.parse::<u32>().ok()?;
+    (feed.parse::<u32>().ok() == Some(dpi)).then_some(dpi)
+}
+
+/// Start of the stand-in for a redacted document name or path
+const REDACTED_PREFIX: &str = "redacted-";
+
+lazy_static::lazy_static! {
+    /// Key redacted values are hashed with, random for each process unless
+    /// one is set, so hashes of guessable names can't be checked offline
+    static ref REDACTION_KEY: RwLock<Vec<u8>> = RwLock::new(random_redaction_key());
+}
+
+fn random_redaction_key() -> Vec<u8> {
+    [Uuid::new_v4(), Uuid::new_v4()]
+        .iter()
+        .flat_map(|uuid| *uuid.as_bytes())
+        .collect()
+}
+
+/// Set the key document names and paths are hashed with when redacted, so
+/// the same name gives the same hash across restarts and processes. None
+/// goes back to a random key for this process.
+pub fn set_redaction_key(key: Option<&[u8]>) -> Result<(), String> {
+    let key = match key {
+        Some(key) if key.len() < 16 => {
+            return Err(format!(
+                "Redaction key must be at least 16 bytes, got {}",
+                key.len()
+            ))
+        }
+        Some(key) => key.to_vec(),
+        None => random_redaction_key(),
+    };
+    *REDACTION_KEY.write().unwrap() = key;
+    Ok(())
+}
+
+/// Stand-in for a document name or path: a keyed hash of it. The same
+/// value gives the same hash under one key, so jobs for one document can
+/// still be matched up.
+fn redacted(value: &str) -> String {
+    if value.starts_with(REDACTED_PREFIX) {
+        return value.to_string();
+    }
+    let hash = crate::crypto::hmac_sha256(&REDACTION_KEY.read().unwrap(), value.as_bytes());
+    format!("{}{}", REDACTED_PREFIX, crate::crypto::hex(&hash[..8]))
+}
+
+/// Replace a tracked job's document name and file path with hashes if the
+/// job or the configuration asks for it, before the job is recorded
+/// anywhere. The spooler is still given the real name, except for held
+/// jobs, which are persisted redacted.
+fn redact_job(job: &mut PrinterJob) {
+    let redact = job
+        .job_options
+        .redact
+        .unwrap_or_else(|| CONFIG.read().unwrap().redact_job_data);
+    if redact {
+        job.name = redacted(&job.name);
+        job.job_options.name = job.job_options.name.as_deref().map(redacted);
+        job.source_path = job.source_path.as_deref().map(redacted);
+    }
+}
+
+/// Write a job's payload to the spool directory, within the configured quota
+fn spool_payload(
+    job_id: JobId,
+    printer_name: &str,
+    data: &[u8],
+) -> Result<crate::spool::SpoolFile, PrintError> {
+    let config = CONFIG.read().unwrap();
+    crate::spool::write(job_id, data, config.temp_dir.as_deref(), config.spool_quota).map_err(|e| {
+        tracing::warn!(job_id, printer = printer_name, "Failed to spool job: {}", e);
+        match e {
+            crate::spool::SpoolError::QuotaExceeded { .. } => PrintError::SpoolQuotaExceeded,
+            crate::spool::SpoolError::Io(_) => PrintError::SpoolFailed,
+        }
+    })
+}
+
+// ===== PRINTER CACHE =====
+
+lazy_static::lazy_static! {
+    /// System printers from the last enumeration and when it was taken
+    static ref PRINTER_CACHE: RwLock<Option<(Instant, Arc<Vec<Printer>>)>> = RwLock::new(None);
+    /// Bumped when the cache is invalidated, so handles holding a printer
+    /// know to look it up again
+    static ref PRINTER_CACHE_GENERATION: AtomicU64 = AtomicU64::new(0);
+}
+
+/// System printers, enumerated at most once per `printer_cache_ttl`.
+/// Lookups, state monitoring and capability queries all read this, so
+/// listing n printers costs one enumeration rather than n.
+fn cached_printers() -> Arc<Vec<Printer>> {
+    let ttl = CONFIG.read().unwrap().printer_cache_ttl;
+    if let Some((taken, printers)) = PRINTER_CACHE.read().unwrap().as_ref() {
+        if taken.elapsed() < ttl {
+            return Arc::clone(printers);
+        }
+    }
+    let printers = Arc::new(printers::get_printers());
+    *PRINTER_CACHE.write().unwrap() = Some((Instant::now(), Arc::clone(&printers)));
+    printers
+}
+
+/// System printer by name or system name, from the cache
+fn cached_printer(name: &str) -> Option<Printer> {
+    cached_printers()
+        .iter()
+        .find(|printer| printer.name == name || printer.system_name == name)
+        .cloned()
+}
+
+/// Drop the cached printers so the next lookup enumerates the system
+fn invalidate_printer_cache() {
+    *PRINTER_CACHE.write().unwrap() = None;
+    PRINTER_CACHE_GENERATION.fetch_add(1, Ordering::Relaxed);
+}
+
+/// A printer looked up once by name and reused until it is stale: when
+/// `printer_cache_ttl` has passed or the printer cache was invalidated
+pub struct PrinterHandle {
+    name: String,
+    resolved: RwLock<Option<ResolvedPrinter>>,
+}
+
+struct ResolvedPrinter {
+    printer: Printer,
+    at: Instant,
+    generation: u64,
+}
+
+impl PrinterHandle {
+    pub fn new(name: impl Into<String>) -> Self {
+        PrinterHandle {
+            name: name.into(),
+            resolved: RwLock::new(None),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The printer, looked up again if the one held is stale. None if it no
+    /// longer exists.
+    pub fn get(&self) -> Option<Printer> {
+        let ttl = CONFIG.read().unwrap().printer_cache_ttl;
+        let generation = PRINTER_CACHE_GENERATION.load(Ordering::Relaxed);
+        if let Some(resolved) = self.resolved.read().unwrap().as_ref() {
+            if resolved.generation == generation && resolved.at.elapsed() < ttl {
+                return Some(resolved.printer.clone());
+            }
+        }
+        self.resolve()
+    }
+
+    /// Enumerate the system again and look the printer up
+    pub fn refresh(&self) -> Option<Printer> {
+        invalidate_printer_cache();
+        self.resolve()
+    }
+
+    fn resolve(&self) -> Option<Printer> {
+        let generation = PRINTER_CACHE_GENERATION.load(Ordering::Relaxed);
+        let printer = PrinterCore::find_printer_by_name(&self.name);
+        *self.resolved.write().unwrap() = printer.clone().map(|printer| ResolvedPrinter {
+            printer,
+            at: Instant::now(),
+            generation,
+        });
+        printer
+    }
+}
+
+impl PrinterCore {
+    /// Forget the cached printer list, e.g. after a printer was added or
+    /// removed outside this library
+    pub fn refresh_printers() {
+        invalidate_printer_cache();
+    }
+}
+
+/// The "Simulated Printer" of simulation mode, modelled on the first real
+/// printer if there is one
+fn simulated_printer() -> Printer {
+    let name = "Simulated Printer".to_string();
+    if let Some(mut printer) = cached_printers().first().cloned() {
+        printer.name = name;
+        printer.is_default = true; // Always mark simulated printer as default
+        return printer;
+    }
+    // No real printers available - create a mock printer struct
+    Printer {
+        name,
+        system_name: "Brother_MFC_J6955DW".to_string(),
+        driver_name: "Brother MFC-J6955DW-AirPrint".to_string(),
+        uri: "mock://printer".to_string(),
+        location: "Test Location".to_string(),
+        description: "Mock printer for testing".to_string(),
+        port_name: "MOCK:".to_string(),
+        processor: "Mock Processor".to_string(),
+        data_type: "RAW".to_string(),
+        is_shared: false,
+        is_default: true,
+        state: printers::common::base::printer::PrinterState::READY,
+        state_reasons: Vec::new(),
+    }
+}
+
+/// Core printer operations
+pub struct PrinterCore;
+
+impl PrinterCore {
+    /// Find a printer by name or alias
+    pub fn find_printer_by_name(name: &str) -> Option<Printer> {
+        let name = &crate::aliases::resolve(name);
+        if let Some(remote) = crate::remote::get(name) {
+            return Some(remote.printer_info());
+        }
+        if should_simulate_printing() {
+            // In simulation mode, only return printer if name matches simulated printers
+            if name == "Simulated Printer" {
+                Some(simulated_printer())
+            } else {
+                SIMULATED_INSTALLS
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .find(|printer| &printer.name == name)
+                    .cloned()
+            }
+        } else {
+            cached_printer(name)
+        }
+    }
+
+    /// Check if a printer exists
+    pub fn printer_exists(name: &str) -> bool {
+        Self::find_printer_by_name(name).is_some()
+    }
+
+    /// Get all printers, local and remote, from a single enumeration
+    pub fn get_all_printers() -> Vec<Printer> {
+        let mut printers = if should_simulate_printing() {
+            let mut printers = vec![simulated_printer()];
+            printers.extend(SIMULATED_INSTALLS.lock().unwrap().iter().cloned());
+            printers
+        } else {
+            cached_printers().as_ref().clone()
+        };
+        // Remote printers take the place of local ones with the same name,
+        // as they do in lookups
+        for remote in crate::remote::list() {
+            match printers
+                .iter_mut()
+                .find(|printer| printer.name == remote.name)
+            {
+                Some(printer) => *printer = remote.printer_info(),
+                None => printers.push(remote.printer_info()),
+            }
+        }
+        printers
+    }
+
+    /// Get all printer names
+    pub fn get_all_printer_names() -> Vec<String> {
+        Self::get_all_printers()
+            .into_iter()
+            .map(|printer| printer.name)
+            .collect()
+    }
+
+    /// Printers whose location contains `location`, ignoring case, e.g.
+    /// "3rd floor" for a printer located at "Building 2, 3rd Floor"
+    pub fn get_printers_by_location(location: &str) -> Vec<Printer> {
+        let location = location.trim().to_lowercase();
+        if location.is_empty() {
+            return Vec::new();
+        }
+        Self::get_all_printers()
+            .into_iter()
+            .filter(|printer| printer.location.to_lowercase().contains(&location))
+            .collect()
+    }
+
+    /// Printers with a tag (see `aliases::tag_printer`), ignoring case.
+    /// Tagged printers that no longer exist are left out.
+    pub fn get_printers_by_tag(tag: &str) -> Vec<Printer> {
+        let tagged = crate::aliases::tagged_printers(tag);
+        Self::get_all_printers()
+            .into_iter()
+            .filter(|printer| tagged.contains(&printer.name))
+            .collect()
+    }
+
+    /// Stable identifier of the device behind a printer: a hash of its
+    /// system name and URI, so it survives changes to the display name and
+    /// can be compared after the printer has been serialized
+    pub fn printer_id(printer: &Printer) -> String {
+        let digest =
+            crate::crypto::sha256(format!("{}\n{}", printer.system_name, printer.uri).as_bytes());
+        crate::crypto::hex(&digest[..8])
+    }
+
+    /// Full printer information, with the fields of the JavaScript
+    /// `PrinterInfo`
+    pub fn printer_info_value(printer: &Printer) -> serde_json::Value {
+        serde_json::to_value(PrinterInfo::from(printer)).unwrap_or_default()
+    }
+
+    /// Look up a printer and serialize its full information, for callers
+    /// that only need metadata, such as stateless HTTP handlers
+    pub fn printer_info_json(name: &str) -> Option<String> {
+        Self::find_printer_by_name(name)
+            .and_then(|printer| printer_info_to_json(&PrinterInfo::from(&printer)).ok())
+    }
+
+    /// Get printer state as string
+    pub fn get_printer_state(printer: &Printer) -> String {
+        // Convert the PrinterState enum to a string representation
+        use printers::common::base::printer::PrinterState;
+
+        match printer.state {
+            PrinterState::READY => "idle".to_string(),
+            PrinterState::PRINTING => "printing".to_string(),
+            PrinterState::PAUSED => "paused".to_string(),
+            PrinterState::OFFLINE => "offline".to_string(),
+            PrinterState::UNKNOWN => "unknown".to_string(),
+        }
+    }
+
+    /// Get printer state reasons as JSON array
+    pub fn get_printer_state_reasons(printer: &Printer) -> String {
+        serde_json::to_string(&printer.state_reasons).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// Print a file with optional job properties
+    pub fn print_file(
+        printer_name: &str,
+        file_path: &str,
+        job_options: Option<PrinterJobOptions>,
+    ) -> Result<JobId, PrintError> {
+        Self::submit_file(printer_name, file_path, job_options, None)
+    }
+
+    /// Resubmit a finished job from its retained payload, or from the file it
+    /// was printed from if that still exists. The new job's `reprint_of`
+    /// links it to the original.
+    pub fn reprint_job(job_id: JobId) -> Result<JobId, PrintError> {
+        Self::reprint(job_id, None)
+    }
+
+    /// Reprint a finished job on `printer_name`, or on its own printer
+    fn reprint(job_id: JobId, printer_name: Option<&str>) -> Result<JobId, PrintError> {
+        let original = JOB_TRACKER.get(job_id).ok_or(PrintError::JobNotFound)?;
+        if !original.state.is_terminal() {
+            return Err(PrintError::InvalidParams);
+        }
+
+        // The retained payload is held until the reprint has finished, even
+        // if the original job leaves the history in the meantime
+        let payload = crate::spool::retained(job_id);
+        let file_path = match (&payload, &original.source_path) {
+            (Some(spool_file), _) => spool_file.path().to_string_lossy().into_owned(),
+            (None, Some(path)) if std::path::Path::new(path).exists() => path.clone(),
+            _ => return Err(PrintError::FileNotFound),
+        };
+
+        // The original's dependencies were already satisfied, and a reprint
+        // is never a duplicate of it
+        let job_options = PrinterJobOptions {
+            name: Some(original.name.clone()),
+            depends_on: Vec::new(),
+            dedupe_key: None,
+            ..original.job_options.clone()
+        };
+        let printer_name = printer_name.unwrap_or(&original.printer_name);
+        tracing::info!(job_id, printer = printer_name, "Reprinting job");
+        Self::submit_file(
+            printer_name,
+            &file_path,
+            Some(job_options),
+            Some(Reprint {
+                original: &original,
+                payload,
+            }),
+        )
+    }
+
+    /// Move the jobs queued for one printer to another, e.g. after it was
+    /// removed or went offline. Jobs that haven't started printing are moved
+    /// as they are; jobs that failed because the printer was removed are
+    /// reprinted on the other printer, unless they were reprinted already.
+    pub fn migrate_jobs(from_printer: &str, to_printer: &str) -> Result<JobMigration, PrintError> {
+        let from_printer = crate::aliases::resolve(from_printer);
+        let to_printer = crate::aliases::resolve(to_printer);
+        if from_printer == to_printer {
+            return Err(PrintError::InvalidParams);
+        }
+        Self::find_printer_by_name(&to_printer).ok_or(PrintError::PrinterNotFound)?;
+
+        let mut migration = JobMigration::default();
+        for job in JOB_TRACKER.filter(|job| job.printer_name == from_printer) {
+            let moved = JOB_TRACKER.update(job.id, |job| {
+                let queued = matches!(
+                    job.state,
+                    PrinterJobState::PENDING | PrinterJobState::PAUSED
+                );
+                if queued {
+                    job.printer_name = to_printer.clone();
+                }
+                queued
+            });
+            if moved == Some(true) {
+                JOB_TRACKER.log(
+                    job.id,
+                    LogLevel::Info,
+                    format!("Moved from '{}' to '{}'", from_printer, to_printer),
+                );
+                migration.moved.push(job.id);
+                continue;
+            }
+            let reprinted = JOB_TRACKER.count(|other| other.reprint_of == Some(job.id)) > 0;
+            if !job.printer_removed || reprinted {
+                continue;
+            }
+            match Self::reprint(job.id, Some(to_printer.as_str())) {
+                Ok(new_job_id) => {
+                    JOB_TRACKER.log(
+                        job.id,
+                        LogLevel::Info,
+                        format!("Requeued on '{}' as job {}", to_printer, new_job_id),
+                    );
+                    migration.requeued.push((job.id, new_job_id));
+                }
+                Err(error) => {
+                    tracing::warn!(job_id = job.id, ?error, "Failed to requeue job");
+                    migration.unavailable.push(job.id);
+                }
+            }
+        }
+        tracing::info!(
+            from = %from_printer,
+            to = %to_printer,
+            moved = migration.moved.len(),
+            requeued = migration.requeued.len(),
+            "Migrated jobs"
+        );
+        Ok(migration)
+    }
+
+    /// Submit a file job, optionally as a reprint of an earlier job
+    fn submit_file(
+        printer_name: &str,
+        file_path: &str,
+        job_options: Option<PrinterJobOptions>,
+        reprint: Option<Reprint<'_>>,
+    ) -> Result<JobId, PrintError> {
+        if !ACCEPTING_JOBS.load(Ordering::Relaxed) {
+            return Err(PrintError::ShuttingDown);
+        }
+
+        // Jobs submitted through an alias go to its printer with its defaults
+        let alias = crate::aliases::get(printer_name);
+        let printer_name = alias
+            .as_ref()
+            .map_or(printer_name, |alias| alias.printer.as_str());
+
+        // Check if printer exists
+        let _printer =
+            Self::find_printer_by_name(printer_name).ok_or(PrintError::PrinterNotFound)?;
+
+        // Generate job ID
+        let job_id = generate_job_id();
+
+        let mut job_options = prepare_job_options(job_options, Some(printer_name), alias.as_ref())?;
+        check_printer_options(printer_name, &mut job_options)?;
+        let dedupe = match claim_dedupe_key(&job_options, &[job_id]) {
+            Ok(claim) => claim,
+            Err(originals) => return duplicate_job(&job_options, &originals),
+        };
+        let _admission = admit_job(printer_name)?;
+        let file = Self::prepare_file(
+            job_id,
+            printer_name,
+            file_path,
+            &job_options,
+            reprint,
+            job_options.keep_spool_file,
+        )?;
+        check_job_policy(printer_name, &job_options, file.total_pages)?;
+        Self::enqueue_file_job(job_id, printer_name, &file, job_options)?;
+        dedupe.commit();
+        Ok(job_id)
+    }
+
+    /// Print one file to several printers. The file is read, checked and
+    /// spooled once, and every job prints the same copy. Problems with the
+    /// file fail the whole call; otherwise there is one result per printer.
+    pub fn print_to_many(
+        printer_names: &[String],
+        file_path: &str,
+        job_options: Option<PrinterJobOptions>,
+    ) -> Result<Vec<Result<JobId, PrintError>>, PrintError> {
+        if !ACCEPTING_JOBS.load(Ordering::Relaxed) {
+            return Err(PrintError::ShuttingDown);
+        }
+        if printer_names.is_empty() {
+            return Err(PrintError::InvalidParams);
+        }
+
+        let job_ids: Vec<JobId> = printer_names.iter().map(|_| generate_job_id()).collect();
+        let checked_options = prepare_job_options(job_options.clone(), None, None)?;
+        let dedupe = match claim_dedupe_key(&checked_options, &job_ids) {
+            Ok(claim) => claim,
+            Err(originals) => {
+                return Self::duplicate_print_to_many(printer_names, &checked_options, &originals)
+            }
+        };
+        let file = Self::prepare_file(
+            job_ids[0],
+            &printer_names.join(", "),
+            file_path,
+            &checked_options,
+            None,
+            true,
+        )?;
+
+        let results: Vec<Result<JobId, PrintError>> = printer_names
+            .iter()
+            .zip(job_ids)
+            .map(|(printer_name, job_id)| {
+                // Each alias applies its own defaults
+                let alias = crate::aliases::get(printer_name);
+                let printer_name = alias
+                    .as_ref()
+                    .map_or(printer_name.as_str(), |alias| alias.printer.as_str());
+                Self::find_printer_by_name(printer_name).ok_or(PrintError::PrinterNotFound)?;
+                let mut job_options =
+                    prepare_job_options(job_options.clone(), Some(printer_name), alias.as_ref())?;
+                check_printer_options(printer_name, &mut job_options)?;
+                // The document is shared by every printer, so only their
+                // drivers' color modes apply
+                job_options.convert_to_gray = false;
+                let _admission = admit_job(printer_name)?;
+                check_job_policy(printer_name, &job_options, file.total_pages)?;
+                Self::enqueue_file_job(job_id, printer_name, &file, job_options)?;
+                Ok(job_id)
+            })
+            .collect();
+        if results.iter().any(Result::is_ok) {
+            dedupe.commit();
+        }
+        Ok(results)
+    }
+
+    /// Results of a duplicate print_to_many call. When coalescing, each
+    /// printer gets the job the original call submitted to it.
+    fn duplicate_print_to_many(
+        printer_names: &[String],
+        job_options: &PrinterJobOptions,
+        originals: &[JobId],
+    ) -> Result<Vec<Result<JobId, PrintError>>, PrintError> {
+        if job_options.dedupe_mode == DedupeMode::Reject {
+            return Err(PrintError::DuplicateJob);
+        }
+        Ok(printer_names
+            .iter()
+            .map(|printer_name| {
+                let printer_name = crate::aliases::resolve(printer_name);
+                originals
+                    .iter()
+                    .copied()
+                    .find(|id| {
+                        JOB_TRACKER.inspect(*id, |job| job.printer_name == printer_name)
+                            == Some(true)
+                    })
+                    .ok_or(PrintError::DuplicateJob)
+            })
+            .collect())
+    }
+
+    /// Read and check a file for submission, optionally spooling a copy for
+    /// the jobs to print from
+    fn prepare_file(
+        job_id: JobId,
+        printer_name: &str,
+        file_path: &str,
+        job_options: &PrinterJobOptions,
+        reprint: Option<Reprint<'_>>,
+        mut spool: bool,
+    ) -> Result<PreparedFile, PrintError> {
+        check_file_exists(file_path)?;
+
+        // Use the caller's document format, or detect it from the file.
+        // Reprints keep the original's, as spooled payloads have no extension.
+        let data = std::fs::read(file_path).ok();
+        let (mut media_type, mut media_type_source) =
+            match (&reprint, document_format(&job_options.raw_properties)?) {
+                (Some(reprint), _) => (
+                    reprint.original.media_type.clone(),
+                    reprint.original.media_type_source,
+                ),
+                (None, Some(format)) => (format, MediaTypeSource::Option),
+                (None, None) => detect_file_media_type(file_path, data.as_deref()),
+            };
+        if job_options.validate_document {
+            match &data {
+                Some(data) => check_document(data, &media_type)?,
+                None if !should_simulate_printing() => return Err(PrintError::FileNotFound),
+                None => {}
+            }
+        }
+        // Reprints were checked when they were first submitted
+        if reprint.is_none() {
+            check_document_policy(printer_name, &media_type, data.as_deref())?;
+        }
+
+        // Apply page options like watermarks, printing from the changed
+        // copy. Retained payloads of reprints already have them.
+        let reprints_payload = reprint
+            .as_ref()
+            .is_some_and(|reprint| reprint.payload.is_some());
+        let data = match data {
+            Some(data) if !reprints_payload => match preprocess_document(
+                &data,
+                job_options,
+                &mut media_type,
+                &mut media_type_source,
+            )? {
+                Some(processed) => {
+                    spool = true;
+                    Some(processed)
+                }
+                None => Some(data),
+            },
+            data => data,
+        };
+
+        // Print from a copy in the spool directory so a retained payload is
+        // exactly what was sent
+        let mut payload = reprint.as_ref().and_then(|reprint| reprint.payload.clone());
+        if let Some(data) = data.as_deref().filter(|_| spool) {
+            payload = Some(Arc::new(spool_payload(job_id, printer_name, data)?));
+        }
+
+        let (source_path, reprint_of) = match reprint {
+            Some(reprint) => (
+                reprint.original.source_path.clone(),
+                Some(reprint.original.id),
+            ),
+            None => (Some(file_path.to_string()), None),
+        };
+        Ok(PreparedFile {
+            print_path: payload.as_ref().map_or_else(
+                || file_path.to_string(),
+                |payload| payload.path().to_string_lossy().into_owned(),
+            ),
+            payload,
+            source_path,
+            reprint_of,
+            media_type,
+            media_type_source,
+            size_bytes: data.as_ref().map(|data| data.len() as u64),
+            total_pages: data.as_deref().and_then(crate::document::pdf_page_count),
+        })
+    }
+
+    /// Track a job for a prepared file and start printing it, or hold it
+    /// if it requires approval
+    fn enqueue_file_job(
+        job_id: JobId,
+        printer_name: &str,
+        file: &PreparedFile,
+        job_options: PrinterJobOptions,
+    ) -> Result<(), PrintError> {
+        // Create job name from options or default to GUID
+        let job_name = job_options
+            .name
+            .clone()
+            .unwrap_or_else(|| Uuid::new_v4().simple().to_string());
+
+        // Create job status
+        let mut job_status = PrinterJob::new(
+            job_id,
+            job_name,
+            file.media_type.clone(),
+            printer_name.to_string(),
+        );
+        job_status.media_type_source = file.media_type_source;
+        job_status.owner = job_options
+            .requesting_user
+            .clone()
+            .or_else(crate::spooler::current_user);
+        job_status.job_options = job_options.clone();
+        job_status.source_path = file.source_path.clone();
+        job_status.reprint_of = file.reprint_of;
+        redact_job(&mut job_status);
+
+        // Count pages before submission for usage accounting
+        if let Some(size_bytes) = file.size_bytes {
+            job_status.size_bytes = size_bytes;
+            job_status.total_pages = file.total_pages;
+            (job_status.pages, job_status.sheets) =
+                job_usage(job_status.total_pages, &job_options.raw_properties);
+        }
+
+        if job_options.require_approval {
+            // Held jobs print from their own copy, as the file may change or
+            // be gone by the time the job is approved
+            let data = match std::fs::read(&file.print_path) {
+                Ok(data) => data,
+                Err(_) if should_simulate_printing() => Vec::new(),
+                Err(_) => return Err(PrintError::FileNotFound),
+            };
+            return hold_job(job_status, &data);
+        }
+
+        // Store job in tracker
+        tracing::info!(
+            job_id,
+            printer = printer_name,
+            size_bytes = job_status.size_bytes,
+            "Job submitted"
+        );
+        JOB_TRACKER.insert(job_status.clone());
+        log_submission(&job_status);
+        crate::metrics::job_submitted();
+        notify_job_event(WebhookEvent::Submitted, &job_status);
+        crate::metrics::set_queue_depth(queue_depth(&JOB_TRACKER));
+        ensure_history_sweeper();
+        ensure_stall_watchdog();
+
+        if let Some(timeout) = job_options.timeout {
+            start_job_timeout(JOB_TRACKER.clone(), job_id, timeout);
+        }
+        if job_options.keep_spool_file {
+            if let Some(payload) = &file.payload {
+                crate::spool::retain(job_id, Arc::clone(payload));
+            }
+        }
+
+        // Spawn background thread to handle printing (simplified)
+        let file_path_owned = file.print_path.clone();
+        let job_options_owned = Some(job_options);
+        let shutdown_flag = SHUTDOWN_FLAG.clone();
+        let job_tracker = JOB_TRACKER.clone();
+        let payload = file.payload.clone();
+
+        let task = supervisor::spawn_once(format!("job-{}", job_id), move || {
+            // The spooled copy is removed once every job printing it is done
+            let _payload = payload;
+            Self::handle_print_job_simple(
+                job_id,
+                file_path_owned,
+                job_options_owned,
+                shutdown_flag,
+                job_tracker,
+            );
+        });
+
+        JOB_TASKS.push(task);
+        Ok(())
+    }
+
+    /// Print raw bytes with optional job properties
+    pub fn print_bytes(
+        printer_name: &str,
+        data: &[u8],
+        job_options: Option<PrinterJobOptions>,
+    ) -> Result<JobId, PrintError> {
+        if !ACCEPTING_JOBS.load(Ordering::Relaxed) {
+            return Err(PrintError::ShuttingDown);
+        }
+
+        // Jobs submitted through an alias go to its printer with its defaults
+        let alias = crate::aliases::get(printer_name);
+        let printer_name = alias
+            .as_ref()
+            .map_or(printer_name, |alias| alias.printer.as_str());
+
+        // Check if printer exists
+        let _printer =
+            Self::find_printer_by_name(printer_name).ok_or(PrintError::PrinterNotFound)?;
+
+        // Generate job ID
+        let job_id = generate_job_id();
+
+        let mut job_options = prepare_job_options(job_options, Some(printer_name), alias.as_ref())?;
+        check_printer_options(printer_name, &mut job_options)?;
+        let dedupe = match claim_dedupe_key(&job_options, &[job_id]) {
+            Ok(claim) => claim,
+            Err(originals) => return duplicate_job(&job_options, &originals),
+        };
+        let _admission = admit_job(printer_name)?;
+
+        // Use the caller's document format, or detect it from the content
+        let (mut media_type, mut media_type_source) =
+            match document_format(&job_options.raw_properties)? {
+                Some(format) => (format, MediaTypeSource::Option),
+                None => detect_bytes_media_type(data),
+            };
+        if job_options.validate_document {
+            check_document(data, &media_type)?;
+        }
+        check_document_policy(printer_name, &media_type, Some(data))?;
+        let processed =
+            preprocess_document(data, &job_options, &mut media_type, &mut media_type_source)?;
+        let data = processed.as_deref().unwrap_or(data);
+        check_job_policy(
+            printer_name,
+            &job_options,
+            crate::document::pdf_page_count(data),
+        )?;
+
+        // Create job name from options or default
+        let job_name = job_options
+            .name
+            .clone()
+            .unwrap_or_else(|| "Raw Bytes Print Job".to_string());
+
+        // Create job status
+        let mut job_status =
+            PrinterJob::new(job_id, job_name, media_type, printer_name.to_string());
+        job_status.media_type_source = media_type_source;
+        job_status.owner = job_options
+            .requesting_user
+            .clone()
+            .or_else(crate::spooler::current_user);
+        job_status.job_options = job_options.clone();
+        job_status.size_bytes = data.len() as u64;
+        job_status.total_pages = crate::document::pdf_page_count(data);
+        (job_status.pages, job_status.sheets) =
+            job_usage(job_status.total_pages, &job_options.raw_properties);
+        redact_job(&mut job_status);
+
+        if job_options.require_approval {
+            hold_job(job_status, data)?;
+            dedupe.commit();
+            return Ok(job_id);
+        }
+
+        // Queued jobs keep their payload on disk rather than in memory, and
+        // keep it afterwards if it may be needed to retry a stalled job
+        let spool_file = Arc::new(spool_payload(job_id, printer_name, data)?);
+        if job_options.keep_spool_file || retries_stalled_jobs() {
+            crate::spool::retain(job_id, Arc::clone(&spool_file));
+        }
+
+        // Store job in tracker
+        tracing::info!(
+            job_id,
+            printer = printer_name,
+            size_bytes = job_status.size_bytes,
+            "Job submitted"
+        );
+        JOB_TRACKER.insert(job_status.clone());
+        log_submission(&job_status);
+        crate::metrics::job_submitted();
+        notify_job_event(WebhookEvent::Submitted, &job_status);
+        crate::metrics::set_queue_depth(queue_depth(&JOB_TRACKER));
+        ensure_history_sweeper();
+        ensure_stall_watchdog();
+
+        if let Some(timeout) = job_options.timeout {
+            start_job_timeout(JOB_TRACKER.clone(), job_id, timeout);
+        }
+
+        // Spawn background thread to handle printing
+        let job_options_owned = Some(job_options);
+        let shutdown_flag = SHUTDOWN_FLAG.clone();
+        let job_tracker = JOB_TRACKER.clone();
+
+        let task = supervisor::spawn_once(format!("job-{}", job_id), move || {
+            Self::handle_print_bytes_job(
+                job_id,
+                spool_file,
+                job_options_owned,
+                shutdown_flag,
+                job_tracker,
+            );
+        });
+
+        JOB_TASKS.push(task);
+
+        dedupe.commit();
+        Ok(job_id)
+    }
+
+    /// Handle print job (file) - updated with real printing
+    fn handle_print_job_simple(
+        job_id: JobId,
+        file_path: String,
+        job_options: Option<PrinterJobOptions>,
+        shutdown_flag: Arc<AtomicBool>,
+        job_tracker: JobTracker,
+    ) {
+        // Jobs stay pending until their dependencies have completed and a
+        // worker slot is free
+        let depends_on = job_options
+            .as_ref()
+            .map_or(&[][..], |options| &options.depends_on);
+        if !await_dependencies(&job_tracker, job_id, depends_on, &shutdown_flag) {
+            return;
+        }
+        let Some(slot) = acquire_worker_slot(&shutdown_flag) else {
+            return;
+        };
+
+        // Update status to processing, unless the job was cancelled while
+        // queued or its printer was removed
+        let Some(printer_name) = start_processing(&job_tracker, job_id) else {
+            return;
+        };
+        if !check_transport(&job_tracker, job_id, &printer_name) {
+            return;
+        }
+
+        if let Some(remote) = crate::remote::get(&printer_name) {
+            let job_options = job_options.unwrap_or_else(PrinterJobOptions::none);
+            return print_remote(
+                &remote,
+                job_id,
+                Path::new(&file_path),
+                &job_options,
+                slot,
+                &shutdown_flag,
+                &job_tracker,
+            );
+        }
+
+        if should_simulate_printing() {
+            if simulate_print_delay(&shutdown_flag) {
+                complete_job(&job_tracker, job_id, true, None);
+            }
+        } else {
+            // Real printing using printers crate
+            let job_options = job_options.unwrap_or_else(PrinterJobOptions::none);
+            let print_result = submit_as_user(&printer_name, &job_options, || {
+                Self::execute_real_print_job(&printer_name, &file_path, &job_options.raw_properties)
+            });
+
+            match print_result {
+                Ok(system_job_id)
+                    if job_tracker.inspect(job_id, PrinterJob::abandoned) == Some(true) =>
+                {
+                    cancel_late_submission(&printer_name, job_id, system_job_id)
+                }
+                Ok(system_job_id) => {
+                    job_tracker.log(
+                        job_id,
+                        LogLevel::Info,
+                        format!("Spooler accepted the job as job {}", system_job_id),
+                    );
+                    record_spooler_usage(&job_tracker, job_id, &printer_name, system_job_id);
+                    complete_job(&job_tracker, job_id, true, None)
+                }
+                Err(error_msg) => complete_job(&job_tracker, job_id, false, Some(error_msg)),
+            }
+        }
+    }
+
+    /// Execute actual printing using the printers crate
+    fn execute_real_print_job(
+        printer_name: &str,
+        file_path: &str,
+        job_options: &HashMap<String, String>,
+    ) -> Result<u64, String> {
+        // Check if file exists. The path is left out of the error, which
+        // ends up in the job's error message even when the job is redacted.
+        if !std::path::Path::new(file_path).exists() {
+            return Err("Document file not found".to_string());
+        }
+
+        // Find the printer
+        let printer = cached_printer(printer_name)
+            .ok_or_else(|| format!("Printer '{}' not found", printer_name))?;
+
+        // Convert HashMap to upstream PrinterJobOptions
+        use printers::common::base::job::PrinterJobOptions as PrinterJobOpts;
+        // Execute print with proper lifetime management
+        let result = if job_options.is_empty() {
+            let job_opts = PrinterJobOpts::none();
+            match printer.print_file(file_path, job_opts) {
+                Ok(system_job_id) => Ok(system_job_id),
+                Err(e) => Err(format!("Print failed: {:?}", e)),
+            }
+        } else {
+            // Convert HashMap to slice of tuple references with proper lifetime
+            let properties: Vec<(&str, &str)> = job_options
+                .iter()
+                .map(|(k, v)| (k.as_str(), v.as_str()))
+                .collect();
+
+            let job_opts = PrinterJobOpts {
+                name: job_options.get("job-name").map(|s| s.as_str()),
+                raw_properties: &properties,
+                converter: printers::common::converters::Converter::None,
+            };
+
+            match printer.print_file(file_path, job_opts) {
+                Ok(system_job_id) => Ok(system_job_id),
+                Err(e) => Err(format!("Print failed: {:?}", e)),
+            }
+        };
+
+        // Spooler errors may quote the file too
+        result.map_err(|e| e.replace(file_path, "<document>"))
+    }
+
+    /// Handle print bytes job. The spool file is removed once the job is done,
+    /// unless it is retained for the job.
+    fn handle_print_bytes_job(
+        job_id: JobId,
+        spool_file: Arc<crate::spool::SpoolFile>,
+        job_options: Option<PrinterJobOptions>,
+        shutdown_flag: Arc<AtomicBool>,
+        job_tracker: JobTracker,
+    ) {
+        // Jobs stay pending until their dependencies have completed and a
+        // worker slot is free
+        let depends_on = job_options
+            .as_ref()
+            .map_or(&[][..], |options| &options.depends_on);
+        if !await_dependencies(&job_tracker, job_id, depends_on, &shutdown_flag) {
+            return;
+        }
+        let Some(slot) = acquire_worker_slot(&shutdown_flag) else {
+            return;
+        };
+
+        // Update status to processing, unless the job was cancelled while
+        // queued or its printer was removed
+        let Some(printer_name) = start_processing(&job_tracker, job_id) else {
+            return;
+        };
+        if !check_transport(&job_tracker, job_id, &printer_name) {
+            return;
+        }
+
+        if let Some(remote) = crate::remote::get(&printer_name) {
+            let job_options = job_options.unwrap_or_else(PrinterJobOptions::none);
+            return print_remote(
+                &remote,
+                job_id,
+                spool_file.path(),
+                &job_options,
+                slot,
+                &shutdown_flag,
+                &job_tracker,
+            );
+        }
+
+        if should_simulate_printing() {
+            if simulate_print_delay(&shutdown_flag) {
+                complete_job(&job_tracker, job_id, true, None);
+            }
+        } else {
+            // Real printing using printers crate
+            let job_options = job_options.unwrap_or_else(PrinterJobOptions::none);
+            let print_result = submit_as_user(&printer_name, &job_options, || {
+                Self::execute_real_print_job(
+                    &printer_name,
+                    &spool_file.path().to_string_lossy(),
+                    &job_options.raw_properties,
+                )
+            });
+
+            match print_result {
+                Ok(system_job_id)
+                    if job_tracker.inspect(job_id, PrinterJob::abandoned) == Some(true) =>
+                {
+                    cancel_late_submission(&printer_name, job_id, system_job_id)
+                }
+                Ok(system_job_id) => {
+                    job_tracker.log(
+                        job_id,
+                        LogLevel::Info,
+                        format!("Spooler accepted the job as job {}", system_job_id),
+                    );
+                    record_spooler_usage(&job_tracker, job_id, &printer_name, system_job_id);
+                    complete_job(&job_tracker, job_id, true, None)
+                }
+                Err(error_msg) => complete_job(&job_tracker, job_id, false, Some(error_msg)),
+            }
+        }
+    }
+
+    /// What happened to a job: its state changes and the messages of the
+    /// spooler, driver or remote printer handling it, oldest first. Only the
+    /// last `MAX_JOB_LOG_ENTRIES` are kept, and the log leaves the history
+    /// along with the job.
+    pub fn get_job_log(job_id: JobId) -> Option<Vec<JobLogEntry>> {
+        JOB_TRACKER.log_entries(job_id)
+    }
+
+    /// Get job status
+    pub fn get_job_status(job_id: JobId) -> Option<PrinterJob> {
+        JOB_TRACKER.get(job_id)
+    }
+
+    /// Get all active jobs (pending or processing), oldest first
+    pub fn get_active_jobs() -> Vec<PrinterJob> {
+        Self::query_jobs(&JobQuery::active(None)).jobs
+    }
+
+    /// Get active jobs for a specific printer, oldest first
+    pub fn get_active_jobs_for_printer(printer_name: &str) -> Vec<PrinterJob> {
+        Self::query_jobs(&JobQuery::active(Some(printer_name))).jobs
+    }
+
+    /// Get job history (completed, cancelled or failed jobs), most recent first
+    pub fn get_job_history() -> Vec<PrinterJob> {
+        Self::query_jobs(&JobQuery::history(None)).jobs
+    }
+
+    /// Get job history for a specific printer, most recent first
+    pub fn get_job_history_for_printer(printer_name: &str) -> Vec<PrinterJob> {
+        Self::query_jobs(&JobQuery::history(Some(printer_name))).jobs
+    }
+
+    /// Get all jobs for a specific printer, most recent first
+    pub fn get_all_jobs_for_printer(printer_name: &str) -> Vec<PrinterJob> {
+        Self::query_jobs(&JobQuery {
+            printer_name: Some(printer_name.to_string()),
+            ..JobQuery::default()
+        })
+        .jobs
+    }
+
+    /// Jobs whose metadata has every given key with the given value,
+    /// optionally only for one printer, oldest first
+    pub fn find_jobs(
+        printer_name: Option<&str>,
+        metadata: &HashMap<String, String>,
+    ) -> Vec<PrinterJob> {
+        Self::query_jobs(&JobQuery {
+            printer_name: printer_name.map(str::to_string),
+            metadata: metadata.clone(),
+            sort: JobSort::CreatedAsc,
+            ..JobQuery::default()
+        })
+        .jobs
+    }
+
+    /// Clean up old completed/failed jobs
+    pub fn cleanup_old_jobs(max_age_seconds: u64) -> u32 {
+        remove_finished_jobs(None, Duration::from_secs(max_age_seconds))
+    }
+
+    /// Clean up old completed/failed jobs for a specific printer
+    pub fn cleanup_old_jobs_for_printer(printer_name: &str, max_age_seconds: u64) -> u32 {
+        let printer_name = crate::aliases::resolve(printer_name);
+        remove_finished_jobs(Some(&printer_name), Duration::from_secs(max_age_seconds))
+    }
+
+    /// Cancel every job that hasn't finished, optionally only for one printer.
+    /// Jobs already accepted by the system spooler are not affected, and
+    /// jobs held for approval are only removed by `reject_job`.
+    pub fn cancel_all_jobs(printer_name: Option<&str>) -> u32 {
+        let printer_name = printer_name.map(crate::aliases::resolve);
+        cancel_outstanding_jobs(
+            |job| {
+                printer_name
+                    .as_ref()
+                    .is_none_or(|name| job.printer_name == *name)
+            },
+            "Cancelled by request",
+        )
+    }
+
+    /// Path of the payload retained for a job submitted with `keep_spool_file`
+    pub fn get_job_spool_file(job_id: JobId) -> Option<PathBuf> {
+        crate::spool::retained(job_id).map(|spool_file| spool_file.path().to_path_buf())
+    }
+
+    /// Remove all completed and cancelled jobs, optionally only for one printer
+    pub fn clear_job_history(printer_name: Option<&str>) -> u32 {
+        match printer_name {
+            Some(name) => Self::cleanup_old_jobs_for_printer(name, 0),
+            None => Self::cleanup_old_jobs(0),
+        }
+    }
+
+    /// Shutdown the library and cleanup all background threads
+    pub fn shutdown_library() {
+        Self::shutdown_library_with(ShutdownMode::Graceful, DEFAULT_SHUTDOWN_TIMEOUT);
+    }
+
+    /// Shutdown the library, handling outstanding jobs according to `mode`.
+    /// New submissions are refused until shutdown completes. Jobs still
+    /// outstanding after `timeout` are marked cancelled rather than abandoned.
+    pub fn shutdown_library_with(mode: ShutdownMode, timeout: Duration) -> ShutdownSummary {
+        ACCEPTING_JOBS.store(false, Ordering::Relaxed);
+        tracing::info!(mode = mode.as_str(), "Shutting down");
+
+        let mut summary = ShutdownSummary::default();
+        let outstanding_at_start = Self::outstanding_job_ids(|_| true);
+
+        match mode {
+            ShutdownMode::Force => {
+                SHUTDOWN_FLAG.store(true, Ordering::Relaxed);
+                summary.cancelled +=
+                    cancel_outstanding_jobs(|_| true, "Cancelled by forced shutdown");
+            }
+            ShutdownMode::Graceful => {
+                // Jobs that haven't reached the spooler yet won't be started
+                summary.cancelled += cancel_outstanding_jobs(
+                    |job| job.state == PrinterJobState::PENDING,
+                    "Cancelled by shutdown before printing started",
+                );
+            }
+            ShutdownMode::Drain => {}
+        }
+
+        // Wait for the remaining jobs to finish
+        let start = Instant::now();
+        while !Self::outstanding_job_ids(|_| true).is_empty() && start.elapsed() < timeout {
+            thread::sleep(Duration::from_millis(50));
+        }
+
+        SHUTDOWN_FLAG.store(true, Ordering::Relaxed);
+        WORKER_SLOTS.1.notify_all();
+        summary.timed_out =
+            cancel_outstanding_jobs(|_| true, "Shutdown timed out before the job finished");
+        summary.cancelled += summary.timed_out;
+
+        summary.completed = JOB_TRACKER.count(|job| {
+            job.state == PrinterJobState::COMPLETED && outstanding_at_start.contains(&job.id)
+        }) as u32;
+
+        join_job_threads();
+        stop_history_sweeper();
+        stop_stall_watchdog();
+        crate::spool::cleanup();
+
+        // Reset flags for potential reuse
+        SHUTDOWN_FLAG.store(false, Ordering::Relaxed);
+        ACCEPTING_JOBS.store(true, Ordering::Relaxed);
+        summary
+    }
+
+    /// IDs of queued or printing jobs that match the filter
+    fn outstanding_job_ids(filter: impl Fn(&PrinterJob) -> bool) -> Vec<JobId> {
+        JOB_TRACKER
+            .filter(|job| job.state.is_queued() && filter(job))
+            .iter()
+            .map(|job| job.id)
+            .collect()
+    }
+}
+
+// ===== SHUTDOWN =====
+
+/// Time allowed for outstanding jobs when shutting down without options
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Whether print_file/print_bytes accept new jobs (false while shutting down)
+static ACCEPTING_JOBS: AtomicBool = AtomicBool::new(true);
+
+/// Time in-flight jobs get to reach the spooler when the host process exits
+const EXIT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Identifies a JavaScript environment (main thread, worker) using the
+/// library
+pub type EnvId = u64;
+
+/// Number of JavaScript environments (main thread, workers) using the library
+static ATTACHED_ENVS: AtomicUsize = AtomicUsize::new(0);
+
+static NEXT_ENV_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Environment that took ownership of library-wide state with `attach`, or 0
+static OWNER_ENV: AtomicU64 = AtomicU64::new(0);
+
+lazy_static::lazy_static! {
+    /// Environments that started state monitoring
+    static ref MONITORING_ENVS: Mutex<std::collections::HashSet<EnvId>> =
+        Mutex::new(std::collections::HashSet::new());
+}
+
+const NOT_OWNER: &str = "The library is attached to another JavaScript environment";
+
+impl PrinterCore {
+    /// Record that a JavaScript environment loaded the library, returning
+    /// its ID
+    pub fn env_attached() -> EnvId {
+        // Jobs held for approval by a previous run are picked up once
+        if ATTACHED_ENVS.fetch_add(1, Ordering::SeqCst) == 0 {
+            Self::restore_held_jobs();
+        }
+        NEXT_ENV_ID.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Record that a JavaScript environment is exiting, releasing its
+    /// ownership. Background threads are torn down when the last one goes,
+    /// since they must not outlive the runtime that loaded this library.
+    pub fn env_detached(env: EnvId) {
+        Self::detach(env);
+        let _ = Self::stop_state_monitoring_for(env);
+        let previous = ATTACHED_ENVS
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+                Some(count.saturating_sub(1))
+            })
+            .unwrap_or(0);
+        if previous <= 1 {
+            tracing::debug!("Last environment detached, shutting down");
+            let _ = Self::stop_state_monitoring();
+            Self::shutdown_library_with(ShutdownMode::Graceful, EXIT_SHUTDOWN_TIMEOUT);
+        }
+    }
+
+    /// Number of JavaScript environments using the library
+    pub fn attached_envs() -> usize {
+        ATTACHED_ENVS.load(Ordering::SeqCst)
+    }
+
+    /// Make `env` the owner of library-wide state. While an environment
+    /// owns the library, only it may reconfigure it or shut it down, so an
+    /// Electron renderer or a worker can't tear down jobs the main process
+    /// relies on. Fails if another environment owns it.
+    pub fn attach(env: EnvId) -> Result<(), String> {
+        match OWNER_ENV.compare_exchange(0, env, Ordering::SeqCst, Ordering::SeqCst) {
+            Ok(_) => Ok(()),
+            Err(owner) if owner == env => Ok(()),
+            Err(_) => Err(NOT_OWNER.to_string()),
+        }
+    }
+
+    /// Give up ownership. Returns false if `env` didn't own the library.
+    pub fn detach(env: EnvId) -> bool {
+        OWNER_ENV
+            .compare_exchange(env, 0, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+    }
+
+    /// Environment that owns the library, if any
+    pub fn owner_env() -> Option<EnvId> {
+        match OWNER_ENV.load(Ordering::SeqCst) {
+            0 => None,
+            env => Some(env),
+        }
+    }
+
+    /// Check that `env` may change library-wide state: it owns the library
+    /// or no environment does
+    pub fn check_owner(env: EnvId) -> Result<(), String> {
+        match Self::owner_env() {
+            Some(owner) if owner != env => Err(NOT_OWNER.to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// How outstanding jobs are handled when the library shuts down
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ShutdownMode {
+    /// Wait for jobs already handed to the spooler; cancel jobs not yet started
+    Graceful,
+    /// Finish all queued and in-flight jobs
+    Drain,
+    /// Cancel everything immediately
+    Force,
+}
+
+impl ShutdownMode {
+    pub fn parse(mode: &str) -> Option<ShutdownMode> {
+        match mode {
+            "graceful" => Some(ShutdownMode::Graceful),
+            "drain" => Some(ShutdownMode::Drain),
+            "force" => Some(ShutdownMode::Force),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ShutdownMode::Graceful => "graceful",
+            ShutdownMode::Drain => "drain",
+            ShutdownMode::Force => "force",
+        }
+    }
+}
+
+/// What happened to the jobs that were outstanding at shutdown
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ShutdownSummary {
+    /// Jobs that finished during shutdown
+    pub completed: u32,
+    /// Jobs marked cancelled, including those that timed out
+    pub cancelled: u32,
+    /// Jobs still running when the timeout expired
+    pub timed_out: u32,
+}
+
+/// Mark matching queued or printing jobs as cancelled. Jobs held for
+/// approval are left for `reject_job`, so they survive shutdown.
+fn cancel_outstanding_jobs(filter: impl Fn(&PrinterJob) -> bool, reason: &str) -> u32 {
+    let now = SystemTime::now();
+    let mut cancelled_ids = Vec::new();
+    JOB_TRACKER.update_all(|job| {
+        if job.state.is_queued()
+            && filter(job)
+            && job.transition_to(PrinterJobState::CANCELLED).is_ok()
+        {
+            job.error_message = Some(reason.to_string());
+            job.completed_at = Some(now);
+            cancelled_ids.push(job.id);
+        }
+    });
+    for job_id in &cancelled_ids {
+        JOB_TRACKER.log(*job_id, LogLevel::Warn, reason);
+    }
+    let cancelled = cancelled_ids.len() as u32;
+    if cancelled > 0 {
+        tracing::info!(cancelled, "{}", reason);
+        evict_job_history(&JOB_TRACKER);
+    }
+    crate::metrics::set_queue_depth(queue_depth(&JOB_TRACKER));
+    cancelled
+}
+
+/// Join job threads that have exited. Threads blocked inside the spooler
+/// can't be interrupted and are detached.
+fn join_job_threads() {
+    let detached = JOB_TASKS.join(Duration::from_millis(500));
+    if detached > 0 {
+        tracing::warn!(
+            threads = detached,
+            "Detaching job threads blocked in the spooler"
+        );
+    }
+}
+
+// ===== RUNTIME STATUS =====
+
+/// Background tasks of the library
+#[derive(Clone, Debug)]
+pub struct RuntimeStatus {
+    /// Tasks running, waiting to restart after a panic, or given up
+    pub tasks: Vec<supervisor::TaskStatus>,
+    /// Threads printing jobs
+    pub job_threads: u32,
+    /// Whether the state monitor is running
+    pub monitoring_running: bool,
+}
+
+impl PrinterCore {
+    /// List the library's background tasks, including monitors that
+    /// panicked and were restarted or gave up
+    pub fn get_runtime_status() -> RuntimeStatus {
+        let monitoring_running = GLOBAL_STATE_MONITOR
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|monitor| monitor.is_running());
+        RuntimeStatus {
+            tasks: supervisor::status(),
+            job_threads: JOB_TASKS.running() as u32,
+            monitoring_running,
+        }
+    }
+}
+
+/// Extended functionality for Printer objects
+pub trait PrinterJobTracking {
+    /// Get active jobs for this printer
+    fn get_active_jobs(&self) -> Vec<PrinterJob>;
+
+    /// Get job history for this printer
+    fn get_job_history(&self, limit: Option<usize>) -> Vec<PrinterJob>;
+
+    /// Get a specific job by ID (only if it belongs to this printer)
+    fn get_job(&self, job_id: JobId) -> Option<PrinterJob>;
+
+    /// Get all jobs for this printer
+    fn get_all_jobs(&self) -> Vec<PrinterJob>;
+
+    /// Clean up old jobs for this printer
+    fn cleanup_old_jobs(&self, max_age_seconds: u64) -> u32;
+}
+
+impl PrinterJobTracking for Printer {
+    fn get_active_jobs(&self) -> Vec<PrinterJob> {
+        JOB_TRACKER.filter(|job| job.printer_name == self.name && !job.state.is_terminal())
+    }
+
+    fn get_job_history(&self, limit: Option<usize>) -> Vec<PrinterJob> {
+        // Sorted by creation time (most recent first)
+        PrinterCore::query_jobs(&JobQuery {
+            limit,
+            ..JobQuery::history(Some(&self.name))
+        })
+        .jobs
+    }
+
+    fn get_job(&self, job_id: JobId) -> Option<PrinterJob> {
+        JOB_TRACKER
+            .get(job_id)
+            .filter(|job| job.printer_name == self.name)
+    }
+
+    fn get_all_jobs(&self) -> Vec<PrinterJob> {
+        JOB_TRACKER.filter(|job| job.printer_name == self.name)
+    }
+
+    fn cleanup_old_jobs(&self, max_age_seconds: u64) -> u32 {
+        remove_finished_jobs(Some(&self.name), Duration::from_secs(max_age_seconds))
+    }
+}
+
+/// Remove completed and cancelled jobs created at least `max_age` ago,
+/// optionally only those of one printer
+fn remove_finished_jobs(printer_name: Option<&str>, max_age: Duration) -> u32 {
+    let removed = JOB_TRACKER.retain(|job| {
+        let should_remove = printer_name.is_none_or(|name| job.printer_name == name)
+            && job.state.is_terminal()
+            && job.created_at.elapsed().unwrap_or_default() >= max_age;
+        !should_remove
+    }) as u32;
+    release_spool_files(&JOB_TRACKER);
+    removed
+}
+
+// ===== TEST PAGE =====
+
+impl PrinterCore {
+    /// Generate a diagnostic page for a printer (its name, driver, location
+    /// and the time, over an alignment grid) and print it as a PDF. The job
+    /// is named "Test Page" unless the options name it.
+    pub fn print_test_page(
+        printer_name: &str,
+        job_options: Option<PrinterJobOptions>,
+    ) -> Result<JobId, PrintError> {
+        let printer =
+            Self::find_printer_by_name(printer_name).ok_or(PrintError::PrinterNotFound)?;
+        let mut lines = vec![
+            format!("Printer: {}", printer.name),
+            format!("Driver: {}", printer.driver_name),
+        ];
+        if !printer.location.is_empty() {
+            lines.push(format!("Location: {}", printer.location));
+        }
+        lines.extend([
+            format!("URI: {}", printer.uri),
+            format!(
+                "Printed: {}",
+                crate::document::format_utc(SystemTime::now())
+            ),
+            format!("printers-js {}", env!("CARGO_PKG_VERSION")),
+            "Grid squares are 1 inch; the border is 1/4 inch inside the page edges".to_string(),
+        ]);
+        let pdf = crate::document::test_page_pdf("Test Page", &lines);
+
+        let mut job_options = job_options.unwrap_or_else(PrinterJobOptions::none);
+        job_options
+            .raw_properties
+            .entry("document-format".to_string())
+            .or_insert_with(|| "application/pdf".to_string());
+        job_options
+            .name
+            .get_or_insert_with(|| "Test Page".to_string());
+        Self::print_bytes(printer_name, &pdf, Some(job_options))
+    }
+}
+
+// ===== USAGE ACCOUNTING =====
+
+/// Job attributes CUPS fills in from filter and backend page accounting
+const SPOOLER_USAGE_ATTRIBUTES: &[&str] = &[
+    "job-impressions",
+    "job-impressions-completed",
+    "job-media-sheets",
+    "job-media-sheets-completed",
+];
+
+/// Aggregated usage over completed jobs
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UsageStats {
+    pub jobs: u32,
+    pub pages: u64,
+    pub sheets: u64,
+    pub bytes: u64,
+    /// Completed jobs whose page count couldn't be determined
+    pub jobs_without_page_count: u32,
+}
+
+/// Count the pages selected by an IPP `page-ranges` value (e.g. "1-5,8")
+fn count_page_range(ranges: &str, document_pages: u32) -> Option<u32> {
+    let mut selected = vec![false; document_pages as usize];
+    for range in ranges.split(',').map(str::trim).filter(|r| !r.is_empty()) {
+        let (start, end): (u32, u32) = match range.split_once('-') {
+            Some((start, end)) => (start.trim().parse().ok()?, end.trim().parse().ok()?),
+            None => {
+                let page = range.parse().ok()?;
+                (page, page)
+            }
+        };
+        for page in start.max(1)..=end.min(document_pages) {
+            selected[page as usize - 1] = true;
+        }
+    }
+    Some(selected.iter().filter(|selected| **selected).count() as u32)
+}
+
+/// Total pages and sheets for a job from its document page count and options
+fn job_usage(
+    document_pages: Option<u32>,
+    raw_properties: &HashMap<String, String>,
+) -> (Option<u32>, Option<u32>) {
+    let Some(document_pages) = document_pages else {
+        return (None, None);
+    };
+    let number = |key: &str| {
+        raw_properties
+            .get(key)
+            .and_then(|value| value.trim().parse::<u32>().ok())
+            .filter(|value| *value > 0)
+            .unwrap_or(1)
+    };
+
+    let copies = number("copies");
+    let number_up = number("number-up");
+    let duplex = raw_properties
+        .get("sides")
+        .is_some_and(|sides| sides.starts_with("two-sided"));
+
+    let pages = raw_properties
+        .get("page-ranges")
+        .and_then(|ranges| count_page_range(ranges, document_pages))
+        .unwrap_or(document_pages);
+    let impressions = pages.div_ceil(number_up);
+    let sheets = if duplex {
+        impressions.div_ceil(2)
+    } else {
+        impressions
+    };
+
+    (
+        Some(pages.saturating_mul(copies)),
+        Some(sheets.saturating_mul(copies)),
+    )
+}
+
+/// Prefer the spooler's own page accounting when it reports one (CUPS only)
+fn record_spooler_usage(
+    job_tracker: &JobTracker,
+    job_id: JobId,
+    printer_name: &str,
+    system_job_id: u64,
+) {
+    let Some(printer) = cached_printer(printer_name) else {
+        return;
+    };
+    let Some(address) = DeviceAddress::parse(&printer.uri) else {
+        return;
+    };
+    if !address.speaks_ipp() {
+        return;
+    }
+    let response = match ipp::get_job_attributes(
+        &address.uri(),
+        system_job_id as i32,
+        SPOOLER_USAGE_ATTRIBUTES,
+        DEVICE_PROBE_TIMEOUT,
+    ) {
+        Ok(response) => response,
+        Err(e) => {
+            tracing::debug!(job_id, system_job_id, "Spooler usage unavailable: {}", e);
+            job_tracker.log(
+                job_id,
+                LogLevel::Debug,
+                format!("Spooler usage unavailable: {}", e),
+            );
+            return;
+        }
+    };
+
+    let count = |names: [&str; 2]| {
+        names.iter().find_map(|name| {
+            response
+                .attribute(ipp::TAG_JOB_ATTRIBUTES, name)
+                .and_then(|attribute| attribute.value())
+                .and_then(ipp::IppValue::as_i32)
+                .filter(|count| *count > 0)
+                .map(|count| count as u32)
+        })
+    };
+    let pages = count(["job-impressions-completed", "job-impressions"]);
+    let sheets = count(["job-media-sheets-completed", "job-media-sheets"]);
+
+    job_tracker.update(job_id, |job| {
+        job.pages = pages.or(job.pages);
+        job.sheets = sheets.or(job.sheets);
+    });
+}
+
+impl PrinterCore {
+    /// Aggregate usage over completed jobs, optionally for one printer and since a point in time
+    pub fn get_usage_stats(printer_name: Option<&str>, since: Option<SystemTime>) -> UsageStats {
+        let printer_name = printer_name.map(crate::aliases::resolve);
+        JOB_TRACKER
+            .filter(|job| {
+                job.state == PrinterJobState::COMPLETED
+                    && printer_name
+                        .as_ref()
+                        .is_none_or(|name| job.printer_name == *name)
+                    && since.is_none_or(|since| job.completed_at.unwrap_or(job.created_at) >= since)
+            })
+            .iter()
+            .fold(UsageStats::default(), |mut stats, job| {
+                stats.jobs += 1;
+                stats.bytes += job.size_bytes;
+                stats.sheets += u64::from(job.sheets.unwrap_or(0));
+                match job.pages {
+                    Some(pages) => stats.pages += u64::from(pages),
+                    None => stats.jobs_without_page_count += 1,
+                }
+                stats
+            })
+    }
+}
+
+// ===== PRINTER STATE REASONS =====
+
+/// Normalized printer state reason
+///
+/// CUPS reports IPP `printer-state-reasons` keywords (e.g. `media-empty-error`,
+/// `toner-low-warning`) while the Windows backend reports lowercase
+/// `PRINTER_STATUS_*` names (e.g. `paper_out`, `door_open`). Both are mapped
+/// onto this enum so callers can branch on reasons without string matching.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StateReason {
+    MediaEmpty,
+    MediaLow,
+    MediaJam,
+    MediaNeeded,
+    TonerLow,
+    TonerEmpty,
+    MarkerSupplyLow,
+    MarkerSupplyEmpty,
+    CoverOpen,
+    InputTrayMissing,
+    OutputBinFull,
+    Offline,
+    Paused,
+    Shutdown,
+    ConnectingToDevice,
+    SpoolAreaFull,
+    UserIntervention,
+    OutOfMemory,
+    PowerSave,
+    LabelOut,
+    RibbonOut,
+    Other,
+}
+
+// Windows PRINTER_STATUS_* bits (winspool.h)
+const PRINTER_STATUS_PAUSED: u32 = 0x0000_0001;
+const PRINTER_STATUS_ERROR: u32 = 0x0000_0002;
+const PRINTER_STATUS_PAPER_JAM: u32 = 0x0000_0008;
+const PRINTER_STATUS_PAPER_OUT: u32 = 0x0000_0010;
+const PRINTER_STATUS_MANUAL_FEED: u32 = 0x0000_0020;
+const PRINTER_STATUS_PAPER_PROBLEM: u32 = 0x0000_0040;
+const PRINTER_STATUS_OFFLINE: u32 = 0x0000_0080;
+const PRINTER_STATUS_OUTPUT_BIN_FULL: u32 = 0x0000_0800;
+const PRINTER_STATUS_NOT_AVAILABLE: u32 = 0x0000_1000;
+const PRINTER_STATUS_TONER_LOW: u32 = 0x0002_0000;
+const PRINTER_STATUS_NO_TONER: u32 = 0x0004_0000;
+const PRINTER_STATUS_USER_INTERVENTION: u32 = 0x0010_0000;
+const PRINTER_STATUS_OUT_OF_MEMORY: u32 = 0x0020_0000;
+const PRINTER_STATUS_DOOR_OPEN: u32 = 0x0040_0000;
+const PRINTER_STATUS_POWER_SAVE: u32 = 0x0100_0000;
+
+const WINDOWS_STATUS_REASONS: &[(u32, StateReason)] = &[
+    (PRINTER_STATUS_PAUSED, StateReason::Paused),
+    (PRINTER_STATUS_ERROR, StateReason::Other),
+    (PRINTER_STATUS_PAPER_JAM, StateReason::MediaJam),
+    (PRINTER_STATUS_PAPER_OUT, StateReason::MediaEmpty),
+    (PRINTER_STATUS_MANUAL_FEED, StateReason::MediaNeeded),
+    (PRINTER_STATUS_PAPER_PROBLEM, StateReason::MediaNeeded),
+    (PRINTER_STATUS_OFFLINE, StateReason::Offline),
+    (PRINTER_STATUS_OUTPUT_BIN_FULL, StateReason::OutputBinFull),
+    (PRINTER_STATUS_NOT_AVAILABLE, StateReason::Offline),
+    (PRINTER_STATUS_TONER_LOW, StateReason::TonerLow),
+    (PRINTER_STATUS_NO_TONER, StateReason::TonerEmpty),
+    (
+        PRINTER_STATUS_USER_INTERVENTION,
+        StateReason::UserIntervention,
+    ),
+    (PRINTER_STATUS_OUT_OF_MEMORY, StateReason::OutOfMemory),
+    (PRINTER_STATUS_DOOR_OPEN, StateReason::CoverOpen),
+    (PRINTER_STATUS_POWER_SAVE, StateReason::PowerSave),
+];
+
+impl StateReason {
+    /// Parse a raw state reason string from CUPS or Windows.
+    /// Returns None for informational reasons that don't indicate a condition
+    /// (e.g. `none`, `busy`, `io_active`).
+    pub fn parse(raw: &str) -> Option<StateReason> {
+        let normalized = raw.trim().to_lowercase().replace('_', "-");
+
+        // Strip the IPP severity suffix (RFC 8011 section 5.4.12)
+        let keyword = ["-error", "-warning", "-report"]
+            .iter()
+            .find_map(|suffix| normalized.strip_suffix(suffix))
+            .unwrap_or(normalized.as_str());
+
+        let reason = match keyword {
+            "" | "none" | "busy" | "io-active" | "printing" | "processing" | "waiting"
+            | "initializing" | "warming-up" | "page-punt" | "pending-deletion" => return None,
+            "media-empty" | "paper-out" => StateReason::MediaEmpty,
+            "media-low" => StateReason::MediaLow,
+            "media-jam" | "paper-jam" => StateReason::MediaJam,
+            "media-needed" | "manual-feed" | "paper-problem" => StateReason::MediaNeeded,
+            "toner-low" => StateReason::TonerLow,
+            "toner-empty" | "no-toner" => StateReason::TonerEmpty,
+            "marker-supply-low" | "marker-waste-almost-full" => StateReason::MarkerSupplyLow,
+            "marker-supply-empty" | "marker-waste-full" => StateReason::MarkerSupplyEmpty,
+            "cover-open" | "door-open" | "interlock-open" => StateReason::CoverOpen,
+            "input-tray-missing" => StateReason::InputTrayMissing,
+            "output-area-full" | "output-bin-full" => StateReason::OutputBinFull,
+            "offline" | "not-available" | "timed-out" => StateReason::Offline,
+            "paused" | "moving-to-paused" => StateReason::Paused,
+            "shutdown" => StateReason::Shutdown,
+            "connecting-to-device" => StateReason::ConnectingToDevice,
+            "spool-area-full" => StateReason::SpoolAreaFull,
+            "user-intervention" => StateReason::UserIntervention,
+            "out-of-memory" => StateReason::OutOfMemory,
+            "power-save" | "sleep" => StateReason::PowerSave,
+            "label-out" | "labels-out" | "label-empty" => StateReason::LabelOut,
+            "ribbon-out" | "ribbon-empty" => StateReason::RibbonOut,
+            _ => StateReason::Other,
+        };
+
+        Some(reason)
+    }
+
+    /// Parse a list of raw state reasons, dropping informational entries and duplicates
+    pub fn parse_all(raw_reasons: &[String]) -> Vec<StateReason> {
+        let mut reasons = Vec::new();
+        for reason in raw_reasons.iter().filter_map(|raw| Self::parse(raw)) {
+            if !reasons.contains(&reason) {
+                reasons.push(reason);
+            }
+        }
+        reasons
+    }
+
+    /// Decode a Windows `PRINTER_INFO_2::Status` bitmask
+    pub fn from_windows_status(status: u32) -> Vec<StateReason> {
+        let mut reasons = Vec::new();
+        for (bit, reason) in WINDOWS_STATUS_REASONS {
+            if status & bit != 0 && !reasons.contains(reason) {
+                reasons.push(*reason);
+            }
+        }
+        reasons
+    }
+
+    /// Stable string identifier exposed to JavaScript
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StateReason::MediaEmpty => "media-empty",
+            StateReason::MediaLow => "media-low",
+            StateReason::MediaJam => "media-jam",
+            StateReason::MediaNeeded => "media-needed",
+            StateReason::TonerLow => "toner-low",
+            StateReason::TonerEmpty => "toner-empty",
+            StateReason::MarkerSupplyLow => "marker-supply-low",
+            StateReason::MarkerSupplyEmpty => "marker-supply-empty",
+            StateReason::CoverOpen => "cover-open",
+            StateReason::InputTrayMissing => "input-tray-missing",
+            StateReason::OutputBinFull => "output-bin-full",
+            StateReason::Offline => "offline",
+            StateReason::Paused => "paused",
+            StateReason::Shutdown => "shutdown",
+            StateReason::ConnectingToDevice => "connecting-to-device",
+            StateReason::SpoolAreaFull => "spool-area-full",
+            StateReason::UserIntervention => "user-intervention",
+            StateReason::OutOfMemory => "out-of-memory",
+            StateReason::PowerSave => "power-save",
+            StateReason::LabelOut => "label-out",
+            StateReason::RibbonOut => "ribbon-out",
+            StateReason::Other => "other",
+        }
+    }
+}
+
+impl PrinterCore {
+    /// Get normalized state reasons for a printer
+    pub fn get_normalized_state_reasons(printer: &Printer) -> Vec<StateReason> {
+        StateReason::parse_all(&printer.state_reasons)
+    }
+}
+
+// ===== PRINTER CONNECTIVITY =====
+
+use crate::device::DeviceAddress;
+use crate::ipp;
+
+/// Timeout for reachability probes against printer devices
+const DEVICE_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Time each printer was first observed offline by a probe
+lazy_static::lazy_static! {
+    static ref OFFLINE_SINCE: Mutex<HashMap<String, SystemTime>> = Mutex::new(HashMap::new());
+}
+
+/// The URI of the physical device behind a printer. CUPS reports the local
+/// queue URI, so ask CUPS for the queue's device-uri; None if it can't be
+/// asked.
+fn device_uri(printer: &Printer) -> Option<String> {
+    match DeviceAddress::parse(&printer.uri) {
+        Some(address) if address.is_local() && address.path.starts_with("/printers/") => {
+            let response =
+                ipp::get_printer_attributes(&address.uri(), &["device-uri"], DEVICE_PROBE_TIMEOUT)
+                    .ok()?;
+            response
+                .attribute(ipp::TAG_PRINTER_ATTRIBUTES, "device-uri")
+                .and_then(|attribute| attribute.value())
+                .and_then(|value| value.as_str())
+                .map(str::to_string)
+        }
+        _ => Some(printer.uri.clone()),
+    }
+}
+
+/// Resolve the network address of the physical device behind a printer
+fn resolve_device_address(printer: &Printer) -> Option<DeviceAddress> {
+    let device = device_uri(printer)?;
+    DeviceAddress::parse(&device).or_else(|| DeviceAddress::from_port_name(&printer.port_name))
+}
+
+/// Probe a printer device directly instead of trusting the spooler state
+fn probe_printer(printer: &Printer) -> bool {
+    use printers::common::base::printer::PrinterState;
+
+    match resolve_device_address(printer) {
+        Some(address) => {
+            let ipp_ok = address.speaks_ipp()
+                && ipp::get_printer_attributes(
+                    &address.uri(),
+                    &["printer-state"],
+                    DEVICE_PROBE_TIMEOUT,
+                )
+                .is_ok();
+
+            // Fall back to a plain port check for devices that don't answer IPP
+            let online = ipp_ok || address.connect(DEVICE_PROBE_TIMEOUT).is_ok();
+            tracing::debug!(printer = %printer.name, device = %address.uri(), online, "Probed device");
+            online
+        }
+        // Local devices (USB, parallel, ...) can't be probed over the network
+        None => {
+            !matches!(printer.state, PrinterState::OFFLINE)
+                && !PrinterCore::get_normalized_state_reasons(printer)
+                    .contains(&StateReason::Offline)
+        }
+    }
+}
+
+impl PrinterCore {
+    /// Check whether a printer's device is reachable
+    pub fn is_printer_online(printer_name: &str) -> Result<bool, PrintError> {
+        let printer =
+            Self::find_printer_by_name(printer_name).ok_or(PrintError::PrinterNotFound)?;
+
+        let online = should_simulate_printing() || probe_printer(&printer);
+
+        let mut offline_since = OFFLINE_SINCE.lock().unwrap();
+        if online {
+            offline_since.remove(printer_name);
+        } else {
+            offline_since
+                .entry(printer_name.to_string())
+                .or_insert_with(SystemTime::now);
+        }
+
+        Ok(online)
+    }
+
+    /// Get the time a printer was first found offline, if the last probe failed
+    pub fn get_offline_since(printer_name: &str) -> Option<SystemTime> {
+        OFFLINE_SINCE.lock().unwrap().get(printer_name).copied()
+    }
+}
+
+// ===== PRINTER POWER =====
+
+/// How to wake a sleeping printer
+#[derive(Debug, Clone, Default)]
+pub struct WakeOptions {
+    /// MAC address to wake, overriding the one configured for the printer
+    pub mac: Option<String>,
+    /// Broadcast address for the magic packet (default 255.255.255.255)
+    pub broadcast: Option<std::net::Ipv4Addr>,
+    /// UDP port for the magic packet (default 9)
+    pub port: Option<u16>,
+    /// How long to wait for the printer to answer after waking it
+    pub wait: Option<Duration>,
+}
+
+/// Interval between reachability probes while waiting for a printer to wake
+const WAKE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Power state of a printer's device
+#[derive(Debug, Clone, PartialEq)]
+pub struct PowerStatus {
+    /// PWG power state (`on`, `standby`, `suspend`, ...), if the printer reports it
+    pub power_state: Option<String>,
+    /// Whether the power state means the printer is asleep
+    pub asleep: Option<bool>,
+    /// Whether the device answered
+    pub reachable: bool,
+}
+
+/// Read `power-state-monitor.current-state` from a Get-Printer-Attributes response
+fn current_power_state(response: &ipp::IppResponse) -> Option<String> {
+    let monitor = response.attribute(ipp::TAG_PRINTER_ATTRIBUTES, "power-state-monitor")?;
+    match monitor.value()? {
+        ipp::IppValue::Collection(members) => members
+            .iter()
+            .find(|member| member.name == "current-state")
+            .and_then(|member| member.value())
+            .and_then(|value| value.as_str())
+            .map(str::to_string),
+        _ => None,
+    }
+}
+
+impl PrinterCore {
+    /// Wake a sleeping network printer with a Wake-on-LAN magic packet.
+    /// With `wait`, returns whether the printer answered in that time;
+    /// otherwise returns true once the packet is sent.
+    pub fn wake_printer(printer_name: &str, options: &WakeOptions) -> Result<bool, String> {
+        let printer = Self::find_printer_by_name(printer_name)
+            .ok_or_else(|| format!("Printer '{}' not found", printer_name))?;
+        let mac = match &options.mac {
+            Some(mac) => crate::power::parse_mac(mac)?,
+            None => crate::power::mac_address(&printer.name).ok_or_else(|| {
+                format!(
+                    "No MAC address configured for printer '{}'; set one with setPrinterMacAddress",
+                    printer.name
+                )
+            })?,
+        };
+
+        if should_simulate_printing() {
+            return Ok(true);
+        }
+
+        crate::power::send_magic_packet(&mac, options.broadcast, options.port)?;
+        tracing::info!(printer = %printer.name, mac = %crate::power::format_mac(&mac), "Waking printer");
+
+        let Some(wait) = options.wait else {
+            return Ok(true);
+        };
+        let deadline = Instant::now() + wait;
+        loop {
+            if Self::is_printer_online(&printer.name).unwrap_or(false) {
+                return Ok(true);
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(false);
+            }
+            thread::sleep(remaining.min(WAKE_POLL_INTERVAL));
+        }
+    }
+
+    /// Get whether a printer's device is awake, from its IPP power state
+    /// where reported
+    pub fn get_power_status(printer_name: &str) -> Result<PowerStatus, String> {
+        let printer = Self::find_printer_by_name(printer_name)
+            .ok_or_else(|| format!("Printer '{}' not found", printer_name))?;
+
+        if should_simulate_printing() {
+            return Ok(PowerStatus {
+                power_state: Some("on".to_string()),
+                asleep: Some(false),
+                reachable: true,
+            });
+        }
+
+        let address = resolve_device_address(&printer).filter(|address| address.speaks_ipp());
+        let response = address.as_ref().map(|address| {
+            ipp::get_printer_attributes(
+                &address.uri(),
+                &["power-state-monitor"],
+                DEVICE_PROBE_TIMEOUT,
+            )
+        });
+        let status = match response {
+            Some(Ok(response)) => {
+                let power_state = current_power_state(&response);
+                PowerStatus {
+                    asleep: power_state.as_deref().and_then(crate::power::is_asleep),
+                    power_state,
+                    reachable: true,
+                }
+            }
+            _ => PowerStatus {
+                power_state: None,
+                asleep: None,
+                reachable: probe_printer(&printer),
+            },
+        };
+        Ok(status)
+    }
+}
+
+// ===== PRINTER SUPPLIES =====
+
+/// Printer attributes describing marker supplies (RFC 3805 / PWG 5100.13)
+const MARKER_ATTRIBUTES: &[&str] = &[
+    "marker-names",
+    "marker-types",
+    "marker-colors",
+    "marker-levels",
+    "marker-low-levels",
+    "marker-high-levels",
+];
+
+/// A consumable supply (toner, ink, drum, ...) reported by a printer
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrinterSupply {
+    pub name: String,
+    /// IPP marker type keyword, e.g. "toner" or "ink-cartridge"
+    pub supply_type: String,
+    /// Color as an sRGB hex string (e.g. "#00FFFF"), if reported
+    pub color: Option<String>,
+    /// Remaining level in percent, or None if the printer can't measure it
+    pub level: Option<u8>,
+    /// Level in percent at which the printer reports the supply as low
+    pub low_threshold: Option<u8>,
+}
+
+impl PrinterSupply {
+    /// Whether the supply is at or below its low threshold
+    pub fn is_low(&self) -> bool {
+        matches!((self.level, self.low_threshold), (Some(level), Some(low)) if level <= low)
+    }
+
+    /// Build the supply list from the parallel marker-* attributes of an IPP response
+    pub fn from_ipp_response(response: &ipp::IppResponse) -> Vec<PrinterSupply> {
+        let values = |name: &str| {
+            response
+                .attribute(ipp::TAG_PRINTER_ATTRIBUTES, name)
+                .map(|attribute| attribute.values.as_slice())
+                .unwrap_or(&[])
+        };
+
+        let names = values("marker-names");
+        let types = values("marker-types");
+        let colors = values("marker-colors");
+        let levels = values("marker-levels");
+        let low_levels = values("marker-low-levels");
+        let high_levels = values("marker-high-levels");
+
+        let count = names.len().max(levels.len());
+        (0..count)
+            .map(|index| {
+                let text = |list: &[ipp::IppValue]| {
+                    list.get(index)
+                        .and_then(ipp::IppValue::as_str)
+                        .map(str::to_string)
+                };
+                let number =
+                    |list: &[ipp::IppValue]| list.get(index).and_then(ipp::IppValue::as_i32);
+                let high = number(high_levels).filter(|high| *high > 0).unwrap_or(100);
+
+                PrinterSupply {
+                    name: text(names).unwrap_or_else(|| format!("Supply {}", index + 1)),
+                    supply_type: text(types).unwrap_or_else(|| "unknown".to_string()),
+                    color: text(colors).filter(|color| color.starts_with('#')),
+                    level: number(levels).and_then(|level| marker_percent(level, high)),
+                    low_threshold: number(low_levels).and_then(|low| marker_percent(low, high)),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Convert a marker level to percent. Negative levels mean "unavailable",
+/// "unknown" or "some remaining" and have no percentage.
+fn marker_percent(level: i32, high: i32) -> Option<u8> {
+    if level < 0 {
+        return None;
+    }
+    Some((level.min(high) * 100 / high) as u8)
+}
+
+/// Candidate IPP endpoints that may report marker levels for a printer
+fn supply_query_uris(printer: &Printer, device: Option<&DeviceAddress>) -> Vec<String> {
+    let mut uris = Vec::new();
+
+    // CUPS queues report the levels their backend collected
+    if let Some(address) = DeviceAddress::parse(&printer.uri) {
+        if address.speaks_ipp() {
+            uris.push(address.uri());
+        }
+    }
+
+    if let Some(address) = device {
+        if address.speaks_ipp() {
+            uris.push(address.uri());
+        } else {
+            // Raw-socket and LPD devices usually also run an IPP service
+            uris.push(format!("ipp://{}:631/ipp/print", address.host));
+        }
+    }
+
+    uris.dedup();
+    uris
+}
+
+impl PrinterCore {
+    /// Get supply levels for a printer via IPP marker attributes,
+    /// falling back to the SNMP Printer MIB when enabled
+    pub fn get_printer_supplies(printer_name: &str) -> Result<Vec<PrinterSupply>, PrintError> {
+        let printer =
+            Self::find_printer_by_name(printer_name).ok_or(PrintError::PrinterNotFound)?;
+
+        if should_simulate_printing() {
+            return Ok(vec![PrinterSupply {
+                name: "Black Toner".to_string(),
+                supply_type: "toner".to_string(),
+                color: Some("#000000".to_string()),
+                level: Some(80),
+                low_threshold: Some(10),
+            }]);
+        }
+
+        let device = resolve_device_address(&printer);
+        for uri in supply_query_uris(&printer, device.as_ref()) {
+            match ipp::get_printer_attributes(&uri, MARKER_ATTRIBUTES, DEVICE_PROBE_TIMEOUT) {
+                Ok(response) => {
+                    let supplies = PrinterSupply::from_ipp_response(&response);
+                    if !supplies.is_empty() {
+                        return Ok(supplies);
+                    }
+                }
+                Err(e) => tracing::debug!(uri = %uri, "IPP supply query failed: {}", e),
+            }
+        }
+
+        #[cfg(feature = "snmp")]
+        if let Some(address) = device.filter(|address| !address.is_local()) {
+            let options = crate::snmp::SnmpOptions {
+                timeout: DEVICE_PROBE_TIMEOUT,
+                ..Default::default()
+            };
+            match crate::snmp::query_supplies(&address.host, options) {
+                Ok(supplies) => return Ok(supplies),
+                Err(e) => tracing::debug!(host = %address.host, "SNMP supply query failed: {}", e),
+            }
+        }
+
+        Ok(Vec::new())
+    }
+}
+
+// ===== PJL =====
+
+impl PrinterCore {
+    /// Read a printer's control panel status and page count over PJL on
+    /// its raw printing channel
+    pub fn query_pjl_status(printer_name: &str) -> Result<crate::pjl::PjlStatus, String> {
+        let printer = Self::find_printer_by_name(printer_name)
+            .ok_or_else(|| format!("Printer '{}' not found", printer_name))?;
+
+        if should_simulate_printing() {
+            return Ok(crate::pjl::PjlStatus {
+                code: Some(10001),
+                display: Some("Ready".to_string()),
+                online: Some(true),
+                page_count: Some(0),
+            });
+        }
+
+        let address = resolve_device_address(&printer)
+            .filter(|address| !address.is_local())
+            .ok_or_else(|| format!("'{}' is not a network printer", printer_name))?;
+        let port = if address.scheme == "socket" {
+            address.port
+        } else {
+            crate::pjl::RAW_PORT
+        };
+        crate::pjl::query_status(&address.host, port, DEVICE_PROBE_TIMEOUT)
+    }
+}
+
+// ===== LABEL PRINTERS =====
+
+impl PrinterCore {
+    /// Get the command language of a label printer: the one given, ZPL if
+    /// the printer reports it, or ESC/POS if its driver's name says so
+    pub fn label_language(
+        printer_name: &str,
+        language: Option<crate::label::LabelLanguage>,
+    ) -> Result<crate::label::LabelLanguage, PrintError> {
+        let printer =
+            Self::find_printer_by_name(printer_name).ok_or(PrintError::PrinterNotFound)?;
+        if let Some(language) = language {
+            return Ok(language);
+        }
+        let formats = Self::get_supported_formats(&printer.name)?;
+        if formats.languages.contains(&PrinterLanguage::Zpl) {
+            return Ok(crate::label::LabelLanguage::Zpl);
+        }
+        crate::label::LabelLanguage::from_driver_name(&printer.driver_name).ok_or_else(|| {
+            PrintError::UnsupportedOption(format!(
+                "'{}' isn't a known label printer; pass its language (zpl or escpos)",
+                printer.name
+            ))
+        })
+    }
+
+    /// Make a label printer measure its labels again, e.g. after loading a
+    /// roll of a different size
+    pub fn calibrate_media(
+        printer_name: &str,
+        language: Option<crate::label::LabelLanguage>,
+    ) -> Result<JobId, PrintError> {
+        let language = Self::label_language(printer_name, language)?;
+        tracing::info!(printer = %printer_name, language = language.as_str(), "Calibrating media");
+        Self::send_label_command(
+            printer_name,
+            &crate::label::calibrate(language),
+            "Calibrate media",
+        )
+    }
+
+    /// Feed blank labels through a label printer
+    pub fn feed_labels(
+        printer_name: &str,
+        labels: u32,
+        language: Option<crate::label::LabelLanguage>,
+    ) -> Result<JobId, PrintError> {
+        let language = Self::label_language(printer_name, language)?;
+        let command =
+            crate::label::feed(language, labels).map_err(|_| PrintError::InvalidParams)?;
+        Self::send_label_command(printer_name, &command, "Feed labels")
+    }
+
+    fn send_label_command(
+        printer_name: &str,
+        command: &[u8],
+        job_name: &str,
+    ) -> Result<JobId, PrintError> {
+        let job_options = PrinterJobOptions::from_map(HashMap::from([
+            ("job-name".to_string(), job_name.to_string()),
+            (
+                "document-format".to_string(),
+                "application/vnd.cups-raw".to_string(),
+            ),
+        ]));
+        Self::print_bytes(printer_name, command, Some(job_options))
+    }
+
+    /// Read why a ZPL label printer stopped from its host status on its
+    /// raw printing channel
+    pub fn query_label_status(printer_name: &str) -> Result<crate::label::LabelStatus, String> {
+        let printer = Self::find_printer_by_name(printer_name)
+            .ok_or_else(|| format!("Printer '{}' not found", printer_name))?;
+
+        if should_simulate_printing() {
+            return Ok(crate::label::LabelStatus::default());
+        }
+
+        let address = resolve_device_address(&printer)
+            .filter(|address| !address.is_local())
+            .ok_or_else(|| format!("'{}' is not a network printer", printer_name))?;
+        let port = if address.scheme == "socket" {
+            address.port
+        } else {
+            crate::pjl::RAW_PORT
+        };
+        crate::label::query_status(&address.host, port, DEVICE_PROBE_TIMEOUT)
+    }
+}
+
+// ===== GHOSTSCRIPT =====
+
+#[cfg(feature = "ghostscript")]
+impl PrinterCore {
+    /// Convert a PDF or PostScript document to a printer format with the
+    /// configured Ghostscript, in the configured temp directory
+    pub fn rasterize_document(
+        data: &[u8],
+        options: &crate::ghostscript::RasterOptions,
+        on_progress: impl FnMut(crate::ghostscript::ConversionProgress),
+    ) -> Result<Vec<u8>, String> {
+        let config = CONFIG.read().unwrap().clone();
+        let binary = crate::ghostscript::binary(config.ghostscript_path.as_deref());
+        let work_dir = config.temp_dir.unwrap_or_else(env::temp_dir);
+        tracing::debug!(
+            binary = %binary.display(),
+            format = options.format.as_str(),
+            "Rasterizing document"
+        );
+        crate::ghostscript::rasterize(&binary, data, options, &work_dir, on_progress)
+    }
+
+    /// Render a page of a document, counting from 1, as a PNG image of how
+    /// it prints with a job's options: after pre-processing like layouts,
+    /// scaling and watermarks
+    pub fn render_preview(
+        data: &[u8],
+        job_options: &PrinterJobOptions,
+        page: u32,
+        resolution: Option<u32>,
+    ) -> Result<Vec<u8>, String> {
+        crate::preprocess::validate(job_options)?;
+        let document = crate::preprocess::preview_document(data, job_options)?;
+        let config = CONFIG.read().unwrap().clone();
+        let binary = crate::ghostscript::binary(config.ghostscript_path.as_deref());
+        let work_dir = config.temp_dir.unwrap_or_else(env::temp_dir);
+        let resolution = resolution.unwrap_or(crate::ghostscript::DEFAULT_PREVIEW_RESOLUTION);
+        tracing::debug!(page, resolution, "Rendering preview");
+        crate::ghostscript::render_png(&binary, &document, page, resolution, &work_dir)
+    }
+}
+
+// ===== PRINTER PREFERENCES =====
+
+use crate::spooler::PrinterPreferences;
+
+lazy_static::lazy_static! {
+    /// Preferences of simulated printers, so changes round-trip in tests
+    static ref SIMULATED_PREFERENCES: Mutex<HashMap<String, PrinterPreferences>> =
+        Mutex::new(HashMap::new());
+}
+
+fn simulated_default_preferences() -> PrinterPreferences {
+    PrinterPreferences {
+        orientation: Some(crate::spooler::Orientation::Portrait),
+        paper_size: Some(1),
+        form_name: Some("Letter".to_string()),
+        quality: Some(crate::spooler::PrintQuality::Dpi(600)),
+        color: Some(true),
+        duplex: Some(crate::spooler::Duplex::Simplex),
+    }
+}
+
+impl PrinterCore {
+    /// Get the current user's default preferences (DEVMODE) for a printer.
+    /// Only supported on Windows.
+    pub fn get_printer_preferences(printer_name: &str) -> Result<PrinterPreferences, String> {
+        if !Self::printer_exists(printer_name) {
+            return Err(format!("Printer '{}' not found", printer_name));
+        }
+
+        if should_simulate_printing() {
+            let preferences = SIMULATED_PREFERENCES.lock().unwrap();
+            return Ok(preferences
+                .get(printer_name)
+                .cloned()
+                .unwrap_or_else(simulated_default_preferences));
+        }
+
+        crate::spooler::get_preferences(printer_name)
+    }
+
+    /// Change the current user's default preferences for a printer, keeping
+    /// fields that aren't set. Returns the preferences the driver accepted.
+    pub fn set_printer_preferences(
+        printer_name: &str,
+        preferences: &PrinterPreferences,
+    ) -> Result<PrinterPreferences, String> {
+        if !Self::printer_exists(printer_name) {
+            return Err(format!("Printer '{}' not found", printer_name));
+        }
+
+        if should_simulate_printing() {
+            let mut simulated = SIMULATED_PREFERENCES.lock().unwrap();
+            let current = simulated
+                .entry(printer_name.to_string())
+                .or_insert_with(simulated_default_preferences);
+            current.merge(preferences);
+            return Ok(current.clone());
+        }
+
+        tracing::info!(printer = printer_name, "Updating printer preferences");
+        crate::spooler::set_preferences(printer_name, preferences)
+    }
+}
+
+// ===== PRINTER INSTALLATION =====
+
+use crate::spooler::{PrinterDriver, PrinterInstallation};
+
+lazy_static::lazy_static! {
+    /// Printers installed in simulation mode, listed after "Simulated Printer"
+    static ref SIMULATED_INSTALLS: Mutex<Vec<Printer>> = Mutex::new(Vec::new());
+}
+
+/// Drivers reported in simulation mode
+fn simulated_drivers() -> Vec<PrinterDriver> {
+    ["Generic / Text Only", "Microsoft Print To PDF"]
+        .into_iter()
+        .map(|name| PrinterDriver {
+            name: name.to_string(),
+            environment: "Windows x64".to_string(),
+            version: 3,
+            driver_path: String::new(),
+        })
+        .collect()
+}
+
+fn simulated_installed_printer(installation: &PrinterInstallation) -> Printer {
+    Printer {
+        name: installation.name.clone(),
+        system_name: installation.name.clone(),
+        driver_name: installation.driver.clone(),
+        uri: String::new(),
+        location: installation.location.clone().unwrap_or_default(),
+        description: installation.comment.clone().unwrap_or_default(),
+        port_name: installation.port.clone(),
+        processor: "winprint".to_string(),
+        data_type: "RAW".to_string(),
+        is_shared: false,
+        is_default: false,
+        state: printers::common::base::printer::PrinterState::READY,
+        state_reasons: Vec::new(),
+    }
+}
+
+impl PrinterCore {
+    /// Install a print queue using an installed driver and an existing port.
+    /// Only supported on Windows.
+    pub fn install_printer(installation: &PrinterInstallation) -> Result<(), String> {
+        installation.validate()?;
+        if Self::printer_exists(&installation.name) {
+            return Err(format!("Printer '{}' already exists", installation.name));
+        }
+
+        if should_simulate_printing() {
+            if !simulated_drivers()
+                .iter()
+                .any(|driver| driver.name == installation.driver)
+            {
+                return Err(format!(
+                    "Printer driver '{}' is not installed",
+                    installation.driver
+                ));
+            }
+            SIMULATED_INSTALLS
+                .lock()
+                .unwrap()
+                .push(simulated_installed_printer(installation));
+            return Ok(());
+        }
+
+        tracing::info!(
+            printer = %installation.name,
+            port = %installation.port,
+            driver = %installation.driver,
+            "Installing printer"
+        );
+        crate::spooler::install_printer(installation).inspect(|()| invalidate_printer_cache())
+    }
+
+    /// Remove a print queue. Only supported on Windows.
+    pub fn remove_printer(printer_name: &str) -> Result<(), String> {
+        if crate::remote::get(printer_name).is_some() {
+            return Err(format!(
+                "'{}' is a remote printer; remove it with removeRemotePrinter",
+                printer_name
+            ));
+        }
+        if !Self::printer_exists(printer_name) {
+            return Err(format!("Printer '{}' not found", printer_name));
+        }
+
+        if should_simulate_printing() {
+            let mut installs = SIMULATED_INSTALLS.lock().unwrap();
+            let before = installs.len();
+            installs.retain(|printer| printer.name != printer_name);
+            if installs.len() == before {
+                return Err(format!("Printer '{}' can't be removed", printer_name));
+            }
+            drop(installs);
+            fail_removed_printer_jobs(&JOB_TRACKER, printer_name);
+            return Ok(());
+        }
+
+        tracing::info!(printer = printer_name, "Removing printer");
+        crate::spooler::remove_printer(printer_name).inspect(|()| {
+            invalidate_printer_cache();
+            fail_removed_printer_jobs(&JOB_TRACKER, printer_name);
+        })
+    }
+
+    /// List the installed printer drivers. Only supported on Windows.
+    pub fn list_drivers() -> Result<Vec<PrinterDriver>, String> {
+        if should_simulate_printing() {
+            return Ok(simulated_drivers());
+        }
+        crate::spooler::list_drivers()
+    }
+}
+
+// ===== CUPS QUEUE ADMINISTRATION =====
+
+use crate::spooler::CupsQueue;
+
+/// Run a spooler admin operation. CUPS authenticates it with the queue's
+/// credentials (or the default ones), which must be those of a CUPS
+/// administrator, e.g. a member of the lpadmin group; Windows uses the
+/// process's account.
+#[cfg_attr(not(unix), allow(unused_variables))]
+fn as_printer_admin(
+    queue_name: &str,
+    operation: impl FnOnce() -> Result<(), String>,
+) -> Result<(), String> {
+    #[cfg(unix)]
+    {
+        let credentials = crate::credentials::for_printer(queue_name);
+        if let Some(credentials) = &credentials {
+            crate::spooler::set_thread_credentials(credentials)?;
+        }
+        operation().map_err(|e| explain_authentication_failure(queue_name, credentials.as_ref(), e))
+    }
+    #[cfg(not(unix))]
+    operation()
+}
+
+impl PrinterCore {
+    /// Add a queue to the local CUPS server, or modify the queue with that
+    /// name. Only supported on Unix.
+    pub fn add_cups_queue(queue: &CupsQueue) -> Result<(), String> {
+        queue.validate()?;
+
+        if should_simulate_printing() {
+            let mut installs = SIMULATED_INSTALLS.lock().unwrap();
+            installs.retain(|printer| printer.name != queue.name);
+            installs.push(Printer {
+                name: queue.name.clone(),
+                system_name: queue.name.clone(),
+                driver_name: queue.model.clone().unwrap_or_default(),
+                uri: queue.device_uri.clone(),
+                location: queue.location.clone().unwrap_or_default(),
+                description: queue.description.clone().unwrap_or_default(),
+                port_name: String::new(),
+                processor: String::new(),
+                data_type: String::new(),
+                is_shared: false,
+                is_default: false,
+                state: if queue.enabled {
+                    printers::common::base::printer::PrinterState::READY
+                } else {
+                    printers::common::base::printer::PrinterState::PAUSED
+                },
+                state_reasons: Vec::new(),
+            });
+            return Ok(());
+        }
+
+        tracing::info!(queue = %queue.name, device_uri = %queue.device_uri, "Adding CUPS queue");
+        as_printer_admin(&queue.name, || crate::spooler::add_queue(queue))
+            .inspect(|()| invalidate_printer_cache())
+    }
+
+    /// Delete a queue from the local CUPS server, cancelling its jobs. Only
+    /// supported on Unix.
+    pub fn delete_cups_queue(queue_name: &str) -> Result<(), String> {
+        crate::spooler::validate_cups_queue_name(queue_name)?;
+        if !Self::printer_exists(queue_name) {
+            return Err(format!("Printer '{}' not found", queue_name));
+        }
+
+        if should_simulate_printing() {
+            return Self::remove_printer(queue_name);
+        }
+
+        tracing::info!(queue = queue_name, "Deleting CUPS queue");
+        as_printer_admin(queue_name, || crate::spooler::delete_queue(queue_name)).inspect(|()| {
+            invalidate_printer_cache();
+            fail_removed_printer_jobs(&JOB_TRACKER, queue_name);
+        })
+    }
+
+    /// Enable a CUPS queue (accept jobs and print them) or disable it (stop
+    /// printing; jobs wait in the queue). Only supported on Unix.
+    pub fn set_queue_enabled(queue_name: &str, enabled: bool) -> Result<(), String> {
+        crate::spooler::validate_cups_queue_name(queue_name)?;
+        if !Self::printer_exists(queue_name) {
+            return Err(format!("Printer '{}' not found", queue_name));
+        }
+
+        if should_simulate_printing() {
+            let mut installs = SIMULATED_INSTALLS.lock().unwrap();
+            let printer = installs
+                .iter_mut()
+                .find(|printer| printer.name == queue_name)
+                .ok_or_else(|| format!("Printer '{}' can't be changed", queue_name))?;
+            printer.state = if enabled {
+                printers::common::base::printer::PrinterState::READY
+            } else {
+                printers::common::base::printer::PrinterState::PAUSED
+            };
+            return Ok(());
+        }
+
+        tracing::info!(queue = queue_name, enabled, "Changing CUPS queue state");
+        as_printer_admin(queue_name, || {
+            crate::spooler::set_queue_enabled(queue_name, enabled)
+        })
+        .inspect(|()| invalidate_printer_cache())
+    }
+}
+
+// ===== PRINTER ACCESS CONTROL =====
+
+use crate::spooler::AccessControl;
+
+lazy_static::lazy_static! {
+    /// Access control of simulated printers, so changes round-trip in tests
+    static ref SIMULATED_ACCESS: Mutex<HashMap<String, AccessControl>> = Mutex::new(HashMap::new());
+}
+
+fn simulated_default_access() -> AccessControl {
+    AccessControl {
+        shared: Some(false),
+        allowed_users: Some(Vec::new()),
+        denied_users: Some(Vec::new()),
+    }
+}
+
+impl PrinterCore {
+    /// Get whether a printer is shared and who may print to it
+    pub fn get_access_control(printer_name: &str) -> Result<AccessControl, String> {
+        let printer = Self::find_printer_by_name(printer_name)
+            .ok_or_else(|| format!("Printer '{}' not found", printer_name))?;
+
+        if should_simulate_printing() {
+            return Ok(SIMULATED_ACCESS
+                .lock()
+                .unwrap()
+                .get(&printer.name)
+                .cloned()
+                .unwrap_or_else(simulated_default_access));
+        }
+
+        crate::spooler::get_access_control(&printer.system_name)
+    }
+
+    /// Share or unshare a printer and change who may print to it, keeping
+    /// fields that aren't set. Returns the resulting access control.
+    pub fn set_access_control(
+        printer_name: &str,
+        update: &AccessControl,
+    ) -> Result<AccessControl, String> {
+        update.validate()?;
+        let printer = Self::find_printer_by_name(printer_name)
+            .ok_or_else(|| format!("Printer '{}' not found", printer_name))?;
+
+        if should_simulate_printing() {
+            let mut simulated = SIMULATED_ACCESS.lock().unwrap();
+            let current = simulated
+                .entry(printer.name.clone())
+                .or_insert_with(simulated_default_access);
+            current.merge(update);
+            return Ok(current.clone());
+        }
+
+        // Only rewrite the user lists if they change, as spoolers replace
+        // them wholesale
+        let mut access = Self::get_access_control(&printer.name)?;
+        access.merge(update);
+        if update.allowed_users.is_none() && update.denied_users.is_none() {
+            access.allowed_users = None;
+            access.denied_users = None;
+        }
+        access.shared = update.shared;
+
+        tracing::info!(printer = %printer.name, ?access, "Updating printer access control");
+        as_printer_admin(&printer.system_name, || {
+            crate::spooler::set_access_control(&printer.system_name, &access)
+        })?;
+        Self::get_access_control(&printer.name)
+    }
+}
+
+// ===== DRY RUN =====
+
+/// Printer attributes listing the values a printer supports for the job
+/// attributes a dry run checks, and the color mode it defaults to
+const CAPABILITY_ATTRIBUTES: &[&str] = &[
+    "copies-supported",
+    "sides-supported",
+    "print-color-mode-supported",
+    "print-quality-supported",
+    "printer-resolution-supported",
+    "orientation-requested-supported",
+    "number-up-supported",
+    "output-bin-supported",
+    "finishings-supported",
+    "document-format-supported",
+    "print-color-mode-default",
+    "job-creation-attributes-supported",
+];
+
+/// Options jobs may set besides the job template attributes a printer
+/// lists: operation attributes, and the CUPS options simple options use
+const COMMON_OPTIONS: &[&str] = &[
+    "compression",
+    "document-format",
+    "document-name",
+    "job-name",
+    "landscape",
+    "media-size",
+    "requesting-user-name",
+];
+
+/// Values a printer supports for job attributes
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PrinterCapabilities {
+    /// Supported keyword, enum and integer values by job attribute name
+    pub supported: HashMap<String, Vec<String>>,
+    /// Supported range of copies
+    pub copies: Option<(i32, i32)>,
+    /// Color mode jobs print in unless they set one
+    pub default_color_mode: Option<String>,
+    /// Job template attributes the printer accepts when creating a job
+    pub job_attributes: Vec<String>,
+}
+
+impl PrinterCapabilities {
+    /// Build the capabilities from the `*-supported` attributes of an IPP
+    /// response
+    pub fn from_ipp_response(response: &ipp::IppResponse) -> PrinterCapabilities {
+        let mut capabilities = PrinterCapabilities::default();
+        for name in CAPABILITY_ATTRIBUTES {
+            let Some(attribute) = response.attribute(ipp::TAG_PRINTER_ATTRIBUTES, name) else {
+                continue;
+            };
+            match *name {
+                "copies-supported" => {
+                    if let Some(ipp::IppValue::RangeOfInteger { lower, upper }) = attribute.value()
+                    {
+                        capabilities.copies = Some((*lower, *upper));
+                    }
+                    continue;
+                }
+                "print-color-mode-default" => {
+                    capabilities.default_color_mode = attribute
+                        .value()
+                        .and_then(|v| v.as_str())
+                        .map(str::to_string);
+                    continue;
+                }
+                "job-creation-attributes-supported" => {
+                    capabilities.job_attributes = attribute
+                        .values
+                        .iter()
+                        .filter_map(|value| value.as_str())
+                        .map(str::to_string)
+                        .collect();
+                    continue;
+                }
+                _ => {}
+            }
+            let values = attribute
+                .values
+                .iter()
+                .filter_map(|value| match value {
+                    ipp::IppValue::Resolution { .. } => Some(value.to_string()),
+                    _ => value
+                        .as_str()
+                        .map(str::to_string)
+                        .or_else(|| value.as_i32().map(|v| v.to_string())),
+                })
+                .collect();
+            let job_attribute = name.trim_end_matches("-supported");
+            capabilities
+                .supported
+                .insert(job_attribute.to_string(), values);
+        }
+        capabilities
+    }
+
+    /// The `print-color-mode` keyword the printer supports for a color
+    /// mode, or None if it doesn't report one
+    pub fn color_mode_keyword(&self, mode: crate::preprocess::ColorMode) -> Option<&'static str> {
+        let supported = self.supported.get("print-color-mode")?;
+        mode.keywords()
+            .iter()
+            .copied()
+            .find(|keyword| supported.iter().any(|s| s.eq_ignore_ascii_case(keyword)))
+    }
+
+    /// Keywords of the finishings the printer reports, e.g.
+    /// `staple-top-left`
+    pub fn finishings(&self) -> Vec<&'static str> {
+        self.supported
+            .get("finishings")
+            .map(|values| {
+                values
+                    .iter()
+                    .filter_map(|value| value.parse().ok())
+                    .filter_map(crate::finishing::name)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Whether the printer reported anything to check against
+    pub fn is_empty(&self) -> bool {
+        self.supported.is_empty()
+            && self.copies.is_none()
+            && self.default_color_mode.is_none()
+            && self.job_attributes.is_empty()
+    }
+
+    /// Raw properties a job may set: the attributes the printer lists
+    /// values or accepts for, and common options, sorted
+    pub fn supported_options(&self) -> Vec<String> {
+        let mut options: Vec<String> = self
+            .supported
+            .keys()
+            .chain(&self.job_attributes)
+            .cloned()
+            .chain(COMMON_OPTIONS.iter().map(|name| name.to_string()))
+            .collect();
+        if self.copies.is_some() {
+            options.push("copies".to_string());
+        }
+        options.sort();
+        options.dedup();
+        options
+    }
+
+    /// Raw properties that aren't among the supported options, sorted
+    pub fn unknown_options(&self, raw_properties: &HashMap<String, String>) -> Vec<String> {
+        let supported = self.supported_options();
+        let mut unknown: Vec<String> = raw_properties
+            .keys()
+            .filter(|key| supported.binary_search(key).is_err())
+            .cloned()
+            .collect();
+        unknown.sort();
+        unknown
+    }
+
+    /// Job options the printer doesn't support, as `name=value` followed by
+    /// the values it does
+    pub fn unsupported_options(
+        &self,
+        raw_properties: &HashMap<String, String>,
+        media_type: &str,
+    ) -> Vec<String> {
+        let mut unsupported = Vec::new();
+        if let (Some((lower, upper)), Some(copies)) = (self.copies, raw_properties.get("copies")) {
+            let supported = copies
+                .trim()
+                .parse::<i32>()
+                .is_ok_and(|copies| (lower..=upper).contains(&copies));
+            if !supported {
+                unsupported.push(format!(
+                    "copies={} (supported: {}-{})",
+                    copies, lower, upper
+                ));
+            }
+        }
+
+        let mut names: Vec<&String> = self.supported.keys().collect();
+        names.sort();
+        for name in names {
+            let value = match name.as_str() {
+                "document-format" => media_type,
+                _ => match raw_properties.get(name) {
+                    Some(value) => value.as_str(),
+                    None => continue,
+                },
+            };
+            let supported = &self.supported[name];
+            let matches = |value: &str| {
+                supported.iter().any(|s| {
+                    s.eq_ignore_ascii_case(value.trim())
+                        || (name == "printer-resolution"
+                            && resolution_dpi(s).is_some()
+                            && resolution_dpi(s) == resolution_dpi(value))
+                })
+            };
+            // Jobs can ask for several finishings at once
+            let supported_value = match name.as_str() {
+                "finishings" => value.split(',').all(matches),
+                _ => matches(value),
+            };
+            if !supported_value {
+                unsupported.push(format!(
+                    "{}={} (supported: {})",
+                    name,
+                    value,
+                    supported.join(", ")
+                ));
+            }
+        }
+        unsupported
+    }
+}
+
+/// What a submission would do, worked out without spooling anything
+#[derive(Clone, Debug)]
+pub struct DryRunResult {
+    /// Printer the job would go to, after resolving aliases
+    pub printer_name: String,
+    /// Options after applying alias and library-wide defaults
+    pub job_options: PrinterJobOptions,
+    pub media_type: String,
+    pub media_type_source: MediaTypeSource,
+    /// Size of the document, if it could be read
+    pub size_bytes: Option<u64>,
+    /// Pages in the document, if known
+    pub document_pages: Option<u32>,
+    /// Estimated pages and sheets printed, including copies
+    pub pages: Option<u32>,
+    pub sheets: Option<u32>,
+    /// Capabilities the options were checked against, if the printer
+    /// reported any
+    pub capabilities: Option<PrinterCapabilities>,
+    /// Options the printer doesn't support
+    pub unsupported_options: Vec<String>,
+}
+
+/// Where a resolved job option came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionSource {
+    Library, // Library-wide defaultJobOptions
+    Printer, // Default options of the printer
+    Alias,   // Default options of the alias the job was submitted through
+    Call,    // The job's own options
+    Derived, // Set from a typed option, e.g. print-quality for quality
+}
+
+impl OptionSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OptionSource::Library => "library",
+            OptionSource::Printer => "printer",
+            OptionSource::Alias => "alias",
+            OptionSource::Call => "call",
+            OptionSource::Derived => "derived",
+        }
+    }
+}
+
+/// A job's options after applying every layer of defaults
+#[derive(Clone, Debug)]
+pub struct ResolvedJobOptions {
+    /// Printer the job would go to, after resolving aliases
+    pub printer_name: String,
+    pub job_options: PrinterJobOptions,
+    /// Where each option came from, by the keys of `job_options.to_map()`
+    pub sources: HashMap<String, OptionSource>,
+}
+
+impl PrinterCore {
+    /// Get the values a printer supports for common job attributes via IPP,
+    /// or None if it doesn't report them (e.g. Windows printers)
+    pub fn get_printer_capabilities(
+        printer_name: &str,
+    ) -> Result<Option<PrinterCapabilities>, PrintError> {
+        let printer =
+            Self::find_printer_by_name(printer_name).ok_or(PrintError::PrinterNotFound)?;
+
+        if should_simulate_printing() {
+            let values = |values: &[&str]| values.iter().map(|v| v.to_string()).collect();
+            return Ok(Some(PrinterCapabilities {
+                supported: HashMap::from([
+                    (
+                        "sides".to_string(),
+                        values(&["one-sided", "two-sided-long-edge", "two-sided-short-edge"]),
+                    ),
+                    (
+                        "print-color-mode".to_string(),
+                        values(&["monochrome", "color"]),
+                    ),
+                    ("print-quality".to_string(), values(&["3", "4", "5"])),
+                    (
+                        "finishings".to_string(),
+                        values(&["3", "4", "20", "74", "93"]),
+                    ),
+                    (
+                        "output-bin".to_string(),
+                        values(&["face-down", "mailbox-1"]),
+                    ),
+                    (
+                        "printer-resolution".to_string(),
+                        values(&["300dpi", "600dpi"]),
+                    ),
+                ]),
+                copies: Some((1, 999)),
+                default_color_mode: Some("color".to_string()),
+                job_attributes: values(&[
+                    "copies",
+                    "media",
+                    "media-col",
+                    "number-up",
+                    "orientation-requested",
+                    "page-ranges",
+                    "print-scaling",
+                ]),
+            }));
+        }
+
+        let device = resolve_device_address(&printer);
+        for uri in supply_query_uris(&printer, device.as_ref()) {
+            match ipp::get_printer_attributes(&uri, CAPABILITY_ATTRIBUTES, DEVICE_PROBE_TIMEOUT) {
+                Ok(response) => {
+                    let capabilities = PrinterCapabilities::from_ipp_response(&response);
+                    if !capabilities.is_empty() {
+                        return Ok(Some(capabilities));
+                    }
+                }
+                Err(e) => tracing::debug!(uri = %uri, "IPP capability query failed: {}", e),
+            }
+        }
+        Ok(None)
+    }
+
+    /// Check a file submission the way print_file would (the printer, the
+    /// file, the options and the document, which is always validated) and
+    /// check the options against the printer's capabilities, without
+    /// spooling or tracking a job
+    pub fn dry_run_file(
+        printer_name: &str,
+        file_path: &str,
+        job_options: Option<PrinterJobOptions>,
+    ) -> Result<DryRunResult, PrintError> {
+        let (printer_name, job_options) = Self::dry_run_target(printer_name, job_options)?;
+        check_file_exists(file_path)?;
+        let data = match std::fs::read(file_path) {
+            Ok(data) => Some(data),
+            Err(_) if should_simulate_printing() => None,
+            Err(_) => return Err(PrintError::FileNotFound),
+        };
+        let (media_type, media_type_source) = match document_format(&job_options.raw_properties)? {
+            Some(format) => (format, MediaTypeSource::Option),
+            None => detect_file_media_type(file_path, data.as_deref()),
+        };
+        Self::dry_run_document(
+            printer_name,
+            job_options,
+            media_type,
+            media_type_source,
+            data.as_deref(),
+        )
+    }
+
+    /// Check a raw bytes submission the way print_bytes would, without
+    /// spooling or tracking a job
+    pub fn dry_run_bytes(
+        printer_name: &str,
+        data: &[u8],
+        job_options: Option<PrinterJobOptions>,
+    ) -> Result<DryRunResult, PrintError> {
+        let (printer_name, job_options) = Self::dry_run_target(printer_name, job_options)?;
+        let (media_type, media_type_source) = match document_format(&job_options.raw_properties)? {
+            Some(format) => (format, MediaTypeSource::Option),
+            None => detect_bytes_media_type(data),
+        };
+        Self::dry_run_document(
+            printer_name,
+            job_options,
+            media_type,
+            media_type_source,
+            Some(data),
+        )
+    }
+
+    /// Resolve the options a job submitted to a printer or alias would be
+    /// sent with. Options are taken from, in order of precedence: the call,
+    /// the alias, the printer's defaults and the library-wide defaults.
+    /// Typed options are then checked against the printer's capabilities,
+    /// which may set the raw properties they stand for.
+    pub fn resolve_job_options(
+        printer_name: &str,
+        job_options: Option<PrinterJobOptions>,
+    ) -> Result<ResolvedJobOptions, PrintError> {
+        let alias = crate::aliases::get(printer_name);
+        let printer_name = alias
+            .as_ref()
+            .map_or(printer_name, |alias| alias.printer.as_str());
+        Self::find_printer_by_name(printer_name).ok_or(PrintError::PrinterNotFound)?;
+
+        // Compare options in the form to_map() writes them in
+        let normalize =
+            |options: HashMap<String, String>| PrinterJobOptions::from_map(options).to_map();
+        let layers = [
+            (
+                OptionSource::Call,
+                job_options
+                    .as_ref()
+                    .map(PrinterJobOptions::to_map)
+                    .unwrap_or_default(),
+            ),
+            (
+                OptionSource::Alias,
+                alias
+                    .as_ref()
+                    .map(|alias| normalize(alias.default_options.clone()))
+                    .unwrap_or_default(),
+            ),
+            (
+                OptionSource::Printer,
+                normalize(crate::aliases::printer_defaults(printer_name)),
+            ),
+            (
+                OptionSource::Library,
+                normalize(CONFIG.read().unwrap().default_job_options.clone()),
+            ),
+        ];
+
+        let mut job_options = prepare_job_options(job_options, Some(printer_name), alias.as_ref())?;
+        check_printer_options(printer_name, &mut job_options)?;
+        let sources = job_options
+            .to_map()
+            .into_iter()
+            .map(|(key, value)| {
+                let source = layers
+                    .iter()
+                    .find(|(_, options)| options.get(&key) == Some(&value))
+                    .map_or(OptionSource::Derived, |(source, _)| *source);
+                (key, source)
+            })
+            .collect();
+        Ok(ResolvedJobOptions {
+            printer_name: printer_name.to_string(),
+            job_options,
+            sources,
+        })
+    }
+
+    /// Printer a dry run's job would go to and its resolved options
+    fn dry_run_target(
+        printer_name: &str,
+        job_options: Option<PrinterJobOptions>,
+    ) -> Result<(String, PrinterJobOptions), PrintError> {
+        if !ACCEPTING_JOBS.load(Ordering::Relaxed) {
+            return Err(PrintError::ShuttingDown);
+        }
+        let alias = crate::aliases::get(printer_name);
+        let printer_name = alias
+            .as_ref()
+            .map_or(printer_name, |alias| alias.printer.as_str());
+        Self::find_printer_by_name(printer_name).ok_or(PrintError::PrinterNotFound)?;
+        let mut job_options = prepare_job_options(job_options, Some(printer_name), alias.as_ref())?;
+        check_printer_options(printer_name, &mut job_options)?;
+        Ok((printer_name.to_string(), job_options))
+    }
+
+    /// Validate a dry run's document and estimate its usage
+    fn dry_run_document(
+        printer_name: String,
+        job_options: PrinterJobOptions,
+        mut media_type: String,
+        mut media_type_source: MediaTypeSource,
+        data: Option<&[u8]>,
+    ) -> Result<DryRunResult, PrintError> {
+        if let Some(data) = data {
+            check_document(data, &media_type)?;
+        }
+        check_document_policy(&printer_name, &media_type, data)?;
+        let processed = match data {
+            Some(data) => {
+                preprocess_document(data, &job_options, &mut media_type, &mut media_type_source)?
+            }
+            None => None,
+        };
+        let data = processed.as_deref().or(data);
+        let document_pages = data.and_then(crate::document::pdf_page_count);
+        let (pages, sheets) = job_usage(document_pages, &job_options.raw_properties);
+        check_job_policy(&printer_name, &job_options, document_pages)?;
+        let capabilities = Self::get_printer_capabilities(&printer_name)?;
+        let unsupported_options = capabilities.as_ref().map_or_else(Vec::new, |capabilities| {
+            capabilities.unsupported_options(&job_options.raw_properties, &media_type)
+        });
+        Ok(DryRunResult {
+            printer_name,
+            job_options,
+            media_type,
+            media_type_source,
+            size_bytes: data.map(|data| data.len() as u64),
+            document_pages,
+            pages,
+            sheets,
+            capabilities,
+            unsupported_options,
+        })
+    }
+}
+
+// ===== PRINTER LANGUAGES =====
+
+/// A page description language a printer interprets
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PrinterLanguage {
+    Pdf,
+    PostScript,
+    Pcl,
+    Zpl,
+}
+
+impl PrinterLanguage {
+    /// Languages in order of preference for sending documents: PDF and
+    /// PostScript print most documents unconverted
+    pub const ALL: [PrinterLanguage; 4] = [
+        PrinterLanguage::Pdf,
+        PrinterLanguage::PostScript,
+        PrinterLanguage::Pcl,
+        PrinterLanguage::Zpl,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PrinterLanguage::Pdf => "pdf",
+            PrinterLanguage::PostScript => "postscript",
+            PrinterLanguage::Pcl => "pcl",
+            PrinterLanguage::Zpl => "zpl",
+        }
+    }
+
+    /// Media type to submit documents in this language as
+    pub fn media_type(&self) -> &'static str {
+        match self {
+            PrinterLanguage::Pdf => "application/pdf",
+            PrinterLanguage::PostScript => "application/postscript",
+            PrinterLanguage::Pcl => "application/vnd.hp-pcl",
+            PrinterLanguage::Zpl => "application/vnd.zebra-zpl",
+        }
+    }
+
+    /// Language of a `document-format-supported` value, if it is one
+    pub fn from_media_type(media_type: &str) -> Option<PrinterLanguage> {
+        let media_type = media_type.trim().to_lowercase();
+        match media_type.as_str() {
+            "application/pdf" | "application/vnd.cups-pdf" => Some(PrinterLanguage::Pdf),
+            "application/postscript" | "application/vnd.cups-postscript" => {
+                Some(PrinterLanguage::PostScript)
+            }
+            _ if media_type.contains("pcl") => Some(PrinterLanguage::Pcl),
+            _ if media_type.contains("zpl") => Some(PrinterLanguage::Zpl),
+            _ => None,
+        }
+    }
+
+    /// Languages a driver's name says it produces, e.g. "HP LaserJet PCL 6"
+    /// or "Brother BR-Script3"
+    pub fn from_driver_name(driver_name: &str) -> Vec<PrinterLanguage> {
+        let name = driver_name.to_lowercase();
+        let words: Vec<&str> = name
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect();
+        let has_word = |prefixes: &[&str]| {
+            words
+                .iter()
+                .any(|word| prefixes.iter().any(|prefix| word.starts_with(prefix)))
+        };
+        PrinterLanguage::ALL
+            .into_iter()
+            .filter(|language| match language {
+                PrinterLanguage::Pdf => has_word(&["pdf"]),
+                PrinterLanguage::PostScript => {
+                    has_word(&["postscript", "ps2", "ps3"])
+                        || words.contains(&"ps")
+                        || name.contains("br-script")
+                }
+                PrinterLanguage::Pcl => has_word(&["pcl"]),
+                PrinterLanguage::Zpl => has_word(&["zpl", "zebra"]),
+            })
+            .collect()
+    }
+}
+
+/// Where a printer's languages were learnt from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FormatSource {
+    Ipp,       // The printer's document-format-supported
+    Driver,    // The driver's name
+    Simulated, // Simulation mode
+    Unknown,   // Neither reported a language
+}
+
+impl FormatSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FormatSource::Ipp => "ipp",
+            FormatSource::Driver => "driver",
+            FormatSource::Simulated => "simulated",
+            FormatSource::Unknown => "unknown",
+        }
+    }
+}
+
+/// Document formats and languages a printer accepts
+#[derive(Clone, Debug, PartialEq)]
+pub struct SupportedFormats {
+    /// Languages the printer interprets, most preferred first
+    pub languages: Vec<PrinterLanguage>,
+    /// Media types the printer lists, if it reports them over IPP
+    pub document_formats: Vec<String>,
+    pub source: FormatSource,
+}
+
+impl SupportedFormats {
+    /// Build from a printer's `document-format-supported` values
+    pub fn from_document_formats(document_formats: Vec<String>) -> SupportedFormats {
+        let languages = PrinterLanguage::ALL
+            .into_iter()
+            .filter(|language| {
+                document_formats
+                    .iter()
+                    .any(|format| PrinterLanguage::from_media_type(format) == Some(*language))
+            })
+            .collect();
+        SupportedFormats {
+            languages,
+            document_formats,
+            source: FormatSource::Ipp,
+        }
+    }
+
+    /// Language to send documents in, if the printer interprets any
+    pub fn preferred(&self) -> Option<PrinterLanguage> {
+        self.languages.first().copied()
+    }
+
+    /// Whether the printer takes documents of a media type as they are.
+    /// Printers that only report languages are taken to accept their
+    /// languages' media types.
+    pub fn accepts(&self, media_type: &str) -> bool {
+        if !self.document_formats.is_empty() {
+            return self
+                .document_formats
+                .iter()
+                .any(|format| format.eq_ignore_ascii_case(media_type.trim()));
+        }
+        PrinterLanguage::from_media_type(media_type)
+            .is_some_and(|language| self.languages.contains(&language))
+    }
+}
+
+impl PrinterCore {
+    /// Get the languages a printer interprets, from the document formats it
+    /// lists over IPP or, for printers that don't report them, from the name
+    /// of its driver
+    pub fn get_supported_formats(printer_name: &str) -> Result<SupportedFormats, PrintError> {
+        let printer =
+            Self::find_printer_by_name(printer_name).ok_or(PrintError::PrinterNotFound)?;
+
+        if should_simulate_printing() {
+            let mut formats = SupportedFormats::from_document_formats(
+                ["application/pdf", "application/postscript", "image/jpeg"]
+                    .iter()
+                    .map(|format| format.to_string())
+                    .collect(),
+            );
+            formats.source = FormatSource::Simulated;
+            return Ok(formats);
+        }
+
+        let document_formats = Self::get_printer_capabilities(&printer.name)?
+            .and_then(|mut capabilities| capabilities.supported.remove("document-format"))
+            .unwrap_or_default();
+        if !document_formats.is_empty() {
+            return Ok(SupportedFormats::from_document_formats(document_formats));
+        }
+        let languages = PrinterLanguage::from_driver_name(&printer.driver_name);
+        let source = if languages.is_empty() {
+            FormatSource::Unknown
+        } else {
+            FormatSource::Driver
+        };
+        Ok(SupportedFormats {
+            languages,
+            document_formats: Vec::new(),
+            source,
+        })
+    }
+}
+
+// ===== JOB ESTIMATES =====
+
+/// Whether a job prints in color
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JobColor {
+    Color,
+    Monochrome,
+}
+
+impl JobColor {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobColor::Color => "color",
+            JobColor::Monochrome => "monochrome",
+        }
+    }
+
+    /// Color an IPP `print-color-mode` keyword or CUPS `ColorModel` value
+    /// selects
+    fn from_mode(mode: &str) -> JobColor {
+        let mode = mode.to_ascii_lowercase();
+        let monochrome = mode.contains("monochrome")
+            || matches!(
+                mode.as_str(),
+                "bi-level" | "process-bi-level" | "gray" | "grayscale" | "kgray" | "black"
+            );
+        if monochrome {
+            JobColor::Monochrome
+        } else {
+            JobColor::Color
+        }
+    }
+
+    /// Color a job prints in. Jobs are counted as color unless their
+    /// options, the printer's capabilities or its default color mode make
+    /// them monochrome.
+    fn of_job(
+        printer_name: &str,
+        capabilities: Option<&PrinterCapabilities>,
+        raw_properties: &HashMap<String, String>,
+    ) -> JobColor {
+        let requested = raw_properties
+            .get("print-color-mode")
+            .or_else(|| raw_properties.get("ColorModel"))
+            .map(|mode| JobColor::from_mode(mode));
+        let prints_color = capabilities
+            .and_then(|capabilities| capabilities.supported.get("print-color-mode"))
+            .is_none_or(|modes| {
+                modes
+                    .iter()
+                    .any(|mode| JobColor::from_mode(mode) == JobColor::Color)
+            });
+        let default = capabilities
+            .and_then(|capabilities| capabilities.default_color_mode.as_deref())
+            .map(JobColor::from_mode)
+            .or_else(|| {
+                // Windows printers report their default through the driver
+                PrinterCore::get_printer_preferences(printer_name)
+                    .ok()
+                    .and_then(|preferences| preferences.color)
+                    .map(|color| {
+                        if color {
+                            JobColor::Color
+                        } else {
+                            JobColor::Monochrome
+                        }
+                    })
+            });
+        match (prints_color, requested.or(default)) {
+            (false, _) => JobColor::Monochrome,
+            (true, Some(color)) => color,
+            (true, None) => JobColor::Color,
+        }
+    }
+}
+
+/// Expected usage of a job, for showing a cost estimate or asking for
+/// approval before printing
+#[derive(Clone, Debug, PartialEq)]
+pub struct JobEstimate {
+    /// Printer the job would go to, after resolving aliases
+    pub printer_name: String,
+    /// Pages in the document, if known
+    pub document_pages: Option<u32>,
+    /// Pages printed, including copies and page ranges
+    pub pages: Option<u32>,
+    /// Physical sheets used, including copies, number-up and duplex
+    pub sheets: Option<u32>,
+    pub copies: u32,
+    pub duplex: bool,
+    pub color: JobColor,
+}
+
+impl JobEstimate {
+    /// Estimate a job from a dry run of it
+    pub fn from_dry_run(result: &DryRunResult) -> JobEstimate {
+        let raw_properties = &result.job_options.raw_properties;
+        let copies = raw_properties
+            .get("copies")
+            .and_then(|copies| copies.trim().parse::<u32>().ok())
+            .filter(|copies| *copies > 0)
+            .unwrap_or(1);
+        let duplex = raw_properties
+            .get("sides")
+            .is_some_and(|sides| sides.starts_with("two-sided"));
+
+        let color = JobColor::of_job(
+            &result.printer_name,
+            result.capabilities.as_ref(),
+            raw_properties,
+        );
+
+        JobEstimate {
+            printer_name: result.printer_name.clone(),
+            document_pages: result.document_pages,
+            pages: result.pages,
+            sheets: result.sheets,
+            copies,
+            duplex,
+            color,
+        }
+    }
+}
+
+impl PrinterCore {
+    /// Estimate the pages, sheets and color of printing a file, checking
+    /// the submission like a dry run without printing anything
+    pub fn estimate_job(
+        printer_name: &str,
+        file_path: &str,
+        job_options: Option<PrinterJobOptions>,
+    ) -> Result<JobEstimate, PrintError> {
+        let result = Self::dry_run_file(printer_name, file_path, job_options)?;
+        Ok(JobEstimate::from_dry_run(&result))
+    }
+
+    /// Estimate the pages, sheets and color of printing raw bytes
+    pub fn estimate_bytes_job(
+        printer_name: &str,
+        data: &[u8],
+        job_options: Option<PrinterJobOptions>,
+    ) -> Result<JobEstimate, PrintError> {
+        let result = Self::dry_run_bytes(printer_name, data, job_options)?;
+        Ok(JobEstimate::from_dry_run(&result))
+    }
+}
+
+// ===== PRINTER STATE MONITORING SYSTEM =====
+
+use std::collections::HashSet;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+/// Printer state change event
+#[derive(Clone, Debug, PartialEq)]
+pub enum PrinterStateEvent {
+    /// Printer connected/appeared
+    Connected { name: String },
+    /// Printer disconnected/removed
+    Disconnected { name: String },
+    /// Printer state changed (idle -> printing, etc.)
+    StateChanged {
+        name: String,
+        old_state: String,
+        new_state: String,
+    },
+    /// Printer state reasons changed (error conditions, etc.)
+    StateReasonsChanged {
+        name: String,
+        old_reasons: Vec<String>,
+        new_reasons: Vec<String>,
+    },
+}
+
+/// Printer state snapshot for tracking changes
+#[derive(Clone, Debug, PartialEq)]
+struct PrinterStateSnapshot {
+    name: String,
+    state: String,
+    state_reasons: Vec<String>,
+    exists: bool,
+}
+
+/// Event subscription callback type
+pub type StateChangeCallback = Box<dyn Fn(PrinterStateEvent) + Send + Sync>;
+
+/// How often printer states are re-read while spooler change notifications
+/// are active, to catch anything a notification didn't cover
+const NOTIFICATION_RESYNC_INTERVAL: Duration = Duration::from_secs(60);
+
+// Printer states from the monitor's latest poll, empty while it isn't running
+lazy_static::lazy_static! {
+    static ref MONITORED_STATES: RwLock<HashMap<String, PrinterStateSnapshot>> =
+        RwLock::new(HashMap::new());
+}
+
+/// Wake-up reasons for the monitoring loop
+enum MonitorSignal {
+    Stop,
+    /// The spooler reported a printer or job change
+    Changed,
+}
+
+/// Printer state monitor with event subscription
+pub struct PrinterStateMonitor {
+    callbacks: Arc<Mutex<Vec<StateChangeCallback>>>,
+    monitoring_thread: Option<SupervisedTask>,
+    stop_sender: Option<Sender<MonitorSignal>>,
+    poll_interval: Duration,
+}
+
+impl Default for PrinterStateMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PrinterStateMonitor {
+    /// Create a new printer state monitor
+    pub fn new() -> Self {
+        Self {
+            callbacks: Arc::new(Mutex::new(Vec::new())),
+            monitoring_thread: None,
+            stop_sender: None,
+            poll_interval: CONFIG.read().unwrap().monitoring_interval,
+        }
+    }
+
+    /// Set the polling interval for state monitoring
+    pub fn set_poll_interval(&mut self, interval: Duration) {
+        self.poll_interval = interval;
+    }
+
+    /// Subscribe to printer state change events
+    pub fn subscribe<F>(&mut self, callback: F) -> usize
+    where
+        F: Fn(PrinterStateEvent) + Send + Sync + 'static,
+    {
+        let mut callbacks = self
+            .callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        callbacks.push(Box::new(callback));
+        callbacks.len() - 1 // Return subscription ID
+    }
+
+    /// Remove a subscription by ID
+    pub fn unsubscribe(&mut self, subscription_id: usize) -> bool {
+        let mut callbacks = self
+            .callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if subscription_id < callbacks.len() {
+            let _removed = callbacks.remove(subscription_id);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Start monitoring printer state changes
+    pub fn start_monitoring(&mut self) -> Result<(), String> {
+        if self.monitoring_thread.is_some() {
+            return Err("Monitoring already started".to_string());
+        }
+
+        let (stop_sender, stop_receiver) = mpsc::channel();
+        let callbacks = Arc::clone(&self.callbacks);
+        let poll_interval = self.poll_interval;
+        let change_sender = stop_sender.clone();
+
+        tracing::debug!(
+            poll_interval_ms = poll_interval.as_millis() as u64,
+            "Starting state monitor"
+        );
+        // States left by a monitor that gave up would hide changes
+        MONITORED_STATES.write().unwrap().clear();
+        // A restarted loop carries on from the last states it saw
+        let task = supervisor::spawn("state-monitor", RestartPolicy::BACKGROUND, move || {
+            Self::monitoring_loop(&callbacks, &stop_receiver, &change_sender, poll_interval);
+        });
+
+        self.monitoring_thread = Some(task);
+        self.stop_sender = Some(stop_sender);
+        Ok(())
+    }
+
+    /// Whether the monitoring thread is running
+    pub fn is_running(&self) -> bool {
+        self.monitoring_thread
+            .as_ref()
+            .is_some_and(|task| !task.is_finished())
+    }
+
+    /// Stop monitoring printer state changes
+    pub fn stop_monitoring(&mut self) -> Result<(), String> {
+        if let Some(sender) = self.stop_sender.take() {
+            let _ = sender.send(MonitorSignal::Stop);
+        }
+
+        if let Some(task) = self.monitoring_thread.take() {
+            match task.join() {
+                Ok(_) => Ok(()),
+                Err(_) => Err("Failed to stop monitoring thread".to_string()),
+            }
+        } else {
+            Err("Monitoring not started".to_string())
+        }
+    }
+
+    /// Start spooler change notifications that wake the monitoring loop,
+    /// if the platform supports them
+    fn watch_spooler_changes(
+        change_sender: Sender<MonitorSignal>,
+    ) -> Option<crate::spooler::ChangeWatcher> {
+        if should_simulate_printing() {
+            return None;
+        }
+        match crate::spooler::watch_printer_changes(move || {
+            let _ = change_sender.send(MonitorSignal::Changed);
+        }) {
+            Ok(watcher) => {
+                tracing::debug!("Using spooler change notifications for state monitoring");
+                Some(watcher)
+            }
+            Err(e) => {
+                tracing::debug!("Polling printer states: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Main monitoring loop. Printer states are re-read when the spooler
+    /// reports a change, or every poll interval without notifications.
+    fn monitoring_loop(
+        callbacks: &Arc<Mutex<Vec<StateChangeCallback>>>,
+        stop_receiver: &Receiver<MonitorSignal>,
+        change_sender: &Sender<MonitorSignal>,
+        poll_interval: Duration,
+    ) {
+        let mut previous_states = MONITORED_STATES.read().unwrap().clone();
+        let mut watcher = Self::watch_spooler_changes(change_sender.clone());
+
+        loop {
+            if watcher
+                .as_ref()
+                .is_some_and(|watcher| !watcher.is_running())
+            {
+                tracing::warn!("Spooler change notifications stopped, falling back to polling");
+                watcher = None;
+            }
+            let wait = if watcher.is_some() {
+                NOTIFICATION_RESYNC_INTERVAL.max(poll_interval)
+            } else {
+                poll_interval
+            };
+
+            // Wait for a change, the next poll or the stop signal
+            match stop_receiver.recv_timeout(wait) {
+                Ok(MonitorSignal::Stop) => break,
+                Ok(MonitorSignal::Changed) => {
+                    // Coalesce a burst of notifications into one refresh
+                    let mut stop = false;
+                    while let Ok(signal) = stop_receiver.try_recv() {
+                        stop |= matches!(signal, MonitorSignal::Stop);
+                    }
+                    if stop {
+                        break;
+                    }
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    // Continue monitoring
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => break, // Channel closed
+            }
+
+            // Get current printer states
+            let current_states = Self::get_all_printer_states();
+            tracing::trace!(printers = current_states.len(), "Polled printer states");
+            let current_names: HashSet<String> = current_states.keys().cloned().collect();
+            let previous_names: HashSet<String> = previous_states.keys().cloned().collect();
+
+            // Check for new printers (connected)
+            for name in current_names.difference(&previous_names) {
+                let event = PrinterStateEvent::Connected { name: name.clone() };
+                Self::notify_subscribers(callbacks, event);
+            }
+
+            // Check for removed printers (disconnected)
+            for name in previous_names.difference(&current_names) {
+                fail_removed_printer_jobs(&JOB_TRACKER, name);
+                let event = PrinterStateEvent::Disconnected { name: name.clone() };
+                Self::notify_subscribers(callbacks, event);
+            }
+
+            // Check for state changes in existing printers
+            for (name, current_state) in &current_states {
+                if let Some(previous_state) = previous_states.get(name) {
+                    // Check for state change
+                    if current_state.state != previous_state.state {
+                        let event = PrinterStateEvent::StateChanged {
+                            name: name.clone(),
+                            old_state: previous_state.state.clone(),
+                            new_state: current_state.state.clone(),
+                        };
+                        Self::notify_subscribers(callbacks, event);
+                    }
+
+                    // Check for state reasons change
+                    if current_state.state_reasons != previous_state.state_reasons {
+                        let event = PrinterStateEvent::StateReasonsChanged {
+                            name: name.clone(),
+                            old_reasons: previous_state.state_reasons.clone(),
+                            new_reasons: current_state.state_reasons.clone(),
+                        };
+                        Self::notify_subscribers(callbacks, event);
+                    }
+                }
+            }
+
+            // Update previous states
+            *MONITORED_STATES.write().unwrap() = current_states.clone();
+            previous_states = current_states;
+        }
+        MONITORED_STATES.write().unwrap().clear();
+    }
+
+    /// Get current state of all printers
+    fn get_all_printer_states() -> HashMap<String, PrinterStateSnapshot> {
+        let mut states = HashMap::new();
+
+        if should_simulate_printing() {
+            // In simulation mode, provide some mock printer states
+            states.insert(
+                "Simulated Printer".to_string(),
+                PrinterStateSnapshot {
+                    name: "Simulated Printer".to_string(),
+                    state: "idle".to_string(),
+                    state_reasons: vec![],
+                    exists: true,
+                },
+            );
+        } else {
+            // Get all real printers and their states
+            for printer in cached_printers().iter() {
+                let state = PrinterCore::get_printer_state(printer);
+                states.insert(
+                    printer.name.clone(),
+                    PrinterStateSnapshot {
+                        name: printer.name.clone(),
+                        state,
+                        state_reasons: printer.state_reasons.clone(),
+                        exists: true,
+                    },
+                );
+            }
+        }
+
+        states
+    }
+
+    /// Notify all subscribers of an event
+    fn notify_subscribers(
+        callbacks: &Arc<Mutex<Vec<StateChangeCallback>>>,
+        event: PrinterStateEvent,
+    ) {
+        // A callback that panicked poisons the lock; the monitor is
+        // restarted and carries on with the same subscribers
+        let callbacks = callbacks.lock().unwrap_or_else(PoisonError::into_inner);
+        for callback in callbacks.iter() {
+            callback(event.clone());
+        }
+    }
+}
+
+impl Drop for PrinterStateMonitor {
+    fn drop(&mut self) {
+        let _ = self.stop_monitoring();
+    }
+}
+
+// Global state monitor instance
+lazy_static::lazy_static! {
+    static ref GLOBAL_STATE_MONITOR: Arc<Mutex<Option<PrinterStateMonitor>>> =
+        Arc::new(Mutex::new(None));
+}
+
+/// Global functions for printer state monitoring
+impl PrinterCore {
+    /// Start global printer state monitoring
+    pub fn start_state_monitoring() -> Result<(), String> {
+        let mut monitor_guard = GLOBAL_STATE_MONITOR.lock().unwrap();
+
+        if monitor_guard.is_some() {
+            return Err("State monitoring already active".to_string());
+        }
+
+        let mut monitor = PrinterStateMonitor::new();
+        monitor.start_monitoring()?;
+        *monitor_guard = Some(monitor);
+        Ok(())
+    }
+
+    /// Stop global printer state monitoring
+    pub fn stop_state_monitoring() -> Result<(), String> {
+        let mut monitor_guard = GLOBAL_STATE_MONITOR.lock().unwrap();
+
+        if let Some(mut monitor) = monitor_guard.take() {
+            monitor.stop_monitoring()?;
+            Ok(())
+        } else {
+            Err("State monitoring not active".to_string())
+        }
+    }
+
+    /// Start state monitoring on behalf of a JavaScript environment.
+    /// Environments (main thread, workers) share one monitor, which runs
+    /// until the last of them stops it or exits, so a worker subscribing
+    /// doesn't start a second polling loop and one unsubscribing doesn't
+    /// stop the others' events.
+    pub fn start_state_monitoring_for(env: EnvId) -> Result<(), String> {
+        let mut envs = MONITORING_ENVS.lock().unwrap();
+        if let Err(e) = Self::start_state_monitoring() {
+            if !Self::is_state_monitoring_active() {
+                return Err(e);
+            }
+        }
+        envs.insert(env);
+        Ok(())
+    }
+
+    /// Stop monitoring on behalf of a JavaScript environment, stopping the
+    /// monitor once no environment uses it
+    pub fn stop_state_monitoring_for(env: EnvId) -> Result<(), String> {
+        let mut envs = MONITORING_ENVS.lock().unwrap();
+        if !envs.remove(&env) {
+            return Err("State monitoring not active".to_string());
+        }
+        if envs.is_empty() {
+            let _ = Self::stop_state_monitoring();
+        }
+        Ok(())
+    }
+
+    /// Subscribe to printer state change events
+    pub fn subscribe_to_state_changes<F>(callback: F) -> Result<usize, String>
+    where
+        F: Fn(PrinterStateEvent) + Send + Sync + 'static,
+    {
+        let mut monitor_guard = GLOBAL_STATE_MONITOR.lock().unwrap();
+
+        // Initialize monitor if not already done
+        if monitor_guard.is_none() {
+            let mut monitor = PrinterStateMonitor::new();
+            monitor
+                .start_monitoring()
+                .map_err(|e| format!("Failed to start monitoring: {}", e))?;
+            *monitor_guard = Some(monitor);
+        }
+
+        if let Some(monitor) = monitor_guard.as_mut() {
+            Ok(monitor.subscribe(callback))
+        } else {
+            Err("Failed to initialize state monitoring".to_string())
+        }
+    }
+
+    /// Unsubscribe from printer state change events
+    pub fn unsubscribe_from_state_changes(subscription_id: usize) -> Result<bool, String> {
+        let mut monitor_guard = GLOBAL_STATE_MONITOR.lock().unwrap();
+
+        if let Some(monitor) = monitor_guard.as_mut() {
+            Ok(monitor.unsubscribe(subscription_id))
+        } else {
+            Err("State monitoring not active".to_string())
+        }
+    }
+
+    /// Check if state monitoring is active
+    pub fn is_state_monitoring_active() -> bool {
+        let monitor_guard = GLOBAL_STATE_MONITOR.lock().unwrap();
+        monitor_guard.is_some()
+    }
+
+    /// Set the polling interval for state monitoring (in seconds)
+    pub fn set_state_monitoring_interval(seconds: u64) -> Result<(), String> {
+        let mut monitor_guard = GLOBAL_STATE_MONITOR.lock().unwrap();
+
+        if let Some(monitor) = monitor_guard.as_mut() {
+            monitor.set_poll_interval(Duration::from_secs(seconds));
+            Ok(())
+        } else {
+            Err("State monitoring not active".to_string())
+        }
+    }
+
+    /// Get a snapshot of current printer states
+    pub fn get_printer_state_snapshot() -> HashMap<String, (String, Vec<String>)> {
+        let states = PrinterStateMonitor::get_all_printer_states();
+        states
+            .into_iter()
+            .map(|(name, snapshot)| (name, (snapshot.state, snapshot.state_reasons)))
+            .collect()
+    }
+}
+
+// ===== PRINTER LOAD =====
+
+/// How busy a printer is and whether a condition keeps it from printing
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrinterLoad {
+    /// Jobs submitted through this library that haven't finished
+    pub tracked_jobs: usize,
+    /// Jobs in the printer's spooler queue, including other applications'
+    pub spooler_jobs: usize,
+    /// Conditions that stop the printer from completing jobs
+    pub blocking_reasons: Vec<StateReason>,
+}
+
+impl PrinterLoad {
+    /// Jobs ahead of a newly submitted one. Tracked jobs that were already
+    /// handed to the spooler count twice, which only overestimates the
+    /// depth of printers that are busy anyway.
+    pub fn queue_depth(&self) -> usize {
+        self.tracked_jobs + self.spooler_jobs
+    }
+}
+
+impl StateReason {
+    /// Whether the reason stops a printer from completing jobs, as opposed
+    /// to warnings like low toner that it can keep printing through
+    pub fn is_blocking(&self) -> bool {
+        matches!(
+            self,
+            StateReason::MediaEmpty
+                | StateReason::MediaJam
+                | StateReason::MediaNeeded
+                | StateReason::TonerEmpty
+                | StateReason::MarkerSupplyEmpty
+                | StateReason::CoverOpen
+                | StateReason::InputTrayMissing
+                | StateReason::OutputBinFull
+                | StateReason::Offline
+                | StateReason::Paused
+                | StateReason::Shutdown
+                | StateReason::LabelOut
+                | StateReason::RibbonOut
+        )
+    }
+}
+
+impl PrinterCore {
+    /// Get the current load of a printer. State reasons come from the state
+    /// monitor's latest poll while monitoring is active, and from the
+    /// spooler otherwise.
+    pub fn get_printer_load(printer_name: &str) -> Option<PrinterLoad> {
+        let printer_name = &crate::aliases::resolve(printer_name);
+        let tracked_jobs =
+            JOB_TRACKER.count(|job| job.printer_name == *printer_name && job.state.is_queued());
+
+        let (spooler_jobs, raw_reasons) = if should_simulate_printing() {
+            if printer_name != "Simulated Printer" {
+                return None;
+            }
+            (0, Vec::new())
+        } else {
+            let printer = Self::find_printer_by_name(printer_name)?;
+            let monitored = MONITORED_STATES
+                .read()
+                .unwrap()
+                .get(printer_name)
+                .map(|snapshot| snapshot.state_reasons.clone());
+            (
+                printer.get_active_jobs().len(),
+                monitored.unwrap_or_else(|| printer.state_reasons.clone()),
+            )
+        };
+
+        let blocking_reasons = StateReason::parse_all(&raw_reasons)
+            .into_iter()
+            .filter(StateReason::is_blocking)
+            .collect();
+        Some(PrinterLoad {
+            tracked_jobs,
+            spooler_jobs,
+            blocking_reasons,
+        })
+    }
+}
+
+// ===== HEALTH CHECK =====
+
+/// How long the spooler gets to accept a connection during a health check
+const SPOOLER_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Number of tracked jobs in each state
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct JobStateCounts {
+    pub pending: usize,
+    pub paused: usize,
+    pub processing: usize,
+    pub completed: usize,
+    pub cancelled: usize,
+    pub failed: usize,
+}
+
+/// Whether a printer can take jobs, as seen by a health check
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrinterHealth {
+    pub name: String,
+    pub online: bool,
+    /// Conditions that stop the printer from completing jobs
+    pub blocking_reasons: Vec<StateReason>,
+}
+
+/// Structured health report, e.g. for a readiness probe
+#[derive(Clone, Debug, PartialEq)]
+pub struct HealthReport {
+    /// New jobs are accepted, the spooler is reachable and state
+    /// monitoring, if started, is still running. Printer status doesn't
+    /// affect it, as which printers matter is up to the application.
+    pub healthy: bool,
+    /// False while the library is shutting down
+    pub accepting_jobs: bool,
+    pub spooler_reachable: bool,
+    pub spooler_error: Option<String>,
+    /// State monitoring was started
+    pub monitoring_active: bool,
+    /// The monitoring thread is alive
+    pub monitoring_running: bool,
+    pub jobs: JobStateCounts,
+    /// Jobs currently handed to the spooler
+    pub active_workers: usize,
+    /// Configured worker pool size (None = unlimited)
+    pub worker_pool_size: Option<usize>,
+    pub printers: Vec<PrinterHealth>,
+}
+
+impl HealthReport {
+    /// Fraction of the worker pool in use, if it is limited
+    pub fn worker_saturation(&self) -> Option<f64> {
+        self.worker_pool_size
+            .map(|size| self.active_workers as f64 / size as f64)
+    }
+}
+
+impl PrinterCore {
+    /// Check the library's health. Printers are probed concurrently like
+    /// `is_printer_online`; `printer_names` limits which (None = all).
+    pub fn health_check(printer_names: Option<&[String]>) -> HealthReport {
+        let spooler = if should_simulate_printing() {
+            Ok(())
+        } else {
+            crate::spooler::check_spooler(SPOOLER_CHECK_TIMEOUT)
+        };
+
+        let (monitoring_active, monitoring_running) =
+            match GLOBAL_STATE_MONITOR.lock().unwrap().as_ref() {
+                Some(monitor) => (true, monitor.is_running()),
+                None => (false, false),
+            };
+
+        let count = |state: PrinterJobState| JOB_TRACKER.count(|job| job.state == state);
+        let jobs = JobStateCounts {
+            pending: count(PrinterJobState::PENDING),
+            paused: count(PrinterJobState::PAUSED),
+            processing: count(PrinterJobState::PROCESSING),
+            completed: count(PrinterJobState::COMPLETED),
+            cancelled: count(PrinterJobState::CANCELLED),
+            failed: count(PrinterJobState::FAILED),
+        };
+
+        let names = match printer_names {
+            Some(names) => names.to_vec(),
+            None => Self::get_all_printer_names(),
+        };
+        let printers = thread::scope(|scope| {
+            let probes: Vec<_> = names
+                .into_iter()
+                .map(|name| {
+                    scope.spawn(move || {
+                        let online = Self::is_printer_online(&name).unwrap_or(false);
+                        let blocking_reasons = Self::get_printer_load(&name)
+                            .map(|load| load.blocking_reasons)
+                            .unwrap_or_default();
+                        PrinterHealth {
+                            name,
+                            online,
+                            blocking_reasons,
+                        }
+                    })
+                })
+                .collect();
+            probes
+                .into_iter()
+                .filter_map(|probe| probe.join().ok())
+                .collect()
+        });
+
+        let accepting_jobs = ACCEPTING_JOBS.load(Ordering::Relaxed);
+        let healthy =
+            accepting_jobs && spooler.is_ok() && (!monitoring_active || monitoring_running);
+        if let Err(e) = &spooler {
+            tracing::warn!("Health check: spooler unreachable: {}", e);
+        }
+        HealthReport {
+            healthy,
+            accepting_jobs,
+            spooler_reachable: spooler.is_ok(),
+            spooler_error: spooler.err(),
+            monitoring_active,
+            monitoring_running,
+            jobs,
+            active_workers: *WORKER_SLOTS.0.lock().unwrap(),
+            worker_pool_size: CONFIG.read().unwrap().worker_pool_size,
+            printers,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+    use std::env;
+
+    #[test]
+    #[serial]
+    fn test_should_simulate_printing_when_env_var_is_true() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        assert!(should_simulate_printing());
+    }
+
+    #[test]
+    #[serial]
+    fn test_should_simulate_printing_when_env_var_is_false() {
+        // Store original value to restore later
+        let original = env::var("PRINTERS_JS_SIMULATE").ok();
+
+        env::set_var("PRINTERS_JS_SIMULATE", "false");
+        assert!(!should_simulate_printing());
+
+        // Restore original environment state
+        match original {
+            Some(val) => env::set_var("PRINTERS_JS_SIMULATE", val),
+            None => env::remove_var("PRINTERS_JS_SIMULATE"),
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_should_simulate_printing_when_env_var_is_missing() {
+        // Store original value to restore later
+        let original = env::var("PRINTERS_JS_SIMULATE").ok();
+
+        env::remove_var("PRINTERS_JS_SIMULATE");
+        assert!(!should_simulate_printing());
+
+        // Restore original environment state
+        if let Some(val) = original {
+            env::set_var("PRINTERS_JS_SIMULATE", val);
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_get_all_printer_names_in_simulation_mode() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let names = PrinterCore::get_all_printer_names();
+        assert_eq!(names, vec!["Simulated Printer"]);
+    }
+
+    #[test]
+    #[serial]
+    fn test_printer_exists_in_simulation_mode() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        assert!(PrinterCore::printer_exists("Simulated Printer"));
+        assert!(!PrinterCore::printer_exists("NonExistent Printer"));
+    }
+
+    #[test]
+    #[serial]
+    fn test_find_printer_by_name_in_simulation_mode() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let printer = PrinterCore::find_printer_by_name("Simulated Printer");
+        assert!(printer.is_some());
+
+        let printer = PrinterCore::find_printer_by_name("NonExistent Printer");
+        assert!(printer.is_none());
+    }
+
+    #[test]
+    #[serial]
+    fn test_printer_info_json() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let info: serde_json::Value =
+            serde_json::from_str(&PrinterCore::printer_info_json("Simulated Printer").unwrap())
+                .unwrap();
+        assert_eq!(info["name"], "Simulated Printer");
+        assert_eq!(info["isDefault"], true);
+        assert!(info["state"].is_string());
+        assert!(info["normalizedStateReasons"].is_array());
+        assert!(PrinterCore::printer_info_json("NonExistent Printer").is_none());
+
+        let mut printer = PrinterCore::find_printer_by_name("Simulated Printer").unwrap();
+        let id = PrinterCore::printer_id(&printer);
+        assert_eq!(info["printerId"], id.as_str());
+        assert_eq!(id.len(), 16);
+        // Renaming doesn't change the identity; moving the device does
+        printer.name = "Front Desk".to_string();
+        assert_eq!(PrinterCore::printer_id(&printer), id);
+        printer.uri = "ipp://10.0.0.2/ipp/print".to_string();
+        assert_ne!(PrinterCore::printer_id(&printer), id);
+
+        let info = PrinterInfo::from(&printer);
+        let json = printer_info_to_json(&info).unwrap();
+        let parsed = printer_info_from_json(&json).unwrap();
+        assert_eq!(parsed.printer_id, PrinterCore::printer_id(&printer));
+        assert_eq!(parsed.name, "Front Desk");
+        assert_eq!(printer_info_to_json(&parsed).unwrap(), json);
+        assert!(printer_info_from_json("{\"name\":\"Front Desk\"}").is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn test_print_file_error_codes() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+
+        // Test successful print job
+        let result = PrinterCore::print_file("Simulated Printer", "/path/to/file.pdf", None);
+        assert!(result.is_ok());
+
+        // Test with file that should trigger file not found error
+        let result = PrinterCore::print_file(
+            "Simulated Printer",
+            "/path/that/does_not_exist/file.pdf",
+            None,
+        );
+        assert_eq!(result, Err(PrintError::FileNotFound));
+
+        // Test with file that should trigger simulated failure
+        let result = PrinterCore::print_file("Simulated Printer", "/path/to/fail-test.pdf", None);
+        assert_eq!(result, Err(PrintError::SimulatedFailure));
+
+        // Test print bytes
+        let data = b"Hello, printer!";
+        let result = PrinterCore::print_bytes("Simulated Printer", data, None);
+        assert!(result.is_ok());
+
+        // Test print with job options
+        let job_options = Some(PrinterJobOptions::with_name_and_properties(
+            "Test Job".to_string(),
+            [("copies".to_string(), "2".to_string())]
+                .iter()
+                .cloned()
+                .collect(),
+        ));
+        let result = PrinterCore::print_file("Simulated Printer", "/path/to/file.pdf", job_options);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_print_error_codes() {
+        assert_eq!(PrintError::InvalidParams.as_i32(), 1);
+        assert_eq!(PrintError::InvalidPrinterName.as_i32(), 2);
+        assert_eq!(PrintError::InvalidFilePath.as_i32(), 3);
+        assert_eq!(PrintError::InvalidJson.as_i32(), 4);
+        assert_eq!(PrintError::InvalidJsonEncoding.as_i32(), 5);
+        assert_eq!(PrintError::PrinterNotFound.as_i32(), 6);
+        assert_eq!(PrintError::FileNotFound.as_i32(), 7);
+        assert_eq!(PrintError::SimulatedFailure.as_i32(), 8);
+        assert_eq!(PrintError::ShuttingDown.as_i32(), 9);
+        assert_eq!(PrintError::InvalidDocument.as_i32(), 10);
+        assert_eq!(PrintError::QueueFull.as_i32(), 15);
+        assert_eq!(PrintError::DuplicateJob.as_i32(), 16);
+        assert_eq!(PrintError::PrinterRemoved.as_i32(), 17);
+        assert_eq!(PrintError::UnsupportedOption(String::new()).as_i32(), 18);
+        let violation = crate::policy::PolicyViolation {
+            rule: "rule".to_string(),
+            reason: String::new(),
+        };
+        assert_eq!(PrintError::PolicyViolation(violation).as_i32(), 19);
+
+        for (index, e) in PrintError::ALL.into_iter().enumerate() {
+            assert_eq!(e.as_i32(), index as i32 + 1);
+            assert_eq!(PrintError::from_i32(e.as_i32()).as_ref(), Some(&e));
+            assert!(format!("{:?}", e).starts_with(e.name()));
+            assert!(!e.message().is_empty());
+        }
+        assert_eq!(PrintError::from_i32(0), None);
+    }
+
+    #[test]
+    fn test_media_type_detection() {
+        assert_eq!(detect_media_type("document.pdf"), "application/pdf");
+        assert_eq!(detect_media_type("script.ps"), "application/postscript");
+        assert_eq!(detect_media_type("image.jpg"), "image/jpeg");
+        assert_eq!(detect_media_type("image.jpeg"), "image/jpeg");
+        assert_eq!(detect_media_type("image.png"), "image/png");
+        assert_eq!(detect_media_type("image.gif"), "image/gif");
+        assert_eq!(detect_media_type("file.txt"), "text/plain");
+        assert_eq!(detect_media_type("file.text"), "text/plain");
+        assert_eq!(detect_media_type("unknown.xyz"), "application/octet-stream");
+        assert_eq!(
+            detect_media_type("no_extension"),
+            "application/octet-stream"
+        );
+        assert_eq!(
+            detect_media_type("<bytes:1024 bytes>"),
+            "application/vnd.cups-raw"
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_media_type_sniffing() {
+        let png = b"\x89PNG\r\n\x1a\n".as_slice();
+        assert_eq!(
+            detect_file_media_type("scan", Some(png)),
+            ("image/png".to_string(), MediaTypeSource::Content)
+        );
+        assert_eq!(
+            detect_file_media_type("upload.bin", Some(b"%PDF-1.4")),
+            ("application/pdf".to_string(), MediaTypeSource::Content)
+        );
+        // A known extension wins over the content
+        assert_eq!(
+            detect_file_media_type("notes.txt", Some(b"%PDF-1.4")),
+            ("text/plain".to_string(), MediaTypeSource::Extension)
+        );
+        assert_eq!(
+            detect_file_media_type("label.zpl", Some(b"^XA^XZ")),
+            (
+                "application/octet-stream".to_string(),
+                MediaTypeSource::Default
+            )
+        );
+        assert_eq!(
+            detect_file_media_type("missing", None).1,
+            MediaTypeSource::Default
+        );
+
+        // Raw bytes are sniffed too
+        assert_eq!(
+            detect_bytes_media_type(png),
+            ("image/png".to_string(), MediaTypeSource::Content)
+        );
+        assert_eq!(
+            detect_bytes_media_type(b"^XA^XZ"),
+            (
+                "application/vnd.cups-raw".to_string(),
+                MediaTypeSource::Default
+            )
+        );
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let job_id = PrinterCore::print_bytes("Simulated Printer", b"%PDF-1.4", None).unwrap();
+        let job = PrinterCore::get_job_status(job_id).unwrap();
+        assert_eq!(job.media_type, "application/pdf");
+        assert_eq!(job.media_type_source, MediaTypeSource::Content);
+    }
+
+    #[test]
+    fn test_document_format_option() {
+        assert!(is_mime_type("application/vnd.cups-raw"));
+        assert!(!is_mime_type("application/"));
+        assert!(!is_mime_type("text/plain; charset=utf-8"));
+        let format = |value: &str| {
+            document_format(&HashMap::from([(
+                "document-format".to_string(),
+                value.to_string(),
+            )]))
+        };
+        assert_eq!(
+            format("Application/VND.CUPS-Raw"),
+            Ok(Some("application/vnd.cups-raw".to_string()))
+        );
+        assert_eq!(format("raw"), Err(PrintError::InvalidParams));
+        assert_eq!(document_format(&HashMap::new()), Ok(None));
+    }
+
+    #[test]
+    fn test_printer_job_state_conversions() {
+        assert_eq!(PrinterJobState::PENDING.as_string(), "pending");
+        assert_eq!(PrinterJobState::PAUSED.as_string(), "paused");
+        assert_eq!(PrinterJobState::PROCESSING.as_string(), "processing");
+        assert_eq!(PrinterJobState::CANCELLED.as_string(), "cancelled");
+        assert_eq!(PrinterJobState::COMPLETED.as_string(), "completed");
+        assert_eq!(PrinterJobState::UNKNOWN.as_string(), "unknown");
+    }
+
+    #[test]
+    fn test_state_reason_parsing() {
+        // CUPS keywords with severity suffixes
+        assert_eq!(
+            StateReason::parse("media-empty-error"),
+            Some(StateReason::MediaEmpty)
+        );
+        assert_eq!(
+            StateReason::parse("toner-low-warning"),
+            Some(StateReason::TonerLow)
+        );
+        assert_eq!(
+            StateReason::parse("offline-report"),
+            Some(StateReason::Offline)
+        );
+        assert_eq!(
+            StateReason::parse("cover-open"),
+            Some(StateReason::CoverOpen)
+        );
+        assert_eq!(
+            StateReason::parse("ribbon-out-error"),
+            Some(StateReason::RibbonOut)
+        );
+        assert_eq!(
+            StateReason::parse("label-empty"),
+            Some(StateReason::LabelOut)
+        );
+
+        // Windows status names
+        assert_eq!(
+            StateReason::parse("paper_out"),
+            Some(StateReason::MediaEmpty)
+        );
+        assert_eq!(
+            StateReason::parse("door_open"),
+            Some(StateReason::CoverOpen)
+        );
+
+        // Informational and unrecognized reasons
+        assert_eq!(StateReason::parse("none"), None);
+        assert_eq!(StateReason::parse("io_active"), None);
+        assert_eq!(
+            StateReason::parse("com.vendor-custom-error"),
+            Some(StateReason::Other)
+        );
+
+        let reasons = StateReason::parse_all(&[
+            "media-empty-error".to_string(),
+            "media-empty-warning".to_string(),
+            "none".to_string(),
+            "toner-low-report".to_string(),
+        ]);
+        assert_eq!(
+            reasons,
+            vec![StateReason::MediaEmpty, StateReason::TonerLow]
+        );
+        assert_eq!(StateReason::MediaEmpty.as_str(), "media-empty");
+    }
+
+    #[test]
+    fn test_supplies_from_marker_attributes() {
+        use crate::ipp::{IppAttribute, IppRequest, IppResponse, IppValue, TAG_PRINTER_ATTRIBUTES};
+
+        let mut message = IppRequest::new(crate::ipp::STATUS_OK, "ipp://localhost/");
+        let mut add = |name: &str, values: Vec<IppValue>| {
+            message.add_attribute(
+                TAG_PRINTER_ATTRIBUTES,
+                IppAttribute {
+                    name: name.to_string(),
+                    values,
+                },
+            )
+        };
+        let names = |list: &[&str]| list.iter().map(|v| IppValue::Name(v.to_string())).collect();
+        let keywords = |list: &[&str]| {
+            list.iter()
+                .map(|v| IppValue::Keyword(v.to_string()))
+                .collect()
+        };
+        add("marker-names", names(&["Black Toner", "Drum"]));
+        add("marker-types", keywords(&["toner", "opc"]));
+        add("marker-colors", names(&["#000000", "none"]));
+        add(
+            "marker-levels",
+            vec![IppValue::Integer(8), IppValue::Integer(-2)],
+        );
+        add(
+            "marker-low-levels",
+            vec![IppValue::Integer(10), IppValue::Integer(5)],
+        );
+        add(
+            "marker-high-levels",
+            vec![IppValue::Integer(100), IppValue::Integer(100)],
+        );
+
+        let response = IppResponse::decode(&message.encode()).unwrap();
+        let supplies = PrinterSupply::from_ipp_response(&response);
+
+        assert_eq!(supplies.len(), 2);
+        assert_eq!(supplies[0].name, "Black Toner");
+        assert_eq!(supplies[0].supply_type, "toner");
+        assert_eq!(supplies[0].color.as_deref(), Some("#000000"));
+        assert_eq!(supplies[0].level, Some(8));
+        assert!(supplies[0].is_low());
+        assert_eq!(supplies[1].color, None);
+        assert_eq!(supplies[1].level, None);
+        assert!(!supplies[1].is_low());
+
+        assert_eq!(marker_percent(150, 300), Some(50));
+        assert_eq!(marker_percent(-3, 100), None);
+    }
+
+    #[test]
+    fn test_state_reasons_from_windows_status() {
+        assert!(StateReason::from_windows_status(0).is_empty());
+        assert_eq!(
+            StateReason::from_windows_status(PRINTER_STATUS_PAPER_OUT | PRINTER_STATUS_DOOR_OPEN),
+            vec![StateReason::MediaEmpty, StateReason::CoverOpen]
+        );
+        assert_eq!(
+            StateReason::from_windows_status(PRINTER_STATUS_OFFLINE | PRINTER_STATUS_NOT_AVAILABLE),
+            vec![StateReason::Offline]
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_job_options_creation() {
+        // Test none()
+        let none_options = PrinterJobOptions::none();
+        assert!(none_options.name.is_none());
+        assert!(none_options.raw_properties.is_empty());
+
+        // Test from_map with job-name
+        let mut properties = HashMap::new();
+        properties.insert("job-name".to_string(), "Test Job".to_string());
+        properties.insert("copies".to_string(), "2".to_string());
+
+        let options = PrinterJobOptions::from_map(properties);
+        assert_eq!(options.name, Some("Test Job".to_string()));
+        assert_eq!(options.raw_properties.get("copies"), Some(&"2".to_string()));
+        assert!(!options.raw_properties.contains_key("job-name")); // Should be extracted
+
+        // Test from_map with requesting-user-name
+        let mut properties = HashMap::new();
+        properties.insert("requesting-user-name".to_string(), "alice".to_string());
+
+        let options = PrinterJobOptions::from_map(properties);
+        assert_eq!(options.requesting_user, Some("alice".to_string()));
+        assert!(options.raw_properties.is_empty()); // Should be extracted
+
+        // Test from_map with job-timeout-ms
+        let options = PrinterJobOptions::from_map(HashMap::from([(
+            "job-timeout-ms".to_string(),
+            "1500".to_string(),
+        )]));
+        assert_eq!(options.timeout, Some(Duration::from_millis(1500)));
+        assert!(options.raw_properties.is_empty());
+        let options = PrinterJobOptions::from_map(HashMap::from([(
+            "job-timeout-ms".to_string(),
+            "0".to_string(),
+        )]));
+        assert!(options.timeout.is_none());
+
+        // Test from_map without job-name
+        let mut properties = HashMap::new();
+        properties.insert("copies".to_string(), "3".to_string());
+
+        let options = PrinterJobOptions::from_map(properties);
+        assert!(options.name.is_none());
+        assert!(options.requesting_user.is_none());
+        assert_eq!(options.raw_properties.get("copies"), Some(&"3".to_string()));
+
+        // Test with_name_and_properties
+        let mut properties = HashMap::new();
+        properties.insert("quality".to_string(), "high".to_string());
+
+        let options =
+            PrinterJobOptions::with_name_and_properties("Named Job".to_string(), properties);
+        assert_eq!(options.name, Some("Named Job".to_string()));
+        assert_eq!(
+            options.raw_properties.get("quality"),
+            Some(&"high".to_string())
+        );
+    }
+
+    /// Keys of a serialized job, sorted
+    const JOB_JSON_KEYS: [&str; 22] = [
+        "completedAt",
+        "createdAt",
+        "encryptedTransport",
+        "errorMessage",
+        "id",
+        "mediaType",
+        "mediaTypeSource",
+        "metadata",
+        "name",
+        "owner",
+        "pages",
+        "printerName",
+        "printerRemoved",
+        "processedAt",
+        "reprintOf",
+        "sheets",
+        "sizeBytes",
+        "stalled",
+        "state",
+        "timedOut",
+        "totalPages",
+        "transitions",
+    ];
+
+    #[test]
+    #[serial]
+    fn test_printer_job_json_keys() {
+        let job = PrinterJob {
+            state: PrinterJobState::COMPLETED,
+            created_at: SystemTime::now() - Duration::from_secs(10),
+            processed_at: Some(SystemTime::now() - Duration::from_secs(8)),
+            completed_at: Some(SystemTime::now() - Duration::from_secs(5)),
+            error_message: Some("Test error".to_string()),
+            source_path: Some("/tmp/report.pdf".to_string()),
+            ..PrinterJob::new(
+                1234,
+                "Test Job".to_string(),
+                "application/pdf".to_string(),
+                "Test Printer".to_string(),
+            )
+        };
+
+        let json_value: serde_json::Value =
+            serde_json::from_str(&printer_job_to_json(&job).unwrap()).unwrap();
+        let mut keys: Vec<&str> = json_value
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        keys.sort_unstable();
+        assert_eq!(keys, JOB_JSON_KEYS);
+
+        assert_eq!(json_value["id"], 1234);
+        assert_eq!(json_value["state"], "completed");
+        assert_eq!(json_value["errorMessage"], "Test error");
+        assert!(json_value["createdAt"].is_number());
+        assert!(json_value["completedAt"].is_number());
+    }
+
+    #[test]
+    #[serial]
+    fn test_job_state_tracking() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+
+        // Clear any existing jobs
+        PrinterCore::cleanup_old_jobs(0);
+
+        let job_id = generate_job_id();
+        let initial_job = PrinterJob::new(
+            job_id,
+            "State Transition Test".to_string(),
+            "application/pdf".to_string(),
+            "Simulated Printer".to_string(),
+        );
+
+        // Insert initial job
+        JOB_TRACKER.insert(initial_job);
+
+        // Verify initial state
+        let job = PrinterCore::get_job_status(job_id).unwrap();
+        assert_eq!(job.state, PrinterJobState::PENDING);
+        assert!(job.processed_at.is_none());
+        assert!(job.completed_at.is_none());
+
+        // Transition to processing
+        JOB_TRACKER.update(job_id, |job| {
+            job.state = PrinterJobState::PROCESSING;
+            job.processed_at = Some(SystemTime::now());
+        });
+
+        let job = PrinterCore::get_job_status(job_id).unwrap();
+        assert_eq!(job.state, PrinterJobState::PROCESSING);
+        assert!(job.processed_at.is_some());
+        assert!(job.completed_at.is_none());
+
+        // Transition to completed
+        JOB_TRACKER.update(job_id, |job| {
+            job.state = PrinterJobState::COMPLETED;
+            job.completed_at = Some(SystemTime::now());
+        });
+
+        let job = PrinterCore::get_job_status(job_id).unwrap();
+        assert_eq!(job.state, PrinterJobState::COMPLETED);
+        assert!(job.processed_at.is_some());
+        assert!(job.completed_at.is_some());
+
+        // Cleanup
+        PrinterCore::cleanup_old_jobs(0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_cleanup_old_jobs_for_printer() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+
+        // Clear any existing jobs
+        PrinterCore::cleanup_old_jobs(0);
+
+        // Create jobs for two different printers
+        let job_id_1 = generate_job_id();
+        let job_id_2 = generate_job_id();
+
+        // Job for "Printer A" - completed
+        JOB_TRACKER.insert(PrinterJob {
+            state: PrinterJobState::COMPLETED,
+            created_at: SystemTime::now() - Duration::from_secs(100),
+            processed_at: Some(SystemTime::now() - Duration::from_secs(90)),
+            completed_at: Some(SystemTime::now() - Duration::from_secs(80)),
+            ..PrinterJob::new(
+                job_id_1,
+                "Job for Printer A".to_string(),
+                "application/pdf".to_string(),
+                "Printer A".to_string(),
+            )
+        });
+
+        // Job for "Printer B" - completed
+        JOB_TRACKER.insert(PrinterJob {
+            state: PrinterJobState::COMPLETED,
+            created_at: SystemTime::now() - Duration::from_secs(100),
+            processed_at: Some(SystemTime::now() - Duration::from_secs(90)),
+            completed_at: Some(SystemTime::now() - Duration::from_secs(80)),
+            ..PrinterJob::new(
+                job_id_2,
+                "Job for Printer B".to_string(),
+                "application/pdf".to_string(),
+                "Printer B".to_string(),
+            )
+        });
+
+        // Verify both jobs exist
+        assert!(PrinterCore::get_job_status(job_id_1).is_some());
+        assert!(PrinterCore::get_job_status(job_id_2).is_some());
+
+        // Jobs younger than the maximum age are kept
+        assert_eq!(
+            PrinterCore::cleanup_old_jobs_for_printer("Printer A", 1000),
+            0
+        );
+
+        // Clean up only Printer A's jobs (max_age = 0 means all completed jobs)
+        let removed = PrinterCore::cleanup_old_jobs_for_printer("Printer A", 0);
+        assert_eq!(removed, 1);
+
+        // Verify Printer A's job is gone but Printer B's job remains
+        assert!(PrinterCore::get_job_status(job_id_1).is_none());
+        assert!(PrinterCore::get_job_status(job_id_2).is_some());
+
+        // Clean up remaining
+        PrinterCore::cleanup_old_jobs(0);
+    }
+
+    #[test]
+    fn test_job_usage_from_options() {
+        let options = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<_, _>>()
+        };
+
+        assert_eq!(job_usage(None, &options(&[])), (None, None));
+        assert_eq!(job_usage(Some(5), &options(&[])), (Some(5), Some(5)));
+        assert_eq!(
+            job_usage(
+                Some(5),
+                &options(&[("copies", "2"), ("sides", "two-sided-long-edge")])
+            ),
+            (Some(10), Some(6))
+        );
+        assert_eq!(
+            job_usage(
+                Some(10),
+                &options(&[("page-ranges", "1-3,8,9-20"), ("number-up", "2")])
+            ),
+            (Some(6), Some(3))
+        );
+        assert_eq!(count_page_range("2-1", 4), Some(0));
+        assert_eq!(count_page_range("a-b", 4), None);
+    }
+
+    #[test]
+    #[serial]
+    fn test_usage_stats() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        PrinterCore::cleanup_old_jobs(0);
+
+        let before = SystemTime::now() - Duration::from_secs(60);
+        for (printer, state, pages, size_bytes) in [
+            ("Printer A", PrinterJobState::COMPLETED, Some(4), 1000),
+            ("Printer A", PrinterJobState::COMPLETED, None, 500),
+            ("Printer A", PrinterJobState::CANCELLED, Some(9), 2000),
+            ("Printer B", PrinterJobState::COMPLETED, Some(2), 300),
+        ] {
+            let job_id = generate_job_id();
+            JOB_TRACKER.insert(PrinterJob {
+                state,
+                completed_at: Some(SystemTime::now()),
+                size_bytes,
+                pages,
+                sheets: pages,
+                ..PrinterJob::new(
+                    job_id,
+                    "Usage Test".to_string(),
+                    "application/pdf".to_string(),
+                    printer.to_string(),
+                )
+            });
+        }
+
+        let stats = PrinterCore::get_usage_stats(Some("Printer A"), Some(before));
+        assert_eq!(stats.jobs, 2);
+        assert_eq!(stats.pages, 4);
+        assert_eq!(stats.bytes, 1500);
+        assert_eq!(stats.jobs_without_page_count, 1);
+
+        let all = PrinterCore::get_usage_stats(None, None);
+        assert_eq!(all.jobs, 3);
+        assert_eq!(all.sheets, 6);
+
+        let future = SystemTime::now() + Duration::from_secs(60);
+        assert_eq!(PrinterCore::get_usage_stats(None, Some(future)).jobs, 0);
+
+        PrinterCore::cleanup_old_jobs(0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_library_config() {
+        assert!(LibraryConfig {
+            worker_pool_size: Some(0),
+            ..LibraryConfig::default()
+        }
+        .validate()
+        .is_err());
+        assert!(LibraryConfig {
+            temp_dir: Some(PathBuf::from("/nonexistent/printers-js")),
+            ..LibraryConfig::default()
+        }
+        .validate()
+        .is_err());
+        assert!(LibraryConfig {
+            job_poll_interval: Duration::ZERO,
+            ..LibraryConfig::default()
+        }
+        .validate()
+        .is_err());
+
+        // Config overrides the environment variable
+        env::set_var("PRINTERS_JS_SIMULATE", "false");
+        PrinterCore::configure(LibraryConfig {
+            simulate: Some(true),
+            job_poll_interval: Duration::from_millis(200),
+            default_job_options: HashMap::from([
+                ("copies".to_string(), "2".to_string()),
+                ("job-name".to_string(), "Default Name".to_string()),
+            ]),
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+        assert!(should_simulate_printing());
+        assert_eq!(PrinterCore::get_config().simulate, Some(true));
+
+        let options =
+            PrinterJobOptions::from_map(HashMap::from([("copies".to_string(), "5".to_string())]))
+                .with_defaults(&PrinterCore::get_config().default_job_options);
+        assert_eq!(options.raw_properties.get("copies"), Some(&"5".to_string()));
+        assert_eq!(options.name.as_deref(), Some("Default Name"));
+
+        // Jobs can poll at their own interval
+        let fast = PrinterCore::print_bytes(
+            "Simulated Printer",
+            b"%!PS",
+            Some(PrinterJobOptions::from_map(HashMap::from([(
+                "job-poll-interval-ms".to_string(),
+                "10".to_string(),
+            )]))),
+        )
+        .unwrap();
+        let default = PrinterCore::print_bytes("Simulated Printer", b"%!PS", None).unwrap();
+        assert_eq!(job_poll_interval(fast), Duration::from_millis(10));
+        assert_eq!(job_poll_interval(default), Duration::from_millis(200));
+        assert_eq!(
+            PrinterCore::get_job_status(fast)
+                .unwrap()
+                .job_options
+                .to_map()
+                .get("job-poll-interval-ms"),
+            Some(&"10".to_string())
+        );
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
+
+        PrinterCore::configure(LibraryConfig::default()).unwrap();
+        assert!(!should_simulate_printing());
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+    }
+
+    #[test]
+    #[serial]
+    fn test_shutdown_modes() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        PrinterCore::cleanup_old_jobs(0);
+        PrinterCore::configure(LibraryConfig {
+            simulation_delay: Duration::from_millis(100),
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+
+        // Drain finishes queued jobs
+        let drained = PrinterCore::print_file("Simulated Printer", "/tmp/test.pdf", None).unwrap();
+        let summary =
+            PrinterCore::shutdown_library_with(ShutdownMode::Drain, Duration::from_secs(5));
+        assert_eq!(summary.completed, 1);
+        assert_eq!(summary.cancelled, 0);
+        assert_eq!(
+            PrinterCore::get_job_status(drained).unwrap().state,
+            PrinterJobState::COMPLETED
+        );
+
+        // Force cancels in-flight jobs and records why
+        PrinterCore::configure(LibraryConfig {
+            simulation_delay: Duration::from_secs(30),
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+        let forced = PrinterCore::print_file("Simulated Printer", "/tmp/test.pdf", None).unwrap();
+        let summary =
+            PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::from_secs(5));
+        assert_eq!(summary.cancelled, 1);
+        let job = PrinterCore::get_job_status(forced).unwrap();
+        assert_eq!(job.state, PrinterJobState::CANCELLED);
+        assert!(job.error_message.unwrap().contains("forced shutdown"));
+
+        // Submissions are accepted again afterwards
+        assert!(ShutdownMode::parse("drain").is_some());
+        assert!(ShutdownMode::parse("later").is_none());
+        PrinterCore::configure(LibraryConfig::default()).unwrap();
+        assert!(PrinterCore::print_file("Simulated Printer", "/tmp/test.pdf", None).is_ok());
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_shutdown_when_last_env_detaches() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        PrinterCore::configure(LibraryConfig {
+            simulation_delay: Duration::from_secs(30),
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+
+        let main = PrinterCore::env_attached();
+        let worker = PrinterCore::env_attached();
+        assert_ne!(main, worker);
+        let job_id = PrinterCore::print_file("Simulated Printer", "/tmp/test.pdf", None).unwrap();
+
+        // A worker exiting must not tear down the main thread's jobs
+        PrinterCore::env_detached(worker);
+        assert!(!PrinterCore::get_job_status(job_id)
+            .unwrap()
+            .state
+            .is_terminal());
+
+        PrinterCore::env_detached(main);
+        assert_eq!(
+            PrinterCore::get_job_status(job_id).unwrap().state,
+            PrinterJobState::CANCELLED
+        );
+
+        PrinterCore::configure(LibraryConfig::default()).unwrap();
+        PrinterCore::cleanup_old_jobs(0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_cancel_all_jobs_and_clear_history() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        PrinterCore::cleanup_old_jobs(0);
+        PrinterCore::configure(LibraryConfig {
+            simulation_delay: Duration::from_secs(30),
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+
+        let job_id = PrinterCore::print_file("Simulated Printer", "/tmp/test.pdf", None).unwrap();
+        assert_eq!(PrinterCore::cancel_all_jobs(Some("Other Printer")), 0);
+        assert_eq!(PrinterCore::cancel_all_jobs(Some("Simulated Printer")), 1);
+        let job = PrinterCore::get_job_status(job_id).unwrap();
+        assert_eq!(job.state, PrinterJobState::CANCELLED);
+        assert_eq!(job.error_message.as_deref(), Some("Cancelled by request"));
+        assert_eq!(PrinterCore::cancel_all_jobs(None), 0);
+
+        assert_eq!(PrinterCore::clear_job_history(Some("Other Printer")), 0);
+        assert_eq!(PrinterCore::clear_job_history(None), 1);
+        assert!(PrinterCore::get_job_status(job_id).is_none());
+
+        PrinterCore::configure(LibraryConfig::default()).unwrap();
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+    }
+
+    #[test]
+    #[serial]
+    fn test_job_history_eviction() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        PrinterCore::cleanup_old_jobs(0);
+
+        let mut ids = Vec::new();
+        for age in [30, 20, 10] {
+            let job_id = generate_job_id();
+            JOB_TRACKER.insert(PrinterJob {
+                state: PrinterJobState::COMPLETED,
+                completed_at: Some(SystemTime::now() - Duration::from_secs(age)),
+                ..PrinterJob::new(
+                    job_id,
+                    "History Test".to_string(),
+                    "application/pdf".to_string(),
+                    "Simulated Printer".to_string(),
+                )
+            });
+            ids.push(job_id);
+        }
+
+        // Looking up the oldest job makes it the most recently used
+        PrinterCore::get_job_status(ids[0]).unwrap();
+        PrinterCore::configure(LibraryConfig {
+            max_job_history: Some(2),
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+        assert!(PrinterCore::get_job_status(ids[0]).is_some());
+        assert!(PrinterCore::get_job_status(ids[1]).is_none());
+        assert!(PrinterCore::get_job_status(ids[2]).is_some());
+
+        // TTL only considers completion time
+        PrinterCore::configure(LibraryConfig {
+            history_ttl: Some(Duration::from_secs(15)),
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+        assert!(PrinterCore::get_job_status(ids[0]).is_none());
+        assert!(PrinterCore::get_job_status(ids[2]).is_some());
+
+        assert!(LibraryConfig {
+            max_job_history: Some(0),
+            ..LibraryConfig::default()
+        }
+        .validate()
+        .is_err());
+        assert_eq!(
+            sweep_interval(Duration::from_secs(2)),
+            Duration::from_secs(1)
+        );
+        assert_eq!(
+            sweep_interval(Duration::from_secs(3600)),
+            Duration::from_secs(60)
+        );
+
+        PrinterCore::configure(LibraryConfig::default()).unwrap();
+        stop_history_sweeper();
+        PrinterCore::cleanup_old_jobs(0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_job_timeout() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        PrinterCore::cleanup_old_jobs(0);
+        PrinterCore::configure(LibraryConfig {
+            simulation_delay: Duration::from_secs(30),
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+
+        let options = PrinterJobOptions {
+            timeout: Some(Duration::from_millis(100)),
+            ..PrinterJobOptions::none()
+        };
+        let job_id =
+            PrinterCore::print_file("Simulated Printer", "/tmp/test.pdf", Some(options)).unwrap();
+        let no_timeout =
+            PrinterCore::print_file("Simulated Printer", "/tmp/test.pdf", None).unwrap();
+
+        thread::sleep(Duration::from_millis(500));
+        let job = PrinterCore::get_job_status(job_id).unwrap();
+        assert_eq!(job.state, PrinterJobState::FAILED);
+        assert!(job.timed_out);
+        assert_eq!(
+            job.error_message.as_deref(),
+            Some("Job timed out after 100ms")
+        );
+        let job = PrinterCore::get_job_status(no_timeout).unwrap();
+        assert!(!job.state.is_terminal());
+        assert!(!job.timed_out);
+
+        PrinterCore::configure(LibraryConfig::default()).unwrap();
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_document_validation() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let validate = || {
+            Some(PrinterJobOptions {
+                validate_document: true,
+                ..PrinterJobOptions::none()
+            })
+        };
+
+        let truncated = b"%PDF-1.4\n1 0 obj << /Type /Catalog";
+        assert_eq!(
+            PrinterCore::print_bytes("Simulated Printer", truncated, validate()),
+            Err(PrintError::InvalidDocument)
+        );
+        // Validation is opt-in and skips documents that aren't PDFs
+        assert!(PrinterCore::print_bytes("Simulated Printer", truncated, None).is_ok());
+        assert!(PrinterCore::print_bytes("Simulated Printer", b"^XA^XZ", validate()).is_ok());
+
+        let pdf = b"%PDF-1.4\n\
+            1 0 obj << /Type /Catalog /Pages 2 0 R >> endobj\n\
+            2 0 obj << /Type /Pages /Kids [3 0 R 4 0 R] /Count 2 >> endobj\n\
+            3 0 obj << /Type /Page /Parent 2 0 R >> endobj\n\
+            4 0 obj << /Type /Page /Parent 2 0 R >> endobj\n\
+            trailer << /Root 1 0 R >>\nstartxref\n9\n%%EOF\n";
+        let job_id = PrinterCore::print_bytes("Simulated Printer", pdf, validate()).unwrap();
+        let job = PrinterCore::get_job_status(job_id).unwrap();
+        assert_eq!(job.total_pages, Some(2));
+
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_printer_preferences() {
+        use crate::spooler::{Duplex, Orientation};
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+
+        let defaults = PrinterCore::get_printer_preferences("Simulated Printer").unwrap();
+        assert_eq!(defaults.orientation, Some(Orientation::Portrait));
+
+        let updated = PrinterCore::set_printer_preferences(
+            "Simulated Printer",
+            &PrinterPreferences {
+                orientation: Some(Orientation::Landscape),
+                duplex: Some(Duplex::LongEdge),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(updated.orientation, Some(Orientation::Landscape));
+        assert_eq!(updated.duplex, Some(Duplex::LongEdge));
+        // Fields that weren't set keep their values
+        assert_eq!(updated.color, defaults.color);
+        assert_eq!(
+            PrinterCore::get_printer_preferences("Simulated Printer").unwrap(),
+            updated
+        );
+
+        assert!(PrinterCore::get_printer_preferences("Missing Printer").is_err());
+        SIMULATED_PREFERENCES.lock().unwrap().clear();
+    }
+
+    #[test]
+    #[serial]
+    fn test_spool_quota() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let spool_base = tempfile::tempdir().unwrap();
+        PrinterCore::configure(LibraryConfig {
+            temp_dir: Some(spool_base.path().to_path_buf()),
+            spool_quota: Some(crate::spool::usage() + 10),
+            simulation_delay: Duration::from_millis(200),
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+
+        let job_id = PrinterCore::print_bytes("Simulated Printer", b"12345678", None).unwrap();
+        assert_eq!(
+            PrinterCore::print_bytes("Simulated Printer", b"12345678", None),
+            Err(PrintError::SpoolQuotaExceeded)
+        );
+
+        // The payload is removed once the job finishes, freeing the quota
+        let start = Instant::now();
+        while PrinterCore::get_job_status(job_id).is_some_and(|job| !job.state.is_terminal())
+            && start.elapsed() < Duration::from_secs(5)
+        {
+            thread::sleep(Duration::from_millis(20));
+        }
+        thread::sleep(Duration::from_millis(50));
+        assert!(PrinterCore::print_bytes("Simulated Printer", b"12345678", None).is_ok());
+
+        PrinterCore::configure(LibraryConfig::default()).unwrap();
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
+        let leftover = std::fs::read_dir(spool_base.path()).unwrap().count();
+        assert_eq!(leftover, 0, "shutdown should remove the spool directory");
+    }
+
+    #[test]
+    #[serial]
+    fn test_keep_spool_file() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let spool_base = tempfile::tempdir().unwrap();
+        PrinterCore::configure(LibraryConfig {
+            temp_dir: Some(spool_base.path().to_path_buf()),
+            simulation_delay: Duration::from_millis(50),
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+
+        let keep = PrinterJobOptions::from_map(HashMap::from([(
+            "keep-spool-file".to_string(),
+            "true".to_string(),
+        )]));
+        assert!(keep.keep_spool_file);
+        let job_id =
+            PrinterCore::print_bytes("Simulated Printer", b"%!PS kept", Some(keep)).unwrap();
+        let other_id = PrinterCore::print_bytes("Simulated Printer", b"%!PS", None).unwrap();
+        assert!(PrinterCore::get_job_spool_file(other_id).is_none());
+
+        // The payload outlives the job
+        let start = Instant::now();
+        while PrinterCore::get_job_status(job_id).is_some_and(|job| !job.state.is_terminal())
+            && start.elapsed() < Duration::from_secs(5)
+        {
+            thread::sleep(Duration::from_millis(20));
+        }
+        let path = PrinterCore::get_job_spool_file(job_id).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"%!PS kept");
+
+        // Removing the job from the history releases it
+        PrinterCore::clear_job_history(Some("Simulated Printer"));
+        assert!(PrinterCore::get_job_status(job_id).is_none());
+        assert!(PrinterCore::get_job_spool_file(job_id).is_none());
+        assert!(!path.exists());
+
+        PrinterCore::configure(LibraryConfig::default()).unwrap();
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_reprint_job() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        PrinterCore::configure(LibraryConfig {
+            simulation_delay: Duration::from_millis(20),
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+        let wait = |job_id| {
+            let start = Instant::now();
+            while PrinterCore::get_job_status(job_id).is_some_and(|job| !job.state.is_terminal())
+                && start.elapsed() < Duration::from_secs(5)
+            {
+                thread::sleep(Duration::from_millis(10));
+            }
+        };
+        assert_eq!(
+            PrinterCore::reprint_job(u64::MAX),
+            Err(PrintError::JobNotFound)
+        );
+
+        // Bytes jobs need a retained payload
+        let keep = PrinterJobOptions {
+            name: Some("Kept".to_string()),
+            keep_spool_file: true,
+            ..PrinterJobOptions::none()
+        };
+        let kept_id = PrinterCore::print_bytes("Simulated Printer", b"%!PS", Some(keep)).unwrap();
+        let unkept_id = PrinterCore::print_bytes("Simulated Printer", b"%!PS", None).unwrap();
+        assert_eq!(
+            PrinterCore::reprint_job(kept_id),
+            Err(PrintError::InvalidParams)
+        );
+        wait(kept_id);
+        wait(unkept_id);
+        assert_eq!(
+            PrinterCore::reprint_job(unkept_id),
+            Err(PrintError::FileNotFound)
+        );
+
+        let reprint_id = PrinterCore::reprint_job(kept_id).unwrap();
+        let reprint = PrinterCore::get_job_status(reprint_id).unwrap();
+        assert_eq!(reprint.reprint_of, Some(kept_id));
+        assert_eq!(reprint.name, "Kept");
+        assert_eq!(reprint.media_type, "application/postscript");
+        assert_eq!(reprint.size_bytes, 4);
+
+        // File jobs fall back to the original file while it exists
+        let file = tempfile::NamedTempFile::with_suffix(".pdf").unwrap();
+        std::fs::write(file.path(), b"%PDF-1.4").unwrap();
+        let path = file.path().to_string_lossy().into_owned();
+        let file_id = PrinterCore::print_file("Simulated Printer", &path, None).unwrap();
+        wait(file_id);
+        let reprint_id = PrinterCore::reprint_job(file_id).unwrap();
+        let reprint = PrinterCore::get_job_status(reprint_id).unwrap();
+        assert_eq!(reprint.reprint_of, Some(file_id));
+        assert_eq!(reprint.source_path.as_deref(), Some(path.as_str()));
+        drop(file);
+        assert_eq!(
+            PrinterCore::reprint_job(file_id),
+            Err(PrintError::FileNotFound)
+        );
+
+        PrinterCore::configure(LibraryConfig::default()).unwrap();
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_job_dependencies() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        PrinterCore::configure(LibraryConfig {
+            simulation_delay: Duration::from_millis(200),
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+        let depends_on = |ids: &[JobId]| {
+            let ids: Vec<String> = ids.iter().map(JobId::to_string).collect();
+            Some(PrinterJobOptions::from_map(HashMap::from([(
+                "depends-on".to_string(),
+                ids.join(","),
+            )])))
+        };
+        let wait = |job_id| {
+            let start = Instant::now();
+            while PrinterCore::get_job_status(job_id).is_some_and(|job| !job.state.is_terminal())
+                && start.elapsed() < Duration::from_secs(5)
+            {
+                thread::sleep(Duration::from_millis(10));
+            }
+            PrinterCore::get_job_status(job_id).unwrap()
+        };
+        assert_eq!(
+            PrinterCore::print_bytes("Simulated Printer", b"data", depends_on(&[u64::MAX])),
+            Err(PrintError::JobNotFound)
+        );
+
+        // Dependents start after their dependencies complete
+        let first = PrinterCore::print_bytes("Simulated Printer", b"data", None).unwrap();
+        let second =
+            PrinterCore::print_bytes("Simulated Printer", b"data", depends_on(&[first])).unwrap();
+        let second_job = wait(second);
+        let first_job = wait(first);
+        assert_eq!(second_job.state, PrinterJobState::COMPLETED);
+        assert!(second_job.processed_at.unwrap() >= first_job.completed_at.unwrap());
+
+        // A failed dependency fails its dependents
+        let failed = PrinterCore::print_bytes("Simulated Printer", b"data", None).unwrap();
+        PrinterCore::cancel_all_jobs(None);
+        let dependent =
+            PrinterCore::print_bytes("Simulated Printer", b"data", depends_on(&[failed])).unwrap();
+        let dependent_job = wait(dependent);
+        assert_eq!(dependent_job.state, PrinterJobState::CANCELLED);
+        assert_eq!(
+            dependent_job.error_message,
+            Some(format!("Dependency job {} did not complete", failed))
+        );
+
+        PrinterCore::configure(LibraryConfig::default()).unwrap();
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_print_to_many() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        PrinterCore::configure(LibraryConfig {
+            simulation_delay: Duration::from_millis(20),
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+        let file = tempfile::NamedTempFile::with_suffix(".pdf").unwrap();
+        std::fs::write(file.path(), b"%PDF-1.4").unwrap();
+        let path = file.path().to_string_lossy().into_owned();
+        assert_eq!(
+            PrinterCore::print_to_many(&[], &path, None),
+            Err(PrintError::InvalidParams)
+        );
+
+        let printers =
+            ["Simulated Printer", "Missing Printer", "Simulated Printer"].map(String::from);
+        let keep = PrinterJobOptions {
+            keep_spool_file: true,
+            ..PrinterJobOptions::none()
+        };
+        let results = PrinterCore::print_to_many(&printers, &path, Some(keep)).unwrap();
+        assert_eq!(results[1], Err(PrintError::PrinterNotFound));
+        let (first, second) = (results[0].clone().unwrap(), results[2].clone().unwrap());
+        assert_ne!(first, second);
+
+        // Both jobs print the same spooled copy
+        let spool_path = PrinterCore::get_job_spool_file(first).unwrap();
+        assert_eq!(
+            PrinterCore::get_job_spool_file(second),
+            Some(spool_path.clone())
+        );
+        assert_eq!(std::fs::read(&spool_path).unwrap(), b"%PDF-1.4");
+        let job = PrinterCore::get_job_status(second).unwrap();
+        assert_eq!(job.media_type, "application/pdf");
+        assert_eq!(job.source_path.as_deref(), Some(path.as_str()));
+
+        PrinterCore::configure(LibraryConfig::default()).unwrap();
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
+        assert!(!spool_path.exists());
+    }
+
+    #[test]
+    #[serial]
+    fn test_print_through_alias() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        PrinterCore::configure(LibraryConfig {
+            default_job_options: HashMap::from([
+                ("copies".to_string(), "1".to_string()),
+                ("media".to_string(), "A4".to_string()),
+            ]),
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+        crate::aliases::define(crate::aliases::PrinterAlias {
+            name: "RECEIPT".to_string(),
+            printer: "Simulated Printer".to_string(),
+            default_options: HashMap::from([("copies".to_string(), "2".to_string())]),
+        })
+        .unwrap();
+
+        assert!(PrinterCore::printer_exists("RECEIPT"));
+        let job_id = PrinterCore::print_file("RECEIPT", "/tmp/test.pdf", None).unwrap();
+        let job = PrinterCore::get_job_status(job_id).unwrap();
+        assert_eq!(job.printer_name, "Simulated Printer");
+        // Alias defaults take precedence over library-wide ones
+        let properties = &job.job_options.raw_properties;
+        assert_eq!(properties.get("copies").map(String::as_str), Some("2"));
+        assert_eq!(properties.get("media").map(String::as_str), Some("A4"));
+
+        assert!(crate::aliases::remove("RECEIPT").unwrap());
+        assert!(!PrinterCore::printer_exists("RECEIPT"));
+        PrinterCore::configure(LibraryConfig::default()).unwrap();
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_resolve_job_options() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let options = |entries: &[(&str, &str)]| -> HashMap<String, String> {
+            entries
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect()
+        };
+        PrinterCore::configure(LibraryConfig {
+            default_job_options: options(&[("media", "A4"), ("sides", "one-sided")]),
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+        crate::aliases::set_printer_defaults(
+            "Simulated Printer",
+            options(&[("media", "Letter"), ("copies", "3")]),
+        )
+        .unwrap();
+        crate::aliases::define(crate::aliases::PrinterAlias {
+            name: "RECEIPT".to_string(),
+            printer: "Simulated Printer".to_string(),
+            default_options: options(&[("copies", "2")]),
+        })
+        .unwrap();
+
+        let resolved = PrinterCore::resolve_job_options(
+            "RECEIPT",
+            Some(PrinterJobOptions::from_map(options(&[
+                ("sides", "two-sided-long-edge"),
+                ("job-quality", "draft"),
+            ]))),
+        )
+        .unwrap();
+        assert_eq!(resolved.printer_name, "Simulated Printer");
+        let resolved_options = resolved.job_options.to_map();
+        for (key, value, source) in [
+            ("media", "Letter", OptionSource::Printer),
+            ("copies", "2", OptionSource::Alias),
+            ("sides", "two-sided-long-edge", OptionSource::Call),
+            ("job-quality", "draft", OptionSource::Call),
+            ("print-quality", "3", OptionSource::Derived),
+        ] {
+            assert_eq!(resolved_options.get(key).map(String::as_str), Some(value));
+            assert_eq!(resolved.sources.get(key), Some(&source), "{}", key);
+        }
+
+        // Jobs are sent with the same options
+        let job_id = PrinterCore::print_file("Simulated Printer", "/tmp/test.pdf", None).unwrap();
+        let job = PrinterCore::get_job_status(job_id).unwrap();
+        let properties = &job.job_options.raw_properties;
+        assert_eq!(properties.get("copies").map(String::as_str), Some("3"));
+        assert_eq!(properties.get("media").map(String::as_str), Some("Letter"));
+        assert_eq!(
+            properties.get("sides").map(String::as_str),
+            Some("one-sided")
+        );
+        assert_eq!(
+            PrinterCore::resolve_job_options("Missing Printer", None).unwrap_err(),
+            PrintError::PrinterNotFound
+        );
+
+        assert!(crate::aliases::remove("RECEIPT").unwrap());
+        crate::aliases::set_printer_defaults("Simulated Printer", HashMap::new()).unwrap();
+        PrinterCore::configure(LibraryConfig::default()).unwrap();
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_health_check() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        PrinterCore::configure(LibraryConfig {
+            worker_pool_size: Some(2),
+            simulation_delay: Duration::from_secs(30),
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+        PrinterCore::print_file("Simulated Printer", "/tmp/test.pdf", None).unwrap();
+
+        let report = PrinterCore::health_check(None);
+        assert!(report.healthy);
+        assert!(report.spooler_reachable);
+        assert_eq!(report.jobs.pending + report.jobs.processing, 1);
+        assert_eq!(report.worker_pool_size, Some(2));
+        assert_eq!(report.printers.len(), 1);
+        assert!(report.printers[0].online);
+
+        let missing = ["Missing Printer".to_string()];
+        let report = PrinterCore::health_check(Some(&missing));
+        assert!(!report.printers[0].online);
+        // Printer status doesn't make the library unhealthy
+        assert!(report.healthy);
+
+        PrinterCore::configure(LibraryConfig::default()).unwrap();
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_stalled_job_watchdog() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        PrinterCore::cleanup_old_jobs(0);
+        assert!(PrinterCore::configure(LibraryConfig {
+            stall_threshold: Some(Duration::ZERO),
+            ..LibraryConfig::default()
+        })
+        .is_err());
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let received = Arc::clone(&events);
+        PrinterCore::set_job_stalled_callback(Some(Arc::new(move |event: &JobStalledEvent| {
+            received.lock().unwrap().push(event.clone());
+        })));
+        let config = LibraryConfig {
+            simulation_delay: Duration::from_secs(30),
+            stall_threshold: Some(Duration::from_millis(200)),
+            ..LibraryConfig::default()
+        };
+
+        // Flagged jobs keep running
+        PrinterCore::configure(config.clone()).unwrap();
+        let flagged = PrinterCore::print_bytes("Simulated Printer", b"stuck", None).unwrap();
+        thread::sleep(Duration::from_millis(600));
+        let job = PrinterCore::get_job_status(flagged).unwrap();
+        assert_eq!(job.state, PrinterJobState::PROCESSING);
+        assert!(job.stalled);
+        {
+            let events = events.lock().unwrap();
+            assert_eq!(events.len(), 1);
+            assert_eq!(events[0].job_id, flagged);
+            assert_eq!(events[0].action, StallAction::Flag);
+            assert!(events[0].processing_for >= Duration::from_millis(200));
+        }
+
+        // A stalled job is retried once, then its retry is cancelled
+        PrinterCore::configure(LibraryConfig {
+            stall_action: StallAction::Retry,
+            ..config
+        })
+        .unwrap();
+        let retried = PrinterCore::print_bytes("Simulated Printer", b"stuck", None).unwrap();
+        thread::sleep(Duration::from_millis(1500));
+        let job = PrinterCore::get_job_status(retried).unwrap();
+        assert_eq!(job.state, PrinterJobState::CANCELLED);
+        assert!(job.stalled);
+
+        let events = events.lock().unwrap().clone();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[1].job_id, retried);
+        assert_eq!(events[1].action, StallAction::Retry);
+        let retry_id = events[1].retry_job_id.unwrap();
+        let retry = PrinterCore::get_job_status(retry_id).unwrap();
+        assert_eq!(retry.reprint_of, Some(retried));
+        assert_eq!(retry.state, PrinterJobState::CANCELLED);
+        assert_eq!(events[2].job_id, retry_id);
+        assert_eq!(events[2].action, StallAction::Cancel);
+        assert_eq!(events[2].retry_job_id, None);
+
+        PrinterCore::set_job_stalled_callback(None);
+        PrinterCore::configure(LibraryConfig::default()).unwrap();
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_queue_limits() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        PrinterCore::cleanup_old_jobs(0);
+        assert!(PrinterCore::configure(LibraryConfig {
+            max_pending_jobs: Some(0),
+            ..LibraryConfig::default()
+        })
+        .is_err());
+
+        PrinterCore::configure(LibraryConfig {
+            simulation_delay: Duration::from_secs(30),
+            max_queued_jobs_per_printer: Some(2),
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+        for _ in 0..2 {
+            PrinterCore::print_bytes("Simulated Printer", b"queued", None).unwrap();
+        }
+        assert_eq!(
+            PrinterCore::print_bytes("Simulated Printer", b"queued", None),
+            Err(PrintError::QueueFull)
+        );
+        assert_eq!(
+            PrinterCore::print_file("Simulated Printer", "/tmp/test.pdf", None),
+            Err(PrintError::QueueFull)
+        );
+
+        // A blocked submission goes through once a job finishes
+        PrinterCore::configure(LibraryConfig {
+            simulation_delay: Duration::from_secs(30),
+            max_pending_jobs: Some(2),
+            queue_full_timeout: Some(Duration::from_secs(5)),
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+        let cancel = thread::spawn(|| {
+            thread::sleep(Duration::from_millis(200));
+            PrinterCore::cancel_all_jobs(None)
+        });
+        let start = Instant::now();
+        assert!(PrinterCore::print_bytes("Simulated Printer", b"queued", None).is_ok());
+        assert!(start.elapsed() >= Duration::from_millis(200));
+        assert_eq!(cancel.join().unwrap(), 2);
+
+        PrinterCore::configure(LibraryConfig::default()).unwrap();
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_dedupe_window() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        PrinterCore::cleanup_old_jobs(0);
+        let options = |key: &str, mode: &str| {
+            PrinterJobOptions::from_map(HashMap::from([
+                ("dedupe-key".to_string(), key.to_string()),
+                ("dedupe-window-ms".to_string(), "300".to_string()),
+                ("dedupe-mode".to_string(), mode.to_string()),
+            ]))
+        };
+
+        // A failed submission doesn't hold its key
+        assert_eq!(
+            PrinterCore::print_file(
+                "Simulated Printer",
+                "/tmp/nonexistent.pdf",
+                Some(options("receipt-1", "coalesce"))
+            ),
+            Err(PrintError::FileNotFound)
+        );
+
+        let first = PrinterCore::print_bytes(
+            "Simulated Printer",
+            b"receipt",
+            Some(options("receipt-1", "coalesce")),
+        )
+        .unwrap();
+        let second = PrinterCore::print_bytes(
+            "Simulated Printer",
+            b"receipt",
+            Some(options("receipt-1", "coalesce")),
+        )
+        .unwrap();
+        assert_eq!(first, second);
+        assert_eq!(
+            PrinterCore::print_file(
+                "Simulated Printer",
+                "/tmp/test.pdf",
+                Some(options("receipt-1", "reject"))
+            ),
+            Err(PrintError::DuplicateJob)
+        );
+        let other = PrinterCore::print_bytes(
+            "Simulated Printer",
+            b"receipt",
+            Some(options("receipt-2", "reject")),
+        )
+        .unwrap();
+        assert_ne!(other, first);
+
+        // The key is free again once the window has passed
+        thread::sleep(Duration::from_millis(400));
+        let later = PrinterCore::print_bytes(
+            "Simulated Printer",
+            b"receipt",
+            Some(options("receipt-1", "reject")),
+        )
+        .unwrap();
+        assert_ne!(later, first);
+
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_print_test_page() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        assert_eq!(
+            PrinterCore::print_test_page("Missing Printer", None),
+            Err(PrintError::PrinterNotFound)
+        );
+
+        let job_id = PrinterCore::print_test_page("Simulated Printer", None).unwrap();
+        let job = PrinterCore::get_job_status(job_id).unwrap();
+        assert_eq!(job.name, "Test Page");
+        assert_eq!(job.media_type, "application/pdf");
+        assert_eq!(job.media_type_source, MediaTypeSource::Option);
+        assert_eq!(job.total_pages, Some(1));
+
+        let named = PrinterJobOptions::with_name_and_properties(
+            "Install check".to_string(),
+            HashMap::new(),
+        );
+        let job_id = PrinterCore::print_test_page("Simulated Printer", Some(named)).unwrap();
+        assert_eq!(
+            PrinterCore::get_job_status(job_id).unwrap().name,
+            "Install check"
+        );
+
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_dry_run() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let options = |pairs: &[(&str, &str)]| {
+            Some(PrinterJobOptions::from_map(
+                pairs
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), value.to_string()))
+                    .collect(),
+            ))
+        };
+        let pdf = crate::document::test_page_pdf("Dry run", &[]);
+        let jobs_before = PrinterCore::get_active_jobs().len();
+
+        let result = PrinterCore::dry_run_bytes(
+            "Simulated Printer",
+            &pdf,
+            options(&[
+                ("document-format", "application/pdf"),
+                ("copies", "3"),
+                ("sides", "two-sided-long-edge"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(result.printer_name, "Simulated Printer");
+        assert_eq!(result.media_type, "application/pdf");
+        assert_eq!(result.document_pages, Some(1));
+        assert_eq!((result.pages, result.sheets), (Some(3), Some(3)));
+        assert!(result.capabilities.is_some());
+        assert!(result.unsupported_options.is_empty());
+        // Nothing was spooled or tracked
+        assert_eq!(PrinterCore::get_active_jobs().len(), jobs_before);
+
+        let result = PrinterCore::dry_run_bytes(
+            "Simulated Printer",
+            b"^XA^XZ",
+            options(&[("sides", "booklet"), ("copies", "0")]),
+        )
+        .unwrap();
+        assert_eq!(result.unsupported_options.len(), 2);
+        assert!(result.unsupported_options[0].starts_with("copies=0"));
+        assert!(result.unsupported_options[1].starts_with("sides=booklet"));
+
+        // Documents are always validated
+        assert_eq!(
+            PrinterCore::dry_run_bytes("Simulated Printer", &pdf[..pdf.len() - 20], None)
+                .unwrap_err(),
+            PrintError::InvalidDocument
+        );
+        assert_eq!(
+            PrinterCore::dry_run_file("Simulated Printer", "/tmp/nonexistent.pdf", None)
+                .unwrap_err(),
+            PrintError::FileNotFound
+        );
+        assert_eq!(
+            PrinterCore::dry_run_file("Missing Printer", "/tmp/test.pdf", None).unwrap_err(),
+            PrintError::PrinterNotFound
+        );
+    }
+
+    #[test]
+    fn test_capabilities_from_ipp_response() {
+        use crate::ipp::{IppAttribute, IppRequest, IppResponse, IppValue, TAG_PRINTER_ATTRIBUTES};
+
+        let mut message = IppRequest::new(crate::ipp::STATUS_OK, "ipp://localhost/");
+        message.add_attribute(
+            TAG_PRINTER_ATTRIBUTES,
+            IppAttribute::new(
+                "copies-supported",
+                IppValue::RangeOfInteger {
+                    lower: 1,
+                    upper: 99,
+                },
+            ),
+        );
+        message.add_attribute(
+            TAG_PRINTER_ATTRIBUTES,
+            IppAttribute::new(
+                "sides-supported",
+                IppValue::Keyword("one-sided".to_string()),
+            ),
+        );
+        message.add_attribute(
+            TAG_PRINTER_ATTRIBUTES,
+            IppAttribute {
+                name: "print-quality-supported".to_string(),
+                values: vec![IppValue::Enum(4), IppValue::Enum(5)],
+            },
+        );
+        let response = IppResponse::decode(&message.encode()).unwrap();
+        let capabilities = PrinterCapabilities::from_ipp_response(&response);
+
+        assert_eq!(capabilities.copies, Some((1, 99)));
+        assert_eq!(capabilities.supported["print-quality"], ["4", "5"]);
+        let properties = HashMap::from([
+            ("copies".to_string(), "100".to_string()),
+            ("print-quality".to_string(), "5".to_string()),
+            ("sides".to_string(), "two-sided-long-edge".to_string()),
+        ]);
+        assert_eq!(
+            capabilities.unsupported_options(&properties, "application/pdf"),
+            [
+                "copies=100 (supported: 1-99)",
+                "sides=two-sided-long-edge (supported: one-sided)"
+            ]
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_estimate_job() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let options = |pairs: &[(&str, &str)]| {
+            Some(PrinterJobOptions::from_map(
+                pairs
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), value.to_string()))
+                    .collect(),
+            ))
+        };
+        let pdf = crate::document::test_page_pdf("Estimate", &[]);
+
+        let estimate = PrinterCore::estimate_bytes_job(
+            "Simulated Printer",
+            &pdf,
+            options(&[
+                ("document-format", "application/pdf"),
+                ("copies", "4"),
+                ("sides", "two-sided-long-edge"),
+                ("print-color-mode", "monochrome"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(estimate.document_pages, Some(1));
+        assert_eq!((estimate.pages, estimate.sheets), (Some(4), Some(4)));
+        assert_eq!(estimate.copies, 4);
+        assert!(estimate.duplex);
+        assert_eq!(estimate.color, JobColor::Monochrome);
+
+        // The simulated printer defaults to color
+        let estimate =
+            PrinterCore::estimate_job("Simulated Printer", "/tmp/test.pdf", None).unwrap();
+        assert_eq!(estimate.color, JobColor::Color);
+        assert_eq!(estimate.copies, 1);
+        assert!(!estimate.duplex);
+
+        assert_eq!(
+            JobColor::from_mode("process-monochrome"),
+            JobColor::Monochrome
+        );
+        assert_eq!(JobColor::from_mode("KGray"), JobColor::Monochrome);
+        assert_eq!(JobColor::from_mode("auto"), JobColor::Color);
+    }
+
+    #[test]
+    #[serial]
+    fn test_job_approval() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let held_dir = tempfile::tempdir().unwrap();
+        PrinterCore::configure(LibraryConfig {
+            held_jobs_dir: Some(held_dir.path().to_path_buf()),
+            simulation_delay: Duration::from_millis(100),
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+
+        let mut options = PrinterJobOptions::from_map(HashMap::from([
+            ("require-approval".to_string(), "true".to_string()),
+            ("copies".to_string(), "2".to_string()),
+        ]));
+        assert!(options.require_approval);
+        assert_eq!(
+            PrinterJobOptions::from_map(options.to_map()).to_map(),
+            options.to_map()
+        );
+        options.name = Some("Payroll".to_string());
+        let approved =
+            PrinterCore::print_bytes("Simulated Printer", b"approve me", Some(options.clone()))
+                .unwrap();
+        let rejected =
+            PrinterCore::print_bytes("Simulated Printer", b"reject me", Some(options)).unwrap();
+
+        let held = PrinterCore::get_held_jobs(Some("Simulated Printer"));
+        assert_eq!(
+            held.iter().map(|job| job.id).collect::<Vec<_>>(),
+            [approved, rejected]
+        );
+        assert_eq!(held[0].state, PrinterJobState::HELD_FOR_APPROVAL);
+        // Held jobs aren't queued, so they don't count towards queue limits
+        assert_eq!(queue_depth(&JOB_TRACKER), 0);
+
+        // After a restart the held jobs are restored from disk
+        JOB_TRACKER.remove(approved);
+        JOB_TRACKER.remove(rejected);
+        assert_eq!(PrinterCore::restore_held_jobs(), 2);
+        assert_eq!(PrinterCore::restore_held_jobs(), 0);
+        let restored = PrinterCore::get_job_status(approved).unwrap();
+        assert_eq!(restored.state, PrinterJobState::HELD_FOR_APPROVAL);
+        assert_eq!(restored.name, "Payroll");
+        assert_eq!(restored.job_options.raw_properties["copies"], "2");
+
+        PrinterCore::reject_job(rejected, Some("Not authorised")).unwrap();
+        let job = PrinterCore::get_job_status(rejected).unwrap();
+        assert_eq!(job.state, PrinterJobState::CANCELLED);
+        assert_eq!(job.error_message.as_deref(), Some("Not authorised"));
+        assert_eq!(
+            PrinterCore::reject_job(rejected, None),
+            Err(PrintError::InvalidParams)
+        );
+        assert_eq!(
+            PrinterCore::approve_job(rejected),
+            Err(PrintError::InvalidParams)
+        );
+        assert_eq!(
+            PrinterCore::approve_job(999_999),
+            Err(PrintError::JobNotFound)
+        );
+
+        PrinterCore::approve_job(approved).unwrap();
+        assert!(PrinterCore::get_held_jobs(None).is_empty());
+        while PrinterCore::get_job_status(approved).is_some_and(|job| !job.state.is_terminal()) {
+            thread::sleep(Duration::from_millis(20));
+        }
+        assert_eq!(
+            PrinterCore::get_job_status(approved).unwrap().state,
+            PrinterJobState::COMPLETED
+        );
+
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
+        // The approved job's payload is removed once it has printed
+        assert_eq!(std::fs::read_dir(held_dir.path()).unwrap().count(), 0);
+        PrinterCore::configure(LibraryConfig::default()).unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn test_find_jobs_by_metadata() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let metadata = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect()
+        };
+        let submit = |pairs: &[(&str, &str)]| {
+            let mut options = PrinterJobOptions::none();
+            options.metadata = metadata(pairs);
+            PrinterCore::print_bytes("Simulated Printer", b"tagged", Some(options)).unwrap()
+        };
+        let first = submit(&[("orderId", "A-1"), ("customerId", "42")]);
+        let second = submit(&[("orderId", "A-1"), ("customerId", "7")]);
+        let other = submit(&[("orderId", "B-2")]);
+
+        let ids = |jobs: Vec<PrinterJob>| jobs.iter().map(|job| job.id).collect::<Vec<_>>();
+        assert_eq!(
+            ids(PrinterCore::find_jobs(
+                None,
+                &metadata(&[("orderId", "A-1")])
+            )),
+            [first, second]
+        );
+        assert_eq!(
+            ids(PrinterCore::find_jobs(
+                Some("Simulated Printer"),
+                &metadata(&[("orderId", "A-1"), ("customerId", "7")])
+            )),
+            [second]
+        );
+        assert!(
+            PrinterCore::find_jobs(Some("Other Printer"), &metadata(&[("orderId", "B-2")]))
+                .is_empty()
+        );
+        assert_eq!(
+            PrinterCore::get_job_status(other)
+                .unwrap()
+                .job_options
+                .metadata["orderId"],
+            "B-2"
+        );
+
+        // Metadata round-trips through raw properties as one JSON object
+        let options = PrinterJobOptions::from_map(HashMap::from([(
+            "job-metadata".to_string(),
+            r#"{"orderId":"A-1"}"#.to_string(),
+        )]));
+        assert_eq!(options.metadata, metadata(&[("orderId", "A-1")]));
+        assert!(!options.raw_properties.contains_key("job-metadata"));
+        assert_eq!(
+            PrinterJobOptions::from_map(options.to_map()).metadata,
+            options.metadata
+        );
+
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_query_jobs() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        PrinterCore::configure(LibraryConfig {
+            simulation_delay: Duration::from_secs(30),
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+        let start = SystemTime::now();
+        let ids: Vec<JobId> = (0..5)
+            .map(|_| PrinterCore::print_bytes("Simulated Printer", b"query", None).unwrap())
+            .collect();
+        PrinterCore::cancel_all_jobs(None);
+        let queued = PrinterCore::print_bytes("Simulated Printer", b"queued", None).unwrap();
+        let job_ids = |page: &JobPage| page.jobs.iter().map(|job| job.id).collect::<Vec<_>>();
+
+        // Newest first by default, paged
+        let page = PrinterCore::query_jobs(&JobQuery {
+            since: Some(start),
+            offset: 1,
+            limit: Some(2),
+            ..JobQuery::default()
+        });
+        assert_eq!(page.total, 6);
+        assert_eq!(job_ids(&page), [ids[4], ids[3]]);
+
+        let page = PrinterCore::query_jobs(&JobQuery {
+            printer_name: Some("Simulated Printer".to_string()),
+            states: vec![PrinterJobState::CANCELLED],
+            since: Some(start),
+            sort: JobSort::CreatedAsc,
+            limit: Some(3),
+            ..JobQuery::default()
+        });
+        assert_eq!(page.total, 5);
+        assert_eq!(job_ids(&page), ids[..3]);
+
+        let page = PrinterCore::query_jobs(&JobQuery {
+            since: Some(start),
+            sort: JobSort::CompletedDesc,
+            ..JobQuery::default()
+        });
+        assert_eq!(page.jobs.last().unwrap().id, queued);
+        assert!(PrinterCore::query_jobs(&JobQuery {
+            until: Some(start),
+            printer_name: Some("Simulated Printer".to_string()),
+            ..JobQuery::default()
+        })
+        .jobs
+        .iter()
+        .all(|job| !ids.contains(&job.id)));
+
+        assert_eq!(
+            PrinterCore::get_active_jobs_for_printer("Simulated Printer")
+                .iter()
+                .map(|job| job.id)
+                .collect::<Vec<_>>(),
+            [queued]
+        );
+        assert_eq!(
+            PrinterJobState::parse("held-for-approval"),
+            Some(PrinterJobState::HELD_FOR_APPROVAL)
+        );
+        assert_eq!(JobSort::parse("completed-asc"), Some(JobSort::CompletedAsc));
+
+        PrinterCore::configure(LibraryConfig::default()).unwrap();
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_export_job_history() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let before = PrinterCore::print_bytes("Simulated Printer", b"old", None).unwrap();
+        let since = SystemTime::now();
+        let first = PrinterCore::print_bytes("Simulated Printer", b"first", None).unwrap();
+        let second = PrinterCore::print_bytes("Simulated Printer", b"second", None).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        assert_eq!(
+            PrinterCore::export_job_history(&path, ExportFormat::Jsonl, Some(since)).unwrap(),
+            2
+        );
+        let ids: Vec<u64> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| {
+                serde_json::from_str::<serde_json::Value>(line).unwrap()["id"]
+                    .as_u64()
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(ids, [first, second]);
+        assert!(!ids.contains(&before));
+
+        let path = dir.path().join("history.csv");
+        let written = PrinterCore::export_job_history(&path, ExportFormat::Csv, None).unwrap();
+        assert!(written >= 3);
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap().lines().count(),
+            written + 1
+        );
+
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_webhook_job_events() {
+        use std::io::{Read, Write};
+
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut config = crate::webhooks::WebhookConfig::new(&format!(
+            "http://{}/events",
+            listener.local_addr().unwrap()
+        ));
+        config.events = vec![WebhookEvent::Submitted, WebhookEvent::Completed];
+        crate::webhooks::set(Some(config)).unwrap();
+
+        let job_id = PrinterCore::print_bytes("Simulated Printer", b"hooked", None).unwrap();
+        let mut events = Vec::new();
+        for _ in 0..2 {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream
+                .set_read_timeout(Some(Duration::from_secs(5)))
+                .unwrap();
+            let mut request = String::new();
+            let mut buf = [0u8; 4096];
+            let body = loop {
+                let read = stream.read(&mut buf).unwrap();
+                assert!(read > 0, "Connection closed mid-request");
+                request.push_str(std::str::from_utf8(&buf[..read]).unwrap());
+                if let Some((headers, body)) = request.split_once("\r\n\r\n") {
+                    let length: usize = headers
+                        .lines()
+                        .find_map(|line| line.strip_prefix("Content-Length: "))
+                        .unwrap()
+                        .parse()
+                        .unwrap();
+                    if body.len() >= length {
+                        break body.to_string();
+                    }
+                }
+            };
+            stream
+                .write_all(b"HTTP/1.1 204 No Content\r\n\r\n")
+                .unwrap();
+            events.push(serde_json::from_str::<serde_json::Value>(&body).unwrap());
+        }
+        crate::webhooks::set(None).unwrap();
+
+        assert_eq!(events[0]["event"], "job.submitted");
+        assert_eq!(events[1]["event"], "job.completed");
+        assert_eq!(events[1]["job"]["id"], job_id);
+        assert_eq!(events[1]["job"]["state"], "completed");
+
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_job_event_subscription() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let (sender, receiver) = mpsc::channel();
+        let sender = Mutex::new(sender);
+        let subscription = PrinterCore::subscribe_to_job_events(Arc::new(move |event, job| {
+            let _ = sender.lock().unwrap().send((event, job.id));
+        }));
+
+        let job_id = PrinterCore::print_bytes("Simulated Printer", b"events", None).unwrap();
+        let timeout = Duration::from_secs(10);
+        assert_eq!(
+            receiver.recv_timeout(timeout).unwrap(),
+            (WebhookEvent::Submitted, job_id)
+        );
+        let (event, finished) = receiver.recv_timeout(timeout).unwrap();
+        assert_eq!(finished, job_id);
+        assert_ne!(event, WebhookEvent::Submitted);
+
+        assert!(PrinterCore::unsubscribe_from_job_events(subscription));
+        assert!(!PrinterCore::unsubscribe_from_job_events(subscription));
+        PrinterCore::print_bytes("Simulated Printer", b"quiet", None).unwrap();
+        assert!(receiver.recv_timeout(Duration::from_millis(200)).is_err());
+
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_remote_printer_forwards_jobs() {
+        use std::io::{Read, Write};
+
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/api", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for response in [r#"{"id":7}"#, r#"{"id":7,"state":"completed"}"#] {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut raw = Vec::new();
+                let mut chunk = [0u8; 1024];
+                while !raw.windows(4).any(|w| w == b"\r\n\r\n") {
+                    let read = stream.read(&mut chunk).unwrap();
+                    raw.extend_from_slice(&chunk[..read]);
+                }
+                let head = String::from_utf8_lossy(&raw).to_string();
+                let length: usize = head
+                    .lines()
+                    .find_map(|line| line.strip_prefix("Content-Length: "))
+                    .map_or(0, |length| length.trim().parse().unwrap());
+                let header_end = head.find("\r\n\r\n").unwrap() + 4;
+                while raw.len() < header_end + length {
+                    let read = stream.read(&mut chunk).unwrap();
+                    raw.extend_from_slice(&chunk[..read]);
+                }
+                requests.push((head, raw[header_end..].to_vec()));
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+                    response.len(),
+                    response
+                )
+                .unwrap();
+            }
+            requests
+        });
+
+        let mut remote = crate::remote::RemotePrinter::new("Warehouse Printer", &url);
+        remote.printer = Some("Label Printer".to_string());
+        remote.auth_token = Some("secret".to_string());
+        remote.poll_interval = Duration::from_millis(100);
+        crate::remote::define(remote).unwrap();
+        assert!(PrinterCore::printer_exists("Warehouse Printer"));
+        assert!(PrinterCore::get_all_printer_names().contains(&"Warehouse Printer".to_string()));
+
+        let options = PrinterJobOptions {
+            name: Some("Labels".to_string()),
+            raw_properties: HashMap::from([("copies".to_string(), "2".to_string())]),
+            ..PrinterJobOptions::none()
+        };
+        let job_id =
+            PrinterCore::print_bytes("Warehouse Printer", b"^XA^XZ", Some(options)).unwrap();
+
+        let requests = server.join().unwrap();
+        let (submit, body) = &requests[0];
+        assert!(
+            submit.starts_with("POST /api/printers/Label%20Printer/jobs?copies=2&job-name=Labels ")
+        );
+        assert!(submit.contains("Authorization: Bearer secret"));
+        assert_eq!(body, b"^XA^XZ");
+        assert!(requests[1].0.starts_with("GET /api/jobs/7 "));
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while PrinterCore::get_job_status(job_id).unwrap().state != PrinterJobState::COMPLETED {
+            assert!(Instant::now() < deadline, "remote job never completed");
+            thread::sleep(Duration::from_millis(50));
+        }
+
+        assert!(crate::remote::remove("Warehouse Printer"));
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_wake_printer_needs_mac_address() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let options = WakeOptions::default();
+        let err = PrinterCore::wake_printer("Simulated Printer", &options).unwrap_err();
+        assert!(err.contains("setPrinterMacAddress"));
+        assert!(PrinterCore::wake_printer("Missing Printer", &options).is_err());
+
+        crate::power::set_mac_address("Simulated Printer", Some("00:1b:63:84:45:e6")).unwrap();
+        assert_eq!(
+            PrinterCore::wake_printer("Simulated Printer", &options),
+            Ok(true)
+        );
+        crate::power::set_mac_address("Simulated Printer", None).unwrap();
+
+        let with_mac = WakeOptions {
+            mac: Some("not-a-mac".to_string()),
+            ..WakeOptions::default()
+        };
+        assert!(PrinterCore::wake_printer("Simulated Printer", &with_mac).is_err());
+
+        let status = PrinterCore::get_power_status("Simulated Printer").unwrap();
+        assert_eq!(status.asleep, Some(false));
+        assert!(status.reachable);
+    }
+
+    #[test]
+    #[serial]
+    fn test_install_and_remove_printer() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let drivers = PrinterCore::list_drivers().unwrap();
+        assert!(drivers
+            .iter()
+            .any(|driver| driver.name == "Generic / Text Only"));
+
+        let mut installation = PrinterInstallation {
+            name: "Receiving Dock".to_string(),
+            port: "FILE:".to_string(),
+            driver: "Missing Driver".to_string(),
+            location: Some("Building 2".to_string()),
+            comment: None,
+        };
+        assert!(PrinterCore::install_printer(&installation).is_err());
+        installation.driver = "Generic / Text Only".to_string();
+        PrinterCore::install_printer(&installation).unwrap();
+        assert!(PrinterCore::install_printer(&installation).is_err());
+
+        let printer = PrinterCore::find_printer_by_name("Receiving Dock").unwrap();
+        assert_eq!(printer.port_name, "FILE:");
+        assert_eq!(printer.location, "Building 2");
+        assert!(PrinterCore::get_all_printer_names().contains(&"Receiving Dock".to_string()));
+
+        assert!(PrinterCore::remove_printer("Simulated Printer").is_err());
+        PrinterCore::remove_printer("Receiving Dock").unwrap();
+        assert!(!PrinterCore::printer_exists("Receiving Dock"));
+        assert!(PrinterCore::remove_printer("Receiving Dock").is_err());
+
+        installation.name = "Bad\\Name".to_string();
+        assert!(PrinterCore::install_printer(&installation).is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn test_printers_by_location_and_tag() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        PrinterCore::install_printer(&PrinterInstallation {
+            name: "Floor Three".to_string(),
+            port: "FILE:".to_string(),
+            driver: "Generic / Text Only".to_string(),
+            location: Some("Building 2, 3rd Floor".to_string()),
+            comment: None,
+        })
+        .unwrap();
+        let names = |printers: Vec<Printer>| -> Vec<String> {
+            printers.into_iter().map(|printer| printer.name).collect()
+        };
+
+        assert_eq!(
+            names(PrinterCore::get_printers_by_location("3rd floor")),
+            vec!["Floor Three"]
+        );
+        assert!(PrinterCore::get_printers_by_location(" ").is_empty());
+
+        crate::aliases::tag_printer("Floor Three", &["copy-room".to_string()]).unwrap();
+        assert_eq!(
+            names(PrinterCore::get_printers_by_tag("Copy-Room")),
+            vec!["Floor Three"]
+        );
+        // Tags of removed printers are kept but not listed
+        PrinterCore::remove_printer("Floor Three").unwrap();
+        assert!(PrinterCore::get_printers_by_tag("copy-room").is_empty());
+
+        crate::aliases::untag_printer("Floor Three", None).unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn test_cups_queue_administration() {
+        use printers::common::base::printer::PrinterState;
+
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let mut queue = CupsQueue {
+            name: "Front Desk".to_string(),
+            device_uri: "ipp://10.0.0.5/ipp/print".to_string(),
+            model: Some("everywhere".to_string()),
+            enabled: true,
+            ..CupsQueue::default()
+        };
+        assert!(PrinterCore::add_cups_queue(&queue).is_err());
+        queue.name = "Front_Desk".to_string();
+        queue.ppd = Some(PathBuf::from("/nonexistent/front-desk.ppd"));
+        assert!(PrinterCore::add_cups_queue(&queue).is_err());
+        queue.ppd = None;
+        PrinterCore::add_cups_queue(&queue).unwrap();
+
+        let printer = PrinterCore::find_printer_by_name("Front_Desk").unwrap();
+        assert_eq!(printer.uri, "ipp://10.0.0.5/ipp/print");
+        assert!(matches!(printer.state, PrinterState::READY));
+
+        PrinterCore::set_queue_enabled("Front_Desk", false).unwrap();
+        let printer = PrinterCore::find_printer_by_name("Front_Desk").unwrap();
+        assert!(matches!(printer.state, PrinterState::PAUSED));
+        assert!(PrinterCore::set_queue_enabled("Missing_Queue", true).is_err());
+
+        PrinterCore::delete_cups_queue("Front_Desk").unwrap();
+        assert!(!PrinterCore::printer_exists("Front_Desk"));
+    }
+
+    #[test]
+    #[serial]
+    fn test_access_control_round_trips() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let access = PrinterCore::get_access_control("Simulated Printer").unwrap();
+        assert_eq!(access.shared, Some(false));
+
+        let access = PrinterCore::set_access_control(
+            "Simulated Printer",
+            &AccessControl {
+                shared: Some(true),
+                allowed_users: Some(vec!["alice".to_string(), "bob".to_string()]),
+                ..AccessControl::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(access.shared, Some(true));
+        assert_eq!(access.allowed_users.unwrap().len(), 2);
+
+        let access = PrinterCore::set_access_control(
+            "Simulated Printer",
+            &AccessControl {
+                denied_users: Some(vec!["guest".to_string()]),
+                ..AccessControl::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(access.shared, Some(true));
+        assert_eq!(access.allowed_users, Some(Vec::new()));
+        assert_eq!(access.denied_users, Some(vec!["guest".to_string()]));
+        assert!(PrinterCore::get_access_control("Missing Printer").is_err());
+
+        SIMULATED_ACCESS.lock().unwrap().clear();
+    }
+
+    #[test]
+    #[serial]
+    fn test_job_events_are_audited() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+        assert!(PrinterCore::verify_audit_log(None).is_err());
+        PrinterCore::configure(LibraryConfig {
+            audit_log: Some(dir.path().join("missing").join("audit.log")),
+            ..LibraryConfig::default()
+        })
+        .unwrap_err();
+        PrinterCore::configure(LibraryConfig {
+            audit_log: Some(path.clone()),
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+
+        let job = PrinterJob::new(
+            1,
+            "Audited".to_string(),
+            "text/plain".to_string(),
+            "Simulated Printer".to_string(),
+        );
+        notify_job_event(WebhookEvent::Submitted, &job);
+        notify_job_event(WebhookEvent::Completed, &job);
+        PrinterCore::configure(LibraryConfig::default()).unwrap();
+
+        let verification = PrinterCore::verify_audit_log(Some(&path)).unwrap();
+        assert!(verification.valid);
+        assert_eq!(verification.records, 2);
+        let log = std::fs::read_to_string(&path).unwrap();
+        assert!(log.contains("\"event\":\"job.completed\""));
+        assert!(log.contains("\"printer\":\"Simulated Printer\""));
+    }
+
+    #[test]
+    #[serial]
+    fn test_job_data_redaction() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        PrinterCore::configure(LibraryConfig {
+            redact_job_data: true,
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+        let named = |name: &str, redact| PrinterJobOptions {
+            name: Some(name.to_string()),
+            redact,
+            ..PrinterJobOptions::none()
+        };
+
+        let file = tempfile::NamedTempFile::with_suffix(".txt").unwrap();
+        std::fs::write(file.path(), "Salary review").unwrap();
+        let path = file.path().to_string_lossy().into_owned();
+        let job_id = PrinterCore::print_file(
+            "Simulated Printer",
+            &path,
+            Some(named("Salary review - J. Doe", None)),
+        )
+        .unwrap();
+        let job = PrinterCore::get_job_status(job_id).unwrap();
+        assert!(job.name.starts_with(REDACTED_PREFIX));
+        assert_eq!(job.name, redacted("Salary review - J. Doe"));
+        assert_eq!(job.job_options.name, Some(job.name.clone()));
+        assert_eq!(job.source_path, Some(redacted(&path)));
+        assert!(!printer_job_to_json(&job).unwrap().contains("J. Doe"));
+
+        // Jobs can opt out, and redacted values aren't hashed again
+        let kept = PrinterCore::print_bytes(
+            "Simulated Printer",
+            b"%!PS",
+            Some(named("Lunch menu", Some(false))),
+        )
+        .unwrap();
+        assert_eq!(
+            PrinterCore::get_job_status(kept).unwrap().name,
+            "Lunch menu"
+        );
+        assert_eq!(redacted(&job.name), job.name);
+
+        // Per-job redaction without the global setting
+        PrinterCore::configure(LibraryConfig::default()).unwrap();
+        let redacted_id = PrinterCore::print_bytes(
+            "Simulated Printer",
+            b"%!PS",
+            Some(named("Lunch menu", Some(true))),
+        )
+        .unwrap();
+        assert_eq!(
+            PrinterCore::get_job_status(redacted_id).unwrap().name,
+            redacted("Lunch menu")
+        );
+        assert_eq!(
+            PrinterJobOptions::from_map(named("x", Some(false)).to_map()).redact,
+            Some(false)
+        );
+
+        // Hashes are keyed, and errors don't quote the file
+        let hash = redacted("Lunch menu");
+        set_redaction_key(Some(&[7; 16])).unwrap();
+        let keyed = redacted("Lunch menu");
+        assert_ne!(keyed, hash);
+        set_redaction_key(Some(&[7; 16])).unwrap();
+        assert_eq!(redacted("Lunch menu"), keyed);
+        assert!(set_redaction_key(Some(&[7; 8])).is_err());
+        set_redaction_key(None).unwrap();
+        let missing = "/no/such/dir/Salary review - J. Doe.pdf";
+        let error =
+            PrinterCore::execute_real_print_job("Simulated Printer", missing, &HashMap::new())
+                .unwrap_err();
+        assert!(!error.contains("J. Doe"), "{}", error);
+    }
+
+    #[test]
+    #[serial]
+    fn test_state_monitor_restarts_after_panic() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let _ = PrinterCore::stop_state_monitoring();
+        PrinterCore::configure(LibraryConfig {
+            monitoring_interval: Duration::from_millis(20),
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        PrinterCore::subscribe_to_state_changes(move |_| {
+            if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                panic!("subscriber failed");
+            }
+        })
+        .unwrap();
+
+        // The restarted monitor reports the printer again
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while calls.load(Ordering::SeqCst) < 2 && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(20));
+        }
+        assert!(calls.load(Ordering::SeqCst) >= 2);
+
+        let status = PrinterCore::get_runtime_status();
+        assert!(status.monitoring_running);
+        let monitor = status
+            .tasks
+            .iter()
+            .find(|task| task.name == "state-monitor")
+            .unwrap();
+        assert_eq!(monitor.restarts, 1);
+        assert_eq!(monitor.last_panic.as_deref(), Some("subscriber failed"));
+
+        PrinterCore::stop_state_monitoring().unwrap();
+        assert!(!PrinterCore::get_runtime_status()
+            .tasks
+            .iter()
+            .any(|task| task.name == "state-monitor"));
+        PrinterCore::configure(LibraryConfig::default()).unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn test_printer_cache() {
+        PrinterCore::configure(LibraryConfig {
+            printer_cache_ttl: Duration::from_secs(60),
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+        PrinterCore::refresh_printers();
+        let printers = cached_printers();
+        assert!(Arc::ptr_eq(&printers, &cached_printers()));
+
+        PrinterCore::refresh_printers();
+        assert!(!Arc::ptr_eq(&printers, &cached_printers()));
+
+        PrinterCore::configure(LibraryConfig {
+            printer_cache_ttl: Duration::ZERO,
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+        let printers = cached_printers();
+        assert!(!Arc::ptr_eq(&printers, &cached_printers()));
+
+        PrinterCore::configure(LibraryConfig::default()).unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn test_printer_handle() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        PrinterCore::configure(LibraryConfig {
+            printer_cache_ttl: Duration::from_secs(60),
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+        let installation = PrinterInstallation {
+            name: "Handle Test".to_string(),
+            port: "LPT1:".to_string(),
+            driver: "Generic / Text Only".to_string(),
+            location: None,
+            comment: None,
+        };
+        PrinterCore::install_printer(&installation).unwrap();
+
+        let handle = PrinterHandle::new("Handle Test");
+        assert_eq!(handle.get().unwrap().name, "Handle Test");
+        // Held until the printer cache is invalidated
+        SIMULATED_INSTALLS
+            .lock()
+            .unwrap()
+            .retain(|printer| printer.name != "Handle Test");
+        assert!(handle.get().is_some());
+        assert!(handle.refresh().is_none());
+        assert!(handle.get().is_none());
+
+        PrinterCore::install_printer(&installation).unwrap();
+        assert!(handle.get().is_some());
+        PrinterCore::remove_printer("Handle Test").unwrap();
+        PrinterCore::refresh_printers();
+        assert!(handle.get().is_none());
+
+        PrinterCore::configure(LibraryConfig::default()).unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn test_job_ids_carry_epoch() {
+        let before = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let first = generate_job_id();
+        let second = generate_job_id();
+        assert!(second > first);
+        assert!(first >= before << JOB_ID_SEQUENCE_BITS);
+        assert!(second < 1 << 53);
+        let issued = PrinterCore::job_id_time(first)
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        assert!(issued >= before && issued <= before + 1);
+
+        // A restarted process starts above the IDs issued before it
+        let restarted = job_id_floor(SystemTime::now() + Duration::from_secs(1));
+        assert!(restarted > second);
+    }
+
+    #[test]
+    #[serial]
+    fn test_removed_printer_jobs() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        PrinterCore::configure(LibraryConfig {
+            simulation_delay: Duration::from_millis(300),
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+        let wait = |job_id| {
+            let start = Instant::now();
+            while PrinterCore::get_job_status(job_id).is_some_and(|job| !job.state.is_terminal())
+                && start.elapsed() < Duration::from_secs(5)
+            {
+                thread::sleep(Duration::from_millis(10));
+            }
+        };
+        for name in ["Doomed_Queue", "Old_Queue"] {
+            let queue = CupsQueue {
+                name: name.to_string(),
+                device_uri: "ipp://10.0.0.9/ipp/print".to_string(),
+                enabled: true,
+                ..CupsQueue::default()
+            };
+            PrinterCore::add_cups_queue(&queue).unwrap();
+        }
+        let failed = Arc::new(Mutex::new(Vec::new()));
+        let events = Arc::clone(&failed);
+        let subscription = PrinterCore::subscribe_to_job_events(Arc::new(move |event, job| {
+            if event == WebhookEvent::Failed {
+                events.lock().unwrap().push(job.id);
+            }
+        }));
+
+        // Jobs wait behind a running job until it completes
+        let busy = PrinterCore::print_bytes("Simulated Printer", b"%!PS", None).unwrap();
+        let queued = |printer: &str, keep_spool_file| {
+            let options = PrinterJobOptions {
+                depends_on: vec![busy],
+                keep_spool_file,
+                ..PrinterJobOptions::none()
+            };
+            PrinterCore::print_bytes(printer, b"%!PS", Some(options)).unwrap()
+        };
+        let kept = queued("Doomed_Queue", true);
+        let lost = queued("Doomed_Queue", false);
+        let waiting = queued("Old_Queue", false);
+
+        PrinterCore::remove_printer("Doomed_Queue").unwrap();
+        for job_id in [kept, lost] {
+            let job = PrinterCore::get_job_status(job_id).unwrap();
+            assert_eq!(job.state, PrinterJobState::CANCELLED);
+            assert!(job.printer_removed);
+            assert_eq!(
+                job.error_message.as_deref(),
+                Some("Printer 'Doomed_Queue' was removed")
+            );
+        }
+        let mut failed = failed.lock().unwrap().clone();
+        failed.sort();
+        assert_eq!(failed, vec![kept, lost]);
+        PrinterCore::unsubscribe_from_job_events(subscription);
+
+        assert_eq!(
+            PrinterCore::migrate_jobs("Old_Queue", "Old_Queue"),
+            Err(PrintError::InvalidParams)
+        );
+        assert_eq!(
+            PrinterCore::migrate_jobs("Old_Queue", "Missing_Queue"),
+            Err(PrintError::PrinterNotFound)
+        );
+        let migration = PrinterCore::migrate_jobs("Doomed_Queue", "Simulated Printer").unwrap();
+        assert!(migration.moved.is_empty());
+        assert_eq!(migration.requeued.len(), 1);
+        assert_eq!(migration.requeued[0].0, kept);
+        assert_eq!(migration.unavailable, vec![lost]);
+        let reprint = PrinterCore::get_job_status(migration.requeued[0].1).unwrap();
+        assert_eq!(reprint.printer_name, "Simulated Printer");
+        assert_eq!(reprint.reprint_of, Some(kept));
+        // Jobs are only requeued once
+        let migration = PrinterCore::migrate_jobs("Doomed_Queue", "Simulated Printer").unwrap();
+        assert!(migration.requeued.is_empty());
+
+        let migration = PrinterCore::migrate_jobs("Old_Queue", "Simulated Printer").unwrap();
+        assert_eq!(migration.moved, vec![waiting]);
+        wait(waiting);
+        let job = PrinterCore::get_job_status(waiting).unwrap();
+        assert_eq!(job.state, PrinterJobState::COMPLETED);
+        assert_eq!(job.printer_name, "Simulated Printer");
+
+        PrinterCore::remove_printer("Old_Queue").unwrap();
+        PrinterCore::configure(LibraryConfig::default()).unwrap();
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_job_log() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        PrinterCore::configure(LibraryConfig {
+            simulation_delay: Duration::from_millis(200),
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+        let wait = |job_id| {
+            let start = Instant::now();
+            while PrinterCore::get_job_status(job_id).is_some_and(|job| !job.state.is_terminal())
+                && start.elapsed() < Duration::from_secs(5)
+            {
+                thread::sleep(Duration::from_millis(10));
+            }
+        };
+        let messages = |job_id| -> Vec<String> {
+            PrinterCore::get_job_log(job_id)
+                .unwrap()
+                .into_iter()
+                .map(|entry| entry.message)
+                .collect()
+        };
+        assert!(PrinterCore::get_job_log(u64::MAX).is_none());
+
+        let job_id = PrinterCore::print_bytes("Simulated Printer", b"%!PS", None).unwrap();
+        wait(job_id);
+        assert_eq!(
+            messages(job_id),
+            [
+                "Submitted to 'Simulated Printer': 4 bytes of application/postscript (type from content)",
+                "Processing on 'Simulated Printer'",
+                "Completed",
+            ]
+        );
+
+        let options = PrinterJobOptions {
+            timeout: Some(Duration::from_millis(20)),
+            ..PrinterJobOptions::none()
+        };
+        let job_id = PrinterCore::print_bytes("Simulated Printer", b"%!PS", Some(options)).unwrap();
+        wait(job_id);
+        let log = PrinterCore::get_job_log(job_id).unwrap();
+        let last = log.last().unwrap();
+        assert_eq!(last.level, LogLevel::Error);
+        assert_eq!(last.message, "Failed: Job timed out after 20ms");
+
+        PrinterCore::configure(LibraryConfig::default()).unwrap();
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
+        assert!(PrinterCore::get_job_log(job_id).is_none());
+    }
+
+    #[test]
+    #[serial]
+    fn test_job_transitions() {
+        use PrinterJobState::*;
+        assert!(PENDING.can_transition_to(&PROCESSING));
+        assert!(HELD_FOR_APPROVAL.can_transition_to(&PENDING));
+        assert!(!COMPLETED.can_transition_to(&PROCESSING));
+        assert!(!CANCELLED.can_transition_to(&PENDING));
+        assert!(!PENDING.can_transition_to(&COMPLETED));
+        assert!(!PROCESSING.can_transition_to(&PROCESSING));
+
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        PrinterCore::configure(LibraryConfig {
+            simulation_delay: Duration::from_millis(100),
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+        let job_id = PrinterCore::print_bytes("Simulated Printer", b"%!PS", None).unwrap();
+        let start = Instant::now();
+        while PrinterCore::get_job_status(job_id).is_some_and(|job| !job.state.is_terminal())
+            && start.elapsed() < Duration::from_secs(5)
+        {
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        let mut job = PrinterCore::get_job_status(job_id).unwrap();
+        let states: Vec<(PrinterJobState, PrinterJobState)> = job
+            .transitions
+            .iter()
+            .map(|transition| (transition.from.clone(), transition.to.clone()))
+            .collect();
+        assert_eq!(states, [(PENDING, PROCESSING), (PROCESSING, COMPLETED)]);
+        assert!(job.transitions[0].at <= job.transitions[1].at);
+
+        // Finished jobs can't be restarted
+        assert!(job.transition_to(PROCESSING).is_err());
+        assert_eq!(job.state, COMPLETED);
+        assert_eq!(job.transitions.len(), 2);
+
+        PrinterCore::configure(LibraryConfig::default()).unwrap();
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
+    }
+
+    #[test]
+    fn test_printer_job_json() {
+        let mut job = PrinterJob::new(
+            7,
+            "Invoice".to_string(),
+            "application/pdf".to_string(),
+            "Simulated Printer".to_string(),
+        );
+        job.created_at = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        job.source_path = Some("/home/alice/invoice.pdf".to_string());
+        job.owner = Some("alice".to_string());
+        job.job_options
+            .metadata
+            .insert("orderId".to_string(), "A-1".to_string());
+        job.job_options.requesting_user = Some("alice".to_string());
+        job.transition_to(PrinterJobState::HELD_FOR_APPROVAL)
+            .unwrap();
+
+        let json = printer_job_to_json(&job).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["state"], "held-for-approval");
+        assert_eq!(value["mediaTypeSource"], "default");
+        assert_eq!(value["createdAt"], 1_700_000_000);
+        assert_eq!(value["processedAt"], serde_json::Value::Null);
+        assert_eq!(value["metadata"]["orderId"], "A-1");
+        assert_eq!(value["transitions"][0]["from"], "pending");
+        // Only the metadata of the options is kept, and never the file path
+        assert!(!json.contains("/home/alice"));
+        assert!(value.get("sourcePath").is_none());
+
+        let parsed = printer_job_from_json(&json).unwrap();
+        assert_eq!(parsed.id, 7);
+        assert_eq!(parsed.state, PrinterJobState::HELD_FOR_APPROVAL);
+        assert_eq!(parsed.created_at, job.created_at);
+        assert_eq!(parsed.owner.as_deref(), Some("alice"));
+        assert_eq!(parsed.job_options.metadata, job.job_options.metadata);
+        assert_eq!(parsed.job_options.requesting_user, None);
+        assert_eq!(parsed.transitions.len(), 1);
+        assert_eq!(printer_job_to_json(&parsed).unwrap(), json);
+
+        // Fields added since a job was written default
+        let minimal = printer_job_from_json(
+            r#"{"id":1,"name":"a","state":"completed","mediaType":"text/plain","createdAt":0,"printerName":"p"}"#,
+        )
+        .unwrap();
+        assert_eq!(minimal.media_type_source, MediaTypeSource::Default);
+        assert!(minimal.transitions.is_empty());
+        assert!(printer_job_from_json(r#"{"id":1,"state":"printing"}"#).is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn test_attach_and_detach() {
+        let main = PrinterCore::env_attached();
+        let worker = PrinterCore::env_attached();
+        assert_eq!(PrinterCore::owner_env(), None);
+        assert!(PrinterCore::check_owner(worker).is_ok());
+
+        PrinterCore::attach(main).unwrap();
+        PrinterCore::attach(main).unwrap();
+        assert!(PrinterCore::attach(worker).is_err());
+        assert!(PrinterCore::check_owner(main).is_ok());
+        assert!(PrinterCore::check_owner(worker).is_err());
+        assert!(!PrinterCore::detach(worker));
+
+        // Ownership is released when the owner exits
+        PrinterCore::env_detached(main);
+        assert_eq!(PrinterCore::owner_env(), None);
+        PrinterCore::attach(worker).unwrap();
+        assert!(PrinterCore::detach(worker));
+        assert!(PrinterCore::check_owner(main).is_ok());
+
+        PrinterCore::env_detached(worker);
+        PrinterCore::cleanup_old_jobs(0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_state_monitoring_shared_by_envs() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let _ = PrinterCore::stop_state_monitoring();
+        let main = PrinterCore::env_attached();
+        let worker = PrinterCore::env_attached();
+
+        PrinterCore::start_state_monitoring_for(main).unwrap();
+        // A second environment shares the running monitor
+        PrinterCore::start_state_monitoring_for(worker).unwrap();
+        assert!(PrinterCore::is_state_monitoring_active());
+
+        PrinterCore::stop_state_monitoring_for(worker).unwrap();
+        assert!(PrinterCore::is_state_monitoring_active());
+        assert!(PrinterCore::stop_state_monitoring_for(worker).is_err());
+
+        // The last environment exiting stops it
+        PrinterCore::env_detached(main);
+        assert!(!PrinterCore::is_state_monitoring_active());
+
+        PrinterCore::env_detached(worker);
+        PrinterCore::cleanup_old_jobs(0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_job_registry() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        assert!(PrinterCore::job_process(1).is_err());
+        assert!(PrinterCore::configure(LibraryConfig {
+            job_registry: Some(PathBuf::from("/path/that/does_not_exist/jobs.registry")),
+            ..LibraryConfig::default()
+        })
+        .is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("jobs.registry");
+        // Another process reserved IDs ahead of the clock
+        let other =
+            crate::registry::reserve(&path, job_id_floor(SystemTime::now()) + 1000).unwrap();
+        PrinterCore::configure(LibraryConfig {
+            job_registry: Some(path.clone()),
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+
+        let first = generate_job_id();
+        let second = generate_job_id();
+        assert!(first >= other.end);
+        assert_eq!(second, first + 1);
+        // The block is used up even once the clock has moved on
+        thread::sleep(Duration::from_millis(1100));
+        assert_eq!(generate_job_id(), second + 1);
+        let registry: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(registry["blocks"].as_array().unwrap().len(), 2);
+        assert_eq!(
+            PrinterCore::job_process(first).unwrap(),
+            Some(std::process::id())
+        );
+        assert_eq!(PrinterCore::job_process(1).unwrap(), None);
+
+        // A corrupt registry falls back to local IDs
+        std::fs::write(&path, "not json").unwrap();
+        *REGISTRY_BLOCK.lock().unwrap() = None;
+        assert!(generate_job_id() > second + 1);
+
+        PrinterCore::configure(LibraryConfig::default()).unwrap();
+        *REGISTRY_BLOCK.lock().unwrap() = None;
+    }
+
+    #[test]
+    #[serial]
+    fn test_query_pjl_status() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let status = PrinterCore::query_pjl_status("Simulated Printer").unwrap();
+        assert_eq!(status.severity(), Some("info"));
+        assert!(PrinterCore::query_pjl_status("NonExistentPrinter12345").is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn test_label_commands() {
+        use crate::label::LabelLanguage;
+
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let job_id =
+            PrinterCore::calibrate_media("Simulated Printer", Some(LabelLanguage::Zpl)).unwrap();
+        assert!(job_id > 0);
+        assert!(
+            PrinterCore::feed_labels("Simulated Printer", 3, Some(LabelLanguage::EscPos)).is_ok()
+        );
+        assert_eq!(
+            PrinterCore::feed_labels("Simulated Printer", 0, Some(LabelLanguage::Zpl)),
+            Err(PrintError::InvalidParams)
+        );
+        // The simulated printer takes PDF, not a label language
+        assert!(matches!(
+            PrinterCore::calibrate_media("Simulated Printer", None),
+            Err(PrintError::UnsupportedOption(reason)) if reason.contains("known label printer")
+        ));
+        assert_eq!(
+            PrinterCore::query_label_status("Simulated Printer").unwrap(),
+            crate::label::LabelStatus::default()
+        );
+    }
+
+    #[test]
+    fn test_printer_languages() {
+        assert_eq!(
+            PrinterLanguage::from_driver_name("HP LaserJet 4050 Series PCL6"),
+            vec![PrinterLanguage::Pcl]
+        );
+        assert_eq!(
+            PrinterLanguage::from_driver_name("Brother HL-L6400DW BR-Script3"),
+            vec![PrinterLanguage::PostScript]
+        );
+        assert_eq!(
+            PrinterLanguage::from_driver_name("ZDesigner ZD420-203dpi ZPL"),
+            vec![PrinterLanguage::Zpl]
+        );
+        assert!(PrinterLanguage::from_driver_name("Epson ET-2850 Series").is_empty());
+
+        let formats = SupportedFormats::from_document_formats(vec![
+            "application/octet-stream".to_string(),
+            "application/vnd.hp-PCL".to_string(),
+            "application/postscript".to_string(),
+            "image/urf".to_string(),
+        ]);
+        assert_eq!(
+            formats.languages,
+            vec![PrinterLanguage::PostScript, PrinterLanguage::Pcl]
+        );
+        assert_eq!(formats.preferred(), Some(PrinterLanguage::PostScript));
+        assert!(formats.accepts("image/urf"));
+        assert!(!formats.accepts("application/pdf"));
+    }
+
+    #[test]
+    #[serial]
+    fn test_get_supported_formats() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let formats = PrinterCore::get_supported_formats("Simulated Printer").unwrap();
+        assert_eq!(formats.source, FormatSource::Simulated);
+        assert_eq!(formats.preferred(), Some(PrinterLanguage::Pdf));
+        assert_eq!(
+            PrinterCore::get_supported_formats("NonExistentPrinter12345"),
+            Err(PrintError::PrinterNotFound)
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_watermark() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let watermarked = |watermark: &str| {
+            Some(PrinterJobOptions::from_map(HashMap::from([(
+                "job-watermark".to_string(),
+                watermark.to_string(),
+            )])))
+        };
+
+        let pdf = crate::document::test_page_pdf("Payroll", &[]);
+        let job_id = PrinterCore::print_bytes(
+            "Simulated Printer",
+            &pdf,
+            watermarked(r#"{"text":"DRAFT"}"#),
+        )
+        .unwrap();
+        let job = PrinterCore::get_job_status(job_id).unwrap();
+        assert!(job.size_bytes > pdf.len() as u64);
+        assert_eq!(job.total_pages, Some(1));
+
+        // Only PDFs can be stamped, and watermarks need something to stamp
+        assert_eq!(
+            PrinterCore::print_bytes(
+                "Simulated Printer",
+                b"^XA^XZ",
+                watermarked(r#"{"text":"DRAFT"}"#)
+            ),
+            Err(PrintError::InvalidDocument)
+        );
+        assert_eq!(
+            PrinterCore::print_bytes(
+                "Simulated Printer",
+                &pdf,
+                watermarked(r#"{"text":"DRAFT","opacity":2}"#)
+            ),
+            Err(PrintError::InvalidParams)
+        );
+
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
     }
 
     #[test]
     #[serial]
-    fn test_printer_exists_in_simulation_mode() {
+    fn test_header_footer() {
         env::set_var("PRINTERS_JS_SIMULATE", "true");
-        assert!(PrinterCore::printer_exists("Simulated Printer"));
-        assert!(!PrinterCore::printer_exists("NonExistent Printer"));
+        let with_header = |header: &str| {
+            Some(PrinterJobOptions::from_map(HashMap::from([
+                ("job-header".to_string(), header.to_string()),
+                ("job-footer".to_string(), "{{jobName}}".to_string()),
+                ("job-name".to_string(), "Packing slip".to_string()),
+                (
+                    "job-metadata".to_string(),
+                    r#"{"orderId":"A-1"}"#.to_string(),
+                ),
+            ])))
+        };
+
+        let pdf = crate::document::test_page_pdf("Packing slip", &[]);
+        let job_id = PrinterCore::print_bytes(
+            "Simulated Printer",
+            &pdf,
+            with_header("Order {{orderId}} page {{page}}/{{pages}}"),
+        )
+        .unwrap();
+        let job = PrinterCore::get_job_status(job_id).unwrap();
+        assert!(job.size_bytes > pdf.len() as u64);
+        assert_eq!(job.total_pages, Some(1));
+
+        // Variables the job doesn't have are caught before submission
+        assert_eq!(
+            PrinterCore::print_bytes(
+                "Simulated Printer",
+                &pdf,
+                with_header("Shipment {{shipmentId}}")
+            ),
+            Err(PrintError::InvalidParams)
+        );
+
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
     }
 
     #[test]
     #[serial]
-    fn test_find_printer_by_name_in_simulation_mode() {
+    fn test_booklet_layout() {
         env::set_var("PRINTERS_JS_SIMULATE", "true");
-        let printer = PrinterCore::find_printer_by_name("Simulated Printer");
-        assert!(printer.is_some());
+        let booklet = |sides: Option<&str>| {
+            let mut properties = HashMap::from([("job-layout".to_string(), "booklet".to_string())]);
+            if let Some(sides) = sides {
+                properties.insert("sides".to_string(), sides.to_string());
+            }
+            Some(PrinterJobOptions::from_map(properties))
+        };
 
-        let printer = PrinterCore::find_printer_by_name("NonExistent Printer");
-        assert!(printer.is_none());
+        // One page is padded out to a sheet, printed on both sides
+        let pdf = crate::document::test_page_pdf("Program", &[]);
+        let result = PrinterCore::dry_run_bytes("Simulated Printer", &pdf, booklet(None)).unwrap();
+        assert_eq!(
+            result
+                .job_options
+                .raw_properties
+                .get("sides")
+                .map(String::as_str),
+            Some("two-sided-short-edge")
+        );
+        assert_eq!(result.document_pages, Some(2));
+        assert_eq!(result.sheets, Some(1));
+
+        let job_id = PrinterCore::print_bytes("Simulated Printer", &pdf, booklet(None)).unwrap();
+        assert_eq!(
+            PrinterCore::get_job_status(job_id).unwrap().total_pages,
+            Some(2)
+        );
+        assert_eq!(
+            PrinterCore::print_bytes("Simulated Printer", &pdf, booklet(Some("one-sided"))),
+            Err(PrintError::InvalidParams)
+        );
+
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
     }
 
     #[test]
     #[serial]
-    fn test_print_file_error_codes() {
+    fn test_margins_and_scale() {
         env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let options = |entries: &[(&str, &str)]| {
+            Some(PrinterJobOptions::from_map(
+                entries
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), value.to_string()))
+                    .collect(),
+            ))
+        };
 
-        // Test successful print job
-        let result = PrinterCore::print_file("Simulated Printer", "/path/to/file.pdf", None);
-        assert!(result.is_ok());
+        // Images are placed on a page of the job's media and sent as PDFs
+        let jpeg = [
+            0xff, 0xd8, 0xff, 0xe0, 0x00, 0x04, 0x00, 0x00, 0xff, 0xc0, 0x00, 0x0b, 0x08, 0x00,
+            0x01, 0x00, 0x02, 0x03, 0x01, 0x11, 0x00, 0xff, 0xd9,
+        ];
+        let job_id = PrinterCore::print_bytes(
+            "Simulated Printer",
+            &jpeg,
+            options(&[
+                ("media", "oe_4x6-label_4x6in"),
+                ("job-margins", r#"{"top":9,"bottom":9}"#),
+            ]),
+        )
+        .unwrap();
+        let job = PrinterCore::get_job_status(job_id).unwrap();
+        assert_eq!(job.media_type, "application/pdf");
+        assert_eq!(job.total_pages, Some(1));
 
-        // Test with file that should trigger file not found error
-        let result = PrinterCore::print_file(
+        let pdf = crate::document::test_page_pdf("Label", &[]);
+        assert!(PrinterCore::print_bytes(
             "Simulated Printer",
-            "/path/that/does_not_exist/file.pdf",
-            None,
+            &pdf,
+            options(&[
+                ("job-fit-to-page", "true"),
+                ("job-margins", r#"{"left":18}"#)
+            ]),
+        )
+        .is_ok());
+        assert_eq!(
+            PrinterCore::print_bytes(
+                "Simulated Printer",
+                &pdf,
+                options(&[("job-fit-to-page", "true"), ("job-scale", "90")]),
+            ),
+            Err(PrintError::InvalidParams)
+        );
+        assert_eq!(
+            PrinterCore::print_bytes(
+                "Simulated Printer",
+                &pdf,
+                options(&[("job-margins", r#"{"left":400,"right":400}"#)]),
+            ),
+            Err(PrintError::InvalidDocument)
         );
-        assert_eq!(result, Err(PrintError::FileNotFound));
-
-        // Test with file that should trigger simulated failure
-        let result = PrinterCore::print_file("Simulated Printer", "/path/to/fail-test.pdf", None);
-        assert_eq!(result, Err(PrintError::SimulatedFailure));
-
-        // Test print bytes
-        let data = b"Hello, printer!";
-        let result = PrinterCore::print_bytes("Simulated Printer", data, None);
-        assert!(result.is_ok());
 
-        // Test print with job options
-        let job_options = Some(PrinterJobOptions::with_name_and_properties(
-            "Test Job".to_string(),
-            [("copies".to_string(), "2".to_string())]
-                .iter()
-                .cloned()
-                .collect(),
-        ));
-        let result = PrinterCore::print_file("Simulated Printer", "/path/to/file.pdf", job_options);
-        assert!(result.is_ok());
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
     }
 
     #[test]
-    fn test_print_error_codes() {
-        assert_eq!(PrintError::InvalidParams.as_i32(), 1);
-        assert_eq!(PrintError::InvalidPrinterName.as_i32(), 2);
-        assert_eq!(PrintError::InvalidFilePath.as_i32(), 3);
-        assert_eq!(PrintError::InvalidJson.as_i32(), 4);
-        assert_eq!(PrintError::InvalidJsonEncoding.as_i32(), 5);
-        assert_eq!(PrintError::PrinterNotFound.as_i32(), 6);
-        assert_eq!(PrintError::FileNotFound.as_i32(), 7);
-        assert_eq!(PrintError::SimulatedFailure.as_i32(), 8);
-    }
+    #[serial]
+    fn test_color_mode() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let options = |mode: &str| {
+            PrinterJobOptions::from_map(HashMap::from([(
+                "job-color-mode".to_string(),
+                mode.to_string(),
+            )]))
+        };
 
-    #[test]
-    fn test_media_type_detection() {
-        assert_eq!(detect_media_type("document.pdf"), "application/pdf");
-        assert_eq!(detect_media_type("script.ps"), "application/postscript");
-        assert_eq!(detect_media_type("image.jpg"), "image/jpeg");
-        assert_eq!(detect_media_type("image.jpeg"), "image/jpeg");
-        assert_eq!(detect_media_type("image.png"), "image/png");
-        assert_eq!(detect_media_type("image.gif"), "image/gif");
-        assert_eq!(detect_media_type("file.txt"), "text/plain");
-        assert_eq!(detect_media_type("file.text"), "text/plain");
-        assert_eq!(detect_media_type("unknown.xyz"), "application/octet-stream");
+        // The simulated printer reports monochrome, so the driver is asked
+        let pdf = crate::document::test_page_pdf("Photo", &[]);
+        let result =
+            PrinterCore::dry_run_bytes("Simulated Printer", &pdf, Some(options("grayscale")))
+                .unwrap();
         assert_eq!(
-            detect_media_type("no_extension"),
-            "application/octet-stream"
+            result
+                .job_options
+                .raw_properties
+                .get("print-color-mode")
+                .map(String::as_str),
+            Some("monochrome")
         );
+        assert!(!result.job_options.convert_to_gray);
+
+        // Printers that don't report the mode get gray documents too
+        let mut job_options = options("monochrome");
+        check_color_mode("Printer", None, &mut job_options);
+        assert!(job_options.convert_to_gray);
+        let capabilities = PrinterCapabilities {
+            supported: HashMap::from([(
+                "print-color-mode".to_string(),
+                vec!["bi-level".to_string(), "color".to_string()],
+            )]),
+            ..PrinterCapabilities::default()
+        };
+        let mut job_options = options("monochrome");
+        check_color_mode("Printer", Some(&capabilities), &mut job_options);
+        assert!(!job_options.convert_to_gray);
         assert_eq!(
-            detect_media_type("<bytes:1024 bytes>"),
-            "application/vnd.cups-raw"
+            job_options
+                .raw_properties
+                .get("print-color-mode")
+                .map(String::as_str),
+            Some("bi-level")
         );
-    }
 
-    #[test]
-    fn test_printer_job_state_conversions() {
-        assert_eq!(PrinterJobState::PENDING.as_string(), "pending");
-        assert_eq!(PrinterJobState::PAUSED.as_string(), "paused");
-        assert_eq!(PrinterJobState::PROCESSING.as_string(), "processing");
-        assert_eq!(PrinterJobState::CANCELLED.as_string(), "cancelled");
-        assert_eq!(PrinterJobState::COMPLETED.as_string(), "completed");
-        assert_eq!(PrinterJobState::UNKNOWN.as_string(), "unknown");
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
     }
 
     #[test]
     #[serial]
-    fn test_job_options_creation() {
-        // Test none()
-        let none_options = PrinterJobOptions::none();
-        assert!(none_options.name.is_none());
-        assert!(none_options.raw_properties.is_empty());
-
-        // Test from_map with job-name
-        let mut properties = HashMap::new();
-        properties.insert("job-name".to_string(), "Test Job".to_string());
-        properties.insert("copies".to_string(), "2".to_string());
-
-        let options = PrinterJobOptions::from_map(properties);
-        assert_eq!(options.name, Some("Test Job".to_string()));
-        assert_eq!(options.raw_properties.get("copies"), Some(&"2".to_string()));
-        assert!(!options.raw_properties.contains_key("job-name")); // Should be extracted
-
-        // Test from_map without job-name
-        let mut properties = HashMap::new();
-        properties.insert("copies".to_string(), "3".to_string());
-
-        let options = PrinterJobOptions::from_map(properties);
-        assert!(options.name.is_none());
-        assert_eq!(options.raw_properties.get("copies"), Some(&"3".to_string()));
+    fn test_quality_and_dpi() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let options = |quality: &str, dpi: &str| {
+            Some(PrinterJobOptions::from_map(HashMap::from([
+                ("job-quality".to_string(), quality.to_string()),
+                ("job-dpi".to_string(), dpi.to_string()),
+            ])))
+        };
 
-        // Test with_name_and_properties
-        let mut properties = HashMap::new();
-        properties.insert("quality".to_string(), "high".to_string());
+        let pdf = crate::document::test_page_pdf("Drawing", &[]);
+        let result =
+            PrinterCore::dry_run_bytes("Simulated Printer", &pdf, options("high", "600")).unwrap();
+        let raw = &result.job_options.raw_properties;
+        assert_eq!(raw.get("print-quality").map(String::as_str), Some("5"));
+        assert_eq!(
+            raw.get("printer-resolution").map(String::as_str),
+            Some("600dpi")
+        );
+        assert!(result.unsupported_options.is_empty());
 
-        let options =
-            PrinterJobOptions::with_name_and_properties("Named Job".to_string(), properties);
-        assert_eq!(options.name, Some("Named Job".to_string()));
+        // The simulated printer prints at 300 and 600 dpi only
         assert_eq!(
-            options.raw_properties.get("quality"),
-            Some(&"high".to_string())
+            PrinterCore::print_bytes("Simulated Printer", &pdf, options("draft", "1200")),
+            Err(PrintError::UnsupportedOption(
+                "Printer doesn't support printing at 1200dpi".to_string()
+            ))
         );
+        assert_eq!(resolution_dpi("600x600dpi"), Some(600));
+        assert_eq!(resolution_dpi("600x300dpi"), None);
+
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
     }
 
     #[test]
     #[serial]
-    fn test_create_status_json() {
-        let job = PrinterJob {
-            id: 1234,
-            name: "Test Job".to_string(),
-            state: PrinterJobState::COMPLETED,
-            media_type: "application/pdf".to_string(),
-            created_at: SystemTime::now() - Duration::from_secs(10),
-            processed_at: Some(SystemTime::now() - Duration::from_secs(8)),
-            completed_at: Some(SystemTime::now() - Duration::from_secs(5)),
-            printer_name: "Test Printer".to_string(),
-            error_message: Some("Test error".to_string()),
+    fn test_finishing() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let options = |finishing: &str| {
+            Some(PrinterJobOptions::from_map(HashMap::from([(
+                "job-finishing".to_string(),
+                finishing.to_string(),
+            )])))
         };
 
-        let json_str = create_status_json(1234, &job).unwrap();
-        let json_value: serde_json::Value = serde_json::from_str(&json_str).unwrap();
+        let pdf = crate::document::test_page_pdf("Report", &[]);
+        let result = PrinterCore::dry_run_bytes(
+            "Simulated Printer",
+            &pdf,
+            options(r#"{"staple":"top-left","punch":"dual-left","outputBin":"mailbox-1"}"#),
+        )
+        .unwrap();
+        let raw = &result.job_options.raw_properties;
+        assert_eq!(raw.get("finishings").map(String::as_str), Some("20,74"));
+        assert_eq!(raw.get("output-bin").map(String::as_str), Some("mailbox-1"));
+        assert!(result.unsupported_options.is_empty());
+        let capabilities = result.capabilities.unwrap();
+        assert!(capabilities.finishings().contains(&"fold-half"));
+
+        // The simulated finisher can't staple twice or deliver to a tray
+        assert_eq!(
+            PrinterCore::print_bytes(
+                "Simulated Printer",
+                &pdf,
+                options(r#"{"staple":"dual-top"}"#)
+            ),
+            Err(PrintError::UnsupportedOption(
+                "Printer doesn't support finishing staple-dual-top".to_string()
+            ))
+        );
+        assert_eq!(
+            PrinterCore::print_bytes(
+                "Simulated Printer",
+                &pdf,
+                options(r#"{"outputBin":"tray-3"}"#)
+            ),
+            Err(PrintError::UnsupportedOption(
+                "Printer has no output bin 'tray-3'".to_string()
+            ))
+        );
+        assert_eq!(
+            PrinterCore::print_bytes("Simulated Printer", &pdf, options(r#"{"fold":"origami"}"#)),
+            Err(PrintError::InvalidParams)
+        );
 
-        assert_eq!(json_value["id"], 1234);
-        assert_eq!(json_value["name"], "Test Job");
-        assert_eq!(json_value["state"], "completed");
-        assert_eq!(json_value["media_type"], "application/pdf");
-        assert_eq!(json_value["printer_name"], "Test Printer");
-        assert_eq!(json_value["error_message"], "Test error");
-        assert!(json_value["created_at"].is_number());
-        assert!(json_value["processed_at"].is_number());
-        assert!(json_value["completed_at"].is_number());
-        assert!(json_value["age_seconds"].is_number());
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
     }
 
     #[test]
     #[serial]
-    fn test_job_state_tracking() {
+    fn test_require_encrypted_transport() {
         env::set_var("PRINTERS_JS_SIMULATE", "true");
-
-        // Clear any existing jobs
-        PrinterCore::cleanup_old_jobs(0);
-
-        let job_id = generate_job_id();
-        let initial_job = PrinterJob {
-            id: job_id,
-            name: "State Transition Test".to_string(),
-            state: PrinterJobState::PENDING,
-            media_type: "application/pdf".to_string(),
-            created_at: SystemTime::now(),
-            processed_at: None,
-            completed_at: None,
-            printer_name: "Simulated Printer".to_string(),
-            error_message: None,
+        PrinterCore::configure(LibraryConfig {
+            simulation_delay: Duration::from_millis(10),
+            require_encrypted_transport: true,
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+        let queue = CupsQueue {
+            name: "Plain_Queue".to_string(),
+            device_uri: "ipp://10.0.0.7/ipp/print".to_string(),
+            enabled: true,
+            ..CupsQueue::default()
         };
-
-        // Insert initial job
-        {
-            let mut tracker = JOB_TRACKER.lock().unwrap();
-            tracker.insert(job_id, initial_job);
-        }
-
-        // Verify initial state
-        let job = PrinterCore::get_job_status(job_id).unwrap();
-        assert_eq!(job.state, PrinterJobState::PENDING);
-        assert!(job.processed_at.is_none());
-        assert!(job.completed_at.is_none());
-
-        // Transition to processing
-        {
-            let mut tracker = JOB_TRACKER.lock().unwrap();
-            if let Some(job) = tracker.get_mut(&job_id) {
-                job.state = PrinterJobState::PROCESSING;
-                job.processed_at = Some(SystemTime::now());
-            }
-        }
-
-        let job = PrinterCore::get_job_status(job_id).unwrap();
-        assert_eq!(job.state, PrinterJobState::PROCESSING);
-        assert!(job.processed_at.is_some());
-        assert!(job.completed_at.is_none());
-
-        // Transition to completed
-        {
-            let mut tracker = JOB_TRACKER.lock().unwrap();
-            if let Some(job) = tracker.get_mut(&job_id) {
-                job.state = PrinterJobState::COMPLETED;
-                job.completed_at = Some(SystemTime::now());
+        PrinterCore::add_cups_queue(&queue).unwrap();
+        let wait = |job_id| {
+            let start = Instant::now();
+            while PrinterCore::get_job_status(job_id).is_some_and(|job| !job.state.is_terminal())
+                && start.elapsed() < Duration::from_secs(5)
+            {
+                thread::sleep(Duration::from_millis(10));
             }
-        }
+            PrinterCore::get_job_status(job_id).unwrap()
+        };
 
-        let job = PrinterCore::get_job_status(job_id).unwrap();
+        // Plaintext network destinations are refused
+        let job = wait(PrinterCore::print_bytes("Plain_Queue", b"%!PS", None).unwrap());
+        assert_eq!(job.state, PrinterJobState::CANCELLED);
+        assert_eq!(job.encrypted_transport, Some(false));
+        assert!(job
+            .error_message
+            .is_some_and(|message| message.contains("encrypted transport is required")));
+
+        // So are destinations that can't be told apart from them
+        let queue = CupsQueue {
+            name: "Bonjour_Queue".to_string(),
+            device_uri: "dnssd://Office._ipp._tcp.local/".to_string(),
+            enabled: true,
+            ..CupsQueue::default()
+        };
+        PrinterCore::add_cups_queue(&queue).unwrap();
+        let job = wait(PrinterCore::print_bytes("Bonjour_Queue", b"%!PS", None).unwrap());
+        assert_eq!(job.state, PrinterJobState::CANCELLED);
+        assert_eq!(job.encrypted_transport, None);
+        assert!(job
+            .error_message
+            .is_some_and(|message| message.contains("encrypted transport is required")));
+
+        // Local devices aren't network transfers
+        let job = wait(PrinterCore::print_bytes("Simulated Printer", b"%!PS", None).unwrap());
         assert_eq!(job.state, PrinterJobState::COMPLETED);
-        assert!(job.processed_at.is_some());
-        assert!(job.completed_at.is_some());
+        assert_eq!(job.encrypted_transport, None);
+        let queue = CupsQueue {
+            name: "Usb_Queue".to_string(),
+            device_uri: "usb://HP/LaserJet?serial=123".to_string(),
+            enabled: true,
+            ..CupsQueue::default()
+        };
+        PrinterCore::add_cups_queue(&queue).unwrap();
+        let job = wait(PrinterCore::print_bytes("Usb_Queue", b"%!PS", None).unwrap());
+        assert_eq!(job.state, PrinterJobState::COMPLETED);
+        assert_eq!(job.encrypted_transport, None);
 
-        // Cleanup
+        PrinterCore::delete_cups_queue("Usb_Queue").unwrap();
+        PrinterCore::delete_cups_queue("Bonjour_Queue").unwrap();
+        PrinterCore::delete_cups_queue("Plain_Queue").unwrap();
+        PrinterCore::configure(LibraryConfig::default()).unwrap();
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
         PrinterCore::cleanup_old_jobs(0);
     }
 
     #[test]
     #[serial]
-    fn test_cleanup_old_jobs_for_printer() {
+    fn test_strict_options() {
         env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let strict = |entries: &[(&str, &str)]| {
+            let mut options: HashMap<String, String> = entries
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect();
+            options.insert("job-strict-options".to_string(), "true".to_string());
+            Some(PrinterJobOptions::from_map(options))
+        };
 
-        // Clear any existing jobs
-        PrinterCore::cleanup_old_jobs(0);
-
-        // Create jobs for two different printers
-        let job_id_1 = generate_job_id();
-        let job_id_2 = generate_job_id();
-
-        {
-            let mut tracker = JOB_TRACKER.lock().unwrap();
-
-            // Job for "Printer A" - completed
-            tracker.insert(
-                job_id_1,
-                PrinterJob {
-                    id: job_id_1,
-                    name: "Job for Printer A".to_string(),
-                    state: PrinterJobState::COMPLETED,
-                    media_type: "application/pdf".to_string(),
-                    created_at: SystemTime::now() - Duration::from_secs(100),
-                    processed_at: Some(SystemTime::now() - Duration::from_secs(90)),
-                    completed_at: Some(SystemTime::now() - Duration::from_secs(80)),
-                    printer_name: "Printer A".to_string(),
-                    error_message: None,
-                },
-            );
-
-            // Job for "Printer B" - completed
-            tracker.insert(
-                job_id_2,
-                PrinterJob {
-                    id: job_id_2,
-                    name: "Job for Printer B".to_string(),
-                    state: PrinterJobState::COMPLETED,
-                    media_type: "application/pdf".to_string(),
-                    created_at: SystemTime::now() - Duration::from_secs(100),
-                    processed_at: Some(SystemTime::now() - Duration::from_secs(90)),
-                    completed_at: Some(SystemTime::now() - Duration::from_secs(80)),
-                    printer_name: "Printer B".to_string(),
-                    error_message: None,
-                },
-            );
-        }
-
-        // Verify both jobs exist
-        assert!(PrinterCore::get_job_status(job_id_1).is_some());
-        assert!(PrinterCore::get_job_status(job_id_2).is_some());
+        let pdf = crate::document::test_page_pdf("Report", &[]);
+        PrinterCore::dry_run_bytes(
+            "Simulated Printer",
+            &pdf,
+            strict(&[("copies", "2"), ("media", "iso_a4_210x297mm")]),
+        )
+        .unwrap();
+        let reason = |result: Result<JobId, PrintError>| match result {
+            Err(PrintError::UnsupportedOption(reason)) => reason,
+            other => panic!("Expected UnsupportedOption, got {:?}", other),
+        };
+        let unknown = reason(PrinterCore::print_bytes(
+            "Simulated Printer",
+            &pdf,
+            strict(&[("copeis", "2")]),
+        ));
+        assert!(unknown.starts_with("unknown option 'copeis'"));
+        assert!(unknown.contains("copies, document-format"));
+        let unsupported = reason(PrinterCore::print_bytes(
+            "Simulated Printer",
+            &pdf,
+            strict(&[("sides", "three")]),
+        ));
+        assert!(unsupported.starts_with("sides=three"));
 
-        // Clean up only Printer A's jobs (max_age = 0 means all completed jobs)
-        let removed = PrinterCore::cleanup_old_jobs_for_printer("Printer A", 0);
-        assert_eq!(removed, 1);
+        // Unknown options are left to the driver unless the job is strict
+        PrinterCore::dry_run_bytes(
+            "Simulated Printer",
+            &pdf,
+            Some(PrinterJobOptions::from_map(HashMap::from([(
+                "copeis".to_string(),
+                "2".to_string(),
+            )]))),
+        )
+        .unwrap();
+
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
+    }
 
-        // Verify Printer A's job is gone but Printer B's job remains
-        assert!(PrinterCore::get_job_status(job_id_1).is_none());
-        assert!(PrinterCore::get_job_status(job_id_2).is_some());
+    #[test]
+    #[serial]
+    fn test_policy_rules() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let copies = |copies: &str| {
+            let mut options = HashMap::new();
+            options.insert("copies".to_string(), copies.to_string());
+            Some(PrinterJobOptions::from_map(options))
+        };
+        crate::policy::set(vec![crate::policy::PolicyRule {
+            id: "one-page".to_string(),
+            max_pages: Some(1),
+            ..Default::default()
+        }])
+        .unwrap();
+
+        let pdf = crate::document::test_page_pdf("Report", &[]);
+        PrinterCore::print_bytes("Simulated Printer", &pdf, copies("1")).unwrap();
+        assert_eq!(
+            PrinterCore::print_bytes("Simulated Printer", &pdf, copies("2")),
+            Err(PrintError::PolicyViolation(
+                crate::policy::PolicyViolation {
+                    rule: "one-page".to_string(),
+                    reason: "Job prints 2 pages, more than the 1 allowed".to_string(),
+                }
+            ))
+        );
+        assert!(matches!(
+            PrinterCore::dry_run_bytes("Simulated Printer", &pdf, copies("2")),
+            Err(PrintError::PolicyViolation(violation)) if violation.rule == "one-page"
+        ));
 
-        // Clean up remaining
+        crate::policy::set(Vec::new()).unwrap();
+        PrinterCore::print_bytes("Simulated Printer", &pdf, copies("2")).unwrap();
         PrinterCore::cleanup_old_jobs(0);
     }
 }
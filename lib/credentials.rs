@@ -0,0 +1,232 @@
+//! Credentials for protected print queues
+//!
+//! Credentials are set per printer or as a default for every printer. They
+//! are offered only when a server asks for authentication, never sent up
+//! front:
+//!
+//! - CUPS queues ask through the per-thread password callback, which the
+//!   submitting thread points at the job's credentials (see `spooler`).
+//!   Negotiate (Kerberos) uses the process's ticket cache, so it only
+//!   selects the user name.
+//! - IPP requests made by this library answer a `401` with HTTP Basic
+//!   authentication. A printer's credentials are found from its CUPS queue
+//!   URI (`/printers/{name}`), or by registering them under the printer URI
+//!   itself for devices that aren't CUPS queues.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::RwLock;
+
+lazy_static::lazy_static! {
+    static ref REGISTRY: RwLock<Registry> = RwLock::new(Registry::default());
+}
+
+#[derive(Default)]
+struct Registry {
+    default: Option<Credentials>,
+    /// By printer name or printer URI
+    printers: HashMap<String, Credentials>,
+}
+
+/// How to authenticate to a print queue
+#[derive(Clone, PartialEq)]
+pub enum Credentials {
+    /// User name and password
+    Basic { username: String, password: String },
+    /// Kerberos through the process's ticket cache, optionally as a given
+    /// user. Only CUPS supports it.
+    Negotiate { username: Option<String> },
+}
+
+// Keep passwords out of logs
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Credentials::Basic { username, .. } => f
+                .debug_struct("Basic")
+                .field("username", username)
+                .field("password", &"<redacted>")
+                .finish(),
+            Credentials::Negotiate { username } => f
+                .debug_struct("Negotiate")
+                .field("username", username)
+                .finish(),
+        }
+    }
+}
+
+impl Credentials {
+    pub fn username(&self) -> Option<&str> {
+        match self {
+            Credentials::Basic { username, .. } => Some(username),
+            Credentials::Negotiate { username } => username.as_deref(),
+        }
+    }
+
+    pub fn method(&self) -> &'static str {
+        match self {
+            Credentials::Basic { .. } => "basic",
+            Credentials::Negotiate { .. } => "negotiate",
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if let Some(username) = self.username() {
+            crate::spooler::validate_user_name(username)?;
+        }
+        if let Credentials::Basic { username, password } = self {
+            if username.contains(':') {
+                return Err("User name must not contain ':'".to_string());
+            }
+            if password.contains('\0') {
+                return Err("Password must not contain a NUL byte".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Authorization header value answering an HTTP challenge
+    pub fn authorization(&self) -> Result<String, String> {
+        match self {
+            Credentials::Basic { username, password } => Ok(format!(
+                "Basic {}",
+                crate::encoding::base64(format!("{}:{}", username, password).as_bytes())
+            )),
+            Credentials::Negotiate { .. } => Err(
+                "Negotiate (Kerberos) authentication is only supported for CUPS queues".to_string(),
+            ),
+        }
+    }
+}
+
+/// Set the credentials for a printer (a name or printer URI), or the
+/// default for every printer if `printer` is None. None clears them.
+pub fn set(printer: Option<&str>, credentials: Option<Credentials>) -> Result<(), String> {
+    if let Some(credentials) = &credentials {
+        credentials.validate()?;
+    }
+    let printer = printer.map(|printer| {
+        if printer.contains("://") {
+            normalize_uri(printer)
+        } else {
+            crate::aliases::resolve(printer)
+        }
+    });
+
+    tracing::info!(
+        printer = printer.as_deref().unwrap_or("*"),
+        method = credentials.as_ref().map_or("none", Credentials::method),
+        "Printer credentials set"
+    );
+    let mut registry = REGISTRY.write().unwrap();
+    match (printer, credentials) {
+        (None, credentials) => registry.default = credentials,
+        (Some(printer), Some(credentials)) => {
+            registry.printers.insert(printer, credentials);
+        }
+        (Some(printer), None) => {
+            registry.printers.remove(&printer);
+        }
+    }
+    Ok(())
+}
+
+/// Credentials for a printer, falling back to the default
+pub fn for_printer(printer_name: &str) -> Option<Credentials> {
+    let registry = REGISTRY.read().unwrap();
+    registry
+        .printers
+        .get(printer_name)
+        .or(registry.default.as_ref())
+        .cloned()
+}
+
+/// Credentials for a printer URI: registered under the URI itself, or for
+/// the CUPS queue it names, falling back to the default
+pub fn for_uri(uri: &str) -> Option<Credentials> {
+    {
+        let registry = REGISTRY.read().unwrap();
+        if let Some(credentials) = registry.printers.get(&normalize_uri(uri)) {
+            return Some(credentials.clone());
+        }
+    }
+    let queue = crate::device::DeviceAddress::parse(uri).and_then(|address| {
+        let path = address.path.split('?').next().unwrap_or_default();
+        path.strip_prefix("/printers/")
+            .or_else(|| path.strip_prefix("/classes/"))
+            .map(|name| crate::encoding::percent_decode(name.trim_end_matches('/'), false))
+    });
+    match queue {
+        Some(queue) => for_printer(&queue),
+        None => REGISTRY.read().unwrap().default.clone(),
+    }
+}
+
+/// Compare URIs by scheme, host, port and path, so default ports and case
+/// don't matter
+fn normalize_uri(uri: &str) -> String {
+    match crate::device::DeviceAddress::parse(uri) {
+        Some(address) => format!(
+            "{}://{}:{}{}",
+            address.scheme,
+            address.host.to_lowercase(),
+            address.port,
+            address.path.trim_end_matches('/')
+        ),
+        None => uri.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+
+    #[test]
+    #[serial]
+    fn test_printer_credentials() {
+        let basic = Credentials::Basic {
+            username: "alice".to_string(),
+            password: "s3cret".to_string(),
+        };
+        assert_eq!(basic.authorization().unwrap(), "Basic YWxpY2U6czNjcmV0");
+        assert!(!format!("{:?}", basic).contains("s3cret"));
+        assert!(Credentials::Negotiate { username: None }
+            .authorization()
+            .is_err());
+        assert!(set(
+            None,
+            Some(Credentials::Basic {
+                username: "a:b".to_string(),
+                password: String::new(),
+            })
+        )
+        .is_err());
+
+        set(Some("Finance Printer"), Some(basic.clone())).unwrap();
+        set(
+            Some("ipp://10.0.0.5/ipp/print"),
+            Some(Credentials::Negotiate { username: None }),
+        )
+        .unwrap();
+        assert_eq!(for_printer("Finance Printer"), Some(basic.clone()));
+        assert_eq!(for_printer("Other Printer"), None);
+        assert_eq!(
+            for_uri("ipp://localhost:631/printers/Finance%20Printer"),
+            Some(basic.clone())
+        );
+        assert_eq!(
+            for_uri("ipp://10.0.0.5:631/ipp/print"),
+            Some(Credentials::Negotiate { username: None })
+        );
+
+        set(None, Some(basic.clone())).unwrap();
+        assert_eq!(for_printer("Other Printer"), Some(basic.clone()));
+        assert_eq!(for_uri("ipp://10.0.0.9/ipp/print"), Some(basic));
+
+        set(None, None).unwrap();
+        set(Some("Finance Printer"), None).unwrap();
+        set(Some("ipp://10.0.0.5/ipp/print"), None).unwrap();
+        assert_eq!(for_printer("Finance Printer"), None);
+    }
+}
@@ -0,0 +1,247 @@
+//! Network addressing for printer devices
+//!
+//! Resolves printer URIs (`ipp://`, `socket://`, `lpd://`, ...) and Windows
+//! TCP/IP port names into host/port pairs that can be contacted directly.
+
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+/// A connection to a device, over TLS for `ipps` and `https`
+pub trait DeviceStream: Read + Write + Send {}
+
+impl<T: Read + Write + Send> DeviceStream for T {}
+
+/// Network location of a printer device
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeviceAddress {
+    pub scheme: String,
+    pub host: String,
+    pub port: u16,
+    pub path: String,
+}
+
+impl DeviceAddress {
+    /// Parse a device URI. Returns None for non-network schemes (usb, dnssd, ...).
+    pub fn parse(uri: &str) -> Option<DeviceAddress> {
+        let (scheme, rest) = uri.trim().split_once("://")?;
+        let scheme = scheme.to_lowercase();
+        let default_port = match scheme.as_str() {
+            "ipp" | "ipps" => 631,
+            "http" => 80,
+            "https" => 443,
+            "socket" => 9100,
+            "lpd" => 515,
+            _ => return None,
+        };
+
+        let authority_end = rest.find(['/', '?']).unwrap_or(rest.len());
+        let (authority, path) = rest.split_at(authority_end);
+
+        // Drop any userinfo component
+        let authority = authority.rsplit('@').next().unwrap_or(authority);
+
+        let (host, port) = if let Some(bracketed) = authority.strip_prefix('[') {
+            // IPv6 literal, e.g. [fe80::1]:631
+            let end = bracketed.find(']')?;
+            let port = match bracketed[end + 1..].strip_prefix(':') {
+                Some(port) => port.parse().ok()?,
+                None => default_port,
+            };
+            (&bracketed[..end], port)
+        } else {
+            match authority.rsplit_once(':') {
+                Some((host, port)) => (host, port.parse().ok()?),
+                None => (authority, default_port),
+            }
+        };
+
+        if host.is_empty() {
+            return None;
+        }
+
+        Some(DeviceAddress {
+            scheme,
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+        })
+    }
+
+    /// Parse a Windows standard TCP/IP port name (e.g. `IP_192.168.1.20`)
+    pub fn from_port_name(port_name: &str) -> Option<DeviceAddress> {
+        let host = port_name.strip_prefix("IP_")?.trim_end_matches(':');
+        if host.is_empty() {
+            return None;
+        }
+
+        Some(DeviceAddress {
+            scheme: "socket".to_string(),
+            host: host.to_string(),
+            port: 9100,
+            path: String::new(),
+        })
+    }
+
+    /// Whether the host refers to the local machine (e.g. a CUPS queue URI)
+    pub fn is_local(&self) -> bool {
+        matches!(self.host.as_str(), "localhost" | "127.0.0.1" | "::1")
+    }
+
+    /// Whether the scheme is IPP or HTTP, which IPP requests can be sent over
+    pub fn speaks_ipp(&self) -> bool {
+        matches!(self.scheme.as_str(), "ipp" | "ipps" | "http" | "https")
+    }
+
+    /// Whether the scheme requires TLS
+    pub fn is_secure(&self) -> bool {
+        matches!(self.scheme.as_str(), "ipps" | "https")
+    }
+
+    /// `host:port` with IPv6 literals bracketed
+    pub fn authority(&self) -> String {
+        if self.host.contains(':') {
+            format!("[{}]:{}", self.host, self.port)
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+
+    /// Reassemble the address as a URI
+    pub fn uri(&self) -> String {
+        format!("{}://{}{}", self.scheme, self.authority(), self.path)
+    }
+
+    /// Open a TCP connection to the device
+    pub fn connect(&self, timeout: Duration) -> Result<TcpStream, String> {
+        let addresses = (self.host.as_str(), self.port)
+            .to_socket_addrs()
+            .map_err(|e| format!("Failed to resolve '{}': {}", self.host, e))?;
+
+        let mut last_error = format!("No addresses found for '{}'", self.host);
+        for address in addresses {
+            match TcpStream::connect_timeout(&address, timeout) {
+                Ok(stream) => return Ok(stream),
+                Err(e) => last_error = format!("Failed to connect to {}: {}", address, e),
+            }
+        }
+
+        Err(last_error)
+    }
+
+    /// Open a connection with read and write timeouts, negotiating TLS for
+    /// `ipps` and `https`
+    pub fn open(&self, timeout: Duration) -> Result<Box<dyn DeviceStream>, String> {
+        let stream = self.connect(timeout)?;
+        stream
+            .set_read_timeout(Some(timeout))
+            .and_then(|_| stream.set_write_timeout(Some(timeout)))
+            .map_err(|e| format!("Failed to configure socket: {}", e))?;
+        if !self.is_secure() {
+            return Ok(Box::new(stream));
+        }
+
+        #[cfg(feature = "tls")]
+        return crate::tls::connect(self, stream)
+            .map(|stream| Box::new(stream) as Box<dyn DeviceStream>);
+        #[cfg(not(feature = "tls"))]
+        Err(format!(
+            "Scheme '{}' requires TLS, which this build doesn't include (the `tls` feature)",
+            self.scheme
+        ))
+    }
+}
+
+/// Whether a device URI (`usb://...`, `file:/dev/null`) or Windows port name
+/// (`USB001`, `LPT1:`) is attached to this machine, so jobs don't cross a
+/// network to reach it
+pub fn is_attached(device: &str) -> bool {
+    let device = device.trim();
+    if let Some((scheme, rest)) = device.split_once(':').filter(|(_, rest)| !rest.is_empty()) {
+        return match scheme.to_lowercase().as_str() {
+            "usb" | "parallel" | "serial" | "file" | "cups-pdf" => true,
+            // HPLIP reaches USB and parallel devices as hp:/usb/... and
+            // hp:/par/..., and network ones as hp:/net/...
+            "hp" | "hpfax" => rest.starts_with("/usb/") || rest.starts_with("/par/"),
+            _ => false,
+        };
+    }
+
+    let port = device.trim_end_matches(':').to_uppercase();
+    let numbered = |prefix: &str| {
+        port.strip_prefix(prefix)
+            .is_some_and(|number| !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()))
+    };
+    numbered("USB")
+        || numbered("LPT")
+        || numbered("COM")
+        || matches!(port.as_str(), "FILE" | "PORTPROMPT" | "NUL")
+}
+
+/// Read a response until the server closes the connection. Many printers
+/// close TLS connections without a close_notify alert, which is treated as
+/// the end of the response.
+pub fn read_to_close(stream: &mut dyn Read) -> io::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    match stream.read_to_end(&mut data) {
+        Ok(_) => Ok(data),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof && !data.is_empty() => Ok(data),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_device_uris() {
+        let address = DeviceAddress::parse("ipp://192.168.1.20/ipp/print").unwrap();
+        assert_eq!(address.scheme, "ipp");
+        assert_eq!(address.host, "192.168.1.20");
+        assert_eq!(address.port, 631);
+        assert_eq!(address.path, "/ipp/print");
+
+        let address = DeviceAddress::parse("socket://printer.local:9101?waiteof=false").unwrap();
+        assert_eq!(address.host, "printer.local");
+        assert_eq!(address.port, 9101);
+
+        let address = DeviceAddress::parse("ipps://user@[fe80::1]:8631/ipp").unwrap();
+        assert_eq!(address.host, "fe80::1");
+        assert_eq!(address.port, 8631);
+        assert_eq!(address.uri(), "ipps://[fe80::1]:8631/ipp");
+
+        assert_eq!(DeviceAddress::parse("lpd://host/queue").unwrap().port, 515);
+        assert!(DeviceAddress::parse("usb://HP/LaserJet?serial=123").is_none());
+        assert!(DeviceAddress::parse("mock://printer").is_none());
+        assert!(DeviceAddress::parse("not a uri").is_none());
+    }
+
+    #[test]
+    fn test_parse_windows_port_names() {
+        let address = DeviceAddress::from_port_name("IP_10.0.0.5").unwrap();
+        assert_eq!(address.scheme, "socket");
+        assert_eq!(address.host, "10.0.0.5");
+        assert_eq!(address.port, 9100);
+
+        assert!(DeviceAddress::from_port_name("USB001").is_none());
+        assert!(DeviceAddress::from_port_name("IP_").is_none());
+    }
+
+    #[test]
+    fn test_attached_devices() {
+        assert!(is_attached("usb://HP/LaserJet?serial=123"));
+        assert!(is_attached("file:/dev/null"));
+        assert!(is_attached("hp:/usb/LaserJet?serial=123"));
+        assert!(is_attached("USB001"));
+        assert!(is_attached("LPT1:"));
+        assert!(is_attached("PORTPROMPT:"));
+
+        assert!(!is_attached("hp:/net/LaserJet?ip=10.0.0.5"));
+        assert!(!is_attached("dnssd://Printer._ipp._tcp.local/"));
+        assert!(!is_attached("ipp://10.0.0.5/ipp/print"));
+        assert!(!is_attached("WSD-1234"));
+        assert!(!is_attached("USB"));
+        assert!(!is_attached(""));
+    }
+}
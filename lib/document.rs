@@ -0,0 +1,915 @@
+//! Document inspection helpers
+//!
+//! Lightweight, dependency-free inspection of print documents before they are
+//! handed to the spooler, extraction of their text for indexing and content
+//! checks, and generation of diagnostic test pages.
+
+use crate::pdf::{Dict, Object, Operation};
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+/// Count the pages of a PDF document.
+///
+/// Walks the page tree if the document can be read with `pdf`. Otherwise
+/// counts `/Type /Page` objects, falling back to the largest `/Count` entry of
+/// the page tree when page objects are hidden in compressed object streams.
+/// Returns None if the data isn't a PDF or no page information is visible.
+pub fn pdf_page_count(data: &[u8]) -> Option<u32> {
+    if !is_pdf(data) {
+        return None;
+    }
+    // The page tree gives the exact count, also when incremental updates
+    // have redefined pages
+    if let Ok(pages) = crate::pdf::Document::parse(data).and_then(|document| document.pages()) {
+        return Some(pages.len() as u32);
+    }
+
+    let mut pages = 0u32;
+    let mut max_count = 0u32;
+    let mut pos = 0;
+    while let Some(offset) = find(&data[pos..], b"/Type") {
+        pos += offset + b"/Type".len();
+        let rest = skip_whitespace(&data[pos..]);
+        if let Some(after) = rest.strip_prefix(b"/Page") {
+            // Exclude /Pages (tree nodes) and other names with the same prefix
+            if !after.first().is_some_and(u8::is_ascii_alphanumeric) {
+                pages += 1;
+            }
+        }
+    }
+
+    pos = 0;
+    while let Some(offset) = find(&data[pos..], b"/Count") {
+        pos += offset + b"/Count".len();
+        let digits: Vec<u8> = skip_whitespace(&data[pos..])
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .copied()
+            .collect();
+        if let Some(count) = std::str::from_utf8(&digits)
+            .ok()
+            .and_then(|s| s.parse::<u32>().ok())
+        {
+            max_count = max_count.max(count);
+        }
+    }
+
+    match (pages, max_count) {
+        (0, 0) => None,
+        (0, count) => Some(count),
+        (pages, _) => Some(pages),
+    }
+}
+
+/// Check that a PDF is complete enough to print, returning its page count.
+///
+/// Catches truncated uploads and files that merely carry a `.pdf` name: the
+/// header and version, the `%%EOF` marker and cross-reference pointer at the
+/// end, the document catalog and at least one page must all be present.
+pub fn validate_pdf(data: &[u8]) -> Result<u32, String> {
+    let header = find(&data[..data.len().min(1024)], b"%PDF-")
+        .ok_or_else(|| "Missing %PDF header".to_string())?;
+    let version = &data[header + b"%PDF-".len()..];
+    let valid_version = matches!(
+        version,
+        [b'1'..=b'2', b'.', minor, ..] if minor.is_ascii_digit()
+    );
+    if !valid_version {
+        return Err("Invalid PDF version in header".to_string());
+    }
+
+    let tail = &data[data.len().saturating_sub(1024)..];
+    if find(tail, b"%%EOF").is_none() {
+        return Err("Missing %%EOF marker, the file may be truncated".to_string());
+    }
+    if find(tail, b"startxref").is_none() {
+        return Err("Missing cross-reference table".to_string());
+    }
+    if find(data, b"/Root").is_none() {
+        return Err("Missing document catalog".to_string());
+    }
+
+    pdf_page_count(data)
+        .filter(|pages| *pages > 0)
+        .ok_or_else(|| "No pages found".to_string())
+}
+
+/// Detect a document's media type from its leading magic bytes
+pub fn sniff_media_type(data: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"%!", "application/postscript"),
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+    ];
+    if is_pdf(data) {
+        return Some("application/pdf");
+    }
+    SIGNATURES
+        .iter()
+        .find(|(signature, _)| data.starts_with(signature))
+        .map(|(_, media_type)| *media_type)
+}
+
+/// Whether the data starts with a PDF header
+pub fn is_pdf(data: &[u8]) -> bool {
+    // The header may be preceded by junk bytes within the first KB
+    find(&data[..data.len().min(1024)], b"%PDF-").is_some()
+}
+
+/// Nesting of form XObjects followed when extracting text
+const MAX_FORM_DEPTH: usize = 8;
+
+/// Content stream operators run when extracting the text of a document
+const MAX_TEXT_OPERATIONS: usize = 1_000_000;
+
+/// Largest ToUnicode range read, in character codes
+const MAX_CMAP_RANGE: u32 = 0x10000;
+
+/// PostScript operators that show the last string before them
+const POSTSCRIPT_SHOW_OPERATORS: [&str; 9] = [
+    "show",
+    "ashow",
+    "widthshow",
+    "awidthshow",
+    "kshow",
+    "cshow",
+    "xshow",
+    "yshow",
+    "xyshow",
+];
+
+/// Extract the text of a PDF or PostScript document, in the order it is
+/// drawn, with a line break between lines and pages. Plain text documents
+/// are returned as they are.
+///
+/// Text drawn with fonts that map their characters neither to a standard
+/// encoding nor with a ToUnicode table comes out garbled, and text in
+/// images isn't found. It is meant for indexing and content checks, not
+/// for reproducing the layout.
+pub fn extract_text(data: &[u8]) -> Result<String, String> {
+    match sniff_media_type(data) {
+        Some("application/pdf") => pdf_text(data),
+        Some("application/postscript") => Ok(postscript_text(data)),
+        None if !data.contains(&0) => std::str::from_utf8(data)
+            .map(str::to_string)
+            .map_err(|_| "Document is not text".to_string()),
+        _ => Err(
+            "Text can only be extracted from PDF, PostScript and plain text documents".to_string(),
+        ),
+    }
+}
+
+/// Text of a PDF, page by page
+fn pdf_text(data: &[u8]) -> Result<String, String> {
+    let document = crate::pdf::Document::parse(data)?;
+    let mut text = TextWriter::default();
+    let mut forms = Forms::default();
+    for page in document.pages()? {
+        let content = document.page_content(&page)?;
+        content_text(&document, &content, &page.resources, &mut text, &mut forms)?;
+        text.line_break();
+    }
+    Ok(text.finish())
+}
+
+/// Form XObjects met while extracting text
+#[derive(Default)]
+struct Forms {
+    /// Forms being drawn, innermost last, so a form that draws itself is
+    /// refused instead of followed until the depth limit
+    active: Vec<u32>,
+    /// Text of each form already extracted, as forms are often drawn on
+    /// every page, and the operators extracting it took
+    text: HashMap<u32, (String, usize)>,
+    /// Operators run so far, against `MAX_TEXT_OPERATIONS`
+    operations: usize,
+}
+
+/// Collects extracted text, keeping one space or line break between runs
+#[derive(Default)]
+struct TextWriter {
+    text: String,
+}
+
+impl TextWriter {
+    fn push(&mut self, text: &str) {
+        self.text.push_str(text);
+    }
+
+    fn space(&mut self) {
+        if !self.text.is_empty() && !self.text.ends_with([' ', '\n']) {
+            self.text.push(' ');
+        }
+    }
+
+    fn line_break(&mut self) {
+        let trimmed = self.text.trim_end_matches(' ').len();
+        self.text.truncate(trimmed);
+        if !self.text.is_empty() && !self.text.ends_with('\n') {
+            self.text.push('\n');
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.line_break();
+        self.text
+    }
+}
+
+/// How the character codes of a font map to text
+struct FontText {
+    /// Bytes per character code
+    code_bytes: usize,
+    /// Text of codes, from the font's ToUnicode CMap
+    unicode: HashMap<u32, String>,
+}
+
+impl FontText {
+    fn load(document: &crate::pdf::Document<'_>, font: Option<&Object>) -> FontText {
+        let font = font
+            .and_then(|font| document.resolve(font))
+            .and_then(Object::into_dict)
+            .unwrap_or_default();
+        // Composite fonts mostly use two-byte codes, as Identity-H does
+        let composite = font.get("Subtype").and_then(Object::as_name) == Some("Type0");
+        let mut text = FontText {
+            code_bytes: if composite { 2 } else { 1 },
+            unicode: HashMap::new(),
+        };
+        let cmap = font
+            .get("ToUnicode")
+            .and_then(Object::as_reference)
+            .and_then(|(number, _)| document.decoded_stream(number).ok());
+        if let Some((_, cmap)) = cmap {
+            text.read_cmap(&cmap);
+        }
+        text
+    }
+
+    /// Read the code space and mappings of a ToUnicode CMap
+    fn read_cmap(&mut self, cmap: &[u8]) {
+        let bytes = |object: &Object| match object {
+            Object::String(raw) => Some(crate::pdf::string_bytes(raw)),
+            _ => None,
+        };
+        let code = |bytes: &[u8]| bytes.iter().fold(0u32, |code, b| (code << 8) | *b as u32);
+        let unicode = |bytes: &[u8]| {
+            let units: Vec<u16> = bytes
+                .chunks(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair.get(1).copied().unwrap_or(0)]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        };
+        for Operation { operator, operands } in crate::pdf::operations(cmap) {
+            match operator.as_str() {
+                "endcodespacerange" => {
+                    if let Some(low) = operands.first().and_then(bytes) {
+                        self.code_bytes = low.len().clamp(1, 4);
+                    }
+                }
+                "endbfchar" => {
+                    for pair in operands.chunks(2) {
+                        if let (Some(source), Some(target)) =
+                            (bytes(&pair[0]), pair.get(1).and_then(bytes))
+                        {
+                            self.unicode.insert(code(&source), unicode(&target));
+                        }
+                    }
+                }
+                "endbfrange" => {
+                    for range in operands.chunks(3) {
+                        let (Some(low), Some(high), Some(target)) =
+                            (bytes(&range[0]), range.get(1).and_then(bytes), range.get(2))
+                        else {
+                            continue;
+                        };
+                        let (low, high) = (code(&low), code(&high));
+                        if high < low || high - low >= MAX_CMAP_RANGE {
+                            continue;
+                        }
+                        for (offset, source) in (low..=high).enumerate() {
+                            let text = match target {
+                                Object::Array(targets) => {
+                                    targets.get(offset).and_then(bytes).map(|t| unicode(&t))
+                                }
+                                target => bytes(target).map(|mut first| {
+                                    // Consecutive codes map to consecutive
+                                    // characters
+                                    if let Some(last) = first.last_mut() {
+                                        *last = last.wrapping_add(offset as u8);
+                                    }
+                                    unicode(&first)
+                                }),
+                            };
+                            if let Some(text) = text {
+                                self.unicode.insert(source, text);
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn decode(&self, bytes: &[u8]) -> String {
+        if self.unicode.is_empty() && self.code_bytes == 1 {
+            return crate::pdf::decode_text(bytes);
+        }
+        let mut text = String::new();
+        for chunk in bytes.chunks(self.code_bytes) {
+            let code = chunk.iter().fold(0u32, |code, b| (code << 8) | *b as u32);
+            match self.unicode.get(&code) {
+                Some(mapped) => text.push_str(mapped),
+                // Single bytes without a mapping are most likely the
+                // standard encoding
+                None if self.code_bytes == 1 => text.push_str(&crate::pdf::decode_text(chunk)),
+                None => {}
+            }
+        }
+        text
+    }
+}
+
+/// Extract the text drawn by content with `resources`, following the form
+/// XObjects it draws
+fn content_text(
+    document: &crate::pdf::Document<'_>,
+    content: &[u8],
+    resources: &Dict,
+    text: &mut TextWriter,
+    forms: &mut Forms,
+) -> Result<(), String> {
+    let resource = |category: &str, name: &str| {
+        let entries = document.resolve(resources.get(category)?)?;
+        entries.as_dict()?.get(name).cloned()
+    };
+    let mut fonts: HashMap<String, FontText> = HashMap::new();
+    let mut font = String::new();
+    let mut line_y = None;
+    for Operation { operator, operands } in crate::pdf::operations(content) {
+        forms.operations += 1;
+        if forms.operations > MAX_TEXT_OPERATIONS {
+            return Err(too_much_content());
+        }
+        let mut show = |text: &mut TextWriter, object: Option<&Object>| {
+            if let Some(Object::String(raw)) = object {
+                let decoder = fonts
+                    .entry(font.clone())
+                    .or_insert_with(|| FontText::load(document, resource("Font", &font).as_ref()));
+                text.push(&decoder.decode(&crate::pdf::string_bytes(raw)));
+            }
+        };
+        let number = |index: usize| operands.get(index).and_then(Object::as_number);
+        match operator.as_str() {
+            "Tf" => {
+                if let Some(name) = operands.first().and_then(Object::as_name) {
+                    font = name.to_string();
+                }
+            }
+            "Tj" => show(text, operands.first()),
+            "'" => {
+                text.line_break();
+                show(text, operands.first());
+            }
+            "\"" => {
+                text.line_break();
+                show(text, operands.get(2));
+            }
+            "TJ" => {
+                for item in operands
+                    .first()
+                    .and_then(Object::as_array)
+                    .unwrap_or_default()
+                {
+                    match item {
+                        // Gaps wider than a fifth of the font size separate
+                        // words
+                        Object::Number(adjustment) if *adjustment < -200.0 => text.space(),
+                        item => show(text, Some(item)),
+                    }
+                }
+            }
+            "Td" | "TD" => match number(1) {
+                Some(dy) if dy.abs() > 0.01 => text.line_break(),
+                _ => text.space(),
+            },
+            "Tm" => {
+                let y = number(5);
+                if line_y.is_some() && line_y != y {
+                    text.line_break();
+                } else {
+                    text.space();
+                }
+                line_y = y;
+            }
+            "T*" | "ET" => text.line_break(),
+            "Do" if forms.active.len() < MAX_FORM_DEPTH => {
+                let Some((number, _)) = operands
+                    .first()
+                    .and_then(Object::as_name)
+                    .and_then(|name| resource("XObject", name))
+                    .and_then(|form| form.as_reference())
+                else {
+                    continue;
+                };
+                if forms.active.contains(&number) {
+                    continue;
+                }
+                if !forms.text.contains_key(&number) {
+                    let before = forms.operations;
+                    forms.active.push(number);
+                    let extracted = form_text(document, number, resources, forms);
+                    forms.active.pop();
+                    let operations = forms.operations - before;
+                    forms.text.insert(number, (extracted?, operations));
+                } else {
+                    // Drawing a form again costs what extracting it did
+                    forms.operations += forms.text[&number].1;
+                    if forms.operations > MAX_TEXT_OPERATIONS {
+                        return Err(too_much_content());
+                    }
+                }
+                let (form_text, _) = &forms.text[&number];
+                if !form_text.is_empty() {
+                    text.space();
+                    text.push(form_text);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Text drawn by the form XObject in object `number`, empty if it is an
+/// image or can't be read
+fn form_text(
+    document: &crate::pdf::Document<'_>,
+    number: u32,
+    resources: &Dict,
+    forms: &mut Forms,
+) -> Result<String, String> {
+    let Ok((dict, content)) = document.decoded_stream(number) else {
+        return Ok(String::new());
+    };
+    if dict.get("Subtype").and_then(Object::as_name) != Some("Form") {
+        return Ok(String::new());
+    }
+    let form_resources = dict
+        .get("Resources")
+        .and_then(|resources| document.resolve(resources))
+        .and_then(Object::into_dict);
+    let form_resources = form_resources.as_ref().unwrap_or(resources);
+    let mut text = TextWriter::default();
+    content_text(document, &content, form_resources, &mut text, forms)?;
+    Ok(text.text)
+}
+
+fn too_much_content() -> String {
+    "Document has too much content to extract text from".to_string()
+}
+
+/// Text shown by a PostScript program. Strings are taken from the show
+/// operators, including procedures defined to call them, and a line break
+/// is made when `moveto` starts a new line.
+fn postscript_text(data: &[u8]) -> String {
+    let mut text = TextWriter::default();
+    let mut show_operators: Vec<String> = POSTSCRIPT_SHOW_OPERATORS
+        .iter()
+        .map(|operator| operator.to_string())
+        .collect();
+    let mut last_string: Option<Vec<u8>> = None;
+    let mut last_numbers: Vec<f64> = Vec::new();
+    let mut line_y = None;
+    // Procedure being defined, `/name {`, and whether it shows text
+    let mut pending_name: Option<String> = None;
+    let mut procedure: Option<(String, bool)> = None;
+    let mut depth = 0usize;
+
+    let mut i = 0;
+    while i < data.len() {
+        let byte = data[i];
+        match byte {
+            b'%' => {
+                while i < data.len() && data[i] != b'\n' && data[i] != b'\r' {
+                    i += 1;
+                }
+            }
+            b'(' => {
+                let start = i;
+                let mut nesting = 0;
+                while i < data.len() {
+                    match data[i] {
+                        b'\\' => i += 1,
+                        b'(' => nesting += 1,
+                        b')' => {
+                            nesting -= 1;
+                            if nesting == 0 {
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+                let end = (i + 1).min(data.len());
+                if depth == 0 {
+                    last_string = Some(crate::pdf::string_bytes(&data[start..end]));
+                }
+                i = end;
+                continue;
+            }
+            b'<' if data.get(i + 1) == Some(&b'<') => i += 1,
+            b'<' => {
+                // ASCII85 data ends with ~>, hex strings with >
+                let ascii85 = data.get(i + 1) == Some(&b'~');
+                let terminator: &[u8] = if ascii85 { b"~>" } else { b">" };
+                let end = find(&data[i..], terminator).map_or(data.len(), |at| i + at);
+                if !ascii85 && depth == 0 {
+                    last_string =
+                        Some(crate::pdf::string_bytes(&data[i..=end.min(data.len() - 1)]));
+                }
+                i = end + terminator.len();
+                continue;
+            }
+            b'{' => {
+                if depth == 0 {
+                    procedure = pending_name.take().map(|name| (name, false));
+                }
+                depth += 1;
+            }
+            b'}' => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    if let Some((name, true)) = procedure.take() {
+                        show_operators.push(name);
+                    }
+                }
+            }
+            b'/' => {
+                let start = i + 1;
+                i = start;
+                while i < data.len() && !is_postscript_delimiter(data[i]) {
+                    i += 1;
+                }
+                pending_name = Some(String::from_utf8_lossy(&data[start..i]).into_owned());
+                continue;
+            }
+            byte if is_postscript_delimiter(byte) => {}
+            _ => {
+                let start = i;
+                while i < data.len() && !is_postscript_delimiter(data[i]) {
+                    i += 1;
+                }
+                let token = String::from_utf8_lossy(&data[start..i]);
+                pending_name = None;
+                if let Ok(number) = token.parse::<f64>() {
+                    last_numbers.push(number);
+                    if last_numbers.len() > 2 {
+                        last_numbers.remove(0);
+                    }
+                    continue;
+                }
+                let shows = show_operators.iter().any(|operator| *operator == token);
+                if depth > 0 {
+                    if let Some((_, shows_text)) = procedure.as_mut() {
+                        *shows_text |= shows;
+                    }
+                } else if shows {
+                    if let Some(string) = last_string.take() {
+                        text.push(&crate::pdf::decode_text(&string));
+                    }
+                } else if token == "moveto" {
+                    let y = last_numbers.last().copied();
+                    if line_y.is_some() && line_y != y {
+                        text.line_break();
+                    } else {
+                        text.space();
+                    }
+                    line_y = y;
+                } else if token == "showpage" {
+                    text.line_break();
+                    line_y = None;
+                }
+                continue;
+            }
+        }
+        i += 1;
+    }
+    text.finish()
+}
+
+fn is_postscript_delimiter(byte: u8) -> bool {
+    byte.is_ascii_whitespace() || b"()<>[]{}/%".contains(&byte)
+}
+
+/// Width and height of generated test pages: US Letter, in points
+const TEST_PAGE_SIZE: (u32, u32) = (612, 792);
+
+/// Build a one-page PDF diagnostic page: a title and lines of text over an
+/// alignment grid with a one-inch pitch, a border a quarter inch inside the
+/// page edges and a crosshair at the center, so offset and scaling can be
+/// checked with a ruler.
+pub fn test_page_pdf(title: &str, lines: &[String]) -> Vec<u8> {
+    let (width, height) = TEST_PAGE_SIZE;
+    let mut content = String::new();
+
+    // Light gray grid
+    content.push_str("0.75 G 0.5 w\n");
+    for x in (72..width).step_by(72) {
+        content.push_str(&format!("{} 0 m {} {} l S\n", x, x, height));
+    }
+    for y in (72..height).step_by(72) {
+        content.push_str(&format!("0 {} m {} {} l S\n", y, width, y));
+    }
+
+    // Border and center crosshair
+    let (cx, cy) = (width / 2, height / 2);
+    content.push_str(&format!(
+        "0 G 1 w\n18 18 {} {} re S\n{} {} m {} {} l S\n{} {} m {} {} l S\n",
+        width - 36,
+        height - 36,
+        cx - 36,
+        cy,
+        cx + 36,
+        cy,
+        cx,
+        cy - 36,
+        cx,
+        cy + 36
+    ));
+
+    // Text on a white panel near the top, so the grid doesn't obscure it
+    let top = height - 54;
+    let panel_height = 66 + 18 * lines.len() as u32;
+    content.push_str(&format!(
+        "1 g\n54 {} {} {} re f\n0 g\n",
+        top - panel_height,
+        width - 108,
+        panel_height
+    ));
+    content.push_str(&format!(
+        "BT /F1 24 Tf 72 {} Td ({}) Tj ET\n",
+        top - 36,
+        pdf_string(title)
+    ));
+    for (i, line) in lines.iter().enumerate() {
+        content.push_str(&format!(
+            "BT /F1 12 Tf 72 {} Td ({}) Tj ET\n",
+            top - 66 - 18 * i as u32,
+            pdf_string(line)
+        ));
+    }
+
+    let objects = [
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+             /Resources << /Font << /F1 4 0 R >> >> /Contents 5 0 R >>",
+            width, height
+        ),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+        format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            content.len(),
+            content
+        ),
+    ];
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).as_bytes());
+    }
+    let xref = pdf.len();
+    let mut trailer = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        trailer.push_str(&format!("{:010} 00000 n \n", offset));
+    }
+    trailer.push_str(&format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    ));
+    pdf.extend_from_slice(trailer.as_bytes());
+    pdf
+}
+
+/// Escape text for a PDF string literal, replacing characters the standard
+/// fonts can't show
+fn pdf_string(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '(' | ')' | '\\' => format!("\\{}", c),
+            ' '..='~' => c.to_string(),
+            _ => "?".to_string(),
+        })
+        .collect()
+}
+
+/// Format a time as `YYYY-MM-DD HH:MM:SS UTC`
+pub fn format_utc(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+
+    // Civil date from days since the epoch (Howard Hinnant's algorithm)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+pub(crate) fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn skip_whitespace(data: &[u8]) -> &[u8] {
+    let start = data
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(data.len());
+    &data[start..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pdf_page_count() {
+        let pdf = b"%PDF-1.4\n\
+            1 0 obj << /Type /Catalog /Pages 2 0 R >> endobj\n\
+            2 0 obj << /Type /Pages /Kids [3 0 R 4 0 R 5 0 R] /Count 3 >> endobj\n\
+            3 0 obj << /Type /Page /Parent 2 0 R >> endobj\n\
+            4 0 obj << /Type/Page /Parent 2 0 R >> endobj\n\
+            5 0 obj << /Type /Page\n/Parent 2 0 R >> endobj\n\
+            %%EOF";
+        assert_eq!(pdf_page_count(pdf), Some(3));
+
+        // Page objects in compressed object streams: only the tree count is visible
+        let compressed = b"%PDF-1.7\n2 0 obj << /Type /Pages /Count 12 /Kids [] >> endobj";
+        assert_eq!(pdf_page_count(compressed), Some(12));
+
+        assert_eq!(pdf_page_count(b"%PDF-1.7\n%%EOF"), None);
+        assert_eq!(pdf_page_count(b"plain text"), None);
+    }
+
+    #[test]
+    fn test_sniff_media_type() {
+        assert_eq!(sniff_media_type(b"%PDF-1.7\n"), Some("application/pdf"));
+        assert_eq!(
+            sniff_media_type(b"%!PS-Adobe-3.0\n"),
+            Some("application/postscript")
+        );
+        assert_eq!(
+            sniff_media_type(b"\x89PNG\r\n\x1a\n\0\0"),
+            Some("image/png")
+        );
+        assert_eq!(sniff_media_type(b"\xff\xd8\xff\xe0"), Some("image/jpeg"));
+        assert_eq!(sniff_media_type(b"GIF89a"), Some("image/gif"));
+        assert_eq!(sniff_media_type(b"^XA^FO50,50^XZ"), None);
+        assert_eq!(sniff_media_type(b""), None);
+    }
+
+    #[test]
+    fn test_validate_pdf() {
+        let pdf = b"%PDF-1.4\n\
+            1 0 obj << /Type /Catalog /Pages 2 0 R >> endobj\n\
+            2 0 obj << /Type /Pages /Kids [3 0 R] /Count 1 >> endobj\n\
+            3 0 obj << /Type /Page /Parent 2 0 R >> endobj\n\
+            trailer << /Root 1 0 R >>\nstartxref\n9\n%%EOF\n";
+        assert_eq!(validate_pdf(pdf), Ok(1));
+
+        let truncated = &pdf[..pdf.len() - 20];
+        assert!(validate_pdf(truncated).unwrap_err().contains("%%EOF"));
+        assert!(validate_pdf(b"%PDF-x.y\n%%EOF").is_err());
+        assert!(validate_pdf(b"not a pdf %%EOF").is_err());
+        assert_eq!(
+            validate_pdf(b"%PDF-1.7\n/Root 1 0 R\nstartxref\n0\n%%EOF"),
+            Err("No pages found".to_string())
+        );
+    }
+
+    #[test]
+    fn test_test_page_pdf() {
+        let lines = ["Printer: Label (Line 1)".to_string(), "Café".to_string()];
+        let pdf = test_page_pdf("Test Page", &lines);
+        assert_eq!(validate_pdf(&pdf), Ok(1));
+        assert_eq!(sniff_media_type(&pdf), Some("application/pdf"));
+
+        let text = String::from_utf8(pdf.clone()).unwrap();
+        assert!(text.contains("(Printer: Label \\(Line 1\\)) Tj"));
+        assert!(text.contains("(Caf?) Tj"));
+
+        // Cross-reference offsets point at their objects
+        let xref = text.find("\nxref\n").unwrap() + 1;
+        let offset: usize = text[xref..].lines().nth(4).unwrap()[..10].parse().unwrap();
+        assert!(text[offset..].starts_with("2 0 obj"));
+    }
+
+    #[test]
+    fn test_format_utc() {
+        let time = |secs| SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(secs);
+        assert_eq!(format_utc(time(0)), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_utc(time(951_782_400)), "2000-02-29 00:00:00 UTC");
+        assert_eq!(format_utc(time(1_792_156_245)), "2026-10-16 13:10:45 UTC");
+    }
+
+    #[test]
+    fn test_extract_text() {
+        let lines = [
+            "Printer: Label (Line 1)".to_string(),
+            "Card 4111 1111".to_string(),
+        ];
+        let pdf = test_page_pdf("Test Page", &lines);
+        assert_eq!(
+            extract_text(&pdf).unwrap(),
+            "Test Page\nPrinter: Label (Line 1)\nCard 4111 1111\n"
+        );
+
+        let postscript = b"%!PS-Adobe-3.0\n/S { show } bind def\n\
+            72 700 moveto (Hello) show ( world) S\n72 686 moveto <4869> show\nshowpage\n";
+        assert_eq!(extract_text(postscript).unwrap(), "Hello world\nHi\n");
+
+        assert_eq!(extract_text(b"plain text").unwrap(), "plain text");
+        assert!(extract_text(b"\x89PNG\r\n\x1a\n\0\0").is_err());
+    }
+
+    #[test]
+    fn test_extract_text_forms() {
+        let stream = |number: u32, dict: &str, content: &str| {
+            format!(
+                "{} 0 obj << {} /Length {} >>\nstream\n{}\nendstream endobj\n",
+                number,
+                dict,
+                content.len(),
+                content
+            )
+        };
+        let form = "/Type /XObject /Subtype /Form /Resources << /XObject << /Logo 5 0 R >> >>";
+        let pdf = format!(
+            "%PDF-1.4\n\
+             1 0 obj << /Type /Catalog /Pages 2 0 R >> endobj\n\
+             2 0 obj << /Type /Pages /Kids [3 0 R] /Count 1 >> endobj\n\
+             3 0 obj << /Type /Page /Parent 2 0 R /Contents 4 0 R \
+             /Resources << /XObject << /Logo 5 0 R >> >> >> endobj\n{}{}\
+             trailer << /Root 1 0 R >>\nstartxref\n9\n%%EOF\n",
+            stream(4, "", "/Logo Do /Logo Do"),
+            // A form that draws itself is followed once
+            stream(5, form, "BT (ACME) Tj ET /Logo Do")
+        );
+        assert_eq!(extract_text(pdf.as_bytes()).unwrap(), "ACME\nACME\n");
+
+        // Forms drawn over and over count against the operator budget
+        let draws = "/Logo Do ".repeat(MAX_TEXT_OPERATIONS / 4);
+        let pdf = pdf.replace("/Length 17", &format!("/Length {}", draws.len()));
+        let pdf = pdf.replace("/Logo Do /Logo Do", &draws);
+        assert!(extract_text(pdf.as_bytes())
+            .unwrap_err()
+            .contains("too much content"));
+    }
+
+    #[test]
+    fn test_to_unicode_cmap() {
+        let cmap = b"/CIDInit /ProcSet findresource begin 12 dict begin begincmap\n\
+            1 begincodespacerange <0000> <FFFF> endcodespacerange\n\
+            1 beginbfchar <0003> <0020> endbfchar\n\
+            2 beginbfrange <0024> <0026> <0041> <0030> <0031> [<00E9> <00DF>] endbfrange\n\
+            endcmap CMapName currentdict /CMap defineresource pop end end";
+        let mut font = FontText {
+            code_bytes: 1,
+            unicode: HashMap::new(),
+        };
+        font.read_cmap(cmap);
+        assert_eq!(font.code_bytes, 2);
+        assert_eq!(
+            font.decode(&[0, 0x24, 0, 3, 0, 0x26, 0, 0x31, 0, 0x99]),
+            "A Cß"
+        );
+    }
+}
@@ -0,0 +1,178 @@
+//! Encryption of print payloads at rest
+//!
+//! Payloads of jobs held for approval stay on disk until the job is approved
+//! or rejected, possibly across restarts. With a key set, they are written
+//! encrypted with AES-GCM (AES-128 or AES-256, by key length) under a random
+//! nonce and only decrypted when the job is released to print. Each payload
+//! is bound to its job, so payloads swapped between jobs fail to decrypt.
+//!
+//! The key is only kept in memory, so payloads encrypted with it can't be
+//! printed after a restart until it is set again. Payloads written without
+//! a key stay readable after one is set.
+
+#[cfg(feature = "encryption")]
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_128_GCM, AES_256_GCM, NONCE_LEN};
+#[cfg(feature = "encryption")]
+use ring::rand::{SecureRandom, SystemRandom};
+use std::borrow::Cow;
+#[cfg(feature = "encryption")]
+use std::sync::{Arc, RwLock};
+
+/// Start of every encrypted payload, followed by the nonce and the
+/// ciphertext with its tag
+pub const MAGIC: &[u8; 8] = b"PJSENC1\0";
+
+#[cfg(feature = "encryption")]
+lazy_static::lazy_static! {
+    static ref KEY: RwLock<Option<Arc<LessSafeKey>>> = RwLock::new(None);
+}
+
+/// Set the key payloads are encrypted with: 16 bytes for AES-128-GCM or 32
+/// for AES-256-GCM. None stops encrypting new payloads.
+#[cfg(feature = "encryption")]
+pub fn set_key(key: Option<&[u8]>) -> Result<(), String> {
+    let key = match key {
+        Some(key) => {
+            let algorithm = match key.len() {
+                16 => &AES_128_GCM,
+                32 => &AES_256_GCM,
+                len => {
+                    return Err(format!(
+                        "Encryption key must be 16 or 32 bytes, got {}",
+                        len
+                    ))
+                }
+            };
+            let key = UnboundKey::new(algorithm, key)
+                .map_err(|_| "Invalid encryption key".to_string())?;
+            Some(Arc::new(LessSafeKey::new(key)))
+        }
+        None => None,
+    };
+    tracing::info!(enabled = key.is_some(), "Spool encryption key set");
+    *KEY.write().unwrap() = key;
+    Ok(())
+}
+
+#[cfg(not(feature = "encryption"))]
+pub fn set_key(key: Option<&[u8]>) -> Result<(), String> {
+    match key {
+        Some(_) => Err("Spool encryption requires the encryption feature".to_string()),
+        None => Ok(()),
+    }
+}
+
+/// Whether new payloads are encrypted
+#[cfg(feature = "encryption")]
+pub fn enabled() -> bool {
+    KEY.read().unwrap().is_some()
+}
+
+#[cfg(not(feature = "encryption"))]
+pub fn enabled() -> bool {
+    false
+}
+
+/// Whether data starts like an encrypted payload
+pub fn is_sealed(header: &[u8]) -> bool {
+    header.starts_with(MAGIC)
+}
+
+/// Encrypt a payload for the job `context` identifies, or return it as it
+/// is if no key is set
+pub fn seal<'a>(data: &'a [u8], context: &[u8]) -> Result<Cow<'a, [u8]>, String> {
+    #[cfg(feature = "encryption")]
+    if let Some(key) = KEY.read().unwrap().clone() {
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| "Failed to generate a nonce".to_string())?;
+        let mut sealed = Vec::with_capacity(MAGIC.len() + NONCE_LEN + data.len() + 16);
+        sealed.extend_from_slice(MAGIC);
+        sealed.extend_from_slice(&nonce);
+        let mut ciphertext = data.to_vec();
+        key.seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(context),
+            &mut ciphertext,
+        )
+        .map_err(|_| "Failed to encrypt payload".to_string())?;
+        sealed.extend_from_slice(&ciphertext);
+        return Ok(Cow::Owned(sealed));
+    }
+    #[cfg(not(feature = "encryption"))]
+    let _ = context;
+    Ok(Cow::Borrowed(data))
+}
+
+/// Decrypt a payload sealed for the job `context` identifies. Payloads that
+/// weren't encrypted are returned as they are.
+pub fn open(data: Vec<u8>, context: &[u8]) -> Result<Vec<u8>, String> {
+    if !is_sealed(&data) {
+        return Ok(data);
+    }
+    #[cfg(feature = "encryption")]
+    {
+        let key = KEY
+            .read()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| "Payload is encrypted but no encryption key is set".to_string())?;
+        let body = &data[MAGIC.len()..];
+        if body.len() < NONCE_LEN {
+            return Err("Encrypted payload is truncated".to_string());
+        }
+        let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce)
+            .map_err(|_| "Encrypted payload is truncated".to_string())?;
+        let mut plaintext = ciphertext.to_vec();
+        let len = key
+            .open_in_place(nonce, Aad::from(context), &mut plaintext)
+            .map_err(|_| {
+                "Failed to decrypt payload: wrong key, or the payload was altered".to_string()
+            })?
+            .len();
+        plaintext.truncate(len);
+        Ok(plaintext)
+    }
+    #[cfg(not(feature = "encryption"))]
+    {
+        let _ = context;
+        Err("Payload is encrypted, which requires the encryption feature".to_string())
+    }
+}
+
+#[cfg(all(test, feature = "encryption"))]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+
+    #[test]
+    #[serial]
+    fn test_seal_and_open() {
+        assert!(set_key(Some(&[7; 20][..])).is_err());
+        set_key(None).unwrap();
+        assert!(!enabled());
+        assert_eq!(seal(b"plain", b"job-1").unwrap().as_ref(), b"plain");
+
+        set_key(Some(&[7; 32][..])).unwrap();
+        assert!(enabled());
+        let sealed = seal(b"%PDF-1.7 payroll", b"job-1").unwrap().into_owned();
+        assert!(is_sealed(&sealed));
+        assert!(!sealed.windows(7).any(|window| window == b"payroll"));
+        // Nonces are random, so the same payload encrypts differently
+        assert_ne!(seal(b"%PDF-1.7 payroll", b"job-1").unwrap(), sealed);
+        assert_eq!(open(sealed.clone(), b"job-1").unwrap(), b"%PDF-1.7 payroll");
+        assert!(open(sealed.clone(), b"job-2").is_err());
+        assert_eq!(open(b"plain".to_vec(), b"job-1").unwrap(), b"plain");
+
+        let mut altered = sealed.clone();
+        *altered.last_mut().unwrap() ^= 1;
+        assert!(open(altered, b"job-1").is_err());
+
+        set_key(Some(&[8; 16][..])).unwrap();
+        assert!(open(sealed.clone(), b"job-1").is_err());
+        set_key(None).unwrap();
+        assert!(open(sealed, b"job-1").is_err());
+    }
+}
@@ -0,0 +1,241 @@
+//! Job history export
+//!
+//! Writes job records to disk as JSON Lines or CSV for audit and compliance
+//! retention. Records are written one at a time through a buffered writer
+//! into a temporary file that replaces the target once it is complete, so a
+//! failed export never leaves a truncated file behind.
+//!
+//! JSON Lines records are jobs as serialized by `printer_job_to_json`, so
+//! they can be read back with `printer_job_from_json`. CSV has the columns
+//! in `FIELDS`, named the same. Times are Unix timestamps in seconds; in
+//! CSV, metadata is a JSON object and missing values are empty. Text that a
+//! spreadsheet would read as a formula (starting with `=`, `+`, `-`, `@`, a
+//! tab or a carriage return) is prefixed with `'`.
+
+use crate::core::PrinterJob;
+use serde_json::{json, Value};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::SystemTime;
+
+/// File format of an export
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// One JSON object per line
+    Jsonl,
+    /// Comma-separated values with a header row and CRLF line endings
+    /// (RFC 4180)
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn parse(format: &str) -> Option<ExportFormat> {
+        match format {
+            "jsonl" => Some(ExportFormat::Jsonl),
+            "csv" => Some(ExportFormat::Csv),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportFormat::Jsonl => "jsonl",
+            ExportFormat::Csv => "csv",
+        }
+    }
+}
+
+/// Exported fields, in CSV column order
+const FIELDS: [&str; 17] = [
+    "id",
+    "name",
+    "printerName",
+    "state",
+    "owner",
+    "mediaType",
+    "sizeBytes",
+    "totalPages",
+    "pages",
+    "sheets",
+    "createdAt",
+    "processedAt",
+    "completedAt",
+    "errorMessage",
+    "timedOut",
+    "reprintOf",
+    "metadata",
+];
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// A job's exported fields, in the order of `FIELDS`
+fn record(job: &PrinterJob) -> [Value; FIELDS.len()] {
+    [
+        json!(job.id),
+        json!(job.name),
+        json!(job.printer_name),
+        json!(job.state.as_string()),
+        json!(job.owner),
+        json!(job.media_type),
+        json!(job.size_bytes),
+        json!(job.total_pages),
+        json!(job.pages),
+        json!(job.sheets),
+        json!(unix_secs(job.created_at)),
+        json!(job.processed_at.map(unix_secs)),
+        json!(job.completed_at.map(unix_secs)),
+        json!(job.error_message),
+        json!(job.timed_out),
+        json!(job.reprint_of),
+        json!(job.job_options.metadata),
+    ]
+}
+
+/// A job as a JSON object, as written to JSONL exports
+pub fn job_json(job: &PrinterJob) -> Value {
+    serde_json::to_value(job).unwrap_or_default()
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break, and
+/// keep spreadsheets from evaluating text as a formula
+fn csv_field(value: &Value) -> String {
+    let text = match value {
+        Value::Null => return String::new(),
+        Value::String(text) if text.starts_with(['=', '+', '-', '@', '\t', '\r']) => {
+            format!("'{}", text)
+        }
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    };
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text
+    }
+}
+
+/// Write job records to `out`, returning how many were written
+pub fn write_jobs<'a>(
+    out: &mut impl Write,
+    jobs: impl IntoIterator<Item = &'a PrinterJob>,
+    format: ExportFormat,
+) -> io::Result<usize> {
+    if format == ExportFormat::Csv {
+        write!(out, "{}\r\n", FIELDS.join(","))?;
+    }
+    let mut written = 0;
+    for job in jobs {
+        match format {
+            ExportFormat::Jsonl => {
+                serde_json::to_writer(&mut *out, job)?;
+                writeln!(out)?;
+            }
+            ExportFormat::Csv => {
+                let fields: Vec<String> = record(job).iter().map(csv_field).collect();
+                write!(out, "{}\r\n", fields.join(","))?;
+            }
+        }
+        written += 1;
+    }
+    Ok(written)
+}
+
+/// Write job records to a file, replacing it once the export is complete
+pub fn write_file(path: &Path, jobs: &[PrinterJob], format: ExportFormat) -> Result<usize, String> {
+    let temp_path = path.with_extension(format!("{}.tmp", format.as_str()));
+    let result = File::create(&temp_path).and_then(|file| {
+        let mut out = BufWriter::new(file);
+        let written = write_jobs(&mut out, jobs, format)?;
+        out.into_inner()?.sync_all()?;
+        fs::rename(&temp_path, path)?;
+        Ok(written)
+    });
+    result.map_err(|e| {
+        let _ = fs::remove_file(&temp_path);
+        format!(
+            "Failed to export job history to '{}': {}",
+            path.display(),
+            e
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::PrinterJobState;
+
+    fn job(id: u64, name: &str) -> PrinterJob {
+        let mut job = PrinterJob::new(
+            id,
+            name.to_string(),
+            "application/pdf".to_string(),
+            "Simulated Printer".to_string(),
+        );
+        job.state = PrinterJobState::COMPLETED;
+        job.created_at = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        job
+    }
+
+    #[test]
+    fn test_export_formats() {
+        let mut tagged = job(2, "Invoice \"May\", final");
+        tagged
+            .job_options
+            .metadata
+            .insert("orderId".to_string(), "A-1".to_string());
+        let jobs = [job(1, "Report"), tagged, job(3, "=HYPERLINK(\"x\")")];
+
+        let mut jsonl = Vec::new();
+        assert_eq!(
+            write_jobs(&mut jsonl, &jobs, ExportFormat::Jsonl).unwrap(),
+            3
+        );
+        let lines: Vec<Value> = String::from_utf8(jsonl)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["state"], "completed");
+        assert_eq!(lines[0]["createdAt"], 1_700_000_000);
+        assert_eq!(lines[0]["completedAt"], Value::Null);
+        assert_eq!(lines[1]["metadata"]["orderId"], "A-1");
+        let parsed = crate::core::printer_job_from_json(&lines[1].to_string()).unwrap();
+        assert_eq!(parsed.name, jobs[1].name);
+        assert_eq!(parsed.created_at, jobs[1].created_at);
+
+        let mut csv = Vec::new();
+        write_jobs(&mut csv, &jobs, ExportFormat::Csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(rows[0], FIELDS.join(","));
+        assert!(rows[1].starts_with("1,Report,Simulated Printer,completed,,application/pdf,0,"));
+        assert!(rows[2].starts_with("2,\"Invoice \"\"May\"\", final\","));
+        assert!(rows[2].ends_with(",\"{\"\"orderId\"\":\"\"A-1\"\"}\""));
+        assert!(rows[3].starts_with("3,\"'=HYPERLINK(\"\"x\"\")\","));
+        assert!(csv.ends_with("\r\n"));
+    }
+
+    #[test]
+    fn test_export_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        fs::write(&path, "old export").unwrap();
+
+        assert_eq!(
+            write_file(&path, &[job(1, "Report")], ExportFormat::Jsonl).unwrap(),
+            1
+        );
+        assert!(fs::read_to_string(&path).unwrap().starts_with("{\"id\":1,"));
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        let missing = dir.path().join("missing").join("history.csv");
+        assert!(write_file(&missing, &[], ExportFormat::Csv).is_err());
+    }
+}
@@ -0,0 +1,149 @@
+//! Finishing: stapling, punching, folding and output bins
+//!
+//! Office multifunction printers with a finisher staple, punch and fold
+//! jobs and deliver them to one of several output bins. IPP asks for these
+//! with the `finishings` enum attribute and the `output-bin` keyword,
+//! which CUPS also maps to the options of PPD drivers for such printers.
+//! This module maps the typed finishing options to those values and names
+//! the finishings a printer reports.
+
+use serde::{Deserialize, Serialize};
+
+/// IPP `finishings` enum values by keyword (PWG 5100.1)
+const FINISHINGS: &[(&str, i32)] = &[
+    ("none", 3),
+    ("staple", 4),
+    ("punch", 5),
+    ("fold", 10),
+    ("staple-top-left", 20),
+    ("staple-bottom-left", 21),
+    ("staple-top-right", 22),
+    ("staple-bottom-right", 23),
+    ("staple-dual-left", 28),
+    ("staple-dual-top", 29),
+    ("staple-dual-right", 30),
+    ("staple-dual-bottom", 31),
+    ("punch-top-left", 70),
+    ("punch-bottom-left", 71),
+    ("punch-top-right", 72),
+    ("punch-bottom-right", 73),
+    ("punch-dual-left", 74),
+    ("punch-dual-top", 75),
+    ("punch-dual-right", 76),
+    ("punch-dual-bottom", 77),
+    ("punch-triple-left", 78),
+    ("punch-triple-top", 79),
+    ("punch-triple-right", 80),
+    ("punch-triple-bottom", 81),
+    ("punch-quad-left", 82),
+    ("punch-quad-top", 83),
+    ("punch-quad-right", 84),
+    ("punch-quad-bottom", 85),
+    ("fold-accordion", 90),
+    ("fold-double-gate", 91),
+    ("fold-gate", 92),
+    ("fold-half", 93),
+    ("fold-half-z", 94),
+    ("fold-left-gate", 95),
+    ("fold-letter", 96),
+    ("fold-parallel", 97),
+    ("fold-poster", 98),
+    ("fold-right-gate", 99),
+    ("fold-z", 100),
+];
+
+/// Keyword of a `finishings` enum value
+pub fn name(value: i32) -> Option<&'static str> {
+    FINISHINGS
+        .iter()
+        .find(|(_, v)| *v == value)
+        .map(|(name, _)| *name)
+}
+
+/// `finishings` enum value of a keyword
+pub fn value(name: &str) -> Option<i32> {
+    FINISHINGS
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, value)| *value)
+}
+
+/// Finishing a job asks for
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Finishing {
+    /// Where to staple, e.g. `top-left` or `dual-left`, or `default` for
+    /// the printer's usual position
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub staple: Option<String>,
+    /// Where to punch holes, e.g. `dual-left` or `quad-top`, or `default`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub punch: Option<String>,
+    /// How to fold, e.g. `half`, `letter` or `z`, or `default`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fold: Option<String>,
+    /// Output bin keyword, e.g. `face-down` or `mailbox-1`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_bin: Option<String>,
+}
+
+impl Finishing {
+    /// `finishings` enum values to ask for, in staple, punch, fold order
+    pub fn values(&self) -> Result<Vec<i32>, String> {
+        let kinds = [
+            ("staple", &self.staple),
+            ("punch", &self.punch),
+            ("fold", &self.fold),
+        ];
+        let mut values = Vec::new();
+        for (kind, option) in kinds {
+            let Some(option) = option else {
+                continue;
+            };
+            let keyword = match option.trim() {
+                "default" => kind.to_string(),
+                option => format!("{}-{}", kind, option),
+            };
+            values.push(value(&keyword).ok_or_else(|| format!("Unknown {} '{}'", kind, option))?);
+        }
+        Ok(values)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        self.values()?;
+        if let Some(bin) = &self.output_bin {
+            if bin.trim().is_empty() || bin.contains(',') {
+                return Err(format!("Invalid output bin '{}'", bin));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finishing_values() {
+        let finishing = Finishing {
+            staple: Some("top-left".to_string()),
+            punch: Some("dual-left".to_string()),
+            fold: Some("default".to_string()),
+            output_bin: Some("mailbox-1".to_string()),
+        };
+        assert_eq!(finishing.values(), Ok(vec![20, 74, 10]));
+        assert!(finishing.validate().is_ok());
+        assert_eq!(name(93), Some("fold-half"));
+        assert_eq!(name(1), None);
+
+        let unknown = Finishing {
+            staple: Some("middle".to_string()),
+            ..Finishing::default()
+        };
+        assert!(unknown.validate().is_err());
+        let finishing: Finishing = serde_json::from_str(r#"{"outputBin":"top"}"#).unwrap();
+        assert_eq!(finishing.output_bin.as_deref(), Some("top"));
+        assert_eq!(finishing.values(), Ok(Vec::new()));
+    }
+}
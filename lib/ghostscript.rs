@@ -0,0 +1,377 @@
+//! Rasterizing documents with Ghostscript
+//!
+//! Printers without a PDF or PostScript interpreter, like dot matrix
+//! printers, older lasers and label printers, only take their own raster
+//! formats. On systems with a spooler, its filters convert documents for
+//! them; minimal Linux containers printing straight to a device have no
+//! such filters. This module converts PDF and PostScript documents to
+//! printer formats by running the Ghostscript executable (`gs`), which
+//! needs to be installed separately. It also renders pages as PNG images
+//! for print previews.
+//!
+//! Ghostscript writes `Page N` as it starts each page, which is reported as
+//! conversion progress.
+
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Executable run when no path is configured, found on the PATH
+#[cfg(windows)]
+pub const DEFAULT_BINARY: &str = "gswin64c";
+#[cfg(not(windows))]
+pub const DEFAULT_BINARY: &str = "gs";
+
+/// Resolution of previews that don't set one, in dots per inch
+pub const DEFAULT_PREVIEW_RESOLUTION: u32 = 96;
+
+/// A printer format Ghostscript can produce
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RasterFormat {
+    /// PCL 5 for monochrome laser printers
+    Pcl,
+    /// PCL 6 (PCL XL)
+    PclXl,
+    /// ESC/P for Epson-compatible dot matrix printers
+    EscP,
+    /// PWG Raster, for driverless IPP Everywhere printers
+    PwgRaster,
+    /// Apple Raster, for driverless AirPrint printers
+    AppleRaster,
+    /// CUPS Raster, which label printer drivers take
+    CupsRaster,
+}
+
+impl RasterFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RasterFormat::Pcl => "pcl",
+            RasterFormat::PclXl => "pclxl",
+            RasterFormat::EscP => "escp",
+            RasterFormat::PwgRaster => "pwg-raster",
+            RasterFormat::AppleRaster => "urf",
+            RasterFormat::CupsRaster => "cups-raster",
+        }
+    }
+
+    pub fn parse(format: &str) -> Option<RasterFormat> {
+        match format.trim().to_lowercase().as_str() {
+            "pcl" => Some(RasterFormat::Pcl),
+            "pclxl" => Some(RasterFormat::PclXl),
+            "escp" => Some(RasterFormat::EscP),
+            "pwg-raster" => Some(RasterFormat::PwgRaster),
+            "urf" => Some(RasterFormat::AppleRaster),
+            "cups-raster" => Some(RasterFormat::CupsRaster),
+            _ => None,
+        }
+    }
+
+    /// Ghostscript output device writing this format, in color if the
+    /// format has a color variant
+    pub fn device(&self, color: bool) -> &'static str {
+        match self {
+            RasterFormat::Pcl => "ljet4",
+            RasterFormat::PclXl if color => "pxlcolor",
+            RasterFormat::PclXl => "pxlmono",
+            RasterFormat::EscP => "epson",
+            RasterFormat::PwgRaster => "pwgraster",
+            RasterFormat::AppleRaster => "urf",
+            RasterFormat::CupsRaster => "cups",
+        }
+    }
+
+    /// Device parameters selecting 8-bit sGray or sRGB pages, for the
+    /// driverless raster formats
+    fn color_space_args(&self, color: bool) -> Vec<String> {
+        match self {
+            RasterFormat::PwgRaster | RasterFormat::AppleRaster => vec![
+                format!("-dcupsColorSpace={}", if color { 19 } else { 18 }),
+                "-dcupsBitsPerColor=8".to_string(),
+            ],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Media type to submit the output as
+    pub fn media_type(&self) -> &'static str {
+        match self {
+            RasterFormat::Pcl => "application/vnd.hp-pcl",
+            RasterFormat::PclXl => "application/vnd.hp-pclxl",
+            RasterFormat::EscP => "application/octet-stream",
+            RasterFormat::PwgRaster => "image/pwg-raster",
+            RasterFormat::AppleRaster => "image/urf",
+            RasterFormat::CupsRaster => "application/vnd.cups-raster",
+        }
+    }
+
+    /// Resolution used unless one is given, in dots per inch
+    pub fn default_resolution(&self) -> u32 {
+        match self {
+            RasterFormat::Pcl | RasterFormat::PclXl => 600,
+            RasterFormat::EscP => 180,
+            RasterFormat::PwgRaster | RasterFormat::AppleRaster => 300,
+            // Most thermal label printers print at 203 dpi
+            RasterFormat::CupsRaster => 203,
+        }
+    }
+}
+
+/// How a document is rasterized
+#[derive(Clone, Debug, PartialEq)]
+pub struct RasterOptions {
+    pub format: RasterFormat,
+    /// Dots per inch (None = the format's default)
+    pub resolution: Option<u32>,
+    /// First and last page to convert, counting from 1 (None = all pages)
+    pub first_page: Option<u32>,
+    pub last_page: Option<u32>,
+    /// Convert in color, for formats with a color variant
+    pub color: bool,
+}
+
+impl RasterOptions {
+    pub fn new(format: RasterFormat) -> RasterOptions {
+        RasterOptions {
+            format,
+            resolution: None,
+            first_page: None,
+            last_page: None,
+            color: false,
+        }
+    }
+}
+
+/// Progress of a conversion: Ghostscript started on a page
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConversionProgress {
+    /// Page being converted, counting from 1
+    pub page: u32,
+    /// Pages to convert, if Ghostscript reported the count
+    pub pages: Option<u32>,
+}
+
+/// Executable to run: the configured one or `DEFAULT_BINARY`
+pub fn binary(configured: Option<&Path>) -> PathBuf {
+    configured.map_or_else(|| PathBuf::from(DEFAULT_BINARY), Path::to_path_buf)
+}
+
+fn spawn_error(binary: &Path, e: std::io::Error) -> String {
+    if e.kind() == std::io::ErrorKind::NotFound {
+        format!(
+            "Ghostscript not found at '{}'; install it or set ghostscriptPath",
+            binary.display()
+        )
+    } else {
+        format!("Failed to run Ghostscript '{}': {}", binary.display(), e)
+    }
+}
+
+/// Version of a Ghostscript executable, e.g. "10.02.1"
+pub fn version(binary: &Path) -> Result<String, String> {
+    let output = Command::new(binary)
+        .arg("--version")
+        .output()
+        .map_err(|e| spawn_error(binary, e))?;
+    if !output.status.success() {
+        return Err(format!("'{}' is not Ghostscript", binary.display()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Read a line of Ghostscript's output: `Processing pages 1 through 3.`
+/// gives the page count, and `Page 2` the page being converted. Pages are
+/// numbered from the first page converted.
+fn parse_progress(line: &str, first_page: u32, pages: &mut Option<u32>) -> Option<u32> {
+    let line = line.trim();
+    if let Some(range) = line.strip_prefix("Processing pages ") {
+        let range = range.trim_end_matches('.');
+        if let Some((first, last)) = range.split_once(" through ") {
+            if let (Ok(first), Ok(last)) = (first.parse::<u32>(), last.parse::<u32>()) {
+                *pages = Some(last.saturating_sub(first) + 1);
+            }
+        }
+        return None;
+    }
+    let page: u32 = line.strip_prefix("Page ")?.parse().ok()?;
+    Some(page.saturating_sub(first_page) + 1)
+}
+
+/// Check that a document is one Ghostscript reads
+fn check_document(data: &[u8]) -> Result<(), String> {
+    let media_type = crate::document::sniff_media_type(data);
+    if !matches!(
+        media_type,
+        Some("application/pdf") | Some("application/postscript")
+    ) {
+        return Err("Ghostscript only converts PDF and PostScript documents".to_string());
+    }
+    Ok(())
+}
+
+/// Convert a PDF or PostScript document to a printer format, working in a
+/// temporary directory under `work_dir`. `on_progress` is called as each
+/// page is started.
+pub fn rasterize(
+    binary: &Path,
+    data: &[u8],
+    options: &RasterOptions,
+    work_dir: &Path,
+    on_progress: impl FnMut(ConversionProgress),
+) -> Result<Vec<u8>, String> {
+    check_document(data)?;
+    let resolution = options
+        .resolution
+        .unwrap_or_else(|| options.format.default_resolution());
+    if resolution == 0 {
+        return Err("Resolution must be greater than zero".to_string());
+    }
+    let first_page = options.first_page.unwrap_or(1);
+    if first_page == 0 || options.last_page.is_some_and(|last| last < first_page) {
+        return Err("Invalid page range".to_string());
+    }
+
+    let mut args = vec![format!("-sDEVICE={}", options.format.device(options.color))];
+    args.extend(options.format.color_space_args(options.color));
+    args.push(format!("-r{}", resolution));
+    if let Some(first) = options.first_page {
+        args.push(format!("-dFirstPage={}", first));
+    }
+    if let Some(last) = options.last_page {
+        args.push(format!("-dLastPage={}", last));
+    }
+    run(binary, data, &args, first_page, work_dir, on_progress)
+}
+
+/// Render one page of a PDF or PostScript document, counting from 1, as an
+/// RGB PNG image with smoothed text and graphics, for previews
+pub fn render_png(
+    binary: &Path,
+    data: &[u8],
+    page: u32,
+    resolution: u32,
+    work_dir: &Path,
+) -> Result<Vec<u8>, String> {
+    check_document(data)?;
+    if resolution == 0 {
+        return Err("Resolution must be greater than zero".to_string());
+    }
+    if page == 0 {
+        return Err("Pages are counted from 1".to_string());
+    }
+    let args = [
+        "-sDEVICE=png16m".to_string(),
+        "-dTextAlphaBits=4".to_string(),
+        "-dGraphicsAlphaBits=4".to_string(),
+        format!("-r{}", resolution),
+        format!("-dFirstPage={}", page),
+        format!("-dLastPage={}", page),
+    ];
+    run(binary, data, &args, page, work_dir, |_| {}).map_err(|e| {
+        // Ghostscript writes nothing for pages past the end
+        if e.starts_with(NO_OUTPUT) {
+            format!("The document has no page {}", page)
+        } else {
+            e
+        }
+    })
+}
+
+/// Start of the error for a conversion that wrote no output
+const NO_OUTPUT: &str = "Failed to read Ghostscript output";
+
+/// Run Ghostscript on a document with device arguments, in a temporary
+/// directory under `work_dir`, returning what it wrote
+fn run(
+    binary: &Path,
+    data: &[u8],
+    args: &[String],
+    first_page: u32,
+    work_dir: &Path,
+    mut on_progress: impl FnMut(ConversionProgress),
+) -> Result<Vec<u8>, String> {
+    let dir = tempfile::Builder::new()
+        .prefix("printers-js-gs")
+        .tempdir_in(work_dir)
+        .map_err(|e| format!("Failed to create conversion directory: {}", e))?;
+    let input = dir.path().join("input");
+    let output = dir.path().join("output");
+    std::fs::write(&input, data).map_err(|e| format!("Failed to write document: {}", e))?;
+
+    let mut child = Command::new(binary)
+        .args(["-dSAFER", "-dBATCH", "-dNOPAUSE"])
+        .args(args)
+        .arg(format!("-sOutputFile={}", output.display()))
+        .arg(&input)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| spawn_error(binary, e))?;
+
+    // Read errors on their own thread so neither pipe fills up
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+    let errors = std::thread::spawn(move || {
+        let mut errors = String::new();
+        let _ = stderr.read_to_string(&mut errors);
+        errors
+    });
+    let mut pages = None;
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if let Some(page) = parse_progress(&line, first_page, &mut pages) {
+                on_progress(ConversionProgress { page, pages });
+            }
+        }
+    }
+    let status = child
+        .wait()
+        .map_err(|e| format!("Failed to wait for Ghostscript: {}", e))?;
+    let errors = errors.join().unwrap_or_default();
+    if !status.success() {
+        let reason = errors
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .unwrap_or("no error output");
+        return Err(format!("Ghostscript failed ({}): {}", status, reason));
+    }
+    std::fs::read(&output).map_err(|e| format!("{}: {}", NO_OUTPUT, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_progress() {
+        let mut pages = None;
+        assert_eq!(
+            parse_progress("Processing pages 3 through 5.", 3, &mut pages),
+            None
+        );
+        assert_eq!(pages, Some(3));
+        assert_eq!(parse_progress("Page 4", 3, &mut pages), Some(2));
+        assert_eq!(
+            parse_progress("GPL Ghostscript 10.02.1 (2023-11-01)", 3, &mut pages),
+            None
+        );
+    }
+
+    #[test]
+    fn test_rasterize_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = Path::new("/path/that/does_not_exist/gs");
+        let options = RasterOptions::new(RasterFormat::Pcl);
+        let error = rasterize(missing, b"%PDF-1.7\n", &options, dir.path(), |_| {}).unwrap_err();
+        assert!(error.contains("Ghostscript not found"));
+        // Documents are checked before Ghostscript is run
+        let error = rasterize(missing, b"plain text", &options, dir.path(), |_| {}).unwrap_err();
+        assert!(error.contains("only converts PDF and PostScript"));
+        let error = render_png(missing, b"%PDF-1.7\n", 0, 96, dir.path()).unwrap_err();
+        assert!(error.contains("counted from 1"));
+        assert_eq!(
+            RasterFormat::parse("PWG-Raster"),
+            Some(RasterFormat::PwgRaster)
+        );
+    }
+}
@@ -0,0 +1,328 @@
+//! Jobs held for approval
+//!
+//! Jobs submitted with `require_approval` are spooled here instead of being
+//! sent to the printer, until they are approved or rejected. Each held job
+//! is a JSON record of the job next to a copy of its payload, so pending
+//! approvals survive restarts: the records are loaded back into the job
+//! tracker when the library starts.
+//!
+//! The record is written after the payload and removed before it, so every
+//! record found on disk has its payload. With an encryption key set, payloads
+//! are encrypted (see `encryption`) and decrypted to a short-lived copy when
+//! the job is released.
+
+use crate::core::{JobId, PrinterJob, PrinterJobOptions, PrinterJobState};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Name of the default directory for held jobs, under the temp directory
+pub const DEFAULT_DIR_NAME: &str = "printers-js-held";
+
+fn record_path(dir: &Path, job_id: JobId) -> PathBuf {
+    dir.join(format!("job-{}.json", job_id))
+}
+
+fn payload_path(dir: &Path, job_id: JobId) -> PathBuf {
+    dir.join(format!("job-{}.bin", job_id))
+}
+
+/// Decrypted payload of a released job
+fn print_path(dir: &Path, job_id: JobId) -> PathBuf {
+    dir.join(format!("job-{}.print", job_id))
+}
+
+/// What an encrypted payload is bound to
+fn encryption_context(job_id: JobId) -> Vec<u8> {
+    format!("printers-js held job {}", job_id).into_bytes()
+}
+
+/// A held job's payload once it has been approved, removed when dropped
+#[derive(Debug)]
+pub struct HeldPayload {
+    path: PathBuf,
+}
+
+impl HeldPayload {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for HeldPayload {
+    fn drop(&mut self) {
+        remove_file(&self.path);
+    }
+}
+
+fn remove_file(path: &Path) {
+    if let Err(e) = fs::remove_file(path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            tracing::warn!(path = %path.display(), "Failed to remove held job file: {}", e);
+        }
+    }
+}
+
+/// A held job on disk: the job as serialized for JavaScript, plus what that
+/// leaves out and is needed to print it once approved
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HeldRecord {
+    #[serde(flatten)]
+    job: PrinterJob,
+    source_path: Option<String>,
+    options: HashMap<String, String>,
+}
+
+/// Persist a held job and its payload
+pub fn save(dir: &Path, job: &PrinterJob, data: &[u8]) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| {
+        format!(
+            "Failed to create held job directory '{}': {}",
+            dir.display(),
+            e
+        )
+    })?;
+
+    let data = crate::encryption::seal(data, &encryption_context(job.id))?;
+    let payload_path = payload_path(dir, job.id);
+    fs::write(&payload_path, data).map_err(|e| {
+        format!(
+            "Failed to write held job payload '{}': {}",
+            payload_path.display(),
+            e
+        )
+    })?;
+
+    let record = HeldRecord {
+        job: job.clone(),
+        source_path: job.source_path.clone(),
+        options: job.job_options.to_map(),
+    };
+    let path = record_path(dir, job.id);
+    let temp_path = path.with_extension("tmp");
+    let written = serde_json::to_string_pretty(&record)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| {
+            fs::write(&temp_path, contents + "\n")
+                .and_then(|_| fs::rename(&temp_path, &path))
+                .map_err(|e| e.to_string())
+        });
+    if let Err(e) = written {
+        remove_file(&payload_path);
+        return Err(format!(
+            "Failed to write held job record '{}': {}",
+            path.display(),
+            e
+        ));
+    }
+    Ok(())
+}
+
+/// Read a held job record
+fn load(path: &Path) -> Result<PrinterJob, String> {
+    let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let record: HeldRecord = serde_json::from_str(&contents).map_err(|e| e.to_string())?;
+    let mut job = record.job;
+    job.state = PrinterJobState::HELD_FOR_APPROVAL;
+    job.source_path = record.source_path;
+    job.job_options = PrinterJobOptions::from_map(record.options);
+    Ok(job)
+}
+
+/// All held jobs in the directory, oldest first. Unreadable records and
+/// records whose payload is missing are skipped, and decrypted payloads
+/// left behind by a crash are removed.
+pub fn load_all(dir: &Path) -> Vec<PrinterJob> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let paths: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+    for path in paths
+        .iter()
+        .filter(|path| path.extension().is_some_and(|ext| ext == "print"))
+    {
+        remove_file(path);
+    }
+    let mut jobs: Vec<PrinterJob> = paths
+        .into_iter()
+        .filter(|path| {
+            path.extension().is_some_and(|ext| ext == "json")
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with("job-"))
+        })
+        .filter_map(|path| match load(&path) {
+            Ok(job) if payload_path(dir, job.id).is_file() => Some(job),
+            Ok(job) => {
+                tracing::warn!(job_id = job.id, "Held job payload is missing, skipping it");
+                None
+            }
+            Err(e) => {
+                tracing::warn!(path = %path.display(), "Invalid held job record: {}", e);
+                None
+            }
+        })
+        .collect();
+    jobs.sort_by_key(|job| job.id);
+    jobs
+}
+
+/// Remove a job's record so it is no longer held, handing over its payload
+/// to print from. An encrypted payload is decrypted first; if that fails,
+/// the job stays held.
+pub fn release(dir: &Path, job_id: JobId) -> Result<Option<HeldPayload>, String> {
+    let path = payload_path(dir, job_id);
+    let mut header = [0u8; crate::encryption::MAGIC.len()];
+    let sealed = fs::File::open(&path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .is_ok_and(|_| crate::encryption::is_sealed(&header));
+    let payload = if sealed {
+        let data = fs::read(&path).map_err(|e| {
+            format!(
+                "Failed to read held job payload '{}': {}",
+                path.display(),
+                e
+            )
+        })?;
+        let data = crate::encryption::open(data, &encryption_context(job_id))?;
+        let print_path = print_path(dir, job_id);
+        fs::write(&print_path, data).map_err(|e| {
+            format!(
+                "Failed to write decrypted payload '{}': {}",
+                print_path.display(),
+                e
+            )
+        })?;
+        Some(HeldPayload { path: print_path })
+    } else {
+        path.is_file().then(|| HeldPayload { path: path.clone() })
+    };
+    remove_file(&record_path(dir, job_id));
+    if sealed {
+        remove_file(&path);
+    }
+    Ok(payload)
+}
+
+/// Remove a job's record and payload
+pub fn remove(dir: &Path, job_id: JobId) {
+    remove_file(&record_path(dir, job_id));
+    remove_file(&payload_path(dir, job_id));
+    remove_file(&print_path(dir, job_id));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::MediaTypeSource;
+    use serial_test::serial;
+
+    #[test]
+    #[serial]
+    fn test_held_job_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut options = PrinterJobOptions::none();
+        options.name = Some("Payroll".to_string());
+        options.require_approval = true;
+        options
+            .raw_properties
+            .insert("copies".to_string(), "2".to_string());
+        let mut job = PrinterJob::new(
+            42,
+            "Payroll".to_string(),
+            "application/pdf".to_string(),
+            "Simulated Printer".to_string(),
+        );
+        job.state = PrinterJobState::HELD_FOR_APPROVAL;
+        job.media_type_source = MediaTypeSource::Content;
+        job.owner = Some("alice".to_string());
+        job.size_bytes = 7;
+        job.pages = Some(2);
+        job.job_options = options;
+
+        save(dir.path(), &job, b"%PDF-1.").unwrap();
+
+        // The record is the serialized job plus what it leaves out
+        let keys = |json: &str| -> Vec<String> {
+            let value: serde_json::Value = serde_json::from_str(json).unwrap();
+            let mut keys: Vec<String> = value.as_object().unwrap().keys().cloned().collect();
+            keys.sort_unstable();
+            keys
+        };
+        let mut expected = keys(&crate::core::printer_job_to_json(&job).unwrap());
+        expected.extend(["options".to_string(), "sourcePath".to_string()]);
+        expected.sort_unstable();
+        let record = fs::read_to_string(record_path(dir.path(), 42)).unwrap();
+        assert_eq!(keys(&record), expected);
+
+        fs::write(dir.path().join("job-43.json"), "not json").unwrap();
+        fs::write(dir.path().join("job-44.json"), r#"{"id": 44}"#).unwrap();
+
+        let jobs = load_all(dir.path());
+        assert_eq!(jobs.len(), 1);
+        let restored = &jobs[0];
+        assert_eq!(restored.id, 42);
+        assert_eq!(restored.state, PrinterJobState::HELD_FOR_APPROVAL);
+        assert_eq!(restored.printer_name, "Simulated Printer");
+        assert_eq!(restored.media_type_source, MediaTypeSource::Content);
+        assert_eq!(restored.owner.as_deref(), Some("alice"));
+        assert_eq!(restored.pages, Some(2));
+        assert_eq!(
+            crate::core::printer_job_to_json(restored),
+            crate::core::printer_job_to_json(&job)
+        );
+        assert!(restored.job_options.require_approval);
+        assert_eq!(restored.job_options.name.as_deref(), Some("Payroll"));
+        assert_eq!(restored.job_options.raw_properties["copies"], "2");
+
+        let payload = release(dir.path(), 42).unwrap().unwrap();
+        assert_eq!(fs::read(payload.path()).unwrap(), b"%PDF-1.");
+        assert!(load_all(dir.path()).is_empty());
+        let path = payload.path().to_path_buf();
+        drop(payload);
+        assert!(!path.exists());
+        assert!(release(dir.path(), 42).unwrap().is_none());
+
+        save(dir.path(), &job, b"data").unwrap();
+        remove(dir.path(), 42);
+        assert!(load_all(dir.path()).is_empty());
+        assert!(!payload_path(dir.path(), 42).exists());
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    #[serial]
+    fn test_encrypted_payloads() {
+        let dir = tempfile::tempdir().unwrap();
+        let job = PrinterJob::new(
+            7,
+            "Contract".to_string(),
+            "application/pdf".to_string(),
+            "Simulated Printer".to_string(),
+        );
+        crate::encryption::set_key(Some(&[3; 32][..])).unwrap();
+        save(dir.path(), &job, b"%PDF-1.7 confidential").unwrap();
+        let on_disk = fs::read(payload_path(dir.path(), 7)).unwrap();
+        assert!(crate::encryption::is_sealed(&on_disk));
+        assert!(!on_disk.windows(12).any(|w| w == b"confidential"));
+
+        // Without the right key the job stays held
+        crate::encryption::set_key(Some(&[4; 32][..])).unwrap();
+        assert!(release(dir.path(), 7).is_err());
+        assert_eq!(load_all(dir.path()).len(), 1);
+
+        crate::encryption::set_key(Some(&[3; 32][..])).unwrap();
+        let payload = release(dir.path(), 7).unwrap().unwrap();
+        assert_eq!(fs::read(payload.path()).unwrap(), b"%PDF-1.7 confidential");
+        assert!(!payload_path(dir.path(), 7).exists());
+        assert!(load_all(dir.path()).is_empty());
+        let path = payload.path().to_path_buf();
+        drop(payload);
+        assert!(!path.exists());
+        crate::encryption::set_key(None).unwrap();
+    }
+}
@@ -0,0 +1,219 @@
+//! HTTP/1.1 request handling shared by the built-in servers
+//!
+//! Just enough of HTTP for one request per connection: the request line,
+//! headers, and a body framed by Content-Length or chunked transfer coding.
+//! Responses always close the connection.
+
+use crate::encoding::percent_decode;
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+/// Idle time after which a client that stops sending is disconnected
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_HEADER_BYTES: usize = 64 * 1024;
+
+/// A request the connection failed to deliver
+pub enum RequestError {
+    TooLarge,
+    Malformed(String),
+    Io(String),
+}
+
+/// An HTTP request with its body
+pub struct HttpRequest {
+    pub method: String,
+    /// Request target, including any query string
+    pub target: String,
+    /// Header values by lowercase name
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl HttpRequest {
+    /// Target without the query string
+    pub fn path(&self) -> &str {
+        self.target.split('?').next().unwrap_or_default()
+    }
+
+    /// Percent-decoded query parameters, in order
+    pub fn query(&self) -> Vec<(String, String)> {
+        let Some((_, query)) = self.target.split_once('?') else {
+            return Vec::new();
+        };
+        query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+                (percent_decode(name, true), percent_decode(value, true))
+            })
+            .collect()
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+}
+
+/// Switch an accepted connection to blocking I/O with timeouts
+pub fn prepare_stream(stream: &TcpStream) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    stream.set_write_timeout(Some(READ_TIMEOUT))
+}
+
+/// Reads an HTTP request from the connection, keeping bytes read ahead
+pub struct Incoming<'a> {
+    stream: &'a mut TcpStream,
+    buf: Vec<u8>,
+}
+
+impl<'a> Incoming<'a> {
+    pub fn new(stream: &'a mut TcpStream) -> Self {
+        Incoming {
+            stream,
+            buf: Vec::new(),
+        }
+    }
+
+    fn fill(&mut self) -> Result<(), RequestError> {
+        let mut chunk = [0u8; 16 * 1024];
+        match self.stream.read(&mut chunk) {
+            Ok(0) => Err(RequestError::Io("Connection closed".to_string())),
+            Ok(read) => {
+                self.buf.extend_from_slice(&chunk[..read]);
+                Ok(())
+            }
+            Err(e) => Err(RequestError::Io(e.to_string())),
+        }
+    }
+
+    /// Take the bytes up to and including `delimiter`
+    fn take_until(&mut self, delimiter: &[u8], limit: usize) -> Result<Vec<u8>, RequestError> {
+        loop {
+            if let Some(pos) = self
+                .buf
+                .windows(delimiter.len())
+                .position(|w| w == delimiter)
+            {
+                return Ok(self.buf.drain(..pos + delimiter.len()).collect());
+            }
+            if self.buf.len() > limit {
+                return Err(RequestError::TooLarge);
+            }
+            self.fill()?;
+        }
+    }
+
+    fn take(&mut self, len: usize) -> Result<Vec<u8>, RequestError> {
+        while self.buf.len() < len {
+            self.fill()?;
+        }
+        Ok(self.buf.drain(..len).collect())
+    }
+
+    /// Read a request whose body is at most `max_body` bytes
+    pub fn read_request(&mut self, max_body: usize) -> Result<HttpRequest, RequestError> {
+        let mut request = self.read_head()?;
+        self.read_body(&mut request, max_body)?;
+        Ok(request)
+    }
+
+    /// Read the request line and headers, leaving the body unread, e.g. to
+    /// check credentials before accepting a large upload
+    pub fn read_head(&mut self) -> Result<HttpRequest, RequestError> {
+        let head = self.take_until(b"\r\n\r\n", MAX_HEADER_BYTES)?;
+        let head = String::from_utf8_lossy(&head);
+        let mut lines = head.lines();
+        let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+        let method = request_line.next().unwrap_or_default().to_string();
+        let target = request_line.next().unwrap_or_default().to_string();
+        let headers: HashMap<String, String> = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+            .collect();
+
+        Ok(HttpRequest {
+            method,
+            target,
+            headers,
+            body: Vec::new(),
+        })
+    }
+
+    /// Read the body of a request read with `read_head`, of at most
+    /// `max_body` bytes
+    pub fn read_body(
+        &mut self,
+        request: &mut HttpRequest,
+        max_body: usize,
+    ) -> Result<(), RequestError> {
+        let headers = &request.headers;
+        if headers
+            .get("expect")
+            .is_some_and(|expect| expect.eq_ignore_ascii_case("100-continue"))
+        {
+            let _ = self.stream.write_all(b"HTTP/1.1 100 Continue\r\n\r\n");
+        }
+
+        let chunked = headers
+            .get("transfer-encoding")
+            .is_some_and(|encoding| encoding.to_lowercase().contains("chunked"));
+        let body = if chunked {
+            self.read_chunked(max_body)?
+        } else {
+            let length = match headers.get("content-length") {
+                Some(length) => length.parse::<usize>().map_err(|_| {
+                    RequestError::Malformed(format!("Invalid Content-Length '{}'", length))
+                })?,
+                None => 0,
+            };
+            if length > max_body {
+                return Err(RequestError::TooLarge);
+            }
+            self.take(length)?
+        };
+        request.body = body;
+        Ok(())
+    }
+
+    fn read_chunked(&mut self, max_body: usize) -> Result<Vec<u8>, RequestError> {
+        let mut body = Vec::new();
+        loop {
+            let line = self.take_until(b"\r\n", 1024)?;
+            let line = String::from_utf8_lossy(&line);
+            let size_hex = line.split(';').next().unwrap_or_default().trim();
+            let size = usize::from_str_radix(size_hex, 16).map_err(|_| {
+                RequestError::Malformed(format!("Invalid chunk size '{}'", size_hex))
+            })?;
+            if size == 0 {
+                // Skip any trailer fields up to the final empty line
+                while self.take_until(b"\r\n", MAX_HEADER_BYTES)? != b"\r\n" {}
+                return Ok(body);
+            }
+            if size > max_body.saturating_sub(body.len()) {
+                return Err(RequestError::TooLarge);
+            }
+            body.extend_from_slice(&self.take(size)?);
+            self.take(2)?;
+        }
+    }
+}
+
+/// A complete response, e.g. `response("200 OK", &[("Content-Type",
+/// "text/plain")], b"ok")`
+pub fn response(status: &str, headers: &[(&str, &str)], body: &[u8]) -> Vec<u8> {
+    let mut head = format!("HTTP/1.1 {}\r\n", status);
+    for (name, value) in headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str(&format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    ));
+    let mut response = head.into_bytes();
+    response.extend_from_slice(body);
+    response
+}
@@ -0,0 +1,1548 @@
+//! Minimal IPP/1.1 client
+//!
+//! Implements the binary encoding from RFC 8010 and a blocking HTTP/1.1
+//! transport (over TLS for `ipps://`), enough to talk to printers and CUPS directly for operations the
+//! `printers` crate doesn't expose (printer attributes, supply levels, etc.).
+//! `send_raw_request` sends any other operation, with values given in the
+//! text form `IppValue::parse` reads and `Display` writes.
+
+use crate::device::DeviceAddress;
+use std::fmt;
+use std::io::{Read, Write};
+use std::time::Duration;
+
+/// Timeout of raw requests that don't set one
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Operation IDs (RFC 8011 section 5.4.15)
+pub const OP_PRINT_JOB: u16 = 0x0002;
+pub const OP_VALIDATE_JOB: u16 = 0x0004;
+pub const OP_CANCEL_JOB: u16 = 0x0008;
+pub const OP_GET_JOB_ATTRIBUTES: u16 = 0x0009;
+pub const OP_GET_JOBS: u16 = 0x000A;
+pub const OP_GET_PRINTER_ATTRIBUTES: u16 = 0x000B;
+pub const OP_CREATE_PRINTER_SUBSCRIPTIONS: u16 = 0x0016;
+pub const OP_CANCEL_SUBSCRIPTION: u16 = 0x001B;
+pub const OP_GET_NOTIFICATIONS: u16 = 0x001C;
+
+/// Operation names (RFC 8011, RFC 3995, PWG 5100.x) and their IDs
+const OPERATIONS: &[(&str, u16)] = &[
+    ("Print-Job", OP_PRINT_JOB),
+    ("Print-URI", 0x0003),
+    ("Validate-Job", OP_VALIDATE_JOB),
+    ("Create-Job", 0x0005),
+    ("Send-Document", 0x0006),
+    ("Send-URI", 0x0007),
+    ("Cancel-Job", OP_CANCEL_JOB),
+    ("Get-Job-Attributes", OP_GET_JOB_ATTRIBUTES),
+    ("Get-Jobs", OP_GET_JOBS),
+    ("Get-Printer-Attributes", OP_GET_PRINTER_ATTRIBUTES),
+    ("Hold-Job", 0x000C),
+    ("Release-Job", 0x000D),
+    ("Restart-Job", 0x000E),
+    ("Pause-Printer", 0x0010),
+    ("Resume-Printer", 0x0011),
+    ("Purge-Jobs", 0x0012),
+    ("Set-Printer-Attributes", 0x0013),
+    ("Set-Job-Attributes", 0x0014),
+    ("Get-Printer-Supported-Values", 0x0015),
+    (
+        "Create-Printer-Subscriptions",
+        OP_CREATE_PRINTER_SUBSCRIPTIONS,
+    ),
+    ("Create-Job-Subscriptions", 0x0017),
+    ("Get-Subscription-Attributes", 0x0018),
+    ("Get-Subscriptions", 0x0019),
+    ("Renew-Subscription", 0x001A),
+    ("Cancel-Subscription", OP_CANCEL_SUBSCRIPTION),
+    ("Get-Notifications", OP_GET_NOTIFICATIONS),
+    ("Cancel-Current-Job", 0x002D),
+    ("Cancel-Jobs", 0x0038),
+    ("Cancel-My-Jobs", 0x0039),
+    ("Close-Job", 0x003B),
+    ("Identify-Printer", 0x003C),
+    ("Validate-Document", 0x003D),
+];
+
+/// Operation ID for an operation name such as `"Get-Jobs"`, or a decimal
+/// or `0x` hexadecimal ID for operations not listed
+pub fn operation_code(operation: &str) -> Option<u16> {
+    let operation = operation.trim();
+    if let Some(&(_, code)) = OPERATIONS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(operation))
+    {
+        return Some(code);
+    }
+    match operation.strip_prefix("0x") {
+        Some(hex) => u16::from_str_radix(hex, 16).ok(),
+        None => operation.parse().ok(),
+    }
+}
+
+// Delimiter tags
+pub const TAG_OPERATION_ATTRIBUTES: u8 = 0x01;
+pub const TAG_JOB_ATTRIBUTES: u8 = 0x02;
+pub const TAG_END_OF_ATTRIBUTES: u8 = 0x03;
+pub const TAG_PRINTER_ATTRIBUTES: u8 = 0x04;
+pub const TAG_UNSUPPORTED_ATTRIBUTES: u8 = 0x05;
+pub const TAG_SUBSCRIPTION_ATTRIBUTES: u8 = 0x06;
+pub const TAG_EVENT_NOTIFICATION_ATTRIBUTES: u8 = 0x07;
+pub const TAG_DOCUMENT_ATTRIBUTES: u8 = 0x09;
+
+/// Names of attribute groups, by delimiter tag
+const GROUPS: &[(&str, u8)] = &[
+    ("operation", TAG_OPERATION_ATTRIBUTES),
+    ("job", TAG_JOB_ATTRIBUTES),
+    ("printer", TAG_PRINTER_ATTRIBUTES),
+    ("unsupported", TAG_UNSUPPORTED_ATTRIBUTES),
+    ("subscription", TAG_SUBSCRIPTION_ATTRIBUTES),
+    ("event-notification", TAG_EVENT_NOTIFICATION_ATTRIBUTES),
+    ("document", TAG_DOCUMENT_ATTRIBUTES),
+];
+
+/// Delimiter tag of an attribute group name such as `"job"`
+pub fn group_tag(group: &str) -> Option<u8> {
+    GROUPS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(group.trim()))
+        .map(|(_, tag)| *tag)
+}
+
+/// Name of an attribute group, or its tag in hexadecimal if it has none
+pub fn group_name(tag: u8) -> String {
+    GROUPS
+        .iter()
+        .find(|(_, group_tag)| *group_tag == tag)
+        .map_or_else(|| format!("0x{:02x}", tag), |(name, _)| name.to_string())
+}
+
+// Value tags
+const TAG_UNSUPPORTED: u8 = 0x10;
+const TAG_UNKNOWN: u8 = 0x12;
+const TAG_NO_VALUE: u8 = 0x13;
+const TAG_INTEGER: u8 = 0x21;
+const TAG_BOOLEAN: u8 = 0x22;
+const TAG_ENUM: u8 = 0x23;
+const TAG_OCTET_STRING: u8 = 0x30;
+const TAG_DATE_TIME: u8 = 0x31;
+const TAG_RESOLUTION: u8 = 0x32;
+const TAG_RANGE_OF_INTEGER: u8 = 0x33;
+const TAG_BEGIN_COLLECTION: u8 = 0x34;
+const TAG_TEXT_WITH_LANGUAGE: u8 = 0x35;
+const TAG_NAME_WITH_LANGUAGE: u8 = 0x36;
+const TAG_END_COLLECTION: u8 = 0x37;
+const TAG_TEXT: u8 = 0x41;
+const TAG_NAME: u8 = 0x42;
+const TAG_KEYWORD: u8 = 0x44;
+const TAG_URI: u8 = 0x45;
+const TAG_URI_SCHEME: u8 = 0x46;
+const TAG_CHARSET: u8 = 0x47;
+const TAG_NATURAL_LANGUAGE: u8 = 0x48;
+const TAG_MIME_MEDIA_TYPE: u8 = 0x49;
+const TAG_MEMBER_ATTR_NAME: u8 = 0x4A;
+
+/// Successful status codes are in the 0x0000-0x00FF range
+pub const STATUS_OK: u16 = 0x0000;
+/// client-error-not-found, e.g. for an expired subscription
+pub const STATUS_NOT_FOUND: u16 = 0x0406;
+pub const STATUS_BAD_REQUEST: u16 = 0x0400;
+/// client-error-forbidden, e.g. for a job the print policy doesn't allow
+pub const STATUS_FORBIDDEN: u16 = 0x0401;
+pub const STATUS_REQUEST_ENTITY_TOO_LARGE: u16 = 0x0409;
+/// client-error-attributes-or-values-not-supported
+pub const STATUS_ATTRIBUTES_NOT_SUPPORTED: u16 = 0x040B;
+pub const STATUS_COMPRESSION_NOT_SUPPORTED: u16 = 0x040F;
+pub const STATUS_COMPRESSION_ERROR: u16 = 0x0410;
+pub const STATUS_INTERNAL_ERROR: u16 = 0x0500;
+pub const STATUS_OPERATION_NOT_SUPPORTED: u16 = 0x0501;
+pub const STATUS_SERVICE_UNAVAILABLE: u16 = 0x0502;
+pub const STATUS_NOT_ACCEPTING_JOBS: u16 = 0x0506;
+pub const STATUS_BUSY: u16 = 0x0507;
+
+/// A single IPP attribute value
+#[derive(Clone, Debug, PartialEq)]
+pub enum IppValue {
+    Integer(i32),
+    Boolean(bool),
+    Enum(i32),
+    OctetString(Vec<u8>),
+    DateTime(Vec<u8>),
+    Resolution {
+        cross_feed: i32,
+        feed: i32,
+        units: u8,
+    },
+    RangeOfInteger {
+        lower: i32,
+        upper: i32,
+    },
+    Text(String),
+    Name(String),
+    Keyword(String),
+    Uri(String),
+    UriScheme(String),
+    Charset(String),
+    NaturalLanguage(String),
+    MimeMediaType(String),
+    Collection(Vec<IppAttribute>),
+    NoValue,
+    Unknown,
+    Unsupported,
+    /// Any value tag this module doesn't decode, kept verbatim
+    Other {
+        tag: u8,
+        data: Vec<u8>,
+    },
+}
+
+impl IppValue {
+    /// String content for text-like values
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            IppValue::Text(s)
+            | IppValue::Name(s)
+            | IppValue::Keyword(s)
+            | IppValue::Uri(s)
+            | IppValue::UriScheme(s)
+            | IppValue::Charset(s)
+            | IppValue::NaturalLanguage(s)
+            | IppValue::MimeMediaType(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Numeric content for integer and enum values
+    pub fn as_i32(&self) -> Option<i32> {
+        match self {
+            IppValue::Integer(v) | IppValue::Enum(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    fn tag(&self) -> u8 {
+        match self {
+            IppValue::Integer(_) => TAG_INTEGER,
+            IppValue::Boolean(_) => TAG_BOOLEAN,
+            IppValue::Enum(_) => TAG_ENUM,
+            IppValue::OctetString(_) => TAG_OCTET_STRING,
+            IppValue::DateTime(_) => TAG_DATE_TIME,
+            IppValue::Resolution { .. } => TAG_RESOLUTION,
+            IppValue::RangeOfInteger { .. } => TAG_RANGE_OF_INTEGER,
+            IppValue::Text(_) => TAG_TEXT,
+            IppValue::Name(_) => TAG_NAME,
+            IppValue::Keyword(_) => TAG_KEYWORD,
+            IppValue::Uri(_) => TAG_URI,
+            IppValue::UriScheme(_) => TAG_URI_SCHEME,
+            IppValue::Charset(_) => TAG_CHARSET,
+            IppValue::NaturalLanguage(_) => TAG_NATURAL_LANGUAGE,
+            IppValue::MimeMediaType(_) => TAG_MIME_MEDIA_TYPE,
+            IppValue::Collection(_) => TAG_BEGIN_COLLECTION,
+            IppValue::NoValue => TAG_NO_VALUE,
+            IppValue::Unknown => TAG_UNKNOWN,
+            IppValue::Unsupported => TAG_UNSUPPORTED,
+            IppValue::Other { tag, .. } => *tag,
+        }
+    }
+
+    fn encode_data(&self) -> Vec<u8> {
+        match self {
+            IppValue::Integer(v) | IppValue::Enum(v) => v.to_be_bytes().to_vec(),
+            IppValue::Boolean(v) => vec![*v as u8],
+            IppValue::OctetString(data) | IppValue::DateTime(data) => data.clone(),
+            IppValue::Resolution {
+                cross_feed,
+                feed,
+                units,
+            } => {
+                let mut data = cross_feed.to_be_bytes().to_vec();
+                data.extend_from_slice(&feed.to_be_bytes());
+                data.push(*units);
+                data
+            }
+            IppValue::RangeOfInteger { lower, upper } => {
+                let mut data = lower.to_be_bytes().to_vec();
+                data.extend_from_slice(&upper.to_be_bytes());
+                data
+            }
+            IppValue::Other { data, .. } => data.clone(),
+            IppValue::Collection(_)
+            | IppValue::NoValue
+            | IppValue::Unknown
+            | IppValue::Unsupported => Vec::new(),
+            text => text.as_str().unwrap_or_default().as_bytes().to_vec(),
+        }
+    }
+
+    fn decode(tag: u8, data: &[u8]) -> Result<IppValue, String> {
+        let int = |data: &[u8]| -> Result<i32, String> {
+            data.try_into()
+                .map(i32::from_be_bytes)
+                .map_err(|_| format!("Invalid integer length {}", data.len()))
+        };
+        let text = |data: &[u8]| String::from_utf8_lossy(data).into_owned();
+
+        Ok(match tag {
+            TAG_INTEGER => IppValue::Integer(int(data)?),
+            TAG_ENUM => IppValue::Enum(int(data)?),
+            TAG_BOOLEAN => IppValue::Boolean(data.first().copied().unwrap_or(0) != 0),
+            TAG_OCTET_STRING => IppValue::OctetString(data.to_vec()),
+            TAG_DATE_TIME => IppValue::DateTime(data.to_vec()),
+            TAG_RESOLUTION if data.len() == 9 => IppValue::Resolution {
+                cross_feed: int(&data[0..4])?,
+                feed: int(&data[4..8])?,
+                units: data[8],
+            },
+            TAG_RANGE_OF_INTEGER if data.len() == 8 => IppValue::RangeOfInteger {
+                lower: int(&data[0..4])?,
+                upper: int(&data[4..8])?,
+            },
+            // textWithLanguage/nameWithLanguage carry the language inline; keep the text
+            TAG_TEXT_WITH_LANGUAGE | TAG_NAME_WITH_LANGUAGE => {
+                IppValue::Text(decode_with_language(data)?)
+            }
+            TAG_TEXT => IppValue::Text(text(data)),
+            TAG_NAME => IppValue::Name(text(data)),
+            TAG_KEYWORD => IppValue::Keyword(text(data)),
+            TAG_URI => IppValue::Uri(text(data)),
+            TAG_URI_SCHEME => IppValue::UriScheme(text(data)),
+            TAG_CHARSET => IppValue::Charset(text(data)),
+            TAG_NATURAL_LANGUAGE => IppValue::NaturalLanguage(text(data)),
+            TAG_MIME_MEDIA_TYPE => IppValue::MimeMediaType(text(data)),
+            TAG_NO_VALUE => IppValue::NoValue,
+            TAG_UNKNOWN => IppValue::Unknown,
+            TAG_UNSUPPORTED => IppValue::Unsupported,
+            _ => IppValue::Other {
+                tag,
+                data: data.to_vec(),
+            },
+        })
+    }
+
+    /// Name of the value's syntax, as `parse` takes it
+    pub fn syntax(&self) -> &'static str {
+        match self {
+            IppValue::Integer(_) => "integer",
+            IppValue::Boolean(_) => "boolean",
+            IppValue::Enum(_) => "enum",
+            IppValue::OctetString(_) => "octetString",
+            IppValue::DateTime(_) => "dateTime",
+            IppValue::Resolution { .. } => "resolution",
+            IppValue::RangeOfInteger { .. } => "rangeOfInteger",
+            IppValue::Text(_) => "text",
+            IppValue::Name(_) => "name",
+            IppValue::Keyword(_) => "keyword",
+            IppValue::Uri(_) => "uri",
+            IppValue::UriScheme(_) => "uriScheme",
+            IppValue::Charset(_) => "charset",
+            IppValue::NaturalLanguage(_) => "naturalLanguage",
+            IppValue::MimeMediaType(_) => "mimeMediaType",
+            IppValue::Collection(_) => "collection",
+            IppValue::NoValue => "no-value",
+            IppValue::Unknown => "unknown",
+            IppValue::Unsupported => "unsupported",
+            IppValue::Other { .. } => "other",
+        }
+    }
+
+    /// Parse a value of the given syntax from its text form, as `Display`
+    /// writes it: `600x600dpi` for resolutions, `1-10` for ranges and
+    /// `2024-05-01T09:30:00+02:00` for dates. Collections are built from
+    /// their members instead.
+    pub fn parse(syntax: &str, text: &str) -> Result<IppValue, String> {
+        let int = |text: &str| {
+            text.trim()
+                .parse::<i32>()
+                .map_err(|_| format!("Invalid {} value '{}'", syntax, text))
+        };
+        Ok(match syntax {
+            "integer" => IppValue::Integer(int(text)?),
+            "enum" => IppValue::Enum(int(text)?),
+            "boolean" => match text.trim() {
+                "true" => IppValue::Boolean(true),
+                "false" => IppValue::Boolean(false),
+                _ => return Err(format!("Invalid boolean value '{}'", text)),
+            },
+            "octetString" => IppValue::OctetString(text.as_bytes().to_vec()),
+            "dateTime" => IppValue::DateTime(parse_date_time(text)?),
+            "resolution" => {
+                let invalid = || format!("Invalid resolution '{}'", text);
+                let text = text.trim();
+                let (dimensions, units) = if let Some(dimensions) = text.strip_suffix("dpi") {
+                    (dimensions, 3)
+                } else if let Some(dimensions) = text.strip_suffix("dpcm") {
+                    (dimensions, 4)
+                } else {
+                    return Err(invalid());
+                };
+                let (cross_feed, feed) = dimensions
+                    .split_once('x')
+                    .unwrap_or((dimensions, dimensions));
+                IppValue::Resolution {
+                    cross_feed: cross_feed.parse().map_err(|_| invalid())?,
+                    feed: feed.parse().map_err(|_| invalid())?,
+                    units,
+                }
+            }
+            "rangeOfInteger" => {
+                let (lower, upper) = text
+                    .trim()
+                    .split_once('-')
+                    .ok_or_else(|| format!("Invalid rangeOfInteger value '{}'", text))?;
+                IppValue::RangeOfInteger {
+                    lower: int(lower)?,
+                    upper: int(upper)?,
+                }
+            }
+            "text" => IppValue::Text(text.to_string()),
+            "name" => IppValue::Name(text.to_string()),
+            "keyword" => IppValue::Keyword(text.to_string()),
+            "uri" => IppValue::Uri(text.to_string()),
+            "uriScheme" => IppValue::UriScheme(text.to_string()),
+            "charset" => IppValue::Charset(text.to_string()),
+            "naturalLanguage" => IppValue::NaturalLanguage(text.to_string()),
+            "mimeMediaType" => IppValue::MimeMediaType(text.to_string()),
+            "no-value" => IppValue::NoValue,
+            "unknown" => IppValue::Unknown,
+            "unsupported" => IppValue::Unsupported,
+            _ => return Err(format!("Unsupported IPP syntax '{}'", syntax)),
+        })
+    }
+}
+
+/// Write an RFC 2579 DateAndTime, without its deciseconds
+fn format_date_time(data: &[u8], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if data.len() < 11 {
+        return write!(f, "0x{}", crate::crypto::hex(data));
+    }
+    write!(
+        f,
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}{:02}:{:02}",
+        u16::from_be_bytes([data[0], data[1]]),
+        data[2],
+        data[3],
+        data[4],
+        data[5],
+        data[6],
+        data[8] as char,
+        data[9],
+        data[10]
+    )
+}
+
+/// Parse `YYYY-MM-DDTHH:MM:SS` followed by `Z` or a `+HH:MM`/`-HH:MM`
+/// offset into an RFC 2579 DateAndTime
+fn parse_date_time(text: &str) -> Result<Vec<u8>, String> {
+    let invalid = || format!("Invalid dateTime value '{}'", text);
+    let text = text.trim();
+    let number = |range: std::ops::Range<usize>| -> Result<u16, String> {
+        text.get(range)
+            .and_then(|digits| digits.parse().ok())
+            .ok_or_else(invalid)
+    };
+    if text.len() < 20 || text.as_bytes()[10] != b'T' {
+        return Err(invalid());
+    }
+    let year = number(0..4)?;
+    let mut data = year.to_be_bytes().to_vec();
+    for range in [5..7, 8..10, 11..13, 14..16, 17..19] {
+        data.push(number(range)? as u8);
+    }
+    data.push(0);
+    match &text[19..] {
+        "Z" => data.extend_from_slice(&[b'+', 0, 0]),
+        offset if offset.len() == 6 && matches!(&offset[..1], "+" | "-") => {
+            data.push(offset.as_bytes()[0]);
+            data.push(number(20..22)? as u8);
+            data.push(number(23..25)? as u8);
+        }
+        _ => return Err(invalid()),
+    }
+    Ok(data)
+}
+
+/// The ipptool-style text form of a value, with collections written as
+/// `{member=value ...}`
+impl fmt::Display for IppValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IppValue::Integer(v) | IppValue::Enum(v) => write!(f, "{}", v),
+            IppValue::Boolean(v) => write!(f, "{}", v),
+            IppValue::OctetString(data) => f.write_str(&String::from_utf8_lossy(data)),
+            IppValue::DateTime(data) => format_date_time(data, f),
+            IppValue::Resolution {
+                cross_feed,
+                feed,
+                units,
+            } => {
+                let units = if *units == 4 { "dpcm" } else { "dpi" };
+                if cross_feed == feed {
+                    write!(f, "{}{}", cross_feed, units)
+                } else {
+                    write!(f, "{}x{}{}", cross_feed, feed, units)
+                }
+            }
+            IppValue::RangeOfInteger { lower, upper } => write!(f, "{}-{}", lower, upper),
+            IppValue::Collection(members) => {
+                f.write_str("{")?;
+                for (index, member) in members.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(" ")?;
+                    }
+                    write!(f, "{}=", member.name)?;
+                    for (index, value) in member.values.iter().enumerate() {
+                        if index > 0 {
+                            f.write_str(",")?;
+                        }
+                        write!(f, "{}", value)?;
+                    }
+                }
+                f.write_str("}")
+            }
+            IppValue::NoValue | IppValue::Unknown | IppValue::Unsupported => {
+                f.write_str(self.syntax())
+            }
+            IppValue::Other { tag, data } => {
+                write!(f, "0x{:02x}:{}", tag, crate::crypto::hex(data))
+            }
+            text => f.write_str(text.as_str().unwrap_or_default()),
+        }
+    }
+}
+
+fn decode_with_language(data: &[u8]) -> Result<String, String> {
+    let mut reader = Reader::new(data);
+    let language_len = reader.u16()? as usize;
+    reader.bytes(language_len)?;
+    let text_len = reader.u16()? as usize;
+    Ok(String::from_utf8_lossy(reader.bytes(text_len)?).into_owned())
+}
+
+/// A named attribute with one or more values
+#[derive(Clone, Debug, PartialEq)]
+pub struct IppAttribute {
+    pub name: String,
+    pub values: Vec<IppValue>,
+}
+
+impl IppAttribute {
+    pub fn new(name: &str, value: IppValue) -> Self {
+        IppAttribute {
+            name: name.to_string(),
+            values: vec![value],
+        }
+    }
+
+    /// First value, which is all most single-valued attributes need
+    pub fn value(&self) -> Option<&IppValue> {
+        self.values.first()
+    }
+}
+
+/// Attributes grouped under a delimiter tag
+#[derive(Clone, Debug, PartialEq)]
+pub struct IppAttributeGroup {
+    pub tag: u8,
+    pub attributes: Vec<IppAttribute>,
+}
+
+/// An IPP request message
+#[derive(Clone, Debug)]
+pub struct IppRequest {
+    pub operation: u16,
+    pub request_id: u32,
+    pub groups: Vec<IppAttributeGroup>,
+    pub data: Vec<u8>,
+}
+
+impl IppRequest {
+    /// Create a request with the mandatory operation attributes
+    /// (attributes-charset, attributes-natural-language, printer-uri)
+    pub fn new(operation: u16, printer_uri: &str) -> Self {
+        IppRequest {
+            operation,
+            request_id: 1,
+            groups: vec![IppAttributeGroup {
+                tag: TAG_OPERATION_ATTRIBUTES,
+                attributes: vec![
+                    IppAttribute::new("attributes-charset", IppValue::Charset("utf-8".to_string())),
+                    IppAttribute::new(
+                        "attributes-natural-language",
+                        IppValue::NaturalLanguage("en".to_string()),
+                    ),
+                    IppAttribute::new("printer-uri", IppValue::Uri(printer_uri.to_string())),
+                ],
+            }],
+            data: Vec::new(),
+        }
+    }
+
+    /// Add an attribute to the group with the given tag, creating the group if needed
+    pub fn add_attribute(&mut self, group_tag: u8, attribute: IppAttribute) {
+        if let Some(group) = self.groups.iter_mut().find(|g| g.tag == group_tag) {
+            group.attributes.push(attribute);
+        } else {
+            self.groups.push(IppAttributeGroup {
+                tag: group_tag,
+                attributes: vec![attribute],
+            });
+        }
+    }
+
+    /// Encode the request into its binary wire format
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = vec![1, 1]; // IPP/1.1
+        buf.extend_from_slice(&self.operation.to_be_bytes());
+        buf.extend_from_slice(&self.request_id.to_be_bytes());
+        encode_groups(&mut buf, &self.groups);
+        buf.extend_from_slice(&self.data);
+        buf
+    }
+
+    /// Decode a request from its binary wire format, as a server receives it
+    pub fn decode(bytes: &[u8]) -> Result<IppRequest, String> {
+        let mut reader = Reader::new(bytes);
+        let _version = reader.bytes(2)?;
+        let operation = reader.u16()?;
+        let request_id = reader.u32()?;
+        let groups = decode_groups(&mut reader)?;
+
+        Ok(IppRequest {
+            operation,
+            request_id,
+            groups,
+            data: reader.rest().to_vec(),
+        })
+    }
+
+    /// Find an attribute by name in any group with the given tag
+    pub fn attribute(&self, group_tag: u8, name: &str) -> Option<&IppAttribute> {
+        self.groups
+            .iter()
+            .filter(|g| g.tag == group_tag)
+            .flat_map(|g| g.attributes.iter())
+            .find(|a| a.name == name)
+    }
+}
+
+/// An IPP response message
+#[derive(Clone, Debug)]
+pub struct IppResponse {
+    pub status_code: u16,
+    pub request_id: u32,
+    pub groups: Vec<IppAttributeGroup>,
+    pub data: Vec<u8>,
+}
+
+impl IppResponse {
+    /// Create a response with the mandatory operation attributes
+    /// (attributes-charset, attributes-natural-language)
+    pub fn new(status_code: u16, request_id: u32) -> Self {
+        IppResponse {
+            status_code,
+            request_id,
+            groups: vec![IppAttributeGroup {
+                tag: TAG_OPERATION_ATTRIBUTES,
+                attributes: vec![
+                    IppAttribute::new("attributes-charset", IppValue::Charset("utf-8".to_string())),
+                    IppAttribute::new(
+                        "attributes-natural-language",
+                        IppValue::NaturalLanguage("en".to_string()),
+                    ),
+                ],
+            }],
+            data: Vec::new(),
+        }
+    }
+
+    /// Add an attribute to the group with the given tag, creating the group if needed
+    pub fn add_attribute(&mut self, group_tag: u8, attribute: IppAttribute) {
+        if let Some(group) = self.groups.iter_mut().find(|g| g.tag == group_tag) {
+            group.attributes.push(attribute);
+        } else {
+            self.groups.push(IppAttributeGroup {
+                tag: group_tag,
+                attributes: vec![attribute],
+            });
+        }
+    }
+
+    /// Encode the response into its binary wire format
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = vec![1, 1]; // IPP/1.1
+        buf.extend_from_slice(&self.status_code.to_be_bytes());
+        buf.extend_from_slice(&self.request_id.to_be_bytes());
+        encode_groups(&mut buf, &self.groups);
+        buf.extend_from_slice(&self.data);
+        buf
+    }
+
+    /// Whether the status code is in the successful range
+    pub fn is_success(&self) -> bool {
+        self.status_code <= 0x00FF
+    }
+
+    /// Find an attribute by name in any group with the given tag
+    pub fn attribute(&self, group_tag: u8, name: &str) -> Option<&IppAttribute> {
+        self.groups
+            .iter()
+            .filter(|g| g.tag == group_tag)
+            .flat_map(|g| g.attributes.iter())
+            .find(|a| a.name == name)
+    }
+
+    /// Decode a response from its binary wire format
+    pub fn decode(bytes: &[u8]) -> Result<IppResponse, String> {
+        let mut reader = Reader::new(bytes);
+        let _version = reader.bytes(2)?;
+        let status_code = reader.u16()?;
+        let request_id = reader.u32()?;
+        let groups = decode_groups(&mut reader)?;
+
+        Ok(IppResponse {
+            status_code,
+            request_id,
+            groups,
+            data: reader.rest().to_vec(),
+        })
+    }
+}
+
+fn encode_groups(buf: &mut Vec<u8>, groups: &[IppAttributeGroup]) {
+    for group in groups {
+        buf.push(group.tag);
+        for attribute in &group.attributes {
+            encode_attribute(buf, attribute);
+        }
+    }
+    buf.push(TAG_END_OF_ATTRIBUTES);
+}
+
+fn encode_attribute(buf: &mut Vec<u8>, attribute: &IppAttribute) {
+    for (index, value) in attribute.values.iter().enumerate() {
+        // Additional values of a multi-valued attribute have an empty name
+        let name = if index == 0 {
+            attribute.name.as_str()
+        } else {
+            ""
+        };
+        encode_value(buf, name, value);
+    }
+}
+
+fn encode_value(buf: &mut Vec<u8>, name: &str, value: &IppValue) {
+    buf.push(value.tag());
+    push_string(buf, name.as_bytes());
+
+    if let IppValue::Collection(members) = value {
+        push_string(buf, &[]);
+        for member in members {
+            for (index, member_value) in member.values.iter().enumerate() {
+                if index == 0 {
+                    buf.push(TAG_MEMBER_ATTR_NAME);
+                    push_string(buf, &[]);
+                    push_string(buf, member.name.as_bytes());
+                }
+                encode_value(buf, "", member_value);
+            }
+        }
+        buf.push(TAG_END_COLLECTION);
+        push_string(buf, &[]);
+        push_string(buf, &[]);
+    } else {
+        push_string(buf, &value.encode_data());
+    }
+}
+
+fn push_string(buf: &mut Vec<u8>, data: &[u8]) {
+    buf.extend_from_slice(&(data.len() as u16).to_be_bytes());
+    buf.extend_from_slice(data);
+}
+
+fn decode_groups(reader: &mut Reader) -> Result<Vec<IppAttributeGroup>, String> {
+    let mut groups: Vec<IppAttributeGroup> = Vec::new();
+
+    loop {
+        let tag = reader.u8()?;
+        if tag == TAG_END_OF_ATTRIBUTES {
+            return Ok(groups);
+        }
+        if tag < 0x10 {
+            groups.push(IppAttributeGroup {
+                tag,
+                attributes: Vec::new(),
+            });
+            continue;
+        }
+
+        let name = reader.string()?;
+        let value = decode_value(reader, tag)?;
+        let group = groups
+            .last_mut()
+            .ok_or_else(|| "Attribute outside of a group".to_string())?;
+
+        if name.is_empty() {
+            // Additional value for the previous attribute
+            match group.attributes.last_mut() {
+                Some(attribute) => attribute.values.push(value),
+                None => return Err("Additional value without attribute".to_string()),
+            }
+        } else {
+            group.attributes.push(IppAttribute {
+                name,
+                values: vec![value],
+            });
+        }
+    }
+}
+
+fn decode_value(reader: &mut Reader, tag: u8) -> Result<IppValue, String> {
+    let length = reader.u16()? as usize;
+    let data = reader.bytes(length)?;
+
+    if tag != TAG_BEGIN_COLLECTION {
+        return IppValue::decode(tag, data);
+    }
+
+    let mut members: Vec<IppAttribute> = Vec::new();
+    loop {
+        let member_tag = reader.u8()?;
+        let _name = reader.string()?;
+        if member_tag == TAG_END_COLLECTION {
+            reader.string()?;
+            return Ok(IppValue::Collection(members));
+        }
+        if member_tag == TAG_MEMBER_ATTR_NAME {
+            let member_name = reader.string()?;
+            members.push(IppAttribute {
+                name: member_name,
+                values: Vec::new(),
+            });
+            continue;
+        }
+
+        let value = decode_value(reader, member_tag)?;
+        match members.last_mut() {
+            Some(member) => member.values.push(value),
+            None => return Err("Collection value without member name".to_string()),
+        }
+    }
+}
+
+/// Bounds-checked big-endian reader
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Reader { data, pos: 0 }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self.pos + len;
+        if end > self.data.len() {
+            return Err("Truncated IPP message".to_string());
+        }
+        let slice = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let b = self.bytes(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let len = self.u16()? as usize;
+        Ok(String::from_utf8_lossy(self.bytes(len)?).into_owned())
+    }
+
+    fn rest(&self) -> &'a [u8] {
+        &self.data[self.pos..]
+    }
+}
+
+/// Send an IPP request over HTTP and decode the response. If the server
+/// asks for authentication, the request is repeated with the printer's
+/// credentials (see `credentials`).
+pub fn send_request(
+    printer_uri: &str,
+    request: &IppRequest,
+    timeout: Duration,
+) -> Result<IppResponse, String> {
+    let address = DeviceAddress::parse(printer_uri)
+        .ok_or_else(|| format!("Invalid printer URI '{}'", printer_uri))?;
+
+    tracing::debug!(uri = printer_uri, "Sending IPP request");
+    let body = request.encode();
+    let challenges = match post(&address, &body, None, timeout)? {
+        HttpReply::Body(body) => return IppResponse::decode(&body),
+        HttpReply::Unauthorized(challenges) => challenges,
+    };
+
+    let credentials = crate::credentials::for_uri(printer_uri).ok_or_else(|| {
+        format!(
+            "{} requires authentication; set credentials with setPrinterCredentials",
+            printer_uri
+        )
+    })?;
+    if !challenges
+        .iter()
+        .any(|challenge| challenge.to_lowercase().starts_with("basic"))
+    {
+        return Err(format!(
+            "{} requires {} authentication, which is not supported",
+            printer_uri,
+            challenges.first().map_or("unknown", String::as_str)
+        ));
+    }
+    let authorization = credentials.authorization()?;
+    tracing::debug!(uri = printer_uri, "Authenticating IPP request");
+    match post(&address, &body, Some(&authorization), timeout)? {
+        HttpReply::Body(body) => IppResponse::decode(&body),
+        HttpReply::Unauthorized(_) => Err(format!(
+            "Authentication as '{}' failed for {}",
+            credentials.username().unwrap_or_default(),
+            printer_uri
+        )),
+    }
+}
+
+/// An HTTP response to an IPP request
+enum HttpReply {
+    Body(Vec<u8>),
+    /// 401, with the WWW-Authenticate challenges
+    Unauthorized(Vec<String>),
+}
+
+/// POST an encoded IPP message over a new connection
+fn post(
+    address: &DeviceAddress,
+    body: &[u8],
+    authorization: Option<&str>,
+    timeout: Duration,
+) -> Result<HttpReply, String> {
+    let mut stream = address.open(timeout)?;
+
+    let header = format!(
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/ipp\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n",
+        if address.path.is_empty() {
+            "/"
+        } else {
+            address.path.as_str()
+        },
+        address.authority(),
+        body.len(),
+        authorization
+            .map(|authorization| format!("Authorization: {}\r\n", authorization))
+            .unwrap_or_default()
+    );
+
+    stream
+        .write_all(header.as_bytes())
+        .and_then(|_| stream.write_all(body))
+        .map_err(|e| format!("Failed to send IPP request: {}", e))?;
+
+    read_http_response(&mut *stream)
+}
+
+/// Read an HTTP response and return its body, handling chunked encoding
+fn read_http_response(stream: &mut dyn Read) -> Result<HttpReply, String> {
+    let raw = crate::device::read_to_close(stream)
+        .map_err(|e| format!("Failed to read IPP response: {}", e))?;
+
+    let header_end = raw
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| "Malformed HTTP response".to_string())?;
+    let head = String::from_utf8_lossy(&raw[..header_end]);
+    let headers = head.to_lowercase();
+    let body = &raw[header_end + 4..];
+
+    let status = headers
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or_else(|| "Malformed HTTP status line".to_string())?;
+    match status {
+        200 => {}
+        401 => {
+            let challenges = head
+                .lines()
+                .filter_map(|line| line.split_once(':'))
+                .filter(|(name, _)| name.trim().eq_ignore_ascii_case("www-authenticate"))
+                .map(|(_, value)| value.trim().to_string())
+                .collect();
+            return Ok(HttpReply::Unauthorized(challenges));
+        }
+        403 => return Err("HTTP error 403: access to the printer is forbidden".to_string()),
+        status => return Err(format!("HTTP error {}", status)),
+    }
+
+    if headers.contains("transfer-encoding: chunked") {
+        decode_chunked(body).map(HttpReply::Body)
+    } else {
+        Ok(HttpReply::Body(body.to_vec()))
+    }
+}
+
+pub(crate) fn decode_chunked(mut body: &[u8]) -> Result<Vec<u8>, String> {
+    let mut decoded = Vec::new();
+    loop {
+        let line_end = body
+            .windows(2)
+            .position(|w| w == b"\r\n")
+            .ok_or_else(|| "Malformed chunked body".to_string())?;
+        let size_line = String::from_utf8_lossy(&body[..line_end]);
+        let size_hex = size_line.split(';').next().unwrap_or("").trim();
+        let size = usize::from_str_radix(size_hex, 16)
+            .map_err(|_| format!("Invalid chunk size '{}'", size_hex))?;
+        body = &body[line_end + 2..];
+        if size == 0 {
+            return Ok(decoded);
+        }
+        if body.len() < size {
+            return Err("Truncated chunked body".to_string());
+        }
+        decoded.extend_from_slice(&body[..size]);
+        body = body.get(size + 2..).unwrap_or(&[]);
+    }
+}
+
+/// Query printer attributes via Get-Printer-Attributes
+pub fn get_printer_attributes(
+    printer_uri: &str,
+    requested: &[&str],
+    timeout: Duration,
+) -> Result<IppResponse, String> {
+    let mut request = IppRequest::new(OP_GET_PRINTER_ATTRIBUTES, printer_uri);
+    if !requested.is_empty() {
+        request.add_attribute(
+            TAG_OPERATION_ATTRIBUTES,
+            IppAttribute {
+                name: "requested-attributes".to_string(),
+                values: requested
+                    .iter()
+                    .map(|name| IppValue::Keyword(name.to_string()))
+                    .collect(),
+            },
+        );
+    }
+
+    let response = send_request(printer_uri, &request, timeout)?;
+    if response.is_success() {
+        Ok(response)
+    } else {
+        Err(format!(
+            "Get-Printer-Attributes failed with status 0x{:04x}",
+            response.status_code
+        ))
+    }
+}
+
+/// Query job attributes via Get-Job-Attributes
+pub fn get_job_attributes(
+    printer_uri: &str,
+    job_id: i32,
+    requested: &[&str],
+    timeout: Duration,
+) -> Result<IppResponse, String> {
+    let mut request = IppRequest::new(OP_GET_JOB_ATTRIBUTES, printer_uri);
+    request.add_attribute(
+        TAG_OPERATION_ATTRIBUTES,
+        IppAttribute::new("job-id", IppValue::Integer(job_id)),
+    );
+    if !requested.is_empty() {
+        request.add_attribute(
+            TAG_OPERATION_ATTRIBUTES,
+            IppAttribute {
+                name: "requested-attributes".to_string(),
+                values: requested
+                    .iter()
+                    .map(|name| IppValue::Keyword(name.to_string()))
+                    .collect(),
+            },
+        );
+    }
+
+    let response = send_request(printer_uri, &request, timeout)?;
+    if response.is_success() {
+        Ok(response)
+    } else {
+        Err(format!(
+            "Get-Job-Attributes failed with status 0x{:04x}",
+            response.status_code
+        ))
+    }
+}
+
+/// Event notifications returned by Get-Notifications (RFC 3996)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Notifications {
+    /// (notify-sequence-number, notify-subscribed-event) of each event
+    pub events: Vec<(i32, String)>,
+    /// Seconds the server asks clients to wait before polling again
+    pub get_interval: Option<Duration>,
+}
+
+impl Notifications {
+    pub fn from_response(response: &IppResponse) -> Notifications {
+        let events = response
+            .groups
+            .iter()
+            .filter(|group| group.tag == TAG_EVENT_NOTIFICATION_ATTRIBUTES)
+            .filter_map(|group| {
+                let value = |name: &str| {
+                    group
+                        .attributes
+                        .iter()
+                        .find(|attribute| attribute.name == name)
+                        .and_then(IppAttribute::value)
+                };
+                let sequence = value("notify-sequence-number")?.as_i32()?;
+                let event = value("notify-subscribed-event")
+                    .and_then(IppValue::as_str)
+                    .unwrap_or("unknown");
+                Some((sequence, event.to_string()))
+            })
+            .collect();
+        let get_interval = response
+            .attribute(TAG_OPERATION_ATTRIBUTES, "notify-get-interval")
+            .and_then(IppAttribute::value)
+            .and_then(IppValue::as_i32)
+            .filter(|seconds| *seconds >= 0)
+            .map(|seconds| Duration::from_secs(seconds as u64));
+        Notifications {
+            events,
+            get_interval,
+        }
+    }
+}
+
+/// Create a pull (ippget) subscription for the given events via
+/// Create-Printer-Subscriptions, returning its notify-subscription-id
+pub fn create_printer_subscription(
+    printer_uri: &str,
+    user: &str,
+    events: &[&str],
+    lease: Duration,
+    timeout: Duration,
+) -> Result<i32, String> {
+    let mut request = IppRequest::new(OP_CREATE_PRINTER_SUBSCRIPTIONS, printer_uri);
+    request.add_attribute(
+        TAG_OPERATION_ATTRIBUTES,
+        IppAttribute::new("requesting-user-name", IppValue::Name(user.to_string())),
+    );
+    request.add_attribute(
+        TAG_SUBSCRIPTION_ATTRIBUTES,
+        IppAttribute::new(
+            "notify-pull-method",
+            IppValue::Keyword("ippget".to_string()),
+        ),
+    );
+    request.add_attribute(
+        TAG_SUBSCRIPTION_ATTRIBUTES,
+        IppAttribute {
+            name: "notify-events".to_string(),
+            values: events
+                .iter()
+                .map(|event| IppValue::Keyword(event.to_string()))
+                .collect(),
+        },
+    );
+    request.add_attribute(
+        TAG_SUBSCRIPTION_ATTRIBUTES,
+        IppAttribute::new(
+            "notify-lease-duration",
+            IppValue::Integer(lease.as_secs().min(i32::MAX as u64) as i32),
+        ),
+    );
+
+    let response = send_request(printer_uri, &request, timeout)?;
+    if !response.is_success() {
+        return Err(format!(
+            "Create-Printer-Subscriptions failed with status 0x{:04x}",
+            response.status_code
+        ));
+    }
+    response
+        .attribute(TAG_SUBSCRIPTION_ATTRIBUTES, "notify-subscription-id")
+        .and_then(IppAttribute::value)
+        .and_then(IppValue::as_i32)
+        .ok_or_else(|| "Response has no notify-subscription-id".to_string())
+}
+
+/// Fetch events of a subscription starting at a sequence number via
+/// Get-Notifications. Fails with STATUS_NOT_FOUND once the subscription expired.
+pub fn get_notifications(
+    printer_uri: &str,
+    subscription_id: i32,
+    first_sequence: i32,
+    timeout: Duration,
+) -> Result<Notifications, (u16, String)> {
+    let mut request = IppRequest::new(OP_GET_NOTIFICATIONS, printer_uri);
+    request.add_attribute(
+        TAG_OPERATION_ATTRIBUTES,
+        IppAttribute::new(
+            "notify-subscription-ids",
+            IppValue::Integer(subscription_id),
+        ),
+    );
+    request.add_attribute(
+        TAG_OPERATION_ATTRIBUTES,
+        IppAttribute::new("notify-sequence-numbers", IppValue::Integer(first_sequence)),
+    );
+
+    let response = send_request(printer_uri, &request, timeout).map_err(|e| (0, e))?;
+    if response.is_success() {
+        Ok(Notifications::from_response(&response))
+    } else {
+        Err((
+            response.status_code,
+            format!(
+                "Get-Notifications failed with status 0x{:04x}",
+                response.status_code
+            ),
+        ))
+    }
+}
+
+/// Cancel a subscription via Cancel-Subscription
+pub fn cancel_subscription(
+    printer_uri: &str,
+    subscription_id: i32,
+    timeout: Duration,
+) -> Result<(), String> {
+    let mut request = IppRequest::new(OP_CANCEL_SUBSCRIPTION, printer_uri);
+    request.add_attribute(
+        TAG_OPERATION_ATTRIBUTES,
+        IppAttribute::new("notify-subscription-id", IppValue::Integer(subscription_id)),
+    );
+
+    let response = send_request(printer_uri, &request, timeout)?;
+    if response.is_success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Cancel-Subscription failed with status 0x{:04x}",
+            response.status_code
+        ))
+    }
+}
+
+/// Send a request for any operation, for operations and attributes this
+/// module has no function for. `attributes` are added to the groups with
+/// the given tags after the mandatory operation attributes, replacing
+/// those of the same name. The response is returned whatever its status.
+pub fn send_raw_request(
+    printer_uri: &str,
+    operation: u16,
+    attributes: Vec<(u8, IppAttribute)>,
+    data: Vec<u8>,
+    timeout: Duration,
+) -> Result<IppResponse, String> {
+    let mut request = IppRequest::new(operation, printer_uri);
+    let mandatory = request.groups[0].attributes.len();
+    for (group_tag, attribute) in attributes {
+        let existing = request.groups[0]
+            .attributes
+            .iter_mut()
+            .take(mandatory)
+            .find(|existing| existing.name == attribute.name);
+        match existing {
+            Some(existing) if group_tag == TAG_OPERATION_ATTRIBUTES => *existing = attribute,
+            _ => request.add_attribute(group_tag, attribute),
+        }
+    }
+    request.data = data;
+    send_request(printer_uri, &request, timeout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_encoding() {
+        let mut request = IppRequest::new(OP_GET_PRINTER_ATTRIBUTES, "ipp://localhost/ipp/print");
+        request.request_id = 7;
+        let bytes = request.encode();
+
+        assert_eq!(&bytes[0..2], &[1, 1]);
+        assert_eq!(&bytes[2..4], &OP_GET_PRINTER_ATTRIBUTES.to_be_bytes());
+        assert_eq!(&bytes[4..8], &7u32.to_be_bytes());
+        assert_eq!(bytes[8], TAG_OPERATION_ATTRIBUTES);
+        assert_eq!(bytes[9], TAG_CHARSET);
+        assert_eq!(*bytes.last().unwrap(), TAG_END_OF_ATTRIBUTES);
+    }
+
+    #[test]
+    fn test_response_round_trip() {
+        // A response has the same layout as a request, with a status code in place of the operation
+        let mut message = IppRequest::new(STATUS_OK, "ipp://localhost/");
+        message.add_attribute(
+            TAG_PRINTER_ATTRIBUTES,
+            IppAttribute {
+                name: "printer-state-reasons".to_string(),
+                values: vec![
+                    IppValue::Keyword("media-low-warning".to_string()),
+                    IppValue::Keyword("toner-low-warning".to_string()),
+                ],
+            },
+        );
+        message.add_attribute(
+            TAG_PRINTER_ATTRIBUTES,
+            IppAttribute::new("printer-state", IppValue::Enum(3)),
+        );
+        message.add_attribute(
+            TAG_PRINTER_ATTRIBUTES,
+            IppAttribute::new(
+                "media-col-default",
+                IppValue::Collection(vec![IppAttribute::new(
+                    "media-size-name",
+                    IppValue::Keyword("iso_a4_210x297mm".to_string()),
+                )]),
+            ),
+        );
+
+        let response = IppResponse::decode(&message.encode()).unwrap();
+        assert!(response.is_success());
+
+        let reasons = response
+            .attribute(TAG_PRINTER_ATTRIBUTES, "printer-state-reasons")
+            .unwrap();
+        assert_eq!(reasons.values.len(), 2);
+        assert_eq!(reasons.values[1].as_str(), Some("toner-low-warning"));
+
+        let state = response
+            .attribute(TAG_PRINTER_ATTRIBUTES, "printer-state")
+            .unwrap();
+        assert_eq!(state.value().and_then(IppValue::as_i32), Some(3));
+
+        let media = response
+            .attribute(TAG_PRINTER_ATTRIBUTES, "media-col-default")
+            .unwrap();
+        match media.value() {
+            Some(IppValue::Collection(members)) => {
+                assert_eq!(members[0].name, "media-size-name");
+                assert_eq!(
+                    members[0].value().and_then(IppValue::as_str),
+                    Some("iso_a4_210x297mm")
+                );
+            }
+            other => panic!("Expected collection, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_notifications_from_response() {
+        let mut message = IppRequest::new(STATUS_OK, "ipp://localhost/");
+        message.add_attribute(
+            TAG_OPERATION_ATTRIBUTES,
+            IppAttribute::new("notify-get-interval", IppValue::Integer(30)),
+        );
+        for (sequence, event) in [(4, "job-completed"), (5, "printer-state-changed")] {
+            message.groups.push(IppAttributeGroup {
+                tag: TAG_EVENT_NOTIFICATION_ATTRIBUTES,
+                attributes: vec![
+                    IppAttribute::new("notify-sequence-number", IppValue::Integer(sequence)),
+                    IppAttribute::new(
+                        "notify-subscribed-event",
+                        IppValue::Keyword(event.to_string()),
+                    ),
+                ],
+            });
+        }
+
+        let response = IppResponse::decode(&message.encode()).unwrap();
+        let notifications = Notifications::from_response(&response);
+        assert_eq!(
+            notifications.events,
+            vec![
+                (4, "job-completed".to_string()),
+                (5, "printer-state-changed".to_string())
+            ]
+        );
+        assert_eq!(notifications.get_interval, Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn test_server_messages() {
+        let mut request = IppRequest::new(OP_PRINT_JOB, "ipp://localhost:8631/ipp/print");
+        request.request_id = 9;
+        request.add_attribute(
+            TAG_JOB_ATTRIBUTES,
+            IppAttribute::new("copies", IppValue::Integer(2)),
+        );
+        request.data = b"%PDF-1.7".to_vec();
+
+        let decoded = IppRequest::decode(&request.encode()).unwrap();
+        assert_eq!(decoded.operation, OP_PRINT_JOB);
+        assert_eq!(decoded.request_id, 9);
+        assert_eq!(decoded.data, b"%PDF-1.7");
+        assert_eq!(
+            decoded
+                .attribute(TAG_JOB_ATTRIBUTES, "copies")
+                .and_then(IppAttribute::value)
+                .and_then(IppValue::as_i32),
+            Some(2)
+        );
+
+        let mut response = IppResponse::new(STATUS_BUSY, 9);
+        response.add_attribute(
+            TAG_JOB_ATTRIBUTES,
+            IppAttribute::new("job-id", IppValue::Integer(42)),
+        );
+        let decoded = IppResponse::decode(&response.encode()).unwrap();
+        assert_eq!(decoded.status_code, STATUS_BUSY);
+        assert_eq!(decoded.request_id, 9);
+        assert!(decoded
+            .attribute(TAG_OPERATION_ATTRIBUTES, "attributes-charset")
+            .is_some());
+        assert_eq!(
+            decoded
+                .attribute(TAG_JOB_ATTRIBUTES, "job-id")
+                .and_then(IppAttribute::value)
+                .and_then(IppValue::as_i32),
+            Some(42)
+        );
+    }
+
+    #[test]
+    fn test_value_text_forms() {
+        for (syntax, text) in [
+            ("integer", "-5"),
+            ("enum", "3"),
+            ("boolean", "true"),
+            ("resolution", "600dpi"),
+            ("resolution", "300x600dpcm"),
+            ("rangeOfInteger", "1-10"),
+            ("dateTime", "2024-05-01T09:30:00+02:00"),
+            ("keyword", "two-sided-long-edge"),
+            ("no-value", "no-value"),
+        ] {
+            let value = IppValue::parse(syntax, text).unwrap();
+            assert_eq!(value.syntax(), syntax);
+            assert_eq!(value.to_string(), text);
+        }
+        assert_eq!(
+            IppValue::parse("dateTime", "2024-05-01T09:30:00Z")
+                .unwrap()
+                .to_string(),
+            "2024-05-01T09:30:00+00:00"
+        );
+        assert!(IppValue::parse("integer", "many").is_err());
+        assert!(IppValue::parse("resolution", "600").is_err());
+        assert!(IppValue::parse("collection", "{}").is_err());
+
+        let media = IppValue::Collection(vec![
+            IppAttribute::new(
+                "media-size",
+                IppValue::Collection(vec![
+                    IppAttribute::new("x-dimension", IppValue::Integer(21000)),
+                    IppAttribute::new("y-dimension", IppValue::Integer(29700)),
+                ]),
+            ),
+            IppAttribute::new("media-source", IppValue::Keyword("tray-1".to_string())),
+        ]);
+        assert_eq!(
+            media.to_string(),
+            "{media-size={x-dimension=21000 y-dimension=29700} media-source=tray-1}"
+        );
+
+        assert_eq!(operation_code("get-jobs"), Some(OP_GET_JOBS));
+        assert_eq!(operation_code("0x003C"), Some(0x003C));
+        assert_eq!(operation_code("11"), Some(OP_GET_PRINTER_ATTRIBUTES));
+        assert_eq!(operation_code("Get-Everything"), None);
+        assert_eq!(group_tag("Job"), Some(TAG_JOB_ATTRIBUTES));
+        assert_eq!(group_name(TAG_PRINTER_ATTRIBUTES), "printer");
+        assert_eq!(group_name(0x0f), "0x0f");
+    }
+
+    #[test]
+    fn test_truncated_response() {
+        assert!(IppResponse::decode(&[1, 1, 0]).is_err());
+    }
+
+    #[test]
+    fn test_decode_chunked() {
+        let body = b"4\r\nabcd\r\n3;ext=1\r\nefg\r\n0\r\n\r\n";
+        assert_eq!(decode_chunked(body).unwrap(), b"abcdefg");
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_basic_authentication() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let uri = format!("ipp://{}/ipp/print", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let mut authorizations = Vec::new();
+            for _ in 0..2 {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut raw = Vec::new();
+                let mut chunk = [0u8; 1024];
+                while !raw.windows(4).any(|w| w == b"\r\n\r\n") {
+                    let read = stream.read(&mut chunk).unwrap();
+                    raw.extend_from_slice(&chunk[..read]);
+                }
+                let head = String::from_utf8_lossy(&raw).to_string();
+                let length: usize = head
+                    .lines()
+                    .find_map(|line| line.strip_prefix("Content-Length: "))
+                    .map_or(0, |length| length.trim().parse().unwrap());
+                while raw.len() < head.find("\r\n\r\n").unwrap() + 4 + length {
+                    let read = stream.read(&mut chunk).unwrap();
+                    raw.extend_from_slice(&chunk[..read]);
+                }
+                let authorization = head
+                    .lines()
+                    .find_map(|line| line.strip_prefix("Authorization: "))
+                    .map(str::to_string);
+                let response = match authorization {
+                    None => b"HTTP/1.1 401 Unauthorized\r\nWWW-Authenticate: Basic realm=\"CUPS\"\r\nContent-Length: 0\r\n\r\n".to_vec(),
+                    Some(_) => {
+                        let body = IppResponse::new(STATUS_OK, 1).encode();
+                        let mut response = format!(
+                            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n",
+                            body.len()
+                        )
+                        .into_bytes();
+                        response.extend_from_slice(&body);
+                        response
+                    }
+                };
+                stream.write_all(&response).unwrap();
+                authorizations.push(authorization);
+            }
+            authorizations
+        });
+
+        let credentials = crate::credentials::Credentials::Basic {
+            username: "alice".to_string(),
+            password: "s3cret".to_string(),
+        };
+        crate::credentials::set(Some(&uri), Some(credentials)).unwrap();
+        let request = IppRequest::new(OP_GET_PRINTER_ATTRIBUTES, &uri);
+        let response = send_request(&uri, &request, Duration::from_secs(5));
+        crate::credentials::set(Some(&uri), None).unwrap();
+
+        assert!(response.unwrap().is_success());
+        assert_eq!(
+            server.join().unwrap(),
+            [None, Some("Basic YWxpY2U6czNjcmV0".to_string())]
+        );
+    }
+}
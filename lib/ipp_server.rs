@@ -0,0 +1,879 @@
+//! Minimal IPP print server
+//!
+//! Listens for IPP/1.1 requests over HTTP and routes Print-Job requests
+//! through the library's job queue to a local printer, so a Node process can
+//! act as a print gateway for devices on the network. Each server exposes a
+//! single queue at one path and supports Print-Job, Validate-Job,
+//! Get-Printer-Attributes and Get-Job-Attributes, the last only for jobs
+//! submitted through the server.
+//!
+//! Job template attributes of a request (copies, sides, media, ...) become
+//! raw job properties, the same as CUPS options; multiple values are joined
+//! with commas and ranges are written as `lower-upper`. Documents may be
+//! sent gzip or deflate compressed, as named by the `compression` operation
+//! attribute, and are decompressed up to the document size limit. There is no
+//! authentication or TLS, so servers listen on localhost unless told
+//! otherwise and should only be exposed to trusted networks.
+
+use crate::compression::{self, Compression};
+use crate::core::{
+    JobId, JobQuery, PrintError, PrinterCore, PrinterJob, PrinterJobOptions, PrinterJobState,
+};
+use crate::http_server::{self, Incoming, RequestError};
+use crate::ipp::{
+    IppAttribute, IppRequest, IppResponse, IppValue, OP_GET_JOB_ATTRIBUTES,
+    OP_GET_PRINTER_ATTRIBUTES, OP_PRINT_JOB, OP_VALIDATE_JOB, STATUS_ATTRIBUTES_NOT_SUPPORTED,
+    STATUS_BAD_REQUEST, STATUS_BUSY, STATUS_COMPRESSION_ERROR, STATUS_COMPRESSION_NOT_SUPPORTED,
+    STATUS_FORBIDDEN, STATUS_INTERNAL_ERROR, STATUS_NOT_ACCEPTING_JOBS, STATUS_NOT_FOUND,
+    STATUS_OK, STATUS_OPERATION_NOT_SUPPORTED, STATUS_SERVICE_UNAVAILABLE, TAG_JOB_ATTRIBUTES,
+    TAG_OPERATION_ATTRIBUTES, TAG_PRINTER_ATTRIBUTES,
+};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::{ErrorKind, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub type ServerId = u64;
+
+pub const DEFAULT_HOST: &str = "127.0.0.1";
+pub const DEFAULT_PORT: u16 = 8631;
+pub const DEFAULT_PATH: &str = "/ipp/print";
+/// Largest document accepted, unless configured otherwise
+pub const DEFAULT_MAX_DOCUMENT_BYTES: usize = 64 * 1024 * 1024;
+
+/// How often the listener checks whether the server was stopped
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Document formats advertised in document-format-supported
+const DOCUMENT_FORMATS: [&str; 6] = [
+    "application/octet-stream",
+    "application/pdf",
+    "application/postscript",
+    "image/jpeg",
+    "image/png",
+    "text/plain",
+];
+
+static NEXT_SERVER_ID: AtomicU64 = AtomicU64::new(1);
+
+lazy_static::lazy_static! {
+    static ref SERVERS: Mutex<HashMap<ServerId, Arc<IppServer>>> = Mutex::new(HashMap::new());
+}
+
+/// Where a server listens and which printer it prints on
+#[derive(Clone, Debug, PartialEq)]
+pub struct IppServerConfig {
+    /// Local printer or alias jobs are printed on
+    pub printer_name: String,
+    pub host: String,
+    /// Port to listen on (0 = any free port)
+    pub port: u16,
+    /// Path of the queue, e.g. `/ipp/print`
+    pub path: String,
+    /// printer-name advertised to clients (None = the printer's name)
+    pub queue_name: Option<String>,
+    pub max_document_bytes: usize,
+}
+
+impl IppServerConfig {
+    pub fn new(printer_name: &str) -> Self {
+        IppServerConfig {
+            printer_name: printer_name.to_string(),
+            host: DEFAULT_HOST.to_string(),
+            port: DEFAULT_PORT,
+            path: DEFAULT_PATH.to_string(),
+            queue_name: None,
+            max_document_bytes: DEFAULT_MAX_DOCUMENT_BYTES,
+        }
+    }
+}
+
+/// A running IPP server
+#[derive(Debug)]
+pub struct IppServer {
+    pub id: ServerId,
+    pub config: IppServerConfig,
+    /// Address the server is listening on, with the port chosen if 0 was
+    /// requested
+    pub local_addr: SocketAddr,
+    started_at: Instant,
+    stopped: AtomicBool,
+    /// Jobs submitted through this server. IPP job IDs are 32-bit, so a
+    /// job's IPP job-id is its position here plus one rather than its ID.
+    jobs: Mutex<Vec<JobId>>,
+}
+
+impl IppServer {
+    /// URI clients print to
+    pub fn uri(&self) -> String {
+        format!("ipp://{}{}", self.local_addr, self.config.path)
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.stopped.load(Ordering::Relaxed)
+    }
+
+    fn queue_name(&self) -> &str {
+        self.config
+            .queue_name
+            .as_deref()
+            .unwrap_or(&self.config.printer_name)
+    }
+
+    /// Job submitted through this server with the given IPP job-id
+    fn local_job_id(&self, ipp_job_id: i32) -> Option<JobId> {
+        let index = usize::try_from(ipp_job_id).ok()?.checked_sub(1)?;
+        self.jobs.lock().unwrap().get(index).copied()
+    }
+}
+
+/// Start a server and register it
+pub fn start(config: IppServerConfig) -> Result<Arc<IppServer>, String> {
+    if !config.path.starts_with('/') {
+        return Err(format!("Queue path '{}' must start with '/'", config.path));
+    }
+    if !PrinterCore::printer_exists(&crate::aliases::resolve(&config.printer_name)) {
+        return Err(format!("Printer '{}' not found", config.printer_name));
+    }
+
+    let listener = TcpListener::bind((config.host.as_str(), config.port))
+        .and_then(|listener| listener.set_nonblocking(true).map(|_| listener))
+        .map_err(|e| format!("Failed to listen on {}:{}: {}", config.host, config.port, e))?;
+    let local_addr = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to get listening address: {}", e))?;
+
+    let server = Arc::new(IppServer {
+        id: NEXT_SERVER_ID.fetch_add(1, Ordering::Relaxed),
+        config,
+        local_addr,
+        started_at: Instant::now(),
+        stopped: AtomicBool::new(false),
+        jobs: Mutex::new(Vec::new()),
+    });
+    SERVERS
+        .lock()
+        .unwrap()
+        .insert(server.id, Arc::clone(&server));
+    tracing::info!(
+        server = server.id,
+        uri = %server.uri(),
+        printer = %server.config.printer_name,
+        "IPP server started"
+    );
+
+    let accepting = Arc::clone(&server);
+    thread::spawn(move || accept_connections(listener, accepting));
+    Ok(server)
+}
+
+pub fn get(server_id: ServerId) -> Option<Arc<IppServer>> {
+    SERVERS.lock().unwrap().get(&server_id).cloned()
+}
+
+/// All running servers, oldest first
+pub fn list() -> Vec<Arc<IppServer>> {
+    let mut servers: Vec<Arc<IppServer>> = SERVERS.lock().unwrap().values().cloned().collect();
+    servers.sort_by_key(|server| server.id);
+    servers
+}
+
+/// Stop a server and unregister it, returning whether it existed. Requests
+/// already being handled are completed.
+pub fn stop(server_id: ServerId) -> bool {
+    let Some(server) = SERVERS.lock().unwrap().remove(&server_id) else {
+        return false;
+    };
+    server.stopped.store(true, Ordering::Relaxed);
+    tracing::info!(server = server_id, "IPP server stopped");
+    true
+}
+
+fn accept_connections(listener: TcpListener, server: Arc<IppServer>) {
+    while server.is_running() {
+        match listener.accept() {
+            Ok((stream, peer)) => {
+                let server = Arc::clone(&server);
+                thread::spawn(move || handle_connection(&server, stream, peer));
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(ACCEPT_INTERVAL),
+            Err(e) => {
+                tracing::warn!(server = server.id, "Failed to accept connection: {}", e);
+                thread::sleep(ACCEPT_INTERVAL);
+            }
+        }
+    }
+}
+
+/// Answer one request and close the connection
+fn handle_connection(server: &IppServer, mut stream: TcpStream, peer: SocketAddr) {
+    if let Err(e) = http_server::prepare_stream(&stream) {
+        tracing::warn!(server = server.id, "Failed to configure connection: {}", e);
+        return;
+    }
+
+    let text = [("Content-Type", "text/plain")];
+    let request = Incoming::new(&mut stream).read_request(server.config.max_document_bytes);
+    let response = match request {
+        Ok(request) if request.method != "POST" => {
+            http_server::response("405 Method Not Allowed", &text, b"")
+        }
+        Ok(request) if request.path() != server.config.path => {
+            http_server::response("404 Not Found", &text, b"")
+        }
+        Ok(request) => match IppRequest::decode(&request.body) {
+            Ok(request) => {
+                let response = handle_request(server, &request);
+                http_server::response(
+                    "200 OK",
+                    &[("Content-Type", "application/ipp")],
+                    &response.encode(),
+                )
+            }
+            Err(e) => {
+                tracing::debug!(server = server.id, %peer, "Invalid IPP request: {}", e);
+                http_server::response("400 Bad Request", &text, e.as_bytes())
+            }
+        },
+        Err(RequestError::TooLarge) => http_server::response("413 Payload Too Large", &text, b""),
+        Err(RequestError::Malformed(e)) => {
+            http_server::response("400 Bad Request", &text, e.as_bytes())
+        }
+        Err(RequestError::Io(e)) => {
+            tracing::debug!(server = server.id, %peer, "Failed to read request: {}", e);
+            return;
+        }
+    };
+
+    if let Err(e) = stream.write_all(&response) {
+        tracing::debug!(server = server.id, %peer, "Failed to send response: {}", e);
+    }
+}
+
+/// An IPP error status with its status-message
+type IppError = (u16, String);
+
+fn handle_request(server: &IppServer, request: &IppRequest) -> IppResponse {
+    let mut response = IppResponse::new(STATUS_OK, request.request_id);
+    let result = match request.operation {
+        OP_PRINT_JOB => print_job(server, request, &mut response),
+        OP_VALIDATE_JOB => document_compression(request).map(|_| ()),
+        OP_GET_PRINTER_ATTRIBUTES => {
+            printer_attributes(server, request, &mut response);
+            Ok(())
+        }
+        OP_GET_JOB_ATTRIBUTES => job_attributes(server, request, &mut response),
+        operation => Err((
+            STATUS_OPERATION_NOT_SUPPORTED,
+            format!("Operation 0x{:04x} is not supported", operation),
+        )),
+    };
+
+    if let Err((status, message)) = result {
+        response.status_code = status;
+        response.add_attribute(
+            TAG_OPERATION_ATTRIBUTES,
+            IppAttribute::new("status-message", IppValue::Text(message)),
+        );
+    }
+    response
+}
+
+/// URI the client addressed the printer by, falling back to the server's
+fn printer_uri(server: &IppServer, request: &IppRequest) -> String {
+    request
+        .attribute(TAG_OPERATION_ATTRIBUTES, "printer-uri")
+        .and_then(IppAttribute::value)
+        .and_then(IppValue::as_str)
+        .map(str::to_string)
+        .unwrap_or_else(|| server.uri())
+}
+
+fn operation_string(request: &IppRequest, name: &str) -> Option<String> {
+    request
+        .attribute(TAG_OPERATION_ATTRIBUTES, name)
+        .and_then(IppAttribute::value)
+        .and_then(IppValue::as_str)
+        .map(str::to_string)
+}
+
+/// A job template value as a raw job property, if it has a string form
+fn raw_value(value: &IppValue) -> Option<String> {
+    match value {
+        IppValue::Integer(v) | IppValue::Enum(v) => Some(v.to_string()),
+        IppValue::Boolean(v) => Some(v.to_string()),
+        IppValue::RangeOfInteger { lower, upper } => Some(format!("{}-{}", lower, upper)),
+        IppValue::Resolution {
+            cross_feed, feed, ..
+        } if cross_feed == feed => Some(format!("{}dpi", cross_feed)),
+        IppValue::Resolution {
+            cross_feed, feed, ..
+        } => Some(format!("{}x{}dpi", cross_feed, feed)),
+        other => other.as_str().map(str::to_string),
+    }
+}
+
+/// Job options for a Print-Job request. Attributes are only ever raw
+/// properties, so clients can't set the library's own job options.
+fn job_options(request: &IppRequest) -> PrinterJobOptions {
+    let mut options = PrinterJobOptions::none();
+    options.name = operation_string(request, "job-name");
+    options.requesting_user = operation_string(request, "requesting-user-name");
+    if let Some(format) = operation_string(request, "document-format") {
+        if format != "application/octet-stream" {
+            options
+                .raw_properties
+                .insert("document-format".to_string(), format);
+        }
+    }
+
+    let attributes = request
+        .groups
+        .iter()
+        .filter(|group| group.tag == TAG_JOB_ATTRIBUTES)
+        .flat_map(|group| group.attributes.iter());
+    for attribute in attributes {
+        let values: Option<Vec<String>> = attribute.values.iter().map(raw_value).collect();
+        match values {
+            Some(values) if !values.is_empty() => {
+                options
+                    .raw_properties
+                    .insert(attribute.name.clone(), values.join(","));
+            }
+            _ => tracing::debug!(
+                attribute = %attribute.name,
+                "Ignoring job attribute without a string form"
+            ),
+        }
+    }
+    options
+}
+
+fn print_error_status(error: PrintError) -> IppError {
+    match error {
+        PrintError::QueueFull => (STATUS_BUSY, "The print queue is full".to_string()),
+        PrintError::UnsupportedOption(reason) => (STATUS_ATTRIBUTES_NOT_SUPPORTED, reason),
+        PrintError::PolicyViolation(violation) => (STATUS_FORBIDDEN, violation.to_string()),
+        PrintError::SpoolQuotaExceeded => (STATUS_BUSY, "Spool quota exceeded".to_string()),
+        PrintError::ShuttingDown => (
+            STATUS_NOT_ACCEPTING_JOBS,
+            "The print server is shutting down".to_string(),
+        ),
+        PrintError::PrinterNotFound | PrintError::NoPrinterAvailable => (
+            STATUS_SERVICE_UNAVAILABLE,
+            "The printer is not available".to_string(),
+        ),
+        PrintError::InvalidParams | PrintError::InvalidFilePath | PrintError::InvalidDocument => {
+            (STATUS_BAD_REQUEST, "Invalid document".to_string())
+        }
+        other => (
+            STATUS_INTERNAL_ERROR,
+            format!("Failed to print the job: {:?}", other),
+        ),
+    }
+}
+
+/// Coding of the request's document, from its `compression` attribute
+fn document_compression(request: &IppRequest) -> Result<Compression, IppError> {
+    let Some(keyword) = operation_string(request, "compression") else {
+        return Ok(Compression::None);
+    };
+    Compression::from_keyword(&keyword)
+        .filter(|coding| Compression::supported().contains(coding))
+        .ok_or_else(|| {
+            (
+                STATUS_COMPRESSION_NOT_SUPPORTED,
+                format!("Compression '{}' is not supported", keyword),
+            )
+        })
+}
+
+fn print_job(
+    server: &IppServer,
+    request: &IppRequest,
+    response: &mut IppResponse,
+) -> Result<(), IppError> {
+    if request.data.is_empty() {
+        return Err((
+            STATUS_BAD_REQUEST,
+            "The request has no document".to_string(),
+        ));
+    }
+    let data = match document_compression(request)? {
+        Compression::None => Cow::Borrowed(&request.data[..]),
+        coding => Cow::Owned(
+            compression::decompress(&request.data, coding, server.config.max_document_bytes)
+                .map_err(|e| (STATUS_COMPRESSION_ERROR, e))?,
+        ),
+    };
+    let job_id = PrinterCore::print_bytes(
+        &server.config.printer_name,
+        &data,
+        Some(job_options(request)),
+    )
+    .map_err(print_error_status)?;
+    let ipp_job_id = {
+        let mut jobs = server.jobs.lock().unwrap();
+        jobs.push(job_id);
+        i32::try_from(jobs.len()).unwrap_or(i32::MAX)
+    };
+    tracing::info!(
+        server = server.id,
+        job_id,
+        size_bytes = data.len(),
+        "Accepted IPP print job"
+    );
+
+    let job = PrinterCore::get_job_status(job_id)
+        .ok_or_else(|| (STATUS_INTERNAL_ERROR, "The job disappeared".to_string()))?;
+    let printer_uri = printer_uri(server, request);
+    for attribute in job_description(&job, ipp_job_id, &printer_uri)
+        .into_iter()
+        .filter(|attribute| {
+            matches!(
+                attribute.name.as_str(),
+                "job-id" | "job-uri" | "job-state" | "job-state-reasons"
+            )
+        })
+    {
+        response.add_attribute(TAG_JOB_ATTRIBUTES, attribute);
+    }
+    Ok(())
+}
+
+/// IPP job-state enum and job-state-reasons keyword
+fn job_state(job: &PrinterJob) -> (i32, &'static str) {
+    match job.state {
+        PrinterJobState::PENDING | PrinterJobState::UNKNOWN => (3, "none"),
+        PrinterJobState::HELD_FOR_APPROVAL => (4, "job-hold-until-specified"),
+        PrinterJobState::PROCESSING => (5, "job-printing"),
+        PrinterJobState::PAUSED => (6, "job-stopped"),
+        PrinterJobState::FAILED => (8, "aborted-by-system"),
+        PrinterJobState::CANCELLED if job.error_message.is_some() => (8, "aborted-by-system"),
+        PrinterJobState::CANCELLED => (7, "job-canceled-by-user"),
+        PrinterJobState::COMPLETED => (9, "job-completed-successfully"),
+    }
+}
+
+fn job_description(job: &PrinterJob, ipp_job_id: i32, printer_uri: &str) -> Vec<IppAttribute> {
+    let (state, reason) = job_state(job);
+    let mut attributes = vec![
+        IppAttribute::new("job-id", IppValue::Integer(ipp_job_id)),
+        IppAttribute::new(
+            "job-uri",
+            IppValue::Uri(format!("{}/jobs/{}", printer_uri, ipp_job_id)),
+        ),
+        IppAttribute::new("job-printer-uri", IppValue::Uri(printer_uri.to_string())),
+        IppAttribute::new("job-name", IppValue::Name(job.name.clone())),
+        IppAttribute::new("job-state", IppValue::Enum(state)),
+        IppAttribute::new("job-state-reasons", IppValue::Keyword(reason.to_string())),
+        IppAttribute::new(
+            "job-k-octets",
+            IppValue::Integer(i32::try_from(job.size_bytes.div_ceil(1024)).unwrap_or(i32::MAX)),
+        ),
+    ];
+    if let Some(owner) = &job.owner {
+        attributes.push(IppAttribute::new(
+            "job-originating-user-name",
+            IppValue::Name(owner.clone()),
+        ));
+    }
+    if let Some(pages) = job
+        .pages
+        .filter(|_| job.state == PrinterJobState::COMPLETED)
+    {
+        attributes.push(IppAttribute::new(
+            "job-impressions-completed",
+            IppValue::Integer(pages as i32),
+        ));
+    }
+    if let Some(message) = &job.error_message {
+        attributes.push(IppAttribute::new(
+            "job-state-message",
+            IppValue::Text(message.clone()),
+        ));
+    }
+    attributes
+}
+
+/// Keep only the attributes the request asked for, if it asked
+fn filter_requested(request: &IppRequest, attributes: &mut Vec<IppAttribute>) {
+    let Some(requested) = request.attribute(TAG_OPERATION_ATTRIBUTES, "requested-attributes")
+    else {
+        return;
+    };
+    let requested: Vec<&str> = requested
+        .values
+        .iter()
+        .filter_map(IppValue::as_str)
+        .collect();
+    if requested.contains(&"all") {
+        return;
+    }
+    let groups = [
+        "printer-description",
+        "job-description",
+        "job-template",
+        "job-status",
+    ];
+    if requested.iter().any(|name| groups.contains(name)) {
+        // Groups are answered in full
+        return;
+    }
+    attributes.retain(|attribute| requested.contains(&attribute.name.as_str()));
+}
+
+fn printer_attributes(server: &IppServer, request: &IppRequest, response: &mut IppResponse) {
+    let printer_uri = printer_uri(server, request);
+    let queued = PrinterCore::query_jobs(&JobQuery::active(Some(&server.config.printer_name)));
+    let processing = queued
+        .jobs
+        .iter()
+        .any(|job| job.state == PrinterJobState::PROCESSING);
+    let keywords = |values: &[&str]| -> Vec<IppValue> {
+        values
+            .iter()
+            .map(|value| IppValue::Keyword(value.to_string()))
+            .collect()
+    };
+    let multi = |name: &str, values: Vec<IppValue>| IppAttribute {
+        name: name.to_string(),
+        values,
+    };
+
+    let mut attributes = vec![
+        IppAttribute::new("printer-uri-supported", IppValue::Uri(printer_uri)),
+        IppAttribute::new(
+            "uri-security-supported",
+            IppValue::Keyword("none".to_string()),
+        ),
+        IppAttribute::new(
+            "uri-authentication-supported",
+            IppValue::Keyword("none".to_string()),
+        ),
+        IppAttribute::new(
+            "printer-name",
+            IppValue::Name(server.queue_name().to_string()),
+        ),
+        IppAttribute::new(
+            "printer-state",
+            IppValue::Enum(if processing { 4 } else { 3 }),
+        ),
+        IppAttribute::new(
+            "printer-state-reasons",
+            IppValue::Keyword("none".to_string()),
+        ),
+        IppAttribute::new("printer-is-accepting-jobs", IppValue::Boolean(true)),
+        IppAttribute::new("queued-job-count", IppValue::Integer(queued.total as i32)),
+        IppAttribute::new(
+            "printer-up-time",
+            IppValue::Integer(server.started_at.elapsed().as_secs().max(1) as i32),
+        ),
+        multi("ipp-versions-supported", keywords(&["1.0", "1.1"])),
+        multi(
+            "operations-supported",
+            [
+                OP_PRINT_JOB,
+                OP_VALIDATE_JOB,
+                OP_GET_JOB_ATTRIBUTES,
+                OP_GET_PRINTER_ATTRIBUTES,
+            ]
+            .iter()
+            .map(|operation| IppValue::Enum(*operation as i32))
+            .collect(),
+        ),
+        IppAttribute::new("charset-configured", IppValue::Charset("utf-8".to_string())),
+        IppAttribute::new("charset-supported", IppValue::Charset("utf-8".to_string())),
+        IppAttribute::new(
+            "natural-language-configured",
+            IppValue::NaturalLanguage("en".to_string()),
+        ),
+        IppAttribute::new(
+            "generated-natural-language-supported",
+            IppValue::NaturalLanguage("en".to_string()),
+        ),
+        IppAttribute::new(
+            "document-format-default",
+            IppValue::MimeMediaType(DOCUMENT_FORMATS[0].to_string()),
+        ),
+        multi(
+            "document-format-supported",
+            DOCUMENT_FORMATS
+                .iter()
+                .map(|format| IppValue::MimeMediaType(format.to_string()))
+                .collect(),
+        ),
+        IppAttribute::new(
+            "pdl-override-supported",
+            IppValue::Keyword("not-attempted".to_string()),
+        ),
+        multi(
+            "compression-supported",
+            Compression::supported()
+                .iter()
+                .map(|coding| IppValue::Keyword(coding.keyword().to_string()))
+                .collect(),
+        ),
+    ];
+    filter_requested(request, &mut attributes);
+    for attribute in attributes {
+        response.add_attribute(TAG_PRINTER_ATTRIBUTES, attribute);
+    }
+}
+
+fn job_attributes(
+    server: &IppServer,
+    request: &IppRequest,
+    response: &mut IppResponse,
+) -> Result<(), IppError> {
+    let ipp_job_id = request
+        .attribute(TAG_OPERATION_ATTRIBUTES, "job-id")
+        .and_then(IppAttribute::value)
+        .and_then(IppValue::as_i32)
+        .or_else(|| {
+            operation_string(request, "job-uri")
+                .and_then(|uri| uri.rsplit('/').next().and_then(|id| id.parse().ok()))
+        })
+        .ok_or_else(|| (STATUS_BAD_REQUEST, "Missing job-id".to_string()))?;
+
+    let job = server
+        .local_job_id(ipp_job_id)
+        .and_then(PrinterCore::get_job_status)
+        .ok_or_else(|| (STATUS_NOT_FOUND, format!("Job {} not found", ipp_job_id)))?;
+
+    let mut attributes = job_description(&job, ipp_job_id, &printer_uri(server, request));
+    filter_requested(request, &mut attributes);
+    for attribute in attributes {
+        response.add_attribute(TAG_JOB_ATTRIBUTES, attribute);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ShutdownMode;
+    use serial_test::serial;
+    use std::io::Read;
+
+    /// Send an IPP request to the server and decode its response
+    fn send(server: &IppServer, request: &IppRequest, chunked: bool) -> IppResponse {
+        let mut stream = TcpStream::connect(server.local_addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let body = request.encode();
+        let framing = if chunked {
+            "Transfer-Encoding: chunked".to_string()
+        } else {
+            format!("Content-Length: {}", body.len())
+        };
+        write!(
+            stream,
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/ipp\r\n{}\r\n\r\n",
+            server.config.path, server.local_addr, framing
+        )
+        .unwrap();
+        if chunked {
+            for chunk in body.chunks(7) {
+                write!(stream, "{:x}\r\n", chunk.len()).unwrap();
+                stream.write_all(chunk).unwrap();
+                stream.write_all(b"\r\n").unwrap();
+            }
+            stream.write_all(b"0\r\n\r\n").unwrap();
+        } else {
+            stream.write_all(&body).unwrap();
+        }
+
+        let mut raw = Vec::new();
+        stream.read_to_end(&mut raw).unwrap();
+        let header_end = raw.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+        assert!(raw.starts_with(b"HTTP/1.1 200 OK"));
+        IppResponse::decode(&raw[header_end + 4..]).unwrap()
+    }
+
+    #[test]
+    #[serial]
+    fn test_ipp_server_print_job() {
+        std::env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let mut config = IppServerConfig::new("Simulated Printer");
+        config.port = 0;
+        config.queue_name = Some("Gateway".to_string());
+        let server = start(config).unwrap();
+        let uri = server.uri();
+
+        let mut request = IppRequest::new(OP_GET_PRINTER_ATTRIBUTES, &uri);
+        request.add_attribute(
+            TAG_OPERATION_ATTRIBUTES,
+            IppAttribute {
+                name: "requested-attributes".to_string(),
+                values: vec![
+                    IppValue::Keyword("printer-name".to_string()),
+                    IppValue::Keyword("printer-state".to_string()),
+                ],
+            },
+        );
+        let response = send(&server, &request, false);
+        assert!(response.is_success());
+        assert_eq!(
+            response
+                .attribute(TAG_PRINTER_ATTRIBUTES, "printer-name")
+                .and_then(IppAttribute::value)
+                .and_then(IppValue::as_str),
+            Some("Gateway")
+        );
+        assert!(response
+            .attribute(TAG_PRINTER_ATTRIBUTES, "document-format-supported")
+            .is_none());
+
+        let mut request = IppRequest::new(OP_PRINT_JOB, &uri);
+        request.add_attribute(
+            TAG_OPERATION_ATTRIBUTES,
+            IppAttribute::new("job-name", IppValue::Name("Network job".to_string())),
+        );
+        request.add_attribute(
+            TAG_OPERATION_ATTRIBUTES,
+            IppAttribute::new("requesting-user-name", IppValue::Name("alice".to_string())),
+        );
+        request.add_attribute(
+            TAG_JOB_ATTRIBUTES,
+            IppAttribute::new("copies", IppValue::Integer(2)),
+        );
+        request.add_attribute(
+            TAG_JOB_ATTRIBUTES,
+            IppAttribute::new(
+                "page-ranges",
+                IppValue::RangeOfInteger { lower: 1, upper: 3 },
+            ),
+        );
+        request.data = b"%!PS-Adobe-3.0".to_vec();
+        let response = send(&server, &request, true);
+        assert!(response.is_success(), "{:?}", response);
+        let ipp_job_id = response
+            .attribute(TAG_JOB_ATTRIBUTES, "job-id")
+            .and_then(IppAttribute::value)
+            .and_then(IppValue::as_i32)
+            .unwrap();
+        assert_eq!(ipp_job_id, 1);
+
+        let job = PrinterCore::get_job_status(server.local_job_id(ipp_job_id).unwrap()).unwrap();
+        assert_eq!(job.name, "Network job");
+        assert_eq!(job.owner.as_deref(), Some("alice"));
+        assert_eq!(job.job_options.raw_properties["copies"], "2");
+        assert_eq!(job.job_options.raw_properties["page-ranges"], "1-3");
+
+        let mut request = IppRequest::new(OP_GET_JOB_ATTRIBUTES, &uri);
+        request.add_attribute(
+            TAG_OPERATION_ATTRIBUTES,
+            IppAttribute::new("job-id", IppValue::Integer(ipp_job_id)),
+        );
+        let response = send(&server, &request, false);
+        assert!(response.is_success());
+        assert!(response
+            .attribute(TAG_JOB_ATTRIBUTES, "job-state")
+            .is_some());
+
+        // Jobs not submitted through the server aren't visible to it
+        let other = PrinterCore::print_bytes("Simulated Printer", b"local", None).unwrap();
+        assert!(!server.jobs.lock().unwrap().contains(&other));
+        let mut request = IppRequest::new(OP_GET_JOB_ATTRIBUTES, &uri);
+        request.add_attribute(
+            TAG_OPERATION_ATTRIBUTES,
+            IppAttribute::new("job-id", IppValue::Integer(2)),
+        );
+        assert_eq!(send(&server, &request, false).status_code, STATUS_NOT_FOUND);
+
+        let response = send(&server, &IppRequest::new(0x0010, &uri), false);
+        assert_eq!(response.status_code, STATUS_OPERATION_NOT_SUPPORTED);
+
+        assert!(stop(server.id));
+        assert!(!stop(server.id));
+        assert!(!server.is_running());
+
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_ipp_server_rejects_unknown_printer() {
+        std::env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let mut config = IppServerConfig::new("Missing Printer");
+        config.port = 0;
+        assert!(start(config).is_err());
+
+        let mut config = IppServerConfig::new("Simulated Printer");
+        config.path = "ipp/print".to_string();
+        assert!(start(config).is_err());
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    #[serial]
+    fn test_ipp_server_compressed_job() {
+        use crate::compression::CompressionMode;
+        use crate::remote::{self, RemotePrinter};
+
+        std::env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let mut config = IppServerConfig::new("Simulated Printer");
+        config.port = 0;
+        config.max_document_bytes = 64 * 1024;
+        let server = start(config).unwrap();
+        let uri = server.uri();
+        let raster = [0u8, 0, 0, 255].repeat(4 * 1024);
+
+        let mut request = IppRequest::new(OP_PRINT_JOB, &uri);
+        request.add_attribute(
+            TAG_OPERATION_ATTRIBUTES,
+            IppAttribute::new("compression", IppValue::Keyword("compress".to_string())),
+        );
+        request.data = raster.clone();
+        assert_eq!(
+            send(&server, &request, false).status_code,
+            STATUS_COMPRESSION_NOT_SUPPORTED
+        );
+
+        let mut request = IppRequest::new(OP_PRINT_JOB, &uri);
+        request.add_attribute(
+            TAG_OPERATION_ATTRIBUTES,
+            IppAttribute::new("compression", IppValue::Keyword("gzip".to_string())),
+        );
+        request.data = b"not gzip".to_vec();
+        assert_eq!(
+            send(&server, &request, false).status_code,
+            STATUS_COMPRESSION_ERROR
+        );
+        // Decompressing beyond the document size limit is refused
+        request.data = compression::compress(&[0u8; 128 * 1024], Compression::Gzip).unwrap();
+        assert_eq!(
+            send(&server, &request, false).status_code,
+            STATUS_COMPRESSION_ERROR
+        );
+
+        // Remote printers pick a coding the server supports
+        let mut remote = RemotePrinter::new("Compressed Remote", &uri);
+        remote.compression = CompressionMode::Auto;
+        remote.poll_interval = Duration::from_millis(100);
+        remote::define(remote).unwrap();
+        PrinterCore::print_bytes("Compressed Remote", &raster, None).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let job_id = loop {
+            if let Some(job_id) = server.jobs.lock().unwrap().iter().next() {
+                break *job_id;
+            }
+            assert!(Instant::now() < deadline, "remote job never arrived");
+            thread::sleep(Duration::from_millis(50));
+        };
+        let job = PrinterCore::get_job_status(job_id).unwrap();
+        assert_eq!(job.size_bytes, raster.len() as u64);
+
+        assert!(remote::remove("Compressed Remote"));
+        assert!(stop(server.id));
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
+    }
+}
@@ -0,0 +1,456 @@
+//! Sharded in-memory job store
+//!
+//! Jobs are spread over independently locked shards by a hash of their ID,
+//! so status polls (shared read locks) and updates to different jobs don't
+//! serialize on one global mutex. IDs are hashed because their low bits
+//! hold the sequence number within a second, which is almost always 0 at
+//! normal rates. Operations spanning all jobs lock one shard at a time, so
+//! they see a consistent view of each shard rather than a global snapshot.
+//!
+//! Each job also has a bounded log of what happened to it, kept next to the
+//! job so it leaves the history along with it.
+
+use crate::core::{JobId, PrinterJob};
+use crate::logging::LogLevel;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, SystemTime};
+
+/// Default number of shards
+const DEFAULT_SHARDS: usize = 16;
+
+/// Entries kept in a job's log, oldest dropped first
+pub const MAX_JOB_LOG_ENTRIES: usize = 100;
+
+/// A message in a job's log
+#[derive(Clone, Debug, PartialEq)]
+pub struct JobLogEntry {
+    pub timestamp: SystemTime,
+    pub level: LogLevel,
+    pub message: String,
+}
+
+struct Entry {
+    job: PrinterJob,
+    /// Last lookup as milliseconds since the Unix epoch (0 = never), kept
+    /// outside the job so lookups only need a read lock
+    last_accessed_ms: AtomicU64,
+    /// Kept outside the job so copies of it don't copy the log, and so
+    /// appending only needs a read lock
+    log: Mutex<VecDeque<JobLogEntry>>,
+}
+
+impl Entry {
+    fn new(job: PrinterJob) -> Self {
+        let last_accessed_ms = job.last_accessed.map_or(0, to_millis);
+        Entry {
+            job,
+            last_accessed_ms: AtomicU64::new(last_accessed_ms),
+            log: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    fn touch(&self) {
+        self.last_accessed_ms
+            .store(to_millis(SystemTime::now()), Ordering::Relaxed);
+    }
+
+    fn last_accessed(&self) -> Option<SystemTime> {
+        match self.last_accessed_ms.load(Ordering::Relaxed) {
+            0 => None,
+            ms => Some(SystemTime::UNIX_EPOCH + Duration::from_millis(ms)),
+        }
+    }
+
+    /// Copy of the job with its access time filled in
+    fn snapshot(&self) -> PrinterJob {
+        PrinterJob {
+            last_accessed: self.last_accessed(),
+            ..self.job.clone()
+        }
+    }
+
+    /// Mutable job with its access time filled in (requires the write lock)
+    fn job_mut(&mut self) -> &mut PrinterJob {
+        self.job.last_accessed = self.last_accessed();
+        &mut self.job
+    }
+}
+
+fn to_millis(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// Concurrent job store
+pub struct JobStore {
+    shards: Vec<RwLock<HashMap<JobId, Entry>>>,
+}
+
+impl Default for JobStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JobStore {
+    pub fn new() -> Self {
+        Self::with_shards(DEFAULT_SHARDS)
+    }
+
+    pub fn with_shards(shards: usize) -> Self {
+        JobStore {
+            shards: (0..shards.max(1))
+                .map(|_| RwLock::new(HashMap::new()))
+                .collect(),
+        }
+    }
+
+    fn shard(&self, job_id: JobId) -> &RwLock<HashMap<JobId, Entry>> {
+        &self.shards[(mix(job_id) % self.shards.len() as u64) as usize]
+    }
+
+    /// Add or replace a job
+    pub fn insert(&self, job: PrinterJob) {
+        self.shard(job.id)
+            .write()
+            .unwrap()
+            .insert(job.id, Entry::new(job));
+    }
+
+    /// Look up a job, recording the access for history eviction
+    pub fn get(&self, job_id: JobId) -> Option<PrinterJob> {
+        let shard = self.shard(job_id).read().unwrap();
+        shard.get(&job_id).map(|entry| {
+            entry.touch();
+            entry.snapshot()
+        })
+    }
+
+    /// Read a job in place without recording an access
+    pub fn inspect<R>(&self, job_id: JobId, f: impl FnOnce(&PrinterJob) -> R) -> Option<R> {
+        let shard = self.shard(job_id).read().unwrap();
+        shard.get(&job_id).map(|entry| f(&entry.job))
+    }
+
+    /// Modify a job in place, returning the closure's result if it exists
+    pub fn update<R>(&self, job_id: JobId, f: impl FnOnce(&mut PrinterJob) -> R) -> Option<R> {
+        let mut shard = self.shard(job_id).write().unwrap();
+        shard.get_mut(&job_id).map(|entry| f(entry.job_mut()))
+    }
+
+    /// Modify every job
+    pub fn update_all(&self, mut f: impl FnMut(&mut PrinterJob)) {
+        for shard in &self.shards {
+            for entry in shard.write().unwrap().values_mut() {
+                f(entry.job_mut());
+            }
+        }
+    }
+
+    /// Append a message to a job's log, returning false if the job isn't
+    /// tracked
+    pub fn log(&self, job_id: JobId, level: LogLevel, message: impl Into<String>) -> bool {
+        let shard = self.shard(job_id).read().unwrap();
+        let Some(entry) = shard.get(&job_id) else {
+            return false;
+        };
+        let mut log = entry.log.lock().unwrap();
+        if log.len() >= MAX_JOB_LOG_ENTRIES {
+            log.pop_front();
+        }
+        log.push_back(JobLogEntry {
+            timestamp: SystemTime::now(),
+            level,
+            message: message.into(),
+        });
+        true
+    }
+
+    /// A job's log, oldest entry first
+    pub fn log_entries(&self, job_id: JobId) -> Option<Vec<JobLogEntry>> {
+        let shard = self.shard(job_id).read().unwrap();
+        shard
+            .get(&job_id)
+            .map(|entry| entry.log.lock().unwrap().iter().cloned().collect())
+    }
+
+    pub fn remove(&self, job_id: JobId) -> Option<PrinterJob> {
+        let mut shard = self.shard(job_id).write().unwrap();
+        shard.remove(&job_id).map(|entry| entry.snapshot())
+    }
+
+    /// Copies of all jobs matching the predicate
+    pub fn filter(&self, predicate: impl Fn(&PrinterJob) -> bool) -> Vec<PrinterJob> {
+        let mut jobs = Vec::new();
+        for shard in &self.shards {
+            jobs.extend(
+                shard
+                    .read()
+                    .unwrap()
+                    .values()
+                    .filter(|entry| predicate(&entry.job))
+                    .map(Entry::snapshot),
+            );
+        }
+        jobs
+    }
+
+    /// One page of the jobs matching the predicate in order of `key`, and the
+    /// number of jobs that matched. Only the sort keys of matching jobs are
+    /// collected, so just the jobs on the page are copied.
+    pub fn page<K: Ord>(
+        &self,
+        predicate: impl Fn(&PrinterJob) -> bool,
+        key: impl Fn(&PrinterJob) -> K,
+        descending: bool,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> (Vec<PrinterJob>, usize) {
+        // The ID breaks ties, so pages don't overlap
+        let mut keys: Vec<(K, JobId)> = Vec::new();
+        for shard in &self.shards {
+            keys.extend(
+                shard
+                    .read()
+                    .unwrap()
+                    .values()
+                    .filter(|entry| predicate(&entry.job))
+                    .map(|entry| (key(&entry.job), entry.job.id)),
+            );
+        }
+        let total = keys.len();
+        if descending {
+            keys.sort_unstable_by(|a, b| b.cmp(a));
+        } else {
+            keys.sort_unstable();
+        }
+
+        // Jobs removed since their key was read are left out of the page
+        let jobs = keys
+            .into_iter()
+            .skip(offset)
+            .take(limit.unwrap_or(usize::MAX))
+            .filter_map(|(_, job_id)| {
+                let shard = self.shard(job_id).read().unwrap();
+                shard.get(&job_id).map(Entry::snapshot)
+            })
+            .collect();
+        (jobs, total)
+    }
+
+    /// Number of jobs matching the predicate (access times aren't filled in)
+    pub fn count(&self, predicate: impl Fn(&PrinterJob) -> bool) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| {
+                shard
+                    .read()
+                    .unwrap()
+                    .values()
+                    .filter(|entry| predicate(&entry.job))
+                    .count()
+            })
+            .sum()
+    }
+
+    /// Keep only jobs matching the predicate, returning how many were removed
+    pub fn retain(&self, mut predicate: impl FnMut(&PrinterJob) -> bool) -> usize {
+        let mut removed = 0;
+        for shard in &self.shards {
+            let mut shard = shard.write().unwrap();
+            let before = shard.len();
+            shard.retain(|_, entry| predicate(entry.job_mut()));
+            removed += before - shard.len();
+        }
+        removed
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.read().unwrap().len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        for shard in &self.shards {
+            shard.write().unwrap().clear();
+        }
+    }
+}
+
+/// Spread every bit of a job ID over the whole hash (the SplitMix64
+/// finalizer), so IDs differing only in their seconds land in different
+/// shards
+fn mix(job_id: JobId) -> u64 {
+    let mut x = job_id;
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::PrinterJobState;
+    use std::collections::HashSet;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Instant;
+
+    fn job(id: JobId, printer: &str) -> PrinterJob {
+        PrinterJob::new(
+            id,
+            format!("Job {}", id),
+            "application/pdf".to_string(),
+            printer.to_string(),
+        )
+    }
+
+    #[test]
+    fn test_job_store_operations() {
+        let store = JobStore::with_shards(4);
+        for id in 0..10 {
+            store.insert(job(id, if id % 2 == 0 { "Even" } else { "Odd" }));
+        }
+        assert_eq!(store.len(), 10);
+
+        assert_eq!(store.inspect(3, |job| job.last_accessed), Some(None));
+        assert!(store.get(3).unwrap().last_accessed.is_some());
+        assert!(store.filter(|job| job.id == 4)[0].last_accessed.is_none());
+
+        let updated = store.update(4, |job| {
+            job.state = PrinterJobState::COMPLETED;
+            job.id
+        });
+        assert_eq!(updated, Some(4));
+        assert_eq!(store.update(99, |_| ()), None);
+        assert_eq!(
+            store.count(|job| job.state == PrinterJobState::COMPLETED),
+            1
+        );
+
+        let even: HashSet<JobId> = store
+            .filter(|job| job.printer_name == "Even")
+            .iter()
+            .map(|job| job.id)
+            .collect();
+        assert_eq!(even, HashSet::from([0, 2, 4, 6, 8]));
+
+        let (page, total) = store.page(
+            |job| job.printer_name == "Even",
+            |job| job.id,
+            true,
+            1,
+            Some(2),
+        );
+        assert_eq!(total, 5);
+        assert_eq!(page.iter().map(|job| job.id).collect::<Vec<_>>(), [6, 4]);
+        assert!(page[0].last_accessed.is_none());
+        let (page, _) = store.page(|_| true, |job| job.id % 3, false, 0, Some(4));
+        assert_eq!(
+            page.iter().map(|job| job.id).collect::<Vec<_>>(),
+            [0, 3, 6, 9]
+        );
+
+        assert!(store.log(3, LogLevel::Info, "Submitted"));
+        assert!(!store.log(99, LogLevel::Info, "Submitted"));
+        for n in 0..MAX_JOB_LOG_ENTRIES {
+            store.log(5, LogLevel::Debug, format!("Message {}", n));
+        }
+        let log = store.log_entries(5).unwrap();
+        assert_eq!(log.len(), MAX_JOB_LOG_ENTRIES);
+        store.log(5, LogLevel::Warn, "Failed");
+        let log = store.log_entries(5).unwrap();
+        assert_eq!(log.len(), MAX_JOB_LOG_ENTRIES);
+        assert_eq!(log[0].message, "Message 1");
+        assert_eq!(log.last().unwrap().level, LogLevel::Warn);
+        assert!(store.log_entries(99).is_none());
+
+        assert_eq!(store.retain(|job| job.printer_name == "Odd"), 5);
+        assert!(store.remove(3).is_some());
+        assert!(store.get(3).is_none());
+        assert_eq!(store.len(), 4);
+
+        store.clear();
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn test_job_ids_spread_across_shards() {
+        use crate::core::JOB_ID_SEQUENCE_BITS;
+
+        // One job per second, as at normal rates: every sequence number is 0
+        let store = JobStore::new();
+        let first_second: u64 = 1_790_000_000;
+        for second in first_second..first_second + 256 {
+            store.insert(job(second << JOB_ID_SEQUENCE_BITS, "Spread"));
+        }
+        let sizes: Vec<usize> = store
+            .shards
+            .iter()
+            .map(|shard| shard.read().unwrap().len())
+            .collect();
+        assert_eq!(sizes.len(), DEFAULT_SHARDS);
+        assert!(sizes.iter().all(|size| *size > 0), "{:?}", sizes);
+        assert!(sizes.iter().all(|size| *size < 256 / 4), "{:?}", sizes);
+    }
+
+    /// Check that status polling is no slower than with a single
+    /// Mutex<HashMap>. Run with `cargo test --release -- --ignored bench_`.
+    #[test]
+    #[ignore]
+    fn bench_concurrent_status_polling() {
+        const JOBS: u64 = 10_000;
+        const THREADS: u64 = 64;
+        const POLLS: u64 = 20_000;
+
+        let store = Arc::new(JobStore::new());
+        let mutex = Arc::new(Mutex::new(HashMap::new()));
+        for id in 0..JOBS {
+            store.insert(job(id, "Bench"));
+            mutex.lock().unwrap().insert(id, job(id, "Bench"));
+        }
+
+        let run = |poll: Arc<dyn Fn(JobId) + Send + Sync>| {
+            let start = Instant::now();
+            let handles: Vec<_> = (0..THREADS)
+                .map(|thread_id| {
+                    let poll = Arc::clone(&poll);
+                    thread::spawn(move || {
+                        for i in 0..POLLS {
+                            poll((thread_id * 7919 + i * 104_729) % JOBS);
+                        }
+                    })
+                })
+                .collect();
+            for handle in handles {
+                handle.join().unwrap();
+            }
+            start.elapsed()
+        };
+
+        let mutex_poll = Arc::clone(&mutex);
+        let mutex_time = run(Arc::new(move |id| {
+            let _ = mutex_poll.lock().unwrap().get(&id).cloned();
+        }));
+        let store_poll = Arc::clone(&store);
+        let store_time = run(Arc::new(move |id| {
+            let _ = store_poll.get(id);
+        }));
+
+        assert!(
+            store_time <= mutex_time,
+            "JobStore {:?}, Mutex<HashMap> {:?}",
+            store_time,
+            mutex_time
+        );
+    }
+}
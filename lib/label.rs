@@ -0,0 +1,242 @@
+//! Maintenance commands for label printers
+//!
+//! Label printers find where each label starts by sensing the gap or black
+//! mark between labels. After a new roll of a different size is loaded, or
+//! when labels start printing across the gap, the printer has to measure
+//! the media again, which vendors otherwise only offer through their own
+//! setup utilities or a button sequence on the printer. A warehouse app can
+//! instead send the commands here as a raw job:
+//!
+//! - ZPL printers (Zebra and compatibles) calibrate with `~JC`, and feed a
+//!   blank label with `~PH`.
+//! - ESC/POS label and receipt printers (Epson TM-L and compatibles) feed
+//!   to the start of the next label with `GS FF`. They have no calibration
+//!   command; initializing them with `ESC @` and feeding to the next label
+//!   makes them sense the mark again.
+//!
+//! Spoolers rarely report why a label printer stopped. ZPL printers answer
+//! the `~HS` host status query on their raw channel (TCP 9100), which
+//! `query_status` reads for the label-out, ribbon-out, head-open and paused
+//! flags.
+
+use crate::core::StateReason;
+use crate::device::DeviceAddress;
+use std::io::{Read, Write};
+use std::time::{Duration, Instant};
+
+/// Most labels fed by one command
+pub const MAX_FEED_LABELS: u32 = 100;
+
+/// Ends each of the three strings of a `~HS` response
+const ETX: u8 = 0x03;
+
+/// Command language of a label printer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LabelLanguage {
+    Zpl,
+    EscPos,
+}
+
+impl LabelLanguage {
+    pub fn parse(language: &str) -> Option<LabelLanguage> {
+        match language.trim().to_ascii_lowercase().as_str() {
+            "zpl" => Some(LabelLanguage::Zpl),
+            "escpos" | "esc/pos" | "esc-pos" => Some(LabelLanguage::EscPos),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LabelLanguage::Zpl => "zpl",
+            LabelLanguage::EscPos => "escpos",
+        }
+    }
+
+    /// Language a driver's name says the printer takes commands in, for
+    /// ESC/POS printers, which don't report it any other way
+    pub fn from_driver_name(driver_name: &str) -> Option<LabelLanguage> {
+        let name = driver_name.to_ascii_lowercase();
+        let escpos = ["esc/pos", "escpos", "esc-pos", "epson tm-"]
+            .iter()
+            .any(|marker| name.contains(marker));
+        escpos.then_some(LabelLanguage::EscPos)
+    }
+}
+
+/// Command making a printer measure its labels again
+pub fn calibrate(language: LabelLanguage) -> Vec<u8> {
+    match language {
+        LabelLanguage::Zpl => b"~JC\r\n".to_vec(),
+        LabelLanguage::EscPos => b"\x1b@\x1d\x0c".to_vec(),
+    }
+}
+
+/// Command feeding blank labels, e.g. to clear a label that was torn off
+/// early or to bring the next one to the tear bar
+pub fn feed(language: LabelLanguage, labels: u32) -> Result<Vec<u8>, String> {
+    if labels == 0 || labels > MAX_FEED_LABELS {
+        return Err(format!(
+            "Labels to feed must be 1 to {}, not {}",
+            MAX_FEED_LABELS, labels
+        ));
+    }
+    let command: &[u8] = match language {
+        LabelLanguage::Zpl => b"~PH\r\n",
+        LabelLanguage::EscPos => b"\x1d\x0c",
+    };
+    Ok(command.repeat(labels as usize))
+}
+
+/// Conditions a ZPL printer reports in its host status
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LabelStatus {
+    /// Out of labels, or the media sensor can't find them
+    pub label_out: bool,
+    /// Out of thermal transfer ribbon
+    pub ribbon_out: bool,
+    pub head_open: bool,
+    pub paused: bool,
+}
+
+impl LabelStatus {
+    /// Parse a `~HS` response. Its first string carries the paper out and
+    /// pause flags in its second and third fields, its second the head up
+    /// and ribbon out flags in its third and fourth.
+    pub fn parse(response: &str) -> Option<LabelStatus> {
+        let strings: Vec<Vec<&str>> = response
+            .split(ETX as char)
+            .map(|string| string.trim_matches(|c: char| c == '\x02' || c.is_whitespace()))
+            .filter(|string| !string.is_empty())
+            .map(|string| string.split(',').map(str::trim).collect())
+            .collect();
+        let flag = |string: usize, field: usize| {
+            strings
+                .get(string)
+                .and_then(|fields| fields.get(field))
+                .map(|value| *value == "1")
+        };
+        Some(LabelStatus {
+            label_out: flag(0, 1)?,
+            paused: flag(0, 2)?,
+            head_open: flag(1, 2).unwrap_or(false),
+            ribbon_out: flag(1, 3).unwrap_or(false),
+        })
+    }
+
+    /// State reasons for the conditions
+    pub fn state_reasons(&self) -> Vec<StateReason> {
+        [
+            (self.label_out, StateReason::LabelOut),
+            (self.ribbon_out, StateReason::RibbonOut),
+            (self.head_open, StateReason::CoverOpen),
+            (self.paused, StateReason::Paused),
+        ]
+        .into_iter()
+        .filter_map(|(set, reason)| set.then_some(reason))
+        .collect()
+    }
+}
+
+/// Ask a ZPL printer for its host status over the raw channel. Waits up to
+/// `timeout` for the response, keeping what arrived.
+pub fn query_status(host: &str, port: u16, timeout: Duration) -> Result<LabelStatus, String> {
+    let address = DeviceAddress {
+        scheme: "socket".to_string(),
+        host: host.to_string(),
+        port,
+        path: String::new(),
+    };
+    let mut stream = address.connect(timeout)?;
+    stream
+        .write_all(b"~HS\r\n")
+        .map_err(|e| format!("Failed to send host status query: {}", e))?;
+
+    let deadline = Instant::now() + timeout;
+    let mut response = Vec::new();
+    let mut chunk = [0u8; 256];
+    while response.iter().filter(|b| **b == ETX).count() < 3 {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        stream
+            .set_read_timeout(Some(remaining))
+            .map_err(|e| format!("Failed to configure socket: {}", e))?;
+        match stream.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(read) => response.extend_from_slice(&chunk[..read]),
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) =>
+            {
+                break
+            }
+            Err(e) => return Err(format!("Failed to read host status: {}", e)),
+        }
+    }
+    LabelStatus::parse(&String::from_utf8_lossy(&response))
+        .ok_or_else(|| format!("{} didn't answer the host status query", host))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_label_commands() {
+        assert_eq!(calibrate(LabelLanguage::Zpl), b"~JC\r\n");
+        assert_eq!(calibrate(LabelLanguage::EscPos), b"\x1b@\x1d\x0c");
+        assert_eq!(feed(LabelLanguage::Zpl, 2), Ok(b"~PH\r\n~PH\r\n".to_vec()));
+        assert_eq!(feed(LabelLanguage::EscPos, 1), Ok(b"\x1d\x0c".to_vec()));
+        assert!(feed(LabelLanguage::Zpl, 0).is_err());
+        assert!(feed(LabelLanguage::Zpl, MAX_FEED_LABELS + 1).is_err());
+
+        assert_eq!(LabelLanguage::parse("ESC/POS"), Some(LabelLanguage::EscPos));
+        assert_eq!(LabelLanguage::parse("zpl"), Some(LabelLanguage::Zpl));
+        assert_eq!(LabelLanguage::parse("epl"), None);
+        assert_eq!(
+            LabelLanguage::from_driver_name("EPSON TM-L90 Receipt"),
+            Some(LabelLanguage::EscPos)
+        );
+        assert_eq!(LabelLanguage::from_driver_name("Generic / Text Only"), None);
+    }
+
+    #[test]
+    fn test_label_status() {
+        let response = "\x02030,1,0,1245,000,0,0,0,000,0,0,0\x03\r\n\
+                        \x02001,0,0,1,1,2,6,0,00000000,1,000\x03\r\n\
+                        \x021234,0\x03\r\n";
+        let status = LabelStatus::parse(response).unwrap();
+        assert!(status.label_out && status.ribbon_out);
+        assert!(!status.head_open && !status.paused);
+        assert_eq!(
+            status.state_reasons(),
+            vec![StateReason::LabelOut, StateReason::RibbonOut]
+        );
+        assert_eq!(LabelStatus::parse(""), None);
+    }
+
+    #[test]
+    fn test_query_status() {
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut query = [0u8; 5];
+            stream.read_exact(&mut query).unwrap();
+            assert_eq!(&query, b"~HS\r\n");
+            stream
+                .write_all(b"\x02030,0,1,1245,000,0,0,0,000,0,0,0\x03\r\n\x02001,0,1,0,1,2,6,0,00000000,1,000\x03\r\n\x021234,0\x03\r\n")
+                .unwrap();
+        });
+        let status = query_status("127.0.0.1", port, Duration::from_secs(5)).unwrap();
+        server.join().unwrap();
+        assert!(status.paused && status.head_open);
+        assert!(!status.label_out && !status.ribbon_out);
+    }
+}
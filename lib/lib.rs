@@ -3,7 +3,65 @@
 //! This library provides printer functionality for JavaScript runtimes
 //! through Node-API bindings, compatible with Node.js, Deno, and Bun.
 
+pub mod aliases;
+pub mod audit;
+pub mod compression;
 pub mod core;
+pub mod credentials;
+pub mod crypto;
+pub mod device;
+pub mod document;
+pub mod encoding;
+pub mod encryption;
+pub mod export;
+pub mod finishing;
+pub mod held;
+pub mod ipp;
+pub mod job_store;
+pub mod label;
+pub mod logging;
+pub mod metrics;
+pub mod pdf;
+pub mod pjl;
+pub mod policy;
+pub mod pool;
+pub mod power;
+pub mod preprocess;
+pub mod raster;
+pub mod registry;
+pub mod remote;
+pub mod spool;
+pub mod spooler;
+pub mod supervisor;
+pub mod templates;
+pub mod webhooks;
+
+#[cfg(feature = "snmp")]
+pub mod snmp;
+
+#[cfg(feature = "tls")]
+pub mod tls;
+
+#[cfg(feature = "ghostscript")]
+pub mod ghostscript;
+
+#[cfg(feature = "async-core")]
+pub mod async_core;
+
+#[cfg(feature = "schema")]
+pub mod schema;
+
+#[cfg(any(feature = "ipp-server", feature = "print-server"))]
+pub mod http_server;
+
+#[cfg(feature = "ipp-server")]
+pub mod ipp_server;
+
+#[cfg(feature = "print-server")]
+pub mod print_server;
+
+#[cfg(feature = "print-server")]
+pub mod websocket;
 
 #[cfg(feature = "napi")]
 pub mod napi;
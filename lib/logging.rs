@@ -0,0 +1,254 @@
+//! Structured logging via `tracing`
+//!
+//! Library code emits `tracing` events under the `printers_js` target. A small
+//! bridge layer filters them by a runtime-adjustable level and forwards them
+//! to a registered callback (e.g. a JavaScript function), or to stderr when no
+//! callback is set. Nothing is installed until logging is configured, so the
+//! library is silent by default.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex, Once};
+use std::time::SystemTime;
+use tracing::field::{Field, Visit};
+use tracing::subscriber::Interest;
+use tracing::{Event, Level, Metadata, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+
+/// Target prefix of events emitted by this crate
+const LOG_TARGET_PREFIX: &str = "printers_js";
+
+/// Minimum severity of events that are delivered
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Off = 0,
+    Error = 1,
+    Warn = 2,
+    Info = 3,
+    Debug = 4,
+    Trace = 5,
+}
+
+impl LogLevel {
+    /// Parse a level name (case-insensitive)
+    pub fn parse(level: &str) -> Option<LogLevel> {
+        match level.trim().to_lowercase().as_str() {
+            "off" | "none" => Some(LogLevel::Off),
+            "error" => Some(LogLevel::Error),
+            "warn" | "warning" => Some(LogLevel::Warn),
+            "info" => Some(LogLevel::Info),
+            "debug" => Some(LogLevel::Debug),
+            "trace" => Some(LogLevel::Trace),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogLevel::Off => "off",
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+            LogLevel::Trace => "trace",
+        }
+    }
+
+    fn from_tracing(level: &Level) -> LogLevel {
+        match *level {
+            Level::ERROR => LogLevel::Error,
+            Level::WARN => LogLevel::Warn,
+            Level::INFO => LogLevel::Info,
+            Level::DEBUG => LogLevel::Debug,
+            Level::TRACE => LogLevel::Trace,
+        }
+    }
+
+    fn from_u8(value: u8) -> LogLevel {
+        match value {
+            1 => LogLevel::Error,
+            2 => LogLevel::Warn,
+            3 => LogLevel::Info,
+            4 => LogLevel::Debug,
+            5 => LogLevel::Trace,
+            _ => LogLevel::Off,
+        }
+    }
+}
+
+/// A single log event
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogRecord {
+    pub level: LogLevel,
+    pub target: String,
+    pub message: String,
+    pub fields: BTreeMap<String, String>,
+    pub timestamp: SystemTime,
+}
+
+impl fmt::Display for LogRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[printers-js] {} {}: {}",
+            self.level.as_str().to_uppercase(),
+            self.target,
+            self.message
+        )?;
+        for (key, value) in &self.fields {
+            write!(f, " {}={}", key, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Log callback type
+pub type LogCallback = Arc<dyn Fn(&LogRecord) + Send + Sync>;
+
+static LOG_LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Warn as u8);
+static INIT: Once = Once::new();
+
+lazy_static::lazy_static! {
+    static ref LOG_CALLBACK: Mutex<Option<LogCallback>> = Mutex::new(None);
+}
+
+/// Collects event fields into a message and key/value pairs
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: BTreeMap<String, String>,
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            self.fields
+                .insert(field.name().to_string(), value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+        } else {
+            self.fields
+                .insert(field.name().to_string(), format!("{:?}", value));
+        }
+    }
+}
+
+/// Layer forwarding this crate's events to the callback or stderr
+struct BridgeLayer;
+
+impl<S: Subscriber> Layer<S> for BridgeLayer {
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        if metadata.target().starts_with(LOG_TARGET_PREFIX) {
+            // The level can change at runtime, so re-check on every event
+            Interest::sometimes()
+        } else {
+            Interest::never()
+        }
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>, _ctx: Context<'_, S>) -> bool {
+        metadata.target().starts_with(LOG_TARGET_PREFIX)
+            && LogLevel::from_tracing(metadata.level()) <= log_level()
+    }
+
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+
+        let record = LogRecord {
+            level: LogLevel::from_tracing(event.metadata().level()),
+            target: event.metadata().target().to_string(),
+            message: visitor.message,
+            fields: visitor.fields,
+            timestamp: SystemTime::now(),
+        };
+
+        // Clone the callback out so it can log without deadlocking
+        let callback = LOG_CALLBACK.lock().unwrap().clone();
+        match callback {
+            Some(callback) => callback(&record),
+            None => eprintln!("{}", record),
+        }
+    }
+}
+
+/// Install the bridge as the global subscriber. Applications that already
+/// installed their own subscriber keep it and receive events directly.
+pub fn init() {
+    INIT.call_once(|| {
+        let subscriber = tracing_subscriber::registry().with(BridgeLayer);
+        let _ = tracing::subscriber::set_global_default(subscriber);
+    });
+}
+
+/// Set the minimum level of delivered events
+pub fn set_log_level(level: LogLevel) {
+    init();
+    LOG_LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+/// Get the current minimum level
+pub fn log_level() -> LogLevel {
+    LogLevel::from_u8(LOG_LEVEL.load(Ordering::Relaxed))
+}
+
+/// Route events to a callback instead of stderr (None restores stderr)
+pub fn set_log_callback(callback: Option<LogCallback>) {
+    init();
+    *LOG_CALLBACK.lock().unwrap() = callback;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+
+    #[test]
+    fn test_log_level_parsing() {
+        assert_eq!(LogLevel::parse("DEBUG"), Some(LogLevel::Debug));
+        assert_eq!(LogLevel::parse("warning"), Some(LogLevel::Warn));
+        assert_eq!(LogLevel::parse("off"), Some(LogLevel::Off));
+        assert_eq!(LogLevel::parse("verbose"), None);
+        assert!(LogLevel::Error < LogLevel::Trace);
+
+        for level in [LogLevel::Off, LogLevel::Info, LogLevel::Trace] {
+            assert_eq!(LogLevel::from_u8(level as u8), level);
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_log_callback_receives_events() {
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&records);
+        set_log_callback(Some(Arc::new(move |record: &LogRecord| {
+            sink.lock().unwrap().push(record.clone());
+        })));
+
+        set_log_level(LogLevel::Info);
+        tracing::debug!(target: "printers_js::test", "filtered out");
+        tracing::info!(target: "printers_js::test", job_id = 42, "job submitted");
+        tracing::info!(target: "other_crate", "not ours");
+
+        set_log_callback(None);
+        set_log_level(LogLevel::Warn);
+
+        let records = records.lock().unwrap();
+        let ours: Vec<_> = records
+            .iter()
+            .filter(|r| r.target == "printers_js::test")
+            .collect();
+        assert_eq!(ours.len(), 1);
+        assert_eq!(ours[0].level, LogLevel::Info);
+        assert_eq!(ours[0].message, "job submitted");
+        assert_eq!(ours[0].fields.get("job_id").map(String::as_str), Some("42"));
+        assert!(records.iter().all(|r| r.target != "other_crate"));
+    }
+}
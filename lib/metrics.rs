@@ -0,0 +1,243 @@
+//! Print health metrics
+//!
+//! Core records job lifecycle events here: submitted/completed/failed
+//! counters, the queue depth gauge and a spool latency histogram (time from
+//! submission until the spooler accepted or rejected the job). Values are kept
+//! in a built-in registry for snapshots and can additionally be forwarded to
+//! an embedder's metrics system through a [`MetricsRecorder`].
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+pub const JOBS_SUBMITTED: &str = "printers_jobs_submitted_total";
+pub const JOBS_COMPLETED: &str = "printers_jobs_completed_total";
+pub const JOBS_FAILED: &str = "printers_jobs_failed_total";
+pub const QUEUE_DEPTH: &str = "printers_queue_depth";
+pub const SPOOL_LATENCY: &str = "printers_spool_latency_seconds";
+
+/// Upper bounds (seconds) of the spool latency histogram buckets
+pub const LATENCY_BUCKETS: &[f64] = &[0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+
+/// Receives metric updates as they happen
+pub trait MetricsRecorder: Send + Sync {
+    fn increment_counter(&self, name: &'static str, value: u64);
+    fn set_gauge(&self, name: &'static str, value: f64);
+    fn record_histogram(&self, name: &'static str, value: f64);
+}
+
+/// Point-in-time copy of the built-in registry
+#[derive(Clone, Debug, PartialEq)]
+pub struct MetricsSnapshot {
+    pub jobs_submitted: u64,
+    pub jobs_completed: u64,
+    pub jobs_failed: u64,
+    pub queue_depth: u64,
+    pub spool_latency: HistogramSnapshot,
+}
+
+/// Cumulative histogram counts, one per entry of [`LATENCY_BUCKETS`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct HistogramSnapshot {
+    pub buckets: Vec<(f64, u64)>,
+    pub count: u64,
+    pub sum: f64,
+}
+
+#[derive(Default)]
+struct Histogram {
+    bucket_counts: Vec<u64>,
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        if self.bucket_counts.is_empty() {
+            self.bucket_counts = vec![0; LATENCY_BUCKETS.len()];
+        }
+        if let Some(index) = LATENCY_BUCKETS.iter().position(|bound| value <= *bound) {
+            self.bucket_counts[index] += 1;
+        }
+        self.count += 1;
+        self.sum += value;
+    }
+
+    fn snapshot(&self) -> HistogramSnapshot {
+        let mut cumulative = 0;
+        let buckets = LATENCY_BUCKETS
+            .iter()
+            .enumerate()
+            .map(|(index, bound)| {
+                cumulative += self.bucket_counts.get(index).copied().unwrap_or(0);
+                (*bound, cumulative)
+            })
+            .collect();
+        HistogramSnapshot {
+            buckets,
+            count: self.count,
+            sum: self.sum,
+        }
+    }
+}
+
+static SUBMITTED: AtomicU64 = AtomicU64::new(0);
+static COMPLETED: AtomicU64 = AtomicU64::new(0);
+static FAILED: AtomicU64 = AtomicU64::new(0);
+static QUEUE: AtomicU64 = AtomicU64::new(0);
+
+lazy_static::lazy_static! {
+    static ref LATENCY: Mutex<Histogram> = Mutex::new(Histogram::default());
+    static ref RECORDER: Mutex<Option<Arc<dyn MetricsRecorder>>> = Mutex::new(None);
+}
+
+/// Forward metric updates to an external recorder (None removes it)
+pub fn set_recorder(recorder: Option<Arc<dyn MetricsRecorder>>) {
+    *RECORDER.lock().unwrap() = recorder;
+}
+
+fn recorder() -> Option<Arc<dyn MetricsRecorder>> {
+    RECORDER.lock().unwrap().clone()
+}
+
+/// Record a job accepted by the library
+pub fn job_submitted() {
+    SUBMITTED.fetch_add(1, Ordering::Relaxed);
+    if let Some(recorder) = recorder() {
+        recorder.increment_counter(JOBS_SUBMITTED, 1);
+    }
+}
+
+/// Record a job handed to (or rejected by) the spooler
+pub fn job_finished(success: bool, spool_latency: Duration) {
+    let (counter, name) = if success {
+        (&COMPLETED, JOBS_COMPLETED)
+    } else {
+        (&FAILED, JOBS_FAILED)
+    };
+    counter.fetch_add(1, Ordering::Relaxed);
+
+    let seconds = spool_latency.as_secs_f64();
+    LATENCY.lock().unwrap().observe(seconds);
+
+    if let Some(recorder) = recorder() {
+        recorder.increment_counter(name, 1);
+        recorder.record_histogram(SPOOL_LATENCY, seconds);
+    }
+}
+
+/// Set the number of pending and processing jobs
+pub fn set_queue_depth(depth: u64) {
+    QUEUE.store(depth, Ordering::Relaxed);
+    if let Some(recorder) = recorder() {
+        recorder.set_gauge(QUEUE_DEPTH, depth as f64);
+    }
+}
+
+/// Copy the current values of all metrics
+pub fn snapshot() -> MetricsSnapshot {
+    MetricsSnapshot {
+        jobs_submitted: SUBMITTED.load(Ordering::Relaxed),
+        jobs_completed: COMPLETED.load(Ordering::Relaxed),
+        jobs_failed: FAILED.load(Ordering::Relaxed),
+        queue_depth: QUEUE.load(Ordering::Relaxed),
+        spool_latency: LATENCY.lock().unwrap().snapshot(),
+    }
+}
+
+/// Render a snapshot in the Prometheus text exposition format
+#[cfg(feature = "prometheus")]
+pub fn to_prometheus(snapshot: &MetricsSnapshot) -> String {
+    use std::fmt::Write;
+
+    let mut out = String::new();
+    let mut counter = |name: &str, help: &str, value: u64| {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} counter", name);
+        let _ = writeln!(out, "{} {}", name, value);
+    };
+    counter(
+        JOBS_SUBMITTED,
+        "Print jobs submitted",
+        snapshot.jobs_submitted,
+    );
+    counter(
+        JOBS_COMPLETED,
+        "Print jobs accepted by the spooler",
+        snapshot.jobs_completed,
+    );
+    counter(JOBS_FAILED, "Print jobs that failed", snapshot.jobs_failed);
+
+    let _ = writeln!(out, "# HELP {} Pending and processing jobs", QUEUE_DEPTH);
+    let _ = writeln!(out, "# TYPE {} gauge", QUEUE_DEPTH);
+    let _ = writeln!(out, "{} {}", QUEUE_DEPTH, snapshot.queue_depth);
+
+    let latency = &snapshot.spool_latency;
+    let _ = writeln!(
+        out,
+        "# HELP {} Time from submission until the spooler accepted the job",
+        SPOOL_LATENCY
+    );
+    let _ = writeln!(out, "# TYPE {} histogram", SPOOL_LATENCY);
+    for (bound, count) in &latency.buckets {
+        let _ = writeln!(
+            out,
+            "{}_bucket{{le=\"{}\"}} {}",
+            SPOOL_LATENCY, bound, count
+        );
+    }
+    let _ = writeln!(
+        out,
+        "{}_bucket{{le=\"+Inf\"}} {}",
+        SPOOL_LATENCY, latency.count
+    );
+    let _ = writeln!(out, "{}_sum {}", SPOOL_LATENCY, latency.sum);
+    let _ = writeln!(out, "{}_count {}", SPOOL_LATENCY, latency.count);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_buckets_are_cumulative() {
+        let mut histogram = Histogram::default();
+        histogram.observe(0.07);
+        histogram.observe(0.3);
+        histogram.observe(0.3);
+        histogram.observe(120.0);
+
+        let snapshot = histogram.snapshot();
+        assert_eq!(snapshot.count, 4);
+        assert!((snapshot.sum - 120.67).abs() < 1e-9);
+        assert_eq!(snapshot.buckets[0], (0.05, 0));
+        assert_eq!(snapshot.buckets[1], (0.1, 1));
+        assert_eq!(snapshot.buckets[3], (0.5, 3));
+        // Observations above the last bound only appear in +Inf (the count)
+        assert_eq!(snapshot.buckets.last(), Some(&(60.0, 3)));
+    }
+
+    #[cfg(feature = "prometheus")]
+    #[test]
+    fn test_prometheus_format() {
+        let mut histogram = Histogram::default();
+        histogram.observe(0.2);
+        let snapshot = MetricsSnapshot {
+            jobs_submitted: 3,
+            jobs_completed: 1,
+            jobs_failed: 1,
+            queue_depth: 1,
+            spool_latency: histogram.snapshot(),
+        };
+
+        let text = to_prometheus(&snapshot);
+        assert!(text.contains("# TYPE printers_jobs_submitted_total counter\n"));
+        assert!(text.contains("printers_jobs_submitted_total 3\n"));
+        assert!(text.contains("printers_queue_depth 1\n"));
+        assert!(text.contains("printers_spool_latency_seconds_bucket{le=\"0.1\"} 0\n"));
+        assert!(text.contains("printers_spool_latency_seconds_bucket{le=\"0.25\"} 1\n"));
+        assert!(text.contains("printers_spool_latency_seconds_bucket{le=\"+Inf\"} 1\n"));
+        assert!(text.contains("printers_spool_latency_seconds_count 1\n"));
+    }
+}
@@ -1,5 +1,5 @@
 //! N-API bindings for Node.js
-use crate::core::{PrintError, PrinterCore, PrinterJobOptions};
+use crate::core::{PrintError, PrinterCore, PrinterHandle, PrinterJobOptions, PrinterJobTracking};
 use napi::bindgen_prelude::*;
 use napi_derive::napi;
 use std::collections::HashMap;
@@ -25,36 +25,20 @@ impl Task for PrintTask {
     type JsValue = f64;
 
     fn compute(&mut self) -> Result<Self::Output> {
-        let result = match PrinterCore::print_file(
+        let job_id = submit_print_file(
             &self.printer_name,
             &self.file_path,
             self.job_options.clone(),
-        ) {
-            Ok(job_id) => Ok(job_id),
-            Err(e) => match e {
-                PrintError::PrinterNotFound => {
-                    Err(Error::new(Status::InvalidArg, "Printer not found"))
-                }
-                PrintError::FileNotFound => Err(Error::new(Status::InvalidArg, "File not found")),
-                PrintError::InvalidFilePath => {
-                    Err(Error::new(Status::InvalidArg, "Invalid file path"))
-                }
-                _ => Err(Error::new(
-                    Status::GenericFailure,
-                    format!("Print failed with error code: {}", e.as_i32()),
-                )),
-            },
-        };
+        )?;
 
         // If print job was successfully submitted and waitForCompletion is true,
         // poll job status until completion to keep printer instance alive
-        if let Ok(job_id) = result {
-            if self.wait_for_completion {
-                poll_job_completion(job_id);
-            }
+        if self.wait_for_completion {
+            poll_job_completion(job_id, None);
+            check_job_timeout(job_id)?;
         }
 
-        result
+        Ok(job_id)
     }
 
     fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
@@ -62,38 +46,39 @@ impl Task for PrintTask {
     }
 }
 
-impl Task for PrintBytesTask {
+/// Async task for rendering a template and printing the result
+pub struct PrintTemplateTask {
+    pub printer_name: String,
+    pub template: String,
+    pub variables: HashMap<String, String>,
+    pub job_options: Option<PrinterJobOptions>,
+    pub wait_for_completion: bool,
+}
+
+impl Task for PrintTemplateTask {
     type Output = u64;
     type JsValue = f64;
 
     fn compute(&mut self) -> Result<Self::Output> {
-        let result = match PrinterCore::print_bytes(
-            &self.printer_name,
-            &self.data,
-            self.job_options.clone(),
-        ) {
-            Ok(job_id) => Ok(job_id),
-            Err(e) => match e {
-                PrintError::PrinterNotFound => {
-                    Err(Error::new(Status::InvalidArg, "Printer not found"))
-                }
-                PrintError::InvalidFilePath => Err(Error::new(Status::InvalidArg, "Invalid data")),
-                _ => Err(Error::new(
-                    Status::GenericFailure,
-                    format!("Print failed with error code: {}", e.as_i32()),
-                )),
-            },
-        };
+        let template = crate::templates::get(&self.template).ok_or_else(|| {
+            Error::new(
+                Status::InvalidArg,
+                format!("Template '{}' not found", self.template),
+            )
+        })?;
+        let document = template
+            .render(&self.variables)
+            .map_err(|e| Error::new(Status::InvalidArg, e))?;
+        let job_options = template.job_options(self.job_options.clone());
+        let job_id =
+            submit_print_bytes(&self.printer_name, document.as_bytes(), Some(job_options))?;
 
-        // If print job was successfully submitted and waitForCompletion is true,
-        // poll job status until completion to keep printer instance alive
-        if let Ok(job_id) = result {
-            if self.wait_for_completion {
-                poll_job_completion(job_id);
-            }
+        if self.wait_for_completion {
+            poll_job_completion(job_id, None);
+            check_job_timeout(job_id)?;
         }
 
-        result
+        Ok(job_id)
     }
 
     fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
@@ -101,417 +86,4667 @@ impl Task for PrintBytesTask {
     }
 }
 
-/// Error codes for printing operations
-#[napi]
-pub enum PrintErrorCode {
-    InvalidParams = 1,
-    InvalidPrinterName = 2,
-    InvalidFilePath = 3,
-    InvalidJson = 4,
-    InvalidJsonEncoding = 5,
-    PrinterNotFound = 6,
-    FileNotFound = 7,
+/// Async task for printing a generated test page
+pub struct PrintTestPageTask {
+    pub printer_name: String,
+    pub job_options: Option<PrinterJobOptions>,
+    pub wait_for_completion: bool,
 }
 
-/// Print job interface matching upstream printers crate
-#[napi(object)]
-pub struct PrinterJob {
-    pub id: f64,
-    pub name: String,
-    pub state: String,
-    #[napi(js_name = "mediaType")]
-    pub media_type: String,
-    #[napi(js_name = "createdAt")]
-    pub created_at: f64,
-    #[napi(js_name = "processedAt")]
-    pub processed_at: Option<f64>,
-    #[napi(js_name = "completedAt")]
-    pub completed_at: Option<f64>,
-    #[napi(js_name = "printerName")]
+impl Task for PrintTestPageTask {
+    type Output = u64;
+    type JsValue = f64;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let job_id = PrinterCore::print_test_page(&self.printer_name, self.job_options.clone())
+            .map_err(print_bytes_error)?;
+
+        if self.wait_for_completion {
+            poll_job_completion(job_id, None);
+            check_job_timeout(job_id)?;
+        }
+
+        Ok(job_id)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output as f64)
+    }
+}
+
+/// What a print job prints
+pub enum PrintPayload {
+    File(String),
+    Bytes(Vec<u8>),
+}
+
+/// Async task for checking a submission without printing it
+pub struct DryRunTask {
     pub printer_name: String,
-    #[napi(js_name = "errorMessage")]
-    pub error_message: Option<String>,
-    #[napi(js_name = "ageSeconds")]
-    pub age_seconds: f64,
+    pub payload: PrintPayload,
+    pub job_options: Option<PrinterJobOptions>,
 }
 
-/// Legacy job status interface for backward compatibility
-#[napi(object)]
-pub struct JobStatus {
-    pub id: u32,
+impl Task for DryRunTask {
+    type Output = crate::core::DryRunResult;
+    type JsValue = DryRunResult;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let job_options = self.job_options.clone();
+        match &self.payload {
+            PrintPayload::File(file_path) => {
+                PrinterCore::dry_run_file(&self.printer_name, file_path, job_options)
+                    .map_err(print_file_error)
+            }
+            PrintPayload::Bytes(data) => {
+                PrinterCore::dry_run_bytes(&self.printer_name, data, job_options)
+                    .map_err(print_bytes_error)
+            }
+        }
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(DryRunResult {
+            printer_name: output.printer_name,
+            job_name: output.job_options.name,
+            options: output.job_options.raw_properties,
+            media_type: output.media_type,
+            media_type_source: output.media_type_source.as_str().to_string(),
+            size_bytes: output.size_bytes.map(|size| size as f64),
+            document_pages: output.document_pages,
+            pages: output.pages,
+            sheets: output.sheets,
+            capabilities_checked: output.capabilities.is_some(),
+            unsupported_options: output.unsupported_options,
+            converts_to_gray: output.job_options.convert_to_gray,
+        })
+    }
+}
+
+/// Async task for resolving the options a job would be sent with
+pub struct ResolveJobOptionsTask {
     pub printer_name: String,
-    pub file_path: String,
-    pub job_name: Option<String>,
-    pub status: String,
-    pub error_message: Option<String>,
-    pub age_seconds: u32,
+    pub job_options: Option<PrinterJobOptions>,
 }
 
-/// Printer information
-#[napi(object)]
-pub struct PrinterInfo {
-    pub name: String,
-    pub system_name: String,
-    pub driver_name: String,
-    pub uri: String,
-    pub port_name: String,
-    pub processor: String,
-    pub data_type: String,
-    pub description: String,
-    pub location: String,
-    pub is_default: bool,
-    pub is_shared: bool,
-    pub state: String,
-    pub state_reasons: Vec<String>,
+impl Task for ResolveJobOptionsTask {
+    type Output = crate::core::ResolvedJobOptions;
+    type JsValue = ResolvedJobOptions;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        PrinterCore::resolve_job_options(&self.printer_name, self.job_options.clone())
+            .map_err(print_error)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(ResolvedJobOptions {
+            printer_name: output.printer_name,
+            options: output.job_options.to_map(),
+            sources: output
+                .sources
+                .into_iter()
+                .map(|(key, source)| (key, source.as_str().to_string()))
+                .collect(),
+        })
+    }
 }
 
-/// Printer class for Node.js
-#[napi]
-pub struct Printer {
-    name: String,
+/// Async task for estimating a job's usage
+pub struct EstimateJobTask {
+    pub printer_name: String,
+    pub payload: PrintPayload,
+    pub job_options: Option<PrinterJobOptions>,
 }
 
-#[napi]
-impl Printer {
-    /// Create a new printer instance
-    #[napi(constructor)]
-    pub fn new(name: String) -> Result<Self> {
-        if PrinterCore::printer_exists(&name) {
-            Ok(Printer { name })
-        } else {
-            Err(Error::new(
-                Status::InvalidArg,
-                format!("Printer '{}' not found", name),
-            ))
+impl Task for EstimateJobTask {
+    type Output = crate::core::JobEstimate;
+    type JsValue = JobEstimate;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let job_options = self.job_options.clone();
+        match &self.payload {
+            PrintPayload::File(file_path) => {
+                PrinterCore::estimate_job(&self.printer_name, file_path, job_options)
+                    .map_err(print_file_error)
+            }
+            PrintPayload::Bytes(data) => {
+                PrinterCore::estimate_bytes_job(&self.printer_name, data, job_options)
+                    .map_err(print_bytes_error)
+            }
         }
     }
 
-    /// Get printer name
-    #[napi(getter)]
-    pub fn name(&self) -> String {
-        self.name.clone()
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(JobEstimate {
+            printer_name: output.printer_name,
+            document_pages: output.document_pages,
+            pages: output.pages,
+            sheets: output.sheets,
+            copies: output.copies,
+            duplex: output.duplex,
+            color: output.color.as_str().to_string(),
+        })
     }
+}
 
-    /// Get printer information
-    #[napi]
-    pub fn get_info(&self) -> Result<PrinterInfo> {
-        if let Some(printer) = PrinterCore::find_printer_by_name(&self.name) {
-            Ok(PrinterInfo {
-                name: printer.name.clone(),
-                system_name: printer.system_name.clone(),
-                driver_name: printer.driver_name.clone(),
-                uri: printer.uri.clone(),
-                port_name: printer.port_name.clone(),
-                processor: printer.processor.clone(),
-                data_type: printer.data_type.clone(),
-                description: printer.description.clone(),
-                location: printer.location.clone(),
-                is_default: printer.is_default,
-                is_shared: printer.is_shared,
-                state: PrinterCore::get_printer_state(&printer),
-                state_reasons: printer.state_reasons.clone(),
+/// Async task for extracting a document's text
+pub struct ExtractTextTask {
+    pub payload: PrintPayload,
+}
+
+impl Task for ExtractTextTask {
+    type Output = String;
+    type JsValue = String;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let read;
+        let data = match &self.payload {
+            PrintPayload::File(file_path) => {
+                read = std::fs::read(file_path)
+                    .map_err(|_| print_file_error(PrintError::FileNotFound))?;
+                &read
+            }
+            PrintPayload::Bytes(data) => data,
+        };
+        crate::document::extract_text(data).map_err(|e| Error::new(Status::GenericFailure, e))
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/// Async task for exporting job history to a file
+pub struct ExportJobHistoryTask {
+    pub path: String,
+    pub format: crate::export::ExportFormat,
+    pub since: Option<std::time::SystemTime>,
+}
+
+impl Task for ExportJobHistoryTask {
+    type Output = usize;
+    type JsValue = u32;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        PrinterCore::export_job_history(std::path::Path::new(&self.path), self.format, self.since)
+            .map_err(|e| Error::new(Status::GenericFailure, e))
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output as u32)
+    }
+}
+
+/// Async task for printing through a printer pool
+pub struct PoolPrintTask {
+    pub pool: std::sync::Arc<crate::pool::PrinterPool>,
+    pub payload: PrintPayload,
+    pub job_options: Option<PrinterJobOptions>,
+    pub wait_for_completion: bool,
+}
+
+impl Task for PoolPrintTask {
+    type Output = u64;
+    type JsValue = f64;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let job_options = self.job_options.clone();
+        let job_id = match &self.payload {
+            PrintPayload::File(file_path) => self
+                .pool
+                .print_file(file_path, job_options)
+                .map_err(print_file_error),
+            PrintPayload::Bytes(data) => self
+                .pool
+                .print_bytes(data, job_options)
+                .map_err(print_bytes_error),
+        }?;
+
+        if self.wait_for_completion {
+            poll_job_completion(job_id, None);
+            check_job_timeout(job_id)?;
+        }
+
+        Ok(job_id)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output as f64)
+    }
+}
+
+/// Async task for printing a file to several printers
+pub struct PrintToManyTask {
+    pub printer_names: Vec<String>,
+    pub file_path: String,
+    pub job_options: Option<PrinterJobOptions>,
+    pub wait_for_completion: bool,
+}
+
+impl Task for PrintToManyTask {
+    type Output = Vec<PrintDestinationResult>;
+    type JsValue = Vec<PrintDestinationResult>;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let results = PrinterCore::print_to_many(
+            &self.printer_names,
+            &self.file_path,
+            self.job_options.clone(),
+        )
+        .map_err(print_file_error)?;
+
+        let mut destinations: Vec<PrintDestinationResult> = self
+            .printer_names
+            .iter()
+            .zip(results)
+            .map(|(printer_name, result)| PrintDestinationResult {
+                printer_name: printer_name.clone(),
+                job_id: result.as_ref().ok().map(|job_id| *job_id as f64),
+                error: result.err().map(|e| print_file_error(e).reason),
             })
-        } else {
-            Err(Error::new(
-                Status::InvalidArg,
-                format!("Printer '{}' not found", self.name),
-            ))
+            .collect();
+
+        // Jobs that fail after submission are reported like submission errors
+        if self.wait_for_completion {
+            for destination in &mut destinations {
+                let Some(job_id) = destination.job_id else {
+                    continue;
+                };
+                poll_job_completion(job_id as u64, None);
+                destination.error = PrinterCore::get_job_status(job_id as u64)
+                    .filter(|job| {
+                        matches!(
+                            job.state,
+                            crate::core::PrinterJobState::CANCELLED
+                                | crate::core::PrinterJobState::FAILED
+                        )
+                    })
+                    .map(|job| {
+                        job.error_message
+                            .unwrap_or_else(|| "Job cancelled".to_string())
+                    });
+            }
         }
+
+        Ok(destinations)
     }
 
-    /// Check if printer exists
-    #[napi]
-    pub fn exists(&self) -> bool {
-        PrinterCore::printer_exists(&self.name)
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
     }
+}
 
-    /// Print a file (async)
-    #[napi]
-    pub fn print_file(
-        &self,
-        file_path: String,
-        job_properties: Option<HashMap<String, String>>,
-        wait_for_completion: Option<bool>,
-    ) -> AsyncTask<PrintTask> {
-        let job_options = job_properties.map(PrinterJobOptions::from_map);
-        AsyncTask::new(PrintTask {
-            printer_name: self.name.clone(),
-            file_path,
-            job_options,
-            wait_for_completion: wait_for_completion.unwrap_or(true), // Default to true
-        })
+impl Task for PrintBytesTask {
+    type Output = u64;
+    type JsValue = f64;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let job_id = submit_print_bytes(&self.printer_name, &self.data, self.job_options.clone())?;
+
+        // If print job was successfully submitted and waitForCompletion is true,
+        // poll job status until completion to keep printer instance alive
+        if self.wait_for_completion {
+            poll_job_completion(job_id, None);
+            check_job_timeout(job_id)?;
+        }
+
+        Ok(job_id)
     }
 
-    /// Print raw bytes (async)
-    #[napi]
-    pub fn print_bytes(
-        &self,
-        data: Buffer,
-        job_properties: Option<HashMap<String, String>>,
-        wait_for_completion: Option<bool>,
-    ) -> AsyncTask<PrintBytesTask> {
-        let job_options = job_properties.map(PrinterJobOptions::from_map);
-        AsyncTask::new(PrintBytesTask {
-            printer_name: self.name.clone(),
-            data: data.to_vec(),
-            job_options,
-            wait_for_completion: wait_for_completion.unwrap_or(true), // Default to true
-        })
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output as f64)
     }
 }
 
-/// Find a printer by name
-#[napi]
-pub fn find_printer_by_name(name: String) -> Option<Printer> {
-    if PrinterCore::printer_exists(&name) {
-        Some(Printer { name })
-    } else {
-        None
+/// Submit a file print job, mapping core errors to N-API errors
+fn submit_print_file(
+    printer_name: &str,
+    file_path: &str,
+    job_options: Option<PrinterJobOptions>,
+) -> Result<u64> {
+    PrinterCore::print_file(printer_name, file_path, job_options).map_err(print_file_error)
+}
+
+/// Map core errors of file print jobs to N-API errors
+fn print_file_error(e: PrintError) -> Error {
+    match e {
+        PrintError::FileNotFound => coded_error(Status::InvalidArg, &e, "File not found"),
+        PrintError::InvalidFilePath => coded_error(Status::InvalidArg, &e, "Invalid file path"),
+        _ => print_error(e),
     }
 }
 
-/// Check if a printer exists by name
-#[napi]
-pub fn printer_exists(name: String) -> bool {
-    PrinterCore::printer_exists(&name)
+/// Submit a raw bytes print job, mapping core errors to N-API errors
+fn submit_print_bytes(
+    printer_name: &str,
+    data: &[u8],
+    job_options: Option<PrinterJobOptions>,
+) -> Result<u64> {
+    PrinterCore::print_bytes(printer_name, data, job_options).map_err(print_bytes_error)
+}
+
+/// Map core errors of raw bytes print jobs to N-API errors
+fn print_bytes_error(e: PrintError) -> Error {
+    match e {
+        PrintError::InvalidFilePath => coded_error(Status::InvalidArg, &e, "Invalid data"),
+        _ => print_error(e),
+    }
+}
+
+/// Map core errors common to file and raw bytes print jobs to N-API errors
+fn print_error(e: PrintError) -> Error {
+    match e {
+        PrintError::PrinterNotFound => coded_error(Status::InvalidArg, &e, "Printer not found"),
+        PrintError::ShuttingDown => {
+            coded_error(Status::GenericFailure, &e, "Library is shutting down")
+        }
+        PrintError::InvalidDocument => invalid_document_error(),
+        PrintError::JobNotFound => coded_error(Status::InvalidArg, &e, "Dependency job not found"),
+        PrintError::SpoolQuotaExceeded => {
+            coded_error(Status::GenericFailure, &e, "Spool quota exceeded")
+        }
+        PrintError::SpoolFailed => {
+            coded_error(Status::GenericFailure, &e, "Failed to spool print data")
+        }
+        PrintError::NoPrinterAvailable => coded_error(
+            Status::GenericFailure,
+            &e,
+            "No printer in the pool is available",
+        ),
+        PrintError::QueueFull => queue_full_error(),
+        PrintError::UnsupportedOption(reason) => unsupported_option_error(reason),
+        PrintError::PolicyViolation(violation) => policy_violation_error(violation),
+        PrintError::DuplicateJob => coded_error(
+            Status::GenericFailure,
+            &e,
+            format!(
+                "{}A job with this dedupe key was already submitted",
+                DUPLICATE_JOB_ERROR_PREFIX
+            ),
+        ),
+        _ => coded_error(
+            Status::GenericFailure,
+            &e,
+            format!("Print failed with error code: {}", e.as_i32()),
+        ),
+    }
+}
+
+/// An error whose message starts with the name of the core error, e.g.
+/// `[PrinterNotFound] Printer not found`. The JavaScript wrapper strips the
+/// name into the error's `code`, so messages can be localized by code.
+fn coded_error(status: Status, e: &PrintError, message: impl std::fmt::Display) -> Error {
+    Error::new(status, format!("[{}] {}", e.name(), message))
+}
+
+/// Code of timeout errors, which don't come from a core error
+const TIMEOUT_ERROR_CODE: &str = "Timeout";
+
+/// Prefix of errors the JavaScript wrapper turns into a TimeoutError
+const TIMEOUT_ERROR_PREFIX: &str = "TimeoutError: ";
+/// Prefix of errors the JavaScript wrapper turns into an InvalidDocumentError
+const INVALID_DOCUMENT_ERROR_PREFIX: &str = "InvalidDocumentError: ";
+/// Prefix of errors the JavaScript wrapper turns into a QueueFullError
+const QUEUE_FULL_ERROR_PREFIX: &str = "QueueFullError: ";
+/// Prefix of errors the JavaScript wrapper turns into a DuplicateJobError
+const DUPLICATE_JOB_ERROR_PREFIX: &str = "DuplicateJobError: ";
+/// Prefix of errors the JavaScript wrapper turns into an
+/// UnsupportedOptionError
+const UNSUPPORTED_OPTION_ERROR_PREFIX: &str = "UnsupportedOptionError: ";
+/// Prefix of errors the JavaScript wrapper turns into a
+/// PolicyViolationError
+const POLICY_VIOLATION_ERROR_PREFIX: &str = "PolicyViolationError: ";
+
+fn invalid_document_error() -> Error {
+    coded_error(
+        Status::InvalidArg,
+        &PrintError::InvalidDocument,
+        format!(
+            "{}Document failed PDF validation",
+            INVALID_DOCUMENT_ERROR_PREFIX
+        ),
+    )
+}
+
+fn queue_full_error() -> Error {
+    coded_error(
+        Status::GenericFailure,
+        &PrintError::QueueFull,
+        format!("{}Job queue is full", QUEUE_FULL_ERROR_PREFIX),
+    )
+}
+
+/// Unsupported option error, with the reason the options were rejected
+fn unsupported_option_error(reason: String) -> Error {
+    let message = format!("{}{}", UNSUPPORTED_OPTION_ERROR_PREFIX, reason);
+    coded_error(
+        Status::InvalidArg,
+        &PrintError::UnsupportedOption(reason),
+        message,
+    )
+}
+
+/// Policy violation error, naming the rule the job broke
+fn policy_violation_error(violation: crate::policy::PolicyViolation) -> Error {
+    let message = format!("{}{}", POLICY_VIOLATION_ERROR_PREFIX, violation);
+    coded_error(
+        Status::GenericFailure,
+        &PrintError::PolicyViolation(violation),
+        message,
+    )
+}
+
+/// Reject callers waiting on a job that failed because its timeout expired
+fn check_job_timeout(job_id: u64) -> Result<()> {
+    match PrinterCore::get_job_status(job_id) {
+        Some(job) if job.timed_out => Err(Error::new(
+            Status::GenericFailure,
+            format!(
+                "[{}] {}Job {}: {}",
+                TIMEOUT_ERROR_CODE,
+                TIMEOUT_ERROR_PREFIX,
+                job_id,
+                job.error_message.unwrap_or_default()
+            ),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Wait on the calling thread for a submitted job, failing if it's still
+/// running after the timeout
+fn wait_for_job_sync(job_id: u64, timeout_ms: Option<u32>) -> Result<f64> {
+    let timeout = timeout_ms.map(|ms| std::time::Duration::from_millis(u64::from(ms)));
+    if !poll_job_completion(job_id, timeout) {
+        return Err(Error::new(
+            Status::GenericFailure,
+            format!(
+                "[{}] {}Timed out after {}ms waiting for job {}",
+                TIMEOUT_ERROR_CODE,
+                TIMEOUT_ERROR_PREFIX,
+                timeout_ms.unwrap_or(0),
+                job_id
+            ),
+        ));
+    }
+    check_job_timeout(job_id)?;
+    Ok(job_id as f64)
+}
+
+/// Async task for probing printer reachability
+pub struct OnlineCheckTask {
+    pub printer_name: String,
+}
+
+impl Task for OnlineCheckTask {
+    type Output = bool;
+    type JsValue = bool;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        PrinterCore::is_printer_online(&self.printer_name).map_err(|e| match e {
+            PrintError::PrinterNotFound => Error::new(Status::InvalidArg, "Printer not found"),
+            _ => Error::new(
+                Status::GenericFailure,
+                format!("Online check failed with error code: {}", e.as_i32()),
+            ),
+        })
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/// Async task for checking library health
+pub struct HealthCheckTask {
+    /// Printers to probe (None = all)
+    pub printer_names: Option<Vec<String>>,
+}
+
+impl Task for HealthCheckTask {
+    type Output = crate::core::HealthReport;
+    type JsValue = HealthReport;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        Ok(PrinterCore::health_check(self.printer_names.as_deref()))
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        let worker_saturation = output.worker_saturation();
+        Ok(HealthReport {
+            healthy: output.healthy,
+            accepting_jobs: output.accepting_jobs,
+            spooler_reachable: output.spooler_reachable,
+            spooler_error: output.spooler_error,
+            monitoring_active: output.monitoring_active,
+            monitoring_running: output.monitoring_running,
+            jobs: JobStateCounts {
+                pending: output.jobs.pending as u32,
+                paused: output.jobs.paused as u32,
+                processing: output.jobs.processing as u32,
+                completed: output.jobs.completed as u32,
+                cancelled: output.jobs.cancelled as u32,
+                failed: output.jobs.failed as u32,
+            },
+            active_workers: output.active_workers as u32,
+            worker_pool_size: output.worker_pool_size.map(|size| size as u32),
+            worker_saturation,
+            printers: output
+                .printers
+                .into_iter()
+                .map(|printer| PrinterHealth {
+                    name: printer.name,
+                    online: printer.online,
+                    blocking_reasons: printer
+                        .blocking_reasons
+                        .iter()
+                        .map(|reason| reason.as_str().to_string())
+                        .collect(),
+                })
+                .collect(),
+        })
+    }
+}
+
+/// Async task for querying printer supply levels
+pub struct SuppliesTask {
+    pub printer_name: String,
+}
+
+impl Task for SuppliesTask {
+    type Output = Vec<crate::core::PrinterSupply>;
+    type JsValue = Vec<PrinterSupply>;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        PrinterCore::get_printer_supplies(&self.printer_name).map_err(|e| match e {
+            PrintError::PrinterNotFound => Error::new(Status::InvalidArg, "Printer not found"),
+            _ => Error::new(
+                Status::GenericFailure,
+                format!("Supply query failed with error code: {}", e.as_i32()),
+            ),
+        })
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output.into_iter().map(convert_printer_supply).collect())
+    }
+}
+
+/// Async task for querying what a printer supports
+pub struct CapabilitiesTask {
+    pub printer_name: String,
+}
+
+impl Task for CapabilitiesTask {
+    type Output = Option<crate::core::PrinterCapabilities>;
+    type JsValue = Option<PrinterCapabilities>;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        PrinterCore::get_printer_capabilities(&self.printer_name).map_err(|e| match e {
+            PrintError::PrinterNotFound => Error::new(Status::InvalidArg, "Printer not found"),
+            _ => Error::new(
+                Status::GenericFailure,
+                format!("Capability query failed with error code: {}", e.as_i32()),
+            ),
+        })
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output.map(|capabilities| PrinterCapabilities {
+            supported_options: capabilities.supported_options(),
+            finishings: capabilities
+                .finishings()
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+            supported: capabilities.supported,
+            copies_min: capabilities.copies.map(|(min, _)| min),
+            copies_max: capabilities.copies.map(|(_, max)| max),
+            default_color_mode: capabilities.default_color_mode,
+        }))
+    }
+}
+
+/// Async task for detecting the languages a printer accepts
+pub struct SupportedFormatsTask {
+    pub printer_name: String,
+}
+
+impl Task for SupportedFormatsTask {
+    type Output = crate::core::SupportedFormats;
+    type JsValue = SupportedFormats;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        PrinterCore::get_supported_formats(&self.printer_name).map_err(|e| match e {
+            PrintError::PrinterNotFound => Error::new(Status::InvalidArg, "Printer not found"),
+            _ => Error::new(
+                Status::GenericFailure,
+                format!("Format query failed with error code: {}", e.as_i32()),
+            ),
+        })
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        let preferred = output.preferred();
+        Ok(SupportedFormats {
+            languages: output
+                .languages
+                .iter()
+                .map(|language| language.as_str().to_string())
+                .collect(),
+            preferred: preferred.map(|language| language.as_str().to_string()),
+            preferred_media_type: preferred.map(|language| language.media_type().to_string()),
+            document_formats: output.document_formats,
+            source: output.source.as_str().to_string(),
+        })
+    }
+}
+
+/// Async task for reading or updating printer preferences
+pub struct PreferencesTask {
+    pub printer_name: String,
+    /// Preferences to apply (None only reads them)
+    pub update: Option<crate::spooler::PrinterPreferences>,
+}
+
+impl Task for PreferencesTask {
+    type Output = crate::spooler::PrinterPreferences;
+    type JsValue = PrinterPreferences;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        match &self.update {
+            Some(update) => PrinterCore::set_printer_preferences(&self.printer_name, update),
+            None => PrinterCore::get_printer_preferences(&self.printer_name),
+        }
+        .map_err(|e| Error::new(Status::GenericFailure, e))
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(convert_printer_preferences(output))
+    }
+}
+
+/// Async task for querying a printer over SNMP
+#[cfg(feature = "snmp")]
+pub struct SnmpQueryTask {
+    pub host: String,
+    pub options: crate::snmp::SnmpOptions,
+}
+
+#[cfg(feature = "snmp")]
+impl Task for SnmpQueryTask {
+    type Output = crate::snmp::SnmpPrinterStatus;
+    type JsValue = SnmpPrinterStatus;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        crate::snmp::query_printer(&self.host, self.options.clone())
+            .map_err(|e| Error::new(Status::GenericFailure, e))
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(SnmpPrinterStatus {
+            description: output.description,
+            device_status: output.device_status,
+            printer_status: output.printer_status,
+            state_reasons: output
+                .state_reasons
+                .iter()
+                .map(|reason| reason.as_str().to_string())
+                .collect(),
+            page_count: output.page_count.map(|count| count as f64),
+            alerts: output
+                .alerts
+                .into_iter()
+                .map(|alert| SnmpAlert {
+                    severity: alert.severity,
+                    group: alert.group.map(|group| group as f64),
+                    code: alert.code.map(|code| code as f64),
+                    description: alert.description,
+                })
+                .collect(),
+            supplies: output
+                .supplies
+                .into_iter()
+                .map(convert_printer_supply)
+                .collect(),
+        })
+    }
+}
+
+/// Error codes for printing operations
+#[napi]
+pub enum PrintErrorCode {
+    InvalidParams = 1,
+    InvalidPrinterName = 2,
+    InvalidFilePath = 3,
+    InvalidJson = 4,
+    InvalidJsonEncoding = 5,
+    PrinterNotFound = 6,
+    FileNotFound = 7,
+}
+
+/// Print job interface matching upstream printers crate
+#[napi(object)]
+pub struct PrinterJob {
+    pub id: f64,
+    pub name: String,
+    pub state: String,
+    #[napi(js_name = "mediaType")]
+    pub media_type: String,
+    #[napi(js_name = "mediaTypeSource")]
+    pub media_type_source: String,
+    #[napi(js_name = "createdAt")]
+    pub created_at: f64,
+    #[napi(js_name = "processedAt")]
+    pub processed_at: Option<f64>,
+    #[napi(js_name = "completedAt")]
+    pub completed_at: Option<f64>,
+    #[napi(js_name = "printerName")]
+    pub printer_name: String,
+    #[napi(js_name = "errorMessage")]
+    pub error_message: Option<String>,
+    #[napi(js_name = "ageSeconds")]
+    pub age_seconds: f64,
+    #[napi(js_name = "sizeBytes")]
+    pub size_bytes: f64,
+    #[napi(js_name = "totalPages")]
+    pub total_pages: Option<u32>,
+    pub pages: Option<u32>,
+    pub sheets: Option<u32>,
+    pub owner: Option<String>,
+    #[napi(js_name = "reprintOf")]
+    pub reprint_of: Option<f64>,
+    /// Whether the job failed because its timeout expired
+    #[napi(js_name = "timedOut")]
+    pub timed_out: bool,
+    pub stalled: bool,
+    /// Whether the job failed because its printer was removed
+    #[napi(js_name = "printerRemoved")]
+    pub printer_removed: bool,
+    /// Whether the document went to a network printer over TLS
+    #[napi(js_name = "encryptedTransport")]
+    pub encrypted_transport: Option<bool>,
+    /// Caller-defined values the job was submitted with
+    pub metadata: Option<HashMap<String, String>>,
+    /// State changes, oldest first
+    pub transitions: Vec<JobTransition>,
+}
+
+/// A change of a job's state
+#[napi(object)]
+pub struct JobTransition {
+    pub from: String,
+    pub to: String,
+    /// Unix timestamp in seconds
+    pub timestamp: f64,
+}
+
+/// What a submission would do, from a dry run
+#[napi(object)]
+pub struct DryRunResult {
+    #[napi(js_name = "printerName")]
+    pub printer_name: String,
+    #[napi(js_name = "jobName")]
+    pub job_name: Option<String>,
+    /// Raw job properties after applying alias and library-wide defaults
+    pub options: HashMap<String, String>,
+    #[napi(js_name = "mediaType")]
+    pub media_type: String,
+    #[napi(js_name = "mediaTypeSource")]
+    pub media_type_source: String,
+    #[napi(js_name = "sizeBytes")]
+    pub size_bytes: Option<f64>,
+    #[napi(js_name = "documentPages")]
+    pub document_pages: Option<u32>,
+    pub pages: Option<u32>,
+    pub sheets: Option<u32>,
+    #[napi(js_name = "capabilitiesChecked")]
+    pub capabilities_checked: bool,
+    #[napi(js_name = "unsupportedOptions")]
+    pub unsupported_options: Vec<String>,
+    /// Whether the document is converted to gray because the printer
+    /// doesn't report the job's color mode
+    #[napi(js_name = "convertsToGray")]
+    pub converts_to_gray: bool,
+}
+
+/// Options a job would be sent with, and where each came from
+#[napi(object)]
+pub struct ResolvedJobOptions {
+    #[napi(js_name = "printerName")]
+    pub printer_name: String,
+    /// Raw job properties, with library options under their reserved keys
+    pub options: HashMap<String, String>,
+    /// "library", "printer", "alias", "call" or "derived" by option key
+    pub sources: HashMap<String, String>,
+}
+
+/// Expected usage of a job
+#[napi(object)]
+pub struct JobEstimate {
+    #[napi(js_name = "printerName")]
+    pub printer_name: String,
+    #[napi(js_name = "documentPages")]
+    pub document_pages: Option<u32>,
+    pub pages: Option<u32>,
+    pub sheets: Option<u32>,
+    pub copies: u32,
+    pub duplex: bool,
+    pub color: String, // "color" or "monochrome"
+}
+
+/// Outcome of printing to one printer with printToMany
+#[napi(object)]
+pub struct PrintDestinationResult {
+    #[napi(js_name = "printerName")]
+    pub printer_name: String,
+    #[napi(js_name = "jobId")]
+    pub job_id: Option<f64>,
+    pub error: Option<String>,
+}
+
+/// Aggregated usage over completed jobs
+#[napi(object)]
+pub struct UsageStats {
+    pub jobs: u32,
+    pub pages: f64,
+    pub sheets: f64,
+    pub bytes: f64,
+    #[napi(js_name = "jobsWithoutPageCount")]
+    pub jobs_without_page_count: u32,
+}
+
+/// Options for shutdown()
+#[napi(object)]
+pub struct ShutdownOptions {
+    /// "graceful" (default), "drain" or "force"
+    pub mode: Option<String>,
+    pub timeout_ms: Option<u32>,
+}
+
+/// Outcome of the jobs outstanding at shutdown
+#[napi(object)]
+pub struct ShutdownSummary {
+    pub completed: u32,
+    pub cancelled: u32,
+    pub timed_out: u32,
+}
+
+/// Library configuration (all fields optional; omitted fields keep their value)
+#[napi(object)]
+pub struct LibraryConfig {
+    /// Maximum concurrent spooler submissions (0 = unlimited)
+    pub worker_pool_size: Option<u32>,
+    /// Default state monitoring poll interval in seconds
+    pub monitoring_interval: Option<u32>,
+    /// Default state monitoring poll interval in milliseconds, overriding
+    /// `monitoring_interval`
+    pub monitoring_interval_ms: Option<u32>,
+    /// Milliseconds between checks of a job's state while waiting for it
+    pub job_poll_interval_ms: Option<u32>,
+    pub temp_dir: Option<String>,
+    /// Maximum megabytes of print data held in spool files (0 = unlimited)
+    pub spool_quota_mb: Option<u32>,
+    pub simulate: Option<bool>,
+    pub simulation_delay_ms: Option<u32>,
+    pub default_job_options: Option<HashMap<String, String>>,
+    /// Maximum completed/cancelled jobs kept (0 = unlimited)
+    pub max_job_history: Option<u32>,
+    /// Seconds completed/cancelled jobs are kept (0 = until removed)
+    pub history_ttl_seconds: Option<u32>,
+    /// Milliseconds a job may be processing before it is stalled (0 = no watchdog)
+    pub stall_threshold_ms: Option<u32>,
+    /// "flag", "cancel" or "retry"
+    pub stall_action: Option<String>,
+    /// Unfinished jobs per printer before submissions are refused (0 = unlimited)
+    pub max_queued_jobs_per_printer: Option<u32>,
+    /// Unfinished jobs across all printers before submissions are refused (0 = unlimited)
+    pub max_pending_jobs: Option<u32>,
+    /// Milliseconds a submission waits for room in a full queue (0 = fail immediately)
+    pub queue_full_timeout_ms: Option<u32>,
+    /// Directory jobs held for approval are persisted in
+    pub held_jobs_dir: Option<String>,
+    /// File job events are appended to as a tamper-evident audit log ("" = none)
+    pub audit_log: Option<String>,
+    /// Replace document names and file paths of tracked jobs with hashes
+    pub redact_job_data: Option<bool>,
+    /// File processes reserve job IDs from so they don't collide ("" = none)
+    pub job_registry: Option<String>,
+    /// Ghostscript executable documents are rasterized with ("" = gs on the PATH)
+    pub ghostscript_path: Option<String>,
+    /// Milliseconds the system printer list is reused (0 = no caching)
+    pub printer_cache_ttl_ms: Option<u32>,
+    /// Fail jobs for network printers reached without TLS
+    pub require_encrypted_transport: Option<bool>,
+}
+
+/// Cumulative spool latency histogram bucket
+#[napi(object)]
+pub struct LatencyBucket {
+    pub le: f64,
+    pub count: f64,
+}
+
+/// Spool latency histogram (seconds)
+#[napi(object)]
+pub struct LatencyHistogram {
+    pub buckets: Vec<LatencyBucket>,
+    pub count: f64,
+    pub sum: f64,
+}
+
+/// Point-in-time print health metrics
+#[napi(object)]
+pub struct MetricsSnapshot {
+    pub jobs_submitted: f64,
+    pub jobs_completed: f64,
+    pub jobs_failed: f64,
+    pub queue_depth: f64,
+    pub spool_latency: LatencyHistogram,
+}
+
+/// Log event delivered to JavaScript
+#[napi(object)]
+#[derive(Clone)]
+pub struct LogRecord {
+    pub level: String,
+    pub target: String,
+    pub message: String,
+    pub fields: HashMap<String, String>,
+    pub timestamp: f64,
+}
+
+/// Legacy job status interface for backward compatibility
+#[napi(object)]
+pub struct JobStatus {
+    pub id: f64,
+    pub printer_name: String,
+    pub file_path: String,
+    pub job_name: Option<String>,
+    pub status: String,
+    pub error_message: Option<String>,
+    pub age_seconds: u32,
+}
+
+/// Printer information
+#[napi(object)]
+pub struct PrinterInfo {
+    pub name: String,
+    /// Hash of the system name and URI, stable across renames
+    pub printer_id: String,
+    pub system_name: String,
+    pub driver_name: String,
+    pub uri: String,
+    pub port_name: String,
+    pub processor: String,
+    pub data_type: String,
+    pub description: String,
+    pub location: String,
+    pub is_default: bool,
+    pub is_shared: bool,
+    pub state: String,
+    pub state_reasons: Vec<String>,
+    pub normalized_state_reasons: Vec<String>,
+    pub offline_since: Option<f64>,
+}
+
+/// Values a printer supports for job attributes, for N-API
+#[napi(object)]
+pub struct PrinterCapabilities {
+    /// Supported values by job attribute name, e.g. "sides"
+    pub supported: HashMap<String, Vec<String>>,
+    pub copies_min: Option<i32>,
+    pub copies_max: Option<i32>,
+    pub default_color_mode: Option<String>,
+    /// Finishings the printer reports, e.g. "staple-top-left"
+    pub finishings: Vec<String>,
+    /// Raw properties jobs may set with strict option checking
+    pub supported_options: Vec<String>,
+}
+
+/// Document formats and languages a printer accepts, for N-API
+#[napi(object)]
+pub struct SupportedFormats {
+    /// "pdf", "postscript", "pcl" or "zpl", most preferred first
+    pub languages: Vec<String>,
+    /// Language to send documents in, if the printer interprets any
+    pub preferred: Option<String>,
+    /// Media type of the preferred language
+    pub preferred_media_type: Option<String>,
+    /// Media types the printer lists over IPP
+    pub document_formats: Vec<String>,
+    /// "ipp", "driver", "simulated" or "unknown"
+    pub source: String,
+}
+
+/// Consumable supply level for N-API
+#[napi(object)]
+pub struct PrinterSupply {
+    pub name: String,
+    #[napi(js_name = "type")]
+    pub supply_type: String,
+    pub color: Option<String>,
+    pub level: Option<u32>,
+    #[napi(js_name = "lowThreshold")]
+    pub low_threshold: Option<u32>,
+    #[napi(js_name = "isLow")]
+    pub is_low: bool,
+}
+
+/// Tracked jobs by state for N-API
+#[napi(object)]
+pub struct JobStateCounts {
+    pub pending: u32,
+    pub paused: u32,
+    pub processing: u32,
+    pub completed: u32,
+    pub cancelled: u32,
+    pub failed: u32,
+}
+
+/// Printer status in a health report for N-API
+#[napi(object)]
+pub struct PrinterHealth {
+    pub name: String,
+    pub online: bool,
+    pub blocking_reasons: Vec<String>,
+}
+
+/// Library health report for N-API
+#[napi(object)]
+pub struct HealthReport {
+    pub healthy: bool,
+    pub accepting_jobs: bool,
+    pub spooler_reachable: bool,
+    pub spooler_error: Option<String>,
+    pub monitoring_active: bool,
+    pub monitoring_running: bool,
+    pub jobs: JobStateCounts,
+    pub active_workers: u32,
+    pub worker_pool_size: Option<u32>,  // Unset when unlimited
+    pub worker_saturation: Option<f64>, // active / pool size, when limited
+    pub printers: Vec<PrinterHealth>,
+}
+
+/// Printer preferences (Windows DEVMODE) for N-API
+#[napi(object)]
+pub struct PrinterPreferences {
+    pub orientation: Option<String>, // "portrait" | "landscape"
+    #[napi(js_name = "paperSize")]
+    pub paper_size: Option<i32>,
+    #[napi(js_name = "formName")]
+    pub form_name: Option<String>,
+    pub quality: Option<String>, // "draft" | "low" | "medium" | "high"
+    pub resolution: Option<u32>, // DPI, instead of a quality level
+    pub color: Option<bool>,
+    pub duplex: Option<String>, // "simplex" | "long-edge" | "short-edge"
+}
+
+/// Connection options for SNMP queries
+#[cfg(feature = "snmp")]
+#[napi(object)]
+pub struct SnmpQueryOptions {
+    pub community: Option<String>,
+    pub port: Option<u32>,
+    pub version: Option<String>, // "1" or "2c"
+    pub timeout_ms: Option<u32>,
+    pub retries: Option<u32>,
+}
+
+/// Printer MIB alert for N-API
+#[cfg(feature = "snmp")]
+#[napi(object)]
+pub struct SnmpAlert {
+    pub severity: String,
+    pub group: Option<f64>,
+    pub code: Option<f64>,
+    pub description: String,
+}
+
+/// SNMP printer status for N-API
+#[cfg(feature = "snmp")]
+#[napi(object)]
+pub struct SnmpPrinterStatus {
+    pub description: Option<String>,
+    pub device_status: String,
+    pub printer_status: String,
+    pub state_reasons: Vec<String>,
+    pub page_count: Option<f64>,
+    pub alerts: Vec<SnmpAlert>,
+    pub supplies: Vec<PrinterSupply>,
+}
+
+/// Build N-API PrinterInfo from a resolved printer
+fn printer_info_from(printer: &printers::common::base::printer::Printer) -> PrinterInfo {
+    convert_printer_info(crate::core::PrinterInfo::from(printer))
+}
+
+/// Convert core PrinterInfo to N-API PrinterInfo
+fn convert_printer_info(info: crate::core::PrinterInfo) -> PrinterInfo {
+    PrinterInfo {
+        name: info.name,
+        printer_id: info.printer_id,
+        system_name: info.system_name,
+        driver_name: info.driver_name,
+        uri: info.uri,
+        port_name: info.port_name,
+        processor: info.processor,
+        data_type: info.data_type,
+        description: info.description,
+        location: info.location,
+        is_default: info.is_default,
+        is_shared: info.is_shared,
+        state: info.state,
+        state_reasons: info.state_reasons,
+        normalized_state_reasons: info.normalized_state_reasons,
+        offline_since: info.offline_since.map(to_unix_secs),
+    }
+}
+
+/// Printer class for Node.js
+#[napi]
+pub struct Printer {
+    /// The resolved printer, reused until the printer cache goes stale
+    handle: PrinterHandle,
+}
+
+impl Printer {
+    /// A Printer for `name`, or None if there is no such printer
+    fn resolve(name: String) -> Option<Printer> {
+        let handle = PrinterHandle::new(name);
+        handle.get()?;
+        Some(Printer { handle })
+    }
+
+    /// Jobs of the resolved printer, converted for JavaScript
+    fn jobs(
+        &self,
+        f: impl FnOnce(&printers::common::base::printer::Printer) -> Vec<crate::core::PrinterJob>,
+    ) -> Vec<PrinterJob> {
+        self.handle
+            .get()
+            .map(|printer| f(&printer).into_iter().map(convert_printer_job).collect())
+            .unwrap_or_default()
+    }
+}
+
+#[napi]
+impl Printer {
+    /// Create a new printer instance
+    #[napi(constructor)]
+    pub fn new(name: String) -> Result<Self> {
+        let message = format!("Printer '{}' not found", name);
+        Printer::resolve(name).ok_or_else(|| Error::new(Status::InvalidArg, message))
+    }
+
+    /// Get printer name
+    #[napi(getter)]
+    pub fn name(&self) -> String {
+        self.handle.name().to_string()
+    }
+
+    /// Hash of the system name and URI, stable across renames. Undefined
+    /// if the printer no longer exists.
+    #[napi(getter)]
+    pub fn printer_id(&self) -> Option<String> {
+        self.handle
+            .get()
+            .map(|printer| PrinterCore::printer_id(&printer))
+    }
+
+    /// Get printer information
+    #[napi]
+    pub fn get_info(&self) -> Result<PrinterInfo> {
+        if let Some(printer) = self.handle.get() {
+            Ok(printer_info_from(&printer))
+        } else {
+            Err(Error::new(
+                Status::InvalidArg,
+                format!("Printer '{}' not found", self.handle.name()),
+            ))
+        }
+    }
+
+    /// Enumerate the system again and return the printer's current
+    /// information, or null if it no longer exists
+    #[napi]
+    pub fn refresh(&self) -> Option<PrinterInfo> {
+        self.handle
+            .refresh()
+            .map(|printer| printer_info_from(&printer))
+    }
+
+    /// Check if printer exists
+    #[napi]
+    pub fn exists(&self) -> bool {
+        self.handle.get().is_some()
+    }
+
+    /// Print a file (async)
+    #[napi]
+    pub fn print_file(
+        &self,
+        file_path: String,
+        job_properties: Option<HashMap<String, String>>,
+        wait_for_completion: Option<bool>,
+    ) -> AsyncTask<PrintTask> {
+        let job_options = job_properties.map(PrinterJobOptions::from_map);
+        AsyncTask::new(PrintTask {
+            printer_name: self.name(),
+            file_path,
+            job_options,
+            wait_for_completion: wait_for_completion.unwrap_or(true), // Default to true
+        })
+    }
+
+    /// Print raw bytes (async)
+    #[napi]
+    pub fn print_bytes(
+        &self,
+        data: Buffer,
+        job_properties: Option<HashMap<String, String>>,
+        wait_for_completion: Option<bool>,
+    ) -> AsyncTask<PrintBytesTask> {
+        let job_options = job_properties.map(PrinterJobOptions::from_map);
+        AsyncTask::new(PrintBytesTask {
+            printer_name: self.name(),
+            data: data.to_vec(),
+            job_options,
+            wait_for_completion: wait_for_completion.unwrap_or(true), // Default to true
+        })
+    }
+
+    /// Print a generated diagnostic page (async)
+    #[napi]
+    pub fn print_test_page(
+        &self,
+        job_properties: Option<HashMap<String, String>>,
+        wait_for_completion: Option<bool>,
+    ) -> AsyncTask<PrintTestPageTask> {
+        let job_options = job_properties.map(PrinterJobOptions::from_map);
+        AsyncTask::new(PrintTestPageTask {
+            printer_name: self.name(),
+            job_options,
+            wait_for_completion: wait_for_completion.unwrap_or(true), // Default to true
+        })
+    }
+
+    /// Get the printer's pending and processing jobs. Empty if the printer
+    /// no longer exists.
+    #[napi]
+    pub fn get_active_jobs(&self) -> Vec<PrinterJob> {
+        self.jobs(|printer| printer.get_active_jobs())
+    }
+
+    /// Get the printer's completed, cancelled and failed jobs, most recent first
+    #[napi]
+    pub fn get_job_history(&self, limit: Option<u32>) -> Vec<PrinterJob> {
+        self.jobs(|printer| printer.get_job_history(limit.map(|limit| limit as usize)))
+    }
+
+    /// Get all of the printer's tracked jobs
+    #[napi]
+    pub fn get_all_jobs(&self) -> Vec<PrinterJob> {
+        self.jobs(|printer| printer.get_all_jobs())
+    }
+
+    /// Get a job by ID, or null if it isn't one of this printer's
+    #[napi]
+    pub fn get_job(&self, job_id: f64) -> Option<PrinterJob> {
+        self.handle
+            .get()?
+            .get_job(job_id as u64)
+            .map(convert_printer_job)
+    }
+
+    /// Remove the printer's completed and cancelled jobs created at least
+    /// `max_age_seconds` ago, returning how many were removed
+    #[napi]
+    pub fn cleanup_old_jobs(&self, max_age_seconds: u32) -> u32 {
+        self.handle.get().map_or(0, |printer| {
+            printer.cleanup_old_jobs(u64::from(max_age_seconds))
+        })
+    }
+}
+
+/// Find a printer by name
+#[napi]
+pub fn find_printer_by_name(name: String) -> Option<Printer> {
+    Printer::resolve(name)
+}
+
+/// Get a printer's information by name, without creating a Printer
+#[napi]
+pub fn get_printer_info(name: String) -> Option<PrinterInfo> {
+    PrinterCore::find_printer_by_name(&name).map(|printer| printer_info_from(&printer))
+}
+
+/// Check if a printer exists by name
+#[napi]
+pub fn printer_exists(name: String) -> bool {
+    PrinterCore::printer_exists(&name)
+}
+
+/// Get all available printer names
+#[napi]
+pub fn get_all_printer_names() -> Vec<String> {
+    PrinterCore::get_all_printer_names()
+}
+
+/// Forget the cached printer list so the next lookup enumerates the system
+#[napi]
+pub fn refresh_printers() {
+    PrinterCore::refresh_printers()
+}
+
+/// Get all available printers. Attributes that need a query to the
+/// printer, like capabilities, are left out; fetch them per printer with
+/// getPrinterCapabilities.
+#[napi]
+pub fn get_all_printers() -> Vec<PrinterInfo> {
+    PrinterCore::get_all_printers()
+        .iter()
+        .map(printer_info_from)
+        .collect()
+}
+
+/// Get printers whose location contains the given text, ignoring case
+#[napi]
+pub fn get_printers_by_location(location: String) -> Vec<PrinterInfo> {
+    PrinterCore::get_printers_by_location(&location)
+        .iter()
+        .map(printer_info_from)
+        .collect()
+}
+
+/// Get the available printers with a tag, ignoring case
+#[napi]
+pub fn get_printers_by_tag(tag: String) -> Vec<PrinterInfo> {
+    PrinterCore::get_printers_by_tag(&tag)
+        .iter()
+        .map(printer_info_from)
+        .collect()
+}
+
+/// Print a file using printer name (async)
+#[cfg(not(feature = "async-core"))]
+#[napi]
+pub fn print_file(
+    printer_name: String,
+    file_path: String,
+    job_properties: Option<HashMap<String, String>>,
+    wait_for_completion: Option<bool>,
+) -> AsyncTask<PrintTask> {
+    let job_options = job_properties.map(PrinterJobOptions::from_map);
+    AsyncTask::new(PrintTask {
+        printer_name,
+        file_path,
+        job_options,
+        wait_for_completion: wait_for_completion.unwrap_or(true), // Default to true
+    })
+}
+
+/// Print a file to several printers, reading it only once (async)
+#[napi]
+pub fn print_to_many(
+    printer_names: Vec<String>,
+    file_path: String,
+    job_properties: Option<HashMap<String, String>>,
+    wait_for_completion: Option<bool>,
+) -> AsyncTask<PrintToManyTask> {
+    let job_options = job_properties.map(PrinterJobOptions::from_map);
+    AsyncTask::new(PrintToManyTask {
+        printer_names,
+        file_path,
+        job_options,
+        wait_for_completion: wait_for_completion.unwrap_or(true), // Default to true
+    })
+}
+
+/// Print raw bytes using printer name (async)
+#[cfg(not(feature = "async-core"))]
+#[napi]
+pub fn print_bytes(
+    printer_name: String,
+    data: Buffer,
+    job_properties: Option<HashMap<String, String>>,
+    wait_for_completion: Option<bool>,
+) -> AsyncTask<PrintBytesTask> {
+    let job_options = job_properties.map(PrinterJobOptions::from_map);
+    AsyncTask::new(PrintBytesTask {
+        printer_name,
+        data: data.to_vec(),
+        job_options,
+        wait_for_completion: wait_for_completion.unwrap_or(true), // Default to true
+    })
+}
+
+/// Print a file using printer name, waiting for it on the async runtime
+#[cfg(feature = "async-core")]
+#[napi]
+pub async fn print_file(
+    printer_name: String,
+    file_path: String,
+    job_properties: Option<HashMap<String, String>>,
+    wait_for_completion: Option<bool>,
+) -> Result<f64> {
+    let job_options = job_properties.map(PrinterJobOptions::from_map);
+    let job_id = PrinterCore::print_file_async(&printer_name, &file_path, job_options)
+        .await
+        .map_err(print_file_error)?;
+    if wait_for_completion.unwrap_or(true) {
+        PrinterCore::wait_for_job(job_id, None).await;
+        check_job_timeout(job_id)?;
+    }
+    Ok(job_id as f64)
+}
+
+/// Print raw bytes using printer name, waiting for them on the async runtime
+#[cfg(feature = "async-core")]
+#[napi]
+pub async fn print_bytes(
+    printer_name: String,
+    data: Buffer,
+    job_properties: Option<HashMap<String, String>>,
+    wait_for_completion: Option<bool>,
+) -> Result<f64> {
+    let job_options = job_properties.map(PrinterJobOptions::from_map);
+    let job_id = PrinterCore::print_bytes_async(&printer_name, data.to_vec(), job_options)
+        .await
+        .map_err(print_bytes_error)?;
+    if wait_for_completion.unwrap_or(true) {
+        PrinterCore::wait_for_job(job_id, None).await;
+        check_job_timeout(job_id)?;
+    }
+    Ok(job_id as f64)
+}
+
+/// Print a generated diagnostic page using printer name (async)
+#[napi]
+pub fn print_test_page(
+    printer_name: String,
+    job_properties: Option<HashMap<String, String>>,
+    wait_for_completion: Option<bool>,
+) -> AsyncTask<PrintTestPageTask> {
+    let job_options = job_properties.map(PrinterJobOptions::from_map);
+    AsyncTask::new(PrintTestPageTask {
+        printer_name,
+        job_options,
+        wait_for_completion: wait_for_completion.unwrap_or(true), // Default to true
+    })
+}
+
+/// Check a file submission without printing it (async)
+#[napi]
+pub fn dry_run_print_file(
+    printer_name: String,
+    file_path: String,
+    job_properties: Option<HashMap<String, String>>,
+) -> AsyncTask<DryRunTask> {
+    AsyncTask::new(DryRunTask {
+        printer_name,
+        payload: PrintPayload::File(file_path),
+        job_options: job_properties.map(PrinterJobOptions::from_map),
+    })
+}
+
+/// Check a raw bytes submission without printing it (async)
+#[napi]
+pub fn dry_run_print_bytes(
+    printer_name: String,
+    data: Buffer,
+    job_properties: Option<HashMap<String, String>>,
+) -> AsyncTask<DryRunTask> {
+    AsyncTask::new(DryRunTask {
+        printer_name,
+        payload: PrintPayload::Bytes(data.to_vec()),
+        job_options: job_properties.map(PrinterJobOptions::from_map),
+    })
+}
+
+/// Resolve the options a job would be sent with, after applying the
+/// alias, printer and library-wide defaults (async)
+#[napi]
+pub fn resolve_job_options(
+    printer_name: String,
+    job_properties: Option<HashMap<String, String>>,
+) -> AsyncTask<ResolveJobOptionsTask> {
+    AsyncTask::new(ResolveJobOptionsTask {
+        printer_name,
+        job_options: job_properties.map(PrinterJobOptions::from_map),
+    })
+}
+
+/// Estimate the pages, sheets and color of printing a file (async)
+#[napi]
+pub fn estimate_job(
+    printer_name: String,
+    file_path: String,
+    job_properties: Option<HashMap<String, String>>,
+) -> AsyncTask<EstimateJobTask> {
+    AsyncTask::new(EstimateJobTask {
+        printer_name,
+        payload: PrintPayload::File(file_path),
+        job_options: job_properties.map(PrinterJobOptions::from_map),
+    })
+}
+
+/// Estimate the pages, sheets and color of printing raw bytes (async)
+#[napi]
+pub fn estimate_bytes_job(
+    printer_name: String,
+    data: Buffer,
+    job_properties: Option<HashMap<String, String>>,
+) -> AsyncTask<EstimateJobTask> {
+    AsyncTask::new(EstimateJobTask {
+        printer_name,
+        payload: PrintPayload::Bytes(data.to_vec()),
+        job_options: job_properties.map(PrinterJobOptions::from_map),
+    })
+}
+
+/// Extract the text of a PDF, PostScript or plain text file (async)
+#[napi]
+pub fn extract_text(file_path: String) -> AsyncTask<ExtractTextTask> {
+    AsyncTask::new(ExtractTextTask {
+        payload: PrintPayload::File(file_path),
+    })
+}
+
+/// Extract the text of a PDF, PostScript or plain text document's bytes
+/// (async)
+#[napi]
+pub fn extract_text_bytes(data: Buffer) -> AsyncTask<ExtractTextTask> {
+    AsyncTask::new(ExtractTextTask {
+        payload: PrintPayload::Bytes(data.to_vec()),
+    })
+}
+
+/// Print a file and wait for it to finish on the calling thread (blocking)
+#[napi]
+pub fn print_file_sync(
+    printer_name: String,
+    file_path: String,
+    job_properties: Option<HashMap<String, String>>,
+    timeout_ms: Option<u32>,
+) -> Result<f64> {
+    let job_options = job_properties.map(PrinterJobOptions::from_map);
+    let job_id = submit_print_file(&printer_name, &file_path, job_options)?;
+    wait_for_job_sync(job_id, timeout_ms)
+}
+
+/// Print raw bytes and wait for them to finish on the calling thread (blocking)
+#[napi]
+pub fn print_bytes_sync(
+    printer_name: String,
+    data: Buffer,
+    job_properties: Option<HashMap<String, String>>,
+    timeout_ms: Option<u32>,
+) -> Result<f64> {
+    let job_options = job_properties.map(PrinterJobOptions::from_map);
+    let job_id = submit_print_bytes(&printer_name, &data, job_options)?;
+    wait_for_job_sync(job_id, timeout_ms)
+}
+
+/// Check whether a printer's device is reachable (async)
+#[napi]
+pub fn is_printer_online(printer_name: String) -> AsyncTask<OnlineCheckTask> {
+    AsyncTask::new(OnlineCheckTask { printer_name })
+}
+
+/// Check library health: spooler reachability, tracked jobs, state
+/// monitoring, worker pool usage and printer status (async)
+#[napi]
+pub fn health_check(printer_names: Option<Vec<String>>) -> AsyncTask<HealthCheckTask> {
+    AsyncTask::new(HealthCheckTask { printer_names })
+}
+
+/// Get ink/toner supply levels for a printer (async)
+#[napi]
+pub fn get_printer_supplies(printer_name: String) -> AsyncTask<SuppliesTask> {
+    AsyncTask::new(SuppliesTask { printer_name })
+}
+
+/// Get the values a printer supports for job attributes (async; null if it
+/// doesn't report them)
+#[napi]
+pub fn get_printer_capabilities(printer_name: String) -> AsyncTask<CapabilitiesTask> {
+    AsyncTask::new(CapabilitiesTask { printer_name })
+}
+
+/// Get the page description languages a printer accepts (async)
+#[napi]
+pub fn get_printer_supported_formats(printer_name: String) -> AsyncTask<SupportedFormatsTask> {
+    AsyncTask::new(SupportedFormatsTask { printer_name })
+}
+
+/// Get the current user's default preferences for a printer (async, Windows only)
+#[napi]
+pub fn get_printer_preferences(printer_name: String) -> AsyncTask<PreferencesTask> {
+    AsyncTask::new(PreferencesTask {
+        printer_name,
+        update: None,
+    })
+}
+
+/// Update the current user's default preferences for a printer (async, Windows only)
+#[napi]
+pub fn set_printer_preferences(
+    printer_name: String,
+    preferences: PrinterPreferences,
+) -> Result<AsyncTask<PreferencesTask>> {
+    Ok(AsyncTask::new(PreferencesTask {
+        printer_name,
+        update: Some(parse_printer_preferences(preferences)?),
+    }))
+}
+
+/// Query a networked printer's status, counters, alerts and supplies over SNMP (async)
+#[cfg(feature = "snmp")]
+#[napi]
+pub fn query_printer_snmp(
+    host: String,
+    options: Option<SnmpQueryOptions>,
+) -> Result<AsyncTask<SnmpQueryTask>> {
+    let mut snmp_options = crate::snmp::SnmpOptions::default();
+    if let Some(options) = options {
+        if let Some(community) = options.community {
+            snmp_options.community = community;
+        }
+        if let Some(port) = options.port {
+            snmp_options.port = u16::try_from(port)
+                .map_err(|_| Error::new(Status::InvalidArg, "Invalid SNMP port"))?;
+        }
+        if let Some(version) = options.version {
+            snmp_options.version = match version.as_str() {
+                "1" => crate::snmp::SnmpVersion::V1,
+                "2c" => crate::snmp::SnmpVersion::V2c,
+                _ => {
+                    return Err(Error::new(
+                        Status::InvalidArg,
+                        format!("Unsupported SNMP version: {}", version),
+                    ))
+                }
+            };
+        }
+        if let Some(timeout_ms) = options.timeout_ms {
+            snmp_options.timeout = std::time::Duration::from_millis(u64::from(timeout_ms.max(1)));
+        }
+        if let Some(retries) = options.retries {
+            snmp_options.retries = retries;
+        }
+    }
+
+    Ok(AsyncTask::new(SnmpQueryTask {
+        host,
+        options: snmp_options,
+    }))
+}
+
+/// Get the status of a print job (new format)
+#[napi]
+pub fn get_printer_job(job_id: f64) -> Option<PrinterJob> {
+    PrinterCore::get_job_status(job_id as u64).map(convert_printer_job)
+}
+
+/// Serialize a printer's information to JSON, or null if it doesn't exist
+#[napi]
+pub fn printer_info_to_json(printer_name: String) -> Result<Option<String>> {
+    PrinterCore::find_printer_by_name(&printer_name)
+        .map(|printer| crate::core::printer_info_to_json(&(&printer).into()))
+        .transpose()
+        .map_err(|e| Error::new(Status::GenericFailure, e))
+}
+
+/// Parse printer information serialized with printerInfoToJson
+#[napi]
+pub fn printer_info_from_json(json: String) -> Result<PrinterInfo> {
+    crate::core::printer_info_from_json(&json)
+        .map(convert_printer_info)
+        .map_err(|e| Error::new(Status::InvalidArg, e))
+}
+
+/// Serialize a tracked job to JSON, or null if it isn't tracked
+#[napi]
+pub fn printer_job_to_json(job_id: f64) -> Result<Option<String>> {
+    PrinterCore::get_job_status(job_id as u64)
+        .map(|job| crate::core::printer_job_to_json(&job))
+        .transpose()
+        .map_err(|e| Error::new(Status::GenericFailure, e))
+}
+
+/// ID of the process that issued a job ID, from the job registry
+#[napi]
+pub fn get_job_process(job_id: f64) -> Result<Option<u32>> {
+    PrinterCore::job_process(job_id as u64).map_err(|e| Error::new(Status::GenericFailure, e))
+}
+
+/// Parse a job serialized with printerJobToJson or written to a JSONL
+/// export
+#[napi]
+pub fn printer_job_from_json(json: String) -> Result<PrinterJob> {
+    crate::core::printer_job_from_json(&json)
+        .map(convert_printer_job)
+        .map_err(|e| Error::new(Status::InvalidArg, e))
+}
+
+/// Get the status of a print job (legacy format for backward compatibility)
+#[napi]
+pub fn get_job_status(job_id: f64) -> Option<JobStatus> {
+    if let Some(job) = PrinterCore::get_job_status(job_id as u64) {
+        // Convert new format back to legacy format
+        let legacy_status = match job.state {
+            crate::core::PrinterJobState::PENDING => "queued",
+            crate::core::PrinterJobState::PROCESSING => "printing",
+            crate::core::PrinterJobState::COMPLETED => "completed",
+            crate::core::PrinterJobState::CANCELLED | crate::core::PrinterJobState::FAILED => {
+                "failed"
+            }
+            crate::core::PrinterJobState::HELD_FOR_APPROVAL => "held",
+            _ => "unknown",
+        };
+
+        Some(JobStatus {
+            id: job.id as f64,
+            printer_name: job.printer_name,
+            file_path: format!("Job: {}", job.name), // Approximate file_path from job name
+            job_name: Some(job.name),
+            status: legacy_status.to_string(),
+            error_message: job.error_message,
+            age_seconds: job
+                .created_at
+                .elapsed()
+                .unwrap_or(std::time::Duration::from_secs(0))
+                .as_secs() as u32,
+        })
+    } else {
+        None
+    }
+}
+
+/// Convert SystemTime to Unix timestamp in seconds
+fn to_unix_secs(time: std::time::SystemTime) -> f64 {
+    time.duration_since(std::time::SystemTime::UNIX_EPOCH)
+        .unwrap_or(std::time::Duration::from_secs(0))
+        .as_secs() as f64
+}
+
+/// Convert N-API PrinterPreferences to spooler preferences
+fn parse_printer_preferences(
+    preferences: PrinterPreferences,
+) -> Result<crate::spooler::PrinterPreferences> {
+    use crate::spooler::{Duplex, Orientation, PrintQuality};
+
+    let invalid = |field: &str, value: &str| {
+        Error::new(
+            Status::InvalidArg,
+            format!("Invalid {} preference: {}", field, value),
+        )
+    };
+    let orientation = match preferences.orientation {
+        Some(value) => {
+            Some(Orientation::parse(&value).ok_or_else(|| invalid("orientation", &value))?)
+        }
+        None => None,
+    };
+    let duplex = match preferences.duplex {
+        Some(value) => Some(Duplex::parse(&value).ok_or_else(|| invalid("duplex", &value))?),
+        None => None,
+    };
+    let quality = match (preferences.quality, preferences.resolution) {
+        (Some(_), Some(_)) => {
+            return Err(Error::new(
+                Status::InvalidArg,
+                "Set either quality or resolution, not both",
+            ))
+        }
+        (Some(value), None) => {
+            Some(PrintQuality::parse(&value).ok_or_else(|| invalid("quality", &value))?)
+        }
+        (None, Some(dpi)) => match u16::try_from(dpi) {
+            Ok(dpi) if dpi > 0 && dpi <= i16::MAX as u16 => Some(PrintQuality::Dpi(dpi)),
+            _ => return Err(invalid("resolution", &dpi.to_string())),
+        },
+        (None, None) => None,
+    };
+    let paper_size = match preferences.paper_size {
+        Some(code) => Some(
+            i16::try_from(code)
+                .ok()
+                .filter(|code| *code > 0)
+                .ok_or_else(|| invalid("paperSize", &code.to_string()))?,
+        ),
+        None => None,
+    };
+
+    Ok(crate::spooler::PrinterPreferences {
+        orientation,
+        paper_size,
+        form_name: preferences.form_name,
+        quality,
+        color: preferences.color,
+        duplex,
+    })
+}
+
+/// Convert spooler preferences to N-API PrinterPreferences
+fn convert_printer_preferences(
+    preferences: crate::spooler::PrinterPreferences,
+) -> PrinterPreferences {
+    let (quality, resolution) = match preferences.quality {
+        Some(crate::spooler::PrintQuality::Dpi(dpi)) => (None, Some(u32::from(dpi))),
+        Some(quality) => (quality.as_str().map(str::to_string), None),
+        None => (None, None),
+    };
+    PrinterPreferences {
+        orientation: preferences
+            .orientation
+            .map(|orientation| orientation.as_str().to_string()),
+        paper_size: preferences.paper_size.map(i32::from),
+        form_name: preferences.form_name,
+        quality,
+        resolution,
+        color: preferences.color,
+        duplex: preferences.duplex.map(|duplex| duplex.as_str().to_string()),
+    }
+}
+
+/// Convert core PrinterSupply to N-API PrinterSupply
+fn convert_printer_supply(supply: crate::core::PrinterSupply) -> PrinterSupply {
+    PrinterSupply {
+        is_low: supply.is_low(),
+        name: supply.name,
+        supply_type: supply.supply_type,
+        color: supply.color,
+        level: supply.level.map(u32::from),
+        low_threshold: supply.low_threshold.map(u32::from),
+    }
+}
+
+/// Convert core PrinterJob to N-API PrinterJob
+fn convert_printer_job(job: crate::core::PrinterJob) -> PrinterJob {
+    PrinterJob {
+        id: job.id as f64,
+        name: job.name,
+        state: job.state.as_string(),
+        media_type: job.media_type,
+        media_type_source: job.media_type_source.as_str().to_string(),
+        created_at: to_unix_secs(job.created_at),
+        processed_at: job.processed_at.map(to_unix_secs),
+        completed_at: job.completed_at.map(to_unix_secs),
+        printer_name: job.printer_name,
+        error_message: job.error_message,
+        age_seconds: job
+            .created_at
+            .elapsed()
+            .unwrap_or(std::time::Duration::from_secs(0))
+            .as_secs() as f64,
+        size_bytes: job.size_bytes as f64,
+        total_pages: job.total_pages,
+        pages: job.pages,
+        sheets: job.sheets,
+        owner: job.owner,
+        reprint_of: job.reprint_of.map(|id| id as f64),
+        timed_out: job.timed_out,
+        stalled: job.stalled,
+        printer_removed: job.printer_removed,
+        encrypted_transport: job.encrypted_transport,
+        metadata: (!job.job_options.metadata.is_empty()).then_some(job.job_options.metadata),
+        transitions: job
+            .transitions
+            .into_iter()
+            .map(|transition| JobTransition {
+                from: transition.from.as_string(),
+                to: transition.to.as_string(),
+                timestamp: to_unix_secs(transition.at),
+            })
+            .collect(),
+    }
+}
+
+/// A message in a job's log
+#[napi(object)]
+pub struct JobLogEntry {
+    /// Unix timestamp in seconds
+    pub timestamp: f64,
+    /// "error", "warn", "info" or "debug"
+    pub level: String,
+    pub message: String,
+}
+
+/// What happened to a job, oldest entry first, or null if it isn't tracked
+#[napi]
+pub fn get_job_log(job_id: f64) -> Option<Vec<JobLogEntry>> {
+    PrinterCore::get_job_log(job_id as u64).map(|log| {
+        log.into_iter()
+            .map(|entry| JobLogEntry {
+                timestamp: to_unix_secs(entry.timestamp),
+                level: entry.level.as_str().to_string(),
+                message: entry.message,
+            })
+            .collect()
+    })
+}
+
+/// Get all active jobs (pending or processing)
+#[napi]
+pub fn get_active_jobs() -> Vec<PrinterJob> {
+    PrinterCore::get_active_jobs()
+        .into_iter()
+        .map(convert_printer_job)
+        .collect()
+}
+
+/// Get active jobs for a specific printer
+#[napi]
+pub fn get_active_jobs_for_printer(printer_name: String) -> Vec<PrinterJob> {
+    PrinterCore::get_active_jobs_for_printer(&printer_name)
+        .into_iter()
+        .map(convert_printer_job)
+        .collect()
+}
+
+/// Get job history (completed or cancelled jobs)
+#[napi]
+pub fn get_job_history() -> Vec<PrinterJob> {
+    PrinterCore::get_job_history()
+        .into_iter()
+        .map(convert_printer_job)
+        .collect()
+}
+
+/// Get job history for a specific printer
+#[napi]
+pub fn get_job_history_for_printer(printer_name: String, limit: Option<u32>) -> Vec<PrinterJob> {
+    let query = crate::core::JobQuery {
+        limit: limit.map(|limit| limit as usize),
+        ..crate::core::JobQuery::history(Some(&printer_name))
+    };
+    PrinterCore::query_jobs(&query)
+        .jobs
+        .into_iter()
+        .map(convert_printer_job)
+        .collect()
+}
+
+/// Get all jobs for a specific printer
+#[napi]
+pub fn get_all_jobs_for_printer(printer_name: String) -> Vec<PrinterJob> {
+    PrinterCore::get_all_jobs_for_printer(&printer_name)
+        .into_iter()
+        .map(convert_printer_job)
+        .collect()
+}
+
+/// Which jobs query_jobs returns (all fields optional)
+#[napi(object)]
+pub struct JobQuery {
+    #[napi(js_name = "printerName")]
+    pub printer_name: Option<String>,
+    pub states: Option<Vec<String>>,
+    /// Jobs created at or after this time (Unix timestamp)
+    pub since: Option<f64>,
+    /// Jobs created before this time (Unix timestamp)
+    pub until: Option<f64>,
+    /// Values the job's metadata must have
+    pub metadata: Option<HashMap<String, String>>,
+    /// "created-asc", "created-desc" (default), "completed-asc" or "completed-desc"
+    pub sort: Option<String>,
+    pub offset: Option<u32>,
+    pub limit: Option<u32>,
+}
+
+/// A page of query_jobs results
+#[napi(object)]
+pub struct JobPage {
+    pub jobs: Vec<PrinterJob>,
+    /// Jobs matching the query, ignoring offset and limit
+    pub total: u32,
+}
+
+/// Filter, sort and page the tracked jobs
+#[napi]
+pub fn query_jobs(query: JobQuery) -> Result<JobPage> {
+    let from_unix_secs = |secs: f64| {
+        std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs_f64(secs.max(0.0))
+    };
+    let states = query
+        .states
+        .unwrap_or_default()
+        .iter()
+        .map(|state| {
+            crate::core::PrinterJobState::parse(state).ok_or_else(|| {
+                Error::new(Status::InvalidArg, format!("Unknown job state: {}", state))
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let sort = match query.sort {
+        Some(sort) => crate::core::JobSort::parse(&sort)
+            .ok_or_else(|| Error::new(Status::InvalidArg, format!("Unknown job sort: {}", sort)))?,
+        None => crate::core::JobSort::default(),
+    };
+
+    let page = PrinterCore::query_jobs(&crate::core::JobQuery {
+        printer_name: query.printer_name,
+        states,
+        since: query.since.map(from_unix_secs),
+        until: query.until.map(from_unix_secs),
+        metadata: query.metadata.unwrap_or_default(),
+        sort,
+        offset: query.offset.unwrap_or(0) as usize,
+        limit: query.limit.map(|limit| limit as usize),
+    });
+    Ok(JobPage {
+        jobs: page.jobs.into_iter().map(convert_printer_job).collect(),
+        total: page.total as u32,
+    })
+}
+
+/// Which jobs find_jobs returns
+#[napi(object)]
+pub struct FindJobsQuery {
+    #[napi(js_name = "printerName")]
+    pub printer_name: Option<String>,
+    /// Values the job's metadata must have
+    pub metadata: Option<HashMap<String, String>>,
+}
+
+/// Find jobs by their metadata, oldest first
+#[napi]
+pub fn find_jobs(query: FindJobsQuery) -> Vec<PrinterJob> {
+    PrinterCore::find_jobs(
+        query.printer_name.as_deref(),
+        &query.metadata.unwrap_or_default(),
+    )
+    .into_iter()
+    .map(convert_printer_job)
+    .collect()
+}
+
+/// Write the tracked jobs to a file as "jsonl" (default) or "csv", oldest
+/// first, resolving to the number of jobs written (async)
+#[napi]
+pub fn export_job_history(
+    path: String,
+    format: Option<String>,
+    since: Option<f64>,
+) -> Result<AsyncTask<ExportJobHistoryTask>> {
+    let format = match format {
+        Some(format) => crate::export::ExportFormat::parse(&format).ok_or_else(|| {
+            Error::new(
+                Status::InvalidArg,
+                format!("Unknown export format: {}", format),
+            )
+        })?,
+        None => crate::export::ExportFormat::Jsonl,
+    };
+    let since = since.map(|secs| {
+        std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs_f64(secs.max(0.0))
+    });
+    Ok(AsyncTask::new(ExportJobHistoryTask {
+        path,
+        format,
+        since,
+    }))
+}
+
+/// Webhook job lifecycle events are POSTed to
+#[napi(object)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// Key the body is signed with, sent as X-Printers-Signature
+    pub secret: Option<String>,
+    /// "job.submitted", "job.completed" and/or "job.failed" (default: all)
+    pub events: Option<Vec<String>>,
+    #[napi(js_name = "maxRetries")]
+    pub max_retries: Option<u32>,
+    #[napi(js_name = "timeoutMs")]
+    pub timeout_ms: Option<u32>,
+}
+
+/// Set the webhook job lifecycle events are sent to, or stop sending them
+#[napi]
+pub fn set_webhook(config: Option<WebhookConfig>) -> Result<()> {
+    require_owner()?;
+    let config = match config {
+        Some(config) => {
+            let events = config
+                .events
+                .unwrap_or_default()
+                .iter()
+                .map(|event| {
+                    crate::webhooks::WebhookEvent::parse(event).ok_or_else(|| {
+                        Error::new(
+                            Status::InvalidArg,
+                            format!("Unknown webhook event: {}", event),
+                        )
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let mut webhook = crate::webhooks::WebhookConfig::new(&config.url);
+            webhook.secret = config.secret;
+            webhook.events = events;
+            if let Some(max_retries) = config.max_retries {
+                webhook.max_retries = max_retries;
+            }
+            if let Some(timeout_ms) = config.timeout_ms {
+                webhook.timeout = std::time::Duration::from_millis(timeout_ms.max(1) as u64);
+            }
+            Some(webhook)
+        }
+        None => None,
+    };
+    crate::webhooks::set(config).map_err(|e| Error::new(Status::InvalidArg, e))
+}
+
+/// Print policy rule, broken by jobs that fail any condition it sets
+#[napi(object)]
+pub struct PolicyRule {
+    pub id: String,
+    /// Most pages a job may print, copies included
+    #[napi(js_name = "maxPages")]
+    pub max_pages: Option<u32>,
+    /// Media types documents may be submitted as, e.g. "image/*"
+    #[napi(js_name = "allowedFormats")]
+    pub allowed_formats: Option<Vec<String>>,
+    /// Users allowed to print in color
+    #[napi(js_name = "colorUsers")]
+    pub color_users: Option<Vec<String>>,
+    /// Words documents may not contain, matched ignoring case
+    #[napi(js_name = "bannedKeywords")]
+    pub banned_keywords: Option<Vec<String>>,
+}
+
+impl From<crate::policy::PolicyRule> for PolicyRule {
+    fn from(rule: crate::policy::PolicyRule) -> Self {
+        PolicyRule {
+            id: rule.id,
+            max_pages: rule.max_pages,
+            allowed_formats: rule.allowed_formats,
+            color_users: rule.color_users,
+            banned_keywords: rule.banned_keywords,
+        }
+    }
+}
+
+/// Replace the print policy rules jobs are checked against when submitted
+#[napi]
+pub fn set_policy_rules(rules: Vec<PolicyRule>) -> Result<()> {
+    require_owner()?;
+    let rules = rules
+        .into_iter()
+        .map(|rule| crate::policy::PolicyRule {
+            id: rule.id,
+            max_pages: rule.max_pages,
+            allowed_formats: rule.allowed_formats,
+            color_users: rule.color_users,
+            banned_keywords: rule.banned_keywords,
+        })
+        .collect();
+    crate::policy::set(rules).map_err(|e| Error::new(Status::InvalidArg, e))
+}
+
+#[napi]
+pub fn get_policy_rules() -> Vec<PolicyRule> {
+    crate::policy::rules()
+        .into_iter()
+        .map(PolicyRule::from)
+        .collect()
+}
+
+/// Aggregate pages, sheets and bytes over completed jobs
+#[napi]
+pub fn get_usage_stats(printer_name: Option<String>, since: Option<f64>) -> UsageStats {
+    let since = since.map(|secs| {
+        std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs_f64(secs.max(0.0))
+    });
+    let stats = PrinterCore::get_usage_stats(printer_name.as_deref(), since);
+
+    UsageStats {
+        jobs: stats.jobs,
+        pages: stats.pages as f64,
+        sheets: stats.sheets as f64,
+        bytes: stats.bytes as f64,
+        jobs_without_page_count: stats.jobs_without_page_count,
+    }
+}
+
+/// Cancel all unfinished jobs, optionally only for one printer
+#[napi]
+pub fn cancel_all_jobs(printer_name: Option<String>) -> Result<u32> {
+    require_owner()?;
+    Ok(PrinterCore::cancel_all_jobs(printer_name.as_deref()))
+}
+
+/// Resubmit a finished job, returning the new job's ID
+#[napi]
+pub fn reprint_job(job_id: f64) -> Result<f64> {
+    PrinterCore::reprint_job(job_id as u64)
+        .map(|id| id as f64)
+        .map_err(|e| match e {
+            PrintError::JobNotFound => coded_error(Status::InvalidArg, &e, "Job not found"),
+            PrintError::InvalidParams => {
+                coded_error(Status::InvalidArg, &e, "Job has not finished")
+            }
+            PrintError::FileNotFound => coded_error(
+                Status::GenericFailure,
+                &e,
+                "Neither a retained payload nor the original file is available",
+            ),
+            PrintError::PrinterNotFound => coded_error(Status::InvalidArg, &e, "Printer not found"),
+            PrintError::ShuttingDown => {
+                coded_error(Status::GenericFailure, &e, "Library is shutting down")
+            }
+            PrintError::SpoolQuotaExceeded => {
+                coded_error(Status::GenericFailure, &e, "Spool quota exceeded")
+            }
+            PrintError::SpoolFailed => {
+                coded_error(Status::GenericFailure, &e, "Failed to spool print data")
+            }
+            PrintError::QueueFull => queue_full_error(),
+            PrintError::UnsupportedOption(reason) => unsupported_option_error(reason),
+            PrintError::PolicyViolation(violation) => policy_violation_error(violation),
+            _ => coded_error(
+                Status::GenericFailure,
+                &e,
+                format!("Reprint failed with error code: {}", e.as_i32()),
+            ),
+        })
+}
+
+/// A failed job and the job reprinting it
+#[napi(object)]
+pub struct RequeuedJob {
+    #[napi(js_name = "jobId")]
+    pub job_id: f64,
+    #[napi(js_name = "newJobId")]
+    pub new_job_id: f64,
+}
+
+/// Jobs moved from one printer to another
+#[napi(object)]
+pub struct JobMigration {
+    /// Queued jobs now waiting for the new printer
+    pub moved: Vec<f64>,
+    /// Jobs that failed because their printer was removed, reprinted on
+    /// the new printer
+    pub requeued: Vec<RequeuedJob>,
+    /// Failed jobs that couldn't be reprinted
+    pub unavailable: Vec<f64>,
+}
+
+/// Move the jobs queued for one printer to another, and reprint there the
+/// jobs that failed because the first printer was removed
+#[napi]
+pub fn migrate_jobs(from_printer: String, to_printer: String) -> Result<JobMigration> {
+    require_owner()?;
+    let migration = PrinterCore::migrate_jobs(&from_printer, &to_printer).map_err(|e| match e {
+        PrintError::InvalidParams => coded_error(
+            Status::InvalidArg,
+            &e,
+            "Jobs must be migrated to a different printer",
+        ),
+        PrintError::PrinterNotFound => coded_error(Status::InvalidArg, &e, "Printer not found"),
+        _ => coded_error(
+            Status::GenericFailure,
+            &e,
+            format!("Migration failed with error code: {}", e.as_i32()),
+        ),
+    })?;
+    let ids = |ids: Vec<u64>| ids.into_iter().map(|id| id as f64).collect();
+    Ok(JobMigration {
+        moved: ids(migration.moved),
+        requeued: migration
+            .requeued
+            .into_iter()
+            .map(|(job_id, new_job_id)| RequeuedJob {
+                job_id: job_id as f64,
+                new_job_id: new_job_id as f64,
+            })
+            .collect(),
+        unavailable: ids(migration.unavailable),
+    })
+}
+
+/// Send a job held for approval to its printer
+#[napi]
+pub fn approve_job(job_id: f64) -> Result<()> {
+    PrinterCore::approve_job(job_id as u64).map_err(approval_error)
+}
+
+/// Cancel a job held for approval without printing it
+#[napi]
+pub fn reject_job(job_id: f64, reason: Option<String>) -> Result<()> {
+    PrinterCore::reject_job(job_id as u64, reason.as_deref()).map_err(approval_error)
+}
+
+/// Map core errors of approving or rejecting a held job to N-API errors
+fn approval_error(e: PrintError) -> Error {
+    match e {
+        PrintError::JobNotFound => coded_error(Status::InvalidArg, &e, "Job not found"),
+        PrintError::InvalidParams => {
+            coded_error(Status::InvalidArg, &e, "Job is not held for approval")
+        }
+        PrintError::FileNotFound => {
+            coded_error(Status::GenericFailure, &e, "Held job payload is missing")
+        }
+        PrintError::ShuttingDown => {
+            coded_error(Status::GenericFailure, &e, "Library is shutting down")
+        }
+        _ => coded_error(
+            Status::GenericFailure,
+            &e,
+            format!("Approval failed with error code: {}", e.as_i32()),
+        ),
+    }
+}
+
+/// Jobs waiting for approval, optionally only for one printer
+#[napi]
+pub fn get_held_jobs(printer_name: Option<String>) -> Vec<PrinterJob> {
+    PrinterCore::get_held_jobs(printer_name.as_deref())
+        .into_iter()
+        .map(convert_printer_job)
+        .collect()
+}
+
+/// Path of the payload retained for a job submitted with keepSpoolFile
+#[napi]
+pub fn get_job_spool_file(job_id: f64) -> Option<String> {
+    PrinterCore::get_job_spool_file(job_id as u64).map(|path| path.to_string_lossy().into_owned())
+}
+
+/// Remove all completed and cancelled jobs, optionally only for one printer
+#[napi]
+pub fn clear_job_history(printer_name: Option<String>) -> Result<u32> {
+    require_owner()?;
+    Ok(PrinterCore::clear_job_history(printer_name.as_deref()))
+}
+
+/// Clean up old completed/failed jobs
+#[napi]
+pub fn cleanup_old_jobs(max_age_seconds: u32) -> u32 {
+    PrinterCore::cleanup_old_jobs(max_age_seconds as u64)
+}
+
+/// Get a specific job for a printer
+#[napi]
+pub fn get_job_for_printer(printer_name: String, job_id: f64) -> Option<PrinterJob> {
+    if let Some(job) = PrinterCore::get_job_status(job_id as u64) {
+        if job.printer_name == crate::aliases::resolve(&printer_name) {
+            Some(convert_printer_job(job))
+        } else {
+            None
+        }
+    } else {
+        None
+    }
+}
+
+/// Clean up old jobs for a specific printer
+#[napi]
+pub fn cleanup_old_jobs_for_printer(printer_name: String, max_age_seconds: u32) -> u32 {
+    PrinterCore::cleanup_old_jobs_for_printer(&printer_name, max_age_seconds as u64)
+}
+
+/// Events buffered for JavaScript to drain, one queue per environment, so
+/// the main thread and each worker all receive every event instead of
+/// whichever drains first taking it. Oldest events beyond `capacity` are
+/// dropped.
+struct EnvBuffers<T: Clone> {
+    queues: std::sync::Mutex<HashMap<crate::core::EnvId, std::collections::VecDeque<T>>>,
+    capacity: usize,
+}
+
+impl<T: Clone> EnvBuffers<T> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            queues: std::sync::Mutex::new(HashMap::new()),
+            capacity,
+        }
+    }
+
+    /// Start or stop buffering for this environment, returning whether any
+    /// environment is still buffering
+    fn set_enabled(&self, enabled: bool) -> bool {
+        let mut queues = self.queues.lock().unwrap();
+        if enabled {
+            queues.entry(current_env()).or_default();
+        } else {
+            queues.remove(&current_env());
+        }
+        !queues.is_empty()
+    }
+
+    /// Stop buffering for an environment that is exiting, returning true if
+    /// it was the last one buffering
+    fn remove(&self, env: crate::core::EnvId) -> bool {
+        let mut queues = self.queues.lock().unwrap();
+        queues.remove(&env).is_some() && queues.is_empty()
+    }
+
+    fn push(&self, event: T) {
+        for queue in self.queues.lock().unwrap().values_mut() {
+            if queue.len() >= self.capacity {
+                queue.pop_front();
+            }
+            queue.push_back(event.clone());
+        }
+    }
+
+    /// Buffer an event for one environment, if it is buffering
+    fn push_to(&self, env: crate::core::EnvId, event: T) {
+        if let Some(queue) = self.queues.lock().unwrap().get_mut(&env) {
+            if queue.len() >= self.capacity {
+                queue.pop_front();
+            }
+            queue.push_back(event);
+        }
+    }
+
+    /// Take this environment's buffered events, oldest first
+    fn drain(&self) -> Vec<T> {
+        self.queues
+            .lock()
+            .unwrap()
+            .get_mut(&current_env())
+            .map(|queue| queue.drain(..).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// A job was processing for longer than the stall threshold, for JavaScript
+#[napi(object)]
+#[derive(Clone)]
+pub struct JobStalledEvent {
+    pub job_id: f64,
+    pub printer_name: String,
+    pub processing_ms: f64,
+    pub action: String, // "flag", "cancel" or "retry"
+    pub retry_job_id: Option<f64>,
+    pub timestamp: f64,
+}
+
+/// Stalled job events kept until drained; older ones are dropped
+const MAX_BUFFERED_STALL_EVENTS: usize = 256;
+
+lazy_static::lazy_static! {
+    static ref STALL_EVENT_BUFFER: EnvBuffers<JobStalledEvent> =
+        EnvBuffers::new(MAX_BUFFERED_STALL_EVENTS);
+}
+
+/// Buffer stalled job events for this environment to drain
+#[napi]
+pub fn set_job_stalled_buffering(enabled: bool) {
+    if !STALL_EVENT_BUFFER.set_enabled(enabled) {
+        PrinterCore::set_job_stalled_callback(None);
+        return;
+    }
+
+    PrinterCore::set_job_stalled_callback(Some(std::sync::Arc::new(
+        |event: &crate::core::JobStalledEvent| {
+            STALL_EVENT_BUFFER.push(JobStalledEvent {
+                job_id: event.job_id as f64,
+                printer_name: event.printer_name.clone(),
+                processing_ms: event.processing_for.as_millis() as f64,
+                action: event.action.as_str().to_string(),
+                retry_job_id: event.retry_job_id.map(|id| id as f64),
+                timestamp: to_unix_secs(event.timestamp),
+            });
+        },
+    )));
+}
+
+/// Take all buffered stalled job events, oldest first
+#[napi]
+pub fn drain_job_stalled_events() -> Vec<JobStalledEvent> {
+    STALL_EVENT_BUFFER.drain()
+}
+
+thread_local! {
+    /// ID of the JavaScript environment running on this thread
+    static ENV_ID: std::cell::Cell<crate::core::EnvId> = const { std::cell::Cell::new(0) };
+}
+
+fn current_env() -> crate::core::EnvId {
+    ENV_ID.with(|id| id.get())
+}
+
+/// Fail unless this environment may change library-wide state
+fn require_owner() -> Result<()> {
+    PrinterCore::check_owner(current_env()).map_err(|e| Error::new(Status::GenericFailure, e))
+}
+
+/// Throw if another environment is attached, for library-wide settings kept
+/// in JavaScript
+#[napi]
+pub fn check_owner() -> Result<()> {
+    require_owner()
+}
+
+/// Register an environment cleanup hook so background threads are torn down
+/// when the Node/Deno/Bun process (or worker) exits without calling shutdown()
+#[napi(module_exports)]
+pub fn module_exports(_exports: Object, mut env: Env) -> Result<()> {
+    let id = PrinterCore::env_attached();
+    ENV_ID.with(|current| current.set(id));
+    env.add_env_cleanup_hook(id, env_exiting)?;
+    Ok(())
+}
+
+/// Drop an exiting environment's event buffers, and its hold on monitoring
+/// and on the library
+fn env_exiting(env: crate::core::EnvId) {
+    if STALL_EVENT_BUFFER.remove(env) {
+        PrinterCore::set_job_stalled_callback(None);
+    }
+    if CONFIG_EVENT_BUFFER.remove(env) {
+        crate::aliases::set_change_callback(None);
+    }
+    if LOG_BUFFER.remove(env) {
+        crate::logging::set_log_callback(None);
+    }
+    #[cfg(feature = "ghostscript")]
+    CONVERSION_PROGRESS_BUFFER.remove(env);
+    PrinterCore::env_detached(env);
+}
+
+/// Which environment owns library-wide state, for N-API
+#[napi(object)]
+pub struct Attachment {
+    /// JavaScript environments (main thread, workers) using the library
+    pub environments: u32,
+    /// Whether this environment owns the library
+    pub attached: bool,
+    /// Whether any environment owns the library
+    pub owned: bool,
+}
+
+/// Make this environment the owner of library-wide state
+#[napi]
+pub fn attach() -> Result<()> {
+    PrinterCore::attach(current_env()).map_err(|e| Error::new(Status::GenericFailure, e))
+}
+
+/// Give up ownership; false if this environment didn't own the library
+#[napi]
+pub fn detach() -> bool {
+    PrinterCore::detach(current_env())
+}
+
+/// Number of environments using the library and which one owns it
+#[napi]
+pub fn get_attachment() -> Attachment {
+    let owner = PrinterCore::owner_env();
+    Attachment {
+        environments: PrinterCore::attached_envs() as u32,
+        attached: owner == Some(current_env()),
+        owned: owner.is_some(),
+    }
+}
+
+/// Shutdown the library and cleanup all background threads
+#[napi]
+pub fn shutdown(options: Option<ShutdownOptions>) -> Result<ShutdownSummary> {
+    require_owner()?;
+    let options = options.unwrap_or(ShutdownOptions {
+        mode: None,
+        timeout_ms: None,
+    });
+    let mode = match options.mode.as_deref() {
+        Some(mode) => crate::core::ShutdownMode::parse(mode).ok_or_else(|| {
+            Error::new(
+                Status::InvalidArg,
+                format!("Unknown shutdown mode: {}", mode),
+            )
+        })?,
+        None => crate::core::ShutdownMode::Graceful,
+    };
+    let timeout = options
+        .timeout_ms
+        .map(|ms| std::time::Duration::from_millis(u64::from(ms)))
+        .unwrap_or(crate::core::DEFAULT_SHUTDOWN_TIMEOUT);
+
+    let summary = PrinterCore::shutdown_library_with(mode, timeout);
+    Ok(ShutdownSummary {
+        completed: summary.completed,
+        cancelled: summary.cancelled,
+        timed_out: summary.timed_out,
+    })
+}
+
+// ===== PRINTER ALIAS N-API BINDINGS =====
+
+/// A logical printer name mapped to a concrete printer, for JavaScript
+#[napi(object)]
+pub struct PrinterAlias {
+    pub name: String,
+    pub printer: String,
+    pub default_options: HashMap<String, String>,
+}
+
+/// Define a printer alias, replacing any existing one with the same name
+#[napi]
+pub fn define_printer_alias(
+    name: String,
+    printer: String,
+    default_options: Option<HashMap<String, String>>,
+) -> Result<()> {
+    require_owner()?;
+    crate::aliases::define(crate::aliases::PrinterAlias {
+        name,
+        printer,
+        default_options: default_options.unwrap_or_default(),
+    })
+    .map_err(|e| Error::new(Status::InvalidArg, e))
+}
+
+/// Remove a printer alias, returning whether it existed
+#[napi]
+pub fn remove_printer_alias(name: String) -> Result<bool> {
+    require_owner()?;
+    crate::aliases::remove(&name).map_err(|e| Error::new(Status::GenericFailure, e))
+}
+
+/// Get all printer aliases, sorted by name
+#[napi]
+pub fn get_printer_aliases() -> Vec<PrinterAlias> {
+    crate::aliases::list()
+        .into_iter()
+        .map(|alias| PrinterAlias {
+            name: alias.name,
+            printer: alias.printer,
+            default_options: alias.default_options,
+        })
+        .collect()
+}
+
+/// Persist printer aliases to a file, loading it if it exists (null stops
+/// persisting). A watched file is re-read whenever it changes on disk.
+#[napi]
+pub fn set_printer_alias_file(path: Option<String>, watch: Option<bool>) -> Result<()> {
+    require_owner()?;
+    crate::aliases::set_file(path.map(std::path::PathBuf::from), watch.unwrap_or(false))
+        .map_err(|e| Error::new(Status::GenericFailure, e))
+}
+
+/// Set a printer's default job options (raw properties); empty or null
+/// options remove them
+#[napi]
+pub fn set_printer_defaults(
+    printer_name: String,
+    options: Option<HashMap<String, String>>,
+) -> Result<()> {
+    require_owner()?;
+    crate::aliases::set_printer_defaults(&printer_name, options.unwrap_or_default())
+        .map_err(|e| Error::new(Status::GenericFailure, e))
+}
+
+/// Get a printer's default job options
+#[napi]
+pub fn get_printer_defaults(printer_name: String) -> HashMap<String, String> {
+    crate::aliases::printer_defaults(&crate::aliases::resolve(&printer_name))
+}
+
+/// Add tags to a printer
+#[napi]
+pub fn tag_printer(printer_name: String, tags: Vec<String>) -> Result<()> {
+    require_owner()?;
+    crate::aliases::tag_printer(&printer_name, &tags)
+        .map_err(|e| Error::new(Status::GenericFailure, e))
+}
+
+/// Remove tags from a printer (null removes all of them)
+#[napi]
+pub fn untag_printer(printer_name: String, tags: Option<Vec<String>>) -> Result<()> {
+    require_owner()?;
+    crate::aliases::untag_printer(&printer_name, tags.as_deref())
+        .map_err(|e| Error::new(Status::GenericFailure, e))
+}
+
+/// Get a printer's tags
+#[napi]
+pub fn get_printer_tags(printer_name: String) -> Vec<String> {
+    crate::aliases::printer_tags(&crate::aliases::resolve(&printer_name))
+}
+
+/// Get the file printer aliases are persisted to, if any
+#[napi]
+pub fn get_printer_alias_file() -> Option<String> {
+    crate::aliases::file().map(|path| path.to_string_lossy().into_owned())
+}
+
+/// Re-read the printer alias file, returning whether anything changed
+#[napi]
+pub fn reload_config() -> Result<bool> {
+    require_owner()?;
+    crate::aliases::reload().map_err(|e| Error::new(Status::GenericFailure, e))
+}
+
+/// A change to the printer alias configuration, for JavaScript
+#[napi(object)]
+#[derive(Clone)]
+pub struct ConfigChangedEvent {
+    pub source: String, // "api", "file" or "reload"
+    pub aliases: Vec<String>,
+    pub printers: Vec<String>,
+    pub default_job_options: bool,
+    pub timestamp: f64,
+}
+
+/// Configuration changes kept until drained; older ones are dropped
+const MAX_BUFFERED_CONFIG_EVENTS: usize = 256;
+
+lazy_static::lazy_static! {
+    static ref CONFIG_EVENT_BUFFER: EnvBuffers<ConfigChangedEvent> =
+        EnvBuffers::new(MAX_BUFFERED_CONFIG_EVENTS);
+}
+
+/// Buffer configuration changes for this environment to drain
+#[napi]
+pub fn set_config_event_buffering(enabled: bool) {
+    if !CONFIG_EVENT_BUFFER.set_enabled(enabled) {
+        crate::aliases::set_change_callback(None);
+        return;
+    }
+
+    crate::aliases::set_change_callback(Some(std::sync::Arc::new(
+        |change: &crate::aliases::ConfigChange| {
+            CONFIG_EVENT_BUFFER.push(ConfigChangedEvent {
+                source: change.source.as_str().to_string(),
+                aliases: change.aliases.clone(),
+                printers: change.printers.clone(),
+                default_job_options: change.default_job_options,
+                timestamp: to_unix_secs(change.timestamp),
+            });
+        },
+    )));
+}
+
+/// Take all buffered configuration changes, oldest first
+#[napi]
+pub fn drain_config_events() -> Vec<ConfigChangedEvent> {
+    CONFIG_EVENT_BUFFER.drain()
+}
+
+// ===== REMOTE PRINTER N-API BINDINGS =====
+
+/// Where a remote printer forwards jobs to, for JavaScript
+#[napi(object)]
+pub struct RemotePrinterOptions {
+    /// `http://` print server base URL or `ipp://` printer URI
+    pub url: String,
+    /// Printer on the remote print server (default: the local name)
+    pub printer: Option<String>,
+    /// Bearer token for the remote print server
+    #[napi(js_name = "authToken")]
+    pub auth_token: Option<String>,
+    #[napi(js_name = "pollIntervalMs")]
+    pub poll_interval_ms: Option<u32>,
+    #[napi(js_name = "timeoutMs")]
+    pub timeout_ms: Option<u32>,
+    /// "none", "auto", "gzip" or "deflate", for IPP printer URIs
+    pub compression: Option<String>,
+    /// "none", "auto", "pwg" or "urf", for IPP printer URIs
+    pub raster: Option<String>,
+}
+
+/// A remote printer, for JavaScript
+#[napi(object)]
+pub struct RemotePrinterInfo {
+    pub name: String,
+    pub url: String,
+    pub printer: Option<String>,
+    #[napi(js_name = "pollIntervalMs")]
+    pub poll_interval_ms: u32,
+    #[napi(js_name = "timeoutMs")]
+    pub timeout_ms: u32,
+    pub compression: String,
+    pub raster: String,
+}
+
+/// Define a printer whose jobs are forwarded to another instance, replacing
+/// any existing one with the same name
+#[napi]
+pub fn define_remote_printer(name: String, options: RemotePrinterOptions) -> Result<()> {
+    require_owner()?;
+    let mut remote = crate::remote::RemotePrinter::new(&name, &options.url);
+    remote.printer = options.printer;
+    remote.auth_token = options.auth_token;
+    if let Some(interval) = options.poll_interval_ms {
+        remote.poll_interval = std::time::Duration::from_millis(interval as u64);
+    }
+    if let Some(timeout) = options.timeout_ms {
+        remote.timeout = std::time::Duration::from_millis(timeout as u64);
+    }
+    if let Some(compression) = options.compression {
+        remote.compression = crate::compression::CompressionMode::parse(&compression)
+            .map_err(|e| Error::new(Status::InvalidArg, e))?;
+    }
+    if let Some(raster) = options.raster {
+        remote.raster = crate::raster::RasterMode::parse(&raster)
+            .map_err(|e| Error::new(Status::InvalidArg, e))?;
+    }
+    crate::remote::define(remote).map_err(|e| Error::new(Status::InvalidArg, e))
+}
+
+/// Remove a remote printer, returning whether it existed
+#[napi]
+pub fn remove_remote_printer(name: String) -> Result<bool> {
+    require_owner()?;
+    Ok(crate::remote::remove(&name))
+}
+
+/// Get all remote printers, sorted by name. Auth tokens are not returned.
+#[napi]
+pub fn get_remote_printers() -> Vec<RemotePrinterInfo> {
+    crate::remote::list()
+        .into_iter()
+        .map(|remote| RemotePrinterInfo {
+            name: remote.name.clone(),
+            url: remote.url.clone(),
+            printer: remote.printer.clone(),
+            poll_interval_ms: remote.poll_interval.as_millis() as u32,
+            timeout_ms: remote.timeout.as_millis() as u32,
+            compression: remote.compression.as_str().to_string(),
+            raster: remote.raster.as_str().to_string(),
+        })
+        .collect()
+}
+
+// ===== PRINTER CREDENTIALS N-API BINDINGS =====
+
+/// Credentials for a protected print queue, from JavaScript
+#[napi(object)]
+pub struct PrinterCredentials {
+    /// "basic" (default) or "negotiate"
+    pub method: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+/// Set the credentials for a printer name or printer URI, or the default
+/// for every printer if `name` is null. Null credentials clear them.
+#[napi]
+pub fn set_printer_credentials(
+    name: Option<String>,
+    credentials: Option<PrinterCredentials>,
+) -> Result<()> {
+    require_owner()?;
+    let credentials = credentials
+        .map(|credentials| match credentials.method.as_deref() {
+            None | Some("basic") => match (credentials.username, credentials.password) {
+                (Some(username), Some(password)) => {
+                    Ok(crate::credentials::Credentials::Basic { username, password })
+                }
+                _ => Err("Basic credentials need a username and password".to_string()),
+            },
+            Some("negotiate") => Ok(crate::credentials::Credentials::Negotiate {
+                username: credentials.username,
+            }),
+            Some(method) => Err(format!(
+                "Unknown authentication method '{}' (expected basic or negotiate)",
+                method
+            )),
+        })
+        .transpose()
+        .map_err(|e| Error::new(Status::InvalidArg, e))?;
+    crate::credentials::set(name.as_deref(), credentials)
+        .map_err(|e| Error::new(Status::InvalidArg, e))
+}
+
+// ===== TLS N-API BINDINGS =====
+
+/// How to verify a TLS printer or server, from JavaScript
+#[cfg(feature = "tls")]
+#[napi(object)]
+pub struct TlsOptions {
+    /// PEM file of CA certificates trusted in addition to the system's
+    #[napi(js_name = "caFile")]
+    pub ca_file: Option<String>,
+    /// Accept any certificate (encrypts without authenticating the server)
+    #[napi(js_name = "insecureSkipVerify")]
+    pub insecure_skip_verify: Option<bool>,
+}
+
+/// Set the TLS options for a destination (host, host:port or URI), or the
+/// default for every destination if `destination` is null. Null options
+/// restore the defaults.
+#[cfg(feature = "tls")]
+#[napi]
+pub fn set_tls_options(destination: Option<String>, options: Option<TlsOptions>) -> Result<()> {
+    require_owner()?;
+    let options = options.map(|options| crate::tls::TlsOptions {
+        ca_file: options.ca_file.map(std::path::PathBuf::from),
+        insecure_skip_verify: options.insecure_skip_verify.unwrap_or(false),
+    });
+    crate::tls::set(destination.as_deref(), options).map_err(|e| Error::new(Status::InvalidArg, e))
+}
+
+// ===== GHOSTSCRIPT N-API BINDINGS =====
+
+/// How a document is rasterized, for JavaScript
+#[cfg(feature = "ghostscript")]
+#[napi(object)]
+pub struct RasterizeOptions {
+    /// "pcl", "pclxl", "escp", "pwg-raster", "urf" or "cups-raster"
+    pub format: String,
+    /// Dots per inch (default depends on the format)
+    pub resolution: Option<u32>,
+    pub first_page: Option<u32>,
+    pub last_page: Option<u32>,
+    /// Convert in color, for PCL XL, PWG Raster and Apple Raster
+    pub color: Option<bool>,
+    /// Tags this conversion's progress events
+    pub conversion_id: Option<u32>,
+}
+
+/// Ghostscript started on a page of a conversion, for JavaScript
+#[cfg(feature = "ghostscript")]
+#[napi(object)]
+#[derive(Clone)]
+pub struct ConversionProgressEvent {
+    pub conversion_id: u32,
+    pub page: u32,
+    pub pages: Option<u32>,
+}
+
+/// Progress events kept until drained; older ones are dropped
+#[cfg(feature = "ghostscript")]
+const MAX_BUFFERED_CONVERSION_EVENTS: usize = 256;
+
+#[cfg(feature = "ghostscript")]
+lazy_static::lazy_static! {
+    static ref CONVERSION_PROGRESS_BUFFER: EnvBuffers<ConversionProgressEvent> =
+        EnvBuffers::new(MAX_BUFFERED_CONVERSION_EVENTS);
+}
+
+/// Buffer progress events of this environment's conversions to drain
+#[cfg(feature = "ghostscript")]
+#[napi]
+pub fn set_conversion_progress_buffering(enabled: bool) {
+    CONVERSION_PROGRESS_BUFFER.set_enabled(enabled);
+}
+
+/// Take all buffered conversion progress events, oldest first
+#[cfg(feature = "ghostscript")]
+#[napi]
+pub fn drain_conversion_progress_events() -> Vec<ConversionProgressEvent> {
+    CONVERSION_PROGRESS_BUFFER.drain()
+}
+
+#[cfg(feature = "ghostscript")]
+pub struct RasterizeTask {
+    pub data: Vec<u8>,
+    pub options: crate::ghostscript::RasterOptions,
+    pub conversion_id: Option<u32>,
+    /// Environment the conversion's progress is reported to
+    pub env: crate::core::EnvId,
+}
+
+#[cfg(feature = "ghostscript")]
+impl Task for RasterizeTask {
+    type Output = Vec<u8>;
+    type JsValue = Buffer;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let (conversion_id, env) = (self.conversion_id, self.env);
+        PrinterCore::rasterize_document(&self.data, &self.options, |progress| {
+            if let Some(conversion_id) = conversion_id {
+                CONVERSION_PROGRESS_BUFFER.push_to(
+                    env,
+                    ConversionProgressEvent {
+                        conversion_id,
+                        page: progress.page,
+                        pages: progress.pages,
+                    },
+                );
+            }
+        })
+        .map_err(|e| Error::new(Status::GenericFailure, e))
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(Buffer::from(output))
+    }
+}
+
+/// Convert a PDF or PostScript document to a printer format with
+/// Ghostscript (async)
+#[cfg(feature = "ghostscript")]
+#[napi]
+pub fn rasterize_document(
+    data: Buffer,
+    options: RasterizeOptions,
+) -> Result<AsyncTask<RasterizeTask>> {
+    let format = crate::ghostscript::RasterFormat::parse(&options.format).ok_or_else(|| {
+        Error::new(
+            Status::InvalidArg,
+            format!("Unknown raster format: {}", options.format),
+        )
+    })?;
+    Ok(AsyncTask::new(RasterizeTask {
+        data: data.to_vec(),
+        options: crate::ghostscript::RasterOptions {
+            format,
+            resolution: options.resolution,
+            first_page: options.first_page,
+            last_page: options.last_page,
+            color: options.color.unwrap_or(false),
+        },
+        conversion_id: options.conversion_id,
+        env: current_env(),
+    }))
+}
+
+/// Page and resolution of a preview, and the options of the job it
+/// previews, for JavaScript
+#[cfg(feature = "ghostscript")]
+#[napi(object)]
+pub struct RenderPreviewOptions {
+    /// Page to render, counting from 1 (default 1)
+    pub page: Option<u32>,
+    /// Dots per inch (default 96)
+    pub dpi: Option<u32>,
+    /// Printer or alias whose defaults and capabilities the job's options
+    /// are resolved against, as for a job sent to it
+    pub printer_name: Option<String>,
+    pub job_properties: Option<HashMap<String, String>>,
+}
+
+#[cfg(feature = "ghostscript")]
+pub struct RenderPreviewTask {
+    pub payload: PrintPayload,
+    pub printer_name: Option<String>,
+    pub job_options: Option<PrinterJobOptions>,
+    pub page: u32,
+    pub dpi: Option<u32>,
+}
+
+#[cfg(feature = "ghostscript")]
+impl Task for RenderPreviewTask {
+    type Output = Vec<u8>;
+    type JsValue = Buffer;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let job_options = match &self.printer_name {
+            Some(printer_name) => {
+                PrinterCore::resolve_job_options(printer_name, self.job_options.take())
+                    .map_err(print_error)?
+                    .job_options
+            }
+            None => self
+                .job_options
+                .take()
+                .unwrap_or_else(PrinterJobOptions::none),
+        };
+        let read;
+        let data = match &self.payload {
+            PrintPayload::File(file_path) => {
+                read = std::fs::read(file_path)
+                    .map_err(|_| print_file_error(PrintError::FileNotFound))?;
+                &read
+            }
+            PrintPayload::Bytes(data) => data,
+        };
+        PrinterCore::render_preview(data, &job_options, self.page, self.dpi)
+            .map_err(|e| Error::new(Status::GenericFailure, e))
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(Buffer::from(output))
+    }
+}
+
+#[cfg(feature = "ghostscript")]
+fn render_preview_task(
+    payload: PrintPayload,
+    options: Option<RenderPreviewOptions>,
+) -> RenderPreviewTask {
+    let mut task = RenderPreviewTask {
+        payload,
+        printer_name: None,
+        job_options: None,
+        page: 1,
+        dpi: None,
+    };
+    if let Some(options) = options {
+        task.printer_name = options.printer_name;
+        task.job_options = options.job_properties.map(PrinterJobOptions::from_map);
+        task.page = options.page.unwrap_or(1);
+        task.dpi = options.dpi;
+    }
+    task
+}
+
+/// Render a page of a file as a PNG image of how it prints, after page
+/// options like layouts, scaling and watermarks are applied (async)
+#[cfg(feature = "ghostscript")]
+#[napi]
+pub fn render_preview(
+    file_path: String,
+    options: Option<RenderPreviewOptions>,
+) -> AsyncTask<RenderPreviewTask> {
+    AsyncTask::new(render_preview_task(PrintPayload::File(file_path), options))
+}
+
+/// Render a page of a document's bytes as a PNG image of how it prints
+/// (async)
+#[cfg(feature = "ghostscript")]
+#[napi]
+pub fn render_preview_bytes(
+    data: Buffer,
+    options: Option<RenderPreviewOptions>,
+) -> AsyncTask<RenderPreviewTask> {
+    AsyncTask::new(render_preview_task(
+        PrintPayload::Bytes(data.to_vec()),
+        options,
+    ))
+}
+
+/// Version of the configured Ghostscript executable
+#[cfg(feature = "ghostscript")]
+#[napi]
+pub fn get_ghostscript_version() -> Result<String> {
+    let binary = crate::ghostscript::binary(PrinterCore::get_config().ghostscript_path.as_deref());
+    crate::ghostscript::version(&binary).map_err(|e| Error::new(Status::GenericFailure, e))
+}
+
+// ===== PJL N-API BINDINGS =====
+
+/// Settings of a PJL job header, for JavaScript
+#[napi(object)]
+pub struct PjlJobOptions {
+    pub name: Option<String>,
+    /// "simplex", "long-edge" or "short-edge"
+    pub duplex: Option<String>,
+    /// Input tray, e.g. "TRAY2"
+    pub tray: Option<String>,
+    /// "PDF", "POSTSCRIPT", "PCL", ...; detected from the document if unset
+    pub language: Option<String>,
+}
+
+/// Printer status read back over PJL, for JavaScript
+#[napi(object)]
+pub struct PjlStatus {
+    pub code: Option<u32>,
+    pub display: Option<String>,
+    pub online: Option<bool>,
+    pub page_count: Option<f64>,
+    /// "info", "warning" or "error", from the status code
+    pub severity: Option<String>,
+}
+
+/// Wrap a document in a PJL job header and end-of-job trailer
+#[napi]
+pub fn wrap_pjl(data: Buffer, options: Option<PjlJobOptions>) -> Result<Buffer> {
+    let options = match options {
+        Some(options) => crate::pjl::PjlJobOptions {
+            name: options.name,
+            duplex: options
+                .duplex
+                .map(|duplex| {
+                    crate::spooler::Duplex::parse(&duplex).ok_or_else(|| {
+                        Error::new(
+                            Status::InvalidArg,
+                            format!("Unknown duplex mode: {}", duplex),
+                        )
+                    })
+                })
+                .transpose()?,
+            tray: options.tray,
+            language: options.language,
+        },
+        None => crate::pjl::PjlJobOptions::default(),
+    };
+    crate::pjl::wrap(&data, &options)
+        .map(Buffer::from)
+        .map_err(|e| Error::new(Status::InvalidArg, e))
+}
+
+pub struct PjlStatusTask {
+    pub printer_name: String,
+}
+
+impl Task for PjlStatusTask {
+    type Output = crate::pjl::PjlStatus;
+    type JsValue = PjlStatus;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        PrinterCore::query_pjl_status(&self.printer_name)
+            .map_err(|e| Error::new(Status::GenericFailure, e))
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(PjlStatus {
+            severity: output.severity().map(str::to_string),
+            code: output.code,
+            display: output.display,
+            online: output.online,
+            page_count: output.page_count.map(|count| count as f64),
+        })
+    }
+}
+
+/// Read a printer's status and page count over PJL (async)
+#[napi]
+pub fn query_pjl_status(printer_name: String) -> AsyncTask<PjlStatusTask> {
+    AsyncTask::new(PjlStatusTask { printer_name })
+}
+
+// ===== LABEL PRINTER N-API BINDINGS =====
+
+/// Label printer conditions read from its host status, for JavaScript
+#[napi(object)]
+pub struct LabelStatus {
+    pub label_out: bool,
+    pub ribbon_out: bool,
+    pub head_open: bool,
+    pub paused: bool,
+    /// The conditions as printer state reasons
+    pub state_reasons: Vec<String>,
+}
+
+/// Async task sending a maintenance command to a label printer
+pub struct LabelCommandTask {
+    pub printer_name: String,
+    /// Labels to feed; calibrates the media when None
+    pub labels: Option<u32>,
+    pub language: Option<crate::label::LabelLanguage>,
+}
+
+impl Task for LabelCommandTask {
+    type Output = u64;
+    type JsValue = f64;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        match self.labels {
+            Some(labels) => PrinterCore::feed_labels(&self.printer_name, labels, self.language),
+            None => PrinterCore::calibrate_media(&self.printer_name, self.language),
+        }
+        .map_err(print_bytes_error)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output as f64)
+    }
+}
+
+fn label_language(language: Option<String>) -> Result<Option<crate::label::LabelLanguage>> {
+    language
+        .map(|language| {
+            crate::label::LabelLanguage::parse(&language).ok_or_else(|| {
+                Error::new(
+                    Status::InvalidArg,
+                    format!("Unknown label printer language: {}", language),
+                )
+            })
+        })
+        .transpose()
+}
+
+/// Make a label printer measure its labels again (async). The language is
+/// "zpl" or "escpos", detected from the printer if unset.
+#[napi]
+pub fn calibrate_media(
+    printer_name: String,
+    language: Option<String>,
+) -> Result<AsyncTask<LabelCommandTask>> {
+    Ok(AsyncTask::new(LabelCommandTask {
+        printer_name,
+        labels: None,
+        language: label_language(language)?,
+    }))
+}
+
+/// Feed blank labels through a label printer (async)
+#[napi]
+pub fn feed_labels(
+    printer_name: String,
+    labels: u32,
+    language: Option<String>,
+) -> Result<AsyncTask<LabelCommandTask>> {
+    if labels == 0 || labels > crate::label::MAX_FEED_LABELS {
+        return Err(Error::new(
+            Status::InvalidArg,
+            format!(
+                "Labels to feed must be 1 to {}, not {}",
+                crate::label::MAX_FEED_LABELS,
+                labels
+            ),
+        ));
+    }
+    Ok(AsyncTask::new(LabelCommandTask {
+        printer_name,
+        labels: Some(labels),
+        language: label_language(language)?,
+    }))
+}
+
+pub struct LabelStatusTask {
+    pub printer_name: String,
+}
+
+impl Task for LabelStatusTask {
+    type Output = crate::label::LabelStatus;
+    type JsValue = LabelStatus;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        PrinterCore::query_label_status(&self.printer_name)
+            .map_err(|e| Error::new(Status::GenericFailure, e))
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(LabelStatus {
+            state_reasons: output
+                .state_reasons()
+                .iter()
+                .map(|reason| reason.as_str().to_string())
+                .collect(),
+            label_out: output.label_out,
+            ribbon_out: output.ribbon_out,
+            head_open: output.head_open,
+            paused: output.paused,
+        })
+    }
+}
+
+/// Read a ZPL label printer's host status (async)
+#[napi]
+pub fn query_label_status(printer_name: String) -> AsyncTask<LabelStatusTask> {
+    AsyncTask::new(LabelStatusTask { printer_name })
+}
+
+// ===== RAW IPP N-API BINDINGS =====
+
+/// An IPP attribute in its text form, for JavaScript
+#[napi(object)]
+pub struct IppRawAttribute {
+    /// "operation" (default), "job", "printer", "subscription", "document",
+    /// ...
+    pub group: Option<String>,
+    pub name: String,
+    /// Syntax of the values: "keyword", "integer", "enum", "boolean", "text",
+    /// "name", "uri", "mimeMediaType", "resolution", "rangeOfInteger",
+    /// "dateTime", "collection", ...
+    pub syntax: String,
+    /// Values in their text form, e.g. "600dpi" or "1-10"
+    pub values: Vec<String>,
+    /// Members of each value of a collection attribute
+    pub collections: Option<Vec<Vec<IppRawAttribute>>>,
+}
+
+/// Attributes of one group of an IPP response, for JavaScript
+#[napi(object)]
+pub struct IppRawGroup {
+    pub group: String,
+    pub attributes: Vec<IppRawAttribute>,
+}
+
+/// A decoded IPP response, for JavaScript
+#[napi(object)]
+pub struct IppRawResponse {
+    pub status_code: u32,
+    /// Whether the status code is in the successful range
+    pub ok: bool,
+    pub request_id: u32,
+    pub groups: Vec<IppRawGroup>,
+}
+
+#[napi(object)]
+pub struct IppRequestOptions {
+    /// Document data sent after the attributes, for Print-Job or
+    /// Send-Document
+    pub data: Option<Buffer>,
+    pub timeout_ms: Option<u32>,
+}
+
+fn parse_ipp_attribute(attribute: IppRawAttribute) -> Result<crate::ipp::IppAttribute> {
+    let invalid = |e: String| Error::new(Status::InvalidArg, format!("{}: {}", attribute.name, e));
+    let values = if attribute.syntax == "collection" {
+        attribute
+            .collections
+            .unwrap_or_default()
+            .into_iter()
+            .map(|members| {
+                members
+                    .into_iter()
+                    .map(parse_ipp_attribute)
+                    .collect::<Result<Vec<_>>>()
+                    .map(crate::ipp::IppValue::Collection)
+            })
+            .collect::<Result<Vec<_>>>()?
+    } else {
+        attribute
+            .values
+            .iter()
+            .map(|value| crate::ipp::IppValue::parse(&attribute.syntax, value).map_err(invalid))
+            .collect::<Result<Vec<_>>>()?
+    };
+    if values.is_empty() {
+        return Err(invalid("an attribute needs at least one value".to_string()));
+    }
+    Ok(crate::ipp::IppAttribute {
+        name: attribute.name,
+        values,
+    })
+}
+
+fn convert_ipp_attribute(
+    group: Option<String>,
+    attribute: crate::ipp::IppAttribute,
+) -> IppRawAttribute {
+    let syntax = attribute
+        .value()
+        .map_or("no-value", crate::ipp::IppValue::syntax)
+        .to_string();
+    let collections = (syntax == "collection").then(|| {
+        attribute
+            .values
+            .iter()
+            .map(|value| match value {
+                crate::ipp::IppValue::Collection(members) => members
+                    .iter()
+                    .cloned()
+                    .map(|member| convert_ipp_attribute(None, member))
+                    .collect(),
+                _ => Vec::new(),
+            })
+            .collect()
+    });
+    IppRawAttribute {
+        group,
+        name: attribute.name,
+        syntax,
+        values: attribute.values.iter().map(ToString::to_string).collect(),
+        collections,
+    }
+}
+
+pub struct IppRequestTask {
+    pub printer_uri: String,
+    pub operation: u16,
+    pub attributes: Vec<(u8, crate::ipp::IppAttribute)>,
+    pub data: Vec<u8>,
+    pub timeout: std::time::Duration,
+}
+
+impl Task for IppRequestTask {
+    type Output = crate::ipp::IppResponse;
+    type JsValue = IppRawResponse;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        crate::ipp::send_raw_request(
+            &self.printer_uri,
+            self.operation,
+            std::mem::take(&mut self.attributes),
+            std::mem::take(&mut self.data),
+            self.timeout,
+        )
+        .map_err(|e| Error::new(Status::GenericFailure, e))
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(IppRawResponse {
+            status_code: u32::from(output.status_code),
+            ok: output.is_success(),
+            request_id: output.request_id,
+            groups: output
+                .groups
+                .into_iter()
+                .map(|group| {
+                    let name = crate::ipp::group_name(group.tag);
+                    IppRawGroup {
+                        attributes: group
+                            .attributes
+                            .into_iter()
+                            .map(|attribute| convert_ipp_attribute(Some(name.clone()), attribute))
+                            .collect(),
+                        group: name,
+                    }
+                })
+                .collect(),
+        })
+    }
+}
+
+/// Send an IPP request for any operation, by name ("Get-Jobs") or ID, and
+/// decode the response (async)
+#[napi]
+pub fn send_ipp_request(
+    printer_uri: String,
+    operation: String,
+    attributes: Option<Vec<IppRawAttribute>>,
+    options: Option<IppRequestOptions>,
+) -> Result<AsyncTask<IppRequestTask>> {
+    let code = crate::ipp::operation_code(&operation).ok_or_else(|| {
+        Error::new(
+            Status::InvalidArg,
+            format!("Unknown IPP operation: {}", operation),
+        )
+    })?;
+    let attributes = attributes
+        .unwrap_or_default()
+        .into_iter()
+        .map(|attribute| {
+            let group = attribute.group.as_deref().unwrap_or("operation");
+            let tag = crate::ipp::group_tag(group).ok_or_else(|| {
+                Error::new(
+                    Status::InvalidArg,
+                    format!("Unknown IPP attribute group: {}", group),
+                )
+            })?;
+            Ok((tag, parse_ipp_attribute(attribute)?))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let (data, timeout_ms) =
+        options.map_or((None, None), |options| (options.data, options.timeout_ms));
+    Ok(AsyncTask::new(IppRequestTask {
+        printer_uri,
+        operation: code,
+        attributes,
+        data: data.map(|data| data.to_vec()).unwrap_or_default(),
+        timeout: timeout_ms
+            .map(|ms| std::time::Duration::from_millis(u64::from(ms)))
+            .unwrap_or(crate::ipp::DEFAULT_TIMEOUT),
+    }))
+}
+
+// ===== SPOOL ENCRYPTION N-API BINDINGS =====
+
+/// Set the AES-GCM key (16 or 32 bytes) payloads of held jobs are encrypted
+/// with on disk, or stop encrypting new payloads with null
+#[napi]
+pub fn set_spool_encryption_key(key: Option<Buffer>) -> Result<()> {
+    require_owner()?;
+    crate::encryption::set_key(key.as_deref()).map_err(|e| Error::new(Status::InvalidArg, e))
+}
+
+/// Set the key (at least 16 bytes) redacted document names and paths are
+/// hashed with, or go back to a random key for this process with null
+#[napi]
+pub fn set_redaction_key(key: Option<Buffer>) -> Result<()> {
+    require_owner()?;
+    crate::core::set_redaction_key(key.as_deref()).map_err(|e| Error::new(Status::InvalidArg, e))
+}
+
+// ===== PRINTER POWER N-API BINDINGS =====
+
+/// How to wake a printer, for JavaScript
+#[napi(object)]
+pub struct WakeOptions {
+    /// MAC address to wake, overriding the one set for the printer
+    pub mac: Option<String>,
+    /// IPv4 broadcast address for the magic packet (default 255.255.255.255)
+    pub broadcast: Option<String>,
+    /// UDP port for the magic packet (default 9)
+    pub port: Option<u32>,
+    /// Wait up to this long for the printer to answer
+    #[napi(js_name = "timeoutMs")]
+    pub timeout_ms: Option<u32>,
+}
+
+/// Power state of a printer, for JavaScript
+#[napi(object)]
+pub struct PowerStatus {
+    #[napi(js_name = "powerState")]
+    pub power_state: Option<String>,
+    pub asleep: Option<bool>,
+    pub reachable: bool,
+}
+
+/// Async task for waking a printer
+pub struct WakeTask {
+    pub printer_name: String,
+    pub options: crate::core::WakeOptions,
+}
+
+impl Task for WakeTask {
+    type Output = bool;
+    type JsValue = bool;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        PrinterCore::wake_printer(&self.printer_name, &self.options)
+            .map_err(|e| Error::new(Status::GenericFailure, e))
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/// Async task for querying a printer's power state
+pub struct PowerStatusTask {
+    pub printer_name: String,
+}
+
+impl Task for PowerStatusTask {
+    type Output = crate::core::PowerStatus;
+    type JsValue = PowerStatus;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        PrinterCore::get_power_status(&self.printer_name)
+            .map_err(|e| Error::new(Status::GenericFailure, e))
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(PowerStatus {
+            power_state: output.power_state,
+            asleep: output.asleep,
+            reachable: output.reachable,
+        })
+    }
+}
+
+/// Set the MAC address used to wake a printer with Wake-on-LAN (None removes it)
+#[napi]
+pub fn set_printer_mac_address(printer_name: String, mac: Option<String>) -> Result<()> {
+    require_owner()?;
+    crate::power::set_mac_address(&printer_name, mac.as_deref())
+        .map_err(|e| Error::new(Status::InvalidArg, e))
+}
+
+/// Wake a sleeping network printer with a Wake-on-LAN magic packet (async)
+#[napi]
+pub fn wake_printer(
+    printer_name: String,
+    options: Option<WakeOptions>,
+) -> Result<AsyncTask<WakeTask>> {
+    let options = match options {
+        Some(options) => crate::core::WakeOptions {
+            mac: options.mac,
+            broadcast: options
+                .broadcast
+                .map(|broadcast| {
+                    broadcast.parse().map_err(|_| {
+                        Error::new(
+                            Status::InvalidArg,
+                            format!("Invalid broadcast address '{}'", broadcast),
+                        )
+                    })
+                })
+                .transpose()?,
+            port: options
+                .port
+                .map(|port| {
+                    u16::try_from(port).map_err(|_| {
+                        Error::new(Status::InvalidArg, format!("Invalid port {}", port))
+                    })
+                })
+                .transpose()?,
+            wait: options
+                .timeout_ms
+                .map(|timeout| std::time::Duration::from_millis(timeout as u64)),
+        },
+        None => crate::core::WakeOptions::default(),
+    };
+    Ok(AsyncTask::new(WakeTask {
+        printer_name,
+        options,
+    }))
+}
+
+/// Get whether a printer is awake or asleep (async)
+#[napi]
+pub fn get_printer_power_status(printer_name: String) -> AsyncTask<PowerStatusTask> {
+    AsyncTask::new(PowerStatusTask { printer_name })
+}
+
+// ===== PRINTER INSTALLATION N-API BINDINGS =====
+
+/// A print queue to install, for JavaScript
+#[napi(object)]
+pub struct PrinterInstallation {
+    pub name: String,
+    /// Existing port, e.g. "LPT1:", "FILE:" or a Standard TCP/IP port name
+    pub port: String,
+    /// Name of an installed driver
+    pub driver: String,
+    pub location: Option<String>,
+    pub comment: Option<String>,
+}
+
+/// An installed printer driver, for JavaScript
+#[napi(object)]
+pub struct PrinterDriver {
+    pub name: String,
+    pub environment: String,
+    pub version: u32,
+    #[napi(js_name = "driverPath")]
+    pub driver_path: String,
+}
+
+/// Async task for installing or removing a print queue
+pub struct InstallPrinterTask {
+    pub printer_name: String,
+    /// Queue to install (None removes `printer_name`)
+    pub installation: Option<crate::spooler::PrinterInstallation>,
+}
+
+impl Task for InstallPrinterTask {
+    type Output = ();
+    type JsValue = ();
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        match &self.installation {
+            Some(installation) => PrinterCore::install_printer(installation),
+            None => PrinterCore::remove_printer(&self.printer_name),
+        }
+        .map_err(|e| Error::new(Status::GenericFailure, e))
+    }
+
+    fn resolve(&mut self, _env: Env, _output: Self::Output) -> Result<Self::JsValue> {
+        Ok(())
+    }
+}
+
+/// Async task for listing installed printer drivers
+pub struct ListDriversTask;
+
+impl Task for ListDriversTask {
+    type Output = Vec<crate::spooler::PrinterDriver>;
+    type JsValue = Vec<PrinterDriver>;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        PrinterCore::list_drivers().map_err(|e| Error::new(Status::GenericFailure, e))
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output
+            .into_iter()
+            .map(|driver| PrinterDriver {
+                name: driver.name,
+                environment: driver.environment,
+                version: driver.version,
+                driver_path: driver.driver_path,
+            })
+            .collect())
+    }
+}
+
+/// Install a print queue with an installed driver and an existing port (async, Windows only)
+#[napi]
+pub fn install_printer(installation: PrinterInstallation) -> AsyncTask<InstallPrinterTask> {
+    AsyncTask::new(InstallPrinterTask {
+        printer_name: installation.name.clone(),
+        installation: Some(crate::spooler::PrinterInstallation {
+            name: installation.name,
+            port: installation.port,
+            driver: installation.driver,
+            location: installation.location,
+            comment: installation.comment,
+        }),
+    })
+}
+
+/// Remove a print queue (async, Windows only)
+#[napi]
+pub fn remove_printer(printer_name: String) -> AsyncTask<InstallPrinterTask> {
+    AsyncTask::new(InstallPrinterTask {
+        printer_name,
+        installation: None,
+    })
+}
+
+/// List the installed printer drivers (async, Windows only)
+#[napi]
+pub fn list_drivers() -> AsyncTask<ListDriversTask> {
+    AsyncTask::new(ListDriversTask)
+}
+
+// ===== CUPS QUEUE N-API BINDINGS =====
+
+/// A CUPS queue to add, for JavaScript
+#[napi(object)]
+pub struct CupsQueue {
+    pub name: String,
+    #[napi(js_name = "deviceUri")]
+    pub device_uri: String,
+    /// Path of a PPD file for the queue
+    pub ppd: Option<String>,
+    /// Driver known to CUPS (`lpinfo -m`), e.g. "everywhere"
+    pub model: Option<String>,
+    pub location: Option<String>,
+    pub description: Option<String>,
+    /// Start the queue and accept jobs (default: true)
+    pub enabled: Option<bool>,
+}
+
+/// A CUPS admin operation
+pub enum CupsAdminOperation {
+    Add(crate::spooler::CupsQueue),
+    Delete(String),
+    SetEnabled(String, bool),
+}
+
+/// Async task for a CUPS admin operation
+pub struct CupsAdminTask {
+    pub operation: CupsAdminOperation,
+}
+
+impl Task for CupsAdminTask {
+    type Output = ();
+    type JsValue = ();
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        match &self.operation {
+            CupsAdminOperation::Add(queue) => PrinterCore::add_cups_queue(queue),
+            CupsAdminOperation::Delete(name) => PrinterCore::delete_cups_queue(name),
+            CupsAdminOperation::SetEnabled(name, enabled) => {
+                PrinterCore::set_queue_enabled(name, *enabled)
+            }
+        }
+        .map_err(|e| Error::new(Status::GenericFailure, e))
+    }
+
+    fn resolve(&mut self, _env: Env, _output: Self::Output) -> Result<Self::JsValue> {
+        Ok(())
+    }
+}
+
+/// Add a queue to the local CUPS server, or modify it if it exists (async, Unix only)
+#[napi]
+pub fn add_cups_queue(queue: CupsQueue) -> AsyncTask<CupsAdminTask> {
+    let queue = crate::spooler::CupsQueue {
+        name: queue.name,
+        device_uri: queue.device_uri,
+        ppd: queue.ppd.map(std::path::PathBuf::from),
+        model: queue.model,
+        location: queue.location,
+        description: queue.description,
+        enabled: queue.enabled.unwrap_or(true),
+    };
+    AsyncTask::new(CupsAdminTask {
+        operation: CupsAdminOperation::Add(queue),
+    })
+}
+
+/// Delete a queue from the local CUPS server (async, Unix only)
+#[napi]
+pub fn delete_cups_queue(name: String) -> AsyncTask<CupsAdminTask> {
+    AsyncTask::new(CupsAdminTask {
+        operation: CupsAdminOperation::Delete(name),
+    })
+}
+
+/// Enable or disable a CUPS queue (async, Unix only)
+#[napi]
+pub fn set_queue_enabled(name: String, enabled: bool) -> AsyncTask<CupsAdminTask> {
+    AsyncTask::new(CupsAdminTask {
+        operation: CupsAdminOperation::SetEnabled(name, enabled),
+    })
+}
+
+// ===== ACCESS CONTROL N-API BINDINGS =====
+
+/// Whether a printer is shared and who may print to it, for JavaScript
+#[napi(object)]
+pub struct AccessControl {
+    pub shared: Option<bool>,
+    /// Users allowed to print (empty: everyone who isn't denied)
+    #[napi(js_name = "allowedUsers")]
+    pub allowed_users: Option<Vec<String>>,
+    #[napi(js_name = "deniedUsers")]
+    pub denied_users: Option<Vec<String>>,
+}
+
+/// Async task for reading or updating a printer's access control
+pub struct AccessControlTask {
+    pub printer_name: String,
+    /// Changes to apply (None only reads it)
+    pub update: Option<crate::spooler::AccessControl>,
+}
+
+impl Task for AccessControlTask {
+    type Output = crate::spooler::AccessControl;
+    type JsValue = AccessControl;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        match &self.update {
+            Some(update) => PrinterCore::set_access_control(&self.printer_name, update),
+            None => PrinterCore::get_access_control(&self.printer_name),
+        }
+        .map_err(|e| Error::new(Status::GenericFailure, e))
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(AccessControl {
+            shared: output.shared,
+            allowed_users: output.allowed_users,
+            denied_users: output.denied_users,
+        })
+    }
+}
+
+/// Get whether a printer is shared and who may print to it (async)
+#[napi]
+pub fn get_printer_access_control(printer_name: String) -> AsyncTask<AccessControlTask> {
+    AsyncTask::new(AccessControlTask {
+        printer_name,
+        update: None,
+    })
+}
+
+/// Share or unshare a printer and change who may print to it (async)
+#[napi]
+pub fn set_printer_access_control(
+    printer_name: String,
+    access: AccessControl,
+) -> AsyncTask<AccessControlTask> {
+    AsyncTask::new(AccessControlTask {
+        printer_name,
+        update: Some(crate::spooler::AccessControl {
+            shared: access.shared,
+            allowed_users: access.allowed_users,
+            denied_users: access.denied_users,
+        }),
+    })
+}
+
+// ===== TEMPLATE N-API BINDINGS =====
+
+/// A registered print template, for JavaScript
+#[napi(object)]
+pub struct PrintTemplate {
+    pub name: String,
+    pub format: String, // "text", "zpl" or "escpos"
+    pub variables: Vec<String>,
 }
 
-/// Get all available printer names
+/// Register a template with {{variable}} placeholders, replacing any with
+/// the same name
 #[napi]
-pub fn get_all_printer_names() -> Vec<String> {
-    PrinterCore::get_all_printer_names()
+pub fn register_template(name: String, body: String, format: Option<String>) -> Result<()> {
+    require_owner()?;
+    let format = match format.as_deref() {
+        Some(format) => crate::templates::TemplateFormat::parse(format).ok_or_else(|| {
+            Error::new(
+                Status::InvalidArg,
+                format!("Unknown template format: {}", format),
+            )
+        })?,
+        None => crate::templates::TemplateFormat::Text,
+    };
+    crate::templates::register(&name, &body, format).map_err(|e| Error::new(Status::InvalidArg, e))
 }
 
-/// Get all available printers
+/// Remove a template, returning whether it existed
 #[napi]
-pub fn get_all_printers() -> Vec<PrinterInfo> {
-    let printer_names = PrinterCore::get_all_printer_names();
+pub fn unregister_template(name: String) -> Result<bool> {
+    require_owner()?;
+    Ok(crate::templates::unregister(&name))
+}
 
-    printer_names
+/// Get all registered templates, sorted by name
+#[napi]
+pub fn get_templates() -> Vec<PrintTemplate> {
+    crate::templates::list()
         .into_iter()
-        .filter_map(|name| {
-            // Find the actual printer from the core
-            if let Some(printer) = PrinterCore::find_printer_by_name(&name) {
-                let printer_info = PrinterInfo {
-                    name: printer.name.clone(),
-                    system_name: printer.system_name.clone(),
-                    driver_name: printer.driver_name.clone(),
-                    uri: printer.uri.clone(),
-                    port_name: printer.port_name.clone(),
-                    processor: printer.processor.clone(),
-                    data_type: printer.data_type.clone(),
-                    description: printer.description.clone(),
-                    location: printer.location.clone(),
-                    is_default: printer.is_default,
-                    is_shared: printer.is_shared,
-                    state: PrinterCore::get_printer_state(&printer),
-                    state_reasons: printer.state_reasons.clone(),
-                };
-
-                Some(printer_info)
-            } else {
-                None
-            }
+        .map(|template| PrintTemplate {
+            name: template.name.clone(),
+            format: template.format.as_str().to_string(),
+            variables: template.variables.clone(),
         })
         .collect()
 }
 
-/// Print a file using printer name (async)
+/// Render a template without printing it
 #[napi]
-pub fn print_file(
+pub fn render_template(name: String, variables: HashMap<String, String>) -> Result<String> {
+    crate::templates::render(&name, &variables).map_err(|e| Error::new(Status::InvalidArg, e))
+}
+
+/// Render a template and print the result (async)
+#[napi]
+pub fn print_template(
     printer_name: String,
-    file_path: String,
+    template: String,
+    variables: HashMap<String, String>,
     job_properties: Option<HashMap<String, String>>,
     wait_for_completion: Option<bool>,
-) -> AsyncTask<PrintTask> {
+) -> AsyncTask<PrintTemplateTask> {
     let job_options = job_properties.map(PrinterJobOptions::from_map);
-    AsyncTask::new(PrintTask {
+    AsyncTask::new(PrintTemplateTask {
         printer_name,
-        file_path,
+        template,
+        variables,
         job_options,
         wait_for_completion: wait_for_completion.unwrap_or(true), // Default to true
     })
 }
 
-/// Print raw bytes using printer name (async)
+// ===== PRINTER POOL N-API BINDINGS =====
+
+/// A pool printer was skipped, for JavaScript
+#[napi(object)]
+pub struct PoolFailoverEvent {
+    pub from: String,
+    pub to: Option<String>,
+    pub reason: String,
+    pub timestamp: f64,
+}
+
+fn get_pool(pool_id: f64) -> Result<std::sync::Arc<crate::pool::PrinterPool>> {
+    crate::pool::get(pool_id as u64)
+        .ok_or_else(|| Error::new(Status::InvalidArg, "Printer pool not found"))
+}
+
+/// Create a printer pool ("failover", "round-robin" or "least-busy"),
+/// returning its ID
 #[napi]
-pub fn print_bytes(
-    printer_name: String,
+pub fn create_printer_pool(
+    printer_names: Vec<String>,
+    strategy: Option<String>,
+    max_queue_depth: Option<u32>,
+) -> Result<f64> {
+    require_owner()?;
+    let strategy = match strategy.as_deref() {
+        None => crate::pool::PoolStrategy::Failover,
+        Some(name) => crate::pool::PoolStrategy::parse(name).ok_or_else(|| {
+            Error::new(
+                Status::InvalidArg,
+                format!("Unknown pool strategy: {}", name),
+            )
+        })?,
+    };
+    if max_queue_depth == Some(0) {
+        return Err(Error::new(
+            Status::InvalidArg,
+            "maxQueueDepth must be at least 1",
+        ));
+    }
+    crate::pool::create(
+        printer_names,
+        strategy,
+        max_queue_depth.map(|depth| depth as usize),
+    )
+    .map(|pool| pool.id as f64)
+    .map_err(|_| {
+        Error::new(
+            Status::InvalidArg,
+            "A printer pool needs at least one printer",
+        )
+    })
+}
+
+/// Print a file through a printer pool (async)
+#[napi]
+pub fn pool_print_file(
+    pool_id: f64,
+    file_path: String,
+    job_properties: Option<HashMap<String, String>>,
+    wait_for_completion: Option<bool>,
+) -> Result<AsyncTask<PoolPrintTask>> {
+    Ok(AsyncTask::new(PoolPrintTask {
+        pool: get_pool(pool_id)?,
+        payload: PrintPayload::File(file_path),
+        job_options: job_properties.map(PrinterJobOptions::from_map),
+        wait_for_completion: wait_for_completion.unwrap_or(true),
+    }))
+}
+
+/// Print raw bytes through a printer pool (async)
+#[napi]
+pub fn pool_print_bytes(
+    pool_id: f64,
     data: Buffer,
     job_properties: Option<HashMap<String, String>>,
     wait_for_completion: Option<bool>,
-) -> AsyncTask<PrintBytesTask> {
-    let job_options = job_properties.map(PrinterJobOptions::from_map);
-    AsyncTask::new(PrintBytesTask {
-        printer_name,
-        data: data.to_vec(),
-        job_options,
-        wait_for_completion: wait_for_completion.unwrap_or(true), // Default to true
-    })
+) -> Result<AsyncTask<PoolPrintTask>> {
+    Ok(AsyncTask::new(PoolPrintTask {
+        pool: get_pool(pool_id)?,
+        payload: PrintPayload::Bytes(data.to_vec()),
+        job_options: job_properties.map(PrinterJobOptions::from_map),
+        wait_for_completion: wait_for_completion.unwrap_or(true),
+    }))
 }
 
-/// Get the status of a print job (new format)
+/// Take a pool's queued failover events, oldest first
 #[napi]
-pub fn get_printer_job(job_id: f64) -> Option<PrinterJob> {
-    PrinterCore::get_job_status(job_id as u64).map(convert_printer_job)
+pub fn drain_pool_events(pool_id: f64) -> Vec<PoolFailoverEvent> {
+    crate::pool::get(pool_id as u64)
+        .map(|pool| pool.drain_events())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|event| PoolFailoverEvent {
+            from: event.from,
+            to: event.to,
+            reason: event.reason,
+            timestamp: to_unix_secs(event.timestamp),
+        })
+        .collect()
 }
 
-/// Get the status of a print job (legacy format for backward compatibility)
+/// Unregister a printer pool
 #[napi]
-pub fn get_job_status(job_id: u32) -> Option<JobStatus> {
-    if let Some(job) = PrinterCore::get_job_status(job_id as u64) {
-        // Convert new format back to legacy format
-        let legacy_status = match job.state {
-            crate::core::PrinterJobState::PENDING => "queued",
-            crate::core::PrinterJobState::PROCESSING => "printing",
-            crate::core::PrinterJobState::COMPLETED => "completed",
-            crate::core::PrinterJobState::CANCELLED => "failed",
-            _ => "unknown",
-        };
+pub fn remove_printer_pool(pool_id: f64) -> Result<bool> {
+    require_owner()?;
+    Ok(crate::pool::remove(pool_id as u64))
+}
 
-        Some(JobStatus {
-            id: job.id as u32,
-            printer_name: job.printer_name,
-            file_path: format!("Job: {}", job.name), // Approximate file_path from job name
-            job_name: Some(job.name),
-            status: legacy_status.to_string(),
-            error_message: job.error_message,
-            age_seconds: job
-                .created_at
-                .elapsed()
-                .unwrap_or(std::time::Duration::from_secs(0))
-                .as_secs() as u32,
-        })
-    } else {
-        None
-    }
+// ===== IPP SERVER N-API BINDINGS =====
+
+/// Options for start_ipp_server
+#[cfg(feature = "ipp-server")]
+#[napi(object)]
+pub struct IppServerOptions {
+    /// Local printer or alias jobs are printed on
+    #[napi(js_name = "printerName")]
+    pub printer_name: String,
+    /// Address to listen on (default: 127.0.0.1)
+    pub host: Option<String>,
+    /// Port to listen on (default: 8631, 0 = any free port)
+    pub port: Option<u32>,
+    /// Path of the queue (default: /ipp/print)
+    pub path: Option<String>,
+    /// printer-name advertised to clients (default: the printer's name)
+    #[napi(js_name = "queueName")]
+    pub queue_name: Option<String>,
+    #[napi(js_name = "maxDocumentBytes")]
+    pub max_document_bytes: Option<f64>,
 }
 
-/// Convert SystemTime to Unix timestamp in seconds
-fn to_unix_secs(time: std::time::SystemTime) -> f64 {
-    time.duration_since(std::time::SystemTime::UNIX_EPOCH)
-        .unwrap_or(std::time::Duration::from_secs(0))
-        .as_secs() as f64
+/// A running IPP server, for JavaScript
+#[cfg(feature = "ipp-server")]
+#[napi(object)]
+pub struct IppServerInfo {
+    pub id: f64,
+    /// URI clients print to
+    pub uri: String,
+    pub host: String,
+    pub port: u32,
+    #[napi(js_name = "printerName")]
+    pub printer_name: String,
 }
 
-/// Convert core PrinterJob to N-API PrinterJob
-fn convert_printer_job(job: crate::core::PrinterJob) -> PrinterJob {
-    PrinterJob {
-        id: job.id as f64,
-        name: job.name,
-        state: job.state.as_string(),
-        media_type: job.media_type,
-        created_at: to_unix_secs(job.created_at),
-        processed_at: job.processed_at.map(to_unix_secs),
-        completed_at: job.completed_at.map(to_unix_secs),
-        printer_name: job.printer_name,
-        error_message: job.error_message,
-        age_seconds: job
-            .created_at
-            .elapsed()
-            .unwrap_or(std::time::Duration::from_secs(0))
-            .as_secs() as f64,
+#[cfg(feature = "ipp-server")]
+fn convert_ipp_server(server: &crate::ipp_server::IppServer) -> IppServerInfo {
+    IppServerInfo {
+        id: server.id as f64,
+        uri: server.uri(),
+        host: server.local_addr.ip().to_string(),
+        port: server.local_addr.port() as u32,
+        printer_name: server.config.printer_name.clone(),
     }
 }
 
-/// Get all active jobs (pending or processing)
+/// Start an IPP server that prints the jobs it receives on a local printer
+#[cfg(feature = "ipp-server")]
 #[napi]
-pub fn get_active_jobs() -> Vec<PrinterJob> {
-    PrinterCore::get_active_jobs()
-        .into_iter()
-        .map(convert_printer_job)
-        .collect()
+pub fn start_ipp_server(options: IppServerOptions) -> Result<IppServerInfo> {
+    require_owner()?;
+    let mut config = crate::ipp_server::IppServerConfig::new(&options.printer_name);
+    if let Some(host) = options.host {
+        config.host = host;
+    }
+    if let Some(port) = options.port {
+        config.port = u16::try_from(port)
+            .map_err(|_| Error::new(Status::InvalidArg, "Invalid IPP server port"))?;
+    }
+    if let Some(path) = options.path {
+        config.path = path;
+    }
+    config.queue_name = options.queue_name;
+    if let Some(max_document_bytes) = options.max_document_bytes {
+        config.max_document_bytes = max_document_bytes.max(0.0) as usize;
+    }
+    crate::ipp_server::start(config)
+        .map(|server| convert_ipp_server(&server))
+        .map_err(|e| Error::new(Status::GenericFailure, e))
 }
 
-/// Get active jobs for a specific printer
+/// Stop an IPP server, returning whether it was running
+#[cfg(feature = "ipp-server")]
 #[napi]
-pub fn get_active_jobs_for_printer(printer_name: String) -> Vec<PrinterJob> {
-    PrinterCore::get_active_jobs_for_printer(&printer_name)
-        .into_iter()
-        .map(convert_printer_job)
+pub fn stop_ipp_server(server_id: f64) -> Result<bool> {
+    require_owner()?;
+    Ok(crate::ipp_server::stop(server_id as u64))
+}
+
+/// Running IPP servers, oldest first
+#[cfg(feature = "ipp-server")]
+#[napi]
+pub fn get_ipp_servers() -> Vec<IppServerInfo> {
+    crate::ipp_server::list()
+        .iter()
+        .map(|server| convert_ipp_server(server))
         .collect()
 }
 
-/// Get job history (completed or cancelled jobs)
+// ===== PRINT SERVER N-API BINDINGS =====
+
+/// Options for start_print_server
+#[cfg(feature = "print-server")]
+#[napi(object)]
+pub struct PrintServerOptions {
+    /// Address to listen on (default: 127.0.0.1)
+    pub host: Option<String>,
+    /// Port to listen on (default: 8632, 0 = any free port)
+    pub port: Option<u32>,
+    /// Bearer token clients must send
+    #[napi(js_name = "authToken")]
+    pub auth_token: Option<String>,
+    #[napi(js_name = "maxBodyBytes")]
+    pub max_body_bytes: Option<f64>,
+    /// Stream job and printer events over WebSocket at /events
+    pub events: Option<bool>,
+}
+
+/// A running print server, for JavaScript
+#[cfg(feature = "print-server")]
+#[napi(object)]
+pub struct PrintServerInfo {
+    pub id: f64,
+    /// Base URL of the API
+    pub url: String,
+    pub host: String,
+    pub port: u32,
+}
+
+#[cfg(feature = "print-server")]
+fn convert_print_server(server: &crate::print_server::PrintServer) -> PrintServerInfo {
+    PrintServerInfo {
+        id: server.id as f64,
+        url: server.url(),
+        host: server.local_addr.ip().to_string(),
+        port: server.local_addr.port() as u32,
+    }
+}
+
+/// Start an HTTP server exposing printers and jobs as a REST API
+#[cfg(feature = "print-server")]
 #[napi]
-pub fn get_job_history() -> Vec<PrinterJob> {
-    PrinterCore::get_job_history()
-        .into_iter()
-        .map(convert_printer_job)
+pub fn start_print_server(options: PrintServerOptions) -> Result<PrintServerInfo> {
+    require_owner()?;
+    let mut config = crate::print_server::PrintServerConfig::default();
+    if let Some(host) = options.host {
+        config.host = host;
+    }
+    if let Some(port) = options.port {
+        config.port = u16::try_from(port)
+            .map_err(|_| Error::new(Status::InvalidArg, "Invalid print server port"))?;
+    }
+    config.auth_token = options.auth_token;
+    if let Some(max_body_bytes) = options.max_body_bytes {
+        config.max_body_bytes = max_body_bytes.max(0.0) as usize;
+    }
+    config.events = options.events.unwrap_or(false);
+    crate::print_server::start(config)
+        .map(|server| convert_print_server(&server))
+        .map_err(|e| Error::new(Status::GenericFailure, e))
+}
+
+/// Stop a print server, returning whether it was running
+#[cfg(feature = "print-server")]
+#[napi]
+pub fn stop_print_server(server_id: f64) -> Result<bool> {
+    require_owner()?;
+    Ok(crate::print_server::stop(server_id as u64))
+}
+
+/// Running print servers, oldest first
+#[cfg(feature = "print-server")]
+#[napi]
+pub fn get_print_servers() -> Vec<PrintServerInfo> {
+    crate::print_server::list()
+        .iter()
+        .map(|server| convert_print_server(server))
         .collect()
 }
 
-/// Get job history for a specific printer
+// ===== CONFIGURATION N-API BINDINGS =====
+
+/// Apply the fields that are set on top of an existing configuration
+fn apply_library_config(
+    mut config: crate::core::LibraryConfig,
+    update: LibraryConfig,
+) -> Result<crate::core::LibraryConfig> {
+    if let Some(size) = update.worker_pool_size {
+        config.worker_pool_size = (size > 0).then_some(size as usize);
+    }
+    if let Some(seconds) = update.monitoring_interval {
+        config.monitoring_interval = std::time::Duration::from_secs(u64::from(seconds));
+    }
+    if let Some(ms) = update.monitoring_interval_ms {
+        config.monitoring_interval = std::time::Duration::from_millis(u64::from(ms));
+    }
+    if let Some(ms) = update.job_poll_interval_ms {
+        config.job_poll_interval = std::time::Duration::from_millis(u64::from(ms));
+    }
+    if let Some(dir) = update.temp_dir {
+        config.temp_dir = (!dir.is_empty()).then(|| std::path::PathBuf::from(dir));
+    }
+    if let Some(megabytes) = update.spool_quota_mb {
+        config.spool_quota = (megabytes > 0).then(|| u64::from(megabytes) * 1024 * 1024);
+    }
+    if let Some(simulate) = update.simulate {
+        config.simulate = Some(simulate);
+    }
+    if let Some(delay_ms) = update.simulation_delay_ms {
+        config.simulation_delay = std::time::Duration::from_millis(u64::from(delay_ms));
+    }
+    if let Some(options) = update.default_job_options {
+        config.default_job_options = options;
+    }
+    if let Some(max) = update.max_job_history {
+        config.max_job_history = (max > 0).then_some(max as usize);
+    }
+    if let Some(seconds) = update.history_ttl_seconds {
+        config.history_ttl =
+            (seconds > 0).then(|| std::time::Duration::from_secs(u64::from(seconds)));
+    }
+    if let Some(ms) = update.stall_threshold_ms {
+        config.stall_threshold = (ms > 0).then(|| std::time::Duration::from_millis(u64::from(ms)));
+    }
+    if let Some(action) = update.stall_action {
+        config.stall_action = crate::core::StallAction::parse(&action).ok_or_else(|| {
+            Error::new(
+                Status::InvalidArg,
+                format!("Unknown stall action: {}", action),
+            )
+        })?;
+    }
+    if let Some(max) = update.max_queued_jobs_per_printer {
+        config.max_queued_jobs_per_printer = (max > 0).then_some(max as usize);
+    }
+    if let Some(max) = update.max_pending_jobs {
+        config.max_pending_jobs = (max > 0).then_some(max as usize);
+    }
+    if let Some(ms) = update.queue_full_timeout_ms {
+        config.queue_full_timeout =
+            (ms > 0).then(|| std::time::Duration::from_millis(u64::from(ms)));
+    }
+    if let Some(dir) = update.held_jobs_dir {
+        config.held_jobs_dir = (!dir.is_empty()).then(|| std::path::PathBuf::from(dir));
+    }
+    if let Some(path) = update.audit_log {
+        config.audit_log = (!path.is_empty()).then(|| std::path::PathBuf::from(path));
+    }
+    if let Some(redact) = update.redact_job_data {
+        config.redact_job_data = redact;
+    }
+    if let Some(path) = update.job_registry {
+        config.job_registry = (!path.is_empty()).then(|| std::path::PathBuf::from(path));
+    }
+    if let Some(path) = update.ghostscript_path {
+        config.ghostscript_path = (!path.is_empty()).then(|| std::path::PathBuf::from(path));
+    }
+    if let Some(ms) = update.printer_cache_ttl_ms {
+        config.printer_cache_ttl = std::time::Duration::from_millis(u64::from(ms));
+    }
+    if let Some(required) = update.require_encrypted_transport {
+        config.require_encrypted_transport = required;
+    }
+    Ok(config)
+}
+
+/// Reset the library configuration to defaults, then apply the given settings
 #[napi]
-pub fn get_job_history_for_printer(printer_name: String, limit: Option<u32>) -> Vec<PrinterJob> {
-    let mut jobs: Vec<PrinterJob> = PrinterCore::get_job_history_for_printer(&printer_name)
-        .into_iter()
-        .map(convert_printer_job)
-        .collect();
+pub fn initialize(config: Option<LibraryConfig>) -> Result<()> {
+    require_owner()?;
+    let defaults = crate::core::LibraryConfig::default();
+    let config = match config {
+        Some(config) => apply_library_config(defaults, config)?,
+        None => defaults,
+    };
+    PrinterCore::configure(config).map_err(|e| Error::new(Status::InvalidArg, e))
+}
+
+/// Update individual settings of the current library configuration
+#[napi]
+pub fn configure(config: LibraryConfig) -> Result<()> {
+    require_owner()?;
+    let config = apply_library_config(PrinterCore::get_config(), config)?;
+    PrinterCore::configure(config).map_err(|e| Error::new(Status::InvalidArg, e))
+}
+
+/// Get the effective library configuration
+#[napi]
+pub fn get_config() -> LibraryConfig {
+    let config = PrinterCore::get_config();
+    LibraryConfig {
+        worker_pool_size: Some(config.worker_pool_size.unwrap_or(0) as u32),
+        monitoring_interval: Some(config.monitoring_interval.as_secs() as u32),
+        monitoring_interval_ms: Some(config.monitoring_interval.as_millis() as u32),
+        job_poll_interval_ms: Some(config.job_poll_interval.as_millis() as u32),
+        temp_dir: config
+            .temp_dir
+            .map(|dir| dir.to_string_lossy().into_owned()),
+        spool_quota_mb: Some(
+            config
+                .spool_quota
+                .map_or(0, |bytes| (bytes / (1024 * 1024)) as u32),
+        ),
+        simulate: Some(crate::core::should_simulate_printing()),
+        simulation_delay_ms: Some(config.simulation_delay.as_millis() as u32),
+        default_job_options: Some(config.default_job_options),
+        max_job_history: Some(config.max_job_history.unwrap_or(0) as u32),
+        history_ttl_seconds: Some(config.history_ttl.map_or(0, |ttl| ttl.as_secs() as u32)),
+        stall_threshold_ms: Some(
+            config
+                .stall_threshold
+                .map_or(0, |threshold| threshold.as_millis() as u32),
+        ),
+        stall_action: Some(config.stall_action.as_str().to_string()),
+        max_queued_jobs_per_printer: Some(config.max_queued_jobs_per_printer.unwrap_or(0) as u32),
+        max_pending_jobs: Some(config.max_pending_jobs.unwrap_or(0) as u32),
+        queue_full_timeout_ms: Some(
+            config
+                .queue_full_timeout
+                .map_or(0, |timeout| timeout.as_millis() as u32),
+        ),
+        held_jobs_dir: config
+            .held_jobs_dir
+            .map(|dir| dir.to_string_lossy().into_owned()),
+        audit_log: config
+            .audit_log
+            .map(|path| path.to_string_lossy().into_owned()),
+        redact_job_data: Some(config.redact_job_data),
+        job_registry: config
+            .job_registry
+            .map(|path| path.to_string_lossy().into_owned()),
+        ghostscript_path: config
+            .ghostscript_path
+            .map(|path| path.to_string_lossy().into_owned()),
+        printer_cache_ttl_ms: Some(config.printer_cache_ttl.as_millis() as u32),
+        require_encrypted_transport: Some(config.require_encrypted_transport),
+    }
+}
+
+// ===== AUDIT LOG N-API BINDINGS =====
+
+/// Result of checking an audit log, for JavaScript
+#[napi(object)]
+pub struct AuditVerification {
+    pub valid: bool,
+    /// Records checked, up to and including the first invalid one
+    pub records: f64,
+    /// Line of the first record that failed verification
+    pub first_invalid_line: Option<f64>,
+    pub error: Option<String>,
+    /// Hash of the last valid record, to keep elsewhere so truncation shows
+    pub last_hash: Option<String>,
+}
+
+pub struct VerifyAuditLogTask {
+    pub path: Option<String>,
+}
 
-    if let Some(limit) = limit {
-        jobs.truncate(limit as usize);
+impl Task for VerifyAuditLogTask {
+    type Output = crate::audit::AuditVerification;
+    type JsValue = AuditVerification;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        PrinterCore::verify_audit_log(self.path.as_deref().map(std::path::Path::new))
+            .map_err(|e| Error::new(Status::GenericFailure, e))
     }
 
-    jobs
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(AuditVerification {
+            valid: output.valid,
+            records: output.records as f64,
+            first_invalid_line: output.first_invalid_line.map(|line| line as f64),
+            error: output.error,
+            last_hash: output.last_hash,
+        })
+    }
 }
 
-/// Get all jobs for a specific printer
+/// Check an audit log's hash chain for tampering, defaulting to the
+/// configured log (async)
 #[napi]
-pub fn get_all_jobs_for_printer(printer_name: String) -> Vec<PrinterJob> {
-    PrinterCore::get_all_jobs_for_printer(&printer_name)
-        .into_iter()
-        .map(convert_printer_job)
-        .collect()
+pub fn verify_audit_log(path: Option<String>) -> AsyncTask<VerifyAuditLogTask> {
+    AsyncTask::new(VerifyAuditLogTask { path })
 }
 
-/// Clean up old completed/failed jobs
+// ===== METRICS N-API BINDINGS =====
+
+/// Get job counters, queue depth and spool latency histogram
 #[napi]
-pub fn cleanup_old_jobs(max_age_seconds: u32) -> u32 {
-    PrinterCore::cleanup_old_jobs(max_age_seconds as u64)
+pub fn get_metrics_snapshot() -> MetricsSnapshot {
+    let snapshot = crate::metrics::snapshot();
+    let latency = snapshot.spool_latency;
+
+    MetricsSnapshot {
+        jobs_submitted: snapshot.jobs_submitted as f64,
+        jobs_completed: snapshot.jobs_completed as f64,
+        jobs_failed: snapshot.jobs_failed as f64,
+        queue_depth: snapshot.queue_depth as f64,
+        spool_latency: LatencyHistogram {
+            buckets: latency
+                .buckets
+                .into_iter()
+                .map(|(le, count)| LatencyBucket {
+                    le,
+                    count: count as f64,
+                })
+                .collect(),
+            count: latency.count as f64,
+            sum: latency.sum,
+        },
+    }
 }
 
-/// Get a specific job for a printer
+/// Render current metrics in the Prometheus text exposition format
+#[cfg(feature = "prometheus")]
 #[napi]
-pub fn get_job_for_printer(printer_name: String, job_id: f64) -> Option<PrinterJob> {
-    if let Some(job) = PrinterCore::get_job_status(job_id as u64) {
-        if job.printer_name == printer_name {
-            Some(convert_printer_job(job))
-        } else {
-            None
-        }
-    } else {
-        None
+pub fn get_metrics_prometheus() -> String {
+    crate::metrics::to_prometheus(&crate::metrics::snapshot())
+}
+
+// ===== RUNTIME STATUS N-API BINDINGS =====
+
+/// Supervised background task for N-API
+#[napi(object)]
+pub struct TaskStatus {
+    pub name: String,
+    pub state: String, // "running" | "restarting" | "failed"
+    pub restarts: u32,
+    pub started_at: f64, // Unix timestamp in seconds
+    pub last_panic: Option<String>,
+}
+
+/// Background tasks of the library for N-API
+#[napi(object)]
+pub struct RuntimeStatus {
+    pub tasks: Vec<TaskStatus>,
+    pub job_threads: u32,
+    pub monitoring_running: bool,
+}
+
+/// List background tasks, including monitors that panicked and were
+/// restarted or gave up
+#[napi]
+pub fn get_runtime_status() -> RuntimeStatus {
+    let status = PrinterCore::get_runtime_status();
+    RuntimeStatus {
+        tasks: status
+            .tasks
+            .into_iter()
+            .map(|task| TaskStatus {
+                name: task.name,
+                state: task.state.as_str().to_string(),
+                restarts: task.restarts,
+                started_at: to_unix_secs(task.started_at),
+                last_panic: task.last_panic,
+            })
+            .collect(),
+        job_threads: status.job_threads,
+        monitoring_running: status.monitoring_running,
     }
 }
 
-/// Clean up old jobs for a specific printer
+// ===== LOGGING N-API BINDINGS =====
+
+/// Maximum number of log records buffered between JavaScript drains
+const MAX_BUFFERED_LOG_RECORDS: usize = 1000;
+
+lazy_static::lazy_static! {
+    static ref LOG_BUFFER: EnvBuffers<LogRecord> = EnvBuffers::new(MAX_BUFFERED_LOG_RECORDS);
+}
+
+/// Set the minimum level of log events ("off", "error", "warn", "info", "debug", "trace")
 #[napi]
-pub fn cleanup_old_jobs_for_printer(printer_name: String, max_age_seconds: u32) -> u32 {
-    PrinterCore::cleanup_old_jobs_for_printer(&printer_name, max_age_seconds as u64)
+pub fn set_log_level(level: String) -> Result<()> {
+    require_owner()?;
+    let level = crate::logging::LogLevel::parse(&level)
+        .ok_or_else(|| Error::new(Status::InvalidArg, format!("Unknown log level: {}", level)))?;
+    crate::logging::set_log_level(level);
+    Ok(())
 }
 
-/// Shutdown the library and cleanup all background threads
+/// Get the current minimum log level
+#[napi]
+pub fn get_log_level() -> String {
+    crate::logging::log_level().as_str().to_string()
+}
+
+/// Buffer log events for this environment instead of writing them to
+/// stderr
 #[napi]
-pub fn shutdown() -> Result<()> {
-    PrinterCore::shutdown_library();
+pub fn set_log_buffering(enabled: bool) -> Result<()> {
+    require_owner()?;
+    if !LOG_BUFFER.set_enabled(enabled) {
+        crate::logging::set_log_callback(None);
+        return Ok(());
+    }
+
+    crate::logging::set_log_callback(Some(std::sync::Arc::new(
+        |record: &crate::logging::LogRecord| {
+            LOG_BUFFER.push(LogRecord {
+                level: record.level.as_str().to_string(),
+                target: record.target.clone(),
+                message: record.message.clone(),
+                fields: record.fields.clone().into_iter().collect(),
+                timestamp: to_unix_secs(record.timestamp),
+            });
+        },
+    )));
     Ok(())
 }
 
-/// Poll job status until completion to keep printer instance alive
-fn poll_job_completion(job_id: u64) {
-    use crate::core::{PrinterCore, PrinterJobState};
-    use std::{thread, time::Duration};
+/// Take all buffered log events, oldest first
+#[napi]
+pub fn drain_log_records() -> Vec<LogRecord> {
+    LOG_BUFFER.drain()
+}
 
-    while let Some(job) = PrinterCore::get_job_status(job_id) {
-        match job.state {
-            PrinterJobState::COMPLETED | PrinterJobState::CANCELLED => break,
-            _ => {
-                thread::sleep(Duration::from_millis(50));
-            }
+/// Poll job status until completion to keep printer instance alive.
+/// Returns false if the job was still running when the timeout expired.
+/// Jobs held for approval return right away, as they don't progress until
+/// someone approves them.
+fn poll_job_completion(job_id: u64, timeout: Option<std::time::Duration>) -> bool {
+    use crate::core::{job_poll_interval, job_settled};
+    use std::{thread, time::Instant};
+
+    let start = Instant::now();
+    while !job_settled(job_id) {
+        if timeout.is_some_and(|timeout| start.elapsed() >= timeout) {
+            return false;
         }
+        thread::sleep(job_poll_interval(job_id));
     }
-    // If job is not found, the while loop exits naturally
+    true
 }
 
 // ===== PRINTER STATE MONITORING N-API BINDINGS =====
@@ -527,16 +4762,20 @@ pub struct PrinterStateChangeEvent {
     pub new_reasons: Option<Vec<String>>, // For state_reasons_changed events
 }
 
-/// Start global printer state monitoring
+/// Start printer state monitoring for this environment, sharing the
+/// monitor other environments started
 #[napi]
 pub fn start_state_monitoring() -> Result<()> {
-    PrinterCore::start_state_monitoring().map_err(|e| Error::new(Status::GenericFailure, e))
+    PrinterCore::start_state_monitoring_for(current_env())
+        .map_err(|e| Error::new(Status::GenericFailure, e))
 }
 
-/// Stop global printer state monitoring
+/// Stop printer state monitoring for this environment. The monitor keeps
+/// running while other environments use it.
 #[napi]
 pub fn stop_state_monitoring() -> Result<()> {
-    PrinterCore::stop_state_monitoring().map_err(|e| Error::new(Status::GenericFailure, e))
+    PrinterCore::stop_state_monitoring_for(current_env())
+        .map_err(|e| Error::new(Status::GenericFailure, e))
 }
 
 /// Check if state monitoring is active
@@ -548,6 +4787,7 @@ pub fn is_state_monitoring_active() -> bool {
 /// Set the polling interval for state monitoring (in seconds)
 #[napi]
 pub fn set_state_monitoring_interval(seconds: u32) -> Result<()> {
+    require_owner()?;
     PrinterCore::set_state_monitoring_interval(seconds as u64)
         .map_err(|e| Error::new(Status::InvalidArg, e))
 }
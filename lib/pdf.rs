@@ -0,0 +1,1507 @@
+//! Reading and incrementally updating PDF documents
+//!
+//! Just enough of PDF to change pages without a PDF library: objects are
+//! found by scanning for their `N G obj` headers rather than by trusting the
+//! cross-reference table, which is often slightly off in generated files,
+//! and changes are appended as an incremental update. The original bytes
+//! are kept as they are, so content this module doesn't understand, like
+//! fonts, images and annotations, prints exactly as before.
+//!
+//! Objects in compressed object streams are read with the compression
+//! feature. Encrypted documents aren't supported.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::document::find;
+
+/// Object number and generation
+pub type ObjectId = (u32, u16);
+
+/// Nesting of arrays, dictionaries and page tree levels accepted
+const MAX_DEPTH: usize = 64;
+
+/// Nodes of the page tree and pages read from a document, so a tree that
+/// branches out to the same kids over and over can't take forever to walk
+const MAX_PAGE_TREE_NODES: usize = 200_000;
+const MAX_PAGES: usize = 100_000;
+
+/// A PDF object
+#[derive(Clone, Debug, PartialEq)]
+pub enum Object {
+    Null,
+    Bool(bool),
+    Number(f64),
+    /// Name without the leading slash, as written (with `#xx` escapes)
+    Name(String),
+    /// String literal or hex string as written, delimiters included
+    String(Vec<u8>),
+    Array(Vec<Object>),
+    Dict(Dict),
+    Ref(ObjectId),
+}
+
+impl Object {
+    pub fn name(name: &str) -> Object {
+        Object::Name(name.to_string())
+    }
+
+    pub fn as_name(&self) -> Option<&str> {
+        match self {
+            Object::Name(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Object::Number(number) => Some(*number),
+            _ => None,
+        }
+    }
+
+    pub fn as_reference(&self) -> Option<ObjectId> {
+        match self {
+            Object::Ref(id) => Some(*id),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Object]> {
+        match self {
+            Object::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_dict(&self) -> Option<&Dict> {
+        match self {
+            Object::Dict(dict) => Some(dict),
+            _ => None,
+        }
+    }
+
+    pub fn into_dict(self) -> Option<Dict> {
+        match self {
+            Object::Dict(dict) => Some(dict),
+            _ => None,
+        }
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        match self {
+            Object::Null => out.extend_from_slice(b"null"),
+            Object::Bool(value) => out.extend_from_slice(value.to_string().as_bytes()),
+            Object::Number(number) => out.extend_from_slice(format_number(*number).as_bytes()),
+            Object::Name(name) => {
+                out.push(b'/');
+                out.extend_from_slice(name.as_bytes());
+            }
+            Object::String(raw) => out.extend_from_slice(raw),
+            Object::Array(items) => {
+                out.push(b'[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push(b' ');
+                    }
+                    item.write(out);
+                }
+                out.push(b']');
+            }
+            Object::Dict(dict) => dict.write(out),
+            Object::Ref((number, generation)) => {
+                out.extend_from_slice(format!("{} {} R", number, generation).as_bytes())
+            }
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.write(&mut out);
+        out
+    }
+}
+
+/// A dictionary, keeping its entries in order
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Dict(Vec<(String, Object)>);
+
+impl Dict {
+    pub fn new() -> Dict {
+        Dict::default()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Object> {
+        self.0
+            .iter()
+            .find(|(entry, _)| entry == key)
+            .map(|(_, value)| value)
+    }
+
+    pub fn set(&mut self, key: &str, value: Object) {
+        match self.0.iter_mut().find(|(entry, _)| entry == key) {
+            Some((_, existing)) => *existing = value,
+            None => self.0.push((key.to_string(), value)),
+        }
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<Object> {
+        let index = self.0.iter().position(|(entry, _)| entry == key)?;
+        Some(self.0.remove(index).1)
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(b"<<");
+        for (key, value) in &self.0 {
+            out.push(b'/');
+            out.extend_from_slice(key.as_bytes());
+            out.push(b' ');
+            value.write(out);
+        }
+        out.extend_from_slice(b">>");
+    }
+}
+
+impl<const N: usize> From<[(&str, Object); N]> for Dict {
+    fn from(entries: [(&str, Object); N]) -> Dict {
+        Dict(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+}
+
+/// Write a number for a PDF: integers without a fraction, and at most four
+/// decimals otherwise
+pub fn format_number(number: f64) -> String {
+    if number.fract() == 0.0 && number.abs() < 1e15 {
+        return format!("{}", number as i64);
+    }
+    let formatted = format!("{:.4}", number);
+    let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+    match trimmed {
+        "" | "-" | "-0" => "0".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+fn is_whitespace(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\t' | b'\r' | b'\n' | b'\x0c' | b'\0')
+}
+
+fn is_delimiter(byte: u8) -> bool {
+    b"()<>[]{}/%".contains(&byte)
+}
+
+/// Reads objects from PDF syntax
+struct Lexer<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(data: &'a [u8], pos: usize) -> Lexer<'a> {
+        Lexer {
+            data,
+            pos: pos.min(data.len()),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(&byte) = self.data.get(self.pos) {
+            if is_whitespace(byte) {
+                self.pos += 1;
+            } else if byte == b'%' {
+                while self
+                    .data
+                    .get(self.pos)
+                    .is_some_and(|b| *b != b'\n' && *b != b'\r')
+                {
+                    self.pos += 1;
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// A run of regular characters: a number, keyword or name
+    fn token(&mut self) -> &'a [u8] {
+        let data = self.data;
+        let start = self.pos;
+        while data
+            .get(self.pos)
+            .is_some_and(|b| !is_whitespace(*b) && !is_delimiter(*b))
+        {
+            self.pos += 1;
+        }
+        &data[start..self.pos]
+    }
+
+    /// Skip an object header, `N G obj`
+    fn header(&mut self) -> Option<()> {
+        for expected in [None, None, Some(&b"obj"[..])] {
+            self.skip_whitespace();
+            let token = self.token();
+            let matches = match expected {
+                Some(keyword) => token == keyword,
+                None => !token.is_empty() && token.iter().all(u8::is_ascii_digit),
+            };
+            if !matches {
+                return None;
+            }
+        }
+        Some(())
+    }
+
+    fn object(&mut self, depth: usize) -> Result<Object, String> {
+        if depth > MAX_DEPTH {
+            return Err("PDF objects are nested too deeply".to_string());
+        }
+        self.skip_whitespace();
+        let data = self.data;
+        let start = self.pos;
+        match data.get(start) {
+            None => Err("PDF object is truncated".to_string()),
+            Some(b'<') if data.get(start + 1) == Some(&b'<') => {
+                self.pos += 2;
+                let mut dict = Dict::new();
+                loop {
+                    self.skip_whitespace();
+                    if data[self.pos..].starts_with(b">>") {
+                        self.pos += 2;
+                        return Ok(Object::Dict(dict));
+                    }
+                    match self.object(depth + 1)? {
+                        Object::Name(key) => {
+                            let value = self.object(depth + 1)?;
+                            dict.set(&key, value);
+                        }
+                        _ => return Err("PDF dictionary keys must be names".to_string()),
+                    }
+                }
+            }
+            Some(b'<') => {
+                let end = data[start..]
+                    .iter()
+                    .position(|b| *b == b'>')
+                    .ok_or_else(|| "PDF hex string is truncated".to_string())?;
+                self.pos = start + end + 1;
+                Ok(Object::String(data[start..self.pos].to_vec()))
+            }
+            Some(b'(') => {
+                let mut nesting = 0;
+                while let Some(&byte) = data.get(self.pos) {
+                    self.pos += 1;
+                    match byte {
+                        b'\\' => self.pos += 1,
+                        b'(' => nesting += 1,
+                        b')' => {
+                            nesting -= 1;
+                            if nesting == 0 {
+                                let end = self.pos.min(data.len());
+                                return Ok(Object::String(data[start..end].to_vec()));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                Err("PDF string is truncated".to_string())
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                loop {
+                    self.skip_whitespace();
+                    if data.get(self.pos) == Some(&b']') {
+                        self.pos += 1;
+                        return Ok(Object::Array(items));
+                    }
+                    items.push(self.object(depth + 1)?);
+                }
+            }
+            Some(b'/') => {
+                self.pos += 1;
+                Ok(Object::Name(
+                    String::from_utf8_lossy(self.token()).into_owned(),
+                ))
+            }
+            Some(&byte) => {
+                let token = self.token();
+                match token {
+                    b"" => Err(format!("Unexpected '{}' in PDF object", byte as char)),
+                    b"true" => Ok(Object::Bool(true)),
+                    b"false" => Ok(Object::Bool(false)),
+                    b"null" => Ok(Object::Null),
+                    _ => {
+                        let text = std::str::from_utf8(token).unwrap_or_default();
+                        let number: f64 = text
+                            .parse()
+                            .map_err(|_| format!("Unexpected '{}' in PDF object", text))?;
+                        if let Some(id) = self.reference_after(text) {
+                            return Ok(Object::Ref(id));
+                        }
+                        Ok(Object::Number(number))
+                    }
+                }
+            }
+        }
+    }
+
+    /// Read the rest of an indirect reference, `G R`, after its object
+    /// number, or leave the position as it was
+    fn reference_after(&mut self, number: &str) -> Option<ObjectId> {
+        let start = self.pos;
+        let reference = self.reference_rest(number);
+        if reference.is_none() {
+            self.pos = start;
+        }
+        reference
+    }
+
+    fn reference_rest(&mut self, number: &str) -> Option<ObjectId> {
+        let number: u32 = number.parse().ok()?;
+        self.skip_whitespace();
+        let generation: u16 = std::str::from_utf8(self.token()).ok()?.parse().ok()?;
+        self.skip_whitespace();
+        (self.token() == b"R").then_some((number, generation))
+    }
+}
+
+/// Where the latest definition of an object is
+#[derive(Clone, Copy, Debug)]
+enum Location {
+    Direct {
+        offset: usize,
+    },
+    /// In a decompressed object stream, by index in `object_streams`
+    Compressed {
+        stream: usize,
+        offset: usize,
+    },
+}
+
+/// A page, with what it inherits from the page tree resolved
+#[derive(Clone, Debug)]
+pub struct Page {
+    pub id: ObjectId,
+    pub dict: Dict,
+    /// The page's resources, or those it inherits, with the font,
+    /// graphics state and XObject dictionaries resolved so entries can be
+    /// added to them
+    pub resources: Dict,
+    /// Visible area in user space, `[x0, y0, x1, y1]`: the crop box, or
+    /// the media box
+    pub bounds: [f64; 4],
+    /// Clockwise rotation when displayed: 0, 90, 180 or 270
+    pub rotate: u32,
+}
+
+impl Page {
+    /// Width and height as displayed, in points
+    pub fn size(&self) -> (f64, f64) {
+        let [x0, y0, x1, y1] = self.bounds;
+        match self.rotate {
+            90 | 270 => (y1 - y0, x1 - x0),
+            _ => (x1 - x0, y1 - y0),
+        }
+    }
+
+    /// Matrix from the page's user space to display space, the inverse of
+    /// `display_matrix`
+    pub fn user_matrix(&self) -> [f64; 6] {
+        let [a, b, c, d, e, f] = self.display_matrix();
+        // Rotations have a determinant of 1, and their inverse is their
+        // transpose
+        [a, c, b, d, -(a * e + b * f), -(c * e + d * f)]
+    }
+
+    /// Matrix from display space, with its origin at the bottom left corner
+    /// of the page as displayed, to the page's user space
+    pub fn display_matrix(&self) -> [f64; 6] {
+        let [x0, y0, x1, y1] = self.bounds;
+        match self.rotate {
+            90 => [0.0, 1.0, -1.0, 0.0, x1, y0],
+            180 => [-1.0, 0.0, 0.0, -1.0, x1, y1],
+            270 => [0.0, -1.0, 1.0, 0.0, x0, y1],
+            _ => [1.0, 0.0, 0.0, 1.0, x0, y0],
+        }
+    }
+
+    /// Add a resource, e.g. a font, under `category`
+    pub fn add_resource(&mut self, category: &str, name: &str, value: Object) {
+        let mut entries = match self.resources.get(category) {
+            Some(Object::Dict(entries)) => entries.clone(),
+            _ => Dict::new(),
+        };
+        entries.set(name, value);
+        self.resources.set(category, Object::Dict(entries));
+    }
+}
+
+/// A PDF document being changed
+pub struct Document<'a> {
+    data: &'a [u8],
+    locations: HashMap<u32, Location>,
+    object_streams: Vec<Vec<u8>>,
+    trailer: Dict,
+    /// Offset of the latest cross-reference section
+    prev_xref: usize,
+    next_number: u32,
+    /// New and replaced objects, written out by `finish`
+    updates: BTreeMap<u32, (u16, Vec<u8>)>,
+    /// Stream saving the graphics state before a page's original content
+    save_state: Option<ObjectId>,
+}
+
+impl<'a> Document<'a> {
+    pub fn parse(data: &'a [u8]) -> Result<Document<'a>, String> {
+        if !crate::document::is_pdf(data) {
+            return Err("Not a PDF document".to_string());
+        }
+        let startxref =
+            rfind(data, b"startxref").ok_or_else(|| "Missing cross-reference table".to_string())?;
+        let prev_xref = Lexer::new(data, startxref + b"startxref".len())
+            .object(0)
+            .ok()
+            .and_then(|offset| offset.as_number())
+            .filter(|offset| *offset >= 0.0 && (*offset as usize) < data.len())
+            .ok_or_else(|| "Invalid cross-reference offset".to_string())?
+            as usize;
+
+        // Later definitions replace earlier ones
+        let headers = object_headers(data);
+        let mut positions: HashMap<u32, (usize, Location)> = HashMap::new();
+        for &(number, _, offset) in &headers {
+            positions.insert(number, (offset, Location::Direct { offset }));
+        }
+
+        let mut document = Document {
+            data,
+            locations: positions.iter().map(|(n, (_, l))| (*n, *l)).collect(),
+            object_streams: Vec::new(),
+            trailer: Dict::new(),
+            prev_xref,
+            next_number: 1,
+            updates: BTreeMap::new(),
+            save_state: None,
+        };
+        document.trailer = document.read_trailer()?;
+        if document.trailer.get("Encrypt").is_some() {
+            return Err("Encrypted PDFs can't be changed".to_string());
+        }
+
+        // Read the objects in object streams that are still current
+        let mut search = 0;
+        while let Some(found) = find(&data[search..], b"/ObjStm") {
+            let at = search + found;
+            search = at + 1;
+            let Some(&(number, _, offset)) =
+                headers.iter().rev().find(|(_, _, offset)| *offset < at)
+            else {
+                continue;
+            };
+            let current = matches!(
+                document.locations.get(&number),
+                Some(Location::Direct { offset: latest }) if *latest == offset
+            );
+            if !current {
+                continue;
+            }
+            let Some((dict, raw)) = document.stream(number) else {
+                continue;
+            };
+            if dict.get("Type").and_then(Object::as_name) != Some("ObjStm") {
+                continue;
+            }
+            let decoded = decode_stream(&dict, raw)?;
+            let count = dict.get("N").and_then(Object::as_number).unwrap_or(0.0) as usize;
+            let first = dict.get("First").and_then(Object::as_number).unwrap_or(0.0) as usize;
+            let mut lexer = Lexer::new(&decoded, 0);
+            let stream = document.object_streams.len();
+            for _ in 0..count {
+                let (Ok(Object::Number(inner)), Ok(Object::Number(inner_offset))) =
+                    (lexer.object(0), lexer.object(0))
+                else {
+                    break;
+                };
+                let inner = inner as u32;
+                if positions
+                    .get(&inner)
+                    .is_none_or(|(position, _)| *position < offset)
+                {
+                    let location = Location::Compressed {
+                        stream,
+                        offset: first + inner_offset as usize,
+                    };
+                    positions.insert(inner, (offset, location));
+                }
+            }
+            document.object_streams.push(decoded);
+        }
+        document.locations = positions.into_iter().map(|(n, (_, l))| (n, l)).collect();
+
+        let size = document
+            .trailer
+            .get("Size")
+            .and_then(Object::as_number)
+            .unwrap_or(0.0) as u32;
+        let highest = document.locations.keys().copied().max().unwrap_or(0);
+        document.next_number = size.max(highest + 1);
+        Ok(document)
+    }
+
+    /// The latest trailer: the dictionary after `trailer`, or of the
+    /// cross-reference stream
+    fn read_trailer(&self) -> Result<Dict, String> {
+        let data = self.data;
+        let section = &data[self.prev_xref..];
+        let at_xref = if section.starts_with(b"xref") {
+            find(section, b"trailer").map(|at| self.prev_xref + at + b"trailer".len())
+        } else {
+            let mut lexer = Lexer::new(data, self.prev_xref);
+            lexer.header().map(|_| lexer.pos)
+        };
+        // Fall back to the last trailer in the file if the offset is off
+        let fallback = rfind(data, b"trailer").map(|at| at + b"trailer".len());
+        for start in [at_xref, fallback].into_iter().flatten() {
+            if let Ok(Object::Dict(trailer)) = Lexer::new(data, start).object(0) {
+                if trailer.get("Root").is_some() {
+                    return Ok(trailer);
+                }
+            }
+        }
+        Err("Missing document catalog".to_string())
+    }
+
+    /// An object by number, None if it doesn't exist or can't be read.
+    /// For streams, this is the stream's dictionary.
+    pub fn object(&self, number: u32) -> Option<Object> {
+        if let Some((_, body)) = self.updates.get(&number) {
+            return Lexer::new(body, 0).object(0).ok();
+        }
+        match *self.locations.get(&number)? {
+            Location::Direct { offset } => {
+                let mut lexer = Lexer::new(self.data, offset);
+                lexer.header()?;
+                lexer.object(0).ok()
+            }
+            Location::Compressed { stream, offset } => {
+                Lexer::new(&self.object_streams[stream], offset)
+                    .object(0)
+                    .ok()
+            }
+        }
+    }
+
+    /// Follow references to the object they point at
+    pub fn resolve(&self, object: &Object) -> Option<Object> {
+        let mut object = object.clone();
+        for _ in 0..MAX_DEPTH {
+            match object {
+                Object::Ref((number, _)) => object = self.object(number)?,
+                object => return Some(object),
+            }
+        }
+        None
+    }
+
+    /// A stream's dictionary and its data as stored, still encoded
+    pub fn stream(&self, number: u32) -> Option<(Dict, &'a [u8])> {
+        let Location::Direct { offset } = *self.locations.get(&number)? else {
+            return None;
+        };
+        let mut lexer = Lexer::new(self.data, offset);
+        lexer.header()?;
+        let (dict, range) = self.stream_body(lexer)?;
+        Some((dict, &self.data[range]))
+    }
+
+    /// Like `stream`, including streams added since the document was read
+    fn stream_data(&self, number: u32) -> Option<(Dict, Vec<u8>)> {
+        if let Some((_, body)) = self.updates.get(&number) {
+            let (dict, range) = self.stream_body(Lexer::new(body, 0))?;
+            return Some((dict, body[range].to_vec()));
+        }
+        self.stream(number)
+            .map(|(dict, data)| (dict, data.to_vec()))
+    }
+
+    /// Dictionary of the stream at the lexer and the range of its data
+    fn stream_body(&self, mut lexer: Lexer<'_>) -> Option<(Dict, std::ops::Range<usize>)> {
+        let data = lexer.data;
+        let dict = lexer.object(0).ok()?.into_dict()?;
+        lexer.skip_whitespace();
+        if !data[lexer.pos..].starts_with(b"stream") {
+            return None;
+        }
+        let mut start = lexer.pos + b"stream".len();
+        if data.get(start) == Some(&b'\r') {
+            start += 1;
+        }
+        if data.get(start) == Some(&b'\n') {
+            start += 1;
+        }
+        let length = dict
+            .get("Length")
+            .and_then(|length| self.resolve(length))
+            .and_then(|length| length.as_number())
+            .map(|length| length as usize)
+            .filter(|length| start + length <= data.len());
+        let end = match length {
+            Some(length) => start + length,
+            None => start + find(&data[start..], b"endstream")?,
+        };
+        Some((dict, start..end))
+    }
+
+    /// Root node of the page tree
+    fn page_tree(&self) -> Result<ObjectId, String> {
+        let root = self
+            .trailer
+            .get("Root")
+            .and_then(|root| self.resolve(root))
+            .and_then(Object::into_dict)
+            .ok_or_else(|| "Missing document catalog".to_string())?;
+        root.get("Pages")
+            .and_then(Object::as_reference)
+            .ok_or_else(|| "Missing page tree".to_string())
+    }
+
+    /// Pages in order
+    pub fn pages(&self) -> Result<Vec<Page>, String> {
+        let tree = self.page_tree()?;
+        let mut pages = Vec::new();
+        let mut visited = HashSet::new();
+        self.collect_pages(tree, Inherited::default(), &mut pages, &mut visited, 0)?;
+        if pages.is_empty() {
+            return Err("No pages found".to_string());
+        }
+        Ok(pages)
+    }
+
+    fn collect_pages(
+        &self,
+        id: ObjectId,
+        mut inherited: Inherited,
+        pages: &mut Vec<Page>,
+        visited: &mut HashSet<ObjectId>,
+        depth: usize,
+    ) -> Result<(), String> {
+        if depth > MAX_DEPTH {
+            return Err("Page tree is nested too deeply".to_string());
+        }
+        // Each node has one parent, so a node reached twice means the tree
+        // loops or shares kids
+        if !visited.insert(id) {
+            return Err(format!("Page tree visits object {} more than once", id.0));
+        }
+        if visited.len() > MAX_PAGE_TREE_NODES || pages.len() >= MAX_PAGES {
+            return Err("Page tree is too large".to_string());
+        }
+        let node = self
+            .object(id.0)
+            .and_then(Object::into_dict)
+            .ok_or_else(|| format!("Page object {} not found", id.0))?;
+        for (key, slot) in [
+            ("Resources", &mut inherited.resources),
+            ("MediaBox", &mut inherited.media_box),
+            ("CropBox", &mut inherited.crop_box),
+            ("Rotate", &mut inherited.rotate),
+        ] {
+            if let Some(value) = node.get(key) {
+                *slot = self.resolve(value);
+            }
+        }
+
+        if let Some(kids) = node.get("Kids") {
+            let kids = self.resolve(kids).unwrap_or(Object::Null);
+            for kid in kids.as_array().unwrap_or_default() {
+                if let Some(kid) = kid.as_reference() {
+                    self.collect_pages(kid, inherited.clone(), pages, visited, depth + 1)?;
+                }
+            }
+            return Ok(());
+        }
+
+        let mut resources = inherited
+            .resources
+            .and_then(Object::into_dict)
+            .unwrap_or_default();
+        for category in ["Font", "ExtGState", "XObject"] {
+            if let Some(entries) = resources.get(category).and_then(|e| self.resolve(e)) {
+                resources.set(category, entries);
+            }
+        }
+        let rectangle = |object: Option<Object>| {
+            let items = object?;
+            let numbers: Vec<f64> = items
+                .as_array()?
+                .iter()
+                .filter_map(|item| self.resolve(item)?.as_number())
+                .collect();
+            let [a, b, c, d] = numbers[..] else {
+                return None;
+            };
+            Some([a.min(c), b.min(d), a.max(c), b.max(d)])
+        };
+        let media_box = rectangle(inherited.media_box).unwrap_or([0.0, 0.0, 612.0, 792.0]);
+        let bounds = rectangle(inherited.crop_box).unwrap_or(media_box);
+        let rotate = inherited
+            .rotate
+            .and_then(|rotate| rotate.as_number())
+            .map_or(0, |rotate| (rotate as i64).rem_euclid(360) as u32 / 90 * 90);
+        pages.push(Page {
+            id,
+            dict: node,
+            resources,
+            bounds,
+            rotate,
+        });
+        Ok(())
+    }
+
+    /// Add an object, returning its reference
+    pub fn add(&mut self, object: Object) -> ObjectId {
+        let id = (self.next_number, 0);
+        self.next_number += 1;
+        self.updates.insert(id.0, (id.1, object.to_bytes()));
+        id
+    }
+
+    /// Add a stream with its data as given; `dict` names any filter the
+    /// data is encoded with
+    pub fn add_stream(&mut self, mut dict: Dict, data: &[u8]) -> ObjectId {
+        dict.set("Length", Object::Number(data.len() as f64));
+        let mut body = Object::Dict(dict).to_bytes();
+        body.extend_from_slice(b"\nstream\n");
+        body.extend_from_slice(data);
+        body.extend_from_slice(b"\nendstream");
+        let id = (self.next_number, 0);
+        self.next_number += 1;
+        self.updates.insert(id.0, (id.1, body));
+        id
+    }
+
+    /// Add a stream, compressing its data if this build can
+    pub fn add_deflated_stream(&mut self, mut dict: Dict, data: &[u8]) -> ObjectId {
+        match deflate(data) {
+            Some(compressed) => {
+                dict.set("Filter", Object::name("FlateDecode"));
+                self.add_stream(dict, &compressed)
+            }
+            None => self.add_stream(dict, data),
+        }
+    }
+
+    /// Replace an object
+    pub fn replace(&mut self, id: ObjectId, object: Object) {
+        self.updates.insert(id.0, (id.1, object.to_bytes()));
+    }
+
+    /// Replace the document's pages, e.g. with sheets laid out from the
+    /// original pages. The new pages must set their media box, resources
+    /// and rotation, since the page tree's are inherited otherwise.
+    pub fn replace_pages(&mut self, pages: Vec<Dict>) -> Result<(), String> {
+        let tree = self.page_tree()?;
+        let mut node = self
+            .object(tree.0)
+            .and_then(Object::into_dict)
+            .ok_or_else(|| "Missing page tree".to_string())?;
+        let mut kids = Vec::with_capacity(pages.len());
+        for mut page in pages {
+            page.set("Parent", Object::Ref(tree));
+            kids.push(Object::Ref(self.add(Object::Dict(page))));
+        }
+        node.set("Count", Object::Number(kids.len() as f64));
+        node.set("Kids", Object::Array(kids));
+        self.replace(tree, Object::Dict(node));
+        Ok(())
+    }
+
+    /// Add pages after the document's last page. Like those of
+    /// `replace_pages`, they must set their media box, resources and
+    /// rotation.
+    pub fn append_pages(&mut self, pages: Vec<Dict>) -> Result<(), String> {
+        let tree = self.page_tree()?;
+        let mut node = self
+            .object(tree.0)
+            .and_then(Object::into_dict)
+            .ok_or_else(|| "Missing page tree".to_string())?;
+        let mut kids = node
+            .get("Kids")
+            .and_then(|kids| self.resolve(kids))
+            .and_then(|kids| kids.as_array().map(<[Object]>::to_vec))
+            .unwrap_or_default();
+        let count = node
+            .get("Count")
+            .and_then(|count| self.resolve(count))
+            .and_then(|count| count.as_number())
+            .unwrap_or(0.0);
+        let added = pages.len();
+        for mut page in pages {
+            page.set("Parent", Object::Ref(tree));
+            kids.push(Object::Ref(self.add(Object::Dict(page))));
+        }
+        node.set("Count", Object::Number(count + added as f64));
+        node.set("Kids", Object::Array(kids));
+        self.replace(tree, Object::Dict(node));
+        Ok(())
+    }
+
+    /// References to a page's content streams
+    fn content_streams(&self, page: &Page) -> Vec<Object> {
+        match page.dict.get("Contents") {
+            None => Vec::new(),
+            Some(Object::Array(items)) => items.clone(),
+            Some(contents) => match self.resolve(contents) {
+                Some(Object::Array(items)) => items,
+                _ => vec![contents.clone()],
+            },
+        }
+    }
+
+    /// A stream's dictionary and its decoded data
+    pub fn decoded_stream(&self, number: u32) -> Result<(Dict, Vec<u8>), String> {
+        let (dict, data) = self
+            .stream_data(number)
+            .ok_or_else(|| format!("Stream {} not found", number))?;
+        let decoded = decode_stream(&dict, &data)?;
+        Ok((dict, decoded))
+    }
+
+    /// A page's content streams, decoded and joined
+    pub fn page_content(&self, page: &Page) -> Result<Vec<u8>, String> {
+        let mut content = Vec::new();
+        for stream in self.content_streams(page) {
+            let number = stream
+                .as_reference()
+                .ok_or_else(|| format!("Invalid content stream on page object {}", page.id.0))?
+                .0;
+            content.extend_from_slice(&self.decoded_stream(number)?.1);
+            // Keep the last token of one stream apart from the next's first
+            content.push(b'\n');
+        }
+        Ok(content)
+    }
+
+    /// Add a page as a form XObject, so it can be drawn onto other pages.
+    /// The form is in the page's user space and clipped to its visible
+    /// area.
+    pub fn add_page_form(&mut self, page: &Page) -> Result<ObjectId, String> {
+        let content = self.page_content(page)?;
+        let bounds = page.bounds.iter().map(|n| Object::Number(*n)).collect();
+        let form = Dict::from([
+            ("Type", Object::name("XObject")),
+            ("Subtype", Object::name("Form")),
+            ("BBox", Object::Array(bounds)),
+            ("Resources", Object::Dict(page.resources.clone())),
+        ]);
+        Ok(self.add_deflated_stream(form, &content))
+    }
+
+    /// Draw `content` over a page, in display space (see
+    /// `Page::display_matrix`) with the default graphics state. The
+    /// resources it uses must have been added to the page.
+    pub fn overlay(&mut self, page: &mut Page, content: &[u8]) {
+        let save_state = match self.save_state {
+            Some(id) => id,
+            None => {
+                let id = self.add_stream(Dict::new(), b"q");
+                self.save_state = Some(id);
+                id
+            }
+        };
+        let original = self.content_streams(page);
+
+        let matrix: Vec<String> = page
+            .display_matrix()
+            .iter()
+            .map(|value| format_number(*value))
+            .collect();
+        let mut stamp = format!("Q\nq {} cm\n", matrix.join(" ")).into_bytes();
+        stamp.extend_from_slice(content);
+        stamp.extend_from_slice(b"\nQ");
+        let stamp = self.add_deflated_stream(Dict::new(), &stamp);
+
+        let mut contents = vec![Object::Ref(save_state)];
+        contents.extend(original);
+        contents.push(Object::Ref(stamp));
+        page.dict.set("Contents", Object::Array(contents));
+        page.dict
+            .set("Resources", Object::Dict(page.resources.clone()));
+        self.replace(page.id, Object::Dict(page.dict.clone()));
+    }
+
+    /// The document with the changes appended as an incremental update
+    pub fn finish(self) -> Vec<u8> {
+        let mut pdf = self.data.to_vec();
+        if self.updates.is_empty() {
+            return pdf;
+        }
+        if !pdf.ends_with(b"\n") {
+            pdf.push(b'\n');
+        }
+        let mut offsets = Vec::with_capacity(self.updates.len());
+        for (number, (generation, body)) in &self.updates {
+            offsets.push((*number, *generation, pdf.len()));
+            pdf.extend_from_slice(format!("{} {} obj\n", number, generation).as_bytes());
+            pdf.extend_from_slice(body);
+            pdf.extend_from_slice(b"\nendobj\n");
+        }
+
+        let xref = pdf.len();
+        pdf.extend_from_slice(b"xref\n");
+        for (number, generation, offset) in offsets {
+            pdf.extend_from_slice(
+                format!("{} 1\n{:010} {:05} n \n", number, offset, generation).as_bytes(),
+            );
+        }
+        let mut trailer = Dict::from([
+            ("Size", Object::Number(self.next_number as f64)),
+            ("Prev", Object::Number(self.prev_xref as f64)),
+        ]);
+        for key in ["Root", "Info", "ID"] {
+            if let Some(value) = self.trailer.get(key) {
+                trailer.set(key, value.clone());
+            }
+        }
+        pdf.extend_from_slice(b"trailer\n");
+        pdf.extend_from_slice(&Object::Dict(trailer).to_bytes());
+        pdf.extend_from_slice(format!("\nstartxref\n{}\n%%EOF\n", xref).as_bytes());
+        pdf
+    }
+}
+
+/// Attributes pages inherit from the page tree
+#[derive(Clone, Default)]
+struct Inherited {
+    resources: Option<Object>,
+    media_box: Option<Object>,
+    crop_box: Option<Object>,
+    rotate: Option<Object>,
+}
+
+fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .rposition(|window| window == needle)
+}
+
+/// Object headers (`N G obj`) in file order: number, generation and the
+/// offset the header starts at
+fn object_headers(data: &[u8]) -> Vec<(u32, u16, usize)> {
+    let mut headers = Vec::new();
+    let mut pos = 0;
+    while let Some(found) = find(&data[pos..], b"obj") {
+        let at = pos + found;
+        pos = at + b"obj".len();
+        // Skip `endobj` and longer words, which don't follow digits
+        if data
+            .get(pos)
+            .is_some_and(|b| !is_whitespace(*b) && !is_delimiter(*b))
+        {
+            continue;
+        }
+        if let Some(header) = header_before(data, at) {
+            headers.push(header);
+        }
+    }
+    headers
+}
+
+/// Read `N G ` backwards from the `obj` keyword at `at`
+fn header_before(data: &[u8], at: usize) -> Option<(u32, u16, usize)> {
+    let start_of = |end: usize, class: fn(u8) -> bool| {
+        let start = data[..end]
+            .iter()
+            .rposition(|b| !class(*b))
+            .map_or(0, |p| p + 1);
+        (start < end).then_some(start)
+    };
+    let generation_end = start_of(at, is_whitespace)?;
+    let generation_start = start_of(generation_end, |b| b.is_ascii_digit())?;
+    let number_end = start_of(generation_start, is_whitespace)?;
+    let number_start = start_of(number_end, |b| b.is_ascii_digit())?;
+    if number_start > 0 && !is_whitespace(data[number_start - 1]) {
+        return None;
+    }
+    let parse = |start: usize, end: usize| std::str::from_utf8(&data[start..end]).ok();
+    let number = parse(number_start, number_end)?.parse().ok()?;
+    let generation = parse(generation_start, generation_end)?.parse().ok()?;
+    Some((number, generation, number_start))
+}
+
+/// A document with one empty page of `width` by `height` points, to build
+/// on with `Document`, e.g. to print an image as a PDF
+pub fn blank_document(width: f64, height: f64) -> Vec<u8> {
+    let objects = [
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] >>",
+            format_number(width),
+            format_number(height)
+        ),
+    ];
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).as_bytes());
+    }
+    let xref = pdf.len();
+    let mut trailer = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        trailer.push_str(&format!("{:010} 00000 n \n", offset));
+    }
+    trailer.push_str(&format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    ));
+    pdf.extend_from_slice(trailer.as_bytes());
+    pdf
+}
+
+/// Decode a stream's data. Only FlateDecode without a predictor is
+/// supported, which is what content and object streams use.
+pub fn decode_stream(dict: &Dict, raw: &[u8]) -> Result<Vec<u8>, String> {
+    let filters: Vec<&str> = match dict.get("Filter") {
+        None => Vec::new(),
+        Some(Object::Name(filter)) => vec![filter.as_str()],
+        Some(Object::Array(filters)) => filters.iter().filter_map(Object::as_name).collect(),
+        Some(_) => return Err("Invalid PDF stream filter".to_string()),
+    };
+    let predictor = dict
+        .get("DecodeParms")
+        .and_then(Object::as_dict)
+        .and_then(|parameters| parameters.get("Predictor"))
+        .and_then(Object::as_number);
+    if predictor.is_some_and(|predictor| predictor > 1.0) {
+        return Err("PDF stream predictors aren't supported".to_string());
+    }
+    let mut data = raw.to_vec();
+    for filter in filters {
+        data = match filter {
+            "FlateDecode" | "Fl" => inflate(&data)?,
+            filter => return Err(format!("PDF stream filter {} isn't supported", filter)),
+        };
+    }
+    Ok(data)
+}
+
+#[cfg(feature = "compression")]
+fn inflate(data: &[u8]) -> Result<Vec<u8>, String> {
+    use std::io::Read;
+
+    /// Largest stream decompressed, against decompression bombs
+    const MAX_STREAM_BYTES: u64 = 256 * 1024 * 1024;
+
+    let mut decoded = Vec::new();
+    flate2::read::ZlibDecoder::new(data)
+        .take(MAX_STREAM_BYTES)
+        .read_to_end(&mut decoded)
+        .map_err(|e| format!("Failed to decompress PDF stream: {}", e))?;
+    Ok(decoded)
+}
+
+#[cfg(not(feature = "compression"))]
+fn inflate(_data: &[u8]) -> Result<Vec<u8>, String> {
+    Err("Reading compressed PDF streams requires the compression feature".to_string())
+}
+
+#[cfg(feature = "compression")]
+fn deflate(data: &[u8]) -> Option<Vec<u8>> {
+    use std::io::Write;
+
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(data).ok()?;
+    encoder.finish().ok()
+}
+
+#[cfg(not(feature = "compression"))]
+fn deflate(_data: &[u8]) -> Option<Vec<u8>> {
+    None
+}
+
+/// A standard font every PDF reader has, so text can be added without
+/// embedding one
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Font {
+    Helvetica,
+    HelveticaBold,
+}
+
+/// Widths of the printable ASCII characters, from space to `~`, in
+/// thousandths of the font size
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+const HELVETICA_BOLD_WIDTHS: [u16; 95] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611, 975, 722, 722, 722, 722, 667,
+    611, 778, 722, 278, 556, 722, 611, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 333, 278, 333, 584, 556, 333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556,
+    278, 889, 611, 611, 611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+];
+
+impl Font {
+    /// Font dictionary, in WinAnsiEncoding
+    pub fn dict(&self) -> Object {
+        let base_font = match self {
+            Font::Helvetica => "Helvetica",
+            Font::HelveticaBold => "Helvetica-Bold",
+        };
+        Object::Dict(Dict::from([
+            ("Type", Object::name("Font")),
+            ("Subtype", Object::name("Type1")),
+            ("BaseFont", Object::name(base_font)),
+            ("Encoding", Object::name("WinAnsiEncoding")),
+        ]))
+    }
+
+    /// Width of text set in the font, in points
+    pub fn width(&self, text: &str, size: f64) -> f64 {
+        let units: u32 = encode_text(text)
+            .into_iter()
+            .map(|byte| self.glyph_width(byte))
+            .sum();
+        units as f64 * size / 1000.0
+    }
+
+    fn glyph_width(&self, byte: u8) -> u32 {
+        let bold = *self == Font::HelveticaBold;
+        let width = match byte {
+            b' '..=b'~' => match self {
+                Font::Helvetica => HELVETICA_WIDTHS[(byte - b' ') as usize],
+                Font::HelveticaBold => HELVETICA_BOLD_WIDTHS[(byte - b' ') as usize],
+            },
+            // Curly quotes
+            0x91 | 0x92 if bold => 278,
+            0x91 | 0x92 => 222,
+            0x93 | 0x94 if bold => 500,
+            0x93 | 0x94 => 333,
+            0x95 => 350,
+            0x85 | 0x97 | 0x99 => 1000,
+            // Euro, en dash and most accented letters
+            _ => 556,
+        };
+        width as u32
+    }
+}
+
+/// Characters of WinAnsiEncoding outside Latin-1 that text is encoded with
+const WIN_ANSI_EXTRAS: [(char, u8); 10] = [
+    ('€', 0x80),
+    ('…', 0x85),
+    ('‘', 0x91),
+    ('’', 0x92),
+    ('“', 0x93),
+    ('”', 0x94),
+    ('•', 0x95),
+    ('–', 0x96),
+    ('—', 0x97),
+    ('™', 0x99),
+];
+
+/// Encode text in WinAnsiEncoding, replacing characters it doesn't have
+/// with `?`
+pub fn encode_text(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            ' '..='~' | '\u{a0}'..='\u{ff}' => c as u8,
+            c => WIN_ANSI_EXTRAS
+                .iter()
+                .find(|(extra, _)| *extra == c)
+                .map_or(b'?', |(_, byte)| *byte),
+        })
+        .collect()
+}
+
+/// Decode text in WinAnsiEncoding, reading other bytes as Latin-1
+pub fn decode_text(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&byte| {
+            WIN_ANSI_EXTRAS
+                .iter()
+                .find(|(_, extra)| *extra == byte)
+                .map_or(byte as char, |(c, _)| *c)
+        })
+        .collect()
+}
+
+/// Bytes of a string object as written (see `Object::String`): a literal
+/// with its escapes resolved, or a hex string
+pub fn string_bytes(raw: &[u8]) -> Vec<u8> {
+    if let Some(hex) = raw.strip_prefix(b"<") {
+        let digits: Vec<u8> = hex
+            .iter()
+            .filter_map(|b| (*b as char).to_digit(16))
+            .map(|digit| digit as u8)
+            .collect();
+        // An odd last digit is followed by an implied 0
+        return digits
+            .chunks(2)
+            .map(|pair| (pair[0] << 4) | pair.get(1).copied().unwrap_or(0))
+            .collect();
+    }
+    let inner = raw.strip_prefix(b"(").unwrap_or(raw);
+    let inner = inner.strip_suffix(b")").unwrap_or(inner);
+    let mut bytes = Vec::with_capacity(inner.len());
+    let mut i = 0;
+    while i < inner.len() {
+        let byte = inner[i];
+        i += 1;
+        if byte != b'\\' {
+            bytes.push(byte);
+            continue;
+        }
+        let Some(&escaped) = inner.get(i) else {
+            break;
+        };
+        i += 1;
+        match escaped {
+            b'n' => bytes.push(b'\n'),
+            b'r' => bytes.push(b'\r'),
+            b't' => bytes.push(b'\t'),
+            b'b' => bytes.push(0x08),
+            b'f' => bytes.push(0x0c),
+            b'0'..=b'7' => {
+                let mut value = (escaped - b'0') as u32;
+                for _ in 0..2 {
+                    match inner.get(i) {
+                        Some(digit @ b'0'..=b'7') => {
+                            value = value * 8 + (digit - b'0') as u32;
+                            i += 1;
+                        }
+                        _ => break,
+                    }
+                }
+                bytes.push(value as u8);
+            }
+            // A backslash at the end of a line continues the string
+            b'\r' => {
+                if inner.get(i) == Some(&b'\n') {
+                    i += 1;
+                }
+            }
+            b'\n' => {}
+            escaped => bytes.push(escaped),
+        }
+    }
+    bytes
+}
+
+/// An operator of a content stream and its operands
+#[derive(Clone, Debug, PartialEq)]
+pub struct Operation {
+    pub operator: String,
+    pub operands: Vec<Object>,
+}
+
+/// Read the operations of a content stream, or of a CMap, which has the
+/// same syntax. Inline images are skipped, and reading stops at anything
+/// that isn't content syntax.
+pub fn operations(content: &[u8]) -> Vec<Operation> {
+    let mut lexer = Lexer::new(content, 0);
+    let mut operations = Vec::new();
+    let mut operands = Vec::new();
+    loop {
+        lexer.skip_whitespace();
+        let Some(&byte) = content.get(lexer.pos) else {
+            break;
+        };
+        if !(byte.is_ascii_alphabetic() || byte == b'\'' || byte == b'"') {
+            match lexer.object(0) {
+                Ok(operand) => operands.push(operand),
+                Err(_) => break,
+            }
+            continue;
+        }
+        let operator = lexer.token();
+        match operator {
+            b"true" => operands.push(Object::Bool(true)),
+            b"false" => operands.push(Object::Bool(false)),
+            b"null" => operands.push(Object::Null),
+            b"BI" => {
+                // Image data runs from ID to the next EI on its own
+                let Some(data) = find(&content[lexer.pos..], b"ID") else {
+                    break;
+                };
+                let data = lexer.pos + data + b"ID".len();
+                let end = content[data..].windows(4).position(|window| {
+                    is_whitespace(window[0])
+                        && &window[1..3] == b"EI"
+                        && (is_whitespace(window[3]) || is_delimiter(window[3]))
+                });
+                let Some(end) = end else {
+                    break;
+                };
+                lexer.pos = data + end + 3;
+                operands.clear();
+            }
+            operator => operations.push(Operation {
+                operator: String::from_utf8_lossy(operator).into_owned(),
+                operands: std::mem::take(&mut operands),
+            }),
+        }
+    }
+    operations
+}
+
+/// Text as a hex string for showing with `Tj`
+pub fn text_string(text: &str) -> String {
+    let hex: String = encode_text(text)
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect();
+    format!("<{}>", hex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_objects() {
+        let mut lexer = Lexer::new(
+            b"<< /Type /Page /Kids [3 0 R 4 0 R] /Title (A (nested) title\\)) \
+              /Id <0aff> /Scale -.5 % comment\n /Flag true >>",
+            0,
+        );
+        let dict = lexer.object(0).unwrap().into_dict().unwrap();
+        assert_eq!(dict.get("Type").and_then(Object::as_name), Some("Page"));
+        assert_eq!(
+            dict.get("Kids").and_then(Object::as_array).unwrap()[1],
+            Object::Ref((4, 0))
+        );
+        assert_eq!(
+            dict.get("Title"),
+            Some(&Object::String(b"(A (nested) title\\))".to_vec()))
+        );
+        assert_eq!(dict.get("Scale").and_then(Object::as_number), Some(-0.5));
+        assert_eq!(dict.get("Flag"), Some(&Object::Bool(true)));
+        // Written back in the same form
+        assert_eq!(
+            Object::Dict(dict).to_bytes(),
+            b"<</Type /Page/Kids [3 0 R 4 0 R]/Title (A (nested) title\\))\
+              /Id <0aff>/Scale -0.5/Flag true>>"
+                .to_vec()
+        );
+        assert_eq!(format_number(612.0), "612");
+        assert_eq!(format_number(std::f64::consts::FRAC_1_SQRT_2), "0.7071");
+    }
+
+    #[test]
+    fn test_incremental_update() {
+        let original = crate::document::test_page_pdf("Update", &[]);
+        let mut document = Document::parse(&original).unwrap();
+        let mut pages = document.pages().unwrap();
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].bounds, [0.0, 0.0, 612.0, 792.0]);
+        assert_eq!(pages[0].size(), (612.0, 792.0));
+
+        let font = document.add(Font::Helvetica.dict());
+        pages[0].add_resource("Font", "Stamp", Object::Ref(font));
+        document.overlay(&mut pages[0], b"BT /Stamp 12 Tf (Stamped) Tj ET");
+        let updated = document.finish();
+
+        // The original is kept and the update appended after it
+        assert!(updated.starts_with(&original));
+        assert_eq!(crate::document::validate_pdf(&updated), Ok(1));
+        let document = Document::parse(&updated).unwrap();
+        let page = &document.pages().unwrap()[0];
+        let fonts = page
+            .resources
+            .get("Font")
+            .and_then(Object::as_dict)
+            .unwrap();
+        assert!(fonts.get("F1").is_some());
+        assert_eq!(fonts.get("Stamp"), Some(&Object::Ref(font)));
+        let contents = page
+            .dict
+            .get("Contents")
+            .and_then(Object::as_array)
+            .unwrap();
+        assert_eq!(contents.len(), 3);
+        assert_eq!(contents[1], Object::Ref((5, 0)));
+
+        // The new cross-reference section points at the objects
+        let text = String::from_utf8_lossy(&updated);
+        let xref = text.rfind("\nxref\n").unwrap() + 1;
+        let trailer = &text[xref..];
+        assert!(trailer.contains("/Prev"));
+        assert!(trailer.contains("/Root 1 0 R"));
+    }
+
+    #[test]
+    fn test_page_tree_revisits() {
+        let pdf = |kids: &str| {
+            format!(
+                "%PDF-1.4\n\
+                 1 0 obj << /Type /Catalog /Pages 2 0 R >> endobj\n\
+                 2 0 obj << /Type /Pages /Kids [{}] /Count 2 >> endobj\n\
+                 3 0 obj << /Type /Page /Parent 2 0 R >> endobj\n\
+                 trailer << /Root 1 0 R >>\nstartxref\n9\n%%EOF\n",
+                kids
+            )
+        };
+        let pages = |kids: &str| Document::parse(pdf(kids).as_bytes()).unwrap().pages();
+        assert_eq!(pages("3 0 R").unwrap().len(), 1);
+        // Shared kids would be walked once per path to them, and a loop forever
+        for kids in ["3 0 R 3 0 R", "3 0 R 2 0 R"] {
+            assert!(pages(kids).unwrap_err().contains("more than once"));
+        }
+    }
+
+    #[test]
+    fn test_rotated_pages() {
+        let page = Page {
+            id: (3, 0),
+            dict: Dict::new(),
+            resources: Dict::new(),
+            bounds: [0.0, 0.0, 612.0, 792.0],
+            rotate: 90,
+        };
+        assert_eq!(page.size(), (792.0, 612.0));
+        // The bottom left corner as displayed is the page's bottom right
+        let [_, b, c, _, e, f] = page.display_matrix();
+        assert_eq!((e, f), (612.0, 0.0));
+        // and the top right corner as displayed is its top left
+        assert_eq!((c * 612.0 + e, b * 792.0 + f), (0.0, 792.0));
+    }
+
+    #[test]
+    fn test_operations() {
+        let content = b"BT /F1 12 Tf 72 700 Td (Hello \\(1\\)\\101) Tj T* [<48> -250 (i)] TJ ET\n\
+            BI /W 1 /H 1 ID \x00EI\xff EI Q";
+        let operations = operations(content);
+        let operators: Vec<&str> = operations.iter().map(|o| o.operator.as_str()).collect();
+        assert_eq!(operators, ["BT", "Tf", "Td", "Tj", "T*", "TJ", "ET", "Q"]);
+        assert_eq!(
+            operations[1].operands,
+            [Object::name("F1"), Object::Number(12.0)]
+        );
+        let Object::String(raw) = &operations[3].operands[0] else {
+            panic!("Tj should show a string");
+        };
+        assert_eq!(string_bytes(raw), b"Hello (1)A");
+        assert_eq!(string_bytes(b"<48 6>"), b"H`");
+        assert_eq!(string_bytes(b"(a\\\nb)"), b"ab");
+    }
+
+    #[test]
+    fn test_text() {
+        assert_eq!(text_string("Café — 1"), "<436166E920972031>");
+        assert_eq!(decode_text(&encode_text("Café — 1")), "Café — 1");
+        assert_eq!(Font::Helvetica.width("Hi", 10.0), 9.44);
+        assert!(Font::HelveticaBold.width("DRAFT", 10.0) > Font::Helvetica.width("DRAFT", 10.0));
+    }
+}
@@ -0,0 +1,305 @@
+//! PJL (Printer Job Language) for HP-style printers
+//!
+//! Many laser printers and print servers take job settings from a PJL
+//! header in front of the document rather than from the spooler: the job
+//! name shown on the control panel and in job accounting, duplexing and the
+//! input tray. `wrap` adds such a header and the matching end-of-job
+//! trailer, each preceded by the Universal Exit Language sequence that
+//! resets the printer's interpreter.
+//!
+//! The same raw channel (TCP 9100) answers `@PJL INFO` queries, which
+//! `query_status` uses to read the control panel status and page counter
+//! without SNMP or IPP.
+
+use crate::device::DeviceAddress;
+use crate::spooler::Duplex;
+use std::io::{Read, Write};
+use std::time::{Duration, Instant};
+
+/// Universal Exit Language: ends the current job and returns to PJL
+pub const UEL: &[u8] = b"\x1b%-12345X";
+
+/// Port of the raw printing channel
+pub const RAW_PORT: u16 = 9100;
+
+/// Ends each `@PJL INFO` response
+const FORM_FEED: u8 = 0x0c;
+
+/// Settings written to a PJL job header
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PjlJobOptions {
+    /// Job name, shown on the control panel and in job accounting
+    pub name: Option<String>,
+    pub duplex: Option<Duplex>,
+    /// Input tray, e.g. `TRAY2` or `MANUALFEED`
+    pub tray: Option<String>,
+    /// Printer language of the document, e.g. `PDF`, `POSTSCRIPT` or
+    /// `PCL`; detected from the document if not set
+    pub language: Option<String>,
+}
+
+/// PJL keyword values are plain words
+fn keyword(value: &str, setting: &str) -> Result<String, String> {
+    let value = value.trim().to_uppercase();
+    if value.is_empty() || !value.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(format!("Invalid PJL {} '{}'", setting, value));
+    }
+    Ok(value)
+}
+
+/// Job names are quoted strings without quotes or control characters
+fn quoted_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .filter(|c| *c != '"' && !c.is_control())
+        .take(80)
+        .collect();
+    format!("\"{}\"", name)
+}
+
+/// Printer language a document is written in, from its leading bytes
+fn detect_language(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(b"\x1bE") {
+        return Some("PCL");
+    }
+    match crate::document::sniff_media_type(data)? {
+        "application/pdf" => Some("PDF"),
+        "application/postscript" => Some("POSTSCRIPT"),
+        _ => None,
+    }
+}
+
+/// Whether a document already starts with a PJL header
+pub fn is_pjl(data: &[u8]) -> bool {
+    data.starts_with(UEL) || data.starts_with(b"@PJL")
+}
+
+/// Put a PJL job header with `options` in front of a document and an
+/// end-of-job trailer after it
+pub fn wrap(data: &[u8], options: &PjlJobOptions) -> Result<Vec<u8>, String> {
+    if is_pjl(data) {
+        return Err("Document already has a PJL header".to_string());
+    }
+    let name = quoted_name(options.name.as_deref().unwrap_or("printers-js"));
+    let mut header = format!("@PJL JOB NAME={}\r\n", name);
+    match options.duplex {
+        Some(Duplex::Simplex) => header.push_str("@PJL SET DUPLEX=OFF\r\n"),
+        Some(Duplex::LongEdge) => {
+            header.push_str("@PJL SET DUPLEX=ON\r\n@PJL SET BINDING=LONGEDGE\r\n")
+        }
+        Some(Duplex::ShortEdge) => {
+            header.push_str("@PJL SET DUPLEX=ON\r\n@PJL SET BINDING=SHORTEDGE\r\n")
+        }
+        None => {}
+    }
+    if let Some(tray) = &options.tray {
+        header.push_str(&format!(
+            "@PJL SET MEDIASOURCE={}\r\n",
+            keyword(tray, "tray")?
+        ));
+    }
+    let language = match &options.language {
+        Some(language) => Some(keyword(language, "language")?),
+        None => detect_language(data).map(str::to_string),
+    };
+    if let Some(language) = language {
+        header.push_str(&format!("@PJL ENTER LANGUAGE={}\r\n", language));
+    }
+
+    let mut wrapped = Vec::with_capacity(data.len() + header.len() + 64);
+    wrapped.extend_from_slice(UEL);
+    wrapped.extend_from_slice(header.as_bytes());
+    wrapped.extend_from_slice(data);
+    wrapped.extend_from_slice(UEL);
+    wrapped.extend_from_slice(format!("@PJL EOJ NAME={}\r\n", name).as_bytes());
+    wrapped.extend_from_slice(UEL);
+    Ok(wrapped)
+}
+
+/// Printer status read back over PJL
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PjlStatus {
+    /// Status code, e.g. 10001 (ready) or 41xyy (paper out)
+    pub code: Option<u32>,
+    /// Control panel message
+    pub display: Option<String>,
+    pub online: Option<bool>,
+    /// Lifetime page count
+    pub page_count: Option<u64>,
+}
+
+impl PjlStatus {
+    /// "info", "warning" or "error", from the status code's category: 10xxx
+    /// and 11xxx are informational, 30xxx to 35xxx are conditions the
+    /// printer continues through, and 40xxx to 44xxx need an operator
+    pub fn severity(&self) -> Option<&'static str> {
+        match self.code? / 1000 {
+            10 | 11 => Some("info"),
+            30..=35 => Some("warning"),
+            40..=44 => Some("error"),
+            _ => None,
+        }
+    }
+
+    /// Read the responses to `@PJL INFO STATUS` and `@PJL INFO PAGECOUNT`
+    pub fn parse(response: &str) -> PjlStatus {
+        let mut status = PjlStatus::default();
+        let mut section = "";
+        for line in response.lines().map(str::trim) {
+            if let Some(query) = line.strip_prefix("@PJL INFO ") {
+                section = if query.eq_ignore_ascii_case("PAGECOUNT") {
+                    "PAGECOUNT"
+                } else {
+                    "STATUS"
+                };
+                continue;
+            }
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim().to_uppercase(), value.trim()),
+                // Some printers answer PAGECOUNT with the bare number
+                None if section == "PAGECOUNT" => ("PAGECOUNT".to_string(), line),
+                None => continue,
+            };
+            match key.as_str() {
+                "CODE" => status.code = value.parse().ok(),
+                "DISPLAY" => status.display = Some(value.trim_matches('"').to_string()),
+                "ONLINE" => status.online = Some(value.eq_ignore_ascii_case("TRUE")),
+                "PAGECOUNT" => status.page_count = value.parse().ok().or(status.page_count),
+                _ => {}
+            }
+        }
+        status
+    }
+}
+
+/// Ask a printer for its status and page count over the raw channel.
+/// Waits up to `timeout` for the responses, keeping what arrived.
+pub fn query_status(host: &str, port: u16, timeout: Duration) -> Result<PjlStatus, String> {
+    let address = DeviceAddress {
+        scheme: "socket".to_string(),
+        host: host.to_string(),
+        port,
+        path: String::new(),
+    };
+    let mut stream = address.connect(timeout)?;
+    let mut query = UEL.to_vec();
+    query.extend_from_slice(b"@PJL\r\n@PJL INFO STATUS\r\n@PJL INFO PAGECOUNT\r\n");
+    query.extend_from_slice(UEL);
+    stream
+        .write_all(&query)
+        .map_err(|e| format!("Failed to send PJL query: {}", e))?;
+
+    let deadline = Instant::now() + timeout;
+    let mut response = Vec::new();
+    let mut chunk = [0u8; 1024];
+    while response.iter().filter(|b| **b == FORM_FEED).count() < 2 {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        stream
+            .set_read_timeout(Some(remaining))
+            .map_err(|e| format!("Failed to configure socket: {}", e))?;
+        match stream.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(read) => response.extend_from_slice(&chunk[..read]),
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) =>
+            {
+                break
+            }
+            Err(e) => return Err(format!("Failed to read PJL response: {}", e)),
+        }
+    }
+    if response.is_empty() {
+        return Err(format!("{} didn't answer the PJL status query", host));
+    }
+    Ok(PjlStatus::parse(&String::from_utf8_lossy(&response)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap() {
+        let options = PjlJobOptions {
+            name: Some("Invoice \"42\"".to_string()),
+            duplex: Some(Duplex::LongEdge),
+            tray: Some("tray2".to_string()),
+            language: None,
+        };
+        let wrapped = wrap(b"%PDF-1.7\n", &options).unwrap();
+        let text = String::from_utf8_lossy(&wrapped);
+        assert!(wrapped.starts_with(UEL));
+        assert!(wrapped.ends_with(UEL));
+        assert!(text.contains("@PJL JOB NAME=\"Invoice 42\"\r\n"));
+        assert!(text.contains("@PJL SET BINDING=LONGEDGE\r\n"));
+        assert!(text.contains("@PJL SET MEDIASOURCE=TRAY2\r\n"));
+        assert!(text.contains("@PJL ENTER LANGUAGE=PDF\r\n%PDF-1.7\n"));
+        assert!(text.contains("@PJL EOJ NAME=\"Invoice 42\"\r\n"));
+
+        // Unknown formats are left to the printer to detect
+        let wrapped = wrap(b"plain text", &PjlJobOptions::default()).unwrap();
+        assert!(!String::from_utf8_lossy(&wrapped).contains("ENTER LANGUAGE"));
+
+        assert!(wrap(&wrapped, &PjlJobOptions::default()).is_err());
+        let injected = PjlJobOptions {
+            tray: Some("TRAY1\r\n@PJL SET COPIES=99".to_string()),
+            ..PjlJobOptions::default()
+        };
+        assert!(wrap(b"%!PS", &injected).is_err());
+    }
+
+    #[test]
+    fn test_parse_status() {
+        let status = PjlStatus::parse(
+            "@PJL INFO STATUS\r\nCODE=41038\r\nDISPLAY=\"LOAD TRAY 2\"\r\nONLINE=FALSE\r\n\x0c\
+             @PJL INFO PAGECOUNT\r\n18234\r\n\x0c",
+        );
+        assert_eq!(status.code, Some(41038));
+        assert_eq!(status.display.as_deref(), Some("LOAD TRAY 2"));
+        assert_eq!(status.online, Some(false));
+        assert_eq!(status.page_count, Some(18234));
+        assert_eq!(status.severity(), Some("error"));
+
+        let status = PjlStatus::parse("@PJL INFO PAGECOUNT\r\nPAGECOUNT=77\r\n\x0c");
+        assert_eq!(status.page_count, Some(77));
+        assert_eq!(status.severity(), None);
+    }
+
+    #[test]
+    fn test_query_status() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let printer = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut query = Vec::new();
+            let mut chunk = [0u8; 256];
+            while !query.ends_with(b"PAGECOUNT\r\n\x1b%-12345X") {
+                let read = stream.read(&mut chunk).unwrap();
+                query.extend_from_slice(&chunk[..read]);
+            }
+            stream
+                .write_all(
+                    b"@PJL INFO STATUS\r\nCODE=10001\r\nDISPLAY=\"Ready\"\r\nONLINE=TRUE\r\n\x0c",
+                )
+                .unwrap();
+            stream
+                .write_all(b"@PJL INFO PAGECOUNT\r\n512\r\n\x0c")
+                .unwrap();
+            // Printers keep the channel open after answering
+            std::thread::sleep(Duration::from_millis(500));
+        });
+
+        let status = query_status("127.0.0.1", port, Duration::from_secs(5)).unwrap();
+        assert_eq!(status.code, Some(10001));
+        assert_eq!(status.online, Some(true));
+        assert_eq!(status.page_count, Some(512));
+        assert_eq!(status.severity(), Some("info"));
+        printer.join().unwrap();
+    }
+}
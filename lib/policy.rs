@@ -0,0 +1,335 @@
+//! Print policy rules checked when jobs are submitted
+//!
+//! Print servers often need to limit what may be printed: no more than a
+//! number of pages per job, only some document formats, color only for some
+//! users, or nothing containing certain words. Rules are set for the whole
+//! library and every file and byte job is checked against them before it is
+//! queued. A job that breaks a rule fails with `PolicyViolation`, naming the
+//! rule so the caller can explain why.
+//!
+//! Rules only judge what they can see: jobs whose page count or color isn't
+//! known pass the page and color conditions, and documents whose text can't
+//! be extracted pass the keyword condition.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::RwLock;
+
+lazy_static::lazy_static! {
+    static ref RULES: RwLock<Vec<PolicyRule>> = RwLock::new(Vec::new());
+}
+
+/// A policy rule. Jobs break it if they fail any of the conditions it sets.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyRule {
+    /// Name of the rule, reported with violations
+    pub id: String,
+    /// Most pages a job may print, copies included
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_pages: Option<u32>,
+    /// Media types documents may be submitted as, e.g. `application/pdf`,
+    /// or a whole type like `image/*`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_formats: Option<Vec<String>>,
+    /// Users allowed to print in color; other users' jobs must print in
+    /// monochrome
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color_users: Option<Vec<String>>,
+    /// Words documents may not contain, matched ignoring case
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub banned_keywords: Option<Vec<String>>,
+}
+
+impl PolicyRule {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.id.trim().is_empty() || self.id.contains('"') {
+            return Err(format!("Invalid policy rule id '{}'", self.id));
+        }
+        if self.max_pages.is_none()
+            && self.allowed_formats.is_none()
+            && self.color_users.is_none()
+            && self.banned_keywords.is_none()
+        {
+            return Err(format!("Policy rule '{}' has no conditions", self.id));
+        }
+        if let Some(formats) = &self.allowed_formats {
+            if let Some(format) = formats.iter().find(|format| !format.contains('/')) {
+                return Err(format!(
+                    "Policy rule '{}' allows an invalid format '{}'",
+                    self.id, format
+                ));
+            }
+        }
+        if let Some(keywords) = &self.banned_keywords {
+            if keywords.iter().any(|keyword| keyword.trim().is_empty()) {
+                return Err(format!("Policy rule '{}' bans an empty keyword", self.id));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Why a job broke a rule
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PolicyViolation {
+    /// Id of the rule
+    pub rule: String,
+    pub reason: String,
+}
+
+impl std::fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Rule \"{}\": {}", self.rule, self.reason)
+    }
+}
+
+/// What the rules are checked against. Parts that aren't given pass the
+/// conditions on them, so a job can be checked in steps as it is prepared.
+#[derive(Clone, Debug, Default)]
+pub struct PolicyJob<'a> {
+    /// User the job is printed for
+    pub user: Option<&'a str>,
+    /// Media type the document was submitted as
+    pub media_type: Option<&'a str>,
+    /// The document as submitted, for its text
+    pub data: Option<&'a [u8]>,
+    /// Pages printed, copies included
+    pub pages: Option<u32>,
+    /// Whether the job prints in color
+    pub color: Option<bool>,
+}
+
+/// Replace the policy rules. Rules are checked in order, and ids must be
+/// unique.
+pub fn set(rules: Vec<PolicyRule>) -> Result<(), String> {
+    let mut ids = HashSet::new();
+    for rule in &rules {
+        rule.validate()?;
+        if !ids.insert(rule.id.as_str()) {
+            return Err(format!("Duplicate policy rule id '{}'", rule.id));
+        }
+    }
+    tracing::info!(rules = rules.len(), "Policy rules set");
+    *RULES.write().unwrap() = rules;
+    Ok(())
+}
+
+pub fn rules() -> Vec<PolicyRule> {
+    RULES.read().unwrap().clone()
+}
+
+/// Whether any rule restricts color, so callers only work out a job's
+/// color when it matters
+pub fn restricts_color() -> bool {
+    RULES
+        .read()
+        .unwrap()
+        .iter()
+        .any(|rule| rule.color_users.is_some())
+}
+
+/// Check a job against the policy rules, returning the first one it breaks
+pub fn check(job: &PolicyJob<'_>) -> Result<(), PolicyViolation> {
+    evaluate(&RULES.read().unwrap(), job)
+}
+
+/// Check a job against rules, returning the first one it breaks. The
+/// document's text is only extracted if a rule bans keywords.
+pub fn evaluate(rules: &[PolicyRule], job: &PolicyJob<'_>) -> Result<(), PolicyViolation> {
+    let mut text: Option<Option<String>> = None;
+    for rule in rules {
+        let violation = |reason: String| PolicyViolation {
+            rule: rule.id.clone(),
+            reason,
+        };
+        if let (Some(max_pages), Some(pages)) = (rule.max_pages, job.pages) {
+            if pages > max_pages {
+                return Err(violation(format!(
+                    "Job prints {} pages, more than the {} allowed",
+                    pages, max_pages
+                )));
+            }
+        }
+        if let (Some(formats), Some(media_type)) = (&rule.allowed_formats, job.media_type) {
+            if !formats
+                .iter()
+                .any(|format| format_matches(format, media_type))
+            {
+                return Err(violation(format!(
+                    "Documents of type {} are not allowed",
+                    media_type
+                )));
+            }
+        }
+        if let (Some(users), Some(true)) = (&rule.color_users, job.color) {
+            let allowed = job
+                .user
+                .is_some_and(|user| users.iter().any(|allowed| allowed == user));
+            if !allowed {
+                return Err(violation(format!(
+                    "{} may not print in color",
+                    job.user.unwrap_or("This user")
+                )));
+            }
+        }
+        if let (Some(keywords), Some(data)) = (&rule.banned_keywords, job.data) {
+            let text = text.get_or_insert_with(|| {
+                crate::document::extract_text(data)
+                    .ok()
+                    .map(|text| text.to_lowercase())
+            });
+            if let Some(text) = text {
+                let banned = keywords
+                    .iter()
+                    .find(|keyword| text.contains(&keyword.trim().to_lowercase()));
+                if let Some(keyword) = banned {
+                    return Err(violation(format!(
+                        "Document contains the banned keyword '{}'",
+                        keyword.trim()
+                    )));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether a media type is one an allowed format names, ignoring
+/// parameters like `charset` and case
+fn format_matches(format: &str, media_type: &str) -> bool {
+    let media_type = media_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    let format = format.trim().to_ascii_lowercase();
+    match format.strip_suffix("/*") {
+        Some(kind) => media_type
+            .split_once('/')
+            .is_some_and(|(media_kind, _)| media_kind == kind),
+        None => format == media_type,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluate_rules() {
+        let rules = vec![
+            PolicyRule {
+                id: "short-jobs".to_string(),
+                max_pages: Some(10),
+                ..PolicyRule::default()
+            },
+            PolicyRule {
+                id: "formats".to_string(),
+                allowed_formats: Some(vec!["application/pdf".to_string(), "image/*".to_string()]),
+                ..PolicyRule::default()
+            },
+            PolicyRule {
+                id: "color".to_string(),
+                color_users: Some(vec!["alice".to_string()]),
+                ..PolicyRule::default()
+            },
+            PolicyRule {
+                id: "keywords".to_string(),
+                banned_keywords: Some(vec!["Secret".to_string()]),
+                ..PolicyRule::default()
+            },
+        ];
+        let job = PolicyJob {
+            user: Some("bob"),
+            media_type: Some("image/png"),
+            pages: Some(10),
+            color: Some(false),
+            data: Some(b"Quarterly report"),
+        };
+        assert_eq!(evaluate(&rules, &job), Ok(()));
+        assert_eq!(evaluate(&rules, &PolicyJob::default()), Ok(()));
+
+        let rule = |job: PolicyJob| evaluate(&rules, &job).unwrap_err().rule;
+        assert_eq!(
+            rule(PolicyJob {
+                pages: Some(11),
+                ..job.clone()
+            }),
+            "short-jobs"
+        );
+        assert_eq!(
+            rule(PolicyJob {
+                media_type: Some("Text/Plain; charset=utf-8"),
+                ..job.clone()
+            }),
+            "formats"
+        );
+        assert_eq!(
+            rule(PolicyJob {
+                color: Some(true),
+                ..job.clone()
+            }),
+            "color"
+        );
+        let alice = PolicyJob {
+            user: Some("alice"),
+            color: Some(true),
+            ..job.clone()
+        };
+        assert_eq!(evaluate(&rules, &alice), Ok(()));
+        let violation = evaluate(
+            &rules,
+            &PolicyJob {
+                data: Some(b"TOP SECRET plans"),
+                ..job.clone()
+            },
+        )
+        .unwrap_err();
+        assert_eq!(violation.rule, "keywords");
+        assert_eq!(
+            violation.to_string(),
+            "Rule \"keywords\": Document contains the banned keyword 'Secret'"
+        );
+        // Documents without extractable text pass keyword rules
+        let binary = PolicyJob {
+            data: Some(&[0x00, 0x01, 0x02]),
+            ..job
+        };
+        assert_eq!(evaluate(&rules, &binary), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_rules() {
+        let rule = PolicyRule {
+            id: "pages".to_string(),
+            max_pages: Some(5),
+            ..PolicyRule::default()
+        };
+        assert!(rule.validate().is_ok());
+        assert!(PolicyRule {
+            id: "empty".to_string(),
+            ..PolicyRule::default()
+        }
+        .validate()
+        .is_err());
+        assert!(PolicyRule {
+            allowed_formats: Some(vec!["pdf".to_string()]),
+            ..rule.clone()
+        }
+        .validate()
+        .is_err());
+        assert!(PolicyRule {
+            id: " ".to_string(),
+            ..rule.clone()
+        }
+        .validate()
+        .is_err());
+
+        let rule: PolicyRule =
+            serde_json::from_str(r#"{"id":"color","colorUsers":["alice"]}"#).unwrap();
+        assert_eq!(rule.color_users, Some(vec!["alice".to_string()]));
+        assert!(rule.validate().is_ok());
+    }
+}
@@ -0,0 +1,290 @@
+//! Printer pools
+//!
+//! A pool routes each job to one printer of a group: the first available
+//! printer (failover), the next available printer in turn (round robin) or
+//! the available printer with the shortest queue (least busy). Printers that
+//! are offline, report a blocking condition such as `media-empty`, or whose
+//! queue has reached the pool's depth limit are unavailable. Whenever the
+//! printer the strategy would have used is skipped, a failover event is
+//! queued on the pool for bindings to drain and pass on to their listeners.
+
+use crate::core::{JobId, PrintError, PrinterCore, PrinterJobOptions};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+pub type PoolId = u64;
+
+/// Failover events kept per pool until drained; older ones are dropped
+const MAX_QUEUED_EVENTS: usize = 256;
+
+static NEXT_POOL_ID: AtomicU64 = AtomicU64::new(1);
+
+lazy_static::lazy_static! {
+    static ref POOLS: Mutex<HashMap<PoolId, Arc<PrinterPool>>> = Mutex::new(HashMap::new());
+}
+
+/// How a pool picks the printer for a job
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PoolStrategy {
+    /// First available printer, in the order given
+    Failover,
+    /// Next available printer after the one used last
+    RoundRobin,
+    /// Available printer with the fewest queued jobs, checking every printer
+    /// for each job
+    LeastBusy,
+}
+
+impl PoolStrategy {
+    pub fn parse(strategy: &str) -> Option<PoolStrategy> {
+        match strategy {
+            "failover" => Some(PoolStrategy::Failover),
+            "round-robin" => Some(PoolStrategy::RoundRobin),
+            "least-busy" => Some(PoolStrategy::LeastBusy),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PoolStrategy::Failover => "failover",
+            PoolStrategy::RoundRobin => "round-robin",
+            PoolStrategy::LeastBusy => "least-busy",
+        }
+    }
+}
+
+/// A printer the strategy would have used was skipped
+#[derive(Clone, Debug, PartialEq)]
+pub struct FailoverEvent {
+    /// Printer that was skipped
+    pub from: String,
+    /// Printer the job went to instead, if any was available
+    pub to: Option<String>,
+    pub reason: String,
+    pub timestamp: SystemTime,
+}
+
+/// A group of printers jobs are routed across
+pub struct PrinterPool {
+    pub id: PoolId,
+    pub printers: Vec<String>,
+    pub strategy: PoolStrategy,
+    /// Printers with this many queued jobs are skipped (None = no limit)
+    pub max_queue_depth: Option<usize>,
+    /// Round robin position, also used to spread ties between least busy
+    /// printers
+    next: AtomicUsize,
+    events: Mutex<VecDeque<FailoverEvent>>,
+}
+
+impl PrinterPool {
+    /// Printers in the order the strategy tries them for the next job
+    fn candidates(&self) -> Vec<&str> {
+        let start = match self.strategy {
+            PoolStrategy::Failover => 0,
+            PoolStrategy::RoundRobin | PoolStrategy::LeastBusy => {
+                self.next.fetch_add(1, Ordering::Relaxed)
+            }
+        };
+        let len = self.printers.len();
+        (0..len)
+            .map(|offset| self.printers[(start + offset) % len].as_str())
+            .collect()
+    }
+
+    /// Queue depth of a printer that can take jobs right now, or why it
+    /// can't
+    fn check_printer(&self, printer_name: &str) -> Result<usize, String> {
+        match PrinterCore::is_printer_online(printer_name) {
+            Ok(true) => {}
+            Ok(false) => return Err("offline".to_string()),
+            Err(_) => return Err("not found".to_string()),
+        }
+        let load = PrinterCore::get_printer_load(printer_name).ok_or("not found")?;
+        if let Some(reason) = load.blocking_reasons.first() {
+            return Err(reason.as_str().to_string());
+        }
+        let depth = load.queue_depth();
+        if self.max_queue_depth.is_some_and(|max| depth >= max) {
+            return Err(format!("queue full ({} queued)", depth));
+        }
+        Ok(depth)
+    }
+
+    /// Choose the printer for the next job
+    pub fn select(&self) -> Result<String, PrintError> {
+        let mut skipped = Vec::new();
+        let mut best: Option<(&str, usize)> = None;
+        for candidate in self.candidates() {
+            match self.check_printer(candidate) {
+                Err(reason) => skipped.push((candidate.to_string(), reason)),
+                Ok(depth) => {
+                    if best.is_none_or(|(_, best_depth)| depth < best_depth) {
+                        best = Some((candidate, depth));
+                    }
+                    if self.strategy != PoolStrategy::LeastBusy {
+                        break;
+                    }
+                }
+            }
+        }
+        let selected = best.map(|(name, _)| name.to_string());
+
+        for (from, reason) in skipped {
+            tracing::warn!(
+                pool = self.id,
+                from = %from,
+                to = selected.as_deref().unwrap_or("none"),
+                "Pool failover: printer {}",
+                reason
+            );
+            self.push_event(FailoverEvent {
+                from,
+                to: selected.clone(),
+                reason,
+                timestamp: SystemTime::now(),
+            });
+        }
+        selected.ok_or(PrintError::NoPrinterAvailable)
+    }
+
+    fn push_event(&self, event: FailoverEvent) {
+        let mut events = self.events.lock().unwrap();
+        if events.len() >= MAX_QUEUED_EVENTS {
+            events.pop_front();
+        }
+        events.push_back(event);
+    }
+
+    /// Take all queued failover events, oldest first
+    pub fn drain_events(&self) -> Vec<FailoverEvent> {
+        self.events.lock().unwrap().drain(..).collect()
+    }
+
+    /// Print a file on the printer the strategy selects
+    pub fn print_file(
+        &self,
+        file_path: &str,
+        job_options: Option<PrinterJobOptions>,
+    ) -> Result<JobId, PrintError> {
+        PrinterCore::print_file(&self.select()?, file_path, job_options)
+    }
+
+    /// Print raw bytes on the printer the strategy selects
+    pub fn print_bytes(
+        &self,
+        data: &[u8],
+        job_options: Option<PrinterJobOptions>,
+    ) -> Result<JobId, PrintError> {
+        PrinterCore::print_bytes(&self.select()?, data, job_options)
+    }
+}
+
+/// Create a pool and register it
+pub fn create(
+    printers: Vec<String>,
+    strategy: PoolStrategy,
+    max_queue_depth: Option<usize>,
+) -> Result<Arc<PrinterPool>, PrintError> {
+    if printers.is_empty() || max_queue_depth == Some(0) {
+        return Err(PrintError::InvalidParams);
+    }
+    let pool = Arc::new(PrinterPool {
+        id: NEXT_POOL_ID.fetch_add(1, Ordering::Relaxed),
+        printers,
+        strategy,
+        max_queue_depth,
+        next: AtomicUsize::new(0),
+        events: Mutex::new(VecDeque::new()),
+    });
+    POOLS.lock().unwrap().insert(pool.id, Arc::clone(&pool));
+    tracing::debug!(
+        pool = pool.id,
+        strategy = pool.strategy.as_str(),
+        "Created printer pool"
+    );
+    Ok(pool)
+}
+
+pub fn get(pool_id: PoolId) -> Option<Arc<PrinterPool>> {
+    POOLS.lock().unwrap().get(&pool_id).cloned()
+}
+
+/// Unregister a pool, returning whether it existed
+pub fn remove(pool_id: PoolId) -> bool {
+    POOLS.lock().unwrap().remove(&pool_id).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{LibraryConfig, ShutdownMode};
+    use serial_test::serial;
+    use std::env;
+    use std::time::Duration;
+
+    #[test]
+    #[serial]
+    fn test_printer_pool_routing() {
+        env::set_var("PRINTERS_JS_SIMULATE", "true");
+        assert_eq!(
+            PoolStrategy::parse("round-robin"),
+            Some(PoolStrategy::RoundRobin)
+        );
+        assert!(create(Vec::new(), PoolStrategy::Failover, None).is_err());
+
+        let names = ["Missing Printer", "Simulated Printer"].map(String::from);
+        let failover = create(names.to_vec(), PoolStrategy::Failover, None).unwrap();
+        assert_eq!(failover.select().unwrap(), "Simulated Printer");
+        let events = failover.drain_events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].from, "Missing Printer");
+        assert_eq!(events[0].to.as_deref(), Some("Simulated Printer"));
+        assert!(failover.drain_events().is_empty());
+
+        // Round robin only fails over when its turn lands on the missing printer
+        let round_robin = create(names.to_vec(), PoolStrategy::RoundRobin, None).unwrap();
+        for _ in 0..4 {
+            assert_eq!(round_robin.select().unwrap(), "Simulated Printer");
+        }
+        assert_eq!(round_robin.drain_events().len(), 2);
+
+        let unavailable = create(
+            vec!["Missing Printer".to_string()],
+            PoolStrategy::Failover,
+            None,
+        )
+        .unwrap();
+        assert_eq!(unavailable.select(), Err(PrintError::NoPrinterAvailable));
+        assert_eq!(unavailable.drain_events()[0].to, None);
+
+        // A printer whose queue reached the limit is skipped like an offline one
+        let limited = create(names.to_vec(), PoolStrategy::LeastBusy, Some(1)).unwrap();
+        assert_eq!(limited.select().unwrap(), "Simulated Printer");
+        limited.drain_events();
+        PrinterCore::configure(LibraryConfig {
+            simulation_delay: Duration::from_secs(30),
+            ..LibraryConfig::default()
+        })
+        .unwrap();
+        PrinterCore::print_bytes("Simulated Printer", b"queued", None).unwrap();
+        assert_eq!(limited.select(), Err(PrintError::NoPrinterAvailable));
+        let events = limited.drain_events();
+        assert_eq!(events.len(), 2);
+        assert!(events
+            .iter()
+            .any(|event| event.reason == "queue full (1 queued)"));
+        PrinterCore::configure(LibraryConfig::default()).unwrap();
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
+
+        assert!(get(failover.id).is_some());
+        for pool in [failover, round_robin, unavailable, limited] {
+            assert!(remove(pool.id));
+            assert!(get(pool.id).is_none());
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! Printer power management
+//!
+//! Network printers that have gone to sleep can take longer to answer than
+//! a job's connect timeout. They are woken with a Wake-on-LAN magic packet
+//! sent to the MAC address configured for the printer, and their power
+//! state is read from the IPP `power-state-monitor` attribute where the
+//! printer reports it (PWG 5100.13).
+
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+use std::sync::RwLock;
+
+/// Port Wake-on-LAN packets are sent to by default ("discard")
+pub const DEFAULT_WAKE_PORT: u16 = 9;
+
+lazy_static::lazy_static! {
+    /// MAC addresses by printer name
+    static ref MAC_ADDRESSES: RwLock<HashMap<String, [u8; 6]>> = RwLock::new(HashMap::new());
+}
+
+/// Parse a MAC address written as `aa:bb:cc:dd:ee:ff`, `aa-bb-cc-dd-ee-ff`
+/// or `aabbccddeeff`
+pub fn parse_mac(mac: &str) -> Result<[u8; 6], String> {
+    let hex: String = mac
+        .trim()
+        .chars()
+        .filter(|c| *c != ':' && *c != '-')
+        .collect();
+    if hex.len() != 12 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Invalid MAC address '{}'", mac));
+    }
+    let mut bytes = [0u8; 6];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)
+            .map_err(|_| format!("Invalid MAC address '{}'", mac))?;
+    }
+    Ok(bytes)
+}
+
+/// Format a MAC address as `aa:bb:cc:dd:ee:ff`
+pub fn format_mac(mac: &[u8; 6]) -> String {
+    mac.iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Wake-on-LAN magic packet: six 0xFF bytes, then the MAC sixteen times
+pub fn magic_packet(mac: &[u8; 6]) -> [u8; 102] {
+    let mut packet = [0xFFu8; 102];
+    for chunk in packet[6..].chunks_mut(6) {
+        chunk.copy_from_slice(mac);
+    }
+    packet
+}
+
+/// Broadcast a magic packet for `mac`. `broadcast` defaults to the limited
+/// broadcast address; pass the subnet's directed broadcast address for
+/// printers on another subnet whose router forwards it.
+pub fn send_magic_packet(
+    mac: &[u8; 6],
+    broadcast: Option<Ipv4Addr>,
+    port: Option<u16>,
+) -> Result<(), String> {
+    let target = SocketAddr::from((
+        broadcast.unwrap_or(Ipv4Addr::BROADCAST),
+        port.unwrap_or(DEFAULT_WAKE_PORT),
+    ));
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .and_then(|socket| socket.set_broadcast(true).map(|_| socket))
+        .map_err(|e| format!("Failed to open Wake-on-LAN socket: {}", e))?;
+    socket
+        .send_to(&magic_packet(mac), target)
+        .map_err(|e| format!("Failed to send Wake-on-LAN packet to {}: {}", target, e))?;
+    tracing::debug!(mac = %format_mac(mac), %target, "Sent Wake-on-LAN packet");
+    Ok(())
+}
+
+/// Set the MAC address used to wake a printer. None removes it.
+pub fn set_mac_address(printer_name: &str, mac: Option<&str>) -> Result<(), String> {
+    let printer_name = crate::aliases::resolve(printer_name);
+    let mut addresses = MAC_ADDRESSES.write().unwrap();
+    match mac {
+        Some(mac) => {
+            addresses.insert(printer_name, parse_mac(mac)?);
+        }
+        None => {
+            addresses.remove(&printer_name);
+        }
+    }
+    Ok(())
+}
+
+/// MAC address configured for a printer
+pub fn mac_address(printer_name: &str) -> Option<[u8; 6]> {
+    MAC_ADDRESSES.read().unwrap().get(printer_name).copied()
+}
+
+/// Whether a PWG power state (`on`, `standby`, `suspend-vendor1`, ...)
+/// means the printer is asleep. None for transitional states like `reset-*`.
+pub fn is_asleep(power_state: &str) -> Option<bool> {
+    let base = power_state.split("-vendor").next().unwrap_or(power_state);
+    match base {
+        "on" => Some(false),
+        "standby" | "suspend" | "hibernate" | "off-soft" | "off-hard" => Some(true),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_magic_packet() {
+        let mac = parse_mac("00:1B-63:84:45:E6").unwrap();
+        assert_eq!(mac, [0x00, 0x1B, 0x63, 0x84, 0x45, 0xE6]);
+        assert_eq!(parse_mac("001b638445e6").unwrap(), mac);
+        assert_eq!(format_mac(&mac), "00:1b:63:84:45:e6");
+        assert!(parse_mac("00:1b:63:84:45").is_err());
+        assert!(parse_mac("00:1b:63:84:45:zz").is_err());
+
+        let packet = magic_packet(&mac);
+        assert_eq!(&packet[..6], &[0xFF; 6]);
+        assert!(packet[6..].chunks(6).all(|chunk| chunk == mac));
+    }
+
+    #[test]
+    fn test_power_states() {
+        assert_eq!(is_asleep("on"), Some(false));
+        assert_eq!(is_asleep("on-vendor2"), Some(false));
+        assert_eq!(is_asleep("standby"), Some(true));
+        assert_eq!(is_asleep("suspend-vendor1"), Some(true));
+        assert_eq!(is_asleep("off-soft"), Some(true));
+        assert_eq!(is_asleep("reset-soft"), None);
+    }
+}
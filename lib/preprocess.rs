@@ -0,0 +1,1340 @@
+//! Document pre-processing
+//!
+//! Job options that change what is printed on the page are applied to PDF
+//! documents before submission rather than left to the driver, so they come
+//! out the same on every printer: margins and scaling, watermarks like
+//! CONFIDENTIAL or DRAFT stamped over each page, header and footer lines
+//! carrying the job's metadata, like an order number, onto every page, and
+//! layouts putting several pages on each sheet. The changes are appended to
+//! the document as an incremental update (see `pdf`), so the original
+//! content is untouched.
+//!
+//! PNG and JPEG images with these options are first placed on a page of
+//! the job's media, turning them into one-page PDFs.
+//!
+//! Gray and monochrome jobs for printers that don't report supporting the
+//! color mode are also converted to gray here, in case the driver ignores
+//! the request and prints in color.
+//!
+//! Copies of a duplex job with an odd number of sides share a sheet: the
+//! first page of each copy after the first prints on the back of the last
+//! page of the one before. Jobs asking for it get a blank page added so
+//! every copy starts on a new sheet.
+
+use crate::core::PrinterJobOptions;
+use crate::pdf::{Dict, Document, Font, Object, ObjectId, Page};
+use crate::templates::{Template, TemplateFormat};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Opacity of watermarks that don't set one
+pub const DEFAULT_OPACITY: f64 = 0.3;
+
+/// Largest watermark text, in points
+const MAX_TEXT_SIZE: f64 = 144.0;
+
+/// Watermark text at the top or bottom of the page, in points
+const BAND_TEXT_SIZE: f64 = 36.0;
+
+/// Distance of top and bottom watermarks from the page edge, in points
+const EDGE_MARGIN: f64 = 36.0;
+
+/// Names of the resources watermarks add to pages
+const WATERMARK_FONT: &str = "PjsWatermarkFont";
+const WATERMARK_STATE: &str = "PjsWatermarkState";
+const WATERMARK_IMAGE: &str = "PjsWatermarkImage";
+const WATERMARK_IMAGE_STATE: &str = "PjsWatermarkImageState";
+const HEADER_FONT: &str = "PjsHeaderFont";
+const GRAY_STATE: &str = "PjsGrayState";
+
+/// Size of header and footer text, in points
+const HEADER_TEXT_SIZE: f64 = 9.0;
+
+/// Distance of header and footer text from the page edge, in points,
+/// inside the unprintable border of most printers
+const HEADER_MARGIN: f64 = 18.0;
+
+/// Space around each page of 2-up and 4-up sheets, in points, where the
+/// crop marks go
+const CROP_MARGIN: f64 = 18.0;
+
+/// Gap between a page's corner and its crop marks, and their length
+const CROP_MARK_OFFSET: f64 = 3.0;
+const CROP_MARK_LENGTH: f64 = 12.0;
+
+/// Largest scale, in percent
+const MAX_SCALE: f64 = 1000.0;
+
+/// Page images are placed on when the job's media isn't a PWG media name
+const DEFAULT_MEDIA: &str = "na_letter_8.5x11in";
+
+/// Variables headers and footers can use besides the job's metadata, which
+/// they take precedence over
+pub const PAGE_VARIABLES: [&str; 5] = ["page", "pages", "jobName", "user", "date"];
+
+/// Where a watermark goes on the page
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WatermarkPosition {
+    /// Across the page from the bottom left to the top right corner
+    #[default]
+    Diagonal,
+    Center,
+    Top,
+    Bottom,
+}
+
+/// Text or an image stamped over every page
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Watermark {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// Path of a PNG or JPEG image, e.g. a logo or an approval stamp. PNG
+    /// images need the compression feature.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    /// From 0 (invisible) to 1 (opaque)
+    #[serde(default = "default_opacity")]
+    pub opacity: f64,
+    #[serde(default)]
+    pub position: WatermarkPosition,
+}
+
+fn default_opacity() -> f64 {
+    DEFAULT_OPACITY
+}
+
+impl Watermark {
+    pub fn validate(&self) -> Result<(), String> {
+        let has_text = self
+            .text
+            .as_deref()
+            .is_some_and(|text| !text.trim().is_empty());
+        if !has_text && self.image.is_none() {
+            return Err("A watermark needs text or an image".to_string());
+        }
+        if !(self.opacity > 0.0 && self.opacity <= 1.0) {
+            return Err(format!(
+                "Watermark opacity must be greater than 0 and at most 1, not {}",
+                self.opacity
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Space to leave blank at each edge of the page, in points
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Margins {
+    #[serde(default)]
+    pub top: f64,
+    #[serde(default)]
+    pub right: f64,
+    #[serde(default)]
+    pub bottom: f64,
+    #[serde(default)]
+    pub left: f64,
+}
+
+impl Margins {
+    pub fn validate(&self) -> Result<(), String> {
+        for (edge, margin) in [
+            ("top", self.top),
+            ("right", self.right),
+            ("bottom", self.bottom),
+            ("left", self.left),
+        ] {
+            if !(margin.is_finite() && margin >= 0.0) {
+                return Err(format!("Invalid {} margin {}", edge, margin));
+            }
+        }
+        Ok(())
+    }
+
+    /// Area of a page of `width` by `height` inside the margins, as
+    /// `[x0, y0, x1, y1]`
+    fn area(&self, width: f64, height: f64) -> Result<[f64; 4], String> {
+        let area = [
+            self.left,
+            self.bottom,
+            width - self.right,
+            height - self.top,
+        ];
+        if area[2] <= area[0] || area[3] <= area[1] {
+            return Err(format!(
+                "Margins leave no room on a {} by {} point page",
+                crate::pdf::format_number(width),
+                crate::pdf::format_number(height)
+            ));
+        }
+        Ok(area)
+    }
+}
+
+/// How pages are laid out on the sheets printed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layout {
+    /// Two pages side by side on each side of the sheet, ordered so the
+    /// sheets fold into a booklet. Printed on both sides of the sheet.
+    Booklet,
+    /// Two pages side by side on each sheet, with crop marks
+    TwoUp,
+    /// Four pages in a grid on each sheet, with crop marks
+    FourUp,
+}
+
+impl Layout {
+    pub fn parse(layout: &str) -> Option<Layout> {
+        match layout {
+            "booklet" => Some(Layout::Booklet),
+            "2-up" => Some(Layout::TwoUp),
+            "4-up" => Some(Layout::FourUp),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Layout::Booklet => "booklet",
+            Layout::TwoUp => "2-up",
+            Layout::FourUp => "4-up",
+        }
+    }
+}
+
+/// Colors a job is printed in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorMode {
+    Color,
+    /// Shades of gray
+    Grayscale,
+    /// Black and white only, for the sharpest text. Printers without a
+    /// bi-level mode print these jobs in grayscale.
+    Monochrome,
+}
+
+impl ColorMode {
+    pub fn parse(mode: &str) -> Option<ColorMode> {
+        match mode {
+            "color" => Some(ColorMode::Color),
+            "grayscale" => Some(ColorMode::Grayscale),
+            "monochrome" => Some(ColorMode::Monochrome),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ColorMode::Color => "color",
+            ColorMode::Grayscale => "grayscale",
+            ColorMode::Monochrome => "monochrome",
+        }
+    }
+
+    /// IPP `print-color-mode` keywords selecting the mode, best first
+    pub fn keywords(&self) -> &'static [&'static str] {
+        match self {
+            ColorMode::Color => &["color"],
+            ColorMode::Grayscale => &["monochrome", "process-monochrome"],
+            ColorMode::Monochrome => &["bi-level", "process-bi-level", "monochrome"],
+        }
+    }
+
+    /// Keyword asked for when the printer doesn't report its color modes,
+    /// which drivers understand most widely
+    pub fn default_keyword(&self) -> &'static str {
+        match self {
+            ColorMode::Color => "color",
+            ColorMode::Grayscale | ColorMode::Monochrome => "monochrome",
+        }
+    }
+}
+
+/// Parse a header or footer, checking that the job has every variable it
+/// uses
+fn page_template(
+    name: &str,
+    text: &str,
+    metadata: &HashMap<String, String>,
+) -> Result<Template, String> {
+    let template = Template::parse(name, text, TemplateFormat::Text)?;
+    let unknown: Vec<&str> = template
+        .variables
+        .iter()
+        .map(String::as_str)
+        .filter(|variable| !PAGE_VARIABLES.contains(variable) && !metadata.contains_key(*variable))
+        .collect();
+    if !unknown.is_empty() {
+        return Err(format!(
+            "Unknown variables in the {}: {}",
+            name,
+            unknown.join(", ")
+        ));
+    }
+    Ok(template)
+}
+
+/// Check a job's pre-processing options before it is submitted
+pub fn validate(options: &PrinterJobOptions) -> Result<(), String> {
+    if let Some(scale) = options.scale {
+        if !(scale > 0.0 && scale <= MAX_SCALE) {
+            return Err(format!(
+                "Scale must be greater than 0 and at most {}%, not {}",
+                MAX_SCALE, scale
+            ));
+        }
+        if options.fit_to_page {
+            return Err("Scale and fit to page can't be combined".to_string());
+        }
+    }
+    if let Some(margins) = &options.margins {
+        margins.validate()?;
+    }
+    if let Some(watermark) = &options.watermark {
+        watermark.validate()?;
+    }
+    if let Some(header) = &options.header {
+        page_template("header", header, &options.metadata)?;
+    }
+    if let Some(footer) = &options.footer {
+        page_template("footer", footer, &options.metadata)?;
+    }
+    Ok(())
+}
+
+/// Whether a job's options change its document
+pub fn needed(options: &PrinterJobOptions) -> bool {
+    resizes(options)
+        || options.watermark.is_some()
+        || options.header.is_some()
+        || options.footer.is_some()
+        || options.layout.is_some()
+        || options.convert_to_gray
+        || pads(options)
+}
+
+/// Whether a job asks for copies to be padded to whole sheets and prints
+/// collated copies on both sides. Page ranges leave the padding out, so
+/// jobs with them aren't padded.
+fn pads(options: &PrinterJobOptions) -> bool {
+    let raw_properties = &options.raw_properties;
+    let copies = raw_properties
+        .get("copies")
+        .and_then(|copies| copies.trim().parse::<u32>().ok())
+        .unwrap_or(1);
+    options.pad_odd_pages
+        && copies > 1
+        && raw_properties
+            .get("sides")
+            .is_some_and(|sides| sides.starts_with("two-sided"))
+        && raw_properties.get("collate").map(String::as_str) != Some("false")
+        && !raw_properties.contains_key("page-ranges")
+}
+
+/// Whether a job's options scale or move the content of its pages
+fn resizes(options: &PrinterJobOptions) -> bool {
+    options.scale.is_some() || options.fit_to_page || options.margins.is_some()
+}
+
+/// Apply a job's pre-processing options to its document, returning the
+/// changed document, or None if the options don't change it. Images come
+/// back as PDFs.
+pub fn apply(data: &[u8], options: &PrinterJobOptions) -> Result<Option<Vec<u8>>, String> {
+    if !needed(options) {
+        return Ok(None);
+    }
+    let image = !crate::document::is_pdf(data);
+    let blank;
+    let mut document = if image {
+        if !matches!(
+            crate::document::sniff_media_type(data),
+            Some("image/png" | "image/jpeg")
+        ) {
+            // Other documents are left to the driver's color mode and
+            // its handling of copies
+            if !needed(&PrinterJobOptions {
+                convert_to_gray: false,
+                pad_odd_pages: false,
+                ..options.clone()
+            }) {
+                return Ok(None);
+            }
+            return Err(
+                "Page options like margins, watermarks and layouts can only be \
+                        applied to PDF documents and PNG and JPEG images"
+                    .to_string(),
+            );
+        }
+        let media = options.raw_properties.get("media").map(String::as_str);
+        let (width, height) = media
+            .and_then(crate::raster::media_size)
+            .or_else(|| crate::raster::media_size(DEFAULT_MEDIA))
+            .unwrap_or((612.0, 792.0));
+        blank = crate::pdf::blank_document(width, height);
+        Document::parse(&blank)?
+    } else {
+        Document::parse(data)?
+    };
+
+    if image {
+        place_image(&mut document, data, options)?;
+    } else if resizes(options) {
+        let pages = document.pages()?;
+        resize_pages(&mut document, &pages, options)?;
+    }
+    let mut pages = document.pages()?;
+    if let Some(watermark) = &options.watermark {
+        stamp_watermark(&mut document, &mut pages, watermark)?;
+    }
+    if options.header.is_some() || options.footer.is_some() {
+        stamp_headers(&mut document, &mut pages, options)?;
+    }
+    if options.convert_to_gray {
+        gray_pages(&mut document, &mut pages);
+    }
+    // Pages are laid out last, so what was stamped on them is scaled with
+    // them
+    if let Some(layout) = options.layout {
+        impose(&mut document, &pages, layout)?;
+    }
+    if pads(options) {
+        pad_sides(&mut document, options)?;
+    }
+    Ok(Some(document.finish()))
+}
+
+/// The document a job prints, for previews: the pre-processed document,
+/// or the document itself if the options don't change it. Images are
+/// placed on a page of the job's media even without page options, as they
+/// are only printed as pages.
+pub fn preview_document(data: &[u8], options: &PrinterJobOptions) -> Result<Vec<u8>, String> {
+    if let Some(processed) = apply(data, options)? {
+        return Ok(processed);
+    }
+    match crate::document::sniff_media_type(data) {
+        Some("application/pdf" | "application/postscript") => Ok(data.to_vec()),
+        Some("image/png" | "image/jpeg") => {
+            let placed = apply(
+                data,
+                &PrinterJobOptions {
+                    fit_to_page: true,
+                    ..options.clone()
+                },
+            )?;
+            Ok(placed.expect("fitting to the page changes images"))
+        }
+        _ => Err(
+            "Previews can only be rendered of PDF and PostScript documents and PNG \
+                and JPEG images"
+                .to_string(),
+        ),
+    }
+}
+
+/// Print the header and footer lines on every page, with the page number
+/// and count filled in for each
+fn stamp_headers(
+    document: &mut Document,
+    pages: &mut [Page],
+    options: &PrinterJobOptions,
+) -> Result<(), String> {
+    let header = options
+        .header
+        .as_deref()
+        .map(|text| page_template("header", text, &options.metadata))
+        .transpose()?;
+    let footer = options
+        .footer
+        .as_deref()
+        .map(|text| page_template("footer", text, &options.metadata))
+        .transpose()?;
+
+    let mut variables = options.metadata.clone();
+    variables.insert("pages".to_string(), pages.len().to_string());
+    variables.insert(
+        "jobName".to_string(),
+        options.name.clone().unwrap_or_default(),
+    );
+    variables.insert(
+        "user".to_string(),
+        options
+            .requesting_user
+            .clone()
+            .or_else(crate::spooler::current_user)
+            .unwrap_or_default(),
+    );
+    let now = crate::document::format_utc(std::time::SystemTime::now());
+    variables.insert("date".to_string(), now[..10].to_string());
+
+    let font = document.add(Font::Helvetica.dict());
+    for (index, page) in pages.iter_mut().enumerate() {
+        variables.insert("page".to_string(), (index + 1).to_string());
+        let (width, height) = page.size();
+        let mut content = String::new();
+        if let Some(header) = &header {
+            let text = header.render(&variables)?;
+            let baseline = height - HEADER_MARGIN - 0.7 * HEADER_TEXT_SIZE;
+            content.push_str(&line_content(&text, width, baseline));
+        }
+        if let Some(footer) = &footer {
+            let text = footer.render(&variables)?;
+            content.push_str(&line_content(&text, width, HEADER_MARGIN));
+        }
+        page.add_resource("Font", HEADER_FONT, Object::Ref(font));
+        document.overlay(page, content.as_bytes());
+    }
+    Ok(())
+}
+
+/// Draw a line of black text centered across the page, shrunk to fit its
+/// width
+fn line_content(text: &str, width: f64, baseline: f64) -> String {
+    let text: String = text
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect();
+    let font = Font::Helvetica;
+    let size = HEADER_TEXT_SIZE.min(0.9 * width / font.width(&text, 1.0).max(f64::EPSILON));
+    let number = crate::pdf::format_number;
+    format!(
+        "q 0 g BT /{} {} Tf {} {} Td {} Tj ET Q\n",
+        HEADER_FONT,
+        number(size),
+        number((width - font.width(&text, size)) / 2.0),
+        number(baseline),
+        crate::pdf::text_string(&text)
+    )
+}
+
+/// A new page of `width` by `height` points drawing `content`, which uses
+/// the form and image XObjects in `xobjects`
+fn new_page(
+    document: &mut Document,
+    (width, height): (f64, f64),
+    xobjects: Dict,
+    content: &str,
+) -> Dict {
+    let contents = document.add_deflated_stream(Dict::new(), content.as_bytes());
+    let media_box = vec![
+        Object::Number(0.0),
+        Object::Number(0.0),
+        Object::Number(width),
+        Object::Number(height),
+    ];
+    Dict::from([
+        ("Type", Object::name("Page")),
+        ("MediaBox", Object::Array(media_box.clone())),
+        ("CropBox", Object::Array(media_box)),
+        ("Rotate", Object::Number(0.0)),
+        (
+            "Resources",
+            Object::Dict(Dict::from([("XObject", Object::Dict(xobjects))])),
+        ),
+        ("Contents", Object::Ref(contents)),
+    ])
+}
+
+/// Draw a page added as the form XObject `name`, upright, scaled by
+/// `scale` with its bottom left corner at `x`, `y`
+fn form_content(name: &str, page: &Page, scale: f64, x: f64, y: f64) -> String {
+    let number = crate::pdf::format_number;
+    let matrix: Vec<String> = page.user_matrix().iter().map(|n| number(*n)).collect();
+    format!(
+        "q {} 0 0 {} {} {} cm {} cm /{} Do Q\n",
+        number(scale),
+        number(scale),
+        number(x),
+        number(y),
+        matrix.join(" "),
+        name
+    )
+}
+
+/// Clip what follows to an area, as `[x0, y0, x1, y1]`
+fn clip_content([x0, y0, x1, y1]: [f64; 4]) -> String {
+    let number = crate::pdf::format_number;
+    format!(
+        "{} {} {} {} re W n\n",
+        number(x0),
+        number(y0),
+        number(x1 - x0),
+        number(y1 - y0)
+    )
+}
+
+/// Scale of content `width` by `height` points drawn in an area: the
+/// job's scale, the scale that fits the area with fit to page, and
+/// otherwise its own size, shrunk if it doesn't fit
+fn content_scale(options: &PrinterJobOptions, (width, height): (f64, f64), area: [f64; 4]) -> f64 {
+    let fit = ((area[2] - area[0]) / width).min((area[3] - area[1]) / height);
+    match options.scale {
+        Some(scale) => scale / 100.0,
+        None if options.fit_to_page => fit,
+        None => fit.min(1.0),
+    }
+}
+
+/// Redraw each page's content scaled and centered within the margins,
+/// clipped so nothing prints in them. Pages keep their size.
+fn resize_pages(
+    document: &mut Document,
+    pages: &[Page],
+    options: &PrinterJobOptions,
+) -> Result<(), String> {
+    let margins = options.margins.unwrap_or_default();
+    let mut resized = Vec::with_capacity(pages.len());
+    for page in pages {
+        let (width, height) = page.size();
+        let area = margins.area(width, height)?;
+        let scale = content_scale(options, (width, height), area);
+        let x = (area[0] + area[2] - width * scale) / 2.0;
+        let y = (area[1] + area[3] - height * scale) / 2.0;
+
+        let form = document.add_page_form(page)?;
+        let content = clip_content(area) + &form_content("PjsContent", page, scale, x, y);
+        let xobjects = Dict::from([("PjsContent", Object::Ref(form))]);
+        resized.push(new_page(document, (width, height), xobjects, &content));
+    }
+    document.replace_pages(resized)
+}
+
+/// Draw an image on the document's one blank page, as large as fits within
+/// the margins and then scaled by the job's scale, centered
+fn place_image(
+    document: &mut Document,
+    data: &[u8],
+    options: &PrinterJobOptions,
+) -> Result<(), String> {
+    let page = document.pages()?.remove(0);
+    let (width, height) = page.size();
+    let area = options.margins.unwrap_or_default().area(width, height)?;
+    let image = add_image_data(document, data, "document")?;
+    let fit =
+        ((area[2] - area[0]) / image.width as f64).min((area[3] - area[1]) / image.height as f64);
+    let scale = fit * options.scale.map_or(1.0, |scale| scale / 100.0);
+    let (drawn_width, drawn_height) = (image.width as f64 * scale, image.height as f64 * scale);
+
+    let number = crate::pdf::format_number;
+    let content = clip_content(area)
+        + &format!(
+            "q {} 0 0 {} {} {} cm /PjsImage Do Q\n",
+            number(drawn_width),
+            number(drawn_height),
+            number((area[0] + area[2] - drawn_width) / 2.0),
+            number((area[1] + area[3] - drawn_height) / 2.0)
+        );
+    let xobjects = Dict::from([("PjsImage", Object::Ref(image.id))]);
+    let page = new_page(document, (width, height), xobjects, &content);
+    document.replace_pages(vec![page])
+}
+
+/// Pages on each side of each sheet, in reading order from the top left.
+/// None leaves a cell blank.
+fn sheet_sides(layout: Layout, pages: usize) -> Vec<Vec<Option<usize>>> {
+    let page = |index: usize| (index < pages).then_some(index);
+    match layout {
+        Layout::Booklet => {
+            // Each sheet holds two pages from the front of the booklet and
+            // two from the back
+            let count = pages.div_ceil(4) * 4;
+            (0..count / 4)
+                .flat_map(|sheet| {
+                    let (front, back) = (2 * sheet, count - 1 - 2 * sheet);
+                    [
+                        vec![page(back), page(front)],
+                        vec![page(front + 1), page(back - 1)],
+                    ]
+                })
+                .collect()
+        }
+        Layout::TwoUp | Layout::FourUp => {
+            let cells = if layout == Layout::TwoUp { 2 } else { 4 };
+            (0..pages.div_ceil(cells))
+                .map(|sheet| (0..cells).map(|cell| page(sheet * cells + cell)).collect())
+                .collect()
+        }
+    }
+}
+
+/// Replace the document's pages with sheets holding them. Sheets are the
+/// size of the first page: for two pages a sheet, turned so its long side
+/// is split in half.
+fn impose(document: &mut Document, pages: &[Page], layout: Layout) -> Result<(), String> {
+    let (width, height) = pages[0].size();
+    let (sheet_width, sheet_height, columns, rows) = match layout {
+        Layout::Booklet | Layout::TwoUp if width <= height => (height, width, 2, 1),
+        Layout::Booklet | Layout::TwoUp => (height, width, 1, 2),
+        Layout::FourUp => (width, height, 2, 2),
+    };
+    let margin = if layout == Layout::Booklet {
+        0.0
+    } else {
+        CROP_MARGIN
+    };
+    let (cell_width, cell_height) = (sheet_width / columns as f64, sheet_height / rows as f64);
+
+    let mut forms = Vec::with_capacity(pages.len());
+    for page in pages {
+        forms.push(document.add_page_form(page)?);
+    }
+    let mut sheets = Vec::new();
+    for side in sheet_sides(layout, pages.len()) {
+        let mut content = String::new();
+        let mut xobjects = Dict::new();
+        for (cell, index) in side.into_iter().enumerate() {
+            let Some(index) = index else {
+                continue;
+            };
+            let page = &pages[index];
+            let (page_width, page_height) = page.size();
+            let (column, row) = (cell % columns, cell / columns);
+            let scale = ((cell_width - 2.0 * margin) / page_width)
+                .min((cell_height - 2.0 * margin) / page_height);
+            let (drawn_width, drawn_height) = (page_width * scale, page_height * scale);
+            let x = column as f64 * cell_width + (cell_width - drawn_width) / 2.0;
+            let y =
+                sheet_height - (row + 1) as f64 * cell_height + (cell_height - drawn_height) / 2.0;
+
+            let name = format!("PjsPage{}", index + 1);
+            xobjects.set(&name, Object::Ref(forms[index]));
+            content.push_str(&form_content(&name, page, scale, x, y));
+            if layout != Layout::Booklet {
+                content.push_str(&crop_marks([x, y, x + drawn_width, y + drawn_height]));
+            }
+        }
+        let sheet = new_page(document, (sheet_width, sheet_height), xobjects, &content);
+        sheets.push(sheet);
+    }
+    document.replace_pages(sheets)
+}
+
+/// Add blank pages the size of the last one until the document prints on
+/// an even number of sides, counting the pages the driver puts on each
+/// side with `number-up`
+fn pad_sides(document: &mut Document, options: &PrinterJobOptions) -> Result<(), String> {
+    let pages = document.pages()?;
+    let number_up = options
+        .raw_properties
+        .get("number-up")
+        .and_then(|number_up| number_up.trim().parse::<usize>().ok())
+        .filter(|number_up| *number_up > 0)
+        .unwrap_or(1);
+    let sides = pages.len().div_ceil(number_up);
+    if sides % 2 == 1 {
+        let size = pages[pages.len() - 1].size();
+        let blanks = (pages.len()..sides * number_up + 1)
+            .map(|_| new_page(document, size, Dict::new(), ""))
+            .collect();
+        document.append_pages(blanks)?;
+    }
+    Ok(())
+}
+
+/// Thin black lines just outside each corner of a page drawn on a sheet,
+/// marking where to cut
+fn crop_marks([x0, y0, x1, y1]: [f64; 4]) -> String {
+    let number = crate::pdf::format_number;
+    let mut marks = String::from("q 0 G 0.25 w\n");
+    for (x, y, dx, dy) in [
+        (x0, y0, -1.0, -1.0),
+        (x1, y0, 1.0, -1.0),
+        (x0, y1, -1.0, 1.0),
+        (x1, y1, 1.0, 1.0),
+    ] {
+        let (near, far) = (CROP_MARK_OFFSET, CROP_MARK_OFFSET + CROP_MARK_LENGTH);
+        marks.push_str(&format!(
+            "{} {} m {} {} l {} {} m {} {} l S\n",
+            number(x + dx * near),
+            number(y),
+            number(x + dx * far),
+            number(y),
+            number(x),
+            number(y + dy * near),
+            number(x),
+            number(y + dy * far)
+        ));
+    }
+    marks.push_str("Q\n");
+    marks
+}
+
+/// An image added to the document, with its size in pixels
+struct Image {
+    id: ObjectId,
+    width: u32,
+    height: u32,
+}
+
+/// Turn every page gray. A black rectangle painted over the page with the
+/// saturation blend mode keeps the luminosity of what's below and takes
+/// the rectangle's saturation, which is none.
+fn gray_pages(document: &mut Document, pages: &mut [Page]) {
+    let state = document.add(Object::Dict(Dict::from([
+        ("Type", Object::name("ExtGState")),
+        ("BM", Object::name("Saturation")),
+    ])));
+    for page in pages.iter_mut() {
+        let (width, height) = page.size();
+        page.add_resource("ExtGState", GRAY_STATE, Object::Ref(state));
+        // Blend in RGB unless the page already says how
+        if page.dict.get("Group").is_none() {
+            page.dict.set(
+                "Group",
+                Object::Dict(Dict::from([
+                    ("Type", Object::name("Group")),
+                    ("S", Object::name("Transparency")),
+                    ("CS", Object::name("DeviceRGB")),
+                ])),
+            );
+        }
+        let content = format!(
+            "q /{} gs 0 g 0 0 {} {} re f Q",
+            GRAY_STATE,
+            crate::pdf::format_number(width),
+            crate::pdf::format_number(height)
+        );
+        document.overlay(page, content.as_bytes());
+    }
+}
+
+fn stamp_watermark(
+    document: &mut Document,
+    pages: &mut [Page],
+    watermark: &Watermark,
+) -> Result<(), String> {
+    watermark.validate()?;
+    let image = match &watermark.image {
+        Some(path) => Some(add_image(document, path)?),
+        None => None,
+    };
+    let text = watermark
+        .text
+        .as_deref()
+        .map(str::trim)
+        .filter(|text| !text.is_empty());
+
+    let opacity = Object::Number(watermark.opacity);
+    let graphics_state = |blend_mode: Option<&str>| {
+        let mut state = Dict::from([
+            ("Type", Object::name("ExtGState")),
+            ("ca", opacity.clone()),
+            ("CA", opacity.clone()),
+        ]);
+        if let Some(blend_mode) = blend_mode {
+            state.set("BM", Object::name(blend_mode));
+        }
+        Object::Dict(state)
+    };
+    // Multiplying lets the page show through the white parts of images,
+    // which have no transparency once decoded
+    let image = image.map(|image| (image, document.add(graphics_state(Some("Multiply")))));
+    let text = text.map(|text| {
+        let font = document.add(Font::HelveticaBold.dict());
+        (text, font, document.add(graphics_state(None)))
+    });
+
+    for page in pages.iter_mut() {
+        let size = page.size();
+        let mut content = String::new();
+        if let Some((image, state)) = &image {
+            page.add_resource("XObject", WATERMARK_IMAGE, Object::Ref(image.id));
+            page.add_resource("ExtGState", WATERMARK_IMAGE_STATE, Object::Ref(*state));
+            content.push_str(&image_content(image, watermark.position, size));
+        }
+        if let Some((text, font, state)) = text {
+            page.add_resource("Font", WATERMARK_FONT, Object::Ref(font));
+            page.add_resource("ExtGState", WATERMARK_STATE, Object::Ref(state));
+            content.push_str(&text_content(text, watermark.position, size));
+        }
+        document.overlay(page, content.as_bytes());
+    }
+    Ok(())
+}
+
+/// Draw watermark text in mid gray, centered on its position
+fn text_content(text: &str, position: WatermarkPosition, (width, height): (f64, f64)) -> String {
+    let font = Font::HelveticaBold;
+    let unit_width = font.width(text, 1.0).max(f64::EPSILON);
+    let (size, x, y, angle) = match position {
+        WatermarkPosition::Diagonal => {
+            let size = (0.8 * width.hypot(height) / unit_width)
+                .min(MAX_TEXT_SIZE)
+                .min(0.25 * width.min(height));
+            (size, width / 2.0, height / 2.0, height.atan2(width))
+        }
+        WatermarkPosition::Center => {
+            let size = (0.8 * width / unit_width).min(MAX_TEXT_SIZE);
+            (size, width / 2.0, height / 2.0, 0.0)
+        }
+        WatermarkPosition::Top => {
+            let size = (0.8 * width / unit_width).min(BAND_TEXT_SIZE);
+            (size, width / 2.0, height - EDGE_MARGIN - size * 0.35, 0.0)
+        }
+        WatermarkPosition::Bottom => {
+            let size = (0.8 * width / unit_width).min(BAND_TEXT_SIZE);
+            (size, width / 2.0, EDGE_MARGIN + size * 0.35, 0.0)
+        }
+    };
+    let number = crate::pdf::format_number;
+    // Capital letters are about 0.7 of the size tall, so the text is
+    // vertically centered on its position by dropping it half that
+    format!(
+        "q /{} gs 0.5 g\n{} {} {} {} {} {} cm\nBT /{} {} Tf {} {} Td {} Tj ET\nQ\n",
+        WATERMARK_STATE,
+        number(angle.cos()),
+        number(angle.sin()),
+        number(-angle.sin()),
+        number(angle.cos()),
+        number(x),
+        number(y),
+        WATERMARK_FONT,
+        number(size),
+        number(-unit_width * size / 2.0),
+        number(-0.35 * size),
+        crate::pdf::text_string(text)
+    )
+}
+
+/// Draw a watermark image as large as fits its position, keeping its
+/// aspect ratio
+fn image_content(
+    image: &Image,
+    position: WatermarkPosition,
+    (width, height): (f64, f64),
+) -> String {
+    let (box_width, box_height) = match position {
+        WatermarkPosition::Diagonal | WatermarkPosition::Center => (0.6 * width, 0.6 * height),
+        WatermarkPosition::Top | WatermarkPosition::Bottom => (0.5 * width, 0.12 * height),
+    };
+    let scale = (box_width / image.width as f64).min(box_height / image.height as f64);
+    let (drawn_width, drawn_height) = (image.width as f64 * scale, image.height as f64 * scale);
+    let y = match position {
+        WatermarkPosition::Diagonal | WatermarkPosition::Center => (height - drawn_height) / 2.0,
+        WatermarkPosition::Top => height - EDGE_MARGIN - drawn_height,
+        WatermarkPosition::Bottom => EDGE_MARGIN,
+    };
+    let number = crate::pdf::format_number;
+    format!(
+        "q /{} gs {} 0 0 {} {} {} cm /{} Do Q\n",
+        WATERMARK_IMAGE_STATE,
+        number(drawn_width),
+        number(drawn_height),
+        number((width - drawn_width) / 2.0),
+        number(y),
+        WATERMARK_IMAGE
+    )
+}
+
+/// Add a PNG or JPEG image file to the document as an image XObject
+fn add_image(document: &mut Document, path: &str) -> Result<Image, String> {
+    let data = std::fs::read(path)
+        .map_err(|e| format!("Failed to read watermark image '{}': {}", path, e))?;
+    add_image_data(document, &data, &format!("watermark image '{}'", path))
+}
+
+/// Add a PNG or JPEG image to the document as an image XObject. `what`
+/// names the image in errors.
+fn add_image_data(document: &mut Document, data: &[u8], what: &str) -> Result<Image, String> {
+    let image = |width: u32, height: u32, color_space: &str| {
+        Dict::from([
+            ("Type", Object::name("XObject")),
+            ("Subtype", Object::name("Image")),
+            ("Width", Object::Number(width as f64)),
+            ("Height", Object::Number(height as f64)),
+            ("ColorSpace", Object::name(color_space)),
+            ("BitsPerComponent", Object::Number(8.0)),
+        ])
+    };
+    match crate::document::sniff_media_type(data) {
+        // JPEG data is embedded as it is
+        Some("image/jpeg") => {
+            let (width, height, components) =
+                jpeg_size(data).ok_or_else(|| format!("Invalid JPEG data in the {}", what))?;
+            let color_space = match components {
+                1 => "DeviceGray",
+                3 => "DeviceRGB",
+                4 => "DeviceCMYK",
+                _ => return Err(format!("Unsupported JPEG colors in the {}", what)),
+            };
+            let mut dict = image(width, height, color_space);
+            dict.set("Filter", Object::name("DCTDecode"));
+            let id = document.add_stream(dict, data);
+            Ok(Image { id, width, height })
+        }
+        Some("image/png") => {
+            let bitmap = crate::raster::decode_png(data)?;
+            let color_space = match bitmap.color {
+                crate::raster::ColorSpace::Gray => "DeviceGray",
+                crate::raster::ColorSpace::Rgb => "DeviceRGB",
+            };
+            let dict = image(bitmap.width, bitmap.height, color_space);
+            let id = document.add_deflated_stream(dict, &bitmap.pixels);
+            Ok(Image {
+                id,
+                width: bitmap.width,
+                height: bitmap.height,
+            })
+        }
+        _ => Err(format!("The {} must be a PNG or JPEG image", what)),
+    }
+}
+
+/// Width, height and number of color components of a JPEG image, from its
+/// start of frame marker
+fn jpeg_size(data: &[u8]) -> Option<(u32, u32, u8)> {
+    let mut pos = 2;
+    while pos + 4 <= data.len() {
+        if data[pos] != 0xff {
+            return None;
+        }
+        let marker = data[pos + 1];
+        // Fill bytes, and markers without a length
+        if marker == 0xff {
+            pos += 1;
+            continue;
+        }
+        if marker == 0x01 || (0xd0..=0xd9).contains(&marker) {
+            pos += 2;
+            continue;
+        }
+        let length = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        // SOF0 to SOF15, except DHT, JPG and DAC, which share the range
+        if (0xc0..=0xcf).contains(&marker) && !matches!(marker, 0xc4 | 0xc8 | 0xcc) {
+            let frame = data.get(pos + 4..pos + 10)?;
+            let height = u16::from_be_bytes([frame[1], frame[2]]) as u32;
+            let width = u16::from_be_bytes([frame[3], frame[4]]) as u32;
+            return (width > 0 && height > 0).then_some((width, height, frame[5]));
+        }
+        pos += 2 + length;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn watermarked(watermark: &str) -> PrinterJobOptions {
+        PrinterJobOptions::from_map(HashMap::from([(
+            "job-watermark".to_string(),
+            watermark.to_string(),
+        )]))
+    }
+
+    #[test]
+    fn test_watermark_options() {
+        let options = watermarked(r#"{"text":"DRAFT"}"#);
+        let watermark = options.watermark.as_ref().unwrap();
+        assert_eq!(watermark.opacity, DEFAULT_OPACITY);
+        assert_eq!(watermark.position, WatermarkPosition::Diagonal);
+        assert!(watermark.validate().is_ok());
+        // Watermarks round-trip through raw properties
+        assert_eq!(
+            PrinterJobOptions::from_map(options.to_map()).watermark,
+            options.watermark
+        );
+
+        let invisible = watermarked(r#"{"text":"DRAFT","opacity":0}"#);
+        assert!(invisible.watermark.unwrap().validate().is_err());
+        let empty = watermarked(r#"{"text":" ","position":"top"}"#);
+        assert!(empty.watermark.unwrap().validate().is_err());
+        assert!(!needed(&watermarked(r#"{"position":"sideways"}"#)));
+    }
+
+    #[test]
+    fn test_stamp_text() {
+        let original = crate::document::test_page_pdf("Invoice", &[]);
+        let options = watermarked(r#"{"text":"CONFIDENTIAL","opacity":0.5}"#);
+        let stamped = apply(&original, &options).unwrap().unwrap();
+        assert!(stamped.starts_with(&original));
+        assert_eq!(crate::document::validate_pdf(&stamped), Ok(1));
+
+        let document = Document::parse(&stamped).unwrap();
+        let page = &document.pages().unwrap()[0];
+        let fonts = page
+            .resources
+            .get("Font")
+            .and_then(Object::as_dict)
+            .unwrap();
+        assert!(fonts.get(WATERMARK_FONT).is_some());
+        let states = page.resources.get("ExtGState").and_then(Object::as_dict);
+        assert!(states.unwrap().get(WATERMARK_STATE).is_some());
+
+        // Diagonal text is centered on the page at the angle of its diagonal
+        let content = text_content("CONFIDENTIAL", WatermarkPosition::Diagonal, (612.0, 792.0));
+        assert!(content.contains("0.6114 0.7913 -0.7913 0.6114 306 396 cm"));
+        assert!(content.contains("<434F4E464944454E5449414C> Tj"));
+
+        assert!(apply(b"plain text", &options).is_err());
+        assert_eq!(apply(&original, &PrinterJobOptions::none()), Ok(None));
+    }
+
+    #[test]
+    fn test_stamp_image() {
+        // A 2x1 baseline JPEG header is enough to embed it
+        let jpeg = [
+            0xff, 0xd8, 0xff, 0xe0, 0x00, 0x04, 0x00, 0x00, 0xff, 0xc0, 0x00, 0x0b, 0x08, 0x00,
+            0x01, 0x00, 0x02, 0x03, 0x01, 0x11, 0x00, 0xff, 0xd9,
+        ];
+        assert_eq!(jpeg_size(&jpeg), Some((2, 1, 3)));
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stamp.jpg");
+        std::fs::write(&path, jpeg).unwrap();
+
+        let original = crate::document::test_page_pdf("Invoice", &[]);
+        let options =
+            watermarked(&serde_json::json!({ "image": path, "position": "bottom" }).to_string());
+        let stamped = apply(&original, &options).unwrap().unwrap();
+        let text = String::from_utf8_lossy(&stamped);
+        assert!(text.contains("/Filter /DCTDecode"));
+        assert!(text.contains("/BM /Multiply"));
+
+        let missing = watermarked(r#"{"image":"/path/that/does_not_exist.png"}"#);
+        assert!(apply(&original, &missing)
+            .unwrap_err()
+            .contains("Failed to read watermark image"));
+    }
+
+    #[test]
+    fn test_stamp_headers() {
+        let original = crate::document::test_page_pdf("Invoice", &[]);
+        let options = PrinterJobOptions::from_map(HashMap::from([
+            (
+                "job-header".to_string(),
+                "Order {{orderId}} — page {{page}}/{{pages}}".to_string(),
+            ),
+            ("job-footer".to_string(), "{{jobName}}".to_string()),
+            ("job-name".to_string(), "Invoice".to_string()),
+            (
+                "job-metadata".to_string(),
+                r#"{"orderId":"A-1"}"#.to_string(),
+            ),
+        ]));
+        assert!(validate(&options).is_ok());
+        let stamped = apply(&original, &options).unwrap().unwrap();
+        assert_eq!(crate::document::validate_pdf(&stamped), Ok(1));
+
+        let document = Document::parse(&stamped).unwrap();
+        let page = &document.pages().unwrap()[0];
+        let fonts = page.resources.get("Font").and_then(Object::as_dict);
+        assert!(fonts.unwrap().get(HEADER_FONT).is_some());
+
+        // Lines are centered, with the header below the top edge
+        let content = line_content("Order A-1 — page 1/1", 612.0, 759.7);
+        assert!(content.contains("759.7 Td <4F7264657220412D31209720706167652031"));
+        assert!(line_content("A\nB", 612.0, 18.0).contains("<412042>"));
+
+        let unknown = PrinterJobOptions::from_map(HashMap::from([(
+            "job-footer".to_string(),
+            "Shipment {{shipmentId}}".to_string(),
+        )]));
+        assert!(validate(&unknown).is_err());
+    }
+
+    #[test]
+    fn test_gray_pages() {
+        let original = crate::document::test_page_pdf("Photo", &[]);
+        let options = PrinterJobOptions {
+            convert_to_gray: true,
+            ..PrinterJobOptions::none()
+        };
+        let gray = apply(&original, &options).unwrap().unwrap();
+        assert_eq!(crate::document::validate_pdf(&gray), Ok(1));
+        let document = Document::parse(&gray).unwrap();
+        let page = &document.pages().unwrap()[0];
+        let states = page.resources.get("ExtGState").and_then(Object::as_dict);
+        assert!(states.unwrap().get(GRAY_STATE).is_some());
+        assert!(page.dict.get("Group").is_some());
+
+        // Other documents are left to the driver
+        assert_eq!(apply(b"plain text", &options), Ok(None));
+        assert_eq!(ColorMode::parse("grayscale"), Some(ColorMode::Grayscale));
+        assert_eq!(ColorMode::Monochrome.keywords()[0], "bi-level");
+    }
+
+    /// A document with `count` copies of the test page
+    fn pages_pdf(count: usize) -> Vec<u8> {
+        let original = crate::document::test_page_pdf("Page", &[]);
+        let mut document = Document::parse(&original).unwrap();
+        let page = document.pages().unwrap().remove(0);
+        document.replace_pages(vec![page.dict; count]).unwrap();
+        document.finish()
+    }
+
+    #[test]
+    fn test_sheet_sides() {
+        assert_eq!(
+            sheet_sides(Layout::Booklet, 6),
+            vec![
+                vec![None, Some(0)],
+                vec![Some(1), None],
+                vec![Some(5), Some(2)],
+                vec![Some(3), Some(4)],
+            ]
+        );
+        assert_eq!(
+            sheet_sides(Layout::FourUp, 5),
+            vec![
+                vec![Some(0), Some(1), Some(2), Some(3)],
+                vec![Some(4), None, None, None],
+            ]
+        );
+        assert_eq!(Layout::parse("2-up"), Some(Layout::TwoUp));
+    }
+
+    #[test]
+    fn test_impose() {
+        let original = pages_pdf(3);
+        assert_eq!(crate::document::validate_pdf(&original), Ok(3));
+        let layout = |layout: &str| {
+            PrinterJobOptions::from_map(HashMap::from([(
+                "job-layout".to_string(),
+                layout.to_string(),
+            )]))
+        };
+
+        // Three pages make one booklet sheet, printed on both sides
+        let booklet = apply(&original, &layout("booklet")).unwrap().unwrap();
+        assert_eq!(crate::document::validate_pdf(&booklet), Ok(2));
+        let document = Document::parse(&booklet).unwrap();
+        let sheets = document.pages().unwrap();
+        assert_eq!(sheets[0].size(), (792.0, 612.0));
+        let xobjects = sheets[0].resources.get("XObject").and_then(Object::as_dict);
+        assert!(xobjects.unwrap().get("PjsPage1").is_some());
+
+        let four_up = apply(&original, &layout("4-up")).unwrap().unwrap();
+        assert_eq!(crate::document::validate_pdf(&four_up), Ok(1));
+        let marks = crop_marks([18.0, 18.0, 288.0, 378.0]);
+        assert!(marks.contains("15 18 m 3 18 l 18 15 m 18 3 l S"));
+
+        // Stamped pages are laid out with what was stamped on them
+        let mut options = layout("2-up");
+        options.header = Some("Page {{page}}".to_string());
+        let two_up = apply(&original, &options).unwrap().unwrap();
+        assert_eq!(crate::document::validate_pdf(&two_up), Ok(2));
+    }
+
+    #[test]
+    fn test_pad_odd_pages() {
+        let original = pages_pdf(3);
+        let options = |entries: &[(&str, &str)]| {
+            let mut map = HashMap::from([
+                ("job-pad-odd-pages".to_string(), "true".to_string()),
+                ("copies".to_string(), "2".to_string()),
+                ("sides".to_string(), "two-sided-long-edge".to_string()),
+            ]);
+            for (key, value) in entries {
+                map.insert(key.to_string(), value.to_string());
+            }
+            PrinterJobOptions::from_map(map)
+        };
+
+        let padded = apply(&original, &options(&[])).unwrap().unwrap();
+        assert_eq!(crate::document::validate_pdf(&padded), Ok(4));
+        let pages = Document::parse(&padded).unwrap().pages().unwrap();
+        assert_eq!(pages[3].size(), pages[2].size());
+
+        // Three pages two to a side already fill both sides of a sheet
+        let two_up = apply(&original, &options(&[("number-up", "2")]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(crate::document::validate_pdf(&two_up), Ok(3));
+        let four_up = apply(&original, &options(&[("job-layout", "4-up")]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(crate::document::validate_pdf(&four_up), Ok(2));
+
+        for entries in [
+            [("copies", "1")],
+            [("sides", "one-sided")],
+            [("collate", "false")],
+            [("page-ranges", "1-2")],
+        ] {
+            assert_eq!(apply(&original, &options(&entries)), Ok(None));
+        }
+    }
+
+    #[test]
+    fn test_resize_pages() {
+        let mut options = PrinterJobOptions::none();
+        options.margins = Some(Margins {
+            top: 36.0,
+            right: 36.0,
+            bottom: 36.0,
+            left: 36.0,
+        });
+        let area = options.margins.unwrap().area(612.0, 792.0).unwrap();
+        assert_eq!(area, [36.0, 36.0, 576.0, 756.0]);
+        // Content is shrunk to fit the margins, and scaled as asked
+        let fit = 540.0 / 612.0;
+        assert_eq!(content_scale(&options, (612.0, 792.0), area), fit);
+        assert_eq!(content_scale(&options, (72.0, 72.0), area), 1.0);
+        options.fit_to_page = true;
+        assert_eq!(content_scale(&options, (72.0, 72.0), area), 7.5);
+        options.fit_to_page = false;
+        options.scale = Some(50.0);
+        assert_eq!(content_scale(&options, (612.0, 792.0), area), 0.5);
+
+        let original = pages_pdf(2);
+        let resized = apply(&original, &options).unwrap().unwrap();
+        assert_eq!(crate::document::validate_pdf(&resized), Ok(2));
+        let document = Document::parse(&resized).unwrap();
+        let pages = document.pages().unwrap();
+        assert_eq!(pages[1].size(), (612.0, 792.0));
+        assert!(pages[1]
+            .resources
+            .get("XObject")
+            .and_then(Object::as_dict)
+            .is_some_and(|xobjects| xobjects.get("PjsContent").is_some()));
+        assert_eq!(clip_content(area), "36 36 540 720 re W n\n");
+
+        options.margins = Some(Margins {
+            left: 400.0,
+            right: 400.0,
+            ..Margins::default()
+        });
+        assert!(apply(&original, &options).is_err());
+        options.scale = Some(0.0);
+        assert!(validate(&options).is_err());
+    }
+
+    #[test]
+    fn test_place_image() {
+        let jpeg = [
+            0xff, 0xd8, 0xff, 0xe0, 0x00, 0x04, 0x00, 0x00, 0xff, 0xc0, 0x00, 0x0b, 0x08, 0x00,
+            0x01, 0x00, 0x02, 0x03, 0x01, 0x11, 0x00, 0xff, 0xd9,
+        ];
+        let options = PrinterJobOptions::from_map(HashMap::from([
+            ("media".to_string(), "oe_4x6-label_4x6in".to_string()),
+            ("job-fit-to-page".to_string(), "true".to_string()),
+        ]));
+        let pdf = apply(&jpeg, &options).unwrap().unwrap();
+        assert_eq!(crate::document::validate_pdf(&pdf), Ok(1));
+        let document = Document::parse(&pdf).unwrap();
+        assert_eq!(document.pages().unwrap()[0].size(), (288.0, 432.0));
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.contains("/Filter /DCTDecode"));
+    }
+
+    #[test]
+    fn test_preview_document() {
+        let jpeg = [
+            0xff, 0xd8, 0xff, 0xe0, 0x00, 0x04, 0x00, 0x00, 0xff, 0xc0, 0x00, 0x0b, 0x08, 0x00,
+            0x01, 0x00, 0x02, 0x03, 0x01, 0x11, 0x00, 0xff, 0xd9,
+        ];
+        let none = PrinterJobOptions::none();
+        // Images are placed on a page even without page options
+        let pdf = preview_document(&jpeg, &none).unwrap();
+        assert_eq!(crate::document::validate_pdf(&pdf), Ok(1));
+
+        let blank = crate::pdf::blank_document(612.0, 792.0);
+        assert_eq!(preview_document(&blank, &none).unwrap(), blank);
+        assert_ne!(
+            preview_document(&blank, &watermarked(r#"{"text":"DRAFT"}"#)).unwrap(),
+            blank
+        );
+        assert!(preview_document(b"plain text", &none).is_err());
+    }
+}
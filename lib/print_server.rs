@@ -0,0 +1,1018 @@
+//! Embeddable HTTP REST print service
+//!
+//! Serves the library's printer and job operations as JSON over HTTP, so the
+//! crate can run as a standalone print microservice. Endpoints mirror the
+//! N-API functions:
+//!
+//! - `GET /health`: health check, 503 when unhealthy
+//! - `GET /printers`, `GET /printers/{name}`: printer info
+//! - `POST /printers/{name}/jobs`: print the request body; query parameters
+//!   are job properties, as for `printBytes`
+//! - `DELETE /printers/{name}/jobs`: cancel the printer's queued jobs
+//! - `GET /jobs`: query jobs (`printerName`, `state`, `since`, `until`,
+//!   `sort`, `offset`, `limit`)
+//! - `GET /jobs/{id}`, `POST /jobs/{id}/reprint`, `POST /jobs/{id}/approve`,
+//!   `POST /jobs/{id}/reject?reason=...`
+//! - `GET /events`: WebSocket stream of job and printer state events, if
+//!   enabled; `?events=` limits it to a comma-separated list of events
+//!
+//! Jobs are the same records as job history exports. Events are JSON text
+//! messages with an `event` name (`job.submitted`, `job.completed`,
+//! `job.failed`, `printer.connected`, `printer.disconnected`,
+//! `printer.state_changed`, `printer.state_reasons_changed`) and a
+//! `timestamp`; printer events are detected by state monitoring, which is
+//! started along with the first server that streams events.
+//!
+//! When an auth token is set, every request needs an `Authorization: Bearer
+//! <token>` header, or for `/events` a `token` query parameter, as browsers
+//! can't set headers on WebSocket connections. A token is required to listen
+//! on anything but a loopback address. There is
+//! no TLS, so put a TLS-terminating proxy in front of servers reachable from
+//! other machines.
+
+use crate::core::{
+    JobId, JobQuery, JobSort, JobStateCounts, PrintError, PrinterCore, PrinterJob,
+    PrinterJobOptions, PrinterJobState, PrinterStateEvent,
+};
+use crate::export;
+use crate::http_server::{self, HttpRequest, Incoming, RequestError};
+use crate::webhooks::WebhookEvent;
+use crate::websocket;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{ErrorKind, Write};
+use std::net::{IpAddr, Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, Once};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+pub type ServerId = u64;
+
+pub const DEFAULT_HOST: &str = "127.0.0.1";
+pub const DEFAULT_PORT: u16 = 8632;
+/// Largest request body accepted, unless configured otherwise
+pub const DEFAULT_MAX_BODY_BYTES: usize = 64 * 1024 * 1024;
+
+/// How often the listener checks whether the server was stopped
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(50);
+/// Events waiting to be sent to a client before it is disconnected as too
+/// slow
+const CLIENT_QUEUE_CAPACITY: usize = 256;
+/// How often event streams check whether they should close
+const STREAM_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// Idle time after which an event stream is pinged
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Event names clients can filter on
+const EVENT_NAMES: [&str; 7] = [
+    "job.submitted",
+    "job.completed",
+    "job.failed",
+    "printer.connected",
+    "printer.disconnected",
+    "printer.state_changed",
+    "printer.state_reasons_changed",
+];
+
+static NEXT_SERVER_ID: AtomicU64 = AtomicU64::new(1);
+static EVENT_SUBSCRIPTIONS: Once = Once::new();
+
+lazy_static::lazy_static! {
+    static ref SERVERS: Mutex<HashMap<ServerId, Arc<PrintServer>>> = Mutex::new(HashMap::new());
+}
+
+/// Where a server listens and how clients authenticate
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrintServerConfig {
+    pub host: String,
+    /// Port to listen on (0 = any free port)
+    pub port: u16,
+    /// Bearer token clients must send (None = no authentication)
+    pub auth_token: Option<String>,
+    pub max_body_bytes: usize,
+    /// Serve the `/events` WebSocket stream
+    pub events: bool,
+}
+
+impl Default for PrintServerConfig {
+    fn default() -> Self {
+        PrintServerConfig {
+            host: DEFAULT_HOST.to_string(),
+            port: DEFAULT_PORT,
+            auth_token: None,
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+            events: false,
+        }
+    }
+}
+
+/// A running print server
+#[derive(Debug)]
+pub struct PrintServer {
+    pub id: ServerId,
+    pub config: PrintServerConfig,
+    /// Address the server is listening on, with the port chosen if 0 was
+    /// requested
+    pub local_addr: SocketAddr,
+    stopped: AtomicBool,
+    /// Connected event stream clients
+    clients: Mutex<Vec<EventClient>>,
+}
+
+impl PrintServer {
+    /// Base URL of the API
+    pub fn url(&self) -> String {
+        format!("http://{}", self.local_addr)
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.stopped.load(Ordering::Relaxed)
+    }
+
+    /// Number of connected event stream clients
+    pub fn event_clients(&self) -> usize {
+        self.clients.lock().unwrap().len()
+    }
+
+    /// Whether the request carries the configured token, if there is one
+    fn authorized(&self, request: &HttpRequest) -> bool {
+        let Some(token) = &self.config.auth_token else {
+            return true;
+        };
+        let given = match request.header("authorization") {
+            Some(value) => value.strip_prefix("Bearer ").map(|t| t.trim().to_string()),
+            None if request.path() == "/events" => request
+                .query()
+                .into_iter()
+                .find(|(name, _)| name == "token")
+                .map(|(_, token)| token),
+            None => None,
+        };
+        given.is_some_and(|given| constant_time_eq(given.as_bytes(), token.as_bytes()))
+    }
+
+    /// Queue an event frame for the clients that want it, disconnecting
+    /// clients that fell too far behind
+    fn broadcast(&self, event: &str, frame: &Arc<Vec<u8>>) {
+        self.clients.lock().unwrap().retain(|client| {
+            if client.closed.load(Ordering::Relaxed) {
+                return false;
+            }
+            if !client.wants(event) {
+                return true;
+            }
+            match client.sender.try_send(Outgoing::Frame(Arc::clone(frame))) {
+                Ok(()) => true,
+                Err(TrySendError::Full(_)) => {
+                    tracing::warn!(
+                        server = self.id,
+                        peer = %client.peer,
+                        "Disconnecting slow event client"
+                    );
+                    client.closed.store(true, Ordering::Relaxed);
+                    false
+                }
+                Err(TrySendError::Disconnected(_)) => false,
+            }
+        });
+    }
+}
+
+/// A message for an event stream's writer
+enum Outgoing {
+    Frame(Arc<Vec<u8>>),
+    /// Reply to the client's close frame, or close after a read error
+    Close,
+}
+
+/// A connected event stream
+struct EventClient {
+    peer: SocketAddr,
+    sender: SyncSender<Outgoing>,
+    /// Events the client asked for (empty = all)
+    events: Vec<String>,
+    /// Set to close the stream from outside the writer
+    closed: Arc<AtomicBool>,
+}
+
+impl std::fmt::Debug for EventClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventClient")
+            .field("peer", &self.peer)
+            .field("events", &self.events)
+            .finish()
+    }
+}
+
+impl EventClient {
+    fn wants(&self, event: &str) -> bool {
+        self.events.is_empty() || self.events.iter().any(|wanted| wanted == event)
+    }
+}
+
+/// Compare secrets without returning early at the first difference
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Start a server and register it
+pub fn start(config: PrintServerConfig) -> Result<Arc<PrintServer>, String> {
+    if config.auth_token.as_deref() == Some("") {
+        return Err("The auth token must not be empty".to_string());
+    }
+    let loopback = config.host == "localhost"
+        || config
+            .host
+            .parse::<IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback());
+    if !loopback && config.auth_token.is_none() {
+        return Err(format!(
+            "An auth token is required to listen on '{}'",
+            config.host
+        ));
+    }
+
+    let listener = TcpListener::bind((config.host.as_str(), config.port))
+        .and_then(|listener| listener.set_nonblocking(true).map(|_| listener))
+        .map_err(|e| format!("Failed to listen on {}:{}: {}", config.host, config.port, e))?;
+    let local_addr = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to get listening address: {}", e))?;
+
+    let server = Arc::new(PrintServer {
+        id: NEXT_SERVER_ID.fetch_add(1, Ordering::Relaxed),
+        config,
+        local_addr,
+        stopped: AtomicBool::new(false),
+        clients: Mutex::new(Vec::new()),
+    });
+    if server.config.events {
+        subscribe_to_events();
+    }
+    SERVERS
+        .lock()
+        .unwrap()
+        .insert(server.id, Arc::clone(&server));
+    tracing::info!(
+        server = server.id,
+        url = %server.url(),
+        authenticated = server.config.auth_token.is_some(),
+        "Print server started"
+    );
+
+    let accepting = Arc::clone(&server);
+    thread::spawn(move || accept_connections(listener, accepting));
+    Ok(server)
+}
+
+pub fn get(server_id: ServerId) -> Option<Arc<PrintServer>> {
+    SERVERS.lock().unwrap().get(&server_id).cloned()
+}
+
+/// All running servers, oldest first
+pub fn list() -> Vec<Arc<PrintServer>> {
+    let mut servers: Vec<Arc<PrintServer>> = SERVERS.lock().unwrap().values().cloned().collect();
+    servers.sort_by_key(|server| server.id);
+    servers
+}
+
+/// Stop a server and unregister it, returning whether it existed. Requests
+/// already being handled are completed.
+pub fn stop(server_id: ServerId) -> bool {
+    let Some(server) = SERVERS.lock().unwrap().remove(&server_id) else {
+        return false;
+    };
+    server.stopped.store(true, Ordering::Relaxed);
+    // Event streams close once they notice
+    server.clients.lock().unwrap().clear();
+    tracing::info!(server = server_id, "Print server stopped");
+    true
+}
+
+fn accept_connections(listener: TcpListener, server: Arc<PrintServer>) {
+    while server.is_running() {
+        match listener.accept() {
+            Ok((stream, peer)) => {
+                let server = Arc::clone(&server);
+                thread::spawn(move || handle_connection(&server, stream, peer));
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(ACCEPT_INTERVAL),
+            Err(e) => {
+                tracing::warn!(server = server.id, "Failed to accept connection: {}", e);
+                thread::sleep(ACCEPT_INTERVAL);
+            }
+        }
+    }
+}
+
+/// An HTTP status line and JSON body
+type ApiResponse = (&'static str, Value);
+
+fn error(status: &'static str, message: impl Into<String>) -> ApiResponse {
+    (status, json!({ "error": message.into() }))
+}
+
+/// Answer one request and close the connection
+fn handle_connection(server: &PrintServer, mut stream: TcpStream, peer: SocketAddr) {
+    if let Err(e) = http_server::prepare_stream(&stream) {
+        tracing::warn!(server = server.id, "Failed to configure connection: {}", e);
+        return;
+    }
+
+    // Unauthorized clients are turned away before their body is read
+    let mut incoming = Incoming::new(&mut stream);
+    let request = incoming.read_head().and_then(|mut request| {
+        if server.authorized(&request) {
+            incoming.read_body(&mut request, server.config.max_body_bytes)?;
+        }
+        Ok(request)
+    });
+    let mut headers = vec![("Content-Type", "application/json")];
+    let (status, body) = match request {
+        Ok(request) if !server.authorized(&request) => {
+            tracing::debug!(server = server.id, %peer, "Rejected unauthorized request");
+            headers.push(("WWW-Authenticate", "Bearer"));
+            error("401 Unauthorized", "Missing or invalid auth token")
+        }
+        Ok(request) if server.config.events && request.path() == "/events" => {
+            match event_filter(&request) {
+                Err(e) => error("400 Bad Request", e),
+                Ok(_) if request.method != "GET" => error(
+                    "405 Method Not Allowed",
+                    format!("{} is not allowed here", request.method),
+                ),
+                Ok(events) => match request.header("sec-websocket-key") {
+                    Some(key) if is_websocket_upgrade(&request) => {
+                        stream_events(server, stream, peer, key, events);
+                        return;
+                    }
+                    _ => {
+                        headers.push(("Upgrade", "websocket"));
+                        error("426 Upgrade Required", "Connect with a WebSocket client")
+                    }
+                },
+            }
+        }
+        Ok(request) => {
+            let response = route(&request);
+            tracing::debug!(
+                server = server.id,
+                %peer,
+                method = %request.method,
+                path = %request.path(),
+                status = response.0,
+                "Handled request"
+            );
+            response
+        }
+        Err(RequestError::TooLarge) => error("413 Payload Too Large", "Request is too large"),
+        Err(RequestError::Malformed(e)) => error("400 Bad Request", e),
+        Err(RequestError::Io(e)) => {
+            tracing::debug!(server = server.id, %peer, "Failed to read request: {}", e);
+            return;
+        }
+    };
+
+    let response = http_server::response(status, &headers, body.to_string().as_bytes());
+    if let Err(e) = stream.write_all(&response) {
+        tracing::debug!(server = server.id, %peer, "Failed to send response: {}", e);
+    }
+}
+
+// ===== EVENT STREAMS =====
+
+fn is_websocket_upgrade(request: &HttpRequest) -> bool {
+    request
+        .header("upgrade")
+        .is_some_and(|upgrade| upgrade.eq_ignore_ascii_case("websocket"))
+}
+
+/// Events asked for by the `events` query parameter
+fn event_filter(request: &HttpRequest) -> Result<Vec<String>, String> {
+    let mut events = Vec::new();
+    for (_, value) in request
+        .query()
+        .into_iter()
+        .filter(|(name, _)| name == "events")
+    {
+        for event in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            if !EVENT_NAMES.contains(&event) {
+                return Err(format!("Unknown event '{}'", event));
+            }
+            events.push(event.to_string());
+        }
+    }
+    Ok(events)
+}
+
+/// Complete the WebSocket handshake and send events until the client
+/// disconnects or the server stops
+fn stream_events(
+    server: &PrintServer,
+    mut stream: TcpStream,
+    peer: SocketAddr,
+    key: &str,
+    events: Vec<String>,
+) {
+    let reader = stream.try_clone().and_then(|reader| {
+        // The reader waits for client frames for as long as the stream is
+        // open
+        reader.set_read_timeout(None)?;
+        stream.write_all(&websocket::handshake_response(key))?;
+        Ok(reader)
+    });
+    let reader = match reader {
+        Ok(reader) => reader,
+        Err(e) => {
+            tracing::debug!(server = server.id, %peer, "Failed to open event stream: {}", e);
+            return;
+        }
+    };
+
+    let (sender, receiver) = mpsc::sync_channel(CLIENT_QUEUE_CAPACITY);
+    let closed = Arc::new(AtomicBool::new(false));
+    server.clients.lock().unwrap().push(EventClient {
+        peer,
+        sender: sender.clone(),
+        events,
+        closed: Arc::clone(&closed),
+    });
+    tracing::debug!(server = server.id, %peer, "Event stream opened");
+
+    thread::spawn(move || read_client_frames(reader, sender));
+    write_events(server, &mut stream, &receiver, &closed);
+    let _ = stream.shutdown(Shutdown::Both);
+    closed.store(true, Ordering::Relaxed);
+    tracing::debug!(server = server.id, %peer, "Event stream closed");
+}
+
+/// Answer pings and notice the client closing the stream
+fn read_client_frames(mut reader: TcpStream, sender: SyncSender<Outgoing>) {
+    loop {
+        match websocket::read_frame(&mut reader) {
+            Ok(frame) if frame.opcode == websocket::OPCODE_PING => {
+                let pong = websocket::frame(websocket::OPCODE_PONG, &frame.payload);
+                let _ = sender.try_send(Outgoing::Frame(Arc::new(pong)));
+            }
+            Ok(frame) if frame.opcode != websocket::OPCODE_CLOSE => {}
+            _ => {
+                // Closing or gone; the writer may be waiting on a full queue,
+                // so block until it takes the close
+                let _ = sender.send(Outgoing::Close);
+                return;
+            }
+        }
+    }
+}
+
+fn write_events(
+    server: &PrintServer,
+    stream: &mut TcpStream,
+    receiver: &Receiver<Outgoing>,
+    closed: &AtomicBool,
+) {
+    // Status 1000, normal closure
+    let close = websocket::frame(websocket::OPCODE_CLOSE, &1000u16.to_be_bytes());
+    let mut last_sent = Instant::now();
+    loop {
+        if !server.is_running() || closed.load(Ordering::Relaxed) {
+            let _ = stream.write_all(&close);
+            return;
+        }
+        let frame = match receiver.recv_timeout(STREAM_CHECK_INTERVAL) {
+            Ok(Outgoing::Frame(frame)) => frame,
+            Ok(Outgoing::Close) => {
+                let _ = stream.write_all(&close);
+                return;
+            }
+            Err(RecvTimeoutError::Timeout) if last_sent.elapsed() >= PING_INTERVAL => {
+                Arc::new(websocket::frame(websocket::OPCODE_PING, b""))
+            }
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => return,
+        };
+        if stream.write_all(&frame).is_err() {
+            return;
+        }
+        last_sent = Instant::now();
+    }
+}
+
+/// Send an event to the event streams of every running server
+fn broadcast(event: &str, mut message: Value) {
+    let servers: Vec<Arc<PrintServer>> = list()
+        .into_iter()
+        .filter(|server| server.config.events)
+        .collect();
+    if servers.is_empty() {
+        return;
+    }
+    message["event"] = json!(event);
+    message["timestamp"] = json!(unix_secs(SystemTime::now()));
+    let frame = Arc::new(websocket::frame(
+        websocket::OPCODE_TEXT,
+        message.to_string().as_bytes(),
+    ));
+    for server in servers {
+        server.broadcast(event, &frame);
+    }
+}
+
+fn broadcast_job_event(event: WebhookEvent, job: &PrinterJob) {
+    broadcast(event.as_str(), json!({ "job": export::job_json(job) }));
+}
+
+fn broadcast_printer_event(event: PrinterStateEvent) {
+    let (name, message) = match event {
+        PrinterStateEvent::Connected { name } => {
+            ("printer.connected", json!({ "printerName": name }))
+        }
+        PrinterStateEvent::Disconnected { name } => {
+            ("printer.disconnected", json!({ "printerName": name }))
+        }
+        PrinterStateEvent::StateChanged {
+            name,
+            old_state,
+            new_state,
+        } => (
+            "printer.state_changed",
+            json!({ "printerName": name, "oldState": old_state, "newState": new_state }),
+        ),
+        PrinterStateEvent::StateReasonsChanged {
+            name,
+            old_reasons,
+            new_reasons,
+        } => (
+            "printer.state_reasons_changed",
+            json!({ "printerName": name, "oldReasons": old_reasons, "newReasons": new_reasons }),
+        ),
+    };
+    broadcast(name, message);
+}
+
+/// Forward job and printer state events to event streams. Subscriptions
+/// last for the life of the process; they cost nothing while no server
+/// streams events.
+fn subscribe_to_events() {
+    EVENT_SUBSCRIPTIONS.call_once(|| {
+        PrinterCore::subscribe_to_job_events(Arc::new(broadcast_job_event));
+        if let Err(e) = PrinterCore::subscribe_to_state_changes(broadcast_printer_event) {
+            tracing::warn!("Printer state events are unavailable: {}", e);
+        }
+    });
+}
+
+// ===== ROUTES =====
+
+fn route(request: &HttpRequest) -> ApiResponse {
+    let segments: Vec<String> = request
+        .path()
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| crate::encoding::percent_decode(segment, false))
+        .collect();
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+    let method = request.method.as_str();
+
+    match (method, segments.as_slice()) {
+        ("GET", ["health"]) => health(),
+        ("GET", ["printers"]) => {
+            let printers: Vec<Value> = PrinterCore::get_all_printers()
+                .iter()
+                .map(PrinterCore::printer_info_value)
+                .collect();
+            ("200 OK", Value::Array(printers))
+        }
+        ("GET", ["printers", name]) => match PrinterCore::find_printer_by_name(name) {
+            Some(printer) => ("200 OK", PrinterCore::printer_info_value(&printer)),
+            None => error("404 Not Found", format!("Printer '{}' not found", name)),
+        },
+        ("POST", ["printers", name, "jobs"]) => print(name, request),
+        ("DELETE", ["printers", name, "jobs"]) => {
+            let cancelled = PrinterCore::cancel_all_jobs(Some(*name));
+            ("200 OK", json!({ "cancelled": cancelled }))
+        }
+        ("GET", ["jobs"]) => match job_query(request) {
+            Ok(query) => {
+                let page = PrinterCore::query_jobs(&query);
+                let jobs: Vec<Value> = page.jobs.iter().map(export::job_json).collect();
+                ("200 OK", json!({ "jobs": jobs, "total": page.total }))
+            }
+            Err(e) => error("400 Bad Request", e),
+        },
+        (_, ["jobs", id, ..]) if id.parse::<JobId>().is_err() => {
+            error("404 Not Found", format!("Job '{}' not found", id))
+        }
+        ("GET", ["jobs", id]) => match PrinterCore::get_job_status(id.parse().unwrap()) {
+            Some(job) => ("200 OK", export::job_json(&job)),
+            None => error("404 Not Found", format!("Job {} not found", id)),
+        },
+        ("POST", ["jobs", id, action]) => job_action(id.parse().unwrap(), action, request),
+        (_, ["health"] | ["printers", ..] | ["jobs", ..]) => error(
+            "405 Method Not Allowed",
+            format!("{} is not allowed here", method),
+        ),
+        _ => error("404 Not Found", "No such endpoint"),
+    }
+}
+
+/// Body of `GET /health`
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Health {
+    healthy: bool,
+    accepting_jobs: bool,
+    spooler_reachable: bool,
+    spooler_error: Option<String>,
+    active_workers: usize,
+    jobs: JobStateCounts,
+}
+
+fn health() -> ApiResponse {
+    let report = PrinterCore::health_check(None);
+    let status = if report.healthy {
+        "200 OK"
+    } else {
+        "503 Service Unavailable"
+    };
+    let body = Health {
+        healthy: report.healthy,
+        accepting_jobs: report.accepting_jobs,
+        spooler_reachable: report.spooler_reachable,
+        spooler_error: report.spooler_error,
+        active_workers: report.active_workers,
+        jobs: report.jobs,
+    };
+    (status, serde_json::to_value(body).unwrap_or_default())
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Status of a failed job operation
+fn print_error(e: PrintError) -> ApiResponse {
+    match e {
+        PrintError::PrinterNotFound => error("404 Not Found", "Printer not found"),
+        PrintError::JobNotFound => error("404 Not Found", "Job not found"),
+        PrintError::InvalidParams | PrintError::InvalidFilePath => {
+            error("400 Bad Request", "Invalid request")
+        }
+        PrintError::InvalidDocument => {
+            error("422 Unprocessable Entity", "Document failed validation")
+        }
+        PrintError::DuplicateJob => error("409 Conflict", "Duplicate job rejected"),
+        PrintError::UnsupportedOption(reason) => error("422 Unprocessable Entity", reason),
+        PrintError::PolicyViolation(violation) => error("403 Forbidden", violation.to_string()),
+        PrintError::QueueFull => error("429 Too Many Requests", "The print queue is full"),
+        PrintError::SpoolQuotaExceeded => error("507 Insufficient Storage", "Spool quota exceeded"),
+        PrintError::ShuttingDown => error("503 Service Unavailable", "Library is shutting down"),
+        PrintError::NoPrinterAvailable => {
+            error("503 Service Unavailable", "No printer is available")
+        }
+        other => error(
+            "500 Internal Server Error",
+            format!("Request failed with error code: {}", other.as_i32()),
+        ),
+    }
+}
+
+fn print(printer_name: &str, request: &HttpRequest) -> ApiResponse {
+    if request.body.is_empty() {
+        return error("400 Bad Request", "The request has no document");
+    }
+    let properties: HashMap<String, String> = request.query().into_iter().collect();
+    let options = PrinterJobOptions::from_map(properties);
+    match PrinterCore::print_bytes(printer_name, &request.body, Some(options)) {
+        Ok(job_id) => match PrinterCore::get_job_status(job_id) {
+            Some(job) => ("201 Created", export::job_json(&job)),
+            None => ("201 Created", json!({ "id": job_id })),
+        },
+        Err(e) => print_error(e),
+    }
+}
+
+/// Build a job query from query parameters
+fn job_query(request: &HttpRequest) -> Result<JobQuery, String> {
+    let mut query = JobQuery::default();
+    let number = |name: &str, value: &str| {
+        value
+            .parse::<u64>()
+            .map_err(|_| format!("Invalid {} '{}'", name, value))
+    };
+    let time = |secs: u64| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+    for (name, value) in request.query() {
+        match name.as_str() {
+            "printerName" => query.printer_name = Some(crate::aliases::resolve(&value)),
+            "state" => {
+                for state in value.split(',') {
+                    let state = PrinterJobState::parse(state)
+                        .ok_or_else(|| format!("Invalid state '{}'", state))?;
+                    query.states.push(state);
+                }
+            }
+            "since" => query.since = Some(time(number(&name, &value)?)),
+            "until" => query.until = Some(time(number(&name, &value)?)),
+            "sort" => {
+                query.sort =
+                    JobSort::parse(&value).ok_or_else(|| format!("Invalid sort '{}'", value))?
+            }
+            "offset" => query.offset = number(&name, &value)? as usize,
+            "limit" => query.limit = Some(number(&name, &value)? as usize),
+            _ => return Err(format!("Unknown query parameter '{}'", name)),
+        }
+    }
+    Ok(query)
+}
+
+fn job_action(job_id: JobId, action: &str, request: &HttpRequest) -> ApiResponse {
+    let result = match action {
+        "reprint" => PrinterCore::reprint_job(job_id).map(|id| json!({ "id": id })),
+        "approve" => PrinterCore::approve_job(job_id).map(|_| json!({ "id": job_id })),
+        "reject" => {
+            let reason = request
+                .query()
+                .into_iter()
+                .find(|(name, _)| name == "reason")
+                .map(|(_, reason)| reason);
+            PrinterCore::reject_job(job_id, reason.as_deref()).map(|_| json!({ "id": job_id }))
+        }
+        _ => return error("404 Not Found", "No such endpoint"),
+    };
+    match result {
+        Ok(body) => ("200 OK", body),
+        Err(PrintError::InvalidParams) if action == "reprint" => {
+            error("409 Conflict", format!("Job {} has not finished", job_id))
+        }
+        Err(PrintError::InvalidParams) => error(
+            "409 Conflict",
+            format!("Job {} is not held for approval", job_id),
+        ),
+        Err(e) => print_error(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ShutdownMode;
+    use serial_test::serial;
+    use std::io::Read;
+
+    /// Send a request and return its status code and JSON body
+    fn send(
+        server: &PrintServer,
+        method: &str,
+        target: &str,
+        token: Option<&str>,
+        body: &[u8],
+    ) -> (u16, Value) {
+        let mut stream = TcpStream::connect(server.local_addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let auth = token
+            .map(|token| format!("Authorization: Bearer {}\r\n", token))
+            .unwrap_or_default();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: {}\r\n{}Content-Length: {}\r\n\r\n",
+            method,
+            target,
+            server.local_addr,
+            auth,
+            body.len()
+        )
+        .unwrap();
+        stream.write_all(body).unwrap();
+
+        let mut raw = String::new();
+        stream.read_to_string(&mut raw).unwrap();
+        let (head, body) = raw.split_once("\r\n\r\n").unwrap();
+        let status = head[9..12].parse().unwrap();
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    /// Send raw request bytes and return the status line of the response
+    fn send_raw(server: &PrintServer, request: &str) -> String {
+        let mut stream = TcpStream::connect(server.local_addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut raw = String::new();
+        stream.read_to_string(&mut raw).unwrap();
+        raw.lines().next().unwrap_or_default().to_string()
+    }
+
+    #[test]
+    #[serial]
+    fn test_print_server_endpoints() {
+        std::env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let server = start(PrintServerConfig {
+            port: 0,
+            auth_token: Some("secret".to_string()),
+            ..PrintServerConfig::default()
+        })
+        .unwrap();
+        let token = Some("secret");
+
+        assert_eq!(send(&server, "GET", "/printers", None, b"").0, 401);
+        assert_eq!(send(&server, "GET", "/printers", Some("wrong"), b"").0, 401);
+        // Unauthorized uploads are turned away without waiting for the body
+        assert_eq!(
+            send_raw(
+                &server,
+                "POST /printers/Simulated%20Printer/jobs HTTP/1.1\r\n\
+                 Content-Length: 1000000\r\n\r\n"
+            ),
+            "HTTP/1.1 401 Unauthorized"
+        );
+        // Chunk sizes that would overflow the body length are too large
+        assert_eq!(
+            send_raw(
+                &server,
+                "POST /printers/Simulated%20Printer/jobs HTTP/1.1\r\n\
+                 Authorization: Bearer secret\r\nTransfer-Encoding: chunked\r\n\r\n\
+                 1\r\na\r\nffffffffffffffff\r\n"
+            ),
+            "HTTP/1.1 413 Payload Too Large"
+        );
+
+        let (status, health) = send(&server, "GET", "/health", token, b"");
+        assert_eq!(status, 200);
+        assert_eq!(health["acceptingJobs"], true);
+        assert!(health["jobs"]["failed"].is_u64());
+
+        let (status, printers) = send(&server, "GET", "/printers", token, b"");
+        assert_eq!(status, 200);
+        assert_eq!(printers[0]["name"], "Simulated Printer");
+        let (status, printer) = send(&server, "GET", "/printers/Simulated%20Printer", token, b"");
+        assert_eq!(status, 200);
+        assert_eq!(printer["isDefault"], true);
+        assert_eq!(send(&server, "GET", "/printers/Missing", token, b"").0, 404);
+
+        let (status, job) = send(
+            &server,
+            "POST",
+            "/printers/Simulated%20Printer/jobs?job-name=REST%20job&copies=2",
+            token,
+            b"%!PS-Adobe-3.0",
+        );
+        assert_eq!(status, 201, "{}", job);
+        assert_eq!(job["name"], "REST job");
+        let job_id = job["id"].as_u64().unwrap();
+        assert_eq!(
+            PrinterCore::get_job_status(job_id)
+                .unwrap()
+                .job_options
+                .raw_properties["copies"],
+            "2"
+        );
+
+        let (status, fetched) = send(&server, "GET", &format!("/jobs/{}", job_id), token, b"");
+        assert_eq!(status, 200);
+        assert_eq!(fetched["printerName"], "Simulated Printer");
+        let (status, page) = send(
+            &server,
+            "GET",
+            "/jobs?printerName=Simulated+Printer&limit=1",
+            token,
+            b"",
+        );
+        assert_eq!(status, 200);
+        assert_eq!(page["jobs"].as_array().unwrap().len(), 1);
+        assert!(page["total"].as_u64().unwrap() >= 1);
+
+        assert_eq!(send(&server, "GET", "/jobs?state=done", token, b"").0, 400);
+        assert_eq!(send(&server, "GET", "/jobs/999999", token, b"").0, 404);
+        assert_eq!(
+            send(
+                &server,
+                "POST",
+                &format!("/jobs/{}/approve", job_id),
+                token,
+                b""
+            )
+            .0,
+            409
+        );
+        assert_eq!(send(&server, "PUT", "/printers", token, b"").0, 405);
+        assert_eq!(send(&server, "GET", "/nothing", token, b"").0, 404);
+
+        assert!(stop(server.id));
+        assert!(!stop(server.id));
+
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
+    }
+
+    /// Read one unmasked server frame
+    fn read_server_frame(stream: &mut TcpStream) -> (u8, Vec<u8>) {
+        let mut header = [0u8; 2];
+        stream.read_exact(&mut header).unwrap();
+        let len = match header[1] {
+            126 => {
+                let mut len = [0u8; 2];
+                stream.read_exact(&mut len).unwrap();
+                u16::from_be_bytes(len) as usize
+            }
+            len => len as usize,
+        };
+        let mut payload = vec![0u8; len];
+        stream.read_exact(&mut payload).unwrap();
+        (header[0] & 0x0F, payload)
+    }
+
+    #[test]
+    #[serial]
+    fn test_print_server_event_stream() {
+        std::env::set_var("PRINTERS_JS_SIMULATE", "true");
+        let server = start(PrintServerConfig {
+            port: 0,
+            auth_token: Some("secret".to_string()),
+            events: true,
+            ..PrintServerConfig::default()
+        })
+        .unwrap();
+        assert_eq!(
+            send(&server, "GET", "/events?token=secret", None, b"").0,
+            426
+        );
+        assert_eq!(
+            send(
+                &server,
+                "GET",
+                "/events?token=secret&events=nope",
+                None,
+                b""
+            )
+            .0,
+            400
+        );
+
+        let mut stream = TcpStream::connect(server.local_addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(10)))
+            .unwrap();
+        write!(
+            stream,
+            "GET /events?token=secret&events=job.submitted HTTP/1.1\r\nHost: {}\r\n\
+             Upgrade: websocket\r\nConnection: Upgrade\r\n\
+             Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n",
+            server.local_addr
+        )
+        .unwrap();
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            let mut byte = [0u8; 1];
+            stream.read_exact(&mut byte).unwrap();
+            head.push(byte[0]);
+        }
+        let head = String::from_utf8(head).unwrap();
+        assert!(head.starts_with("HTTP/1.1 101"), "{}", head);
+        assert!(head.contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo="));
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while server.event_clients() == 0 && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        let job_id = PrinterCore::print_bytes("Simulated Printer", b"streamed", None).unwrap();
+        let (opcode, payload) = read_server_frame(&mut stream);
+        assert_eq!(opcode, websocket::OPCODE_TEXT);
+        let message: Value = serde_json::from_slice(&payload).unwrap();
+        assert_eq!(message["event"], "job.submitted");
+        assert_eq!(message["job"]["id"], job_id);
+
+        // A masked close frame is answered with a close frame
+        stream.write_all(&[0x88, 0x80, 0, 0, 0, 0]).unwrap();
+        let (opcode, _) = loop {
+            let frame = read_server_frame(&mut stream);
+            if frame.0 != websocket::OPCODE_TEXT {
+                break frame;
+            }
+        };
+        assert_eq!(opcode, websocket::OPCODE_CLOSE);
+
+        assert!(stop(server.id));
+        PrinterCore::shutdown_library_with(ShutdownMode::Force, Duration::ZERO);
+        PrinterCore::cleanup_old_jobs(0);
+    }
+
+    #[test]
+    fn test_print_server_requires_token_off_loopback() {
+        let config = PrintServerConfig {
+            host: "0.0.0.0".to_string(),
+            port: 0,
+            ..PrintServerConfig::default()
+        };
+        assert!(start(config).unwrap_err().contains("auth token"));
+        assert!(constant_time_eq(b"token", b"token"));
+        assert!(!constant_time_eq(b"token", b"tokens"));
+    }
+}
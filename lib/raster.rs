@@ -0,0 +1,832 @@
+//! PWG and Apple raster for driverless printers
+//!
+//! IPP Everywhere and AirPrint printers need no driver, but many of them
+//! only take raster pages: PWG Raster (`image/pwg-raster`, PWG 5102.4) or
+//! Apple Raster (`image/urf`). A spooler converts documents for them; when
+//! printing to one directly over IPP, this module does. Images are scaled
+//! to fit the printer's default media at a resolution it lists; PDF and
+//! PostScript documents are rasterized with Ghostscript (the `ghostscript`
+//! feature).
+//!
+//! Both formats carry 8-bit sGray or sRGB pages whose lines are compressed
+//! the same way: a count of identical lines, then runs of a repeated pixel
+//! and of literal pixels.
+
+use crate::ipp::{self, IppValue, TAG_PRINTER_ATTRIBUTES};
+
+/// Printer attributes describing the raster a printer takes
+pub const SUPPORT_ATTRIBUTES: &[&str] = &[
+    "document-format-supported",
+    "pwg-raster-document-resolution-supported",
+    "pwg-raster-document-type-supported",
+    "urf-supported",
+    "media-default",
+];
+
+/// Resolution used when a printer doesn't list any, and preferred among
+/// those it lists, in dots per inch
+const PREFERRED_RESOLUTION: u32 = 300;
+
+/// Media pages are sized for when a printer doesn't report its default
+const DEFAULT_MEDIA: &str = "na_letter_8.5x11in";
+
+/// Images beyond this many pixels are rejected rather than decoded
+#[cfg(feature = "compression")]
+const MAX_IMAGE_PIXELS: u64 = 64 * 1024 * 1024;
+
+/// Size of a PWG Raster page header
+const PWG_HEADER_LEN: usize = 1796;
+
+/// A raster format of driverless printers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RasterType {
+    /// PWG Raster, for IPP Everywhere printers
+    Pwg,
+    /// Apple Raster, for AirPrint printers
+    Urf,
+}
+
+impl RasterType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RasterType::Pwg => "pwg",
+            RasterType::Urf => "urf",
+        }
+    }
+
+    pub fn media_type(&self) -> &'static str {
+        match self {
+            RasterType::Pwg => "image/pwg-raster",
+            RasterType::Urf => "image/urf",
+        }
+    }
+
+    pub fn from_media_type(media_type: &str) -> Option<RasterType> {
+        match media_type.trim().to_lowercase().as_str() {
+            "image/pwg-raster" => Some(RasterType::Pwg),
+            "image/urf" => Some(RasterType::Urf),
+            _ => None,
+        }
+    }
+}
+
+/// When documents sent to a printer are converted to raster
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RasterMode {
+    /// Send documents as they are
+    #[default]
+    Off,
+    /// Convert documents the printer doesn't list in
+    /// `document-format-supported` to a raster format it does
+    Auto,
+    /// Always convert to this format
+    Fixed(RasterType),
+}
+
+impl RasterMode {
+    /// Parse `"none"`, `"auto"`, `"pwg"` or `"urf"`
+    pub fn parse(mode: &str) -> Result<RasterMode, String> {
+        match mode.trim().to_lowercase().as_str() {
+            "none" => Ok(RasterMode::Off),
+            "auto" => Ok(RasterMode::Auto),
+            "pwg" => Ok(RasterMode::Fixed(RasterType::Pwg)),
+            "urf" => Ok(RasterMode::Fixed(RasterType::Urf)),
+            _ => Err(format!(
+                "Unknown raster mode '{}', expected none, auto, pwg or urf",
+                mode
+            )),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RasterMode::Off => "none",
+            RasterMode::Auto => "auto",
+            RasterMode::Fixed(raster_type) => raster_type.as_str(),
+        }
+    }
+}
+
+/// Color space of a bitmap, 8 bits per color
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorSpace {
+    Gray,
+    Rgb,
+}
+
+impl ColorSpace {
+    fn bytes_per_pixel(&self) -> usize {
+        match self {
+            ColorSpace::Gray => 1,
+            ColorSpace::Rgb => 3,
+        }
+    }
+}
+
+/// A page of pixels, rows top to bottom
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bitmap {
+    pub width: u32,
+    pub height: u32,
+    pub color: ColorSpace,
+    pub pixels: Vec<u8>,
+}
+
+impl Bitmap {
+    pub fn white(width: u32, height: u32, color: ColorSpace) -> Bitmap {
+        Bitmap {
+            width,
+            height,
+            color,
+            pixels: vec![0xff; width as usize * height as usize * color.bytes_per_pixel()],
+        }
+    }
+
+    fn row_bytes(&self) -> usize {
+        self.width as usize * self.color.bytes_per_pixel()
+    }
+
+    fn row(&self, y: u32) -> &[u8] {
+        let row_bytes = self.row_bytes();
+        &self.pixels[y as usize * row_bytes..][..row_bytes]
+    }
+
+    /// Scale to fit a page of `width` by `height` pixels, keeping the
+    /// aspect ratio, centered on white
+    pub fn fit_to_page(&self, width: u32, height: u32) -> Bitmap {
+        let mut page = Bitmap::white(width, height, self.color);
+        if self.width == 0 || self.height == 0 {
+            return page;
+        }
+        let scale = (width as f64 / self.width as f64).min(height as f64 / self.height as f64);
+        let scaled_width = ((self.width as f64 * scale) as u32).clamp(1, width);
+        let scaled_height = ((self.height as f64 * scale) as u32).clamp(1, height);
+        let left = (width - scaled_width) / 2;
+        let top = (height - scaled_height) / 2;
+        let bpp = self.color.bytes_per_pixel();
+        for y in 0..scaled_height {
+            let source = self.row((y as u64 * self.height as u64 / scaled_height as u64) as u32);
+            let start = ((top + y) as usize * width as usize + left as usize) * bpp;
+            let target = &mut page.pixels[start..][..scaled_width as usize * bpp];
+            for (x, pixel) in target.chunks_mut(bpp).enumerate() {
+                let source_x = x as u64 * self.width as u64 / scaled_width as u64;
+                pixel.copy_from_slice(&source[source_x as usize * bpp..][..bpp]);
+            }
+        }
+        page
+    }
+
+    /// The bitmap in gray, by luma
+    pub fn to_gray(&self) -> Bitmap {
+        if self.color == ColorSpace::Gray {
+            return self.clone();
+        }
+        Bitmap {
+            width: self.width,
+            height: self.height,
+            color: ColorSpace::Gray,
+            pixels: self
+                .pixels
+                .chunks(3)
+                .map(|rgb| {
+                    ((rgb[0] as u32 * 299 + rgb[1] as u32 * 587 + rgb[2] as u32 * 114) / 1000) as u8
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Append a line of pixels: runs of a repeated pixel as their count minus
+/// one and the pixel, other pixels as 257 minus their count and the pixels
+fn encode_line(line: &[u8], bpp: usize, out: &mut Vec<u8>) {
+    let pixels: Vec<&[u8]> = line.chunks(bpp).collect();
+    let mut i = 0;
+    while i < pixels.len() {
+        let mut run = 1;
+        while i + run < pixels.len() && run < 128 && pixels[i + run] == pixels[i] {
+            run += 1;
+        }
+        if run > 1 || i + 1 == pixels.len() {
+            out.push((run - 1) as u8);
+            out.extend_from_slice(pixels[i]);
+            i += run;
+            continue;
+        }
+        // Literal pixels up to the next repeated one
+        let start = i;
+        while i < pixels.len()
+            && i - start < 128
+            && (i + 1 == pixels.len() || pixels[i + 1] != pixels[i])
+        {
+            i += 1;
+        }
+        // A single pixel is written as a run of one
+        let count = i - start;
+        out.push(if count == 1 { 0 } else { (257 - count) as u8 });
+        for pixel in &pixels[start..i] {
+            out.extend_from_slice(pixel);
+        }
+    }
+}
+
+/// Append a page's lines, each preceded by how many times it repeats
+fn encode_page(page: &Bitmap, out: &mut Vec<u8>) {
+    let mut y = 0;
+    while y < page.height {
+        let line = page.row(y);
+        let mut repeat = 1;
+        while y + repeat < page.height && repeat < 256 && page.row(y + repeat) == line {
+            repeat += 1;
+        }
+        out.push((repeat - 1) as u8);
+        encode_line(line, page.color.bytes_per_pixel(), out);
+        y += repeat;
+    }
+}
+
+fn put_u32(header: &mut [u8], offset: usize, value: u32) {
+    header[offset..offset + 4].copy_from_slice(&value.to_be_bytes());
+}
+
+/// PWG Raster page header: a CUPS `cups_page_header2_t` with the fields
+/// PWG 5102.4 uses
+fn pwg_header(page: &Bitmap, resolution: u32, pages: u32) -> Vec<u8> {
+    let mut header = vec![0u8; PWG_HEADER_LEN];
+    header[..9].copy_from_slice(b"PwgRaster");
+    let (bits_per_pixel, color_space, colors) = match page.color {
+        ColorSpace::Gray => (8, 18, 1),
+        ColorSpace::Rgb => (24, 19, 3),
+    };
+    put_u32(&mut header, 276, resolution); // HWResolution
+    put_u32(&mut header, 280, resolution);
+    put_u32(&mut header, 340, 1); // NumCopies
+    put_u32(&mut header, 352, page.width * 72 / resolution); // PageSize in points
+    put_u32(&mut header, 356, page.height * 72 / resolution);
+    put_u32(&mut header, 372, page.width);
+    put_u32(&mut header, 376, page.height);
+    put_u32(&mut header, 384, 8); // BitsPerColor
+    put_u32(&mut header, 388, bits_per_pixel);
+    put_u32(&mut header, 392, page.row_bytes() as u32);
+    put_u32(&mut header, 400, color_space); // sGray or sRGB
+    put_u32(&mut header, 420, colors);
+    put_u32(&mut header, 452, pages); // TotalPageCount
+    put_u32(&mut header, 456, 1); // CrossFeedTransform
+    put_u32(&mut header, 460, 1); // FeedTransform
+    header
+}
+
+/// Apple Raster page header
+fn urf_header(page: &Bitmap, resolution: u32) -> [u8; 32] {
+    let mut header = [0u8; 32];
+    header[0] = (page.color.bytes_per_pixel() * 8) as u8;
+    header[1] = match page.color {
+        ColorSpace::Gray => 0,
+        ColorSpace::Rgb => 1,
+    };
+    header[2] = 1; // Simplex
+    header[3] = 4; // Normal quality
+    put_u32(&mut header, 12, page.width);
+    put_u32(&mut header, 16, page.height);
+    put_u32(&mut header, 20, resolution);
+    header
+}
+
+/// Encode pages at `resolution` dots per inch
+pub fn encode(raster_type: RasterType, pages: &[Bitmap], resolution: u32) -> Vec<u8> {
+    let mut out = Vec::new();
+    match raster_type {
+        RasterType::Pwg => out.extend_from_slice(b"RaS2"),
+        RasterType::Urf => {
+            out.extend_from_slice(b"UNIRAST\0");
+            out.extend_from_slice(&(pages.len() as u32).to_be_bytes());
+        }
+    }
+    for page in pages {
+        match raster_type {
+            RasterType::Pwg => {
+                out.extend_from_slice(&pwg_header(page, resolution, pages.len() as u32))
+            }
+            RasterType::Urf => out.extend_from_slice(&urf_header(page, resolution)),
+        }
+        encode_page(page, &mut out);
+    }
+    out
+}
+
+/// Width and height in points of a PWG self-describing media name, e.g.
+/// `na_letter_8.5x11in` or `iso_a4_210x297mm`
+pub fn media_size(name: &str) -> Option<(f64, f64)> {
+    let size = name.rsplit('_').next()?;
+    let (size, points_per_unit) = if let Some(size) = size.strip_suffix("in") {
+        (size, 72.0)
+    } else {
+        (size.strip_suffix("mm")?, 72.0 / 25.4)
+    };
+    let (width, height) = size.split_once('x')?;
+    Some((
+        width.parse::<f64>().ok()? * points_per_unit,
+        height.parse::<f64>().ok()? * points_per_unit,
+    ))
+}
+
+/// The raster a printer takes, from its attributes
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RasterSupport {
+    pub document_formats: Vec<String>,
+    /// Resolutions listed for PWG Raster, in dots per inch
+    pub pwg_resolutions: Vec<u32>,
+    /// PWG Raster color spaces, e.g. `sgray_8` and `srgb_8`
+    pub pwg_types: Vec<String>,
+    /// Apple Raster capabilities, e.g. `W8`, `SRGB24` and `RS300-600`
+    pub urf_supported: Vec<String>,
+    pub media_default: Option<String>,
+}
+
+impl RasterSupport {
+    pub fn from_ipp_response(response: &ipp::IppResponse) -> RasterSupport {
+        let values = |name| -> Vec<&IppValue> {
+            response
+                .attribute(TAG_PRINTER_ATTRIBUTES, name)
+                .map(|attribute| attribute.values.iter().collect())
+                .unwrap_or_default()
+        };
+        let strings = |name| -> Vec<String> {
+            values(name)
+                .into_iter()
+                .filter_map(IppValue::as_str)
+                .map(str::to_string)
+                .collect()
+        };
+        RasterSupport {
+            document_formats: strings("document-format-supported"),
+            pwg_resolutions: values("pwg-raster-document-resolution-supported")
+                .into_iter()
+                .filter_map(|value| match value {
+                    IppValue::Resolution {
+                        cross_feed,
+                        units: 3,
+                        ..
+                    } => Some(*cross_feed as u32),
+                    // Dots per centimeter
+                    IppValue::Resolution {
+                        cross_feed,
+                        units: 4,
+                        ..
+                    } => Some((*cross_feed as f64 * 2.54).round() as u32),
+                    _ => None,
+                })
+                .collect(),
+            pwg_types: strings("pwg-raster-document-type-supported"),
+            urf_supported: strings("urf-supported"),
+            media_default: strings("media-default").into_iter().next(),
+        }
+    }
+
+    /// Whether the printer takes documents of a media type as they are
+    pub fn accepts(&self, media_type: &str) -> bool {
+        self.document_formats
+            .iter()
+            .any(|format| format.eq_ignore_ascii_case(media_type.trim()))
+    }
+
+    /// Raster formats the printer lists, PWG Raster first
+    pub fn raster_types(&self) -> Vec<RasterType> {
+        [RasterType::Pwg, RasterType::Urf]
+            .into_iter()
+            .filter(|raster_type| self.accepts(raster_type.media_type()))
+            .collect()
+    }
+
+    /// Resolution to send a raster format at: the listed one closest to
+    /// `PREFERRED_RESOLUTION`
+    pub fn resolution(&self, raster_type: RasterType) -> u32 {
+        let listed: Vec<u32> = match raster_type {
+            RasterType::Pwg => self.pwg_resolutions.clone(),
+            RasterType::Urf => self
+                .urf_supported
+                .iter()
+                .filter_map(|value| value.strip_prefix("RS"))
+                .flat_map(|resolutions| resolutions.split('-'))
+                .filter_map(|resolution| resolution.parse().ok())
+                .collect(),
+        };
+        listed
+            .into_iter()
+            .filter(|resolution| *resolution > 0)
+            .min_by_key(|resolution| resolution.abs_diff(PREFERRED_RESOLUTION))
+            .unwrap_or(PREFERRED_RESOLUTION)
+    }
+
+    /// Whether the printer takes color pages in a raster format
+    pub fn color(&self, raster_type: RasterType) -> bool {
+        match raster_type {
+            RasterType::Pwg => self.pwg_types.iter().any(|t| t == "srgb_8"),
+            RasterType::Urf => self.urf_supported.iter().any(|u| u == "SRGB24"),
+        }
+    }
+
+    /// Page size in points, from the default media
+    pub fn page_size(&self) -> (f64, f64) {
+        self.media_default
+            .as_deref()
+            .and_then(media_size)
+            .or_else(|| media_size(DEFAULT_MEDIA))
+            .unwrap_or((612.0, 792.0))
+    }
+
+    /// Raster format to convert a document to in `mode`, if any
+    pub fn target(&self, mode: RasterMode, media_type: &str) -> Option<RasterType> {
+        if RasterType::from_media_type(media_type).is_some() {
+            return None;
+        }
+        match mode {
+            RasterMode::Off => None,
+            RasterMode::Fixed(raster_type) => Some(raster_type),
+            RasterMode::Auto if self.accepts(media_type) || !can_convert(media_type) => None,
+            RasterMode::Auto => self.raster_types().first().copied(),
+        }
+    }
+}
+
+/// Whether this build can convert documents of a media type to raster
+pub fn can_convert(media_type: &str) -> bool {
+    let pdf = matches!(media_type, "application/pdf" | "application/postscript");
+    (media_type == "image/png" && cfg!(feature = "compression"))
+        || (pdf && cfg!(feature = "ghostscript"))
+}
+
+/// Convert a PNG image, or a PDF or PostScript document, to raster pages
+/// for a printer
+pub fn convert(
+    data: &[u8],
+    media_type: &str,
+    raster_type: RasterType,
+    support: &RasterSupport,
+) -> Result<Vec<u8>, String> {
+    let resolution = support.resolution(raster_type);
+    let color = support.color(raster_type);
+    match media_type {
+        "image/png" => {
+            let (width, height) = support.page_size();
+            let to_pixels = |points: f64| (points * resolution as f64 / 72.0).round() as u32;
+            let page = decode_png(data)?.fit_to_page(to_pixels(width), to_pixels(height));
+            let page = if color { page } else { page.to_gray() };
+            Ok(encode(raster_type, &[page], resolution))
+        }
+        "application/pdf" | "application/postscript" => {
+            rasterize_document(data, raster_type, resolution, color)
+        }
+        _ => Err(format!(
+            "Can't convert {} documents to {}",
+            media_type,
+            raster_type.media_type()
+        )),
+    }
+}
+
+#[cfg(feature = "ghostscript")]
+fn rasterize_document(
+    data: &[u8],
+    raster_type: RasterType,
+    resolution: u32,
+    color: bool,
+) -> Result<Vec<u8>, String> {
+    use crate::ghostscript::{RasterFormat, RasterOptions};
+
+    let mut options = RasterOptions::new(match raster_type {
+        RasterType::Pwg => RasterFormat::PwgRaster,
+        RasterType::Urf => RasterFormat::AppleRaster,
+    });
+    options.resolution = Some(resolution);
+    options.color = color;
+    crate::core::PrinterCore::rasterize_document(data, &options, |_| {})
+}
+
+#[cfg(not(feature = "ghostscript"))]
+fn rasterize_document(
+    _data: &[u8],
+    raster_type: RasterType,
+    _resolution: u32,
+    _color: bool,
+) -> Result<Vec<u8>, String> {
+    Err(format!(
+        "Converting PDF and PostScript to {} requires the ghostscript feature",
+        raster_type.media_type()
+    ))
+}
+
+/// Predict a byte of a filtered PNG row (PNG filter type 4)
+#[cfg(feature = "compression")]
+fn paeth(left: u8, up: u8, up_left: u8) -> u8 {
+    let p = left as i16 + up as i16 - up_left as i16;
+    let (pa, pb, pc) = (
+        (p - left as i16).abs(),
+        (p - up as i16).abs(),
+        (p - up_left as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        left
+    } else if pb <= pc {
+        up
+    } else {
+        up_left
+    }
+}
+
+/// Decode a non-interlaced PNG image with up to 8 bits per sample.
+/// Transparency is composited over white.
+#[cfg(feature = "compression")]
+pub fn decode_png(data: &[u8]) -> Result<Bitmap, String> {
+    use std::io::Read;
+
+    if !data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return Err("Not a PNG image".to_string());
+    }
+    let mut header = None;
+    let mut palette: &[u8] = &[];
+    let mut compressed = Vec::new();
+    let mut pos = 8;
+    while pos + 8 <= data.len() {
+        let length = u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]);
+        let body = data
+            .get(pos + 8..pos + 8 + length as usize)
+            .ok_or_else(|| "Truncated PNG image".to_string())?;
+        match &data[pos + 4..pos + 8] {
+            b"IHDR" if body.len() >= 13 => header = Some(body),
+            b"PLTE" => palette = body,
+            b"IDAT" => compressed.extend_from_slice(body),
+            b"IEND" => break,
+            _ => {}
+        }
+        pos += 12 + length as usize;
+    }
+    let header = header.ok_or_else(|| "PNG image has no header".to_string())?;
+    let width = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+    let height = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+    let (depth, color_type) = (header[8], header[9]);
+    if width == 0 || height == 0 || width as u64 * height as u64 > MAX_IMAGE_PIXELS {
+        return Err(format!("Unsupported PNG image size {}x{}", width, height));
+    }
+    if header[12] != 0 {
+        return Err("Interlaced PNG images are not supported".to_string());
+    }
+    let channels = match (color_type, depth) {
+        (0 | 3, 1 | 2 | 4 | 8) => 1,
+        (2, 8) => 3,
+        (4, 8) => 2,
+        (6, 8) => 4,
+        _ => {
+            return Err(format!(
+                "Unsupported PNG color type {} with {}-bit samples",
+                color_type, depth
+            ))
+        }
+    };
+
+    let bits_per_pixel = channels * depth as usize;
+    let stride = (width as usize * bits_per_pixel).div_ceil(8);
+    let expected = (stride + 1) * height as usize;
+    let mut filtered = Vec::with_capacity(expected);
+    flate2::read::ZlibDecoder::new(compressed.as_slice())
+        .take(expected as u64)
+        .read_to_end(&mut filtered)
+        .map_err(|e| format!("Failed to decompress PNG image: {}", e))?;
+    if filtered.len() < expected {
+        return Err("Truncated PNG image data".to_string());
+    }
+
+    // Undo each row's filter
+    let step = bits_per_pixel.div_ceil(8);
+    let mut samples = vec![0u8; stride * height as usize];
+    for y in 0..height as usize {
+        let filter = filtered[y * (stride + 1)];
+        let line = &filtered[y * (stride + 1) + 1..][..stride];
+        let (previous, current) = samples.split_at_mut(y * stride);
+        let up = if y > 0 {
+            &previous[(y - 1) * stride..]
+        } else {
+            &[][..]
+        };
+        let current = &mut current[..stride];
+        for x in 0..stride {
+            let left = if x >= step { current[x - step] } else { 0 };
+            let above = up.get(x).copied().unwrap_or(0);
+            let up_left = if x >= step {
+                up.get(x - step).copied().unwrap_or(0)
+            } else {
+                0
+            };
+            current[x] = line[x].wrapping_add(match filter {
+                0 => 0,
+                1 => left,
+                2 => above,
+                3 => ((left as u16 + above as u16) / 2) as u8,
+                4 => paeth(left, above, up_left),
+                _ => return Err(format!("Invalid PNG filter type {}", filter)),
+            });
+        }
+    }
+
+    let color = if color_type == 2 || color_type == 3 || color_type == 6 {
+        ColorSpace::Rgb
+    } else {
+        ColorSpace::Gray
+    };
+    let over_white = |value: u8, alpha: u8| {
+        ((value as u32 * alpha as u32 + 255 * (255 - alpha as u32)) / 255) as u8
+    };
+    let mut bitmap = Bitmap::white(width, height, color);
+    let mut out = 0;
+    for row in samples.chunks(stride) {
+        for x in 0..width as usize {
+            match color_type {
+                0 | 3 => {
+                    let bit = x * depth as usize;
+                    let max = (1u16 << depth) - 1;
+                    let value = (row[bit / 8] >> (8 - depth as usize - bit % 8)) as u16 & max;
+                    if color_type == 0 {
+                        bitmap.pixels[out] = (value * 255 / max) as u8;
+                        out += 1;
+                    } else {
+                        let rgb = palette
+                            .get(value as usize * 3..value as usize * 3 + 3)
+                            .ok_or_else(|| "PNG palette index out of range".to_string())?;
+                        bitmap.pixels[out..out + 3].copy_from_slice(rgb);
+                        out += 3;
+                    }
+                }
+                2 => {
+                    bitmap.pixels[out..out + 3].copy_from_slice(&row[x * 3..x * 3 + 3]);
+                    out += 3;
+                }
+                4 => {
+                    bitmap.pixels[out] = over_white(row[x * 2], row[x * 2 + 1]);
+                    out += 1;
+                }
+                _ => {
+                    let rgba = &row[x * 4..x * 4 + 4];
+                    for channel in 0..3 {
+                        bitmap.pixels[out + channel] = over_white(rgba[channel], rgba[3]);
+                    }
+                    out += 3;
+                }
+            }
+        }
+    }
+    Ok(bitmap)
+}
+
+#[cfg(not(feature = "compression"))]
+pub fn decode_png(_data: &[u8]) -> Result<Bitmap, String> {
+    Err("Decoding PNG images requires the compression feature".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Expand lines written by `encode_page`
+    fn decode_page(mut data: &[u8], width: usize, height: usize, bpp: usize) -> Vec<u8> {
+        let mut pixels = Vec::new();
+        while pixels.len() < width * height * bpp {
+            let repeat = data[0] as usize + 1;
+            data = &data[1..];
+            let mut line = Vec::new();
+            while line.len() < width * bpp {
+                let count = data[0] as usize;
+                data = &data[1..];
+                if count < 128 {
+                    for _ in 0..=count {
+                        line.extend_from_slice(&data[..bpp]);
+                    }
+                    data = &data[bpp..];
+                } else {
+                    let literal = (257 - count) * bpp;
+                    line.extend_from_slice(&data[..literal]);
+                    data = &data[literal..];
+                }
+            }
+            for _ in 0..repeat {
+                pixels.extend_from_slice(&line);
+            }
+        }
+        assert!(data.is_empty());
+        pixels
+    }
+
+    #[test]
+    fn test_encode_pages() {
+        let mut page = Bitmap::white(300, 4, ColorSpace::Rgb);
+        // Literal pixels, a long run and a single pixel on the second line
+        for (x, pixel) in page.pixels[900..1800].chunks_mut(3).enumerate() {
+            pixel.copy_from_slice(&[x as u8, (x / 7) as u8, 0]);
+        }
+        page.pixels[1797..1800].copy_from_slice(&[1, 2, 3]);
+
+        let pwg = encode(RasterType::Pwg, std::slice::from_ref(&page), 300);
+        assert_eq!(&pwg[..4], b"RaS2");
+        let header = &pwg[4..4 + PWG_HEADER_LEN];
+        assert_eq!(&header[..9], b"PwgRaster");
+        assert_eq!(&header[372..376], &300u32.to_be_bytes());
+        assert_eq!(&header[400..404], &19u32.to_be_bytes());
+        assert_eq!(
+            decode_page(&pwg[4 + PWG_HEADER_LEN..], 300, 4, 3),
+            page.pixels
+        );
+
+        let gray = page.to_gray();
+        let urf = encode(RasterType::Urf, &[gray.clone(), gray.clone()], 600);
+        assert_eq!(&urf[..12], b"UNIRAST\0\0\0\0\x02");
+        assert_eq!(&urf[12..16], &[8, 0, 1, 4]);
+        assert_eq!(&urf[32..36], &600u32.to_be_bytes());
+        let page_len = (urf.len() - 12) / 2;
+        assert_eq!(decode_page(&urf[44..12 + page_len], 300, 4, 1), gray.pixels);
+    }
+
+    #[test]
+    fn test_raster_support() {
+        assert_eq!(
+            media_size("iso_a4_210x297mm").map(|(w, _)| w.round()),
+            Some(595.0)
+        );
+        assert_eq!(media_size("na_letter_8.5x11in"), Some((612.0, 792.0)));
+        assert_eq!(media_size("custom"), None);
+
+        let support = RasterSupport {
+            document_formats: vec!["image/urf".to_string(), "image/jpeg".to_string()],
+            urf_supported: vec!["W8".to_string(), "RS600-1200".to_string()],
+            ..RasterSupport::default()
+        };
+        assert_eq!(support.resolution(RasterType::Urf), 600);
+        assert_eq!(support.resolution(RasterType::Pwg), PREFERRED_RESOLUTION);
+        assert!(!support.color(RasterType::Urf));
+        assert_eq!(
+            support.target(RasterMode::Auto, "image/png"),
+            cfg!(feature = "compression").then_some(RasterType::Urf)
+        );
+        assert_eq!(support.target(RasterMode::Auto, "text/plain"), None);
+        assert_eq!(support.target(RasterMode::Auto, "image/jpeg"), None);
+        assert_eq!(
+            support.target(RasterMode::Fixed(RasterType::Pwg), "image/jpeg"),
+            Some(RasterType::Pwg)
+        );
+        assert_eq!(support.target(RasterMode::Off, "application/pdf"), None);
+        assert!(RasterMode::parse("tiff").is_err());
+
+        let image = Bitmap {
+            width: 2,
+            height: 1,
+            color: ColorSpace::Gray,
+            pixels: vec![0, 0x80],
+        };
+        let fitted = image.fit_to_page(4, 4);
+        assert_eq!(fitted.pixels[4..8], [0, 0, 0x80, 0x80]);
+        assert_eq!(fitted.pixels[..4], [0xff; 4]);
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_decode_png() {
+        use std::io::Write;
+
+        fn chunk(kind: &[u8], body: &[u8]) -> Vec<u8> {
+            let mut chunk = (body.len() as u32).to_be_bytes().to_vec();
+            chunk.extend_from_slice(kind);
+            chunk.extend_from_slice(body);
+            // Decoding doesn't check CRCs
+            chunk.extend_from_slice(&[0; 4]);
+            chunk
+        }
+
+        // 2x2 RGBA: red and transparent, then two grays with the Sub filter
+        let rows = [
+            0u8, 255, 0, 0, 255, 0, 0, 0, 0, 1, 10, 10, 10, 255, 10, 10, 10, 0,
+        ];
+        let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::fast());
+        encoder.write_all(&rows).unwrap();
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png.extend(chunk(b"IHDR", &[0, 0, 0, 2, 0, 0, 0, 2, 8, 6, 0, 0, 0]));
+        png.extend(chunk(b"IDAT", &encoder.finish().unwrap()));
+        png.extend(chunk(b"IEND", &[]));
+
+        let bitmap = decode_png(&png).unwrap();
+        assert_eq!(bitmap.color, ColorSpace::Rgb);
+        assert_eq!(
+            bitmap.pixels,
+            [255, 0, 0, 255, 255, 255, 10, 10, 10, 20, 20, 20]
+        );
+        assert!(decode_png(&png[..40]).is_err());
+
+        let support = RasterSupport {
+            document_formats: vec!["image/pwg-raster".to_string()],
+            pwg_resolutions: vec![150],
+            media_default: Some("oe_photo-l_3.5x5in".to_string()),
+            ..RasterSupport::default()
+        };
+        let pwg = convert(&png, "image/png", RasterType::Pwg, &support).unwrap();
+        let header = &pwg[4..4 + PWG_HEADER_LEN];
+        // Not listed as color, so the image is sent in gray
+        assert_eq!(&header[372..380], &[0, 0, 2, 13, 0, 0, 2, 238]);
+        assert_eq!(&header[400..404], &18u32.to_be_bytes());
+    }
+}
@@ -0,0 +1,155 @@
+//! Job ID coordination between processes
+//!
+//! Job IDs are derived from the clock, so two processes on one machine that
+//! embed the library and submit jobs in the same second hand out the same
+//! IDs. With a job registry configured, processes instead reserve blocks of
+//! IDs from a shared file, under an exclusive lock on it, so their IDs never
+//! overlap. The registry remembers which process reserved each block, so a
+//! job ID seen in a shared audit log, webhook or spooler listing can be
+//! traced back to the process that submitted it.
+//!
+//! A process uses up its block before reserving another, however long that
+//! takes. Each block starts at the registry's high-water mark, or the clock's
+//! ID floor if that is ahead, so IDs decode to about when their block was
+//! reserved. The oldest blocks are forgotten once there are `MAX_BLOCKS`.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::Path;
+
+/// IDs reserved at a time
+pub const BLOCK_SIZE: u64 = 256;
+
+/// Blocks remembered for attribution, oldest dropped first
+const MAX_BLOCKS: usize = 4096;
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Registry {
+    next_job_id: u64,
+    blocks: Vec<Block>,
+}
+
+/// IDs reserved by one process
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Block {
+    pub pid: u32,
+    pub start: u64,
+    pub end: u64,
+}
+
+fn read(file: &mut File, path: &Path) -> Result<Registry, String> {
+    let mut json = String::new();
+    file.read_to_string(&mut json)
+        .map_err(|e| format!("Failed to read job registry '{}': {}", path.display(), e))?;
+    if json.trim().is_empty() {
+        return Ok(Registry::default());
+    }
+    serde_json::from_str(&json)
+        .map_err(|e| format!("Job registry '{}' is corrupt: {}", path.display(), e))
+}
+
+/// Reserve the next `BLOCK_SIZE` IDs at or above `floor` for this process
+pub fn reserve(path: &Path, floor: u64) -> Result<Range<u64>, String> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .map_err(|e| format!("Failed to open job registry '{}': {}", path.display(), e))?;
+    // Released when the file is closed
+    file.lock()
+        .map_err(|e| format!("Failed to lock job registry '{}': {}", path.display(), e))?;
+
+    let mut registry = read(&mut file, path)?;
+    let start = registry.next_job_id.max(floor);
+    let end = start + BLOCK_SIZE;
+    registry.next_job_id = end;
+    registry.blocks.push(Block {
+        pid: std::process::id(),
+        start,
+        end,
+    });
+    let forgotten = registry.blocks.len().saturating_sub(MAX_BLOCKS);
+    registry.blocks.drain(..forgotten);
+
+    let json = serde_json::to_vec(&registry).map_err(|e| e.to_string())?;
+    file.set_len(0)
+        .and_then(|_| file.seek(SeekFrom::Start(0)))
+        .and_then(|_| file.write_all(&json))
+        .and_then(|_| file.sync_data())
+        .map_err(|e| format!("Failed to write job registry '{}': {}", path.display(), e))?;
+    Ok(start..end)
+}
+
+/// Process that reserved the block holding `job_id`, if the registry still
+/// remembers it
+pub fn owner(path: &Path, job_id: u64) -> Result<Option<u32>, String> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(format!(
+                "Failed to open job registry '{}': {}",
+                path.display(),
+                e
+            ))
+        }
+    };
+    file.lock_shared()
+        .map_err(|e| format!("Failed to lock job registry '{}': {}", path.display(), e))?;
+    let registry = read(&mut file, path)?;
+    Ok(registry
+        .blocks
+        .iter()
+        .rev()
+        .find(|block| (block.start..block.end).contains(&job_id))
+        .map(|block| block.pid))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reserve_blocks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("jobs.registry");
+        assert_eq!(owner(&path, 1).unwrap(), None);
+
+        let first = reserve(&path, 1000).unwrap();
+        assert_eq!(first, 1000..1000 + BLOCK_SIZE);
+        // Later blocks never overlap earlier ones, whatever the floor
+        let second = reserve(&path, 0).unwrap();
+        assert_eq!(second.start, first.end);
+        let third = reserve(&path, 10_000).unwrap();
+        assert_eq!(third.start, 10_000);
+
+        assert_eq!(owner(&path, 1001).unwrap(), Some(std::process::id()));
+        assert_eq!(owner(&path, 999).unwrap(), None);
+        assert_eq!(owner(&path, second.end).unwrap(), None);
+
+        std::fs::write(&path, "not json").unwrap();
+        assert!(reserve(&path, 0).is_err());
+    }
+
+    #[test]
+    fn test_concurrent_reservations() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("jobs.registry");
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let path = path.clone();
+                std::thread::spawn(move || reserve(&path, 0).unwrap())
+            })
+            .collect();
+        let mut blocks: Vec<Range<u64>> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        blocks.sort_by_key(|block| block.start);
+        for pair in blocks.windows(2) {
+            assert_eq!(pair[0].end, pair[1].start);
+        }
+    }
+}
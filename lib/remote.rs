@@ -0,0 +1,614 @@
+//! Remote printers
+//!
+//! A remote printer is a local printer name whose jobs are forwarded to
+//! another instance, so an application can print through a central print
+//! server as if the printer were attached locally. The remote end is either
+//! a REST print server (`http://host:port`, see `print_server`) or an IPP
+//! printer URI (`ipp://host:port/path`), such as an instance's IPP server or
+//! a CUPS queue. `https://` and `ipps://` are used over TLS (see `tls`).
+//!
+//! Jobs are queued and tracked locally like any other job. Once a worker
+//! picks one up, the document is submitted to the remote end and the remote
+//! job is polled until it finishes, and its outcome becomes the local job's.
+//! Library options (timeouts, dedupe, approval) apply locally; raw job
+//! properties, the job name, requesting user and metadata are forwarded.
+//! Over IPP, properties become job template attributes: integers, ranges
+//! (`1-3`) and booleans are typed, other values are keywords, and commas
+//! separate multiple values. Documents sent over IPP can be compressed
+//! with a coding the printer lists in `compression-supported` (see
+//! `compression`), and converted to PWG or Apple raster for driverless
+//! printers that don't take them as they are (see `raster`).
+
+use crate::compression::{self, Compression, CompressionMode, MIN_AUTO_BYTES};
+use crate::core::PrinterJobOptions;
+use crate::device::DeviceAddress;
+use crate::ipp::{
+    self, IppAttribute, IppRequest, IppValue, OP_PRINT_JOB, TAG_JOB_ATTRIBUTES,
+    TAG_OPERATION_ATTRIBUTES, TAG_PRINTER_ATTRIBUTES,
+};
+use crate::raster::{self, RasterMode, RasterSupport};
+use printers::common::base::printer::{Printer, PrinterState};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// How often remote jobs are polled, unless configured otherwise
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Timeout of each request to the remote end, unless configured otherwise
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+lazy_static::lazy_static! {
+    static ref REMOTES: RwLock<HashMap<String, Arc<RemotePrinter>>> = RwLock::new(HashMap::new());
+    /// Coding chosen for each IPP printer URI in `CompressionMode::Auto`
+    static ref NEGOTIATED: RwLock<HashMap<String, Compression>> = RwLock::new(HashMap::new());
+    /// Raster each IPP printer URI takes, for raster modes other than off
+    static ref RASTER_SUPPORT: RwLock<HashMap<String, Arc<RasterSupport>>> =
+        RwLock::new(HashMap::new());
+}
+
+/// A local printer name forwarding jobs to another instance
+#[derive(Clone, Debug, PartialEq)]
+pub struct RemotePrinter {
+    /// Local name jobs are submitted to
+    pub name: String,
+    /// `http://` print server base URL or `ipp://` printer URI
+    pub url: String,
+    /// Printer on the remote print server (None = the local name). Not used
+    /// over IPP, where the URI names the printer.
+    pub printer: Option<String>,
+    /// Bearer token for the remote print server
+    pub auth_token: Option<String>,
+    pub poll_interval: Duration,
+    pub timeout: Duration,
+    /// How documents are compressed. Only used over IPP.
+    pub compression: CompressionMode,
+    /// When documents are converted to raster. Only used over IPP.
+    pub raster: RasterMode,
+}
+
+/// State of a job on the remote end
+#[derive(Clone, Debug, PartialEq)]
+pub enum RemoteJobState {
+    /// Waiting on the remote end
+    Queued,
+    Processing,
+    Completed,
+    /// Failed or cancelled, with the reason
+    Failed(String),
+}
+
+impl RemotePrinter {
+    pub fn new(name: &str, url: &str) -> Self {
+        RemotePrinter {
+            name: name.to_string(),
+            url: url.to_string(),
+            printer: None,
+            auth_token: None,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            timeout: DEFAULT_TIMEOUT,
+            compression: CompressionMode::Off,
+            raster: RasterMode::Off,
+        }
+    }
+
+    fn is_ipp(&self) -> bool {
+        self.url.starts_with("ipp://") || self.url.starts_with("ipps://")
+    }
+
+    fn remote_printer(&self) -> &str {
+        self.printer.as_deref().unwrap_or(&self.name)
+    }
+
+    /// Printer info reported for the remote printer locally
+    pub fn printer_info(&self) -> Printer {
+        Printer {
+            name: self.name.clone(),
+            system_name: self.name.clone(),
+            driver_name: "Remote Printer".to_string(),
+            uri: self.url.clone(),
+            location: String::new(),
+            description: format!("{} on {}", self.remote_printer(), self.url),
+            port_name: String::new(),
+            processor: String::new(),
+            data_type: "RAW".to_string(),
+            is_shared: false,
+            is_default: false,
+            state: PrinterState::READY,
+            state_reasons: Vec::new(),
+        }
+    }
+
+    /// Submit a document, returning the remote job ID
+    pub fn submit(
+        &self,
+        job_name: &str,
+        data: &[u8],
+        options: &PrinterJobOptions,
+    ) -> Result<u64, String> {
+        if self.is_ipp() {
+            return self.submit_ipp(job_name, data, options);
+        }
+
+        let mut properties = options.raw_properties.clone();
+        properties.insert("job-name".to_string(), job_name.to_string());
+        if let Some(user) = &options.requesting_user {
+            properties.insert("requesting-user-name".to_string(), user.clone());
+        }
+        if !options.metadata.is_empty() {
+            properties.insert(
+                "job-metadata".to_string(),
+                serde_json::to_string(&options.metadata).unwrap_or_default(),
+            );
+        }
+        let mut properties: Vec<(String, String)> = properties.into_iter().collect();
+        properties.sort();
+        let query: Vec<String> = properties
+            .iter()
+            .map(|(name, value)| format!("{}={}", encode_component(name), encode_component(value)))
+            .collect();
+        let target = format!(
+            "/printers/{}/jobs?{}",
+            encode_component(self.remote_printer()),
+            query.join("&")
+        );
+
+        let job = self.request("POST", &target, data)?;
+        job["id"]
+            .as_u64()
+            .ok_or_else(|| "Remote print server returned no job ID".to_string())
+    }
+
+    /// Current state of a remote job
+    pub fn job_state(&self, remote_job_id: u64) -> Result<RemoteJobState, String> {
+        if self.is_ipp() {
+            return self.ipp_job_state(remote_job_id);
+        }
+
+        let job = self.request("GET", &format!("/jobs/{}", remote_job_id), b"")?;
+        let error = || {
+            job["errorMessage"]
+                .as_str()
+                .unwrap_or("Cancelled on the remote print server")
+                .to_string()
+        };
+        match job["state"].as_str() {
+            Some("pending" | "paused" | "held-for-approval" | "unknown") => {
+                Ok(RemoteJobState::Queued)
+            }
+            Some("processing") => Ok(RemoteJobState::Processing),
+            Some("completed") => Ok(RemoteJobState::Completed),
+            Some("cancelled" | "failed") => Ok(RemoteJobState::Failed(error())),
+            other => Err(format!("Unexpected remote job state {:?}", other)),
+        }
+    }
+
+    /// Send a request to the remote print server and parse its JSON response
+    fn request(&self, method: &str, target: &str, body: &[u8]) -> Result<Value, String> {
+        let address = DeviceAddress::parse(&self.url)
+            .ok_or_else(|| format!("Invalid remote URL '{}'", self.url))?;
+        let mut stream = address.open(self.timeout)?;
+
+        let auth = self
+            .auth_token
+            .as_ref()
+            .map(|token| format!("Authorization: Bearer {}\r\n", token))
+            .unwrap_or_default();
+        let header = format!(
+            "{} {}{} HTTP/1.1\r\nHost: {}\r\n{}Content-Type: application/octet-stream\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n",
+            method,
+            address.path.trim_end_matches('/'),
+            target,
+            address.authority(),
+            auth,
+            body.len()
+        );
+        stream
+            .write_all(header.as_bytes())
+            .and_then(|_| stream.write_all(body))
+            .map_err(|e| format!("Failed to send request to {}: {}", self.url, e))?;
+
+        let raw = crate::device::read_to_close(&mut *stream)
+            .map_err(|e| format!("Failed to read response from {}: {}", self.url, e))?;
+        let header_end = raw
+            .windows(4)
+            .position(|w| w == b"\r\n\r\n")
+            .ok_or_else(|| "Malformed HTTP response".to_string())?;
+        let headers = String::from_utf8_lossy(&raw[..header_end]).to_lowercase();
+        let status = headers
+            .split_whitespace()
+            .nth(1)
+            .and_then(|code| code.parse::<u16>().ok())
+            .ok_or_else(|| "Malformed HTTP status line".to_string())?;
+        let body = if headers.contains("transfer-encoding: chunked") {
+            ipp::decode_chunked(&raw[header_end + 4..])?
+        } else {
+            raw[header_end + 4..].to_vec()
+        };
+        let body: Value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+
+        if !(200..300).contains(&status) {
+            return Err(format!(
+                "Remote print server returned {}: {}",
+                status,
+                body["error"].as_str().unwrap_or("no details")
+            ));
+        }
+        Ok(body)
+    }
+
+    fn submit_ipp(
+        &self,
+        job_name: &str,
+        data: &[u8],
+        options: &PrinterJobOptions,
+    ) -> Result<u64, String> {
+        let mut request = IppRequest::new(OP_PRINT_JOB, &self.url);
+        let user = options
+            .requesting_user
+            .clone()
+            .or_else(crate::spooler::current_user)
+            .unwrap_or_else(|| "anonymous".to_string());
+        request.add_attribute(
+            TAG_OPERATION_ATTRIBUTES,
+            IppAttribute::new("requesting-user-name", IppValue::Name(user)),
+        );
+        request.add_attribute(
+            TAG_OPERATION_ATTRIBUTES,
+            IppAttribute::new("job-name", IppValue::Name(job_name.to_string())),
+        );
+        let format = options
+            .raw_properties
+            .get("document-format")
+            .map_or("application/octet-stream", String::as_str);
+        let (data, format) = self.rasterize(data, format)?;
+        let compression = self.compression_for(data.len());
+        if compression != Compression::None {
+            request.add_attribute(
+                TAG_OPERATION_ATTRIBUTES,
+                IppAttribute::new(
+                    "compression",
+                    IppValue::Keyword(compression.keyword().to_string()),
+                ),
+            );
+        }
+        request.add_attribute(
+            TAG_OPERATION_ATTRIBUTES,
+            IppAttribute::new("document-format", IppValue::MimeMediaType(format)),
+        );
+
+        let mut properties: Vec<(&String, &String)> = options
+            .raw_properties
+            .iter()
+            .filter(|(name, _)| name.as_str() != "document-format")
+            .collect();
+        properties.sort();
+        for (name, value) in properties {
+            request.add_attribute(
+                TAG_JOB_ATTRIBUTES,
+                IppAttribute {
+                    name: name.clone(),
+                    values: value
+                        .split(',')
+                        .map(|value| ipp_value(value.trim()))
+                        .collect(),
+                },
+            );
+        }
+        request.data = compression::compress(&data, compression)?;
+        if compression != Compression::None {
+            tracing::debug!(
+                url = %self.url,
+                %compression,
+                size_bytes = data.len(),
+                compressed_bytes = request.data.len(),
+                "Compressed document for remote printer"
+            );
+        }
+
+        let response = ipp::send_request(&self.url, &request, self.timeout)?;
+        if !response.is_success() {
+            return Err(format!(
+                "Print-Job failed with status 0x{:04x}{}",
+                response.status_code,
+                status_message(&response)
+            ));
+        }
+        response
+            .attribute(TAG_JOB_ATTRIBUTES, "job-id")
+            .and_then(IppAttribute::value)
+            .and_then(IppValue::as_i32)
+            .map(|id| id as u64)
+            .ok_or_else(|| "Print-Job response has no job-id".to_string())
+    }
+
+    /// Convert a document to raster if the raster mode calls for it,
+    /// returning the data and document format to send
+    fn rasterize<'a>(
+        &self,
+        data: &'a [u8],
+        format: &str,
+    ) -> Result<(Cow<'a, [u8]>, String), String> {
+        if self.raster == RasterMode::Off {
+            return Ok((Cow::Borrowed(data), format.to_string()));
+        }
+        // Jobs without a document-format are sent as octet streams
+        let media_type = match format {
+            "application/octet-stream" => crate::document::sniff_media_type(data).unwrap_or(format),
+            format => format,
+        };
+        let support = self.raster_support();
+        let Some(raster_type) = support.target(self.raster, media_type) else {
+            return Ok((Cow::Borrowed(data), format.to_string()));
+        };
+        let converted = raster::convert(data, media_type, raster_type, &support)?;
+        tracing::debug!(
+            url = %self.url,
+            from = media_type,
+            to = raster_type.media_type(),
+            size_bytes = data.len(),
+            raster_bytes = converted.len(),
+            "Converted document to raster for remote printer"
+        );
+        Ok((Cow::Owned(converted), raster_type.media_type().to_string()))
+    }
+
+    /// Raster the printer takes, queried once per URI
+    fn raster_support(&self) -> Arc<RasterSupport> {
+        if let Some(support) = RASTER_SUPPORT.read().unwrap().get(&self.url) {
+            return support.clone();
+        }
+        match ipp::get_printer_attributes(&self.url, raster::SUPPORT_ATTRIBUTES, self.timeout) {
+            Ok(response) => {
+                let support = Arc::new(RasterSupport::from_ipp_response(&response));
+                RASTER_SUPPORT
+                    .write()
+                    .unwrap()
+                    .insert(self.url.clone(), support.clone());
+                support
+            }
+            Err(e) => {
+                // Ask again with the next job
+                tracing::debug!(url = %self.url, "Raster support query failed: {}", e);
+                Arc::new(RasterSupport::default())
+            }
+        }
+    }
+
+    /// Coding for a document of `size` bytes sent over IPP
+    fn compression_for(&self, size: usize) -> Compression {
+        match self.compression {
+            CompressionMode::Off => Compression::None,
+            CompressionMode::Fixed(coding) => coding,
+            CompressionMode::Auto if size < MIN_AUTO_BYTES => Compression::None,
+            CompressionMode::Auto => {
+                if let Some(coding) = NEGOTIATED.read().unwrap().get(&self.url) {
+                    return *coding;
+                }
+                let response = match ipp::get_printer_attributes(
+                    &self.url,
+                    &["compression-supported"],
+                    self.timeout,
+                ) {
+                    Ok(response) => response,
+                    Err(e) => {
+                        // Ask again with the next job
+                        tracing::debug!(url = %self.url, "Sending document uncompressed: {}", e);
+                        return Compression::None;
+                    }
+                };
+                let coding = Compression::negotiate(
+                    response
+                        .attribute(TAG_PRINTER_ATTRIBUTES, "compression-supported")
+                        .into_iter()
+                        .flat_map(|attribute| attribute.values.iter())
+                        .filter_map(IppValue::as_str),
+                );
+                NEGOTIATED.write().unwrap().insert(self.url.clone(), coding);
+                coding
+            }
+        }
+    }
+
+    fn ipp_job_state(&self, remote_job_id: u64) -> Result<RemoteJobState, String> {
+        let response = ipp::get_job_attributes(
+            &self.url,
+            remote_job_id as i32,
+            &["job-state", "job-state-message"],
+            self.timeout,
+        )?;
+        let attribute = |name| {
+            response
+                .attribute(TAG_JOB_ATTRIBUTES, name)
+                .and_then(IppAttribute::value)
+        };
+        let message = attribute("job-state-message")
+            .and_then(IppValue::as_str)
+            .map(str::to_string);
+        match attribute("job-state").and_then(IppValue::as_i32) {
+            Some(3 | 4 | 6) => Ok(RemoteJobState::Queued),
+            Some(5) => Ok(RemoteJobState::Processing),
+            Some(9) => Ok(RemoteJobState::Completed),
+            Some(7) => {
+                Ok(RemoteJobState::Failed(message.unwrap_or_else(|| {
+                    "Cancelled on the remote printer".to_string()
+                })))
+            }
+            Some(8) => {
+                Ok(RemoteJobState::Failed(message.unwrap_or_else(|| {
+                    "Aborted by the remote printer".to_string()
+                })))
+            }
+            other => Err(format!("Unexpected remote job-state {:?}", other)),
+        }
+    }
+}
+
+/// A raw job property value as a typed IPP value
+fn ipp_value(value: &str) -> IppValue {
+    if let Ok(integer) = value.parse::<i32>() {
+        return IppValue::Integer(integer);
+    }
+    if let Some((lower, upper)) = value.split_once('-') {
+        if let (Ok(lower), Ok(upper)) = (lower.parse(), upper.parse()) {
+            return IppValue::RangeOfInteger { lower, upper };
+        }
+    }
+    match value {
+        "true" => IppValue::Boolean(true),
+        "false" => IppValue::Boolean(false),
+        _ => IppValue::Keyword(value.to_string()),
+    }
+}
+
+fn status_message(response: &ipp::IppResponse) -> String {
+    response
+        .attribute(TAG_OPERATION_ATTRIBUTES, "status-message")
+        .and_then(IppAttribute::value)
+        .and_then(IppValue::as_str)
+        .map(|message| format!(": {}", message))
+        .unwrap_or_default()
+}
+
+/// Percent-encode a path segment or query component
+fn encode_component(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// Register a remote printer, replacing any existing one with the same name
+pub fn define(remote: RemotePrinter) -> Result<(), String> {
+    if remote.name.trim().is_empty() {
+        return Err("Remote printer name must not be empty".to_string());
+    }
+    let address = DeviceAddress::parse(&remote.url)
+        .ok_or_else(|| format!("Invalid remote URL '{}'", remote.url))?;
+    match address.scheme.as_str() {
+        "http" | "https" => {}
+        "ipp" | "ipps" if remote.auth_token.is_some() => {
+            return Err("An auth token is only supported for print server URLs".to_string())
+        }
+        "ipp" | "ipps" => {}
+        scheme => return Err(format!("Unsupported remote URL scheme '{}'", scheme)),
+    }
+    if remote.compression != CompressionMode::Off && !remote.is_ipp() {
+        return Err("Compression is only supported for IPP printer URIs".to_string());
+    }
+    if remote.raster != RasterMode::Off && !remote.is_ipp() {
+        return Err("Raster conversion is only supported for IPP printer URIs".to_string());
+    }
+    if address.is_secure() && !cfg!(feature = "tls") {
+        return Err(format!(
+            "Scheme '{}' requires TLS, which this build doesn't include (the `tls` feature)",
+            address.scheme
+        ));
+    }
+    if remote.poll_interval.is_zero() || remote.timeout.is_zero() {
+        return Err("Poll interval and timeout must be positive".to_string());
+    }
+
+    tracing::info!(name = %remote.name, url = %remote.url, "Remote printer defined");
+    // The printer behind the URI may have changed
+    NEGOTIATED.write().unwrap().remove(&remote.url);
+    RASTER_SUPPORT.write().unwrap().remove(&remote.url);
+    REMOTES
+        .write()
+        .unwrap()
+        .insert(remote.name.clone(), Arc::new(remote));
+    Ok(())
+}
+
+/// Unregister a remote printer, returning whether it existed. Jobs already
+/// forwarded are still followed to completion.
+pub fn remove(name: &str) -> bool {
+    REMOTES.write().unwrap().remove(name).is_some()
+}
+
+pub fn get(name: &str) -> Option<Arc<RemotePrinter>> {
+    REMOTES.read().unwrap().get(name).cloned()
+}
+
+/// All remote printers, sorted by name
+pub fn list() -> Vec<Arc<RemotePrinter>> {
+    let mut remotes: Vec<Arc<RemotePrinter>> = REMOTES.read().unwrap().values().cloned().collect();
+    remotes.sort_by(|a, b| a.name.cmp(&b.name));
+    remotes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_definitions() {
+        assert!(define(RemotePrinter::new("", "http://127.0.0.1:8632")).is_err());
+        assert_eq!(
+            define(RemotePrinter::new("Label", "https://print.example")).is_ok(),
+            cfg!(feature = "tls")
+        );
+        remove("Label");
+        assert!(define(RemotePrinter::new("Label", "usb://printer")).is_err());
+        let mut ipp = RemotePrinter::new("Label", "ipp://127.0.0.1:8631/ipp/print");
+        ipp.auth_token = Some("secret".to_string());
+        assert!(define(ipp).is_err());
+        let mut compressed = RemotePrinter::new("Label", "http://127.0.0.1:8632");
+        compressed.compression = CompressionMode::Auto;
+        assert!(define(compressed).is_err());
+        let mut rasterized = RemotePrinter::new("Label", "http://127.0.0.1:8632");
+        rasterized.raster = RasterMode::Auto;
+        assert!(define(rasterized).is_err());
+
+        let mut ipp = RemotePrinter::new("Label", "ipp://127.0.0.1:8631/ipp/print");
+        ipp.compression = CompressionMode::Auto;
+        assert_eq!(ipp.compression_for(MIN_AUTO_BYTES - 1), Compression::None);
+        ipp.compression = CompressionMode::Fixed(Compression::Deflate);
+        assert_eq!(ipp.compression_for(10), Compression::Deflate);
+
+        define(RemotePrinter::new("Remote Test", "http://127.0.0.1:8632")).unwrap();
+        assert_eq!(get("Remote Test").unwrap().remote_printer(), "Remote Test");
+        assert!(list().iter().any(|remote| remote.name == "Remote Test"));
+        assert!(remove("Remote Test"));
+        assert!(!remove("Remote Test"));
+    }
+
+    #[test]
+    fn test_rasterize() {
+        let mut remote = RemotePrinter::new("Label", "ipp://127.0.0.1:1/ipp/print");
+        remote.timeout = Duration::from_millis(500);
+        let (data, format) = remote.rasterize(b"%PDF-1.7\n", "application/pdf").unwrap();
+        assert!(matches!(data, Cow::Borrowed(_)));
+        assert_eq!(format, "application/pdf");
+
+        // A printer that can't be queried lists no formats, so only a fixed
+        // raster format converts
+        remote.raster = RasterMode::Auto;
+        let (_, format) = remote.rasterize(b"%PDF-1.7\n", "application/pdf").unwrap();
+        assert_eq!(format, "application/pdf");
+        remote.raster = RasterMode::Fixed(raster::RasterType::Urf);
+        assert!(remote.rasterize(b"plain text", "text/plain").is_err());
+    }
+
+    #[test]
+    fn test_ipp_values() {
+        assert_eq!(ipp_value("2"), IppValue::Integer(2));
+        assert_eq!(
+            ipp_value("1-3"),
+            IppValue::RangeOfInteger { lower: 1, upper: 3 }
+        );
+        assert_eq!(ipp_value("true"), IppValue::Boolean(true));
+        assert_eq!(
+            ipp_value("two-sided-long-edge"),
+            IppValue::Keyword("two-sided-long-edge".to_string())
+        );
+        assert_eq!(encode_component("Label Printer/2"), "Label%20Printer%2F2");
+    }
+}
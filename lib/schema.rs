@@ -0,0 +1,185 @@
+//! JSON Schemas of serialized printers and jobs
+//!
+//! Printers and jobs are serialized the same way everywhere: by
+//! `printer_info_to_json` and `printer_job_to_json`, in webhooks, print
+//! server responses and JSONL exports. The schemas are derived from the
+//! same structs and their serde attributes, so they describe what is
+//! actually written, for consumers in other languages and for checking the
+//! TypeScript interfaces against. `task schema` writes them to `schema/`,
+//! and `task schema:check` fails when the committed schemas or the
+//! TypeScript interfaces no longer match.
+
+use crate::core::{PrinterInfo, PrinterJob};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Schemas of the serialized types, by type name
+pub fn schemas() -> Vec<(&'static str, Value)> {
+    vec![
+        (
+            "PrinterInfo",
+            serde_json::to_value(schemars::schema_for!(PrinterInfo)).unwrap_or_default(),
+        ),
+        (
+            "PrinterJob",
+            serde_json::to_value(schemars::schema_for!(PrinterJob)).unwrap_or_default(),
+        ),
+    ]
+}
+
+/// Write each schema to `<dir>/<name>.schema.json`, returning the paths
+/// written
+pub fn write_schemas(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create '{}': {}", dir.display(), e))?;
+    schemas()
+        .into_iter()
+        .map(|(name, schema)| {
+            let path = dir.join(format!("{}.schema.json", name));
+            let mut json = serde_json::to_string_pretty(&schema)
+                .map_err(|e| format!("Failed to serialize the {} schema: {}", name, e))?;
+            json.push('\n');
+            fs::write(&path, json)
+                .map_err(|e| format!("Failed to write '{}': {}", path.display(), e))?;
+            Ok(path)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{printer_info_to_json, printer_job_to_json, PrinterJobState};
+    use std::collections::BTreeSet;
+    use std::time::SystemTime;
+
+    fn property_names(schema: &Value) -> BTreeSet<String> {
+        schema["properties"]
+            .as_object()
+            .map(|properties| properties.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn field_names(json: &str) -> BTreeSet<String> {
+        let value: Value = serde_json::from_str(json).unwrap();
+        value.as_object().unwrap().keys().cloned().collect()
+    }
+
+    #[test]
+    fn test_schemas_match_serialization() {
+        let schemas: std::collections::HashMap<_, _> = schemas().into_iter().collect();
+
+        let mut job = PrinterJob::new(
+            1,
+            "Invoice".to_string(),
+            "application/pdf".to_string(),
+            "Simulated Printer".to_string(),
+        );
+        job.transition_to(PrinterJobState::PROCESSING).unwrap();
+        let job_schema = &schemas["PrinterJob"];
+        assert_eq!(
+            property_names(job_schema),
+            field_names(&printer_job_to_json(&job).unwrap())
+        );
+        assert_eq!(job_schema["properties"]["createdAt"]["type"], "integer");
+        assert!(job_schema["required"]
+            .as_array()
+            .unwrap()
+            .contains(&Value::from("printerName")));
+
+        let info = PrinterInfo {
+            name: "Front Desk".to_string(),
+            printer_id: "0123456789abcdef".to_string(),
+            system_name: "front_desk".to_string(),
+            driver_name: String::new(),
+            uri: "ipp://10.0.0.2/ipp/print".to_string(),
+            port_name: String::new(),
+            processor: String::new(),
+            data_type: String::new(),
+            description: String::new(),
+            location: String::new(),
+            is_default: false,
+            is_shared: false,
+            state: "offline".to_string(),
+            state_reasons: Vec::new(),
+            normalized_state_reasons: Vec::new(),
+            offline_since: Some(SystemTime::now()),
+        };
+        assert_eq!(
+            property_names(&schemas["PrinterInfo"]),
+            field_names(&printer_info_to_json(&info).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_write_schemas() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = write_schemas(dir.path()).unwrap();
+        assert_eq!(paths.len(), schemas().len());
+        let written: Value = serde_json::from_str(&fs::read_to_string(&paths[1]).unwrap()).unwrap();
+        assert_eq!(written["title"], "PrinterJob");
+    }
+
+    fn manifest_path(path: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join(path)
+    }
+
+    /// Field names of an interface in `src/index.ts`
+    fn interface_fields(source: &str, name: &str) -> BTreeSet<String> {
+        let start = source
+            .find(&format!("export interface {} {{", name))
+            .unwrap_or_else(|| panic!("interface {} not found", name));
+        let body = &source[start..];
+        body[..body.find("\n}").unwrap()]
+            .lines()
+            .skip(1)
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with("/*") && !line.starts_with('*'))
+            .map(|line| {
+                let end = line.find(['?', ':']).unwrap_or(line.len());
+                line[..end].to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_committed_schemas_are_current() {
+        for (name, schema) in schemas() {
+            let path = manifest_path(&format!("schema/{}.schema.json", name));
+            let committed: Value = fs::read_to_string(&path)
+                .ok()
+                .and_then(|json| serde_json::from_str(&json).ok())
+                .unwrap_or_default();
+            assert!(
+                committed == schema,
+                "{} is out of date, regenerate it with `task schema`",
+                path.display()
+            );
+        }
+    }
+
+    #[test]
+    fn test_typescript_interfaces_match_schemas() {
+        let source = fs::read_to_string(manifest_path("src/index.ts")).unwrap();
+        let schemas: std::collections::HashMap<_, _> = schemas().into_iter().collect();
+
+        // ageSeconds is computed when a job is handed to JavaScript
+        let mut job_fields = interface_fields(&source, "PrinterJob");
+        assert!(job_fields.remove("ageSeconds"));
+        assert_eq!(job_fields, property_names(&schemas["PrinterJob"]));
+        assert_eq!(
+            interface_fields(&source, "NativePrinter"),
+            property_names(&schemas["PrinterInfo"])
+        );
+    }
+
+    /// Regenerate `schema/` with `task schema`
+    #[test]
+    #[ignore]
+    fn generate_schemas() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("schema");
+        for path in write_schemas(&dir).unwrap() {
+            println!("Wrote {}", path.display());
+        }
+    }
+}
@@ -0,0 +1,968 @@
+//! Minimal SNMP client for the standard Printer MIB
+//!
+//! Implements SNMPv1/v2c GET and GETNEXT over UDP with just enough BER to
+//! read the Host Resources MIB (RFC 2790) and Printer MIB (RFC 3805) objects
+//! that describe device status, page counters, alerts and supplies.
+
+use crate::core::{PrinterSupply, StateReason};
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::time::Duration;
+
+// BER universal tags
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_NULL: u8 = 0x05;
+const TAG_OBJECT_ID: u8 = 0x06;
+const TAG_SEQUENCE: u8 = 0x30;
+
+// SNMP application tags
+const TAG_IP_ADDRESS: u8 = 0x40;
+const TAG_COUNTER32: u8 = 0x41;
+const TAG_GAUGE32: u8 = 0x42;
+const TAG_TIME_TICKS: u8 = 0x43;
+const TAG_COUNTER64: u8 = 0x46;
+
+// SNMPv2 exception values
+const TAG_NO_SUCH_OBJECT: u8 = 0x80;
+const TAG_NO_SUCH_INSTANCE: u8 = 0x81;
+const TAG_END_OF_MIB_VIEW: u8 = 0x82;
+
+// PDU types
+const PDU_GET_REQUEST: u8 = 0xA0;
+const PDU_GET_NEXT_REQUEST: u8 = 0xA1;
+const PDU_GET_RESPONSE: u8 = 0xA2;
+
+/// SNMPv1 error-status returned when an object doesn't exist
+const ERROR_NO_SUCH_NAME: i64 = 2;
+
+/// Upper bound on rows fetched by a single walk
+const MAX_WALK_ROWS: usize = 1024;
+
+// Host Resources MIB
+const OID_SYS_DESCR: &[u32] = &[1, 3, 6, 1, 2, 1, 1, 1, 0];
+const OID_HR_DEVICE_STATUS: &[u32] = &[1, 3, 6, 1, 2, 1, 25, 3, 2, 1, 5, 1];
+const OID_HR_PRINTER_STATUS: &[u32] = &[1, 3, 6, 1, 2, 1, 25, 3, 5, 1, 1, 1];
+const OID_HR_PRINTER_ERROR_STATE: &[u32] = &[1, 3, 6, 1, 2, 1, 25, 3, 5, 1, 2, 1];
+
+// Printer MIB
+const OID_PRT_MARKER_LIFE_COUNT: &[u32] = &[1, 3, 6, 1, 2, 1, 43, 10, 2, 1, 4, 1, 1];
+const OID_PRT_MARKER_SUPPLIES: &[u32] = &[1, 3, 6, 1, 2, 1, 43, 11, 1, 1];
+const OID_PRT_MARKER_COLORANT_VALUE: &[u32] = &[1, 3, 6, 1, 2, 1, 43, 12, 1, 1, 4];
+const OID_PRT_ALERT: &[u32] = &[1, 3, 6, 1, 2, 1, 43, 18, 1, 1];
+
+// Columns of prtMarkerSuppliesTable
+const SUPPLY_COLORANT_INDEX: u32 = 3;
+const SUPPLY_TYPE: u32 = 5;
+const SUPPLY_DESCRIPTION: u32 = 6;
+const SUPPLY_MAX_CAPACITY: u32 = 8;
+const SUPPLY_LEVEL: u32 = 9;
+
+// Columns of prtAlertTable
+const ALERT_SEVERITY_LEVEL: u32 = 2;
+const ALERT_GROUP: u32 = 4;
+const ALERT_CODE: u32 = 7;
+const ALERT_DESCRIPTION: u32 = 8;
+
+/// hrPrinterDetectedErrorState bits (MSB of the first octet is bit 0)
+const ERROR_STATE_BITS: &[(usize, StateReason)] = &[
+    (0, StateReason::MediaLow),
+    (1, StateReason::MediaEmpty),
+    (2, StateReason::TonerLow),
+    (3, StateReason::TonerEmpty),
+    (4, StateReason::CoverOpen),
+    (5, StateReason::MediaJam),
+    (6, StateReason::Offline),
+    (7, StateReason::UserIntervention),
+    (8, StateReason::InputTrayMissing),
+    (12, StateReason::OutputBinFull),
+    (13, StateReason::MediaEmpty),
+];
+
+static NEXT_REQUEST_ID: AtomicI32 = AtomicI32::new(1);
+
+/// An object identifier as a list of arcs
+pub type Oid = Vec<u32>;
+
+/// Parse a dotted OID string such as `1.3.6.1.2.1.1.1.0`
+pub fn parse_oid(text: &str) -> Option<Oid> {
+    let oid: Option<Oid> = text
+        .trim()
+        .trim_start_matches('.')
+        .split('.')
+        .map(|arc| arc.parse().ok())
+        .collect();
+    oid.filter(|arcs| arcs.len() >= 2)
+}
+
+/// Format an OID in dotted notation
+pub fn format_oid(oid: &[u32]) -> String {
+    oid.iter()
+        .map(|arc| arc.to_string())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// SNMP protocol version
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SnmpVersion {
+    V1,
+    V2c,
+}
+
+impl SnmpVersion {
+    fn wire_value(self) -> i64 {
+        match self {
+            SnmpVersion::V1 => 0,
+            SnmpVersion::V2c => 1,
+        }
+    }
+}
+
+/// A decoded SNMP variable value
+#[derive(Clone, Debug, PartialEq)]
+pub enum SnmpValue {
+    Integer(i64),
+    OctetString(Vec<u8>),
+    Null,
+    ObjectId(Oid),
+    IpAddress([u8; 4]),
+    Counter32(u32),
+    Gauge32(u32),
+    TimeTicks(u32),
+    Counter64(u64),
+    NoSuchObject,
+    NoSuchInstance,
+    EndOfMibView,
+    Other { tag: u8, data: Vec<u8> },
+}
+
+impl SnmpValue {
+    /// Numeric content for integer, counter and gauge values
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            SnmpValue::Integer(v) => Some(*v),
+            SnmpValue::Counter32(v) | SnmpValue::Gauge32(v) | SnmpValue::TimeTicks(v) => {
+                Some(i64::from(*v))
+            }
+            SnmpValue::Counter64(v) => i64::try_from(*v).ok(),
+            _ => None,
+        }
+    }
+
+    /// Octet string content decoded as (lossy) UTF-8, without trailing NULs
+    pub fn as_string(&self) -> Option<String> {
+        match self {
+            SnmpValue::OctetString(bytes) => Some(
+                String::from_utf8_lossy(bytes)
+                    .trim_end_matches('\0')
+                    .trim()
+                    .to_string(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Whether the value signals a missing object rather than data
+    pub fn is_exception(&self) -> bool {
+        matches!(
+            self,
+            SnmpValue::NoSuchObject | SnmpValue::NoSuchInstance | SnmpValue::EndOfMibView
+        )
+    }
+
+    fn decode(tag: u8, data: &[u8]) -> Result<SnmpValue, String> {
+        Ok(match tag {
+            TAG_INTEGER => SnmpValue::Integer(decode_integer(data)?),
+            TAG_OCTET_STRING => SnmpValue::OctetString(data.to_vec()),
+            TAG_NULL => SnmpValue::Null,
+            TAG_OBJECT_ID => SnmpValue::ObjectId(decode_oid(data)?),
+            TAG_IP_ADDRESS => match data {
+                [a, b, c, d] => SnmpValue::IpAddress([*a, *b, *c, *d]),
+                _ => return Err("Invalid IpAddress length".to_string()),
+            },
+            TAG_COUNTER32 => SnmpValue::Counter32(decode_unsigned(data)? as u32),
+            TAG_GAUGE32 => SnmpValue::Gauge32(decode_unsigned(data)? as u32),
+            TAG_TIME_TICKS => SnmpValue::TimeTicks(decode_unsigned(data)? as u32),
+            TAG_COUNTER64 => SnmpValue::Counter64(decode_unsigned(data)?),
+            TAG_NO_SUCH_OBJECT => SnmpValue::NoSuchObject,
+            TAG_NO_SUCH_INSTANCE => SnmpValue::NoSuchInstance,
+            TAG_END_OF_MIB_VIEW => SnmpValue::EndOfMibView,
+            _ => SnmpValue::Other {
+                tag,
+                data: data.to_vec(),
+            },
+        })
+    }
+}
+
+// ===== BER ENCODING =====
+
+fn encode_tlv(buf: &mut Vec<u8>, tag: u8, content: &[u8]) {
+    buf.push(tag);
+    let len = content.len();
+    if len < 0x80 {
+        buf.push(len as u8);
+    } else if len <= 0xFF {
+        buf.extend_from_slice(&[0x81, len as u8]);
+    } else {
+        buf.push(0x82);
+        buf.extend_from_slice(&(len as u16).to_be_bytes());
+    }
+    buf.extend_from_slice(content);
+}
+
+fn encode_integer(buf: &mut Vec<u8>, value: i64) {
+    let bytes = value.to_be_bytes();
+    // Drop redundant leading sign bytes
+    let mut start = 0;
+    while start < 7 {
+        let redundant = (bytes[start] == 0x00 && bytes[start + 1] & 0x80 == 0)
+            || (bytes[start] == 0xFF && bytes[start + 1] & 0x80 != 0);
+        if !redundant {
+            break;
+        }
+        start += 1;
+    }
+    encode_tlv(buf, TAG_INTEGER, &bytes[start..]);
+}
+
+fn encode_oid(buf: &mut Vec<u8>, oid: &[u32]) {
+    let mut content = Vec::new();
+    if oid.len() >= 2 {
+        content.push((oid[0] * 40 + oid[1]) as u8);
+    }
+    for &arc in oid.iter().skip(2) {
+        let mut groups = vec![(arc & 0x7F) as u8];
+        let mut rest = arc >> 7;
+        while rest > 0 {
+            groups.push((rest & 0x7F) as u8 | 0x80);
+            rest >>= 7;
+        }
+        content.extend(groups.iter().rev());
+    }
+    encode_tlv(buf, TAG_OBJECT_ID, &content);
+}
+
+/// Encode a request message for the given PDU type and OIDs
+fn encode_request(
+    version: SnmpVersion,
+    community: &str,
+    pdu_type: u8,
+    request_id: i32,
+    oids: &[&[u32]],
+) -> Vec<u8> {
+    let mut bindings = Vec::new();
+    for oid in oids {
+        let mut binding = Vec::new();
+        encode_oid(&mut binding, oid);
+        encode_tlv(&mut binding, TAG_NULL, &[]);
+        encode_tlv(&mut bindings, TAG_SEQUENCE, &binding);
+    }
+
+    let mut pdu = Vec::new();
+    encode_integer(&mut pdu, i64::from(request_id));
+    encode_integer(&mut pdu, 0); // error-status
+    encode_integer(&mut pdu, 0); // error-index
+    encode_tlv(&mut pdu, TAG_SEQUENCE, &bindings);
+
+    let mut message = Vec::new();
+    encode_integer(&mut message, version.wire_value());
+    encode_tlv(&mut message, TAG_OCTET_STRING, community.as_bytes());
+    encode_tlv(&mut message, pdu_type, &pdu);
+
+    let mut buf = Vec::new();
+    encode_tlv(&mut buf, TAG_SEQUENCE, &message);
+    buf
+}
+
+// ===== BER DECODING =====
+
+fn decode_integer(data: &[u8]) -> Result<i64, String> {
+    if data.is_empty() || data.len() > 8 {
+        return Err("Invalid integer length".to_string());
+    }
+    let mut value: i64 = if data[0] & 0x80 != 0 { -1 } else { 0 };
+    for &byte in data {
+        value = (value << 8) | i64::from(byte);
+    }
+    Ok(value)
+}
+
+fn decode_unsigned(data: &[u8]) -> Result<u64, String> {
+    // Unsigned values may carry a leading zero byte
+    let data = match data {
+        [0, rest @ ..] if !rest.is_empty() => rest,
+        _ => data,
+    };
+    if data.is_empty() || data.len() > 8 {
+        return Err("Invalid unsigned integer length".to_string());
+    }
+    Ok(data
+        .iter()
+        .fold(0u64, |value, &byte| (value << 8) | u64::from(byte)))
+}
+
+fn decode_oid(data: &[u8]) -> Result<Oid, String> {
+    let (&first, rest) = data
+        .split_first()
+        .ok_or_else(|| "Empty object identifier".to_string())?;
+    let mut oid = vec![u32::from(first / 40), u32::from(first % 40)];
+
+    let mut arc: u32 = 0;
+    for &byte in rest {
+        arc = arc
+            .checked_mul(128)
+            .ok_or_else(|| "Object identifier arc overflow".to_string())?
+            | u32::from(byte & 0x7F);
+        if byte & 0x80 == 0 {
+            oid.push(arc);
+            arc = 0;
+        }
+    }
+    Ok(oid)
+}
+
+/// Bounds-checked BER TLV reader
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Reader { data, pos: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    fn read_byte(&mut self) -> Result<u8, String> {
+        let byte = *self
+            .data
+            .get(self.pos)
+            .ok_or_else(|| "Truncated SNMP message".to_string())?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    /// Read one TLV, returning its tag and content
+    fn read_tlv(&mut self) -> Result<(u8, &'a [u8]), String> {
+        let tag = self.read_byte()?;
+        let first = self.read_byte()?;
+        let len = if first & 0x80 == 0 {
+            usize::from(first)
+        } else {
+            let count = usize::from(first & 0x7F);
+            if count == 0 || count > 4 {
+                return Err("Unsupported BER length encoding".to_string());
+            }
+            let mut len = 0usize;
+            for _ in 0..count {
+                len = (len << 8) | usize::from(self.read_byte()?);
+            }
+            len
+        };
+
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| "Truncated SNMP message".to_string())?;
+        let content = &self.data[self.pos..end];
+        self.pos = end;
+        Ok((tag, content))
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<&'a [u8], String> {
+        let (tag, content) = self.read_tlv()?;
+        if tag != expected {
+            return Err(format!(
+                "Unexpected BER tag 0x{:02x} (expected 0x{:02x})",
+                tag, expected
+            ));
+        }
+        Ok(content)
+    }
+}
+
+/// A decoded GetResponse PDU
+#[derive(Debug)]
+struct SnmpResponse {
+    request_id: i32,
+    error_status: i64,
+    bindings: Vec<(Oid, SnmpValue)>,
+}
+
+fn decode_response(bytes: &[u8]) -> Result<SnmpResponse, String> {
+    let mut message = Reader::new(Reader::new(bytes).expect(TAG_SEQUENCE)?);
+    message.expect(TAG_INTEGER)?; // version
+    message.expect(TAG_OCTET_STRING)?; // community
+
+    let mut pdu = Reader::new(message.expect(PDU_GET_RESPONSE)?);
+    let request_id = decode_integer(pdu.expect(TAG_INTEGER)?)? as i32;
+    let error_status = decode_integer(pdu.expect(TAG_INTEGER)?)?;
+    pdu.expect(TAG_INTEGER)?; // error-index
+
+    let mut list = Reader::new(pdu.expect(TAG_SEQUENCE)?);
+    let mut bindings = Vec::new();
+    while !list.is_empty() {
+        let mut binding = Reader::new(list.expect(TAG_SEQUENCE)?);
+        let oid = decode_oid(binding.expect(TAG_OBJECT_ID)?)?;
+        let (tag, data) = binding.read_tlv()?;
+        bindings.push((oid, SnmpValue::decode(tag, data)?));
+    }
+
+    Ok(SnmpResponse {
+        request_id,
+        error_status,
+        bindings,
+    })
+}
+
+// ===== CLIENT =====
+
+/// Connection settings for an SNMP agent
+#[derive(Clone, Debug)]
+pub struct SnmpOptions {
+    pub community: String,
+    pub port: u16,
+    pub version: SnmpVersion,
+    pub timeout: Duration,
+    pub retries: u32,
+}
+
+impl Default for SnmpOptions {
+    fn default() -> Self {
+        SnmpOptions {
+            community: "public".to_string(),
+            port: 161,
+            version: SnmpVersion::V2c,
+            timeout: Duration::from_secs(2),
+            retries: 1,
+        }
+    }
+}
+
+/// SNMP client bound to a single agent
+pub struct SnmpClient {
+    socket: UdpSocket,
+    target: SocketAddr,
+    options: SnmpOptions,
+}
+
+impl SnmpClient {
+    /// Resolve the agent address and open a UDP socket
+    pub fn connect(host: &str, options: SnmpOptions) -> Result<SnmpClient, String> {
+        let target = (host, options.port)
+            .to_socket_addrs()
+            .map_err(|e| format!("Failed to resolve '{}': {}", host, e))?
+            .next()
+            .ok_or_else(|| format!("No addresses found for '{}'", host))?;
+
+        let bind_address = if target.is_ipv6() {
+            "[::]:0"
+        } else {
+            "0.0.0.0:0"
+        };
+        let socket = UdpSocket::bind(bind_address)
+            .map_err(|e| format!("Failed to open UDP socket: {}", e))?;
+        socket
+            .set_read_timeout(Some(options.timeout))
+            .map_err(|e| format!("Failed to set socket timeout: {}", e))?;
+
+        Ok(SnmpClient {
+            socket,
+            target,
+            options,
+        })
+    }
+
+    fn send(&self, pdu_type: u8, oids: &[&[u32]]) -> Result<SnmpResponse, String> {
+        let request_id = NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed) & 0x7FFF_FFFF;
+        let request = encode_request(
+            self.options.version,
+            &self.options.community,
+            pdu_type,
+            request_id,
+            oids,
+        );
+
+        let mut buf = [0u8; 65535];
+        for attempt in 0..=self.options.retries {
+            if attempt > 0 {
+                tracing::debug!(target_addr = %self.target, attempt, "Retrying SNMP request");
+            }
+            self.socket
+                .send_to(&request, self.target)
+                .map_err(|e| format!("Failed to send SNMP request: {}", e))?;
+
+            // Skip stale or unrelated datagrams until this request's response arrives
+            while let Ok((len, from)) = self.socket.recv_from(&mut buf) {
+                if from.ip() != self.target.ip() {
+                    continue;
+                }
+                match decode_response(&buf[..len]) {
+                    Ok(response) if response.request_id == request_id => return Ok(response),
+                    _ => continue,
+                }
+            }
+        }
+
+        Err(format!("SNMP request to {} timed out", self.target))
+    }
+
+    /// Fetch a single object. Returns None if the agent doesn't have it.
+    pub fn get(&self, oid: &[u32]) -> Result<Option<SnmpValue>, String> {
+        let response = self.send(PDU_GET_REQUEST, &[oid])?;
+        if response.error_status == ERROR_NO_SUCH_NAME {
+            return Ok(None);
+        }
+        if response.error_status != 0 {
+            return Err(format!(
+                "SNMP GET failed with error-status {}",
+                response.error_status
+            ));
+        }
+        Ok(response
+            .bindings
+            .into_iter()
+            .next()
+            .map(|(_, value)| value)
+            .filter(|value| !value.is_exception()))
+    }
+
+    /// Fetch every object below a subtree using GETNEXT
+    pub fn walk(&self, prefix: &[u32]) -> Result<Vec<(Oid, SnmpValue)>, String> {
+        let mut rows = Vec::new();
+        let mut current = prefix.to_vec();
+
+        while rows.len() < MAX_WALK_ROWS {
+            let response = self.send(PDU_GET_NEXT_REQUEST, &[&current])?;
+            if response.error_status == ERROR_NO_SUCH_NAME {
+                break;
+            }
+            if response.error_status != 0 {
+                return Err(format!(
+                    "SNMP GETNEXT failed with error-status {}",
+                    response.error_status
+                ));
+            }
+
+            let Some((oid, value)) = response.bindings.into_iter().next() else {
+                break;
+            };
+            // Stop at the end of the subtree, or if a broken agent doesn't advance
+            if !oid.starts_with(prefix) || oid <= current || value.is_exception() {
+                break;
+            }
+            current = oid.clone();
+            rows.push((oid, value));
+        }
+
+        Ok(rows)
+    }
+}
+
+// ===== PRINTER MIB =====
+
+/// A row of the Printer MIB alert table
+#[derive(Clone, Debug, PartialEq)]
+pub struct SnmpAlert {
+    /// "critical", "warning", "warning-binary-change" or "other"
+    pub severity: String,
+    /// prtAlertGroup, e.g. 8 for input trays or 11 for marker supplies
+    pub group: Option<i64>,
+    /// prtAlertCode, e.g. 8 for "cover open" or 1101 for "marker toner empty"
+    pub code: Option<i64>,
+    pub description: String,
+}
+
+/// Device status read from the Host Resources and Printer MIBs
+#[derive(Clone, Debug, PartialEq)]
+pub struct SnmpPrinterStatus {
+    pub description: Option<String>,
+    /// hrDeviceStatus: "running", "warning", "testing", "down" or "unknown"
+    pub device_status: String,
+    /// hrPrinterStatus: "idle", "printing", "warmup", "other" or "unknown"
+    pub printer_status: String,
+    /// Conditions decoded from hrPrinterDetectedErrorState
+    pub state_reasons: Vec<StateReason>,
+    /// Lifetime page count (prtMarkerLifeCount)
+    pub page_count: Option<u64>,
+    pub alerts: Vec<SnmpAlert>,
+    pub supplies: Vec<PrinterSupply>,
+}
+
+fn device_status_name(value: Option<i64>) -> &'static str {
+    match value {
+        Some(2) => "running",
+        Some(3) => "warning",
+        Some(4) => "testing",
+        Some(5) => "down",
+        _ => "unknown",
+    }
+}
+
+fn printer_status_name(value: Option<i64>) -> &'static str {
+    match value {
+        Some(1) => "other",
+        Some(3) => "idle",
+        Some(4) => "printing",
+        Some(5) => "warmup",
+        _ => "unknown",
+    }
+}
+
+fn alert_severity_name(value: Option<i64>) -> &'static str {
+    match value {
+        Some(3) => "critical",
+        Some(4) => "warning",
+        Some(5) => "warning-binary-change",
+        _ => "other",
+    }
+}
+
+/// Map prtMarkerSuppliesType to the matching IPP marker-types keyword
+fn supply_type_keyword(value: Option<i64>) -> &'static str {
+    match value {
+        Some(3) => "toner",
+        Some(4) => "waste-toner",
+        Some(5) => "ink",
+        Some(6) => "ink-cartridge",
+        Some(7) => "ink-ribbon",
+        Some(8) => "waste-ink",
+        Some(9) => "opc",
+        Some(10) => "developer",
+        Some(11) => "fuser-oil",
+        Some(12) => "solid-wax",
+        Some(13) => "ribbon-wax",
+        Some(14) => "waste-wax",
+        Some(15) => "fuser",
+        Some(16) => "corona-wire",
+        Some(17) => "fuser-oil-wick",
+        Some(18) => "cleaner-unit",
+        Some(19) => "fuser-cleaning-pad",
+        Some(20) => "transfer-unit",
+        Some(21) => "toner-cartridge",
+        Some(22) => "fuser-oiler",
+        Some(23) => "water",
+        Some(24) => "waste-water",
+        Some(32) => "staples",
+        _ => "other",
+    }
+}
+
+/// Convert a prtMarkerColorantValue name to an sRGB hex color
+fn colorant_hex(name: &str) -> Option<String> {
+    let hex = match name.to_lowercase().as_str() {
+        "black" => "#000000",
+        "cyan" => "#00FFFF",
+        "magenta" => "#FF00FF",
+        "yellow" => "#FFFF00",
+        "red" => "#FF0000",
+        "green" => "#00FF00",
+        "blue" => "#0000FF",
+        "white" => "#FFFFFF",
+        _ => return None,
+    };
+    Some(hex.to_string())
+}
+
+/// Decode the hrPrinterDetectedErrorState bit string
+pub fn error_state_reasons(bits: &[u8]) -> Vec<StateReason> {
+    let mut reasons = Vec::new();
+    for &(bit, reason) in ERROR_STATE_BITS {
+        let set = bits
+            .get(bit / 8)
+            .is_some_and(|byte| byte & (0x80 >> (bit % 8)) != 0);
+        if set && !reasons.contains(&reason) {
+            reasons.push(reason);
+        }
+    }
+    reasons
+}
+
+/// Group walked table cells by row index: `prefix.column.index...`
+fn table_rows(prefix: &[u32], cells: Vec<(Oid, SnmpValue)>) -> Vec<(Oid, Vec<(u32, SnmpValue)>)> {
+    let mut rows: Vec<(Oid, Vec<(u32, SnmpValue)>)> = Vec::new();
+    for (oid, value) in cells {
+        let Some((&column, index)) = oid[prefix.len()..].split_first() else {
+            continue;
+        };
+        match rows.iter_mut().find(|(row, _)| row.as_slice() == index) {
+            Some((_, columns)) => columns.push((column, value)),
+            None => rows.push((index.to_vec(), vec![(column, value)])),
+        }
+    }
+    rows
+}
+
+fn column(columns: &[(u32, SnmpValue)], id: u32) -> Option<&SnmpValue> {
+    columns
+        .iter()
+        .find(|(column, _)| *column == id)
+        .map(|(_, value)| value)
+}
+
+/// Build supplies from prtMarkerSuppliesTable cells and colorant names
+fn supplies_from_table(
+    cells: Vec<(Oid, SnmpValue)>,
+    colorants: &[(Oid, SnmpValue)],
+) -> Vec<PrinterSupply> {
+    table_rows(OID_PRT_MARKER_SUPPLIES, cells)
+        .into_iter()
+        .map(|(index, columns)| {
+            let number = |id| column(&columns, id).and_then(SnmpValue::as_i64);
+            let colorant = number(SUPPLY_COLORANT_INDEX).and_then(|colorant_index| {
+                // Colorant rows are indexed by hrDeviceIndex.prtMarkerColorantIndex
+                let device_index = *index.first()?;
+                colorants
+                    .iter()
+                    .find(|(oid, _)| {
+                        oid[OID_PRT_MARKER_COLORANT_VALUE.len()..]
+                            == [device_index, colorant_index as u32]
+                    })
+                    .and_then(|(_, value)| value.as_string())
+            });
+
+            // Levels are in prtMarkerSuppliesSupplyUnit; -2 and -3 mean unknown/some remaining
+            let level = match (number(SUPPLY_LEVEL), number(SUPPLY_MAX_CAPACITY)) {
+                (Some(level), Some(max)) if level >= 0 && max > 0 => {
+                    Some((level.min(max) * 100 / max) as u8)
+                }
+                _ => None,
+            };
+
+            PrinterSupply {
+                name: column(&columns, SUPPLY_DESCRIPTION)
+                    .and_then(SnmpValue::as_string)
+                    .filter(|name| !name.is_empty())
+                    .unwrap_or_else(|| format!("Supply {}", format_oid(&index))),
+                supply_type: supply_type_keyword(number(SUPPLY_TYPE)).to_string(),
+                color: colorant.as_deref().and_then(colorant_hex),
+                level,
+                low_threshold: None,
+            }
+        })
+        .collect()
+}
+
+/// Build alerts from prtAlertTable cells
+fn alerts_from_table(cells: Vec<(Oid, SnmpValue)>) -> Vec<SnmpAlert> {
+    table_rows(OID_PRT_ALERT, cells)
+        .into_iter()
+        .map(|(_, columns)| {
+            let number = |id| column(&columns, id).and_then(SnmpValue::as_i64);
+            SnmpAlert {
+                severity: alert_severity_name(number(ALERT_SEVERITY_LEVEL)).to_string(),
+                group: number(ALERT_GROUP),
+                code: number(ALERT_CODE),
+                description: column(&columns, ALERT_DESCRIPTION)
+                    .and_then(SnmpValue::as_string)
+                    .unwrap_or_default(),
+            }
+        })
+        .collect()
+}
+
+/// Query a networked printer's status, counters, alerts and supplies
+pub fn query_printer(host: &str, options: SnmpOptions) -> Result<SnmpPrinterStatus, String> {
+    let client = SnmpClient::connect(host, options)?;
+
+    // sysDescr is mandatory for every agent, so it doubles as a reachability check
+    let description = client.get(OID_SYS_DESCR)?.and_then(|v| v.as_string());
+    let number = |oid| -> Result<Option<i64>, String> {
+        Ok(client.get(oid)?.as_ref().and_then(SnmpValue::as_i64))
+    };
+
+    let state_reasons = match client.get(OID_HR_PRINTER_ERROR_STATE)? {
+        Some(SnmpValue::OctetString(bits)) => error_state_reasons(&bits),
+        _ => Vec::new(),
+    };
+
+    Ok(SnmpPrinterStatus {
+        description,
+        device_status: device_status_name(number(OID_HR_DEVICE_STATUS)?).to_string(),
+        printer_status: printer_status_name(number(OID_HR_PRINTER_STATUS)?).to_string(),
+        state_reasons,
+        page_count: number(OID_PRT_MARKER_LIFE_COUNT)?.and_then(|count| u64::try_from(count).ok()),
+        alerts: alerts_from_table(client.walk(OID_PRT_ALERT)?),
+        supplies: supplies_from_table(
+            client.walk(OID_PRT_MARKER_SUPPLIES)?,
+            &client.walk(OID_PRT_MARKER_COLORANT_VALUE)?,
+        ),
+    })
+}
+
+/// Query only the supplies table of a networked printer
+pub fn query_supplies(host: &str, options: SnmpOptions) -> Result<Vec<PrinterSupply>, String> {
+    let client = SnmpClient::connect(host, options)?;
+    Ok(supplies_from_table(
+        client.walk(OID_PRT_MARKER_SUPPLIES)?,
+        &client.walk(OID_PRT_MARKER_COLORANT_VALUE)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_oid_round_trip() {
+        let oid = parse_oid(".1.3.6.1.2.1.43.11.1.1.9.1.1").unwrap();
+        assert_eq!(format_oid(&oid), "1.3.6.1.2.1.43.11.1.1.9.1.1");
+
+        let mut buf = Vec::new();
+        encode_oid(&mut buf, &[1, 3, 6, 1, 4, 1, 11, 300_000]);
+        assert_eq!(buf[0], TAG_OBJECT_ID);
+        assert_eq!(
+            decode_oid(&buf[2..]).unwrap(),
+            vec![1, 3, 6, 1, 4, 1, 11, 300_000]
+        );
+
+        assert!(parse_oid("1").is_none());
+        assert!(parse_oid("1.3.x").is_none());
+    }
+
+    #[test]
+    fn test_integer_encoding() {
+        for value in [
+            0,
+            1,
+            127,
+            128,
+            255,
+            256,
+            -1,
+            -128,
+            -129,
+            i64::from(i32::MAX),
+        ] {
+            let mut buf = Vec::new();
+            encode_integer(&mut buf, value);
+            assert_eq!(usize::from(buf[1]), buf.len() - 2);
+            assert_eq!(decode_integer(&buf[2..]).unwrap(), value);
+        }
+
+        let mut buf = Vec::new();
+        encode_integer(&mut buf, 128);
+        assert_eq!(buf, vec![TAG_INTEGER, 2, 0x00, 0x80]);
+
+        assert_eq!(
+            decode_unsigned(&[0x00, 0xFF, 0xFF, 0xFF, 0xFF]).unwrap(),
+            u64::from(u32::MAX)
+        );
+    }
+
+    #[test]
+    fn test_decode_get_response() {
+        // Build a GetResponse by hand: sysDescr.0 = "HP LaserJet", prtMarkerLifeCount = Counter32(4242)
+        let mut bindings = Vec::new();
+        for (oid, tag, data) in [
+            (OID_SYS_DESCR, TAG_OCTET_STRING, b"HP LaserJet".to_vec()),
+            (OID_PRT_MARKER_LIFE_COUNT, TAG_COUNTER32, vec![0x10, 0x92]),
+        ] {
+            let mut binding = Vec::new();
+            encode_oid(&mut binding, oid);
+            encode_tlv(&mut binding, tag, &data);
+            encode_tlv(&mut bindings, TAG_SEQUENCE, &binding);
+        }
+        let mut pdu = Vec::new();
+        encode_integer(&mut pdu, 42);
+        encode_integer(&mut pdu, 0);
+        encode_integer(&mut pdu, 0);
+        encode_tlv(&mut pdu, TAG_SEQUENCE, &bindings);
+        let mut message = Vec::new();
+        encode_integer(&mut message, 1);
+        encode_tlv(&mut message, TAG_OCTET_STRING, b"public");
+        encode_tlv(&mut message, PDU_GET_RESPONSE, &pdu);
+        let mut bytes = Vec::new();
+        encode_tlv(&mut bytes, TAG_SEQUENCE, &message);
+
+        let response = decode_response(&bytes).unwrap();
+        assert_eq!(response.request_id, 42);
+        assert_eq!(response.error_status, 0);
+        assert_eq!(response.bindings.len(), 2);
+        assert_eq!(
+            response.bindings[0].1.as_string().as_deref(),
+            Some("HP LaserJet")
+        );
+        assert_eq!(response.bindings[1].1, SnmpValue::Counter32(4242));
+
+        assert!(decode_response(&bytes[..bytes.len() - 3]).is_err());
+    }
+
+    #[test]
+    fn test_request_encoding() {
+        let bytes = encode_request(
+            SnmpVersion::V1,
+            "public",
+            PDU_GET_REQUEST,
+            7,
+            &[OID_SYS_DESCR],
+        );
+        let mut message = Reader::new(Reader::new(&bytes).expect(TAG_SEQUENCE).unwrap());
+        assert_eq!(message.expect(TAG_INTEGER).unwrap(), &[0]);
+        assert_eq!(message.expect(TAG_OCTET_STRING).unwrap(), b"public");
+        assert!(message.expect(PDU_GET_REQUEST).is_ok());
+    }
+
+    #[test]
+    fn test_error_state_reasons() {
+        // noPaper (bit 1) and doorOpen (bit 4) in the first octet, outputFull (bit 12) in the second
+        let reasons = error_state_reasons(&[0b0100_1000, 0b0000_1000]);
+        assert_eq!(
+            reasons,
+            vec![
+                StateReason::MediaEmpty,
+                StateReason::CoverOpen,
+                StateReason::OutputBinFull
+            ]
+        );
+        assert!(error_state_reasons(&[0x00]).is_empty());
+    }
+
+    #[test]
+    fn test_supplies_from_table() {
+        let cell = |column: u32, index: u32, value: SnmpValue| {
+            let mut oid = OID_PRT_MARKER_SUPPLIES.to_vec();
+            oid.extend([column, 1, index]);
+            (oid, value)
+        };
+        let cells = vec![
+            cell(SUPPLY_COLORANT_INDEX, 1, SnmpValue::Integer(1)),
+            cell(SUPPLY_COLORANT_INDEX, 2, SnmpValue::Integer(0)),
+            cell(SUPPLY_TYPE, 1, SnmpValue::Integer(21)),
+            cell(SUPPLY_TYPE, 2, SnmpValue::Integer(9)),
+            cell(
+                SUPPLY_DESCRIPTION,
+                1,
+                SnmpValue::OctetString(b"Black Cartridge\0".to_vec()),
+            ),
+            cell(
+                SUPPLY_DESCRIPTION,
+                2,
+                SnmpValue::OctetString(b"Imaging Drum".to_vec()),
+            ),
+            cell(SUPPLY_MAX_CAPACITY, 1, SnmpValue::Integer(2000)),
+            cell(SUPPLY_MAX_CAPACITY, 2, SnmpValue::Integer(-2)),
+            cell(SUPPLY_LEVEL, 1, SnmpValue::Integer(500)),
+            cell(SUPPLY_LEVEL, 2, SnmpValue::Integer(-3)),
+        ];
+        let mut colorant_oid = OID_PRT_MARKER_COLORANT_VALUE.to_vec();
+        colorant_oid.extend([1, 1]);
+        let colorants = vec![(colorant_oid, SnmpValue::OctetString(b"black".to_vec()))];
+
+        let supplies = supplies_from_table(cells, &colorants);
+        assert_eq!(supplies.len(), 2);
+        assert_eq!(supplies[0].name, "Black Cartridge");
+        assert_eq!(supplies[0].supply_type, "toner-cartridge");
+        assert_eq!(supplies[0].color.as_deref(), Some("#000000"));
+        assert_eq!(supplies[0].level, Some(25));
+        assert_eq!(supplies[1].supply_type, "opc");
+        assert_eq!(supplies[1].color, None);
+        assert_eq!(supplies[1].level, None);
+    }
+}
@@ -0,0 +1,289 @@
+//! Spool directory for print payloads
+//!
+//! Byte payloads are written to a per-process directory under the configured
+//! temp directory before they are handed to the spooler, so queued jobs don't
+//! keep their data in memory. Each file is removed when its job finishes, the
+//! total size of spooled data can be capped with a quota, and the directory
+//! is removed at shutdown. Directories left behind by processes that crashed
+//! are swept when a new one is created.
+//!
+//! Jobs submitted with `keep-spool-file` have their payload retained here
+//! after they finish, until they are removed from the job history.
+
+use crate::core::JobId;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use uuid::Uuid;
+
+/// Prefix of per-process spool directories
+const DIR_PREFIX: &str = "printers-js-";
+/// Spool directories of other processes untouched for this long are removed
+const STALE_DIR_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Bytes currently held in spool files
+static USAGE: AtomicU64 = AtomicU64::new(0);
+
+lazy_static::lazy_static! {
+    /// Spool directories created by this process (one per configured base)
+    static ref SPOOL_DIRS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+    /// Payloads kept for debugging, by job ID
+    static ref RETAINED: Mutex<HashMap<JobId, Arc<SpoolFile>>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SpoolError {
+    /// Writing the payload would exceed the configured quota
+    QuotaExceeded {
+        needed: u64,
+        available: u64,
+    },
+    Io(String),
+}
+
+impl fmt::Display for SpoolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpoolError::QuotaExceeded { needed, available } => write!(
+                f,
+                "Spool quota exceeded: {} bytes needed, {} available",
+                needed, available
+            ),
+            SpoolError::Io(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+/// A payload written to the spool directory, removed when dropped
+#[derive(Debug)]
+pub struct SpoolFile {
+    path: PathBuf,
+    size: u64,
+}
+
+impl SpoolFile {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+impl Drop for SpoolFile {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_file(&self.path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                tracing::warn!(path = %self.path.display(), "Failed to remove spool file: {}", e);
+            }
+        }
+        USAGE.fetch_sub(self.size, Ordering::Relaxed);
+    }
+}
+
+/// Reserve quota for a payload, failing if it doesn't fit
+fn reserve(size: u64, quota: Option<u64>) -> Result<(), SpoolError> {
+    let Some(quota) = quota else {
+        USAGE.fetch_add(size, Ordering::Relaxed);
+        return Ok(());
+    };
+    USAGE
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+            used.checked_add(size).filter(|total| *total <= quota)
+        })
+        .map(|_| ())
+        .map_err(|used| SpoolError::QuotaExceeded {
+            needed: size,
+            available: quota.saturating_sub(used),
+        })
+}
+
+/// This process's spool directory under `base` (default: the system temp
+/// directory), created on first use
+fn spool_dir(base: Option<&Path>) -> Result<PathBuf, SpoolError> {
+    let base = base.map_or_else(std::env::temp_dir, Path::to_path_buf);
+    let dir = base.join(format!("{}{}", DIR_PREFIX, std::process::id()));
+
+    let mut dirs = SPOOL_DIRS.lock().unwrap();
+    if dirs.contains(&dir) && dir.is_dir() {
+        return Ok(dir);
+    }
+
+    fs::create_dir_all(&dir).map_err(|e| {
+        SpoolError::Io(format!(
+            "Failed to create spool directory '{}': {}",
+            dir.display(),
+            e
+        ))
+    })?;
+    tracing::debug!(dir = %dir.display(), "Created spool directory");
+    if !dirs.contains(&dir) {
+        dirs.push(dir.clone());
+    }
+    sweep_stale_dirs(&base, &dir);
+    Ok(dir)
+}
+
+/// Remove spool directories of other processes that haven't been modified
+/// recently, which are left behind when a process exits without shutdown
+fn sweep_stale_dirs(base: &Path, own_dir: &Path) {
+    let Ok(entries) = fs::read_dir(base) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        // Spool directories are named after the process ID, which keeps
+        // other directories with the same prefix out of the sweep
+        let is_spool_dir = entry.file_name().to_str().is_some_and(|name| {
+            name.strip_prefix(DIR_PREFIX)
+                .is_some_and(|pid| pid.parse::<u32>().is_ok())
+        });
+        if !is_spool_dir || path == own_dir {
+            continue;
+        }
+        let stale = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .is_some_and(|age| age > STALE_DIR_AGE);
+        if stale && fs::remove_dir_all(&path).is_ok() {
+            tracing::debug!(dir = %path.display(), "Removed stale spool directory");
+        }
+    }
+}
+
+/// Write a job's payload to the spool directory
+pub fn write(
+    job_id: JobId,
+    data: &[u8],
+    base: Option<&Path>,
+    quota: Option<u64>,
+) -> Result<SpoolFile, SpoolError> {
+    let size = data.len() as u64;
+    reserve(size, quota)?;
+    // From here on the reservation is released when the file is dropped
+    let mut spool_file = SpoolFile {
+        path: PathBuf::new(),
+        size,
+    };
+
+    let dir = spool_dir(base)?;
+    spool_file.path = dir.join(format!("job-{}-{}.bin", job_id, Uuid::new_v4().simple()));
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&spool_file.path)
+        .and_then(|mut file| file.write_all(data))
+        .map_err(|e| {
+            SpoolError::Io(format!(
+                "Failed to write spool file '{}': {}",
+                spool_file.path.display(),
+                e
+            ))
+        })?;
+    Ok(spool_file)
+}
+
+/// Bytes currently held in spool files
+pub fn usage() -> u64 {
+    USAGE.load(Ordering::Relaxed)
+}
+
+/// Keep a job's payload after the job finishes
+pub fn retain(job_id: JobId, spool_file: Arc<SpoolFile>) {
+    RETAINED.lock().unwrap().insert(job_id, spool_file);
+}
+
+/// Payload retained for a job, if any
+pub fn retained(job_id: JobId) -> Option<Arc<SpoolFile>> {
+    RETAINED.lock().unwrap().get(&job_id).cloned()
+}
+
+/// Release retained payloads of jobs matching the predicate, returning how
+/// many were released. Files are removed once no running job uses them.
+pub fn release_where(predicate: impl Fn(JobId) -> bool) -> usize {
+    let mut retained = RETAINED.lock().unwrap();
+    let before = retained.len();
+    retained.retain(|job_id, _| !predicate(*job_id));
+    before - retained.len()
+}
+
+/// Remove this process's spool directories and anything left in them
+pub fn cleanup() {
+    RETAINED.lock().unwrap().clear();
+    for dir in SPOOL_DIRS.lock().unwrap().drain(..) {
+        if let Err(e) = fs::remove_dir_all(&dir) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                tracing::warn!(dir = %dir.display(), "Failed to remove spool directory: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+
+    #[test]
+    #[serial]
+    fn test_spool_files_and_quota() {
+        let base = tempfile::tempdir().unwrap();
+        let stale = base.path().join(format!("{}1", DIR_PREFIX));
+        fs::create_dir(&stale).unwrap();
+
+        let usage_before = usage();
+        let file = write(1, b"hello", Some(base.path()), None).unwrap();
+        assert_eq!(fs::read(file.path()).unwrap(), b"hello");
+        assert!(file.path().starts_with(base.path()));
+        assert_eq!(usage(), usage_before + 5);
+        // Recently modified directories of other processes are kept
+        assert!(stale.is_dir());
+
+        let quota = Some(usage() + 3);
+        assert_eq!(
+            write(2, b"four", Some(base.path()), quota).unwrap_err(),
+            SpoolError::QuotaExceeded {
+                needed: 4,
+                available: 3
+            }
+        );
+        let second = write(2, b"abc", Some(base.path()), quota).unwrap();
+        assert_ne!(second.path(), file.path());
+
+        let path = file.path().to_path_buf();
+        drop(file);
+        assert!(!path.exists());
+        drop(second);
+        assert_eq!(usage(), usage_before);
+
+        cleanup();
+        assert!(!path.parent().unwrap().exists());
+    }
+
+    #[test]
+    #[serial]
+    fn test_retained_spool_files() {
+        let base = tempfile::tempdir().unwrap();
+        let file = Arc::new(write(7, b"keep", Some(base.path()), None).unwrap());
+        let path = file.path().to_path_buf();
+        retain(7, Arc::clone(&file));
+        drop(file);
+
+        assert_eq!(fs::read(retained(7).unwrap().path()).unwrap(), b"keep");
+        assert!(retained(8).is_none());
+        assert_eq!(release_where(|job_id| job_id == 8), 0);
+        assert_eq!(release_where(|job_id| job_id == 7), 1);
+        assert!(retained(7).is_none());
+        assert!(!path.exists());
+
+        cleanup();
+    }
+}
@@ -0,0 +1,2501 @@
+//! Platform spooler integration not covered by the printers crate
+//!
+//! CUPS takes the requesting user and the password callback from per-thread
+//! client state, so they must be set on the thread that submits the job. The Windows spooler records the
+//! submitting account as job owner, which is rewritten after submission.
+//! Both spoolers can cancel a submitted job by its system job ID.
+//!
+//! On Windows, printer preferences (orientation, paper, quality, color,
+//! duplex) are read and written through the driver's DEVMODE, both as the
+//! user's printer defaults and on individual submitted jobs.
+//!
+//! On Windows, print queues can also be installed and removed, and the
+//! installed printer drivers listed, for provisioning tools. CUPS queues
+//! are added, deleted, enabled and disabled through the CUPS admin
+//! operations, authenticating like job submission.
+//!
+//! Printer sharing and who may print are read and changed through the
+//! printer's security descriptor on Windows, and through the queue's
+//! `printer-is-shared` and `requesting-user-name-allowed`/`-denied`
+//! attributes on CUPS.
+//!
+//! Both spoolers also report printer and job changes, which lets state
+//! monitoring react to events instead of enumerating printers on a timer:
+//! Windows through change notifications, CUPS through an ippget event
+//! subscription. Either can be checked for reachability by health checks.
+
+use std::collections::HashMap;
+use std::thread::JoinHandle;
+
+/// Name of the user running this process, used as the default job owner
+pub fn current_user() -> Option<String> {
+    let vars: &[&str] = if cfg!(windows) {
+        &["USERNAME"]
+    } else {
+        &["USER", "LOGNAME"]
+    };
+    vars.iter()
+        .find_map(|var| std::env::var(var).ok())
+        .filter(|user| !user.is_empty())
+}
+
+/// Check a user name before handing it to the spooler
+pub fn validate_user_name(user: &str) -> Result<(), String> {
+    if user.is_empty() || user.len() > 255 {
+        return Err("User name must be between 1 and 255 bytes".to_string());
+    }
+    if user.chars().any(char::is_control) {
+        return Err("User name must not contain control characters".to_string());
+    }
+    Ok(())
+}
+
+// DEVMODE values (wingdi.h)
+const DMORIENT_PORTRAIT: i16 = 1;
+const DMORIENT_LANDSCAPE: i16 = 2;
+const DMRES_DRAFT: i16 = -1;
+const DMRES_LOW: i16 = -2;
+const DMRES_MEDIUM: i16 = -3;
+const DMRES_HIGH: i16 = -4;
+#[cfg(windows)]
+const DMCOLOR_MONOCHROME: i16 = 1;
+#[cfg(windows)]
+const DMCOLOR_COLOR: i16 = 2;
+const DMDUP_SIMPLEX: i16 = 1;
+const DMDUP_VERTICAL: i16 = 2;
+const DMDUP_HORIZONTAL: i16 = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Orientation {
+    Portrait,
+    Landscape,
+}
+
+impl Orientation {
+    pub fn parse(value: &str) -> Option<Orientation> {
+        match value {
+            "portrait" => Some(Orientation::Portrait),
+            "landscape" => Some(Orientation::Landscape),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Orientation::Portrait => "portrait",
+            Orientation::Landscape => "landscape",
+        }
+    }
+
+    #[cfg_attr(not(windows), allow(dead_code))]
+    fn to_devmode(self) -> i16 {
+        match self {
+            Orientation::Portrait => DMORIENT_PORTRAIT,
+            Orientation::Landscape => DMORIENT_LANDSCAPE,
+        }
+    }
+
+    #[cfg_attr(not(windows), allow(dead_code))]
+    fn from_devmode(value: i16) -> Option<Orientation> {
+        match value {
+            DMORIENT_PORTRAIT => Some(Orientation::Portrait),
+            DMORIENT_LANDSCAPE => Some(Orientation::Landscape),
+            _ => None,
+        }
+    }
+}
+
+/// Print quality, either a driver-defined level or a resolution in DPI
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PrintQuality {
+    Draft,
+    Low,
+    Medium,
+    High,
+    Dpi(u16),
+}
+
+impl PrintQuality {
+    /// Parse a named quality level (resolutions are set with `Dpi`)
+    pub fn parse(value: &str) -> Option<PrintQuality> {
+        match value {
+            "draft" => Some(PrintQuality::Draft),
+            "low" => Some(PrintQuality::Low),
+            "medium" => Some(PrintQuality::Medium),
+            "high" => Some(PrintQuality::High),
+            _ => None,
+        }
+    }
+
+    /// Name of the quality level, or None for a resolution
+    pub fn as_str(&self) -> Option<&'static str> {
+        match self {
+            PrintQuality::Draft => Some("draft"),
+            PrintQuality::Low => Some("low"),
+            PrintQuality::Medium => Some("medium"),
+            PrintQuality::High => Some("high"),
+            PrintQuality::Dpi(_) => None,
+        }
+    }
+
+    #[cfg_attr(not(windows), allow(dead_code))]
+    fn to_devmode(self) -> i16 {
+        match self {
+            PrintQuality::Draft => DMRES_DRAFT,
+            PrintQuality::Low => DMRES_LOW,
+            PrintQuality::Medium => DMRES_MEDIUM,
+            PrintQuality::High => DMRES_HIGH,
+            PrintQuality::Dpi(dpi) => dpi.min(i16::MAX as u16) as i16,
+        }
+    }
+
+    #[cfg_attr(not(windows), allow(dead_code))]
+    fn from_devmode(value: i16) -> Option<PrintQuality> {
+        match value {
+            DMRES_DRAFT => Some(PrintQuality::Draft),
+            DMRES_LOW => Some(PrintQuality::Low),
+            DMRES_MEDIUM => Some(PrintQuality::Medium),
+            DMRES_HIGH => Some(PrintQuality::High),
+            dpi if dpi > 0 => Some(PrintQuality::Dpi(dpi as u16)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Duplex {
+    Simplex,
+    LongEdge,
+    ShortEdge,
+}
+
+impl Duplex {
+    pub fn parse(value: &str) -> Option<Duplex> {
+        match value {
+            "simplex" => Some(Duplex::Simplex),
+            "long-edge" => Some(Duplex::LongEdge),
+            "short-edge" => Some(Duplex::ShortEdge),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Duplex::Simplex => "simplex",
+            Duplex::LongEdge => "long-edge",
+            Duplex::ShortEdge => "short-edge",
+        }
+    }
+
+    #[cfg_attr(not(windows), allow(dead_code))]
+    fn to_devmode(self) -> i16 {
+        match self {
+            Duplex::Simplex => DMDUP_SIMPLEX,
+            Duplex::LongEdge => DMDUP_VERTICAL,
+            Duplex::ShortEdge => DMDUP_HORIZONTAL,
+        }
+    }
+
+    #[cfg_attr(not(windows), allow(dead_code))]
+    fn from_devmode(value: i16) -> Option<Duplex> {
+        match value {
+            DMDUP_SIMPLEX => Some(Duplex::Simplex),
+            DMDUP_VERTICAL => Some(Duplex::LongEdge),
+            DMDUP_HORIZONTAL => Some(Duplex::ShortEdge),
+            _ => None,
+        }
+    }
+}
+
+/// Printer settings carried in a Windows DEVMODE. Unset fields are left as
+/// the driver has them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PrinterPreferences {
+    pub orientation: Option<Orientation>,
+    /// DMPAPER_* code, e.g. 1 = Letter, 9 = A4
+    pub paper_size: Option<i16>,
+    /// Form name, e.g. "Letter" or "A4" (takes precedence over paper_size)
+    pub form_name: Option<String>,
+    pub quality: Option<PrintQuality>,
+    pub color: Option<bool>,
+    pub duplex: Option<Duplex>,
+}
+
+impl PrinterPreferences {
+    /// Preferences expressed by a job's IPP-style raw properties, which CUPS
+    /// applies itself. Returns None if the job sets none of them.
+    pub fn from_job_properties(properties: &HashMap<String, String>) -> Option<Self> {
+        let value = |key: &str| properties.get(key).map(|value| value.trim());
+        let preferences = PrinterPreferences {
+            orientation: value("orientation-requested")
+                .and_then(|value| match value {
+                    "3" | "portrait" => Some(Orientation::Portrait),
+                    "4" | "landscape" => Some(Orientation::Landscape),
+                    _ => None,
+                })
+                .or_else(|| {
+                    value("landscape")
+                        .filter(|value| *value == "true")
+                        .map(|_| Orientation::Landscape)
+                }),
+            paper_size: None,
+            form_name: value("media")
+                .or_else(|| value("media-size"))
+                .filter(|media| !media.is_empty())
+                .map(str::to_string),
+            quality: value("printer-resolution")
+                .and_then(|value| value.strip_suffix("dpi"))
+                .and_then(|dpi| dpi.parse::<u16>().ok())
+                .filter(|dpi| *dpi > 0)
+                .map(PrintQuality::Dpi)
+                .or_else(|| {
+                    value("print-quality").and_then(|value| match value {
+                        "3" | "draft" => Some(PrintQuality::Draft),
+                        "4" | "normal" => Some(PrintQuality::Medium),
+                        "5" | "high" => Some(PrintQuality::High),
+                        _ => None,
+                    })
+                }),
+            color: value("print-color-mode").and_then(|value| match value {
+                "color" => Some(true),
+                "monochrome" | "process-monochrome" | "bi-level" | "process-bi-level" => {
+                    Some(false)
+                }
+                _ => None,
+            }),
+            duplex: value("sides").and_then(|value| match value {
+                "one-sided" => Some(Duplex::Simplex),
+                "two-sided-long-edge" => Some(Duplex::LongEdge),
+                "two-sided-short-edge" => Some(Duplex::ShortEdge),
+                _ => None,
+            }),
+        };
+        (preferences != PrinterPreferences::default()).then_some(preferences)
+    }
+
+    /// Fill in fields set in `update`
+    pub fn merge(&mut self, update: &PrinterPreferences) {
+        if update.orientation.is_some() {
+            self.orientation = update.orientation;
+        }
+        if update.paper_size.is_some() || update.form_name.is_some() {
+            self.paper_size = update.paper_size;
+            self.form_name = update.form_name.clone();
+        }
+        if update.quality.is_some() {
+            self.quality = update.quality;
+        }
+        if update.color.is_some() {
+            self.color = update.color;
+        }
+        if update.duplex.is_some() {
+            self.duplex = update.duplex;
+        }
+    }
+}
+
+/// A print queue to install
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PrinterInstallation {
+    pub name: String,
+    /// Existing port, e.g. "LPT1:", "FILE:" or a Standard TCP/IP port name
+    pub port: String,
+    /// Name of an installed driver
+    pub driver: String,
+    pub location: Option<String>,
+    pub comment: Option<String>,
+}
+
+impl PrinterInstallation {
+    pub fn validate(&self) -> Result<(), String> {
+        for (field, value) in [
+            ("name", &self.name),
+            ("port", &self.port),
+            ("driver", &self.driver),
+        ] {
+            if value.trim().is_empty() {
+                return Err(format!("Printer {} must not be empty", field));
+            }
+            if value.chars().any(char::is_control) {
+                return Err(format!(
+                    "Printer {} must not contain control characters",
+                    field
+                ));
+            }
+        }
+        if self.name.contains(['\\', ',']) {
+            return Err("Printer name must not contain '\\' or ','".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// A CUPS queue to add
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CupsQueue {
+    pub name: String,
+    /// Where CUPS sends jobs, e.g. "ipp://10.0.0.5/ipp/print" or "socket://10.0.0.5"
+    pub device_uri: String,
+    /// PPD file to install for the queue
+    pub ppd: Option<std::path::PathBuf>,
+    /// Driver known to CUPS (`lpinfo -m`), e.g. "everywhere" for IPP
+    /// Everywhere printers
+    pub model: Option<String>,
+    pub location: Option<String>,
+    pub description: Option<String>,
+    /// Start the queue and accept jobs once it is added
+    pub enabled: bool,
+}
+
+/// Check a CUPS queue name: 1-127 printable characters other than
+/// space, `/`, `\`, `#`, quotes and `,`
+pub fn validate_cups_queue_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.len() > 127 {
+        return Err("CUPS queue name must be between 1 and 127 bytes".to_string());
+    }
+    if name
+        .chars()
+        .any(|c| c.is_control() || c.is_whitespace() || "/\\#'\",".contains(c))
+    {
+        return Err(format!(
+            "Invalid CUPS queue name '{}': it must not contain spaces, '/', '\\', '#', quotes or ','",
+            name
+        ));
+    }
+    Ok(())
+}
+
+impl CupsQueue {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_cups_queue_name(&self.name)?;
+        if !self.device_uri.contains(':') || self.device_uri.chars().any(char::is_control) {
+            return Err(format!("Invalid device URI '{}'", self.device_uri));
+        }
+        if self.ppd.is_some() && self.model.is_some() {
+            return Err("Give either a PPD file or a model, not both".to_string());
+        }
+        if let Some(ppd) = &self.ppd {
+            if !ppd.is_file() {
+                return Err(format!("PPD file '{}' not found", ppd.display()));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether a printer is shared and who may print to it. Unset fields are
+/// left unchanged when updating.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AccessControl {
+    pub shared: Option<bool>,
+    /// Users allowed to print; empty allows everyone who isn't denied
+    pub allowed_users: Option<Vec<String>>,
+    /// Users who may not print
+    pub denied_users: Option<Vec<String>>,
+}
+
+impl AccessControl {
+    pub fn validate(&self) -> Result<(), String> {
+        let users = self
+            .allowed_users
+            .iter()
+            .chain(&self.denied_users)
+            .flatten();
+        for user in users {
+            validate_user_name(user)?;
+        }
+        if self
+            .allowed_users
+            .as_ref()
+            .is_some_and(|users| !users.is_empty())
+            && self
+                .denied_users
+                .as_ref()
+                .is_some_and(|users| !users.is_empty())
+        {
+            return Err("Give either allowed or denied users, not both".to_string());
+        }
+        Ok(())
+    }
+
+    /// Apply the fields set in `update`. Allowing users clears the denied
+    /// users and vice versa, as spoolers keep one list or the other.
+    pub fn merge(&mut self, update: &AccessControl) {
+        if let Some(shared) = update.shared {
+            self.shared = Some(shared);
+        }
+        if let Some(allowed) = &update.allowed_users {
+            if !allowed.is_empty() {
+                self.denied_users = Some(Vec::new());
+            }
+            self.allowed_users = Some(allowed.clone());
+        }
+        if let Some(denied) = &update.denied_users {
+            if !denied.is_empty() {
+                self.allowed_users = Some(Vec::new());
+            }
+            self.denied_users = Some(denied.clone());
+        }
+    }
+}
+
+// Printer access rights in SDDL form
+/// Print and read permissions (PRINTER_ACCESS_USE | READ_CONTROL)
+#[cfg_attr(not(windows), allow(dead_code))]
+const SDDL_PRINT_RIGHTS: &str = "SWRC";
+/// Full control for administrators and the system, and of their own
+/// documents for the users who submitted them
+#[cfg_attr(not(windows), allow(dead_code))]
+const SDDL_ADMIN_ACES: &str =
+    "(A;OIIO;GA;;;CO)(A;;LCSWSDRCWDWO;;;BA)(A;OIIO;GA;;;BA)(A;;LCSWSDRCWDWO;;;SY)(A;OIIO;GA;;;SY)";
+/// SDDL alias of the Everyone group
+#[cfg_attr(not(windows), allow(dead_code))]
+const SDDL_EVERYONE: &str = "WD";
+
+/// SIDs granted and denied the right to print by a DACL in SDDL form, with
+/// Everyone's grant reported as an empty allow list. Administrators' full
+/// control entries are not print grants.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn print_aces(sddl: &str) -> (Vec<String>, Vec<String>) {
+    let dacl = match sddl.find("D:") {
+        Some(start) => &sddl[start + 2..],
+        None => return (Vec::new(), Vec::new()),
+    };
+    let dacl = dacl.find("S:").map_or(dacl, |end| &dacl[..end]);
+
+    let (mut allowed, mut denied, mut everyone) = (Vec::new(), Vec::new(), false);
+    for ace in dacl.split('(').skip(1) {
+        let fields: Vec<&str> = ace.trim_end_matches(')').split(';').collect();
+        let [kind, flags, rights, _, _, sid] = fields[..] else {
+            continue;
+        };
+        if flags.contains("IO") {
+            continue;
+        }
+        match kind {
+            "A" if matches!(rights, "SW" | "SWRC" | "0x8" | "0x20008") => {
+                if sid == SDDL_EVERYONE || sid == "S-1-1-0" {
+                    everyone = true;
+                } else {
+                    allowed.push(sid.to_string());
+                }
+            }
+            "D" if rights.contains("SW") || rights == "0x8" => denied.push(sid.to_string()),
+            _ => {}
+        }
+    }
+    if everyone {
+        allowed.clear();
+    }
+    (allowed, denied)
+}
+
+/// A protected DACL that lets the given SIDs print (everyone if empty)
+/// except the denied ones
+#[cfg_attr(not(windows), allow(dead_code))]
+fn print_dacl(allowed: &[String], denied: &[String]) -> String {
+    let mut sddl = "D:P".to_string();
+    for sid in denied {
+        sddl.push_str(&format!("(D;;SW;;;{})", sid));
+    }
+    sddl.push_str(SDDL_ADMIN_ACES);
+    if allowed.is_empty() {
+        sddl.push_str(&format!("(A;;{};;;{})", SDDL_PRINT_RIGHTS, SDDL_EVERYONE));
+    }
+    for sid in allowed {
+        sddl.push_str(&format!("(A;;{};;;{})", SDDL_PRINT_RIGHTS, sid));
+    }
+    sddl
+}
+
+/// An installed printer driver
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrinterDriver {
+    pub name: String,
+    /// Platform the driver is for, e.g. "Windows x64"
+    pub environment: String,
+    /// Driver model version (3 = v3 printer driver, 4 = v4)
+    pub version: u32,
+    pub driver_path: String,
+}
+
+/// Background watcher that calls back when the spooler reports a change.
+/// Stops when dropped.
+pub struct ChangeWatcher {
+    stop: Option<Box<dyn FnOnce() + Send>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ChangeWatcher {
+    /// Whether the watcher is still receiving notifications
+    pub fn is_running(&self) -> bool {
+        self.thread
+            .as_ref()
+            .is_some_and(|thread| !thread.is_finished())
+    }
+
+    /// Signal the watcher thread to exit and wait for it
+    pub fn stop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            stop();
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for ChangeWatcher {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(unix)]
+mod cups {
+    use super::{AccessControl, CupsQueue};
+    use crate::credentials::Credentials;
+    use crate::ipp;
+    use std::cell::RefCell;
+    use std::ffi::CString;
+    use std::os::raw::{c_char, c_void};
+    use std::ptr;
+
+    /// cups_password_cb2_t
+    type PasswordCallback = extern "C" fn(
+        prompt: *const c_char,
+        http: *mut c_void,
+        method: *const c_char,
+        resource: *const c_char,
+        user_data: *mut c_void,
+    ) -> *const c_char;
+
+    // ipp_status_t values for requests refused for lack of authentication
+    const IPP_STATUS_ERROR_FORBIDDEN: i32 = 0x0401;
+    const IPP_STATUS_ERROR_NOT_AUTHENTICATED: i32 = 0x0402;
+    const IPP_STATUS_ERROR_NOT_AUTHORIZED: i32 = 0x0403;
+    const IPP_STATUS_ERROR_CUPS_AUTHENTICATION_CANCELED: i32 = 0x1000;
+    /// Statuses above this are errors
+    const IPP_STATUS_OK_MAX: i32 = 0x00FF;
+
+    // ipp_op_t
+    const IPP_OP_PAUSE_PRINTER: i32 = 0x0010;
+    const IPP_OP_RESUME_PRINTER: i32 = 0x0011;
+    const IPP_OP_CUPS_ADD_MODIFY_PRINTER: i32 = 0x4003;
+    const IPP_OP_CUPS_DELETE_PRINTER: i32 = 0x4004;
+    const IPP_OP_CUPS_ACCEPT_JOBS: i32 = 0x4008;
+
+    // ipp_tag_t
+    const IPP_TAG_OPERATION: i32 = 0x01;
+    const IPP_TAG_PRINTER: i32 = 0x04;
+    const IPP_TAG_ENUM: i32 = 0x23;
+    const IPP_TAG_TEXT: i32 = 0x41;
+    const IPP_TAG_NAME: i32 = 0x42;
+    const IPP_TAG_URI: i32 = 0x45;
+
+    /// ipp_pstate_t for a queue that is ready to print
+    const IPP_PSTATE_IDLE: i32 = 3;
+    /// Resource the admin operations are sent to
+    const ADMIN_RESOURCE: &str = "/admin/";
+
+    #[link(name = "cups")]
+    extern "C" {
+        fn cupsSetUser(user: *const c_char);
+        fn cupsSetPasswordCB2(callback: Option<PasswordCallback>, user_data: *mut c_void);
+        fn cupsCancelJob(name: *const c_char, job_id: i32) -> i32;
+        fn cupsLastError() -> i32;
+        fn cupsLastErrorString() -> *const c_char;
+        fn cupsUser() -> *const c_char;
+        fn cupsDoFileRequest(
+            http: *mut c_void,
+            request: *mut c_void,
+            resource: *const c_char,
+            filename: *const c_char,
+        ) -> *mut c_void;
+        fn ippNewRequest(operation: i32) -> *mut c_void;
+        fn ippDelete(ipp: *mut c_void);
+        fn ippAddString(
+            ipp: *mut c_void,
+            group: i32,
+            value_tag: i32,
+            name: *const c_char,
+            language: *const c_char,
+            value: *const c_char,
+        ) -> *mut c_void;
+        fn ippAddStrings(
+            ipp: *mut c_void,
+            group: i32,
+            value_tag: i32,
+            name: *const c_char,
+            num_values: i32,
+            language: *const c_char,
+            values: *const *const c_char,
+        ) -> *mut c_void;
+        fn ippAddBoolean(
+            ipp: *mut c_void,
+            group: i32,
+            name: *const c_char,
+            value: c_char,
+        ) -> *mut c_void;
+        fn ippAddInteger(
+            ipp: *mut c_void,
+            group: i32,
+            value_tag: i32,
+            name: *const c_char,
+            value: i32,
+        ) -> *mut c_void;
+    }
+
+    thread_local! {
+        /// Password for this thread's CUPS requests, and whether it was
+        /// already offered
+        static PASSWORD: RefCell<Option<(CString, bool)>> = const { RefCell::new(None) };
+    }
+
+    /// Offer the thread's password once. A second prompt means it was
+    /// rejected, and answering null makes CUPS give up instead of retrying.
+    extern "C" fn password_callback(
+        _prompt: *const c_char,
+        _http: *mut c_void,
+        _method: *const c_char,
+        _resource: *const c_char,
+        _user_data: *mut c_void,
+    ) -> *const c_char {
+        PASSWORD.with(|password| match password.borrow_mut().as_mut() {
+            Some((password, offered)) if !*offered => {
+                *offered = true;
+                password.as_ptr()
+            }
+            _ => std::ptr::null(),
+        })
+    }
+
+    /// Set the IPP requesting-user-name for jobs submitted from this thread
+    pub fn set_thread_requesting_user(user: &str) -> Result<(), String> {
+        super::validate_user_name(user)?;
+        let user = CString::new(user).map_err(|_| "User name contains a NUL byte".to_string())?;
+        // SAFETY: cupsSetUser copies the string into CUPS' per-thread globals
+        unsafe { cupsSetUser(user.as_ptr()) };
+        Ok(())
+    }
+
+    /// Authenticate requests from this thread with the given credentials.
+    /// CUPS authenticates as its current user, so a user name replaces the
+    /// requesting user.
+    pub fn set_thread_credentials(credentials: &Credentials) -> Result<(), String> {
+        if let Some(user) = credentials.username() {
+            set_thread_requesting_user(user)?;
+        }
+        let password = match credentials {
+            Credentials::Basic { password, .. } => Some(
+                CString::new(password.as_str())
+                    .map_err(|_| "Password contains a NUL byte".to_string())?,
+            ),
+            // Kerberos tickets are used without a prompt
+            Credentials::Negotiate { .. } => None,
+        };
+        PASSWORD.with(|slot| *slot.borrow_mut() = password.map(|password| (password, false)));
+        // SAFETY: the callback only reads thread-local state and returns a
+        // pointer that stays valid until the thread's credentials change
+        unsafe { cupsSetPasswordCB2(Some(password_callback), std::ptr::null_mut()) };
+        Ok(())
+    }
+
+    /// Whether the last CUPS request from this thread was refused for lack
+    /// of authentication
+    pub fn last_error_is_unauthorized() -> bool {
+        // SAFETY: cupsLastError only reads CUPS' per-thread globals
+        matches!(
+            unsafe { cupsLastError() },
+            IPP_STATUS_ERROR_FORBIDDEN
+                | IPP_STATUS_ERROR_NOT_AUTHENTICATED
+                | IPP_STATUS_ERROR_NOT_AUTHORIZED
+                | IPP_STATUS_ERROR_CUPS_AUTHENTICATION_CANCELED
+        )
+    }
+
+    /// Cancel a job queued on the local CUPS server
+    pub fn cancel_job(printer_name: &str, job_id: u32) -> Result<(), String> {
+        let name = CString::new(printer_name)
+            .map_err(|_| "Printer name contains a NUL byte".to_string())?;
+        // SAFETY: name is NUL-terminated and only read for the duration of the call
+        if unsafe { cupsCancelJob(name.as_ptr(), job_id as i32) } == 1 {
+            return Ok(());
+        }
+        Err(format!("cupsCancelJob failed: {}", last_error_string()))
+    }
+
+    fn last_error_string() -> String {
+        // SAFETY: CUPS returns a pointer to its per-thread error string (or null)
+        unsafe {
+            let message = cupsLastErrorString();
+            if message.is_null() {
+                "unknown error".to_string()
+            } else {
+                std::ffi::CStr::from_ptr(message)
+                    .to_string_lossy()
+                    .into_owned()
+            }
+        }
+    }
+
+    fn c_string(value: &str, what: &str) -> Result<CString, String> {
+        CString::new(value).map_err(|_| format!("{} contains a NUL byte", what))
+    }
+
+    /// An IPP request under construction, deleted if it is never sent
+    struct Request(*mut c_void);
+
+    impl Request {
+        /// A request for an operation on a local queue
+        fn new(operation: i32, queue: &str) -> Result<Request, String> {
+            super::validate_cups_queue_name(queue)?;
+            let uri = c_string(&format!("ipp://localhost/printers/{}", queue), "Queue name")?;
+            // SAFETY: ippNewRequest adds the charset and language attributes
+            let request = Request(unsafe { ippNewRequest(operation) });
+            if request.0.is_null() {
+                return Err("Failed to allocate IPP request".to_string());
+            }
+            request.add_string(IPP_TAG_OPERATION, IPP_TAG_URI, "printer-uri", &uri);
+            // SAFETY: cupsUser returns CUPS' per-thread user name, copied by ippAddString
+            unsafe {
+                ippAddString(
+                    request.0,
+                    IPP_TAG_OPERATION,
+                    IPP_TAG_NAME,
+                    c"requesting-user-name".as_ptr(),
+                    ptr::null(),
+                    cupsUser(),
+                )
+            };
+            Ok(request)
+        }
+
+        fn add_string(&self, group: i32, value_tag: i32, name: &str, value: &CString) {
+            let name = CString::new(name).expect("attribute names have no NUL bytes");
+            // SAFETY: the request is live; ippAddString copies both strings
+            unsafe {
+                ippAddString(
+                    self.0,
+                    group,
+                    value_tag,
+                    name.as_ptr(),
+                    ptr::null(),
+                    value.as_ptr(),
+                )
+            };
+        }
+
+        fn add_strings(&self, group: i32, value_tag: i32, name: &str, values: &[CString]) {
+            let name = CString::new(name).expect("attribute names have no NUL bytes");
+            let values: Vec<*const c_char> = values.iter().map(|value| value.as_ptr()).collect();
+            // SAFETY: the request is live; ippAddStrings copies the strings
+            unsafe {
+                ippAddStrings(
+                    self.0,
+                    group,
+                    value_tag,
+                    name.as_ptr(),
+                    values.len() as i32,
+                    ptr::null(),
+                    values.as_ptr(),
+                )
+            };
+        }
+
+        /// Send the request to the scheduler's admin resource, with an
+        /// optional file as document data
+        fn send(self, file: Option<&CString>) -> Result<(), String> {
+            let request = self.0;
+            std::mem::forget(self);
+            let resource = c_string(ADMIN_RESOURCE, "Resource")?;
+            // SAFETY: cupsDoFileRequest takes ownership of the request and
+            // returns a response (or null) for us to delete
+            let response = unsafe {
+                cupsDoFileRequest(
+                    ptr::null_mut(),
+                    request,
+                    resource.as_ptr(),
+                    file.map_or(ptr::null(), |file| file.as_ptr()),
+                )
+            };
+            if !response.is_null() {
+                // SAFETY: the response came from cupsDoFileRequest
+                unsafe { ippDelete(response) };
+            }
+            // SAFETY: cupsLastError only reads CUPS' per-thread globals
+            if response.is_null() || unsafe { cupsLastError() } > IPP_STATUS_OK_MAX {
+                return Err(last_error_string());
+            }
+            Ok(())
+        }
+    }
+
+    impl Drop for Request {
+        fn drop(&mut self) {
+            // SAFETY: the request was never handed to CUPS
+            unsafe { ippDelete(self.0) };
+        }
+    }
+
+    /// Add a queue to the local CUPS server, or modify it if it exists
+    pub fn add_queue(queue: &CupsQueue) -> Result<(), String> {
+        queue.validate()?;
+        let request = Request::new(IPP_OP_CUPS_ADD_MODIFY_PRINTER, &queue.name)?;
+        request.add_string(
+            IPP_TAG_PRINTER,
+            IPP_TAG_URI,
+            "device-uri",
+            &c_string(&queue.device_uri, "Device URI")?,
+        );
+        if let Some(model) = &queue.model {
+            request.add_string(
+                IPP_TAG_PRINTER,
+                IPP_TAG_NAME,
+                "ppd-name",
+                &c_string(model, "Model")?,
+            );
+        }
+        if let Some(location) = &queue.location {
+            request.add_string(
+                IPP_TAG_PRINTER,
+                IPP_TAG_TEXT,
+                "printer-location",
+                &c_string(location, "Location")?,
+            );
+        }
+        if let Some(description) = &queue.description {
+            request.add_string(
+                IPP_TAG_PRINTER,
+                IPP_TAG_TEXT,
+                "printer-info",
+                &c_string(description, "Description")?,
+            );
+        }
+        if queue.enabled {
+            // SAFETY: the request is live and the names are NUL-terminated
+            unsafe {
+                ippAddBoolean(
+                    request.0,
+                    IPP_TAG_PRINTER,
+                    c"printer-is-accepting-jobs".as_ptr(),
+                    1,
+                );
+                ippAddInteger(
+                    request.0,
+                    IPP_TAG_PRINTER,
+                    IPP_TAG_ENUM,
+                    c"printer-state".as_ptr(),
+                    IPP_PSTATE_IDLE,
+                );
+            }
+        }
+
+        let ppd = queue
+            .ppd
+            .as_ref()
+            .map(|ppd| c_string(&ppd.to_string_lossy(), "PPD path"))
+            .transpose()?;
+        request.send(ppd.as_ref())
+    }
+
+    /// Attributes CUPS keeps a queue's sharing and user lists in
+    const ACCESS_ATTRIBUTES: &[&str] = &[
+        "printer-is-shared",
+        "requesting-user-name-allowed",
+        "requesting-user-name-denied",
+    ];
+    /// Allow list value that lets everyone print
+    const ALL_USERS: &str = "all";
+    /// Deny list value that denies no one
+    const NO_USERS: &str = "none";
+
+    /// Whether a queue is shared and who may print to it
+    pub fn get_access_control(name: &str) -> Result<AccessControl, String> {
+        super::validate_cups_queue_name(name)?;
+        let uri = format!("{}printers/{}", super::cups_events::scheduler_uri(), name);
+        let response = ipp::get_printer_attributes(
+            &uri,
+            ACCESS_ATTRIBUTES,
+            std::time::Duration::from_secs(5),
+        )?;
+
+        let users = |attribute: &str, none: &str| -> Vec<String> {
+            response
+                .attribute(ipp::TAG_PRINTER_ATTRIBUTES, attribute)
+                .map(|attribute| {
+                    attribute
+                        .values
+                        .iter()
+                        .filter_map(|value| value.as_str())
+                        .filter(|user| *user != none)
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let shared = response
+            .attribute(ipp::TAG_PRINTER_ATTRIBUTES, "printer-is-shared")
+            .and_then(|attribute| attribute.value())
+            .map(|value| matches!(value, ipp::IppValue::Boolean(true)));
+        Ok(AccessControl {
+            shared: Some(shared.unwrap_or(false)),
+            allowed_users: Some(users("requesting-user-name-allowed", ALL_USERS)),
+            denied_users: Some(users("requesting-user-name-denied", NO_USERS)),
+        })
+    }
+
+    /// Share or unshare a queue and replace who may print to it
+    pub fn set_access_control(name: &str, access: &AccessControl) -> Result<(), String> {
+        let request = Request::new(IPP_OP_CUPS_ADD_MODIFY_PRINTER, name)?;
+        if let Some(shared) = access.shared {
+            // SAFETY: the request is live and the name is NUL-terminated
+            unsafe {
+                ippAddBoolean(
+                    request.0,
+                    IPP_TAG_PRINTER,
+                    c"printer-is-shared".as_ptr(),
+                    shared as c_char,
+                )
+            };
+        }
+
+        let users = |users: &[String]| {
+            users
+                .iter()
+                .map(|user| c_string(user, "User name"))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let allowed = access.allowed_users.as_deref().unwrap_or_default();
+        let denied = access.denied_users.as_deref().unwrap_or_default();
+        // CUPS keeps one list: an allow list, or a deny list ("all" and
+        // "none" when no one is restricted)
+        if !allowed.is_empty() {
+            request.add_strings(
+                IPP_TAG_PRINTER,
+                IPP_TAG_NAME,
+                "requesting-user-name-allowed",
+                &users(allowed)?,
+            );
+        } else if !denied.is_empty() {
+            request.add_strings(
+                IPP_TAG_PRINTER,
+                IPP_TAG_NAME,
+                "requesting-user-name-denied",
+                &users(denied)?,
+            );
+        } else if access.allowed_users.is_some() || access.denied_users.is_some() {
+            request.add_strings(
+                IPP_TAG_PRINTER,
+                IPP_TAG_NAME,
+                "requesting-user-name-allowed",
+                &[c_string(ALL_USERS, "User name")?],
+            );
+        }
+        request.send(None)
+    }
+
+    /// Delete a queue from the local CUPS server, cancelling its jobs
+    pub fn delete_queue(name: &str) -> Result<(), String> {
+        Request::new(IPP_OP_CUPS_DELETE_PRINTER, name)?.send(None)
+    }
+
+    /// Enable a queue (accept jobs and start printing) or disable it (stop
+    /// printing; jobs are still accepted and wait in the queue)
+    pub fn set_queue_enabled(name: &str, enabled: bool) -> Result<(), String> {
+        if enabled {
+            Request::new(IPP_OP_CUPS_ACCEPT_JOBS, name)?.send(None)?;
+            Request::new(IPP_OP_RESUME_PRINTER, name)?.send(None)
+        } else {
+            Request::new(IPP_OP_PAUSE_PRINTER, name)?.send(None)
+        }
+    }
+}
+
+/// CUPS event subscriptions, polled with Get-Notifications
+#[cfg(unix)]
+mod cups_events {
+    use super::ChangeWatcher;
+    use crate::ipp;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    const SUBSCRIBED_EVENTS: &[&str] = &[
+        "printer-added",
+        "printer-deleted",
+        "printer-state-changed",
+        "job-state-changed",
+        "job-completed",
+    ];
+    /// Lease requested for the subscription; it is recreated if it expires
+    const SUBSCRIPTION_LEASE: Duration = Duration::from_secs(3600);
+    const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+    /// Bounds for the wait between Get-Notifications requests. CUPS suggests
+    /// long intervals; a notification request is cheap compared to
+    /// enumerating printers, so events are fetched at least every second.
+    const MIN_FETCH_INTERVAL: Duration = Duration::from_millis(250);
+    const MAX_FETCH_INTERVAL: Duration = Duration::from_secs(1);
+
+    /// IPP URI of the CUPS scheduler, from CUPS_SERVER if it names a host
+    pub(super) fn scheduler_uri() -> String {
+        match std::env::var("CUPS_SERVER") {
+            Ok(server) if !server.is_empty() && !server.starts_with('/') => {
+                format!("ipp://{}/", server)
+            }
+            _ => "ipp://localhost:631/".to_string(),
+        }
+    }
+
+    /// Check that the CUPS scheduler accepts connections
+    pub fn check_spooler(timeout: Duration) -> Result<(), String> {
+        let uri = scheduler_uri();
+        let address = crate::device::DeviceAddress::parse(&uri)
+            .ok_or_else(|| format!("Invalid scheduler URI '{}'", uri))?;
+        address.connect(timeout).map(|_| ())
+    }
+
+    fn subscribe(uri: &str) -> Result<i32, String> {
+        let user = super::current_user().unwrap_or_else(|| "anonymous".to_string());
+        ipp::create_printer_subscription(
+            uri,
+            &user,
+            SUBSCRIBED_EVENTS,
+            SUBSCRIPTION_LEASE,
+            REQUEST_TIMEOUT,
+        )
+    }
+
+    /// Subscribe to printer and job events on the CUPS scheduler, calling
+    /// `on_change` from a background thread when events arrive
+    pub fn watch_printer_changes(
+        on_change: impl Fn() + Send + 'static,
+    ) -> Result<ChangeWatcher, String> {
+        let uri = scheduler_uri();
+        let mut subscription_id = subscribe(&uri)?;
+        tracing::debug!(uri = %uri, subscription_id, "Created CUPS event subscription");
+
+        let stopped = Arc::new(AtomicBool::new(false));
+        let thread_stopped = Arc::clone(&stopped);
+        let thread = thread::spawn(move || {
+            let mut next_sequence = 1;
+            while !thread_stopped.load(Ordering::Relaxed) {
+                let wait = match ipp::get_notifications(
+                    &uri,
+                    subscription_id,
+                    next_sequence,
+                    REQUEST_TIMEOUT,
+                ) {
+                    Ok(notifications) => {
+                        if let Some(last) = notifications.events.iter().map(|(seq, _)| *seq).max() {
+                            tracing::trace!(
+                                events = notifications.events.len(),
+                                "CUPS event notifications"
+                            );
+                            next_sequence = last + 1;
+                            on_change();
+                        }
+                        notifications.get_interval.unwrap_or(MAX_FETCH_INTERVAL)
+                    }
+                    Err((ipp::STATUS_NOT_FOUND, _)) => {
+                        // The lease expired; events may have been missed meanwhile
+                        match subscribe(&uri) {
+                            Ok(id) => {
+                                subscription_id = id;
+                                next_sequence = 1;
+                                on_change();
+                                MIN_FETCH_INTERVAL
+                            }
+                            Err(e) => {
+                                tracing::warn!("Failed to renew CUPS subscription: {}", e);
+                                return;
+                            }
+                        }
+                    }
+                    Err((_, e)) => {
+                        tracing::warn!("Failed to fetch CUPS notifications: {}", e);
+                        break;
+                    }
+                };
+
+                let deadline = Instant::now() + wait.clamp(MIN_FETCH_INTERVAL, MAX_FETCH_INTERVAL);
+                while Instant::now() < deadline && !thread_stopped.load(Ordering::Relaxed) {
+                    thread::sleep(Duration::from_millis(50));
+                }
+            }
+
+            if let Err(e) = ipp::cancel_subscription(&uri, subscription_id, REQUEST_TIMEOUT) {
+                tracing::debug!("Failed to cancel CUPS subscription: {}", e);
+            }
+        });
+
+        Ok(ChangeWatcher {
+            stop: Some(Box::new(move || stopped.store(true, Ordering::Relaxed))),
+            thread: Some(thread),
+        })
+    }
+}
+
+#[cfg(unix)]
+pub use cups::{
+    add_queue, cancel_job, delete_queue, get_access_control, last_error_is_unauthorized,
+    set_access_control, set_queue_enabled, set_thread_credentials, set_thread_requesting_user,
+};
+
+#[cfg(unix)]
+pub use cups_events::{check_spooler, watch_printer_changes};
+
+/// There is no spooler to check; printing is left to the printers crate
+#[cfg(not(any(unix, windows)))]
+pub fn check_spooler(_timeout: std::time::Duration) -> Result<(), String> {
+    Ok(())
+}
+
+/// Change notifications aren't available, so callers fall back to polling
+#[cfg(not(any(unix, windows)))]
+pub fn watch_printer_changes(
+    _on_change: impl Fn() + Send + 'static,
+) -> Result<ChangeWatcher, String> {
+    Err("Printer change notifications are not supported on this platform".to_string())
+}
+
+/// Printer preferences are a Windows driver concept; CUPS takes the
+/// equivalent settings as job options
+#[cfg(not(windows))]
+pub fn get_preferences(_printer_name: &str) -> Result<PrinterPreferences, String> {
+    Err("Printer preferences are only supported on Windows".to_string())
+}
+
+#[cfg(not(windows))]
+pub fn set_preferences(
+    _printer_name: &str,
+    _preferences: &PrinterPreferences,
+) -> Result<PrinterPreferences, String> {
+    Err("Printer preferences are only supported on Windows".to_string())
+}
+
+/// CUPS queues only exist on Unix
+#[cfg(not(unix))]
+pub fn add_queue(_queue: &CupsQueue) -> Result<(), String> {
+    Err("CUPS queue administration is only supported on Unix".to_string())
+}
+
+#[cfg(not(unix))]
+pub fn delete_queue(_name: &str) -> Result<(), String> {
+    Err("CUPS queue administration is only supported on Unix".to_string())
+}
+
+#[cfg(not(unix))]
+pub fn set_queue_enabled(_name: &str, _enabled: bool) -> Result<(), String> {
+    Err("CUPS queue administration is only supported on Unix".to_string())
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn get_access_control(_printer_name: &str) -> Result<AccessControl, String> {
+    Err("Printer access control is not supported on this platform".to_string())
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn set_access_control(_printer_name: &str, _access: &AccessControl) -> Result<(), String> {
+    Err("Printer access control is not supported on this platform".to_string())
+}
+
+#[cfg(not(windows))]
+pub fn install_printer(_installation: &PrinterInstallation) -> Result<(), String> {
+    Err("Printer installation is only supported on Windows".to_string())
+}
+
+#[cfg(not(windows))]
+pub fn remove_printer(_printer_name: &str) -> Result<(), String> {
+    Err("Printer removal is only supported on Windows".to_string())
+}
+
+#[cfg(not(windows))]
+pub fn list_drivers() -> Result<Vec<PrinterDriver>, String> {
+    Err("Listing printer drivers is only supported on Windows".to_string())
+}
+
+#[cfg(windows)]
+mod winspool {
+    use super::{
+        AccessControl, ChangeWatcher, Duplex, Orientation, PrintQuality, PrinterDriver,
+        PrinterInstallation, PrinterPreferences, DMCOLOR_COLOR, DMCOLOR_MONOCHROME,
+    };
+    use std::ffi::c_void;
+    use std::ptr;
+    use std::sync::Arc;
+
+    type Handle = isize;
+
+    /// JOB_INFO_1W (winspool.h)
+    #[repr(C)]
+    #[allow(dead_code)] // Most fields are only read by the spooler
+    struct JobInfo1W {
+        job_id: u32,
+        printer_name: *mut u16,
+        machine_name: *mut u16,
+        user_name: *mut u16,
+        document: *mut u16,
+        datatype: *mut u16,
+        status_text: *mut u16,
+        status: u32,
+        priority: u32,
+        position: u32,
+        total_pages: u32,
+        pages_printed: u32,
+        submitted: [u16; 8],
+    }
+
+    /// JOB_INFO_2W (winspool.h)
+    #[repr(C)]
+    #[allow(dead_code)]
+    struct JobInfo2W {
+        job_id: u32,
+        printer_name: *mut u16,
+        machine_name: *mut u16,
+        user_name: *mut u16,
+        document: *mut u16,
+        notify_name: *mut u16,
+        datatype: *mut u16,
+        print_processor: *mut u16,
+        parameters: *mut u16,
+        driver_name: *mut u16,
+        dev_mode: *mut DevModeW,
+        status_text: *mut u16,
+        security_descriptor: *mut c_void,
+        status: u32,
+        priority: u32,
+        position: u32,
+        start_time: u32,
+        until_time: u32,
+        total_pages: u32,
+        size: u32,
+        submitted: [u16; 8],
+        time: u32,
+        pages_printed: u32,
+    }
+
+    /// Leading printer fields of DEVMODEW (wingdi.h). Drivers append private
+    /// data, so it is only ever accessed inside a driver-sized buffer.
+    #[repr(C)]
+    #[allow(dead_code)]
+    struct DevModeW {
+        device_name: [u16; 32],
+        spec_version: u16,
+        driver_version: u16,
+        size: u16,
+        driver_extra: u16,
+        fields: u32,
+        orientation: i16,
+        paper_size: i16,
+        paper_length: i16,
+        paper_width: i16,
+        scale: i16,
+        copies: i16,
+        default_source: i16,
+        print_quality: i16,
+        color: i16,
+        duplex: i16,
+        y_resolution: i16,
+        tt_option: i16,
+        collate: i16,
+        form_name: [u16; 32],
+    }
+
+    /// PRINTER_INFO_9W: the current user's default DEVMODE for a printer
+    #[repr(C)]
+    struct PrinterInfo9W {
+        dev_mode: *mut DevModeW,
+    }
+
+    /// PRINTER_INFO_2W (winspool.h)
+    #[repr(C)]
+    #[allow(dead_code)]
+    struct PrinterInfo2W {
+        server_name: *mut u16,
+        printer_name: *mut u16,
+        share_name: *mut u16,
+        port_name: *mut u16,
+        driver_name: *mut u16,
+        comment: *mut u16,
+        location: *mut u16,
+        dev_mode: *mut DevModeW,
+        sep_file: *mut u16,
+        print_processor: *mut u16,
+        datatype: *mut u16,
+        parameters: *mut u16,
+        security_descriptor: *mut c_void,
+        attributes: u32,
+        priority: u32,
+        default_priority: u32,
+        start_time: u32,
+        until_time: u32,
+        status: u32,
+        jobs: u32,
+        average_ppm: u32,
+    }
+
+    /// DRIVER_INFO_2W (winspool.h)
+    #[repr(C)]
+    #[allow(dead_code)]
+    struct DriverInfo2W {
+        version: u32,
+        name: *mut u16,
+        environment: *mut u16,
+        driver_path: *mut u16,
+        data_file: *mut u16,
+        config_file: *mut u16,
+    }
+
+    /// PRINTER_INFO_3: a printer's security descriptor
+    #[repr(C)]
+    struct PrinterInfo3 {
+        security_descriptor: *mut c_void,
+    }
+
+    /// PRINTER_DEFAULTSW (winspool.h)
+    #[repr(C)]
+    struct PrinterDefaultsW {
+        datatype: *mut u16,
+        dev_mode: *mut DevModeW,
+        desired_access: u32,
+    }
+
+    /// Access needed to delete a printer
+    const PRINTER_ALL_ACCESS: u32 = 0x000F_000C;
+    const PRINTER_ATTRIBUTE_SHARED: u32 = 0x0000_0008;
+    const SDDL_REVISION_1: u32 = 1;
+    const DACL_SECURITY_INFORMATION: u32 = 0x0000_0004;
+    /// Longest account or domain name, plus the terminator
+    const MAX_ACCOUNT_NAME: usize = 257;
+
+    /// Print processor and datatype for new queues
+    const DEFAULT_PRINT_PROCESSOR: &str = "winprint";
+    const DEFAULT_DATATYPE: &str = "RAW";
+
+    /// Leave the job's queue position unchanged when calling SetJob
+    const JOB_POSITION_UNSPECIFIED: u32 = 0;
+    /// SetJob command that deletes the job from the queue
+    const JOB_CONTROL_DELETE: u32 = 5;
+
+    // DEVMODE dmFields flags
+    const DM_ORIENTATION: u32 = 0x0000_0001;
+    const DM_PAPERSIZE: u32 = 0x0000_0002;
+    const DM_PRINTQUALITY: u32 = 0x0000_0400;
+    const DM_COLOR: u32 = 0x0000_0800;
+    const DM_DUPLEX: u32 = 0x0000_1000;
+    const DM_YRESOLUTION: u32 = 0x0000_2000;
+    const DM_FORMNAME: u32 = 0x0001_0000;
+
+    // DocumentProperties modes
+    const DM_OUT_BUFFER: u32 = 2;
+    const DM_IN_BUFFER: u32 = 8;
+
+    /// Printer, job, form, port, print processor and driver changes
+    const PRINTER_CHANGE_ALL: u32 = 0x7777_FFFF;
+    const INVALID_HANDLE_VALUE: Handle = -1;
+    const WAIT_OBJECT_0: u32 = 0;
+    const INFINITE: u32 = u32::MAX;
+
+    #[link(name = "winspool")]
+    extern "system" {
+        fn OpenPrinterW(name: *const u16, handle: *mut Handle, defaults: *const c_void) -> i32;
+        fn GetJobW(
+            handle: Handle,
+            job_id: u32,
+            level: u32,
+            job: *mut u8,
+            buf_size: u32,
+            needed: *mut u32,
+        ) -> i32;
+        fn SetJobW(handle: Handle, job_id: u32, level: u32, job: *const u8, command: u32) -> i32;
+        fn SetPrinterW(handle: Handle, level: u32, printer: *const u8, command: u32) -> i32;
+        fn DocumentPropertiesW(
+            hwnd: isize,
+            handle: Handle,
+            device_name: *const u16,
+            dev_mode_output: *mut u8,
+            dev_mode_input: *const u8,
+            mode: u32,
+        ) -> i32;
+        fn ClosePrinter(handle: Handle) -> i32;
+        fn AddPrinterW(server_name: *const u16, level: u32, printer: *const u8) -> Handle;
+        fn DeletePrinter(handle: Handle) -> i32;
+        fn GetPrinterW(
+            handle: Handle,
+            level: u32,
+            printer: *mut u8,
+            buf_size: u32,
+            needed: *mut u32,
+        ) -> i32;
+        fn EnumPrinterDriversW(
+            server_name: *const u16,
+            environment: *const u16,
+            level: u32,
+            drivers: *mut u8,
+            buf_size: u32,
+            needed: *mut u32,
+            returned: *mut u32,
+        ) -> i32;
+        fn FindFirstPrinterChangeNotification(
+            handle: Handle,
+            filter: u32,
+            options: u32,
+            notify_options: *const c_void,
+        ) -> Handle;
+        fn FindNextPrinterChangeNotification(
+            change: Handle,
+            cause: *mut u32,
+            notify_options: *const c_void,
+            notify_info: *mut *mut c_void,
+        ) -> i32;
+        fn FindClosePrinterChangeNotification(change: Handle) -> i32;
+    }
+
+    #[link(name = "advapi32")]
+    extern "system" {
+        fn ConvertSecurityDescriptorToStringSecurityDescriptorW(
+            security_descriptor: *const c_void,
+            revision: u32,
+            information: u32,
+            string: *mut *mut u16,
+            length: *mut u32,
+        ) -> i32;
+        fn ConvertStringSecurityDescriptorToSecurityDescriptorW(
+            string: *const u16,
+            revision: u32,
+            security_descriptor: *mut *mut c_void,
+            size: *mut u32,
+        ) -> i32;
+        fn LookupAccountNameW(
+            system_name: *const u16,
+            account_name: *const u16,
+            sid: *mut c_void,
+            sid_size: *mut u32,
+            domain: *mut u16,
+            domain_size: *mut u32,
+            sid_use: *mut u32,
+        ) -> i32;
+        fn LookupAccountSidW(
+            system_name: *const u16,
+            sid: *const c_void,
+            name: *mut u16,
+            name_size: *mut u32,
+            domain: *mut u16,
+            domain_size: *mut u32,
+            sid_use: *mut u32,
+        ) -> i32;
+        fn ConvertSidToStringSidW(sid: *const c_void, string: *mut *mut u16) -> i32;
+        fn ConvertStringSidToSidW(string: *const u16, sid: *mut *mut c_void) -> i32;
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn LocalFree(memory: *mut c_void) -> *mut c_void;
+        fn CreateEventW(
+            attributes: *const c_void,
+            manual_reset: i32,
+            initial_state: i32,
+            name: *const u16,
+        ) -> Handle;
+        fn SetEvent(event: Handle) -> i32;
+        fn CloseHandle(handle: Handle) -> i32;
+        fn WaitForMultipleObjects(
+            count: u32,
+            handles: *const Handle,
+            wait_all: i32,
+            milliseconds: u32,
+        ) -> u32;
+    }
+
+    fn wide(value: &str) -> Vec<u16> {
+        value.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+
+    /// Replace the owner recorded by the spooler for a submitted job
+    pub fn set_job_owner(printer_name: &str, job_id: u32, user: &str) -> Result<(), String> {
+        super::validate_user_name(user)?;
+        let printer_name = wide(printer_name);
+        let mut user = wide(user);
+
+        let mut handle: Handle = 0;
+        // SAFETY: printer_name is NUL-terminated and handle is a valid out pointer
+        if unsafe { OpenPrinterW(printer_name.as_ptr(), &mut handle, ptr::null()) } == 0 {
+            return Err(format!(
+                "OpenPrinter failed: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+
+        let result = update_job_owner(handle, job_id, &mut user);
+
+        // SAFETY: handle was opened above
+        unsafe { ClosePrinter(handle) };
+        result
+    }
+
+    /// Check that the print spooler service is running by opening the
+    /// local print server
+    pub fn check_spooler(_timeout: std::time::Duration) -> Result<(), String> {
+        let mut handle: Handle = 0;
+        // SAFETY: a null name opens the local print server; handle is a valid out pointer
+        if unsafe { OpenPrinterW(ptr::null(), &mut handle, ptr::null()) } == 0 {
+            return Err(format!(
+                "OpenPrinter failed: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+        // SAFETY: handle was opened above
+        unsafe { ClosePrinter(handle) };
+        Ok(())
+    }
+
+    /// Remove a queued job from the spooler
+    pub fn cancel_job(printer_name: &str, job_id: u32) -> Result<(), String> {
+        let printer_name = wide(printer_name);
+
+        let mut handle: Handle = 0;
+        // SAFETY: printer_name is NUL-terminated and handle is a valid out pointer
+        if unsafe { OpenPrinterW(printer_name.as_ptr(), &mut handle, ptr::null()) } == 0 {
+            return Err(format!(
+                "OpenPrinter failed: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+
+        // SAFETY: level 0 with a null job buffer only issues the command
+        let result = if unsafe { SetJobW(handle, job_id, 0, ptr::null(), JOB_CONTROL_DELETE) } == 0
+        {
+            Err(format!(
+                "SetJob failed: {}",
+                std::io::Error::last_os_error()
+            ))
+        } else {
+            Ok(())
+        };
+
+        // SAFETY: handle was opened above
+        unsafe { ClosePrinter(handle) };
+        result
+    }
+
+    /// Install a print queue on the local print server
+    pub fn install_printer(installation: &PrinterInstallation) -> Result<(), String> {
+        let mut name = wide(&installation.name);
+        let mut port = wide(&installation.port);
+        let mut driver = wide(&installation.driver);
+        let mut location = installation.location.as_deref().map(wide);
+        let mut comment = installation.comment.as_deref().map(wide);
+        let mut print_processor = wide(DEFAULT_PRINT_PROCESSOR);
+        let mut datatype = wide(DEFAULT_DATATYPE);
+        let optional = |value: &mut Option<Vec<u16>>| {
+            value
+                .as_mut()
+                .map_or(ptr::null_mut(), |value| value.as_mut_ptr())
+        };
+
+        let info = PrinterInfo2W {
+            server_name: ptr::null_mut(),
+            printer_name: name.as_mut_ptr(),
+            share_name: ptr::null_mut(),
+            port_name: port.as_mut_ptr(),
+            driver_name: driver.as_mut_ptr(),
+            comment: optional(&mut comment),
+            location: optional(&mut location),
+            dev_mode: ptr::null_mut(),
+            sep_file: ptr::null_mut(),
+            print_processor: print_processor.as_mut_ptr(),
+            datatype: datatype.as_mut_ptr(),
+            parameters: ptr::null_mut(),
+            security_descriptor: ptr::null_mut(),
+            attributes: 0,
+            priority: 0,
+            default_priority: 0,
+            start_time: 0,
+            until_time: 0,
+            status: 0,
+            jobs: 0,
+            average_ppm: 0,
+        };
+
+        // SAFETY: info and the strings it points to outlive the call
+        let handle =
+            unsafe { AddPrinterW(ptr::null(), 2, &info as *const PrinterInfo2W as *const u8) };
+        if handle == 0 {
+            return Err(format!(
+                "AddPrinter failed: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+        // SAFETY: AddPrinterW returned an open handle
+        unsafe { ClosePrinter(handle) };
+        Ok(())
+    }
+
+    /// Delete a print queue. The spooler removes it once its jobs finish.
+    pub fn remove_printer(printer_name: &str) -> Result<(), String> {
+        with_printer_admin(&wide(printer_name), |handle| {
+            // SAFETY: handle was opened with PRINTER_ALL_ACCESS
+            if unsafe { DeletePrinter(handle) } == 0 {
+                return Err(format!(
+                    "DeletePrinter failed: {}",
+                    std::io::Error::last_os_error()
+                ));
+            }
+            Ok(())
+        })
+    }
+
+    /// Whether a printer is shared and who may print to it, from its
+    /// attributes and security descriptor
+    pub fn get_access_control(printer_name: &str) -> Result<AccessControl, String> {
+        with_printer(&wide(printer_name), |handle| {
+            let info = get_printer(handle, 2)?;
+            // SAFETY: GetPrinterW filled the buffer with a PRINTER_INFO_2W
+            let attributes = unsafe { (*(info.as_ptr() as *const PrinterInfo2W)).attributes };
+
+            let info = get_printer(handle, 3)?;
+            // SAFETY: GetPrinterW filled the buffer with a PRINTER_INFO_3
+            let security_descriptor =
+                unsafe { (*(info.as_ptr() as *const PrinterInfo3)).security_descriptor };
+            let mut sddl: *mut u16 = ptr::null_mut();
+            // SAFETY: the security descriptor lives in the buffer; sddl is
+            // allocated by the call and freed below
+            if unsafe {
+                ConvertSecurityDescriptorToStringSecurityDescriptorW(
+                    security_descriptor,
+                    SDDL_REVISION_1,
+                    DACL_SECURITY_INFORMATION,
+                    &mut sddl,
+                    ptr::null_mut(),
+                )
+            } == 0
+            {
+                return Err(format!(
+                    "ConvertSecurityDescriptorToStringSecurityDescriptor failed: {}",
+                    std::io::Error::last_os_error()
+                ));
+            }
+            // SAFETY: sddl is a NUL-terminated string allocated with LocalAlloc
+            let dacl = unsafe {
+                let dacl = from_wide(sddl);
+                LocalFree(sddl as *mut c_void);
+                dacl
+            };
+
+            let (allowed, denied) = super::print_aces(&dacl);
+            Ok(AccessControl {
+                shared: Some(attributes & PRINTER_ATTRIBUTE_SHARED != 0),
+                allowed_users: Some(allowed.iter().map(|sid| account_name(sid)).collect()),
+                denied_users: Some(denied.iter().map(|sid| account_name(sid)).collect()),
+            })
+        })
+    }
+
+    /// Share or unshare a printer and replace who may print to it.
+    /// Administrators keep full control.
+    pub fn set_access_control(printer_name: &str, access: &AccessControl) -> Result<(), String> {
+        let sids = |users: &Option<Vec<String>>| {
+            users
+                .iter()
+                .flatten()
+                .map(|user| account_sid(user))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let allowed = sids(&access.allowed_users)?;
+        let denied = sids(&access.denied_users)?;
+
+        with_printer_admin(&wide(printer_name), |handle| {
+            if let Some(shared) = access.shared {
+                let mut info = get_printer(handle, 2)?;
+                let mut share_name = wide(printer_name);
+                // SAFETY: GetPrinterW filled the buffer with a PRINTER_INFO_2W
+                // whose strings live in the buffer; share_name outlives SetPrinterW
+                unsafe {
+                    let printer = &mut *(info.as_mut_ptr() as *mut PrinterInfo2W);
+                    if shared {
+                        printer.attributes |= PRINTER_ATTRIBUTE_SHARED;
+                        if printer.share_name.is_null() || *printer.share_name == 0 {
+                            printer.share_name = share_name.as_mut_ptr();
+                        }
+                    } else {
+                        printer.attributes &= !PRINTER_ATTRIBUTE_SHARED;
+                    }
+                    // Leave the security descriptor as it is
+                    printer.security_descriptor = ptr::null_mut();
+                    if SetPrinterW(handle, 2, info.as_ptr() as *const u8, 0) == 0 {
+                        return Err(format!(
+                            "SetPrinter failed: {}",
+                            std::io::Error::last_os_error()
+                        ));
+                    }
+                }
+            }
+
+            if access.allowed_users.is_none() && access.denied_users.is_none() {
+                return Ok(());
+            }
+            let sddl = wide(&super::print_dacl(&allowed, &denied));
+            let mut security_descriptor: *mut c_void = ptr::null_mut();
+            // SAFETY: sddl is NUL-terminated; the descriptor is allocated by
+            // the call and freed below
+            if unsafe {
+                ConvertStringSecurityDescriptorToSecurityDescriptorW(
+                    sddl.as_ptr(),
+                    SDDL_REVISION_1,
+                    &mut security_descriptor,
+                    ptr::null_mut(),
+                )
+            } == 0
+            {
+                return Err(format!(
+                    "ConvertStringSecurityDescriptorToSecurityDescriptor failed: {}",
+                    std::io::Error::last_os_error()
+                ));
+            }
+            let info = PrinterInfo3 {
+                security_descriptor,
+            };
+            // SAFETY: info points to a valid security descriptor, which is
+            // freed once SetPrinterW returns
+            unsafe {
+                let result = SetPrinterW(handle, 3, &info as *const PrinterInfo3 as *const u8, 0);
+                let error = std::io::Error::last_os_error();
+                LocalFree(security_descriptor);
+                if result == 0 {
+                    return Err(format!("SetPrinter failed: {}", error));
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// A printer's PRINTER_INFO_* at `level`, in a u64 buffer so it is aligned
+    fn get_printer(handle: Handle, level: u32) -> Result<Vec<u64>, String> {
+        let mut needed = 0u32;
+        // SAFETY: a null buffer of size 0 only queries the required size
+        unsafe { GetPrinterW(handle, level, ptr::null_mut(), 0, &mut needed) };
+        if needed == 0 {
+            return Err(format!(
+                "GetPrinter failed: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+        let mut buffer = vec![0u64; (needed as usize).div_ceil(8)];
+        // SAFETY: buffer holds at least `needed` bytes
+        if unsafe {
+            GetPrinterW(
+                handle,
+                level,
+                buffer.as_mut_ptr() as *mut u8,
+                needed,
+                &mut needed,
+            )
+        } == 0
+        {
+            return Err(format!(
+                "GetPrinter failed: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+        Ok(buffer)
+    }
+
+    /// String SID of a user or group name
+    fn account_sid(user: &str) -> Result<String, String> {
+        super::validate_user_name(user)?;
+        let name = wide(user);
+        let mut sid = vec![0u64; 16];
+        let mut sid_size = (sid.len() * 8) as u32;
+        let mut domain = vec![0u16; MAX_ACCOUNT_NAME];
+        let mut domain_size = domain.len() as u32;
+        let mut sid_use = 0u32;
+        // SAFETY: the buffers and their sizes match; a SID is at most 68 bytes
+        if unsafe {
+            LookupAccountNameW(
+                ptr::null(),
+                name.as_ptr(),
+                sid.as_mut_ptr() as *mut c_void,
+                &mut sid_size,
+                domain.as_mut_ptr(),
+                &mut domain_size,
+                &mut sid_use,
+            )
+        } == 0
+        {
+            return Err(format!(
+                "Unknown user or group '{}': {}",
+                user,
+                std::io::Error::last_os_error()
+            ));
+        }
+
+        let mut string: *mut u16 = ptr::null_mut();
+        // SAFETY: sid holds a valid SID; string is allocated by the call
+        if unsafe { ConvertSidToStringSidW(sid.as_ptr() as *const c_void, &mut string) } == 0 {
+            return Err(format!(
+                "ConvertSidToStringSid failed: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+        // SAFETY: string is a NUL-terminated string allocated with LocalAlloc
+        Ok(unsafe {
+            let sid = from_wide(string);
+            LocalFree(string as *mut c_void);
+            sid
+        })
+    }
+
+    /// `DOMAIN\name` of a string SID or SDDL alias, or the SID itself if
+    /// the account can't be found
+    fn account_name(sid_string: &str) -> String {
+        let string = wide(sid_string);
+        let mut sid: *mut c_void = ptr::null_mut();
+        // SAFETY: string is NUL-terminated; sid is allocated by the call
+        if unsafe { ConvertStringSidToSidW(string.as_ptr(), &mut sid) } == 0 {
+            return sid_string.to_string();
+        }
+
+        let mut name = vec![0u16; MAX_ACCOUNT_NAME];
+        let mut name_size = name.len() as u32;
+        let mut domain = vec![0u16; MAX_ACCOUNT_NAME];
+        let mut domain_size = domain.len() as u32;
+        let mut sid_use = 0u32;
+        // SAFETY: sid is valid until freed below; the buffers match their sizes
+        let found = unsafe {
+            let found = LookupAccountSidW(
+                ptr::null(),
+                sid,
+                name.as_mut_ptr(),
+                &mut name_size,
+                domain.as_mut_ptr(),
+                &mut domain_size,
+                &mut sid_use,
+            );
+            LocalFree(sid);
+            found != 0
+        };
+        if !found {
+            return sid_string.to_string();
+        }
+        let name = String::from_utf16_lossy(&name[..name_size as usize]);
+        let domain = String::from_utf16_lossy(&domain[..domain_size as usize]);
+        if domain.is_empty() {
+            name
+        } else {
+            format!("{}\\{}", domain, name)
+        }
+    }
+
+    /// Printer drivers installed for this platform
+    pub fn list_drivers() -> Result<Vec<PrinterDriver>, String> {
+        let mut needed = 0u32;
+        let mut returned = 0u32;
+        // SAFETY: a null buffer of size 0 only queries the required size
+        unsafe {
+            EnumPrinterDriversW(
+                ptr::null(),
+                ptr::null(),
+                2,
+                ptr::null_mut(),
+                0,
+                &mut needed,
+                &mut returned,
+            )
+        };
+        if needed == 0 {
+            return Ok(Vec::new());
+        }
+
+        // u64 storage keeps the buffer aligned for DRIVER_INFO_2W
+        let mut buffer = vec![0u64; (needed as usize).div_ceil(8)];
+        let drivers = buffer.as_mut_ptr() as *mut u8;
+        // SAFETY: buffer holds at least `needed` bytes
+        if unsafe {
+            EnumPrinterDriversW(
+                ptr::null(),
+                ptr::null(),
+                2,
+                drivers,
+                needed,
+                &mut needed,
+                &mut returned,
+            )
+        } == 0
+        {
+            return Err(format!(
+                "EnumPrinterDrivers failed: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+
+        // SAFETY: EnumPrinterDriversW filled the buffer with `returned`
+        // DRIVER_INFO_2W structs whose strings live in the same buffer
+        let infos = unsafe {
+            std::slice::from_raw_parts(drivers as *const DriverInfo2W, returned as usize)
+        };
+        Ok(infos
+            .iter()
+            .map(|info| PrinterDriver {
+                // SAFETY: the pointers are null or NUL-terminated strings in the buffer
+                name: unsafe { from_wide(info.name) },
+                environment: unsafe { from_wide(info.environment) },
+                version: info.version,
+                driver_path: unsafe { from_wide(info.driver_path) },
+            })
+            .collect())
+    }
+
+    /// Copy a NUL-terminated wide string, treating null as empty
+    ///
+    /// # Safety
+    /// `value` must be null or point to a NUL-terminated UTF-16 string
+    unsafe fn from_wide(value: *const u16) -> String {
+        if value.is_null() {
+            return String::new();
+        }
+        let mut len = 0;
+        while *value.add(len) != 0 {
+            len += 1;
+        }
+        String::from_utf16_lossy(std::slice::from_raw_parts(value, len))
+    }
+
+    /// The current user's default preferences for a printer
+    pub fn get_preferences(printer_name: &str) -> Result<PrinterPreferences, String> {
+        let name = wide(printer_name);
+        with_printer(&name, |handle| {
+            let dev_mode = default_dev_mode(handle, &name)?;
+            // SAFETY: the buffer holds a DEVMODEW filled in by the driver
+            Ok(unsafe { read_preferences(&*(dev_mode.as_ptr() as *const DevModeW)) })
+        })
+    }
+
+    /// Update the current user's default preferences for a printer,
+    /// returning the preferences the driver accepted
+    pub fn set_preferences(
+        printer_name: &str,
+        preferences: &PrinterPreferences,
+    ) -> Result<PrinterPreferences, String> {
+        let name = wide(printer_name);
+        with_printer(&name, |handle| {
+            let mut input = default_dev_mode(handle, &name)?;
+            // SAFETY: the buffer holds a DEVMODEW filled in by the driver
+            unsafe { apply_preferences(&mut *(input.as_mut_ptr() as *mut DevModeW), preferences) };
+
+            // Let the driver validate the changes and resolve conflicts
+            let mut output = vec![0u64; input.len()];
+            // SAFETY: both buffers are sized for this driver's DEVMODE
+            let status = unsafe {
+                DocumentPropertiesW(
+                    0,
+                    handle,
+                    name.as_ptr(),
+                    output.as_mut_ptr() as *mut u8,
+                    input.as_ptr() as *const u8,
+                    DM_IN_BUFFER | DM_OUT_BUFFER,
+                )
+            };
+            if status < 0 {
+                return Err(format!(
+                    "DocumentProperties failed: {}",
+                    std::io::Error::last_os_error()
+                ));
+            }
+
+            let info = PrinterInfo9W {
+                dev_mode: output.as_mut_ptr() as *mut DevModeW,
+            };
+            // SAFETY: info points at a valid DEVMODEW for the duration of the call
+            if unsafe { SetPrinterW(handle, 9, &info as *const _ as *const u8, 0) } == 0 {
+                return Err(format!(
+                    "SetPrinter failed: {}",
+                    std::io::Error::last_os_error()
+                ));
+            }
+            // SAFETY: the driver filled the output buffer with a DEVMODEW
+            Ok(unsafe { read_preferences(&*(output.as_ptr() as *const DevModeW)) })
+        })
+    }
+
+    /// Apply preferences to a submitted job's DEVMODE. The spooler only
+    /// honors them for jobs it renders itself (EMF/XPS), not RAW data.
+    pub fn set_job_preferences(
+        printer_name: &str,
+        job_id: u32,
+        preferences: &PrinterPreferences,
+    ) -> Result<(), String> {
+        let name = wide(printer_name);
+        with_printer(&name, |handle| {
+            let mut needed = 0u32;
+            // SAFETY: a null buffer of size 0 only queries the required size
+            unsafe { GetJobW(handle, job_id, 2, ptr::null_mut(), 0, &mut needed) };
+            if needed == 0 {
+                return Err(format!(
+                    "GetJob failed: {}",
+                    std::io::Error::last_os_error()
+                ));
+            }
+
+            // u64 storage keeps the buffer aligned for JOB_INFO_2W
+            let mut buffer = vec![0u64; (needed as usize).div_ceil(8)];
+            let job = buffer.as_mut_ptr() as *mut u8;
+            // SAFETY: buffer holds at least `needed` bytes
+            if unsafe { GetJobW(handle, job_id, 2, job, needed, &mut needed) } == 0 {
+                return Err(format!(
+                    "GetJob failed: {}",
+                    std::io::Error::last_os_error()
+                ));
+            }
+
+            // SAFETY: GetJobW filled the buffer with a JOB_INFO_2W whose
+            // DEVMODE (if any) lives inside the same buffer
+            unsafe {
+                let info = &mut *(job as *mut JobInfo2W);
+                if info.dev_mode.is_null() {
+                    return Err("Job has no DEVMODE".to_string());
+                }
+                apply_preferences(&mut *info.dev_mode, preferences);
+                info.position = JOB_POSITION_UNSPECIFIED;
+                if SetJobW(handle, job_id, 2, job, 0) == 0 {
+                    return Err(format!(
+                        "SetJob failed: {}",
+                        std::io::Error::last_os_error()
+                    ));
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Watch the local print server for printer and job changes, calling
+    /// `on_change` from a background thread after each notification
+    pub fn watch_printer_changes(
+        on_change: impl Fn() + Send + 'static,
+    ) -> Result<ChangeWatcher, String> {
+        let mut server: Handle = 0;
+        // SAFETY: a null name opens the local print server
+        if unsafe { OpenPrinterW(ptr::null(), &mut server, ptr::null()) } == 0 {
+            return Err(format!(
+                "OpenPrinter failed: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+
+        // SAFETY: server is an open print server handle
+        let change = unsafe {
+            FindFirstPrinterChangeNotification(server, PRINTER_CHANGE_ALL, 0, ptr::null())
+        };
+        if change == INVALID_HANDLE_VALUE {
+            let error = std::io::Error::last_os_error();
+            // SAFETY: server was opened above
+            unsafe { ClosePrinter(server) };
+            return Err(format!(
+                "FindFirstPrinterChangeNotification failed: {}",
+                error
+            ));
+        }
+
+        // SAFETY: creates an unnamed auto-reset event with default security
+        let stop_event = unsafe { CreateEventW(ptr::null(), 0, 0, ptr::null()) };
+        if stop_event == 0 {
+            let error = std::io::Error::last_os_error();
+            // SAFETY: both handles were opened above
+            unsafe {
+                FindClosePrinterChangeNotification(change);
+                ClosePrinter(server);
+            }
+            return Err(format!("CreateEvent failed: {}", error));
+        }
+
+        let stop_event = Arc::new(Event(stop_event));
+        let thread_stop_event = Arc::clone(&stop_event);
+        let thread = std::thread::spawn(move || {
+            let handles = [change, thread_stop_event.0];
+            loop {
+                // SAFETY: both handles stay open until this thread exits
+                let signaled = unsafe {
+                    WaitForMultipleObjects(handles.len() as u32, handles.as_ptr(), 0, INFINITE)
+                };
+                if signaled != WAIT_OBJECT_0 {
+                    // The stop event, or the wait failed
+                    break;
+                }
+                let mut cause = 0u32;
+                // SAFETY: resets the notification; no info structure is requested
+                if unsafe {
+                    FindNextPrinterChangeNotification(
+                        change,
+                        &mut cause,
+                        ptr::null(),
+                        ptr::null_mut(),
+                    )
+                } == 0
+                {
+                    tracing::warn!(
+                        "FindNextPrinterChangeNotification failed: {}",
+                        std::io::Error::last_os_error()
+                    );
+                    break;
+                }
+                tracing::trace!(cause, "Spooler change notification");
+                on_change();
+            }
+
+            // SAFETY: the handles were opened above and are no longer used
+            unsafe {
+                FindClosePrinterChangeNotification(change);
+                ClosePrinter(server);
+            }
+        });
+
+        Ok(ChangeWatcher {
+            // SAFETY: the event stays open while either side holds it
+            stop: Some(Box::new(move || unsafe {
+                SetEvent(stop_event.0);
+            })),
+            thread: Some(thread),
+        })
+    }
+
+    /// Win32 event handle, closed when the last owner drops it
+    struct Event(Handle);
+
+    impl Drop for Event {
+        fn drop(&mut self) {
+            // SAFETY: the handle was created by CreateEventW and is owned here
+            unsafe { CloseHandle(self.0) };
+        }
+    }
+
+    /// Open a printer for the duration of `f`
+    /// Run `f` with a printer handle opened for administration
+    fn with_printer_admin<R>(
+        name: &[u16],
+        f: impl FnOnce(Handle) -> Result<R, String>,
+    ) -> Result<R, String> {
+        let defaults = PrinterDefaultsW {
+            datatype: ptr::null_mut(),
+            dev_mode: ptr::null_mut(),
+            desired_access: PRINTER_ALL_ACCESS,
+        };
+        let mut handle: Handle = 0;
+        // SAFETY: name is NUL-terminated, defaults outlives the call and
+        // handle is a valid out pointer
+        if unsafe {
+            OpenPrinterW(
+                name.as_ptr(),
+                &mut handle,
+                &defaults as *const PrinterDefaultsW as *const c_void,
+            )
+        } == 0
+        {
+            return Err(format!(
+                "OpenPrinter failed: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+
+        let result = f(handle);
+
+        // SAFETY: handle was opened above
+        unsafe { ClosePrinter(handle) };
+        result
+    }
+
+    fn with_printer<R>(
+        name: &[u16],
+        f: impl FnOnce(Handle) -> Result<R, String>,
+    ) -> Result<R, String> {
+        let mut handle: Handle = 0;
+        // SAFETY: name is NUL-terminated and handle is a valid out pointer
+        if unsafe { OpenPrinterW(name.as_ptr(), &mut handle, ptr::null()) } == 0 {
+            return Err(format!(
+                "OpenPrinter failed: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+
+        let result = f(handle);
+
+        // SAFETY: handle was opened above
+        unsafe { ClosePrinter(handle) };
+        result
+    }
+
+    /// The driver's merged default DEVMODE (printer defaults plus the user's
+    /// changes), in a u64 buffer so it is aligned for DEVMODEW
+    fn default_dev_mode(handle: Handle, name: &[u16]) -> Result<Vec<u64>, String> {
+        // SAFETY: mode 0 only returns the size the driver needs
+        let size = unsafe {
+            DocumentPropertiesW(0, handle, name.as_ptr(), ptr::null_mut(), ptr::null(), 0)
+        };
+        if size <= 0 {
+            return Err(format!(
+                "DocumentProperties failed: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+
+        let mut buffer = vec![0u64; (size as usize).div_ceil(8)];
+        // SAFETY: buffer holds at least `size` bytes
+        let status = unsafe {
+            DocumentPropertiesW(
+                0,
+                handle,
+                name.as_ptr(),
+                buffer.as_mut_ptr() as *mut u8,
+                ptr::null(),
+                DM_OUT_BUFFER,
+            )
+        };
+        if status < 0 {
+            return Err(format!(
+                "DocumentProperties failed: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+        Ok(buffer)
+    }
+
+    fn read_preferences(dev_mode: &DevModeW) -> PrinterPreferences {
+        let has = |field: u32| dev_mode.fields & field != 0;
+        let form_len = dev_mode
+            .form_name
+            .iter()
+            .position(|c| *c == 0)
+            .unwrap_or(dev_mode.form_name.len());
+        PrinterPreferences {
+            orientation: has(DM_ORIENTATION)
+                .then(|| Orientation::from_devmode(dev_mode.orientation))
+                .flatten(),
+            paper_size: has(DM_PAPERSIZE).then_some(dev_mode.paper_size),
+            form_name: (has(DM_FORMNAME) && form_len > 0)
+                .then(|| String::from_utf16_lossy(&dev_mode.form_name[..form_len])),
+            quality: has(DM_PRINTQUALITY)
+                .then(|| PrintQuality::from_devmode(dev_mode.print_quality))
+                .flatten(),
+            color: has(DM_COLOR).then_some(dev_mode.color == DMCOLOR_COLOR),
+            duplex: has(DM_DUPLEX)
+                .then(|| Duplex::from_devmode(dev_mode.duplex))
+                .flatten(),
+        }
+    }
+
+    fn apply_preferences(dev_mode: &mut DevModeW, preferences: &PrinterPreferences) {
+        if let Some(orientation) = preferences.orientation {
+            dev_mode.orientation = orientation.to_devmode();
+            dev_mode.fields |= DM_ORIENTATION;
+        }
+        if let Some(form_name) = &preferences.form_name {
+            let mut name = [0u16; 32];
+            for (slot, c) in name.iter_mut().take(31).zip(form_name.encode_utf16()) {
+                *slot = c;
+            }
+            dev_mode.form_name = name;
+            dev_mode.fields = (dev_mode.fields | DM_FORMNAME) & !DM_PAPERSIZE;
+        } else if let Some(paper_size) = preferences.paper_size {
+            dev_mode.paper_size = paper_size;
+            dev_mode.fields = (dev_mode.fields | DM_PAPERSIZE) & !DM_FORMNAME;
+        }
+        if let Some(quality) = preferences.quality {
+            dev_mode.print_quality = quality.to_devmode();
+            dev_mode.fields |= DM_PRINTQUALITY;
+            if let PrintQuality::Dpi(_) = quality {
+                dev_mode.y_resolution = quality.to_devmode();
+                dev_mode.fields |= DM_YRESOLUTION;
+            }
+        }
+        if let Some(color) = preferences.color {
+            dev_mode.color = if color {
+                DMCOLOR_COLOR
+            } else {
+                DMCOLOR_MONOCHROME
+            };
+            dev_mode.fields |= DM_COLOR;
+        }
+        if let Some(duplex) = preferences.duplex {
+            dev_mode.duplex = duplex.to_devmode();
+            dev_mode.fields |= DM_DUPLEX;
+        }
+    }
+
+    fn update_job_owner(handle: Handle, job_id: u32, user: &mut [u16]) -> Result<(), String> {
+        let mut needed = 0u32;
+        // SAFETY: a null buffer of size 0 only queries the required size
+        unsafe { GetJobW(handle, job_id, 1, ptr::null_mut(), 0, &mut needed) };
+        if needed == 0 {
+            return Err(format!(
+                "GetJob failed: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+
+        // u64 storage keeps the buffer aligned for JOB_INFO_1W
+        let mut buffer = vec![0u64; (needed as usize).div_ceil(8)];
+        let job = buffer.as_mut_ptr() as *mut u8;
+        // SAFETY: buffer holds at least `needed` bytes
+        if unsafe { GetJobW(handle, job_id, 1, job, needed, &mut needed) } == 0 {
+            return Err(format!(
+                "GetJob failed: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+
+        // SAFETY: GetJobW filled the buffer with a JOB_INFO_1W; user outlives SetJobW
+        unsafe {
+            let info = &mut *(job as *mut JobInfo1W);
+            info.user_name = user.as_mut_ptr();
+            info.position = JOB_POSITION_UNSPECIFIED;
+            if SetJobW(handle, job_id, 1, job, 0) == 0 {
+                return Err(format!(
+                    "SetJob failed: {}",
+                    std::io::Error::last_os_error()
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+pub use winspool::{
+    cancel_job, check_spooler, get_access_control, get_preferences, install_printer, list_drivers,
+    remove_printer, set_access_control, set_job_owner, set_job_preferences, set_preferences,
+    watch_printer_changes,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_user_name() {
+        assert!(validate_user_name("alice").is_ok());
+        assert!(validate_user_name("DOMAIN\\bob").is_ok());
+        assert!(validate_user_name("").is_err());
+        assert!(validate_user_name("eve\nmallory").is_err());
+        assert!(validate_user_name(&"x".repeat(256)).is_err());
+    }
+
+    #[test]
+    fn test_preferences_from_job_properties() {
+        let properties = HashMap::from([
+            ("orientation-requested".to_string(), "4".to_string()),
+            ("sides".to_string(), "two-sided-short-edge".to_string()),
+            ("print-color-mode".to_string(), "monochrome".to_string()),
+            ("print-quality".to_string(), "3".to_string()),
+            ("media".to_string(), "A4".to_string()),
+        ]);
+        let preferences = PrinterPreferences::from_job_properties(&properties).unwrap();
+        assert_eq!(preferences.orientation, Some(Orientation::Landscape));
+        assert_eq!(preferences.duplex, Some(Duplex::ShortEdge));
+        assert_eq!(preferences.color, Some(false));
+        assert_eq!(preferences.quality, Some(PrintQuality::Draft));
+        assert_eq!(preferences.form_name.as_deref(), Some("A4"));
+
+        let resolution = HashMap::from([
+            ("printer-resolution".to_string(), "600dpi".to_string()),
+            ("print-quality".to_string(), "5".to_string()),
+        ]);
+        let preferences = PrinterPreferences::from_job_properties(&resolution).unwrap();
+        assert_eq!(preferences.quality, Some(PrintQuality::Dpi(600)));
+
+        // Keys produced by the simple print options
+        let simple = HashMap::from([
+            ("landscape".to_string(), "true".to_string()),
+            ("media-size".to_string(), "Letter".to_string()),
+        ]);
+        let preferences = PrinterPreferences::from_job_properties(&simple).unwrap();
+        assert_eq!(preferences.orientation, Some(Orientation::Landscape));
+        assert_eq!(preferences.form_name.as_deref(), Some("Letter"));
+
+        let unrelated = HashMap::from([("copies".to_string(), "2".to_string())]);
+        assert!(PrinterPreferences::from_job_properties(&unrelated).is_none());
+    }
+
+    #[test]
+    fn test_preferences_devmode_values() {
+        for quality in [
+            PrintQuality::Draft,
+            PrintQuality::Low,
+            PrintQuality::Medium,
+            PrintQuality::High,
+            PrintQuality::Dpi(300),
+        ] {
+            assert_eq!(
+                PrintQuality::from_devmode(quality.to_devmode()),
+                Some(quality)
+            );
+        }
+        assert_eq!(PrintQuality::from_devmode(0), None);
+        assert_eq!(Duplex::LongEdge.to_devmode(), DMDUP_VERTICAL);
+        assert_eq!(
+            Duplex::from_devmode(DMDUP_HORIZONTAL),
+            Some(Duplex::ShortEdge)
+        );
+        assert_eq!(
+            Orientation::from_devmode(DMORIENT_LANDSCAPE),
+            Some(Orientation::Landscape)
+        );
+        assert_eq!(Duplex::parse("long-edge"), Some(Duplex::LongEdge));
+        assert_eq!(PrintQuality::parse("ultra"), None);
+
+        let mut preferences = PrinterPreferences {
+            paper_size: Some(1),
+            color: Some(true),
+            ..Default::default()
+        };
+        preferences.merge(&PrinterPreferences {
+            form_name: Some("A4".to_string()),
+            duplex: Some(Duplex::Simplex),
+            ..Default::default()
+        });
+        assert_eq!(preferences.paper_size, None);
+        assert_eq!(preferences.form_name.as_deref(), Some("A4"));
+        assert_eq!(preferences.color, Some(true));
+        assert_eq!(preferences.duplex, Some(Duplex::Simplex));
+    }
+
+    #[test]
+    fn test_access_control() {
+        let mut access = AccessControl {
+            shared: Some(false),
+            allowed_users: Some(Vec::new()),
+            denied_users: Some(vec!["guest".to_string()]),
+        };
+        let update = AccessControl {
+            allowed_users: Some(vec!["alice".to_string()]),
+            ..AccessControl::default()
+        };
+        assert!(update.validate().is_ok());
+        access.merge(&update);
+        assert_eq!(access.shared, Some(false));
+        assert_eq!(access.allowed_users, Some(vec!["alice".to_string()]));
+        assert_eq!(access.denied_users, Some(Vec::new()));
+
+        let both = AccessControl {
+            allowed_users: Some(vec!["alice".to_string()]),
+            denied_users: Some(vec!["bob".to_string()]),
+            ..AccessControl::default()
+        };
+        assert!(both.validate().is_err());
+    }
+
+    #[test]
+    fn test_print_dacl() {
+        let sid = "S-1-5-21-1-2-3-1001".to_string();
+        let dacl = print_dacl(std::slice::from_ref(&sid), &[]);
+        assert!(dacl.starts_with("D:P"));
+        assert_eq!(print_aces(&dacl), (vec![sid.clone()], Vec::new()));
+
+        let dacl = print_dacl(&[], std::slice::from_ref(&sid));
+        assert_eq!(print_aces(&dacl), (Vec::new(), vec![sid]));
+
+        // A default printer DACL: Everyone may print
+        let default = "O:SYG:SYD:(A;OIIO;GA;;;CO)(A;;SWRC;;;WD)(A;CIIO;GX;;;WD)\
+                       (A;;LCSWDTSDRCWDWO;;;BA)(A;OICIIO;GA;;;BA)";
+        assert_eq!(print_aces(default), (Vec::new(), Vec::new()));
+    }
+}
@@ -0,0 +1,338 @@
+//! Supervised background tasks
+//!
+//! Background work runs as named tasks instead of bare threads. A panic in
+//! a task is caught and logged, and tasks whose policy allows it are
+//! restarted after a backoff, so a panic in the state monitor or the
+//! history sweeper no longer stops it silently for the rest of the process.
+//! `status` lists the tasks that are running, waiting to restart, or have
+//! given up; tasks that return are forgotten.
+
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Delay before the first restart, doubled for each one after it
+const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+const MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+/// Failed tasks kept for `status`, oldest dropped first
+const MAX_FAILED: usize = 32;
+
+lazy_static::lazy_static! {
+    static ref TASKS: Mutex<BTreeMap<u64, TaskStatus>> = Mutex::new(BTreeMap::new());
+    static ref NEXT_ID: AtomicU64 = AtomicU64::new(1);
+}
+
+/// What to do when a task panics
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RestartPolicy {
+    /// Leave it failed
+    Never,
+    /// Run it again, up to `max_restarts` times
+    OnPanic { max_restarts: u32 },
+}
+
+impl RestartPolicy {
+    /// Restarts allowed for long-running background loops
+    pub const BACKGROUND: RestartPolicy = RestartPolicy::OnPanic { max_restarts: 5 };
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TaskState {
+    Running,
+    /// Panicked and waiting to be restarted
+    Restarting,
+    /// Panicked and won't be restarted
+    Failed,
+}
+
+impl TaskState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskState::Running => "running",
+            TaskState::Restarting => "restarting",
+            TaskState::Failed => "failed",
+        }
+    }
+}
+
+/// A task as reported by `status`
+#[derive(Clone, Debug, PartialEq)]
+pub struct TaskStatus {
+    pub name: String,
+    pub state: TaskState,
+    pub restarts: u32,
+    /// When the task was spawned
+    pub started_at: SystemTime,
+    /// Message of the last panic
+    pub last_panic: Option<String>,
+}
+
+/// Handle to a supervised task
+pub struct SupervisedTask {
+    name: String,
+    handle: JoinHandle<()>,
+}
+
+impl SupervisedTask {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether the task has returned or given up
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Wait for the task to return or give up
+    pub fn join(self) -> Result<(), String> {
+        self.handle
+            .join()
+            .map_err(|_| format!("Supervisor of task '{}' panicked", self.name))
+    }
+}
+
+/// Text of a panic payload
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+fn backoff(restarts: u32) -> Duration {
+    INITIAL_BACKOFF
+        .saturating_mul(1 << restarts.saturating_sub(1).min(16))
+        .min(MAX_BACKOFF)
+}
+
+fn update(id: u64, f: impl FnOnce(&mut TaskStatus)) {
+    if let Some(status) = TASKS.lock().unwrap().get_mut(&id) {
+        f(status);
+    }
+}
+
+/// Forget failed tasks beyond `MAX_FAILED`
+fn prune_failed(tasks: &mut BTreeMap<u64, TaskStatus>) {
+    let failed: Vec<u64> = tasks
+        .iter()
+        .filter(|(_, status)| status.state == TaskState::Failed)
+        .map(|(id, _)| *id)
+        .collect();
+    for id in failed.iter().take(failed.len().saturating_sub(MAX_FAILED)) {
+        tasks.remove(id);
+    }
+}
+
+/// Run `body` on a named thread, calling it again after a panic as long as
+/// `policy` allows. The task ends when `body` returns.
+pub fn spawn<F>(name: impl Into<String>, policy: RestartPolicy, mut body: F) -> SupervisedTask
+where
+    F: FnMut() + Send + 'static,
+{
+    let name = name.into();
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    TASKS.lock().unwrap().insert(
+        id,
+        TaskStatus {
+            name: name.clone(),
+            state: TaskState::Running,
+            restarts: 0,
+            started_at: SystemTime::now(),
+            last_panic: None,
+        },
+    );
+
+    let task_name = name.clone();
+    let handle = thread::spawn(move || {
+        let mut restarts = 0;
+        loop {
+            let payload = match panic::catch_unwind(AssertUnwindSafe(&mut body)) {
+                Ok(()) => {
+                    TASKS.lock().unwrap().remove(&id);
+                    return;
+                }
+                Err(payload) => payload,
+            };
+            let message = panic_message(payload.as_ref());
+            let restart = match policy {
+                RestartPolicy::Never => false,
+                RestartPolicy::OnPanic { max_restarts } => restarts < max_restarts,
+            };
+            if !restart {
+                tracing::error!(task = %task_name, restarts, "Task panicked: {}", message);
+                let mut tasks = TASKS.lock().unwrap();
+                if let Some(status) = tasks.get_mut(&id) {
+                    status.state = TaskState::Failed;
+                    status.last_panic = Some(message);
+                }
+                prune_failed(&mut tasks);
+                return;
+            }
+
+            restarts += 1;
+            let delay = backoff(restarts);
+            tracing::error!(
+                task = %task_name,
+                restarts,
+                delay_ms = delay.as_millis() as u64,
+                "Task panicked, restarting: {}",
+                message
+            );
+            update(id, |status| {
+                status.state = TaskState::Restarting;
+                status.restarts = restarts;
+                status.last_panic = Some(message);
+            });
+            thread::sleep(delay);
+            update(id, |status| status.state = TaskState::Running);
+        }
+    });
+    SupervisedTask { name, handle }
+}
+
+/// Run `body` once on a named thread, recording it as failed if it panics
+pub fn spawn_once<F>(name: impl Into<String>, body: F) -> SupervisedTask
+where
+    F: FnOnce() + Send + 'static,
+{
+    let mut body = Some(body);
+    spawn(name, RestartPolicy::Never, move || {
+        if let Some(body) = body.take() {
+            body();
+        }
+    })
+}
+
+/// Tasks running now or that have given up, in the order they were spawned
+pub fn status() -> Vec<TaskStatus> {
+    TASKS.lock().unwrap().values().cloned().collect()
+}
+
+/// Forget tasks that have given up
+pub fn clear_failed() {
+    TASKS
+        .lock()
+        .unwrap()
+        .retain(|_, status| status.state != TaskState::Failed);
+}
+
+/// A group of tasks that are joined together, like the threads running
+/// print jobs
+#[derive(Default)]
+pub struct TaskSet {
+    tasks: Mutex<Vec<SupervisedTask>>,
+}
+
+impl TaskSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a task, dropping the handles of tasks that have finished
+    pub fn push(&self, task: SupervisedTask) {
+        let mut tasks = self.tasks.lock().unwrap();
+        tasks.retain(|task| !task.is_finished());
+        tasks.push(task);
+    }
+
+    /// Tasks that haven't finished
+    pub fn running(&self) -> usize {
+        let tasks = self.tasks.lock().unwrap();
+        tasks.iter().filter(|task| !task.is_finished()).count()
+    }
+
+    /// Wait up to `grace` for the tasks to finish, join those that did and
+    /// detach the rest. Returns the number detached.
+    pub fn join(&self, grace: Duration) -> usize {
+        let mut tasks = self.tasks.lock().unwrap();
+        let started = Instant::now();
+        while tasks.iter().any(|task| !task.is_finished()) && started.elapsed() < grace {
+            thread::sleep(Duration::from_millis(20));
+        }
+
+        let (finished, running): (Vec<_>, Vec<_>) =
+            tasks.drain(..).partition(|task| task.is_finished());
+        for task in finished {
+            let _ = task.join();
+        }
+        running.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+    use std::sync::atomic::AtomicU32;
+    use std::sync::Arc;
+
+    fn task_status(name: &str) -> Option<TaskStatus> {
+        status().into_iter().find(|status| status.name == name)
+    }
+
+    #[test]
+    #[serial]
+    fn test_restart_on_panic() {
+        let runs = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&runs);
+        let task = spawn(
+            "test-flaky",
+            RestartPolicy::OnPanic { max_restarts: 3 },
+            move || {
+                if counter.fetch_add(1, Ordering::SeqCst) < 2 {
+                    panic!("flaky {}", counter.load(Ordering::SeqCst));
+                }
+            },
+        );
+        task.join().unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+        // Tasks that return are forgotten
+        assert!(task_status("test-flaky").is_none());
+
+        let task = spawn(
+            "test-broken",
+            RestartPolicy::OnPanic { max_restarts: 1 },
+            || panic!("always"),
+        );
+        task.join().unwrap();
+        let broken = task_status("test-broken").unwrap();
+        assert_eq!(broken.state, TaskState::Failed);
+        assert_eq!(broken.restarts, 1);
+        assert_eq!(broken.last_panic.as_deref(), Some("always"));
+
+        spawn_once("test-once", || panic!("{}", "once".to_string()))
+            .join()
+            .unwrap();
+        let once = task_status("test-once").unwrap();
+        assert_eq!(once.restarts, 0);
+        assert_eq!(once.last_panic.as_deref(), Some("once"));
+
+        clear_failed();
+        assert!(task_status("test-broken").is_none());
+        assert_eq!(backoff(1), INITIAL_BACKOFF);
+        assert_eq!(backoff(40), MAX_BACKOFF);
+    }
+
+    #[test]
+    #[serial]
+    fn test_task_set() {
+        let set = TaskSet::new();
+        let (sender, receiver) = std::sync::mpsc::channel::<()>();
+        set.push(spawn_once("test-quick", || {}));
+        set.push(spawn_once("test-blocked", move || {
+            let _ = receiver.recv();
+        }));
+        assert!(task_status("test-blocked").is_some());
+        assert_eq!(set.join(Duration::from_millis(50)), 1);
+        assert_eq!(set.running(), 0);
+        drop(sender);
+    }
+}
@@ -0,0 +1,298 @@
+//! Print job templates
+//!
+//! A template is a named document body, such as a ZPL label, an ESC/POS
+//! receipt or plain text, with `{{variable}}` placeholders. Templates are
+//! parsed once when registered and rendered with a set of variables for each
+//! job, so label and receipt printing doesn't need to build documents in
+//! JavaScript.
+//!
+//! Values are checked against the template's format before they are
+//! substituted: a value that would inject printer commands (`^` or `~` in
+//! ZPL, control characters in ESC/POS) is rejected rather than printed.
+
+use crate::core::PrinterJobOptions;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, RwLock};
+
+lazy_static::lazy_static! {
+    static ref TEMPLATES: RwLock<HashMap<String, Arc<Template>>> = RwLock::new(HashMap::new());
+}
+
+/// Printer language of a template, deciding which values are safe to
+/// substitute and how the rendered document is submitted
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TemplateFormat {
+    /// Plain text, rendered by the spooler
+    Text,
+    /// Zebra Programming Language, sent raw
+    Zpl,
+    /// ESC/POS receipt printer commands, sent raw
+    EscPos,
+}
+
+impl TemplateFormat {
+    pub fn parse(format: &str) -> Option<TemplateFormat> {
+        match format {
+            "text" => Some(TemplateFormat::Text),
+            "zpl" => Some(TemplateFormat::Zpl),
+            "escpos" => Some(TemplateFormat::EscPos),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TemplateFormat::Text => "text",
+            TemplateFormat::Zpl => "zpl",
+            TemplateFormat::EscPos => "escpos",
+        }
+    }
+
+    /// Document format rendered documents are submitted as
+    pub fn media_type(&self) -> &'static str {
+        match self {
+            TemplateFormat::Text => "text/plain",
+            TemplateFormat::Zpl | TemplateFormat::EscPos => "application/vnd.cups-raw",
+        }
+    }
+
+    /// Why a value can't be substituted into a template of this format
+    fn check_value(&self, value: &str) -> Result<(), &'static str> {
+        let unsafe_char = match self {
+            TemplateFormat::Text => value.contains('\0'),
+            TemplateFormat::Zpl => value.contains(['^', '~']),
+            TemplateFormat::EscPos => value.chars().any(|c| c.is_control() && c != '\n'),
+        };
+        if !unsafe_char {
+            return Ok(());
+        }
+        Err(match self {
+            TemplateFormat::Text => "contains a NUL character",
+            TemplateFormat::Zpl => "contains a ZPL command prefix (^ or ~)",
+            TemplateFormat::EscPos => "contains a control character",
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Segment {
+    Literal(String),
+    Variable(String),
+}
+
+/// A parsed template
+#[derive(Clone, Debug, PartialEq)]
+pub struct Template {
+    pub name: String,
+    pub format: TemplateFormat,
+    /// Variables the template uses, sorted by name
+    pub variables: Vec<String>,
+    segments: Vec<Segment>,
+}
+
+impl Template {
+    /// Parse a template body, checking that every placeholder is closed and
+    /// names a variable
+    pub fn parse(name: &str, body: &str, format: TemplateFormat) -> Result<Template, String> {
+        if name.trim().is_empty() {
+            return Err("Template name must not be empty".to_string());
+        }
+
+        let mut segments = Vec::new();
+        let mut variables = BTreeSet::new();
+        let mut rest = body;
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                segments.push(Segment::Literal(rest[..start].to_string()));
+            }
+            let after = &rest[start + 2..];
+            let end = after.find("}}").ok_or_else(|| {
+                format!(
+                    "Unclosed placeholder at byte {} of template '{}'",
+                    body.len() - rest.len() + start,
+                    name
+                )
+            })?;
+            let variable = after[..end].trim();
+            if !is_variable_name(variable) {
+                return Err(format!(
+                    "Invalid variable name '{}' in template '{}'",
+                    variable, name
+                ));
+            }
+            variables.insert(variable.to_string());
+            segments.push(Segment::Variable(variable.to_string()));
+            rest = &after[end + 2..];
+        }
+        if !rest.is_empty() {
+            segments.push(Segment::Literal(rest.to_string()));
+        }
+
+        Ok(Template {
+            name: name.to_string(),
+            format,
+            variables: variables.into_iter().collect(),
+            segments,
+        })
+    }
+
+    /// Substitute variables into the template. Every variable the template
+    /// uses must be given; extra ones are ignored.
+    pub fn render(&self, variables: &HashMap<String, String>) -> Result<String, String> {
+        let missing: Vec<&str> = self
+            .variables
+            .iter()
+            .filter(|name| !variables.contains_key(*name))
+            .map(String::as_str)
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!(
+                "Missing variables for template '{}': {}",
+                self.name,
+                missing.join(", ")
+            ));
+        }
+
+        let mut rendered = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => rendered.push_str(text),
+                Segment::Variable(name) => {
+                    let value = &variables[name];
+                    self.format.check_value(value).map_err(|reason| {
+                        format!("Variable '{}' of template '{}' {}", name, self.name, reason)
+                    })?;
+                    rendered.push_str(value);
+                }
+            }
+        }
+        Ok(rendered)
+    }
+
+    /// Options for printing a rendered document: the template's document
+    /// format, and its name as the job name, unless the job sets its own
+    pub fn job_options(&self, job_options: Option<PrinterJobOptions>) -> PrinterJobOptions {
+        let mut job_options = job_options.unwrap_or_else(PrinterJobOptions::none);
+        job_options
+            .raw_properties
+            .entry("document-format".to_string())
+            .or_insert_with(|| self.format.media_type().to_string());
+        job_options.name.get_or_insert_with(|| self.name.clone());
+        job_options
+    }
+}
+
+/// Variable names are letters, digits, `_`, `-` and `.`
+fn is_variable_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+/// Parse and register a template, replacing any with the same name
+pub fn register(name: &str, body: &str, format: TemplateFormat) -> Result<(), String> {
+    let template = Template::parse(name, body, format)?;
+    tracing::debug!(
+        template = name,
+        format = format.as_str(),
+        variables = template.variables.len(),
+        "Registered template"
+    );
+    TEMPLATES
+        .write()
+        .unwrap()
+        .insert(name.to_string(), Arc::new(template));
+    Ok(())
+}
+
+/// Remove a template, returning whether it existed
+pub fn unregister(name: &str) -> bool {
+    TEMPLATES.write().unwrap().remove(name).is_some()
+}
+
+pub fn get(name: &str) -> Option<Arc<Template>> {
+    TEMPLATES.read().unwrap().get(name).cloned()
+}
+
+/// All registered templates, sorted by name
+pub fn list() -> Vec<Arc<Template>> {
+    let mut templates: Vec<Arc<Template>> = TEMPLATES.read().unwrap().values().cloned().collect();
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    templates
+}
+
+/// Render a registered template
+pub fn render(name: &str, variables: &HashMap<String, String>) -> Result<String, String> {
+    get(name)
+        .ok_or_else(|| format!("Template '{}' not found", name))?
+        .render(variables)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_render_templates() {
+        let label = Template::parse(
+            "label",
+            "^XA^FO50,50^FD{{ sku }}^FS^FO50,100^FD{{name}} x{{qty}}^FS^XZ",
+            TemplateFormat::Zpl,
+        )
+        .unwrap();
+        assert_eq!(label.variables, ["name", "qty", "sku"]);
+        assert_eq!(
+            label
+                .render(&vars(&[("sku", "A-1"), ("name", "Widget"), ("qty", "3")]))
+                .unwrap(),
+            "^XA^FO50,50^FDA-1^FS^FO50,100^FDWidget x3^FS^XZ"
+        );
+
+        let error = label.render(&vars(&[("sku", "A-1")])).unwrap_err();
+        assert!(error.contains("name, qty"), "{}", error);
+        // Values can't inject ZPL commands
+        assert!(label
+            .render(&vars(&[("sku", "^XZ"), ("name", "x"), ("qty", "1")]))
+            .is_err());
+
+        let receipt =
+            Template::parse("receipt", "Total: {{total}}\n", TemplateFormat::EscPos).unwrap();
+        assert!(receipt.render(&vars(&[("total", "\x1b@")])).is_err());
+        assert_eq!(
+            receipt.render(&vars(&[("total", "9.99")])).unwrap(),
+            "Total: 9.99\n"
+        );
+
+        let options = receipt.job_options(None);
+        assert_eq!(options.name.as_deref(), Some("receipt"));
+        assert_eq!(
+            options.raw_properties["document-format"],
+            "application/vnd.cups-raw"
+        );
+
+        assert!(Template::parse("broken", "Hello {{name", TemplateFormat::Text).is_err());
+        assert!(Template::parse("broken", "Hello {{na me}}", TemplateFormat::Text).is_err());
+        assert!(Template::parse(" ", "Hello", TemplateFormat::Text).is_err());
+    }
+
+    #[test]
+    fn test_template_registry() {
+        register("greeting", "Hello {{name}}", TemplateFormat::Text).unwrap();
+        assert!(list().iter().any(|template| template.name == "greeting"));
+        assert_eq!(
+            render("greeting", &vars(&[("name", "World")])).unwrap(),
+            "Hello World"
+        );
+        assert!(unregister("greeting"));
+        assert!(!unregister("greeting"));
+        assert!(render("greeting", &HashMap::new()).is_err());
+    }
+}
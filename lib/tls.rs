@@ -0,0 +1,294 @@
+//! TLS for `ipps://` and `https://` connections
+//!
+//! Servers are verified against the system trust store, plus any CA bundle
+//! configured for the destination, since printers often carry certificates
+//! from an internal CA. Verification can also be turned off per destination
+//! for printers with self-signed certificates; the connection is then
+//! encrypted but the printer is not authenticated.
+//!
+//! Options are set for a destination (`host` or `host:port`, or a URI whose
+//! host and port are used) or as the default for all of them.
+
+use crate::device::DeviceAddress;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{
+    ClientConfig, ClientConnection, DigitallySignedStruct, RootCertStore, SignatureScheme,
+    StreamOwned,
+};
+use std::collections::HashMap;
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+
+lazy_static::lazy_static! {
+    static ref REGISTRY: RwLock<Registry> = RwLock::new(Registry::default());
+    /// Client configurations by options, as loading the system trust store
+    /// is slow
+    static ref CONFIGS: Mutex<HashMap<TlsOptions, Arc<ClientConfig>>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Default)]
+struct Registry {
+    default: TlsOptions,
+    /// By `host:port` or `host`
+    destinations: HashMap<String, TlsOptions>,
+}
+
+/// How to verify a TLS server
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TlsOptions {
+    /// PEM file of CA certificates trusted in addition to the system's
+    pub ca_file: Option<PathBuf>,
+    /// Accept any certificate
+    pub insecure_skip_verify: bool,
+}
+
+/// A TLS connection to a device
+pub type TlsStream = StreamOwned<ClientConnection, TcpStream>;
+
+/// Registry key for a destination: `host:port` from a URI, otherwise the
+/// `host` or `host:port` as given
+fn destination_key(destination: &str) -> Result<String, String> {
+    let destination = destination.trim();
+    if destination.contains("://") {
+        return DeviceAddress::parse(destination)
+            .map(|address| address.authority().to_lowercase())
+            .ok_or_else(|| format!("Invalid destination URI '{}'", destination));
+    }
+    if destination.is_empty() {
+        return Err("Destination must not be empty".to_string());
+    }
+    Ok(destination.to_lowercase())
+}
+
+/// Set the TLS options for a destination, or the default for every
+/// destination if `destination` is None. None restores the defaults.
+pub fn set(destination: Option<&str>, options: Option<TlsOptions>) -> Result<(), String> {
+    if let Some(ca_file) = options
+        .as_ref()
+        .and_then(|options| options.ca_file.as_ref())
+    {
+        load_ca_file(ca_file)?;
+    }
+    let destination = destination.map(destination_key).transpose()?;
+
+    if options
+        .as_ref()
+        .is_some_and(|options| options.insecure_skip_verify)
+    {
+        tracing::warn!(
+            destination = destination.as_deref().unwrap_or("*"),
+            "TLS certificate verification disabled"
+        );
+    }
+    let mut registry = REGISTRY.write().unwrap();
+    match (destination, options) {
+        (None, options) => registry.default = options.unwrap_or_default(),
+        (Some(destination), Some(options)) => {
+            registry.destinations.insert(destination, options);
+        }
+        (Some(destination), None) => {
+            registry.destinations.remove(&destination);
+        }
+    }
+    // A CA file may have changed on disk
+    CONFIGS.lock().unwrap().clear();
+    Ok(())
+}
+
+/// Options for connecting to an address: its `host:port`, then its host,
+/// then the default
+pub fn options_for(address: &DeviceAddress) -> TlsOptions {
+    let registry = REGISTRY.read().unwrap();
+    let host = address.host.to_lowercase();
+    registry
+        .destinations
+        .get(&address.authority().to_lowercase())
+        .or_else(|| registry.destinations.get(&host))
+        .unwrap_or(&registry.default)
+        .clone()
+}
+
+/// Negotiate TLS on a connected socket, completing the handshake so that
+/// certificate problems are reported as such
+pub fn connect(address: &DeviceAddress, socket: TcpStream) -> Result<TlsStream, String> {
+    let options = options_for(address);
+    let config = client_config(&options)?;
+    let server_name = ServerName::try_from(address.host.clone())
+        .map_err(|e| format!("Invalid TLS server name '{}': {}", address.host, e))?;
+    let connection = ClientConnection::new(config, server_name)
+        .map_err(|e| format!("Failed to set up TLS: {}", e))?;
+
+    let mut stream = StreamOwned::new(connection, socket);
+    while stream.conn.is_handshaking() {
+        if let Err(e) = stream.conn.complete_io(&mut stream.sock) {
+            let hint = if e.to_string().contains("certificate") && !options.insecure_skip_verify {
+                "; trust its CA with a caFile, or set insecureSkipVerify for the destination"
+            } else {
+                ""
+            };
+            return Err(format!(
+                "TLS handshake with {} failed: {}{}",
+                address.authority(),
+                e,
+                hint
+            ));
+        }
+    }
+    Ok(stream)
+}
+
+fn client_config(options: &TlsOptions) -> Result<Arc<ClientConfig>, String> {
+    if let Some(config) = CONFIGS.lock().unwrap().get(options) {
+        return Ok(Arc::clone(config));
+    }
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = ClientConfig::builder_with_provider(Arc::clone(&provider))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("Failed to set up TLS: {}", e))?;
+    let config = if options.insecure_skip_verify {
+        builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(SkipVerification(provider)))
+            .with_no_client_auth()
+    } else {
+        builder
+            .with_root_certificates(root_store(options.ca_file.as_deref())?)
+            .with_no_client_auth()
+    };
+
+    let config = Arc::new(config);
+    CONFIGS
+        .lock()
+        .unwrap()
+        .insert(options.clone(), Arc::clone(&config));
+    Ok(config)
+}
+
+/// The system trust store plus the certificates in `ca_file`
+fn root_store(ca_file: Option<&Path>) -> Result<RootCertStore, String> {
+    let mut roots = RootCertStore::empty();
+    let native = rustls_native_certs::load_native_certs();
+    for error in &native.errors {
+        tracing::debug!("Failed to load system certificates: {}", error);
+    }
+    let (added, ignored) = roots.add_parsable_certificates(native.certs);
+    tracing::debug!(added, ignored, "Loaded system trust store");
+
+    if let Some(ca_file) = ca_file {
+        for certificate in load_ca_file(ca_file)? {
+            roots
+                .add(certificate)
+                .map_err(|e| format!("Invalid certificate in '{}': {}", ca_file.display(), e))?;
+        }
+    }
+    Ok(roots)
+}
+
+fn load_ca_file(path: &Path) -> Result<Vec<CertificateDer<'static>>, String> {
+    let certificates = CertificateDer::pem_file_iter(path)
+        .and_then(|certificates| certificates.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| format!("Failed to read CA file '{}': {}", path.display(), e))?;
+    if certificates.is_empty() {
+        return Err(format!("CA file '{}' has no certificates", path.display()));
+    }
+    Ok(certificates)
+}
+
+/// Accepts any server certificate, still checking that the server holds
+/// its key
+#[derive(Debug)]
+struct SkipVerification(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for SkipVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+
+    #[test]
+    #[serial]
+    fn test_tls_options() {
+        let insecure = TlsOptions {
+            ca_file: None,
+            insecure_skip_verify: true,
+        };
+        set(
+            Some("ipps://Printer.local/ipp/print"),
+            Some(insecure.clone()),
+        )
+        .unwrap();
+        set(Some("10.0.0.5"), Some(insecure.clone())).unwrap();
+
+        let address = |uri| DeviceAddress::parse(uri).unwrap();
+        assert_eq!(options_for(&address("ipps://printer.local:631/")), insecure);
+        assert_eq!(
+            options_for(&address("ipps://printer.local:8443/")),
+            TlsOptions::default()
+        );
+        assert_eq!(options_for(&address("https://10.0.0.5/")), insecure);
+        assert!(client_config(&insecure).is_ok());
+
+        let missing = TlsOptions {
+            ca_file: Some(PathBuf::from("/nonexistent/ca.pem")),
+            insecure_skip_verify: false,
+        };
+        assert!(set(None, Some(missing)).is_err());
+        assert!(set(Some(""), None).is_err());
+
+        set(Some("ipps://printer.local/"), None).unwrap();
+        set(Some("10.0.0.5"), None).unwrap();
+        assert_eq!(
+            options_for(&address("https://10.0.0.5/")),
+            TlsOptions::default()
+        );
+    }
+}
@@ -0,0 +1,411 @@
+//! Webhook notifications for job lifecycle events
+//!
+//! When a webhook is set, job submissions, completions and failures are
+//! POSTed to it as JSON from a background thread, so print servers can
+//! integrate with other systems without a JavaScript event loop. Deliveries
+//! are made one at a time in the order the events happened. An attempt that
+//! fails to connect, times out or gets a 408, 429 or 5xx response is retried
+//! with exponential backoff; other responses are final.
+//!
+//! With a secret, each request carries an `X-Printers-Signature` header of
+//! `sha256=` followed by the hex HMAC-SHA256 of the body, so the receiver can
+//! check that it came from this library. `https://` endpoints are verified
+//! like other TLS destinations (see `tls`) and need the `tls` feature.
+
+use crate::core::PrinterJob;
+use crate::crypto::{hex, hmac_sha256};
+use crate::device::DeviceAddress;
+use crate::supervisor::{self, RestartPolicy};
+use serde_json::json;
+use std::io::{Read, Write};
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// Retries after a failed first attempt, unless configured otherwise
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+/// Timeout of each attempt, unless configured otherwise
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Events waiting for delivery before new ones are dropped
+const QUEUE_CAPACITY: usize = 1024;
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+lazy_static::lazy_static! {
+    static ref WEBHOOK: RwLock<Option<Arc<WebhookConfig>>> = RwLock::new(None);
+    static ref QUEUE: Mutex<Option<SyncSender<Delivery>>> = Mutex::new(None);
+}
+
+/// Job lifecycle event sent to the webhook
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WebhookEvent {
+    /// The job was accepted into the queue or held for approval
+    Submitted,
+    Completed,
+    /// The job failed or timed out
+    Failed,
+}
+
+impl WebhookEvent {
+    pub fn parse(event: &str) -> Option<WebhookEvent> {
+        match event {
+            "job.submitted" => Some(WebhookEvent::Submitted),
+            "job.completed" => Some(WebhookEvent::Completed),
+            "job.failed" => Some(WebhookEvent::Failed),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEvent::Submitted => "job.submitted",
+            WebhookEvent::Completed => "job.completed",
+            WebhookEvent::Failed => "job.failed",
+        }
+    }
+}
+
+/// Where and how job events are delivered
+#[derive(Clone, Debug, PartialEq)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// Key requests are signed with (None = unsigned)
+    pub secret: Option<String>,
+    /// Events to send (empty = all)
+    pub events: Vec<WebhookEvent>,
+    /// Retries after a failed first attempt
+    pub max_retries: u32,
+    /// Connect, send and response timeout of each attempt
+    pub timeout: Duration,
+}
+
+impl WebhookConfig {
+    pub fn new(url: &str) -> Self {
+        WebhookConfig {
+            url: url.to_string(),
+            secret: None,
+            events: Vec::new(),
+            max_retries: DEFAULT_MAX_RETRIES,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+/// A queued event, sent with the configuration it was raised under
+struct Delivery {
+    config: Arc<WebhookConfig>,
+    event: WebhookEvent,
+    id: String,
+    body: Vec<u8>,
+}
+
+/// Why an attempt failed, and whether it is worth retrying
+struct AttemptError {
+    message: String,
+    retry: bool,
+}
+
+impl AttemptError {
+    fn retry(message: String) -> Self {
+        AttemptError {
+            message,
+            retry: true,
+        }
+    }
+
+    fn fatal(message: String) -> Self {
+        AttemptError {
+            message,
+            retry: false,
+        }
+    }
+}
+
+fn endpoint(url: &str) -> Result<DeviceAddress, String> {
+    let address =
+        DeviceAddress::parse(url).ok_or_else(|| format!("Invalid webhook URL '{}'", url))?;
+    match address.scheme.as_str() {
+        "http" | "https" => Ok(address),
+        _ => Err(format!(
+            "Webhook URL '{}' must be an http:// or https:// URL",
+            url
+        )),
+    }
+}
+
+/// Set the webhook job events are sent to, replacing any previous one, or
+/// stop sending events. Events already queued are still delivered.
+pub fn set(config: Option<WebhookConfig>) -> Result<(), String> {
+    if let Some(config) = &config {
+        endpoint(&config.url)?;
+        tracing::info!(url = %config.url, "Webhook set");
+    }
+    *WEBHOOK.write().unwrap() = config.map(Arc::new);
+    Ok(())
+}
+
+pub fn get() -> Option<WebhookConfig> {
+    WEBHOOK.read().unwrap().as_deref().cloned()
+}
+
+/// Queue a job event for delivery, if a webhook is set and wants it
+pub fn notify(event: WebhookEvent, job: &PrinterJob) {
+    let Some(config) = WEBHOOK.read().unwrap().clone() else {
+        return;
+    };
+    if !config.events.is_empty() && !config.events.contains(&event) {
+        return;
+    }
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let timestamp = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let body = json!({
+        "id": id,
+        "event": event.as_str(),
+        "timestamp": timestamp,
+        "job": crate::export::job_json(job),
+    });
+    let delivery = Delivery {
+        config,
+        event,
+        id,
+        body: body.to_string().into_bytes(),
+    };
+
+    let mut queue = QUEUE.lock().unwrap();
+    let sender = queue.get_or_insert_with(start_worker);
+    match sender.try_send(delivery) {
+        Ok(()) => {}
+        Err(TrySendError::Full(delivery)) => {
+            tracing::warn!(
+                job_id = job.id,
+                event = delivery.event.as_str(),
+                "Webhook queue is full, dropping event"
+            );
+        }
+        Err(TrySendError::Disconnected(delivery)) => {
+            // The worker is gone; start a new one for the next event
+            *queue = None;
+            tracing::warn!(
+                job_id = job.id,
+                event = delivery.event.as_str(),
+                "Webhook worker stopped, dropping event"
+            );
+        }
+    }
+}
+
+fn start_worker() -> SyncSender<Delivery> {
+    let (sender, receiver) = mpsc::sync_channel::<Delivery>(QUEUE_CAPACITY);
+    // The queue outlives a restart, so a panic loses only the delivery
+    // being sent
+    supervisor::spawn("webhook-worker", RestartPolicy::BACKGROUND, move || {
+        for delivery in receiver.iter() {
+            deliver(&delivery);
+        }
+    });
+    sender
+}
+
+/// Whether the webhook a delivery was raised under is still the one set
+fn still_current(config: &Arc<WebhookConfig>) -> bool {
+    WEBHOOK
+        .read()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|current| Arc::ptr_eq(current, config))
+}
+
+/// Send a delivery, retrying while the webhook is unchanged
+fn deliver(delivery: &Delivery) {
+    let mut backoff = INITIAL_BACKOFF;
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let error = match post(delivery) {
+            Ok(()) => {
+                tracing::debug!(
+                    event = delivery.event.as_str(),
+                    delivery = %delivery.id,
+                    attempt,
+                    "Webhook delivered"
+                );
+                return;
+            }
+            Err(error) => error,
+        };
+        if !error.retry || attempt > delivery.config.max_retries {
+            tracing::warn!(
+                url = %delivery.config.url,
+                event = delivery.event.as_str(),
+                delivery = %delivery.id,
+                attempt,
+                "Webhook delivery failed: {}",
+                error.message
+            );
+            return;
+        }
+
+        tracing::debug!(
+            event = delivery.event.as_str(),
+            delivery = %delivery.id,
+            attempt,
+            "Webhook delivery failed, retrying: {}",
+            error.message
+        );
+        thread::sleep(backoff);
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+        if !still_current(&delivery.config) {
+            tracing::debug!(delivery = %delivery.id, "Webhook changed, abandoning delivery");
+            return;
+        }
+    }
+}
+
+/// Make one delivery attempt
+fn post(delivery: &Delivery) -> Result<(), AttemptError> {
+    let config = &delivery.config;
+    let address = endpoint(&config.url).map_err(AttemptError::fatal)?;
+    let mut stream = address.open(config.timeout).map_err(AttemptError::retry)?;
+
+    let mut header = format!(
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nUser-Agent: printers-js\r\nX-Printers-Event: {}\r\nX-Printers-Delivery: {}\r\n",
+        if address.path.is_empty() {
+            "/"
+        } else {
+            address.path.as_str()
+        },
+        address.authority(),
+        delivery.body.len(),
+        delivery.event.as_str(),
+        delivery.id
+    );
+    if let Some(secret) = &config.secret {
+        let signature = hmac_sha256(secret.as_bytes(), &delivery.body);
+        header.push_str(&format!(
+            "X-Printers-Signature: sha256={}\r\n",
+            hex(&signature)
+        ));
+    }
+    header.push_str("Connection: close\r\n\r\n");
+
+    stream
+        .write_all(header.as_bytes())
+        .and_then(|_| stream.write_all(&delivery.body))
+        .map_err(|e| AttemptError::retry(format!("Failed to send request: {}", e)))?;
+
+    match read_status(&mut stream)? {
+        200..=299 => Ok(()),
+        status @ (408 | 429 | 500..=599) => {
+            Err(AttemptError::retry(format!("HTTP error {}", status)))
+        }
+        status => Err(AttemptError::fatal(format!("HTTP error {}", status))),
+    }
+}
+
+/// Read the status code of an HTTP response, ignoring the rest
+fn read_status(stream: &mut dyn Read) -> Result<u16, AttemptError> {
+    let mut response = Vec::new();
+    let mut buf = [0u8; 256];
+    while !response.windows(2).any(|w| w == b"\r\n") && response.len() < 8192 {
+        let read = stream
+            .read(&mut buf)
+            .map_err(|e| AttemptError::retry(format!("Failed to read response: {}", e)))?;
+        if read == 0 {
+            break;
+        }
+        response.extend_from_slice(&buf[..read]);
+    }
+    String::from_utf8_lossy(&response)
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| AttemptError::retry("Malformed HTTP status line".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    /// Accept one request, answer it with `status` and return it
+    fn serve_once(listener: &TcpListener, status: u16) -> String {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            let read = stream.read(&mut buf).unwrap();
+            request.extend_from_slice(&buf[..read]);
+            let text = String::from_utf8_lossy(&request);
+            if let Some((headers, body)) = text.split_once("\r\n\r\n") {
+                let length: usize = headers
+                    .lines()
+                    .find_map(|line| line.strip_prefix("Content-Length: "))
+                    .unwrap()
+                    .parse()
+                    .unwrap();
+                if body.len() >= length {
+                    break;
+                }
+            }
+        }
+        write!(stream, "HTTP/1.1 {} X\r\nContent-Length: 0\r\n\r\n", status).unwrap();
+        String::from_utf8(request).unwrap()
+    }
+
+    #[test]
+    fn test_post_signed_delivery() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut config = WebhookConfig::new(&format!(
+            "http://{}/hooks/print",
+            listener.local_addr().unwrap()
+        ));
+        config.secret = Some("s3cret".to_string());
+        let delivery = Delivery {
+            config: Arc::new(config),
+            event: WebhookEvent::Completed,
+            id: "delivery-1".to_string(),
+            body: br#"{"event":"job.completed"}"#.to_vec(),
+        };
+
+        let server = thread::spawn(move || {
+            let first = serve_once(&listener, 503);
+            let second = serve_once(&listener, 400);
+            (first, second)
+        });
+        let error = post(&delivery).unwrap_err();
+        assert!(error.retry, "{}", error.message);
+        let error = post(&delivery).unwrap_err();
+        assert!(!error.retry, "{}", error.message);
+
+        let (request, _) = server.join().unwrap();
+        assert!(request.starts_with("POST /hooks/print HTTP/1.1\r\n"));
+        assert!(request.contains("X-Printers-Event: job.completed\r\n"));
+        assert!(request.contains("X-Printers-Delivery: delivery-1\r\n"));
+        let signature = hex(&hmac_sha256(b"s3cret", &delivery.body));
+        assert!(request.contains(&format!("X-Printers-Signature: sha256={}\r\n", signature)));
+        assert!(request.ends_with(r#"{"event":"job.completed"}"#));
+    }
+
+    #[test]
+    fn test_webhook_urls() {
+        assert!(endpoint("http://hooks.example.com/print").is_ok());
+        assert_eq!(
+            endpoint("https://hooks.example.com/print").unwrap().port,
+            443
+        );
+        assert!(endpoint("ftp://hooks.example.com").is_err());
+        assert!(set(Some(WebhookConfig::new("not a url"))).is_err());
+        assert_eq!(
+            WebhookEvent::parse("job.failed"),
+            Some(WebhookEvent::Failed)
+        );
+        assert_eq!(WebhookEvent::parse("failed"), None);
+    }
+}
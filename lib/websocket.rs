@@ -0,0 +1,189 @@
+//! Server side of the WebSocket protocol (RFC 6455)
+//!
+//! Covers what an event stream needs: the opening handshake, unfragmented
+//! frames from the server, and reading the client's control frames. Data
+//! frames from clients are read and discarded.
+
+use std::io::{self, Read};
+
+/// GUID appended to the client's key to compute Sec-WebSocket-Accept
+const HANDSHAKE_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+/// Largest client frame accepted; clients only send control frames
+const MAX_CLIENT_PAYLOAD: u64 = 64 * 1024;
+
+pub const OPCODE_TEXT: u8 = 0x1;
+pub const OPCODE_CLOSE: u8 = 0x8;
+pub const OPCODE_PING: u8 = 0x9;
+pub const OPCODE_PONG: u8 = 0xA;
+
+/// Sec-WebSocket-Accept value for a client's Sec-WebSocket-Key
+pub fn accept_key(key: &str) -> String {
+    crate::encoding::base64(&sha1(
+        format!("{}{}", key.trim(), HANDSHAKE_GUID).as_bytes(),
+    ))
+}
+
+/// Response completing the opening handshake
+pub fn handshake_response(key: &str) -> Vec<u8> {
+    format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+         Sec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(key)
+    )
+    .into_bytes()
+}
+
+/// An unmasked, unfragmented server frame
+pub fn frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(payload.len() + 10);
+    frame.push(0x80 | opcode);
+    match payload.len() {
+        len if len < 126 => frame.push(len as u8),
+        len if len <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// A frame read from a client
+#[derive(Debug, PartialEq)]
+pub struct ClientFrame {
+    pub opcode: u8,
+    pub payload: Vec<u8>,
+}
+
+/// Read one client frame, unmasking its payload
+pub fn read_frame(stream: &mut impl Read) -> io::Result<ClientFrame> {
+    let mut header = [0u8; 2];
+    stream.read_exact(&mut header)?;
+    let opcode = header[0] & 0x0F;
+    let masked = header[1] & 0x80 != 0;
+    let len = match header[1] & 0x7F {
+        126 => {
+            let mut len = [0u8; 2];
+            stream.read_exact(&mut len)?;
+            u16::from_be_bytes(len) as u64
+        }
+        127 => {
+            let mut len = [0u8; 8];
+            stream.read_exact(&mut len)?;
+            u64::from_be_bytes(len)
+        }
+        len => len as u64,
+    };
+    if !masked {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Client frames must be masked",
+        ));
+    }
+    if len > MAX_CLIENT_PAYLOAD {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Client frame is too large",
+        ));
+    }
+
+    let mut mask = [0u8; 4];
+    stream.read_exact(&mut mask)?;
+    let mut payload = vec![0u8; len as usize];
+    stream.read_exact(&mut payload)?;
+    for (i, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[i % 4];
+    }
+    Ok(ClientFrame { opcode, payload })
+}
+
+/// SHA-1 (FIPS 180-4), which the handshake requires
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+
+        for (state, value) in h.iter_mut().zip([a, b, c, d, e]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+
+    let mut digest = [0u8; 20];
+    for (chunk, word) in digest.chunks_mut(4).zip(h) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handshake_accept_key() {
+        let hex: String = sha1(b"abc").iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(hex, "a9993e364706816aba3e25717850c26c9cd0d89d");
+        // Example from RFC 6455 section 1.3
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+    }
+
+    #[test]
+    fn test_frames() {
+        assert_eq!(frame(OPCODE_TEXT, b"hi"), [0x81, 2, b'h', b'i']);
+        let long = frame(OPCODE_TEXT, &[0u8; 300]);
+        assert_eq!(&long[..4], &[0x81, 126, 1, 44]);
+
+        let mask = [1u8, 2, 3, 4];
+        let mut client = vec![0x80 | OPCODE_PING, 0x80 | 3];
+        client.extend_from_slice(&mask);
+        client.extend(b"abc".iter().zip(mask).map(|(b, m)| b ^ m));
+        let frame = read_frame(&mut client.as_slice()).unwrap();
+        assert_eq!(frame.opcode, OPCODE_PING);
+        assert_eq!(frame.payload, b"abc");
+
+        // Unmasked client frames are a protocol error
+        assert!(read_frame(&mut [0x81u8, 0].as_slice()).is_err());
+    }
+}
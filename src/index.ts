@@ -20,6 +20,17 @@ export enum PrintError {
   PrinterNotFound = 6,
   FileNotFound = 7,
   SimulatedFailure = 8,
+  ShuttingDown = 9,
+  InvalidDocument = 10,
+  SpoolQuotaExceeded = 11,
+  SpoolFailed = 12,
+  JobNotFound = 13,
+  NoPrinterAvailable = 14,
+  QueueFull = 15,
+  DuplicateJob = 16,
+  PrinterRemoved = 17,
+  UnsupportedOption = 18,
+  PolicyViolation = 19,
 }
 
 // CUPS Printing Options Types
@@ -267,6 +278,11 @@ export interface SimplePrintOptions {
   pageRange?: string;
   /** Job name for identification */
   jobName?: string;
+  /**
+   * User the job is submitted on behalf of.
+   * Sent as IPP requesting-user-name on CUPS and set as the job owner on Windows.
+   */
+  requestingUser?: string;
   /** Pages per sheet */
   pagesPerSheet?: NumberUp;
   /** Print in landscape orientation */
@@ -280,7 +296,16 @@ export type PrinterJobState =
   | "processing" // Job currently being printed
   | "cancelled" // Job cancelled by user or system
   | "completed" // Job finished successfully
-  | "unknown"; // Undetermined state
+  | "failed" // Job gave up, e.g. after its timeout
+  | "unknown" // Undetermined state
+  | "held-for-approval"; // Job spooled locally until approved or rejected
+
+/** How a job's media type was determined */
+export type MediaTypeSource =
+  | "option" // documentFormat job option (sent to CUPS as document-format)
+  | "extension" // File extension
+  | "content" // Magic bytes, when the extension was missing or generic
+  | "default"; // Nothing matched, or raw bytes
 
 /** Print job structure matching upstream printers crate */
 export interface PrinterJob {
@@ -288,12 +313,450 @@ export interface PrinterJob {
   name: string; // Job title/description
   state: PrinterJobState; // Current job status
   mediaType: string; // File type (e.g., "application/pdf")
+  mediaTypeSource: MediaTypeSource; // How mediaType was determined
   createdAt: number; // Job creation timestamp (Unix timestamp)
   processedAt?: number; // Processing start time (Unix timestamp, optional)
   completedAt?: number; // Job completion time (Unix timestamp, optional)
   printerName: string; // Associated printer name
   errorMessage?: string; // Error details if failed
   ageSeconds: number; // Age in seconds for convenience
+  sizeBytes: number; // Size of the submitted document in bytes
+  totalPages?: number; // Pages in the document (PDF only), if known
+  pages?: number; // Pages printed including copies, if known
+  sheets?: number; // Physical sheets used, if known
+  owner?: string; // User the job was submitted for (requestingUser or process user)
+  reprintOf?: number; // ID of the job this one reprints (see reprintJob)
+  timedOut: boolean; // Failed because its timeout expired
+  stalled: boolean; // Processing past the stall threshold (see onJobStalled)
+  printerRemoved: boolean; // Failed because its printer was removed (see migrateJobs)
+  encryptedTransport?: boolean; // Sent to a network printer over TLS; unset if local
+  metadata?: Record<string, string>; // Values the job was submitted with (see findJobs)
+  transitions: JobTransition[]; // State changes, oldest first
+}
+
+/** A change of a job's state */
+export interface JobTransition {
+  from: PrinterJobState;
+  to: PrinterJobState;
+  timestamp: number; // Unix timestamp in seconds
+}
+
+/**
+ * What the watchdog does with a job processing past the stall threshold:
+ * - flag: report the job and leave it running
+ * - cancel: fail the job; the spooler job is cancelled if accepted later
+ * - retry: fail the job and resubmit it once
+ */
+export type StallAction = "flag" | "cancel" | "retry";
+
+/** A job was processing for longer than the stall threshold */
+export interface JobStalledEvent {
+  jobId: number;
+  printerName: string;
+  processingMs: number; // How long the job had been processing
+  action: StallAction; // Action taken; "cancel" for a retry that stalled again
+  retryJobId?: number; // Job resubmitted in place of the stalled one
+  timestamp: number; // Unix timestamp in seconds
+}
+
+/** Callback receiving stalled jobs */
+export type JobStalledListener = (event: JobStalledEvent) => void;
+
+/** Jobs moved from one printer to another by migrateJobs */
+export interface JobMigration {
+  moved: number[]; // Queued jobs now waiting for the new printer
+  requeued: { jobId: number; newJobId: number }[]; // Failed jobs and their reprints
+  unavailable: number[]; // Failed jobs whose payload and file are both gone
+}
+
+/** Outcome of printing to one printer with printToMany */
+export interface PrintDestinationResult {
+  printerName: string;
+  jobId?: number; // Set if the job was submitted
+  error?: string; // Why submission failed, or the job failed when waiting
+  errorCode?: ErrorCode; // Code of the submission error (see setErrorMessageFormatter)
+}
+
+/** What a print submission would do, from a dry run */
+export interface DryRunResult {
+  printerName: string; // Printer the job would go to, after resolving aliases
+  jobName?: string;
+  options: Record<string, string>; // Raw options after applying defaults
+  mediaType: string; // Document format the job would be sent as
+  mediaTypeSource: MediaTypeSource;
+  sizeBytes?: number; // Size of the document, if it could be read
+  documentPages?: number; // Pages in the document, if known
+  pages?: number; // Estimated pages printed, including copies
+  sheets?: number; // Estimated sheets used, including copies and duplex
+  capabilitiesChecked: boolean; // Whether the printer reported capabilities
+  unsupportedOptions: string[]; // e.g. "sides=two-sided-long-edge (supported: one-sided)"
+  convertsToGray: boolean; // Whether the document is converted to gray
+}
+
+/** Where an option resolved by resolveJobOptions came from */
+export type OptionSource =
+  | "library" // Library-wide defaultJobOptions
+  | "printer" // Printer defaults (see setPrinterDefaults)
+  | "alias" // Default options of the alias the job was submitted through
+  | "call" // The options passed with the job
+  | "derived"; // Set from a typed option, e.g. print-quality for quality
+
+/** Options a job would be sent with, from resolveJobOptions */
+export interface ResolvedJobOptions {
+  printerName: string; // Printer the job would go to, after resolving aliases
+  options: Record<string, string>; // Raw properties, typed options as job-* keys
+  sources: Record<string, OptionSource>; // Where each option came from
+}
+
+/** Expected usage of a print job, from estimateJob */
+export interface JobEstimate {
+  printerName: string; // Printer the job would go to, after resolving aliases
+  documentPages?: number; // Pages in the document, if known
+  pages?: number; // Pages printed, including copies and page ranges
+  sheets?: number; // Physical sheets used, including copies, number-up and duplex
+  copies: number;
+  duplex: boolean;
+  color: "color" | "monochrome";
+}
+
+/** Combined result of printToMany */
+export interface PrintToManyResult {
+  results: PrintDestinationResult[]; // One entry per printer, in order
+  succeeded: number; // Destinations without an error
+  failed: number; // Destinations with an error
+}
+
+/** How a printer pool picks the printer for a job */
+export type PoolStrategy =
+  | "failover" // First available printer, in the order given
+  | "round-robin" // Next available printer after the one used last
+  | "least-busy"; // Available printer with the fewest queued jobs
+
+/** Options for createPrinterPool */
+export interface PrinterPoolOptions {
+  strategy?: PoolStrategy; // Default: "failover"
+  maxQueueDepth?: number; // Skip printers with this many queued jobs
+}
+
+/** A logical printer name mapped to a concrete printer */
+export interface PrinterAlias {
+  name: string; // e.g. "RECEIPT"
+  printer: string; // Concrete printer name
+  defaultOptions: Record<string, string>; // Raw job properties for its jobs
+}
+
+/**
+ * How documents sent to a remote IPP printer are compressed. "auto" uses the
+ * best coding the printer lists in compression-supported, for documents of
+ * at least 4 KiB.
+ */
+export type RemoteCompression = "none" | "auto" | "gzip" | "deflate";
+
+/**
+ * When documents sent to a remote IPP printer are converted to raster for
+ * driverless printers. "auto" converts documents the printer doesn't list in
+ * document-format-supported to PWG or Apple raster; "pwg" and "urf" always
+ * convert.
+ */
+export type RemoteRaster = "none" | "auto" | "pwg" | "urf";
+
+/** Where a remote printer forwards jobs to */
+export interface RemotePrinterOptions {
+  url: string; // "http://" print server base URL or "ipp://" printer URI
+  printer?: string; // Printer on the remote print server (default: same name)
+  authToken?: string; // Bearer token for the remote print server
+  pollIntervalMs?: number; // How often remote jobs are polled (default: 2000)
+  timeoutMs?: number; // Timeout of each request (default: 30000)
+  compression?: RemoteCompression; // IPP only (default: "none")
+  raster?: RemoteRaster; // IPP only (default: "none")
+}
+
+/** A printer whose jobs are forwarded to another instance */
+export interface RemotePrinter {
+  name: string; // Local printer name
+  url: string;
+  printer?: string;
+  pollIntervalMs: number;
+  timeoutMs: number;
+  compression: RemoteCompression;
+  raster: RemoteRaster;
+}
+
+/** Credentials for a protected CUPS queue or IPP printer */
+export interface PrinterCredentials {
+  method?: "basic" | "negotiate"; // Default: "basic"
+  username?: string; // Required for basic; for negotiate, the Kerberos user
+  password?: string; // Required for basic
+}
+
+/** How to verify the TLS certificate of an ipps:// or https:// destination */
+export interface TlsOptions {
+  caFile?: string; // PEM CA bundle trusted in addition to the system store
+  insecureSkipVerify?: boolean; // Accept any certificate (self-signed printers)
+}
+
+/** How to wake a sleeping printer with Wake-on-LAN */
+export interface WakeOptions {
+  mac?: string; // MAC address (default: the one set with setPrinterMacAddress)
+  broadcast?: string; // IPv4 broadcast address (default: 255.255.255.255)
+  port?: number; // UDP port (default: 9)
+  timeoutMs?: number; // Wait up to this long for the printer to answer
+}
+
+/** Command language of a label printer */
+export type LabelLanguage = "zpl" | "escpos";
+
+/** Options for label printer maintenance commands */
+export interface LabelCommandOptions {
+  language?: LabelLanguage; // Detected from the printer if omitted
+}
+
+/** Conditions a ZPL label printer reports in its host status */
+export interface LabelStatus {
+  labelOut: boolean;
+  ribbonOut: boolean;
+  headOpen: boolean;
+  paused: boolean;
+  stateReasons: StateReason[]; // The conditions as state reasons
+}
+
+/** Whether a printer is awake */
+export interface PowerStatus {
+  powerState?: string; // PWG power state ("on", "standby", ...) if reported
+  asleep?: boolean; // Unset if the printer doesn't report its power state
+  reachable: boolean; // Whether the device answered
+}
+
+/** A print queue to install with installPrinter */
+export interface PrinterInstallation {
+  name: string;
+  port: string; // Existing port: "LPT1:", "FILE:", a Standard TCP/IP port, ...
+  driver: string; // Name of an installed driver (see listDrivers)
+  location?: string;
+  comment?: string;
+}
+
+/** An installed printer driver */
+export interface PrinterDriver {
+  name: string;
+  environment: string; // e.g. "Windows x64"
+  version: number; // 3 = v3 printer driver, 4 = v4
+  driverPath: string;
+}
+
+/** A CUPS queue to add with addCupsQueue */
+export interface CupsQueue {
+  name: string; // No spaces, "/", "\\", "#", quotes or ","
+  deviceUri: string; // e.g. "ipp://10.0.0.5/ipp/print" or "socket://10.0.0.5"
+  ppd?: string; // Path of a PPD file for the queue
+  model?: string; // Driver known to CUPS (lpinfo -m), e.g. "everywhere"
+  location?: string;
+  description?: string;
+  enabled?: boolean; // Start the queue and accept jobs (default: true)
+}
+
+/**
+ * Whether a printer is shared and who may print to it. When updating,
+ * fields left undefined keep their current values.
+ */
+export interface AccessControl {
+  shared?: boolean;
+  allowedUsers?: string[]; // Empty: everyone who isn't denied may print
+  deniedUsers?: string[];
+}
+
+/** Result of verifyAuditLog() */
+export interface AuditVerification {
+  valid: boolean; // Every record is intact and chained to the one before
+  records: number; // Records checked, up to and including the first invalid one
+  firstInvalidLine?: number;
+  error?: string; // Why the first invalid record failed
+  lastHash?: string; // Keep elsewhere to detect records removed from the end
+}
+
+/** A supervised background task in getRuntimeStatus() */
+export interface TaskStatus {
+  name: string; // e.g. "state-monitor", "history-sweeper" or "job-1001"
+  state: "running" | "restarting" | "failed";
+  restarts: number; // Times restarted after a panic
+  startedAt: number; // Unix timestamp in seconds
+  lastPanic?: string; // Message of the last panic
+}
+
+/** Background tasks of the native library */
+export interface RuntimeStatus {
+  tasks: TaskStatus[]; // Running, restarting, or given up after panics
+  jobThreads: number; // Threads printing jobs
+  monitoringRunning: boolean; // The state monitor is alive
+}
+
+/** Options for setPrinterAliasFile */
+export interface PrinterAliasFileOptions {
+  watch?: boolean; // Re-read the file whenever it changes on disk
+}
+
+/** A change to the printer alias configuration */
+export interface ConfigChangedEvent {
+  source: "api" | "file" | "reload"; // What triggered the change
+  aliases: string[]; // Aliases added, removed or changed
+  printers: string[]; // Printers whose default options or tags changed
+  defaultJobOptions: boolean; // Library-wide default job options changed
+  timestamp: number; // Unix timestamp in seconds
+}
+
+/** Callback receiving configuration changes */
+export type ConfigChangedListener = (event: ConfigChangedEvent) => void;
+
+/** Printer language of a template */
+export type TemplateFormat =
+  | "text" // Plain text
+  | "zpl" // Zebra Programming Language, sent raw
+  | "escpos"; // ESC/POS receipt printer commands, sent raw
+
+/** A registered print template */
+export interface PrintTemplate {
+  name: string;
+  format: TemplateFormat;
+  variables: string[]; // Variables the template uses, sorted by name
+}
+
+/** Options for registerTemplate */
+export interface RegisterTemplateOptions {
+  format?: TemplateFormat; // Default: "text"
+}
+
+/** A pool printer the strategy would have used was skipped */
+export interface PoolFailoverEvent {
+  from: string; // Printer that was skipped
+  to?: string; // Printer the job went to instead, if any was available
+  reason: string; // e.g. "offline", "not found", "media-empty" or "queue full"
+  timestamp: number; // Unix timestamp
+}
+
+/** Filter for usage aggregation */
+export interface UsageStatsQuery {
+  printerName?: string; // Restrict to a single printer
+  since?: Date | number; // Only jobs completed at or after this time (Date or Unix timestamp)
+}
+
+/** Order of queryJobs() results */
+export type JobSort =
+  | "created-asc"
+  | "created-desc" // Default: newest first
+  | "completed-asc" // Unfinished jobs first
+  | "completed-desc"; // Unfinished jobs last
+
+/** Filter, sort and paging for queryJobs(); omitted fields match every job */
+export interface JobQuery {
+  printerName?: string; // Restrict to a single printer
+  states?: PrinterJobState[]; // Restrict to jobs in these states
+  since?: Date | number; // Only jobs created at or after this time (Date or Unix timestamp)
+  until?: Date | number; // Only jobs created before this time (Date or Unix timestamp)
+  metadata?: Record<string, string>; // Values the job's metadata must have
+  sort?: JobSort;
+  offset?: number; // Matching jobs to skip
+  limit?: number; // Maximum number of jobs returned
+}
+
+/** A page of queryJobs() results */
+export interface JobPage {
+  jobs: PrinterJob[];
+  total: number; // Jobs matching the query, ignoring offset and limit
+}
+
+/** Job query as passed to the native module */
+type NativeJobQuery = Omit<JobQuery, "since" | "until"> & {
+  since?: number;
+  until?: number;
+};
+
+/** File format of exportJobHistory() */
+export type ExportFormat =
+  | "jsonl" // Default: one JSON object per line
+  | "csv"; // Header row, metadata as a JSON column
+
+/** Options for exportJobHistory() */
+export interface ExportJobHistoryOptions {
+  format?: ExportFormat;
+  since?: Date | number; // Only jobs created at or after this time (Date or Unix timestamp)
+}
+
+/** Job lifecycle event sent to a webhook */
+export type WebhookEvent =
+  | "job.submitted" // Queued, or held for approval
+  | "job.completed"
+  | "job.failed"; // Failed or timed out
+
+/** Endpoint job lifecycle events are POSTed to */
+export interface WebhookConfig {
+  url: string; // http:// or https:// endpoint
+  secret?: string; // HMAC-SHA256 key for the X-Printers-Signature header
+  events?: WebhookEvent[]; // Events to send (default: all)
+  maxRetries?: number; // Retries after a failed first attempt (default: 3)
+  timeoutMs?: number; // Timeout of each attempt (default: 10000)
+}
+
+/**
+ * Print policy rule checked when jobs are submitted. A job breaks the rule
+ * if it fails any of the conditions set; conditions on what isn't known,
+ * like the pages of a document that can't be counted, pass.
+ */
+export interface PolicyRule {
+  id: string; // Reported as PolicyViolationError.rule
+  maxPages?: number; // Most pages a job may print, copies included
+  allowedFormats?: string[]; // Media types, e.g. "application/pdf", "image/*"
+  colorUsers?: string[]; // Users allowed to print in color
+  bannedKeywords?: string[]; // Words the text may not contain, any case
+}
+
+/** Aggregated usage over completed jobs */
+export interface UsageStats {
+  jobs: number; // Number of completed jobs
+  pages: number; // Total pages printed
+  sheets: number; // Total physical sheets used
+  bytes: number; // Total document bytes submitted
+  jobsWithoutPageCount: number; // Completed jobs whose page count is unknown
+}
+
+/** Cumulative spool latency histogram bucket */
+export interface LatencyBucket {
+  le: number; // Upper bound in seconds
+  count: number; // Jobs with latency at or below the bound
+}
+
+/** Print health metrics since the library was loaded */
+export interface MetricsSnapshot {
+  jobsSubmitted: number;
+  jobsCompleted: number; // Jobs accepted by the spooler
+  jobsFailed: number;
+  queueDepth: number; // Jobs that haven't reached a final state
+  spoolLatency: {
+    buckets: LatencyBucket[];
+    count: number;
+    sum: number; // Total seconds
+  };
+}
+
+/** Minimum severity of delivered log events */
+export type LogLevel = "off" | "error" | "warn" | "info" | "debug" | "trace";
+
+/** Structured log event emitted by the native library */
+export interface LogRecord {
+  level: Exclude<LogLevel, "off">;
+  target: string; // Rust module that emitted the event, e.g. "printers_js::core"
+  message: string;
+  fields: Record<string, string>; // Structured context such as job_id or printer
+  timestamp: number; // Unix timestamp in seconds
+}
+
+/** Callback receiving native log events */
+export type LogCallback = (record: LogRecord) => void;
+
+/** A message in a job's log, see getJobLog */
+export interface JobLogEntry {
+  timestamp: number; // Unix timestamp in seconds
+  level: "error" | "warn" | "info" | "debug";
+  message: string; // State change, or message from the spooler or printer
 }
 
 /** Legacy interface for backward compatibility */
@@ -315,6 +778,272 @@ export type PrinterState =
   | "offline"
   | "unknown";
 
+/** Normalized printer state reason parsed from CUPS or Windows status */
+export type StateReason =
+  | "media-empty"
+  | "media-low"
+  | "media-jam"
+  | "media-needed"
+  | "toner-low"
+  | "toner-empty"
+  | "marker-supply-low"
+  | "marker-supply-empty"
+  | "cover-open"
+  | "input-tray-missing"
+  | "output-bin-full"
+  | "offline"
+  | "paused"
+  | "shutdown"
+  | "connecting-to-device"
+  | "spool-area-full"
+  | "user-intervention"
+  | "out-of-memory"
+  | "power-save"
+  | "label-out"
+  | "ribbon-out"
+  | "other";
+
+/** Tracked jobs by state */
+export interface JobStateCounts {
+  pending: number;
+  paused: number;
+  processing: number;
+  completed: number;
+  cancelled: number;
+  failed: number;
+}
+
+/** Printer status in a health report */
+export interface PrinterHealth {
+  name: string;
+  online: boolean; // Device reachable, as reported by isOnline()
+  blockingReasons: StateReason[]; // Conditions that stop it from printing
+}
+
+/** Structured library health, e.g. for a readiness probe */
+export interface HealthReport {
+  healthy: boolean; // Accepting jobs, spooler reachable, monitoring alive
+  acceptingJobs: boolean; // False while shutting down
+  spoolerReachable: boolean;
+  spoolerError?: string;
+  monitoringActive: boolean; // State monitoring was started
+  monitoringRunning: boolean; // The monitoring thread is alive
+  jobs: JobStateCounts;
+  activeWorkers: number; // Jobs currently handed to the spooler
+  workerPoolSize?: number; // Unset when unlimited
+  workerSaturation?: number; // activeWorkers / workerPoolSize, when limited
+  printers: PrinterHealth[];
+}
+
+/** Options for healthCheck */
+export interface HealthCheckOptions {
+  printers?: Array<string | Printer>; // Printers to probe (default: all)
+}
+
+/** Values a printer reports supporting for job attributes, via IPP */
+export interface PrinterCapabilities {
+  supported: Record<string, string[]>; // By attribute, e.g. { sides: [...] }
+  copiesMin?: number;
+  copiesMax?: number;
+  defaultColorMode?: string; // Color mode jobs print in unless they set one
+  finishings: string[]; // e.g. ["staple-top-left", "punch-dual-left"]
+  supportedOptions: string[]; // Raw properties accepted with strictOptions
+}
+
+/** Page description language a printer interprets */
+export type PrinterLanguage = "pdf" | "postscript" | "pcl" | "zpl";
+
+/** Document formats and languages a printer accepts */
+export interface SupportedFormats {
+  languages: PrinterLanguage[]; // Most preferred first
+  preferred?: PrinterLanguage; // Language to send documents in
+  preferredMediaType?: string; // e.g. "application/pdf"
+  documentFormats: string[]; // Media types listed over IPP, if reported
+  source: "ipp" | "driver" | "simulated" | "unknown"; // Where these came from
+}
+
+/** Consumable supply (toner, ink, drum, ...) reported via IPP marker attributes */
+export interface PrinterSupply {
+  name: string; // Supply name (e.g., "Black Toner")
+  type: string; // IPP marker type (e.g., "toner", "ink-cartridge", "opc")
+  color?: string; // sRGB hex color (e.g., "#00FFFF")
+  level?: number; // Remaining level in percent, if measurable
+  lowThreshold?: number; // Percent at which the printer reports the supply as low
+  isLow: boolean; // Whether level is at or below lowThreshold
+}
+
+/**
+ * Printer preferences stored in the Windows driver's DEVMODE.
+ * Fields left undefined are unchanged by setPreferences.
+ */
+export interface PrinterPreferences {
+  orientation?: "portrait" | "landscape";
+  paperSize?: number; // DMPAPER_* code (e.g., 1 = Letter, 9 = A4)
+  formName?: string; // Form name (e.g., "A4"); takes precedence over paperSize
+  quality?: "draft" | "low" | "medium" | "high"; // Driver quality level
+  resolution?: number; // Resolution in DPI, instead of a quality level
+  color?: boolean;
+  duplex?: "simplex" | "long-edge" | "short-edge";
+}
+
+/** Connection options for SNMP queries */
+export interface SnmpQueryOptions {
+  community?: string; // Community string (default "public")
+  port?: number; // UDP port (default 161)
+  version?: "1" | "2c"; // SNMP version (default "2c")
+  timeoutMs?: number; // Per-request timeout in milliseconds (default 2000)
+  retries?: number; // Retries per request after a timeout (default 1)
+}
+
+/** Entry of the Printer MIB alert table (prtAlertTable) */
+export interface SnmpAlert {
+  severity: "critical" | "warning" | "warning-binary-change" | "other";
+  group?: number; // prtAlertGroup (e.g., 8 = input, 11 = marker supplies)
+  code?: number; // prtAlertCode (e.g., 8 = cover open, 1101 = toner empty)
+  description: string;
+}
+
+/** Printer status read from the Host Resources and Printer MIBs */
+export interface SnmpPrinterStatus {
+  description?: string; // sysDescr
+  deviceStatus: "running" | "warning" | "testing" | "down" | "unknown";
+  printerStatus: "idle" | "printing" | "warmup" | "other" | "unknown";
+  stateReasons: StateReason[]; // Decoded from hrPrinterDetectedErrorState
+  pageCount?: number; // Lifetime page count (prtMarkerLifeCount)
+  alerts: SnmpAlert[];
+  supplies: PrinterSupply[];
+}
+
+/** Printer format rasterizeDocument converts to */
+export type RasterFormat =
+  | "pcl" // PCL 5, monochrome lasers
+  | "pclxl" // PCL 6
+  | "escp" // ESC/P, Epson-compatible dot matrix printers
+  | "pwg-raster" // PWG Raster, driverless IPP Everywhere printers
+  | "urf" // Apple Raster, driverless AirPrint printers
+  | "cups-raster"; // CUPS Raster, for label printer drivers
+
+/** Progress of a rasterizeDocument conversion */
+export interface ConversionProgress {
+  page: number; // Page being converted, counting from 1
+  pages?: number; // Pages to convert, once Ghostscript reports it
+}
+
+/** Options for rasterizeDocument */
+export interface RasterizeOptions {
+  format: RasterFormat;
+  resolution?: number; // Dots per inch (default depends on the format)
+  firstPage?: number;
+  lastPage?: number;
+  color?: boolean; // Color output, for "pclxl", "pwg-raster" and "urf"
+  onProgress?: (progress: ConversionProgress) => void; // Called per page
+}
+
+/** Options for renderPreview */
+export interface RenderPreviewOptions {
+  page?: number; // Page to render, counting from 1 (default 1)
+  dpi?: number; // Resolution in dots per inch (default 96)
+  printer?: string | Printer; // Apply its defaults, as for a job sent to it
+  options?: PrintJobOptions | Record<string, string>; // The job's options
+}
+
+interface NativeRenderPreviewOptions {
+  page?: number;
+  dpi?: number;
+  printerName?: string;
+  jobProperties?: Record<string, string>;
+}
+
+interface ConversionProgressEvent extends ConversionProgress {
+  conversionId: number;
+}
+
+/** Settings of a PJL job header added by wrapPjl */
+export interface PjlJobOptions {
+  name?: string; // Job name shown on the control panel (default "printers-js")
+  duplex?: "simplex" | "long-edge" | "short-edge";
+  tray?: string; // Input tray, e.g. "TRAY2" or "MANUALFEED"
+  language?: string; // "PDF", "POSTSCRIPT", "PCL", ...; detected if omitted
+}
+
+/** Printer status read back over PJL */
+export interface PjlStatus {
+  code?: number; // PJL status code, e.g. 10001 ready, 41xyy paper out
+  display?: string; // Control panel message
+  online?: boolean;
+  pageCount?: number; // Lifetime page count
+  severity?: "info" | "warning" | "error"; // Category of the status code
+}
+
+/** An IPP attribute in its text form, for sendIppRequest */
+export interface IppRawAttribute {
+  /** "operation" (default), "job", "printer", "subscription", ... */
+  group?: string;
+  name: string;
+  /** "keyword", "integer", "enum", "boolean", "text", "name", "uri", ... */
+  syntax: string;
+  /** Values in their text form, e.g. "600dpi", "1-10" or "true" */
+  values: string[];
+  /** Member attributes of each value, for syntax "collection" */
+  collections?: IppRawAttribute[][];
+}
+
+/** Attributes of one group of an IPP response */
+export interface IppRawGroup {
+  group: string; // "operation", "job", "printer", "unsupported", ...
+  attributes: IppRawAttribute[];
+}
+
+/** A decoded IPP response returned by sendIppRequest */
+export interface IppRawResponse {
+  statusCode: number; // IPP status, e.g. 0x0000 successful-ok
+  ok: boolean; // Whether statusCode is in the successful range
+  requestId: number;
+  groups: IppRawGroup[];
+}
+
+/** Options for sendIppRequest */
+export interface IppRequestOptions {
+  data?: Uint8Array; // Document data, for Print-Job or Send-Document
+  timeoutMs?: number; // Default 10000
+}
+
+/** Options for startIppServer */
+export interface IppServerOptions {
+  printerName: string; // Local printer or alias jobs are printed on
+  host?: string; // Address to listen on (default "127.0.0.1")
+  port?: number; // Port to listen on (default 8631, 0 = any free port)
+  path?: string; // Path of the queue (default "/ipp/print")
+  queueName?: string; // printer-name advertised to clients
+  maxDocumentBytes?: number; // Largest document accepted (default 64 MiB)
+}
+
+/** A running IPP server */
+export interface IppServer {
+  id: number;
+  uri: string; // URI clients print to, e.g. "ipp://127.0.0.1:8631/ipp/print"
+  host: string;
+  port: number;
+  printerName: string;
+}
+
+/** Options for startPrintServer */
+export interface PrintServerOptions {
+  host?: string; // Address to listen on (default "127.0.0.1")
+  port?: number; // Port to listen on (default 8632, 0 = any free port)
+  authToken?: string; // Bearer token; required off loopback addresses
+  maxBodyBytes?: number; // Largest request body accepted (default 64 MiB)
+  events?: boolean; // Stream job and printer events over WebSocket at /events
+}
+
+/** A running REST print server */
+export interface PrintServer {
+  id: number;
+  url: string; // Base URL of the API, e.g. "http://127.0.0.1:8632"
+  host: string;
+  port: number;
+}
+
 // ===== PRINTER STATE MONITORING INTERFACES =====
 
 /** Printer state change event types */
@@ -375,12 +1104,126 @@ export interface PrinterStateMonitorConfig {
   autoStart?: boolean;
 }
 
+/**
+ * How shutdown() treats outstanding jobs:
+ * - graceful: wait for jobs already handed to the spooler, cancel jobs not yet started
+ * - drain: finish all queued and in-flight jobs
+ * - force: cancel everything immediately
+ */
+export type ShutdownMode = "graceful" | "drain" | "force";
+
+/** Which JavaScript environment owns library-wide state, see attach() */
+export interface Attachment {
+  environments: number; // Environments (main thread, workers) using the library
+  attached: boolean; // Whether this environment owns the library
+  owned: boolean; // Whether any environment owns the library
+}
+
+/** Options for shutdown() */
+export interface ShutdownOptions {
+  /** Default: "graceful" */
+  mode?: ShutdownMode;
+  /** How long to wait for outstanding jobs before cancelling them (default: 5000) */
+  timeoutMs?: number;
+}
+
+/** Outcome of the jobs outstanding when shutdown() was called */
+export interface ShutdownSummary {
+  completed: number; // Jobs that finished during shutdown
+  cancelled: number; // Jobs marked cancelled, including timed out ones
+  timedOut: number; // Jobs still running when the timeout expired
+}
+
+/** Library-wide settings for initialize() and configure() */
+export interface LibraryConfig {
+  /** Maximum jobs handed to the spooler concurrently; 0 = unlimited (default) */
+  workerPoolSize?: number;
+  /** Default state monitoring poll interval in seconds (default: 2) */
+  monitoringInterval?: number;
+  /** State monitoring poll interval in milliseconds, overriding monitoringInterval */
+  monitoringIntervalMs?: number;
+  /** Milliseconds between checks of a job's state while waiting for it (default: 50) */
+  jobPollIntervalMs?: number;
+  /** Directory for temporary spool files; "" = system temp directory */
+  tempDir?: string;
+  /** Megabytes of print data held in spool files at once; 0 = unlimited (default) */
+  spoolQuotaMb?: number;
+  /** Force simulation on or off instead of reading PRINTERS_JS_SIMULATE */
+  simulate?: boolean;
+  /** How long a simulated job takes to print in milliseconds (default: 2000) */
+  simulationDelayMs?: number;
+  /** Options applied to every job unless the job sets them */
+  defaultJobOptions?: PrintJobOptions;
+  /** Completed/cancelled jobs kept before evicting the least recently used; 0 = unlimited (default) */
+  maxJobHistory?: number;
+  /** Seconds completed/cancelled jobs are kept; 0 = until removed (default) */
+  historyTtlSeconds?: number;
+  /** Milliseconds a job may be processing before it is stalled; 0 = no watchdog (default) */
+  stallThresholdMs?: number;
+  /** What the watchdog does with stalled jobs (default: "flag") */
+  stallAction?: StallAction;
+  /** Unfinished jobs per printer before submissions fail with QueueFullError; 0 = unlimited (default) */
+  maxQueuedJobsPerPrinter?: number;
+  /** Unfinished jobs across all printers before submissions fail with QueueFullError; 0 = unlimited (default) */
+  maxPendingJobs?: number;
+  /** Milliseconds a submission waits for room in a full queue; 0 = fail immediately (default) */
+  queueFullTimeoutMs?: number;
+  /** Directory jobs held for approval are persisted in (default: printers-js-held under the temp directory) */
+  heldJobsDir?: string;
+  /** File job events are appended to as a tamper-evident audit log; "" = none (default) */
+  auditLog?: string;
+  /** Record hashes instead of document names and paths of tracked jobs (default: false) */
+  redactJobData?: boolean;
+  /** File processes on this machine reserve job IDs from so they don't collide; "" = none (default) */
+  jobRegistry?: string;
+  /** Ghostscript executable documents are rasterized with; "" = gs on the PATH (default) */
+  ghostscriptPath?: string;
+  /** Milliseconds the system printer list is reused; 0 = no caching (default: 2000) */
+  printerCacheTtlMs?: number;
+  /** Fail jobs for network printers reached without TLS (default: false) */
+  requireEncryptedTransport?: boolean;
+}
+
+/** Effective library configuration returned by getConfig() */
+export interface EffectiveLibraryConfig {
+  workerPoolSize: number;
+  monitoringInterval: number;
+  monitoringIntervalMs: number;
+  jobPollIntervalMs: number;
+  tempDir?: string;
+  spoolQuotaMb: number;
+  simulate: boolean;
+  simulationDelayMs: number;
+  defaultJobOptions: Record<string, string>; // Raw job properties
+  maxJobHistory: number;
+  historyTtlSeconds: number;
+  stallThresholdMs: number;
+  stallAction: StallAction;
+  maxQueuedJobsPerPrinter: number;
+  maxPendingJobs: number;
+  queueFullTimeoutMs: number;
+  heldJobsDir?: string;
+  auditLog?: string;
+  redactJobData: boolean;
+  jobRegistry?: string;
+  ghostscriptPath?: string;
+  printerCacheTtlMs: number;
+  requireEncryptedTransport: boolean;
+}
+
+/** Library configuration as passed to the native module */
+type NativeLibraryConfig = Omit<LibraryConfig, "defaultJobOptions"> & {
+  defaultJobOptions?: Record<string, string>;
+};
+
 /**
  * N-API native printer interface
  * Represents the raw PrinterInfo struct from Rust - data only, no methods
  */
 export interface NativePrinter {
   name: string;
+  /** Hash of the system name and URI, stable across renames */
+  printerId: string;
   systemName: string;
   driverName: string;
   uri: string;
@@ -393,21 +1236,58 @@ export interface NativePrinter {
   isShared: boolean;
   state: PrinterState;
   stateReasons: string[];
+  normalizedStateReasons: StateReason[];
+  /** Unix timestamp when a reachability probe first found the printer offline */
+  offlineSince?: number;
+}
+
+/** Job tracking methods of the native Printer class */
+interface NativePrinterJobs {
+  getActiveJobs(): PrinterJob[];
+  getJobHistory(limit?: number): PrinterJob[];
+  getAllJobs(): PrinterJob[];
+  getJob(jobId: number): PrinterJob | null;
+  cleanupOldJobs(maxAgeSeconds: number): number;
 }
 
 // Trick to expose NativePrinter properties on Printer for linting and type checking
 // Properties are readonly - automatically proxied from the underlying NativePrinter
 export interface Printer extends Readonly<NativePrinter> {
   exists(): Promise<boolean>;
+  refresh(): Promise<boolean>;
+  isOnline(): Promise<boolean>;
+  wake(options?: WakeOptions): Promise<boolean>;
+  getPowerStatus(): Promise<PowerStatus>;
+  calibrateMedia(options?: LabelCommandOptions): Promise<number>;
+  feed(labels?: number, options?: LabelCommandOptions): Promise<number>;
+  getLabelStatus(): Promise<LabelStatus>;
+  getAccessControl(): Promise<AccessControl>;
+  setAccessControl(access: AccessControl): Promise<AccessControl>;
+  getSupplies(): Promise<PrinterSupply[]>;
+  getCapabilities(): Promise<PrinterCapabilities | null>;
+  getSupportedFormats(): Promise<SupportedFormats>;
+  getPreferences(): Promise<PrinterPreferences>;
+  setPreferences(preferences: PrinterPreferences): Promise<PrinterPreferences>;
   toString(): string;
-  equals(other: Printer): boolean;
+  equals(other: Printer | NativePrinter): boolean;
   getName(): string;
+  printFile(
+    filePath: string,
+    options: PrintJobOptions & { dryRun: true }
+  ): Promise<DryRunResult>;
   printFile(
     filePath: string,
     options?: PrintJobOptions | Record<string, string>
   ): Promise<number>;
   printBytes(
     data: Uint8Array | Buffer,
+    options: PrintJobOptions & { dryRun: true }
+  ): Promise<DryRunResult>;
+  printBytes(
+    data: Uint8Array | Buffer,
+    options?: PrintJobOptions | Record<string, string>
+  ): Promise<number>;
+  printTestPage(
     options?: PrintJobOptions | Record<string, string>
   ): Promise<number>;
 
@@ -507,8 +1387,60 @@ interface NativeModule {
   getAllPrinterNames(): string[];
   getAllPrinters(): NativePrinter[];
   findPrinterByName(name: string): NativePrinter | null;
+  getPrinterInfo?(name: string): NativePrinter | null;
+  printerInfoToJson?(name: string): string | null;
+  printerInfoFromJson?(json: string): NativePrinter;
   printerExists(name: string): boolean;
-  shutdown(): void;
+  refreshPrinters?(): void;
+  isPrinterOnline?(printerName: string): Promise<boolean>;
+  getPrinterSupplies?(printerName: string): Promise<PrinterSupply[]>;
+  getPrinterCapabilities?(
+    printerName: string
+  ): Promise<PrinterCapabilities | null>;
+  getPrinterSupportedFormats?(printerName: string): Promise<SupportedFormats>;
+  getPrinterPreferences?(printerName: string): Promise<PrinterPreferences>;
+  setPrinterPreferences?(
+    printerName: string,
+    preferences: PrinterPreferences
+  ): Promise<PrinterPreferences>;
+  startIppServer?(options: IppServerOptions): IppServer;
+  stopIppServer?(serverId: number): boolean;
+  getIppServers?(): IppServer[];
+  startPrintServer?(options: PrintServerOptions): PrintServer;
+  stopPrintServer?(serverId: number): boolean;
+  getPrintServers?(): PrintServer[];
+  queryPrinterSnmp?(
+    host: string,
+    options?: SnmpQueryOptions
+  ): Promise<SnmpPrinterStatus>;
+  rasterizeDocument?(
+    data: Uint8Array | Buffer,
+    options: Omit<RasterizeOptions, "onProgress"> & { conversionId?: number }
+  ): Promise<Buffer>;
+  setConversionProgressBuffering?(enabled: boolean): void;
+  drainConversionProgressEvents?(): ConversionProgressEvent[];
+  getGhostscriptVersion?(): string;
+  renderPreview?(
+    filePath: string,
+    options?: NativeRenderPreviewOptions
+  ): Promise<Buffer>;
+  renderPreviewBytes?(
+    data: Uint8Array | Buffer,
+    options?: NativeRenderPreviewOptions
+  ): Promise<Buffer>;
+  wrapPjl?(data: Uint8Array | Buffer, options?: PjlJobOptions): Buffer;
+  queryPjlStatus?(printerName: string): Promise<PjlStatus>;
+  sendIppRequest?(
+    printerUri: string,
+    operation: string,
+    attributes?: IppRawAttribute[],
+    options?: IppRequestOptions
+  ): Promise<IppRawResponse>;
+  shutdown(options?: ShutdownOptions): ShutdownSummary;
+  attach?(): void;
+  detach?(): boolean;
+  getAttachment?(): Attachment;
+  checkOwner?(): void;
   printFile(
     printerName: string,
     filePath: string,
@@ -521,21 +1453,194 @@ interface NativeModule {
     jobProperties?: Record<string, string>,
     waitForCompletion?: boolean
   ): Promise<number>;
+  dryRunPrintFile?(
+    printerName: string,
+    filePath: string,
+    jobProperties?: Record<string, string>
+  ): Promise<DryRunResult>;
+  dryRunPrintBytes?(
+    printerName: string,
+    data: Uint8Array | Buffer,
+    jobProperties?: Record<string, string>
+  ): Promise<DryRunResult>;
+  resolveJobOptions?(
+    printerName: string,
+    jobProperties?: Record<string, string>
+  ): Promise<ResolvedJobOptions>;
+  estimateJob?(
+    printerName: string,
+    filePath: string,
+    jobProperties?: Record<string, string>
+  ): Promise<JobEstimate>;
+  estimateBytesJob?(
+    printerName: string,
+    data: Uint8Array | Buffer,
+    jobProperties?: Record<string, string>
+  ): Promise<JobEstimate>;
+  extractText?(filePath: string): Promise<string>;
+  extractTextBytes?(data: Uint8Array | Buffer): Promise<string>;
+  printTestPage?(
+    printerName: string,
+    jobProperties?: Record<string, string>,
+    waitForCompletion?: boolean
+  ): Promise<number>;
+  printToMany?(
+    printerNames: string[],
+    filePath: string,
+    jobProperties?: Record<string, string>,
+    waitForCompletion?: boolean
+  ): Promise<PrintDestinationResult[]>;
+  createPrinterPool?(
+    printerNames: string[],
+    strategy?: string,
+    maxQueueDepth?: number
+  ): number;
+  poolPrintFile?(
+    poolId: number,
+    filePath: string,
+    jobProperties?: Record<string, string>,
+    waitForCompletion?: boolean
+  ): Promise<number>;
+  poolPrintBytes?(
+    poolId: number,
+    data: Uint8Array | Buffer,
+    jobProperties?: Record<string, string>,
+    waitForCompletion?: boolean
+  ): Promise<number>;
+  drainPoolEvents?(poolId: number): PoolFailoverEvent[];
+  removePrinterPool?(poolId: number): boolean;
+  definePrinterAlias?(
+    name: string,
+    printer: string,
+    defaultOptions?: Record<string, string>
+  ): void;
+  removePrinterAlias?(name: string): boolean;
+  getPrinterAliases?(): PrinterAlias[];
+  setPrinterDefaults?(
+    printerName: string,
+    options: Record<string, string> | null
+  ): void;
+  getPrinterDefaults?(printerName: string): Record<string, string>;
+  tagPrinter?(printerName: string, tags: string[]): void;
+  untagPrinter?(printerName: string, tags?: string[] | null): void;
+  getPrinterTags?(printerName: string): string[];
+  getPrintersByLocation?(location: string): NativePrinter[];
+  getPrintersByTag?(tag: string): NativePrinter[];
+  setPrinterAliasFile?(path: string | null, watch?: boolean): void;
+  getPrinterAliasFile?(): string | null;
+  reloadConfig?(): boolean;
+  setConfigEventBuffering?(enabled: boolean): void;
+  drainConfigEvents?(): ConfigChangedEvent[];
+  defineRemotePrinter?(name: string, options: RemotePrinterOptions): void;
+  removeRemotePrinter?(name: string): boolean;
+  getRemotePrinters?(): RemotePrinter[];
+  setPrinterCredentials?(
+    name: string | null,
+    credentials: PrinterCredentials | null
+  ): void;
+  setTlsOptions?(destination: string | null, options: TlsOptions | null): void;
+  setPrinterMacAddress?(printerName: string, mac: string | null): void;
+  wakePrinter?(printerName: string, options?: WakeOptions): Promise<boolean>;
+  getPrinterPowerStatus?(printerName: string): Promise<PowerStatus>;
+  calibrateMedia?(
+    printerName: string,
+    language?: LabelLanguage
+  ): Promise<number>;
+  feedLabels?(
+    printerName: string,
+    labels: number,
+    language?: LabelLanguage
+  ): Promise<number>;
+  queryLabelStatus?(printerName: string): Promise<LabelStatus>;
+  installPrinter?(installation: PrinterInstallation): Promise<void>;
+  removePrinter?(printerName: string): Promise<void>;
+  listDrivers?(): Promise<PrinterDriver[]>;
+  addCupsQueue?(queue: CupsQueue): Promise<void>;
+  deleteCupsQueue?(name: string): Promise<void>;
+  setQueueEnabled?(name: string, enabled: boolean): Promise<void>;
+  getPrinterAccessControl?(printerName: string): Promise<AccessControl>;
+  setPrinterAccessControl?(
+    printerName: string,
+    access: AccessControl
+  ): Promise<AccessControl>;
+  registerTemplate?(name: string, body: string, format?: string): void;
+  unregisterTemplate?(name: string): boolean;
+  getTemplates?(): PrintTemplate[];
+  renderTemplate?(name: string, variables: Record<string, string>): string;
+  printTemplate?(
+    printerName: string,
+    template: string,
+    variables: Record<string, string>,
+    jobProperties?: Record<string, string>,
+    waitForCompletion?: boolean
+  ): Promise<number>;
+  printFileSync?(
+    printerName: string,
+    filePath: string,
+    jobProperties?: Record<string, string>,
+    timeoutMs?: number
+  ): number;
+  printBytesSync?(
+    printerName: string,
+    data: Uint8Array | Buffer,
+    jobProperties?: Record<string, string>,
+    timeoutMs?: number
+  ): number;
   // Job tracking methods (all use x_for_printer naming)
   getActiveJobsForPrinter?(printerName: string): PrinterJob[];
   getJobHistoryForPrinter?(printerName: string, limit?: number): PrinterJob[];
   getAllJobsForPrinter?(printerName: string): PrinterJob[];
+  findJobs?(query: FindJobsQuery): PrinterJob[];
+  queryJobs?(query: NativeJobQuery): JobPage;
+  exportJobHistory?(
+    path: string,
+    format?: string,
+    since?: number
+  ): Promise<number>;
+  setWebhook?(config: WebhookConfig | null): void;
+  setPolicyRules?(rules: PolicyRule[]): void;
+  getPolicyRules?(): PolicyRule[];
   getJobForPrinter?(printerName: string, jobId: number): PrinterJob | null;
   cleanupOldJobsForPrinter?(printerName: string, maxAgeSeconds: number): number;
+  getUsageStats?(printerName?: string, since?: number): UsageStats;
+  cancelAllJobs?(printerName?: string): number;
+  clearJobHistory?(printerName?: string): number;
+  getJobSpoolFile?(jobId: number): string | null;
+  getJobLog?(jobId: number): JobLogEntry[] | null;
+  printerJobToJson?(jobId: number): string | null;
+  getJobProcess?(jobId: number): number | null;
+  printerJobFromJson?(json: string): PrinterJob;
+  reprintJob?(jobId: number): number;
+  migrateJobs?(fromPrinter: string, toPrinter: string): JobMigration;
+  approveJob?(jobId: number): void;
+  rejectJob?(jobId: number, reason?: string): void;
+  getHeldJobs?(printerName?: string): PrinterJob[];
+  setJobStalledBuffering?(enabled: boolean): void;
+  drainJobStalledEvents?(): JobStalledEvent[];
   // Printer state monitoring methods
   startStateMonitoring?(): void;
   stopStateMonitoring?(): void;
   isStateMonitoringActive?(): boolean;
   setStateMonitoringInterval?(seconds: number): void;
   getPrinterStateSnapshot?(): Record<string, [PrinterState, string[]]>;
-  Printer: {
-    fromName(name: string): NativePrinter | null;
-  };
+  // Configuration methods
+  initialize?(config?: NativeLibraryConfig): void;
+  configure?(config: NativeLibraryConfig): void;
+  getConfig?(): EffectiveLibraryConfig;
+  verifyAuditLog?(path?: string): Promise<AuditVerification>;
+  setSpoolEncryptionKey?(key: Uint8Array | Buffer | null): void;
+  setRedactionKey?(key: Uint8Array | Buffer | null): void;
+  // Metrics methods
+  getMetricsSnapshot?(): MetricsSnapshot;
+  getMetricsPrometheus?(): string;
+  healthCheck?(printerNames?: string[]): Promise<HealthReport>;
+  getRuntimeStatus?(): RuntimeStatus;
+  // Logging methods
+  setLogLevel?(level: string): void;
+  getLogLevel?(): LogLevel;
+  setLogBuffering?(enabled: boolean): void;
+  drainLogRecords?(): LogRecord[];
+  Printer: new (name: string) => NativePrinterJobs;
 }
 
 // Helper functions for CUPS options conversion
@@ -648,15 +1753,245 @@ export interface PrintJobOptions {
    * - false: Promise resolves immediately with job ID, background thread keeps printer alive
    */
   waitForCompletion?: boolean;
-}
-
-/**
- * Convert PrintJobOptions to raw properties for the backend
- */
-export function printJobOptionsToRaw(
-  options?: PrintJobOptions
-): Record<string, string> {
-  if (!options) return {};
+  /**
+   * Fail the job if the spooler hasn't accepted it within this many
+   * milliseconds of submission. The job is marked cancelled, a spooler job
+   * accepted afterwards is cancelled, and a waiting promise rejects with a
+   * TimeoutError.
+   */
+  timeoutMs?: number;
+  /**
+   * How often waiting for the job checks its state, in milliseconds,
+   * overriding the configured `jobPollIntervalMs`. Raise it when tracking
+   * many jobs at once; lower it for faster feedback on a kiosk.
+   */
+  pollIntervalMs?: number;
+  /**
+   * Reject PDFs that are truncated or malformed (missing header, `%%EOF`,
+   * cross-reference table, catalog or pages) with an InvalidDocumentError
+   * before submission, instead of failing in the driver mid-print.
+   */
+  validateDocument?: boolean;
+  /**
+   * MIME type of the document (e.g. "application/vnd.cups-raw"), overriding
+   * detection from the file extension. Sent to CUPS as `document-format`.
+   */
+  documentFormat?: string;
+  /**
+   * Keep the exact payload sent to the printer in the spool directory after
+   * the job finishes, for reprinting or diffing when output comes out
+   * garbled. See getJobSpoolFile().
+   */
+  keepSpoolFile?: boolean;
+  /**
+   * IDs of jobs that must complete successfully before this job is
+   * submitted, e.g. to print a pick list, label and invoice in order across
+   * printers. The job fails if a dependency fails or is cancelled.
+   */
+  dependsOn?: number[];
+  /**
+   * Treat submissions with the same key within `dedupeWindowMs` as
+   * duplicates, e.g. to guard against a double-clicked print button. The
+   * key is shared across printers.
+   */
+  dedupeKey?: string;
+  /** How long the dedupe key is held after submission (default: 5000) */
+  dedupeWindowMs?: number;
+  /**
+   * What happens to a duplicate: "coalesce" (default) returns the ID of the
+   * job submitted first, "reject" throws a DuplicateJobError.
+   */
+  dedupeMode?: DedupeMode;
+  /**
+   * Spool the job locally in the "held-for-approval" state instead of
+   * sending it, until `approveJob` releases it or `rejectJob` cancels it.
+   * Held jobs are persisted, so pending approvals survive restarts.
+   */
+  requireApproval?: boolean;
+  /**
+   * Values stored with the job and returned with it, such as
+   * `{ orderId: "A-1001" }`, to correlate jobs to business entities and
+   * find them again with `findJobs`.
+   */
+  metadata?: Record<string, string>;
+  /**
+   * Replace the job's name and file path with hashes in job history,
+   * exports, events and the audit log, so personal data in file names is
+   * never recorded. Overrides the `redactJobData` setting.
+   */
+  redact?: boolean;
+  /**
+   * Scale of the page content in percent, e.g. 90 to shrink it a little.
+   * Applied natively to PDFs and PNG and JPEG images before the job is
+   * sent; images are placed on a page of the job's media and sent as PDFs.
+   */
+  scale?: number;
+  /** Scale the page content to fill the page within the margins */
+  fitToPage?: boolean;
+  /**
+   * Space to leave blank at the page edges, in points (1/72 inch).
+   * Content is shrunk to fit within them and clipped at them.
+   */
+  margins?: Margins;
+  /**
+   * Text or an image stamped over every page, e.g.
+   * `{ text: "CONFIDENTIAL" }`, applied natively before the job is sent.
+   * PDF documents and PNG and JPEG images only; other documents are
+   * rejected with an InvalidDocumentError.
+   */
+  watermark?: Watermark;
+  /**
+   * Line printed at the top of every page of a PDF, e.g.
+   * `"Order {{orderId}} — page {{page}}/{{pages}}"`. Placeholders are
+   * filled from the metadata and the built-in variables page, pages,
+   * jobName, user and date; unknown variables fail the job with an
+   * InvalidParams error.
+   */
+  header?: string;
+  /** Line printed at the bottom of every page of a PDF, like header */
+  footer?: string;
+  /**
+   * Lay out the pages of a PDF several to a sheet: "booklet" orders them
+   * to fold into a booklet and prints on both sides, which the printer
+   * must support; "2-up" and "4-up" add crop marks.
+   */
+  layout?: PageLayout;
+  /**
+   * Add a blank page to PDFs that print on an odd number of sides when
+   * several collated copies are printed on both sides of the sheet, so
+   * each copy starts on its own sheet instead of on the back of the one
+   * before. Counts the sides after `layout` and the number-up option, and
+   * leaves jobs with page-ranges alone.
+   */
+  padOddPages?: boolean;
+  /**
+   * Colors to print in, asked of the driver through print-color-mode.
+   * Grayscale and monochrome jobs for printers that don't report the mode
+   * are also converted to gray before submission, for PDFs and PNG and
+   * JPEG images; see DryRunResult.convertsToGray.
+   */
+  colorMode?: JobColorMode;
+  /**
+   * Print quality, asked of the driver through print-quality (the
+   * DEVMODE print quality on Windows). Throws an UnsupportedOptionError if
+   * the printer reports it can't print at the quality.
+   */
+  quality?: "draft" | "normal" | "high";
+  /**
+   * Resolution in dots per inch, asked of the driver through
+   * printer-resolution and the CUPS Resolution option. Throws an
+   * UnsupportedOptionError if the printer reports other resolutions.
+   */
+  dpi?: number;
+  /**
+   * Staple, punch, fold and output bin for printers with a finisher, asked
+   * of the driver through the IPP finishings and output-bin attributes.
+   * Throws an UnsupportedOptionError if the printer reports it can't
+   * finish the job that way; see PrinterCapabilities.finishings and
+   * supported["output-bin"].
+   */
+  finishing?: Finishing;
+  /**
+   * Fail with an UnsupportedOptionError if a raw property isn't one the
+   * printer lists (e.g. a typo like "copeis"), or asks for a value it
+   * doesn't support, instead of the driver silently ignoring it. The
+   * error lists the options the printer accepts; see
+   * PrinterCapabilities.supportedOptions. Printers that report no
+   * capabilities aren't checked. Set it in `defaultJobOptions` to check
+   * every job.
+   */
+  strictOptions?: boolean;
+  /**
+   * Run every check a submission would (printer exists, file readable,
+   * options valid and supported by the printer, document parses) and
+   * resolve to a DryRunResult instead of a job ID, without spooling
+   * anything.
+   */
+  dryRun?: boolean;
+}
+
+/** What happens to a submission whose dedupeKey was recently used */
+export type DedupeMode = "coalesce" | "reject";
+
+/** Space at each edge of the page, in points (default: 0) */
+export interface Margins {
+  top?: number;
+  right?: number;
+  bottom?: number;
+  left?: number;
+}
+
+/** Finishing for printers with a finisher */
+export interface Finishing {
+  staple?: StaplePosition;
+  punch?: PunchPosition;
+  fold?: FoldType;
+  outputBin?: string; // e.g. "face-down" or "mailbox-1"
+}
+
+/** Where to staple; "default" leaves it to the printer */
+export type StaplePosition =
+  | "default"
+  | "top-left"
+  | "bottom-left"
+  | "top-right"
+  | "bottom-right"
+  | "dual-left"
+  | "dual-top"
+  | "dual-right"
+  | "dual-bottom";
+
+/** Where to punch holes; "default" leaves it to the printer */
+export type PunchPosition =
+  | "default"
+  | "top-left"
+  | "bottom-left"
+  | "top-right"
+  | "bottom-right"
+  | `${"dual" | "triple" | "quad"}-${"left" | "top" | "right" | "bottom"}`;
+
+/** How to fold; "default" leaves it to the printer */
+export type FoldType =
+  | "default"
+  | "accordion"
+  | "double-gate"
+  | "gate"
+  | "half"
+  | "half-z"
+  | "left-gate"
+  | "letter"
+  | "parallel"
+  | "poster"
+  | "right-gate"
+  | "z";
+
+/** How pages are laid out on the sheets printed */
+export type PageLayout = "booklet" | "2-up" | "4-up";
+
+/** Colors a job prints in: monochrome is black and white only */
+export type JobColorMode = "color" | "grayscale" | "monochrome";
+
+/** Where a watermark goes on the page */
+export type WatermarkPosition = "diagonal" | "center" | "top" | "bottom";
+
+/** Text or an image stamped over every page of a PDF */
+export interface Watermark {
+  text?: string;
+  /** Path of a PNG or JPEG image, e.g. a logo or an approval stamp */
+  image?: string;
+  /** From 0 (invisible) to 1 (opaque) (default: 0.3) */
+  opacity?: number;
+  /** Default: "diagonal", across the page corner to corner */
+  position?: WatermarkPosition;
+}
+
+/**
+ * Convert PrintJobOptions to raw properties for the backend
+ */
+export function printJobOptionsToRaw(
+  options?: PrintJobOptions
+): Record<string, string> {
+  if (!options) return {};
 
   let rawOptions: Record<string, string> = {};
 
@@ -680,6 +2015,182 @@ export function printJobOptionsToRaw(
     rawOptions["job-name"] = options.jobName;
   }
 
+  // Add requesting user if specified at top level
+  if (options.requestingUser) {
+    rawOptions["requesting-user-name"] = options.requestingUser;
+  }
+
+  if (options.timeoutMs !== undefined) {
+    if (!Number.isFinite(options.timeoutMs) || options.timeoutMs <= 0) {
+      throw new Error(`Invalid timeoutMs: ${options.timeoutMs}`);
+    }
+    rawOptions["job-timeout-ms"] = String(Math.ceil(options.timeoutMs));
+  }
+
+  if (options.pollIntervalMs !== undefined) {
+    if (
+      !Number.isFinite(options.pollIntervalMs) ||
+      options.pollIntervalMs <= 0
+    ) {
+      throw new Error(`Invalid pollIntervalMs: ${options.pollIntervalMs}`);
+    }
+    rawOptions["job-poll-interval-ms"] = String(
+      Math.ceil(options.pollIntervalMs)
+    );
+  }
+
+  if (options.validateDocument) {
+    rawOptions["validate-document"] = "true";
+  }
+
+  if (options.documentFormat) {
+    rawOptions["document-format"] = options.documentFormat;
+  }
+
+  if (options.keepSpoolFile) {
+    rawOptions["keep-spool-file"] = "true";
+  }
+
+  if (options.requireApproval) {
+    rawOptions["require-approval"] = "true";
+  }
+
+  if (options.redact !== undefined) {
+    rawOptions["redact-job-data"] = String(options.redact);
+  }
+
+  if (options.metadata && Object.keys(options.metadata).length > 0) {
+    for (const [key, value] of Object.entries(options.metadata)) {
+      if (typeof value !== "string") {
+        throw new Error(`Metadata value for "${key}" must be a string`);
+      }
+    }
+    rawOptions["job-metadata"] = JSON.stringify(options.metadata);
+  }
+
+  if (options.scale !== undefined) {
+    if (!Number.isFinite(options.scale) || options.scale <= 0) {
+      throw new Error(`Invalid scale: ${options.scale}`);
+    }
+    if (options.fitToPage) {
+      throw new Error("scale and fitToPage can't be combined");
+    }
+    rawOptions["job-scale"] = options.scale.toString();
+  }
+  if (options.fitToPage) {
+    rawOptions["job-fit-to-page"] = "true";
+  }
+  if (options.margins) {
+    for (const [edge, margin] of Object.entries(options.margins)) {
+      if (!Number.isFinite(margin) || margin < 0) {
+        throw new Error(`Invalid ${edge} margin: ${margin}`);
+      }
+    }
+    rawOptions["job-margins"] = JSON.stringify(options.margins);
+  }
+
+  if (options.watermark) {
+    const { text, image, opacity, position } = options.watermark;
+    if (!text?.trim() && !image) {
+      throw new Error("A watermark needs text or an image");
+    }
+    if (
+      opacity !== undefined &&
+      (!Number.isFinite(opacity) || opacity <= 0 || opacity > 1)
+    ) {
+      throw new Error(`Invalid watermark opacity: ${opacity}`);
+    }
+    if (
+      position !== undefined &&
+      !["diagonal", "center", "top", "bottom"].includes(position)
+    ) {
+      throw new Error(`Invalid watermark position: ${position}`);
+    }
+    rawOptions["job-watermark"] = JSON.stringify(options.watermark);
+  }
+
+  if (options.header?.trim()) {
+    rawOptions["job-header"] = options.header;
+  }
+  if (options.footer?.trim()) {
+    rawOptions["job-footer"] = options.footer;
+  }
+
+  if (options.layout) {
+    if (!["booklet", "2-up", "4-up"].includes(options.layout)) {
+      throw new Error(`Invalid layout: ${options.layout}`);
+    }
+    rawOptions["job-layout"] = options.layout;
+  }
+  if (options.padOddPages) {
+    rawOptions["job-pad-odd-pages"] = "true";
+  }
+
+  if (options.colorMode) {
+    if (!["color", "grayscale", "monochrome"].includes(options.colorMode)) {
+      throw new Error(`Invalid colorMode: ${options.colorMode}`);
+    }
+    rawOptions["job-color-mode"] = options.colorMode;
+  }
+
+  if (options.quality) {
+    if (!["draft", "normal", "high"].includes(options.quality)) {
+      throw new Error(`Invalid quality: ${options.quality}`);
+    }
+    rawOptions["job-quality"] = options.quality;
+  }
+  if (options.dpi !== undefined) {
+    if (!Number.isSafeInteger(options.dpi) || options.dpi <= 0) {
+      throw new Error(`Invalid dpi: ${options.dpi}`);
+    }
+    rawOptions["job-dpi"] = options.dpi.toString();
+  }
+
+  if (options.finishing) {
+    const { staple, punch, fold, outputBin } = options.finishing;
+    if (!staple && !punch && !fold && !outputBin?.trim()) {
+      throw new Error("finishing needs staple, punch, fold or outputBin");
+    }
+    rawOptions["job-finishing"] = JSON.stringify(options.finishing);
+  }
+
+  if (options.strictOptions !== undefined) {
+    rawOptions["job-strict-options"] = String(options.strictOptions);
+  }
+
+  if (options.dependsOn && options.dependsOn.length > 0) {
+    for (const jobId of options.dependsOn) {
+      if (!Number.isSafeInteger(jobId) || jobId < 0) {
+        throw new Error(`Invalid dependsOn job ID: ${jobId}`);
+      }
+    }
+    rawOptions["depends-on"] = options.dependsOn.join(",");
+  }
+
+  if (options.dedupeKey) {
+    rawOptions["dedupe-key"] = options.dedupeKey;
+    if (options.dedupeWindowMs !== undefined) {
+      if (
+        !Number.isFinite(options.dedupeWindowMs) ||
+        options.dedupeWindowMs <= 0
+      ) {
+        throw new Error(`Invalid dedupeWindowMs: ${options.dedupeWindowMs}`);
+      }
+      rawOptions["dedupe-window-ms"] = String(
+        Math.ceil(options.dedupeWindowMs)
+      );
+    }
+    if (options.dedupeMode !== undefined) {
+      if (
+        options.dedupeMode !== "coalesce" &&
+        options.dedupeMode !== "reject"
+      ) {
+        throw new Error(`Invalid dedupeMode: ${options.dedupeMode}`);
+      }
+      rawOptions["dedupe-mode"] = options.dedupeMode;
+    }
+  }
+
   return rawOptions;
 }
 
@@ -739,6 +2250,8 @@ let nativeModulePromise: Promise<NativeModule> | null = null;
 let nativeModuleCache: NativeModule | null = null;
 let simulationModeLogged = false;
 let customNativeModulePath: string | null = null;
+let fallbackBackend: FallbackBackend | null = null;
+let forceFallback = false;
 
 /**
  * Override the path used to load the native N-API binary.
@@ -878,7 +2391,20 @@ function getNativeModule(): Promise<NativeModule> {
 
   // Start loading if not already in progress
   if (!nativeModulePromise) {
-    nativeModulePromise = loadNativeModule()
+    const backend = fallbackBackend;
+    const load =
+      backend && forceFallback
+        ? Promise.resolve(createFallbackModule(backend))
+        : loadNativeModule().catch(error => {
+            if (!backend) throw error;
+            console.warn(
+              `Native printer module unavailable, using the fallback backend: ${
+                error instanceof Error ? error.message : String(error)
+              }`
+            );
+            return createFallbackModule(backend);
+          });
+    nativeModulePromise = load
       .then(mod => {
         nativeModuleCache = mod;
         return mod;
@@ -897,273 +2423,1175 @@ function getNativeModule(): Promise<NativeModule> {
   return nativeModulePromise;
 }
 
+/** A print job handed to a fallback backend */
+export interface FallbackPrintJob {
+  id: number;
+  printerName: string;
+  /** `jobName`, or else the file name */
+  name: string;
+  /** Document data, unless it is a file this runtime can't read */
+  data?: Uint8Array;
+  /** File the job was printed from, for printFile() */
+  filePath?: string;
+  /** Job options as raw properties, e.g. `job-name` and `copies` */
+  options: Record<string, string>;
+}
+
 /**
- * Wrapper class providing consistent API across all runtimes.
- * Uses Proxy to dynamically expose all NativePrinter properties.
+ * Printing backend for runtimes that can't load native modules, such as an
+ * Electron renderer, a browser bundle or an edge runtime. Printing is
+ * delegated to `print`, e.g. a `window.print()` bridge or a request to a
+ * print server.
  */
-class PrinterWrapperImpl {
-  private _native: NativePrinter;
+export interface FallbackBackend {
+  /**
+   * Printer names to expose, the first being the default
+   * (default ["Default"])
+   */
+  printers?: string[];
+  /** Print a job; the job fails if this throws or rejects */
+  print(job: FallbackPrintJob): void | Promise<void>;
+}
 
-  constructor(nativePrinter: NativePrinter) {
-    this._native = nativePrinter;
+/** Options for setFallbackBackend() */
+export interface FallbackOptions {
+  /** Use the backend even where the native module could be loaded */
+  force?: boolean;
+}
 
-    // Return a Proxy that intercepts property access
-    return new Proxy(this, {
-      get(target, prop, receiver) {
-        // If the property exists on the wrapper, use it
-        if (prop in target) {
-          const value = Reflect.get(target, prop, receiver);
-          // Bind functions to maintain correct 'this' context
-          return typeof value === "function" ? value.bind(target) : value;
-        }
+/**
+ * Set the backend used when the native module can't be loaded. Only
+ * printer listing, `printFile`, `printBytes` and the job tracking methods of
+ * `Printer` work with it; other functions report their functionality as not
+ * available. Jobs are tracked in memory for the life of the process.
+ *
+ * Must be called before any other `@printers/printers` API.
+ *
+ * @param backend - Backend to print with, or null to remove it
+ * @param options - Fallback options
+ * @throws If the native module has already been loaded.
+ */
+export function setFallbackBackend(
+  backend: FallbackBackend | null,
+  options?: FallbackOptions
+): void {
+  if (nativeModuleCache !== null || nativeModulePromise !== null) {
+    throw new Error(
+      "Native module already loaded. setFallbackBackend() must be called " +
+        "before any other @printers/printers API."
+    );
+  }
+  fallbackBackend = backend;
+  forceFallback = options?.force === true;
+}
 
-        // Otherwise, try to get it from the native printer
-        const nativeValue = (target._native as any)[prop];
-        if (nativeValue !== undefined) {
-          // Bind native functions to the native printer
-          return typeof nativeValue === "function"
-            ? nativeValue.bind(target._native)
-            : nativeValue;
+/**
+ * Build a module with the native module's printing and job tracking
+ * methods on top of a fallback backend.
+ */
+function createFallbackModule(backend: FallbackBackend): NativeModule {
+  const names = backend.printers?.length ? backend.printers : ["Default"];
+  const printers: NativePrinter[] = names.map((name, index) => ({
+    name,
+    printerId: `fallback-${index}`,
+    systemName: name,
+    driverName: "",
+    uri: "",
+    portName: "",
+    processor: "",
+    dataType: "",
+    description: "",
+    location: "",
+    isDefault: index === 0,
+    isShared: false,
+    state: "idle",
+    stateReasons: [],
+    normalizedStateReasons: [],
+  }));
+  const jobs = new Map<number, PrinterJob>();
+  let nextJobId = 1;
+  const now = () => Math.floor(Date.now() / 1000);
+  const find = (name: string) => printers.find(p => p.name === name) ?? null;
+  const withAge = (job: PrinterJob): PrinterJob => ({
+    ...job,
+    ageSeconds: now() - job.createdAt,
+  });
+  const jobsFor = (printerName: string) =>
+    [...jobs.values()].filter(job => job.printerName === printerName);
+
+  const finish = (job: PrinterJob, error?: unknown) => {
+    const to: PrinterJobState = error === undefined ? "completed" : "cancelled";
+    job.transitions.push({ from: job.state, to, timestamp: now() });
+    job.state = to;
+    job.completedAt = now();
+    if (error !== undefined) {
+      job.errorMessage = error instanceof Error ? error.message : String(error);
+    }
+  };
+
+  const submit = async (
+    printerName: string,
+    document: { data?: Uint8Array; filePath?: string },
+    options: Record<string, string> = {},
+    waitForCompletion = true
+  ): Promise<number> => {
+    if (!find(printerName)) {
+      throw new Error(`Printer not found: ${printerName}`);
+    }
+    const id = nextJobId++;
+    const job: PrinterJob = {
+      id,
+      name:
+        options["job-name"] ??
+        document.filePath?.split(/[\\/]/).pop() ??
+        `Job ${id}`,
+      state: "processing",
+      mediaType: options["document-format"] ?? "application/octet-stream",
+      mediaTypeSource: options["document-format"] ? "option" : "default",
+      createdAt: now(),
+      processedAt: now(),
+      printerName,
+      ageSeconds: 0,
+      sizeBytes: document.data?.byteLength ?? 0,
+      timedOut: false,
+      stalled: false,
+      printerRemoved: false,
+      transitions: [{ from: "pending", to: "processing", timestamp: now() }],
+    };
+    jobs.set(id, job);
+    const printing = Promise.resolve()
+      .then(() =>
+        backend.print({ id, printerName, name: job.name, options, ...document })
+      )
+      .then(
+        () => finish(job),
+        error => {
+          finish(job, error);
+          throw error;
         }
+      );
+    if (waitForCompletion) {
+      await printing;
+    } else {
+      printing.catch(() => {});
+    }
+    return id;
+  };
 
-        return undefined;
-      },
+  class FallbackPrinter implements NativePrinterJobs {
+    private readonly name: string;
 
-      // Make properties enumerable for iteration
-      ownKeys(target) {
-        const wrapperKeys = Reflect.ownKeys(target);
-        const nativeKeys = Reflect.ownKeys(target._native);
-        return [...new Set([...wrapperKeys, ...nativeKeys])];
-      },
+    constructor(name: string) {
+      if (!find(name)) {
+        throw new Error(`Printer '${name}' not found`);
+      }
+      this.name = name;
+    }
 
-      // Required for ownKeys to work properly
-      getOwnPropertyDescriptor(target, prop) {
-        if (prop in target) {
-          return Reflect.getOwnPropertyDescriptor(target, prop);
-        }
-        if (prop in target._native) {
-          return {
-            enumerable: true,
-            configurable: true,
-          };
-        }
-        return undefined;
-      },
-    });
-  }
+    getActiveJobs(): PrinterJob[] {
+      return jobsFor(this.name)
+        .filter(job => job.state === "processing")
+        .map(withAge);
+    }
 
-  /**
-   * Check if the printer exists on the system.
-   * @returns Promise resolving to true if printer exists
-   */
-  async exists(): Promise<boolean> {
-    return await printerExists(this._native.name);
+    getJobHistory(limit?: number): PrinterJob[] {
+      return jobsFor(this.name)
+        .filter(job => job.state !== "processing")
+        .slice(-(limit ?? jobs.size))
+        .map(withAge);
+    }
+
+    getAllJobs(): PrinterJob[] {
+      return jobsFor(this.name).map(withAge);
+    }
+
+    getJob(jobId: number): PrinterJob | null {
+      const job = jobs.get(jobId);
+      return job?.printerName === this.name ? withAge(job) : null;
+    }
+
+    cleanupOldJobs(maxAgeSeconds: number): number {
+      const old = jobsFor(this.name).filter(
+        job =>
+          job.state !== "processing" && now() - job.createdAt >= maxAgeSeconds
+      );
+      old.forEach(job => jobs.delete(job.id));
+      return old.length;
+    }
   }
 
-  /**
-   * Get string representation of the printer.
-   * @returns Formatted printer information string
-   */
-  toString(): string {
-    const status = this._native.isDefault ? " (default)" : "";
-    const state = this._native.state ? ` [${this._native.state}]` : "";
-    return `${this._native.name}${status}${state}`;
+  const readFile = async (
+    filePath: string
+  ): Promise<Uint8Array | undefined> => {
+    try {
+      const fs = await import("node:fs/promises");
+      return new Uint8Array(await fs.readFile(filePath));
+    } catch {
+      return undefined;
+    }
+  };
+
+  const module = {
+    getAllPrinterNames: () => printers.map(p => p.name),
+    getAllPrinters: () => printers,
+    findPrinterByName: find,
+    getPrinterInfo: find,
+    printerExists: (name: string) => find(name) !== null,
+    printFile: async (
+      printerName: string,
+      filePath: string,
+      jobProperties?: Record<string, string>,
+      waitForCompletion?: boolean
+    ) =>
+      submit(
+        printerName,
+        { filePath, data: await readFile(filePath) },
+        jobProperties,
+        waitForCompletion
+      ),
+    printBytes: (
+      printerName: string,
+      data: Uint8Array | Buffer,
+      jobProperties?: Record<string, string>,
+      waitForCompletion?: boolean
+    ) =>
+      submit(
+        printerName,
+        { data: new Uint8Array(data) },
+        jobProperties,
+        waitForCompletion
+      ),
+    shutdown: (): ShutdownSummary => ({
+      completed: 0,
+      cancelled: 0,
+      timedOut: 0,
+    }),
+    Printer: FallbackPrinter,
+  };
+  return module as unknown as NativeModule;
+}
+
+/**
+ * Wrapper class providing consistent API across all runtimes.
+ * Uses Proxy to dynamically expose all NativePrinter properties.
+ */
+/**
+ * Convert options to raw properties for the backend and extract waitForCompletion
+ */
+function convertPrintOptions(
+  options?: PrintJobOptions | Record<string, string>
+): {
+  rawOptions?: Record<string, string>;
+  waitForCompletion: boolean;
+} {
+  if (!options) {
+    return { rawOptions: undefined, waitForCompletion: true };
   }
 
-  /**
-   * Compare equality with another printer.
-   * Printers are considered equal if they have the same name.
-   * @param other - Printer to compare with
-   * @returns True if printers have same name
-   */
-  equals(other: Printer): boolean {
-    // Since name is guaranteed to exist on NativePrinter, we can directly compare
-    return this._native.name === other.name;
+  // If it's already raw properties (has string keys and values)
+  if (isRawPrintOptions(options)) {
+    return { rawOptions: options, waitForCompletion: true };
   }
 
-  /**
-   * Get the printer name.
-   * @returns Printer name
-   */
-  getName(): string {
-    return this._native.name;
+  const typedOptions = options as PrintJobOptions;
+  const waitForCompletion = typedOptions.waitForCompletion !== false; // Default to true
+
+  // Convert typed options to raw (excluding waitForCompletion)
+  const rawOptions = printJobOptionsToRaw(typedOptions);
+
+  return { rawOptions, waitForCompletion };
+}
+
+/**
+ * Check if options are raw properties
+ */
+function isRawPrintOptions(
+  options: PrintJobOptions | Record<string, string>
+): options is Record<string, string> {
+  // If it has any of the PrintJobOptions specific keys, it's typed options
+  return !(
+    "jobName" in options ||
+    "raw" in options ||
+    "simple" in options ||
+    "cups" in options ||
+    "timeoutMs" in options ||
+    "pollIntervalMs" in options ||
+    "validateDocument" in options ||
+    "documentFormat" in options ||
+    "keepSpoolFile" in options ||
+    "dependsOn" in options ||
+    "dedupeKey" in options ||
+    "requireApproval" in options ||
+    "metadata" in options ||
+    "redact" in options ||
+    "dryRun" in options
+  );
+}
+
+/**
+ * Check if options ask for a dry run
+ */
+function isDryRun(
+  options?: PrintJobOptions | Record<string, string>
+): options is PrintJobOptions & { dryRun: true } {
+  return (
+    options !== undefined &&
+    !isRawPrintOptions(options) &&
+    options.dryRun === true
+  );
+}
+
+/**
+ * Error thrown when a print job or a synchronous wait exceeds its timeout.
+ */
+export class TimeoutError extends Error {
+  constructor(message: string) {
+    super(message);
+    this.name = "TimeoutError";
+  }
+}
+
+/**
+ * Error thrown when a document fails the validateDocument checks.
+ */
+export class InvalidDocumentError extends Error {
+  constructor(message: string) {
+    super(message);
+    this.name = "InvalidDocumentError";
+  }
+}
+
+/**
+ * Error thrown when a job is submitted while the printer's queue or the
+ * global queue is at its configured limit.
+ */
+export class QueueFullError extends Error {
+  constructor(message: string) {
+    super(message);
+    this.name = "QueueFullError";
+  }
+}
+
+/**
+ * Error thrown when a job is submitted with a dedupeKey used within its
+ * dedupeWindowMs and `dedupeMode: "reject"`.
+ */
+export class DuplicateJobError extends Error {
+  constructor(message: string) {
+    super(message);
+    this.name = "DuplicateJobError";
+  }
+}
+
+/**
+ * Error thrown when a job asks for a quality or resolution the printer
+ * reports it can't print, rather than printing at its default.
+ */
+export class UnsupportedOptionError extends Error {
+  constructor(message: string) {
+    super(message);
+    this.name = "UnsupportedOptionError";
+  }
+}
+
+/**
+ * Error thrown when the content policy set with setContentPolicy rejects a
+ * document.
+ */
+export class ContentRejectedError extends Error {
+  constructor(message: string) {
+    super(message);
+    this.name = "ContentRejectedError";
+  }
+}
+
+/**
+ * Error thrown when a job breaks a rule set with setPolicyRules. The
+ * message says how, e.g. `Rule "short-jobs": Job prints 12 pages, more
+ * than the 10 allowed`.
+ */
+export class PolicyViolationError extends Error {
+  rule?: string; // Id of the rule the job broke
+  constructor(message: string) {
+    super(message);
+    this.name = "PolicyViolationError";
+    this.rule = /^Rule "([^"]*)"/.exec(message)?.[1];
+  }
+}
+
+/** Error classes keyed by the prefix the native module puts on messages */
+const PRINT_ERROR_TYPES: Array<[string, new (message: string) => Error]> = [
+  ["TimeoutError: ", TimeoutError],
+  ["InvalidDocumentError: ", InvalidDocumentError],
+  ["QueueFullError: ", QueueFullError],
+  ["DuplicateJobError: ", DuplicateJobError],
+  ["UnsupportedOptionError: ", UnsupportedOptionError],
+  ["PolicyViolationError: ", PolicyViolationError],
+];
+
+/**
+ * Stable code of an error thrown by the library: the name of its PrintError,
+ * "Timeout" when a job or a wait for it timed out, or "ContentRejected"
+ * when the content policy rejected a document
+ */
+export type ErrorCode =
+  | keyof typeof PrintError
+  | "Timeout"
+  | "ContentRejected";
+
+/** Default end-user messages for each error code */
+export const ERROR_MESSAGES: Readonly<Record<ErrorCode, string>> = {
+  InvalidParams: "The print settings are not valid.",
+  InvalidPrinterName: "The printer name is not valid.",
+  InvalidFilePath: "The document location is not valid.",
+  InvalidJson: "The print settings could not be read.",
+  InvalidJsonEncoding: "The print settings could not be read.",
+  PrinterNotFound: "The printer could not be found.",
+  FileNotFound: "The document could not be found.",
+  SimulatedFailure: "Printing failed (simulated).",
+  ShuttingDown: "Printing is shutting down.",
+  InvalidDocument: "The document is damaged and can't be printed.",
+  SpoolQuotaExceeded: "There is no room for more print jobs right now.",
+  SpoolFailed: "The document could not be prepared for printing.",
+  JobNotFound: "The print job could not be found.",
+  NoPrinterAvailable: "No printer is available.",
+  QueueFull: "Too many documents are waiting to print.",
+  DuplicateJob: "This document was already sent to print.",
+  PrinterRemoved: "The printer was removed before the document printed.",
+  UnsupportedOption: "The printer doesn't support the print settings.",
+  PolicyViolation: "The print policy doesn't allow this document.",
+  Timeout: "Printing is taking longer than expected.",
+  ContentRejected: "This document isn't allowed to be printed.",
+};
+
+/** An error thrown by the library, with the stable code it was thrown for */
+export interface CodedError extends Error {
+  code?: ErrorCode;
+  detail?: string; // The library's own message, if a formatter replaced it
+}
+
+/** An error for setErrorMessageFormatter to write a message for */
+export interface ErrorMessageContext {
+  code: ErrorCode;
+  message: string; // The library's own message, e.g. "Printer not found"
+  defaultMessage: string; // The code's entry in ERROR_MESSAGES
+}
+
+/** Writes the message of an error; null or undefined keeps the library's */
+export type ErrorMessageFormatter = (
+  context: ErrorMessageContext
+) => string | null | undefined;
+
+let errorMessageFormatter: ErrorMessageFormatter | null = null;
+
+/**
+ * Replace the messages of errors thrown by the library, e.g. with
+ * translated, user-friendly ones for a kiosk UI. Pass a function, or a map
+ * from error code to message; errors without an entry, or that the
+ * function returns null or undefined for, keep the library's message.
+ * Errors carry their code either way, and the library's message in
+ * `detail` when it was replaced.
+ * @param formatter - Formatter or message map, or null to restore the
+ * library's messages
+ */
+export async function setErrorMessageFormatter(
+  formatter: ErrorMessageFormatter | Partial<Record<ErrorCode, string>> | null
+): Promise<void> {
+  const nativeModule = await getNativeModule();
+  nativeModule.checkOwner?.();
+  if (formatter === null || typeof formatter === "function") {
+    errorMessageFormatter = formatter;
+  } else {
+    const messages = { ...formatter };
+    errorMessageFormatter = ({ code }) => messages[code];
+  }
+}
+
+/** Native errors start with their code, e.g. "[PrinterNotFound] " */
+const ERROR_CODE_PATTERN = /^\[(\w+)\] /;
+
+/** Split the code and the error type prefix off a native error message */
+function parseNativeMessage(nativeMessage: string): {
+  code?: ErrorCode;
+  ErrorType?: new (message: string) => Error;
+  message: string;
+} {
+  const match = ERROR_CODE_PATTERN.exec(nativeMessage);
+  let message = match ? nativeMessage.slice(match[0].length) : nativeMessage;
+  const type = PRINT_ERROR_TYPES.find(([prefix]) =>
+    message.startsWith(prefix)
+  );
+  if (type) {
+    message = message.slice(type[0].length);
+  }
+  return {
+    code: match?.[1] as ErrorCode | undefined,
+    ErrorType: type?.[1],
+    message,
+  };
+}
+
+/** Message of an error with the given code, from the formatter if it has one */
+function formatErrorMessage(code: ErrorCode, message: string): string {
+  return (
+    errorMessageFormatter?.({
+      code,
+      message,
+      defaultMessage: ERROR_MESSAGES[code] ?? message,
+    }) ?? message
+  );
+}
+
+/**
+ * Convert native print errors into typed errors with their code and
+ * formatted message, passing others through.
+ */
+function toPrintError(error: unknown): unknown {
+  if (!(error instanceof Error)) {
+    return error;
+  }
+  const { code, ErrorType, message } = parseNativeMessage(error.message);
+  const printError: CodedError = ErrorType ? new ErrorType(message) : error;
+  if (code) {
+    printError.code = code;
+    printError.message = formatErrorMessage(code, message);
+    if (printError.message !== message) {
+      printError.detail = message;
+    }
+  }
+  return printError;
+}
+
+/** A document about to be submitted, for a content policy */
+export interface ContentPolicyContext {
+  printerName?: string; // Unset for pools and printToMany
+  filePath?: string; // File being printed, for printFile
+  data?: Uint8Array; // Bytes being printed, for printBytes
+  text: string | null; // Extracted text; null if it couldn't be extracted
+  options?: PrintJobOptions | Record<string, string>;
+}
+
+/**
+ * Checks documents before they are submitted: returning false or a reason
+ * rejects the document, returning true or nothing lets it print
+ */
+export type ContentPolicy = (
+  context: ContentPolicyContext
+) => boolean | string | void | Promise<boolean | string | void>;
+
+let contentPolicy: ContentPolicy | null = null;
+
+/**
+ * Check every document printed with printFile or printBytes, on a printer,
+ * a pool or with printToMany, before it is submitted, e.g. to keep
+ * documents with credit card numbers from being printed. The policy gets
+ * the text of the document (see extractText) and can reject it by
+ * returning false or a reason, which is thrown as a ContentRejectedError
+ * with the code "ContentRejected". Dry runs are checked too.
+ * @param policy - Policy to apply, or null to remove it
+ */
+export async function setContentPolicy(
+  policy: ContentPolicy | null
+): Promise<void> {
+  const nativeModule = await getNativeModule();
+  nativeModule.checkOwner?.();
+  contentPolicy = policy;
+}
+
+/**
+ * Internal function to apply the content policy to a document about to be
+ * submitted
+ */
+async function checkContentPolicy(
+  context: Omit<ContentPolicyContext, "text">
+): Promise<void> {
+  const policy = contentPolicy;
+  if (!policy) {
+    return;
+  }
+  const document = context.filePath ?? context.data;
+  const text =
+    document === undefined
+      ? null
+      : await extractText(document).catch(() => null);
+  const verdict = await policy({ ...context, text });
+  if (verdict !== false && typeof verdict !== "string") {
+    return;
+  }
+  const reason = verdict || "Rejected by the content policy";
+  const error: CodedError = new ContentRejectedError(
+    formatErrorMessage("ContentRejected", reason)
+  );
+  error.code = "ContentRejected";
+  if (error.message !== reason) {
+    error.detail = reason;
+  }
+  throw error;
+}
+
+class PrinterWrapperImpl {
+  private _native: NativePrinter;
+  private _capabilities?: Promise<PrinterCapabilities | null>;
+  private _jobs?: NativePrinterJobs;
+
+  constructor(nativePrinter: NativePrinter) {
+    this._native = nativePrinter;
+
+    // Return a Proxy that intercepts property access
+    return new Proxy(this, {
+      get(target, prop, receiver) {
+        // If the property exists on the wrapper, use it
+        if (prop in target) {
+          const value = Reflect.get(target, prop, receiver);
+          // Bind functions to maintain correct 'this' context
+          return typeof value === "function" ? value.bind(target) : value;
+        }
+
+        // Otherwise, try to get it from the native printer
+        const nativeValue = (target._native as any)[prop];
+        if (nativeValue !== undefined) {
+          // Bind native functions to the native printer
+          return typeof nativeValue === "function"
+            ? nativeValue.bind(target._native)
+            : nativeValue;
+        }
+
+        return undefined;
+      },
+
+      // Make properties enumerable for iteration
+      ownKeys(target) {
+        const wrapperKeys = Reflect.ownKeys(target);
+        const nativeKeys = Reflect.ownKeys(target._native);
+        return [...new Set([...wrapperKeys, ...nativeKeys])];
+      },
+
+      // Required for ownKeys to work properly
+      getOwnPropertyDescriptor(target, prop) {
+        if (prop in target) {
+          return Reflect.getOwnPropertyDescriptor(target, prop);
+        }
+        if (prop in target._native) {
+          return {
+            enumerable: true,
+            configurable: true,
+          };
+        }
+        return undefined;
+      },
+    });
   }
 
   /**
-   * Print a file using this printer.
-   * @param filePath - Path to file to print
-   * @param options - Typed print options or raw properties
-   * @throws Error if print functionality unavailable
+   * Check if the printer exists on the system.
+   * @returns Promise resolving to true if printer exists
    */
-  async printFile(
-    filePath: string,
-    options?: PrintJobOptions | Record<string, string>
-  ): Promise<number> {
-    const nativeModule = await getNativeModule();
-    if (nativeModule.printFile) {
-      const { rawOptions, waitForCompletion } = this.convertOptions(options);
-      return await nativeModule.printFile(
-        this._native.name,
-        filePath,
-        rawOptions,
-        waitForCompletion
-      );
-    }
-    throw new Error("Print functionality not available");
+  async exists(): Promise<boolean> {
+    return await printerExists(this._native.name);
   }
 
   /**
-   * Print raw bytes using this printer.
-   * @param data - Byte data to print
-   * @param options - Typed print options or raw properties
-   * @throws Error if print functionality unavailable
+   * Enumerate the system again and update this printer's information, so
+   * changes show up before the cached printer list expires.
+   * @returns Promise resolving to false if the printer no longer exists
    */
-  async printBytes(
-    data: Uint8Array | Buffer,
-    options?: PrintJobOptions | Record<string, string>
-  ): Promise<number> {
-    const nativeModule = await getNativeModule();
-    if (nativeModule.printBytes) {
-      const { rawOptions, waitForCompletion } = this.convertOptions(options);
-      return await nativeModule.printBytes(
-        this._native.name,
-        data,
-        rawOptions,
-        waitForCompletion
-      );
+  async refresh(): Promise<boolean> {
+    const native = this._native as NativePrinter & {
+      refresh?: () => NativePrinter | null;
+    };
+    if (typeof native.refresh === "function") {
+      return native.refresh() !== null;
     }
-    throw new Error("Print bytes functionality not available");
+    await refreshPrinters();
+    const info = await getPrinterInfo(native.name);
+    if (info) {
+      this._native = info;
+    }
+    return info !== null;
   }
 
   /**
-   * Convert options to raw properties for the backend and extract waitForCompletion
+   * Check whether the printer device is reachable.
+   * Probes the device over the network (IPP or port check) instead of
+   * trusting the spooler state; local devices fall back to the spooler state.
+   * @returns Promise resolving to true if the printer is online
    */
-  private convertOptions(options?: PrintJobOptions | Record<string, string>): {
-    rawOptions?: Record<string, string>;
-    waitForCompletion: boolean;
-  } {
-    if (!options) {
-      return { rawOptions: undefined, waitForCompletion: true };
+  async isOnline(): Promise<boolean> {
+    const nativeModule = await getNativeModule();
+    if (nativeModule.isPrinterOnline) {
+      return await nativeModule.isPrinterOnline(this._native.name);
     }
+    throw new Error("Online check functionality not available");
+  }
 
-    // If it's already raw properties (has string keys and values)
-    if (this.isRawOptions(options)) {
-      return { rawOptions: options, waitForCompletion: true };
+  /**
+   * Wake the printer with a Wake-on-LAN magic packet, for network printers
+   * with a MAC address set. Call it before the first job of the day so the
+   * job doesn't time out on a sleeping printer.
+   * @param options - MAC and broadcast address overrides, and how long to
+   *   wait for the printer to answer
+   * @returns Promise resolving to true once the packet is sent, or with
+   *   timeoutMs, whether the printer answered in time
+   */
+  async wake(options?: WakeOptions): Promise<boolean> {
+    const nativeModule = await getNativeModule();
+    if (nativeModule.wakePrinter) {
+      return await nativeModule.wakePrinter(this._native.name, options);
     }
-
-    const typedOptions = options as PrintJobOptions;
-    const waitForCompletion = typedOptions.waitForCompletion !== false; // Default to true
-
-    // Convert typed options to raw (excluding waitForCompletion)
-    const rawOptions = printJobOptionsToRaw(typedOptions);
-
-    return { rawOptions, waitForCompletion };
+    throw new Error("Power management functionality not available");
   }
 
   /**
-   * Check if options are raw properties
+   * Get whether the printer is awake, from the power state it reports over
+   * IPP. Printers that don't report one only say whether they answered.
+   * @returns Promise resolving to the printer's power status
    */
-  private isRawOptions(
-    options: PrintJobOptions | Record<string, string>
-  ): options is Record<string, string> {
-    // If it has any of the PrintJobOptions specific keys, it's typed options
-    return !(
-      "jobName" in options ||
-      "raw" in options ||
-      "simple" in options ||
-      "cups" in options
-    );
+  async getPowerStatus(): Promise<PowerStatus> {
+    const nativeModule = await getNativeModule();
+    if (nativeModule.getPrinterPowerStatus) {
+      return await nativeModule.getPrinterPowerStatus(this._native.name);
+    }
+    throw new Error("Power management functionality not available");
   }
 
   /**
-   * Get active print jobs for this printer.
-   * @returns Promise resolving to array of active PrinterJob objects
+   * Make a label printer measure its labels again, e.g. after loading a roll
+   * of a different size or when labels print across the gap. Sends ZPL `~JC`,
+   * or for ESC/POS printers, which have no calibration command, a reset and
+   * a feed to the next label.
+   * @param options - The printer's language, detected if omitted
+   * @returns Promise resolving to the job ID of the command
+   * @throws {UnsupportedOptionError} If the language isn't given and the
+   *   printer isn't a known label printer
    */
-  async getActiveJobs(): Promise<PrinterJob[]> {
+  async calibrateMedia(options?: LabelCommandOptions): Promise<number> {
+    const nativeModule = await getNativeModule();
+    if (!nativeModule.calibrateMedia) {
+      throw new Error("Label printer functionality not available");
+    }
     try {
-      const nativeModule = await getNativeModule();
-      return nativeModule.getActiveJobsForPrinter
-        ? nativeModule.getActiveJobsForPrinter(this._native.name)
-        : [];
-    } catch (error) {
-      console.error(
-        `Failed to get active jobs for ${this._native.name}:`,
-        error
+      return await nativeModule.calibrateMedia(
+        this._native.name,
+        options?.language
       );
-      return [];
+    } catch (error) {
+      throw toPrintError(error);
     }
   }
 
   /**
-   * Get job history for this printer.
-   * @param limit - Maximum number of jobs to return (optional)
-   * @returns Promise resolving to array of completed/cancelled PrinterJob objects
+   * Feed blank labels through a label printer, e.g. to clear one torn off
+   * early.
+   * @param labels - Labels to feed, 1 to 100 (default 1)
+   * @param options - The printer's language, detected if omitted
+   * @returns Promise resolving to the job ID of the command
    */
-  async getJobHistory(limit?: number): Promise<PrinterJob[]> {
+  async feed(labels = 1, options?: LabelCommandOptions): Promise<number> {
+    const nativeModule = await getNativeModule();
+    if (!nativeModule.feedLabels) {
+      throw new Error("Label printer functionality not available");
+    }
     try {
-      const nativeModule = await getNativeModule();
-      return nativeModule.getJobHistoryForPrinter
-        ? nativeModule.getJobHistoryForPrinter(this._native.name, limit)
-        : [];
-    } catch (error) {
-      console.error(
-        `Failed to get job history for ${this._native.name}:`,
-        error
+      return await nativeModule.feedLabels(
+        this._native.name,
+        labels,
+        options?.language
       );
-      return [];
+    } catch (error) {
+      throw toPrintError(error);
     }
   }
 
   /**
-   * Get a specific job by ID if it belongs to this printer.
-   * @param jobId - Job ID to look up
-   * @returns Promise resolving to PrinterJob object if found, null otherwise
+   * Read why a ZPL label printer stopped, e.g. out of labels or ribbon, with
+   * a `~HS` host status query over its raw printing channel (TCP 9100).
+   * Spoolers rarely report these conditions for label printers.
+   * @returns Promise resolving to the printer's conditions
    */
-  async getJob(jobId: number): Promise<PrinterJob | null> {
-    try {
-      const nativeModule = await getNativeModule();
-      return nativeModule.getJobForPrinter
-        ? nativeModule.getJobForPrinter(this._native.name, jobId)
-        : null;
-    } catch (error) {
-      console.error(
-        `Failed to get job ${jobId} for ${this._native.name}:`,
-        error
-      );
-      return null;
+  async getLabelStatus(): Promise<LabelStatus> {
+    const nativeModule = await getNativeModule();
+    if (nativeModule.queryLabelStatus) {
+      return await nativeModule.queryLabelStatus(this._native.name);
     }
+    throw new Error("Label printer functionality not available");
   }
 
   /**
-   * Get all jobs (active and completed) for this printer.
-   * @returns Promise resolving to array of all PrinterJob objects
+   * Get ink/toner supply levels reported by the printer.
+   * @returns Promise resolving to the printer's supplies (empty if not reported)
    */
-  async getAllJobs(): Promise<PrinterJob[]> {
-    try {
-      const nativeModule = await getNativeModule();
-      return nativeModule.getAllJobsForPrinter
-        ? nativeModule.getAllJobsForPrinter(this._native.name)
-        : [];
-    } catch (error) {
-      console.error(`Failed to get all jobs for ${this._native.name}:`, error);
-      return [];
+  async getSupplies(): Promise<PrinterSupply[]> {
+    const nativeModule = await getNativeModule();
+    if (nativeModule.getPrinterSupplies) {
+      return await nativeModule.getPrinterSupplies(this._native.name);
     }
+    throw new Error("Supply level functionality not available");
   }
 
   /**
-   * Clean up old completed/cancelled jobs for this printer.
-   * @param maxAgeSeconds - Maximum age in seconds for jobs to keep
+   * Get the values the printer supports for job attributes. The printer is
+   * queried on the first call only; printers from getAllPrinters() don't
+   * query it up front.
+   * @returns Promise resolving to the capabilities, or null if the printer
+   *   doesn't report them
+   */
+  async getCapabilities(): Promise<PrinterCapabilities | null> {
+    const nativeModule = await getNativeModule();
+    if (!nativeModule.getPrinterCapabilities) {
+      throw new Error("Capability query functionality not available");
+    }
+    this._capabilities ??= nativeModule
+      .getPrinterCapabilities(this._native.name)
+      .catch(error => {
+        // Query again next time rather than caching the failure
+        this._capabilities = undefined;
+        throw error;
+      });
+    return await this._capabilities;
+  }
+
+  /**
+   * Get the page description languages the printer interprets, from the
+   * document formats it lists over IPP or else from its driver's name, to
+   * pick the format to send or convert documents to.
+   * @returns Promise resolving to the languages, most preferred first
+   */
+  async getSupportedFormats(): Promise<SupportedFormats> {
+    const nativeModule = await getNativeModule();
+    if (nativeModule.getPrinterSupportedFormats) {
+      return await nativeModule.getPrinterSupportedFormats(this._native.name);
+    }
+    throw new Error("Format detection functionality not available");
+  }
+
+  /**
+   * Get the current user's default preferences for this printer.
+   * Only supported on Windows; other platforms reject.
+   * @returns Promise resolving to the driver's orientation, paper, quality,
+   * color and duplex settings
+   */
+  async getPreferences(): Promise<PrinterPreferences> {
+    const nativeModule = await getNativeModule();
+    if (nativeModule.getPrinterPreferences) {
+      return await nativeModule.getPrinterPreferences(this._native.name);
+    }
+    throw new Error("Printer preferences functionality not available");
+  }
+
+  /**
+   * Change the current user's default preferences for this printer.
+   * Fields left undefined keep their current values. Only supported on
+   * Windows; on CUPS pass the equivalent settings as job options.
+   * @param preferences - Settings to change
+   * @returns Promise resolving to the preferences the driver accepted
+   */
+  async setPreferences(
+    preferences: PrinterPreferences
+  ): Promise<PrinterPreferences> {
+    const nativeModule = await getNativeModule();
+    if (nativeModule.setPrinterPreferences) {
+      return await nativeModule.setPrinterPreferences(
+        this._native.name,
+        preferences
+      );
+    }
+    throw new Error("Printer preferences functionality not available");
+  }
+
+  /**
+   * Get whether the printer is shared and who may print to it, from its
+   * Windows security descriptor or CUPS allow/deny lists.
+   * @returns Promise resolving to the printer's sharing and user lists
+   */
+  async getAccessControl(): Promise<AccessControl> {
+    const nativeModule = await getNativeModule();
+    if (nativeModule.getPrinterAccessControl) {
+      return await nativeModule.getPrinterAccessControl(this._native.name);
+    }
+    throw new Error("Access control functionality not available");
+  }
+
+  /**
+   * Share or unshare the printer and change who may print to it. Fields
+   * left undefined keep their current values; allowing users clears the
+   * denied users and vice versa. Needs administrator rights.
+   * @param access - Changes to apply
+   * @returns Promise resolving to the resulting access control
+   */
+  async setAccessControl(access: AccessControl): Promise<AccessControl> {
+    const nativeModule = await getNativeModule();
+    if (nativeModule.setPrinterAccessControl) {
+      return await nativeModule.setPrinterAccessControl(
+        this._native.name,
+        access
+      );
+    }
+    throw new Error("Access control functionality not available");
+  }
+
+  /**
+   * Get string representation of the printer.
+   * @returns Formatted printer information string
+   */
+  toString(): string {
+    const status = this._native.isDefault ? " (default)" : "";
+    const state = this._native.state ? ` [${this._native.state}]` : "";
+    return `${this._native.name}${status}${state}`;
+  }
+
+  /**
+   * Compare equality with another printer.
+   * Printers are considered equal if they are the same device, by
+   * `printerId`, even if one of them was renamed. Printers without an ID
+   * are compared by name.
+   * @param other - Printer, or printer information such as a serialized
+   * Printer, to compare with
+   * @returns True if both are the same printer
+   */
+  equals(other: Printer | NativePrinter): boolean {
+    if (this._native.printerId && other.printerId) {
+      return this._native.printerId === other.printerId;
+    }
+    return this._native.name === other.name;
+  }
+
+  /**
+   * Get the printer name.
+   * @returns Printer name
+   */
+  getName(): string {
+    return this._native.name;
+  }
+
+  /**
+   * Print a file using this printer.
+   * @param filePath - Path to file to print
+   * @param options - Typed print options or raw properties
+   * @throws TimeoutError if the job exceeds `timeoutMs`
+   * @throws InvalidDocumentError if `validateDocument` rejects the file
+   * @throws QueueFullError if a configured queue limit is reached
+   * @throws DuplicateJobError if `dedupeMode: "reject"` rejects a duplicate
+   * @throws UnsupportedOptionError if the printer reports it can't print
+   * the job's quality or dpi
+   * @throws PolicyViolationError if the job breaks a policy rule
+   * @throws Error if print functionality unavailable
+   */
+  async printFile(
+    filePath: string,
+    options: PrintJobOptions & { dryRun: true }
+  ): Promise<DryRunResult>;
+  async printFile(
+    filePath: string,
+    options?: PrintJobOptions | Record<string, string>
+  ): Promise<number>;
+  async printFile(
+    filePath: string,
+    options?: PrintJobOptions | Record<string, string>
+  ): Promise<number | DryRunResult> {
+    const nativeModule = await getNativeModule();
+    await checkContentPolicy({
+      printerName: this._native.name,
+      filePath,
+      options,
+    });
+    if (isDryRun(options)) {
+      if (!nativeModule.dryRunPrintFile) {
+        throw new Error("Dry run functionality not available");
+      }
+      try {
+        return await nativeModule.dryRunPrintFile(
+          this._native.name,
+          filePath,
+          printJobOptionsToRaw(options)
+        );
+      } catch (error) {
+        throw toPrintError(error);
+      }
+    }
+    if (nativeModule.printFile) {
+      const { rawOptions, waitForCompletion } = convertPrintOptions(options);
+      try {
+        return await nativeModule.printFile(
+          this._native.name,
+          filePath,
+          rawOptions,
+          waitForCompletion
+        );
+      } catch (error) {
+        throw toPrintError(error);
+      }
+    }
+    throw new Error("Print functionality not available");
+  }
+
+  /**
+   * Print raw bytes using this printer.
+   * @param data - Byte data to print
+   * @param options - Typed print options or raw properties
+   * @throws TimeoutError if the job exceeds `timeoutMs`
+   * @throws InvalidDocumentError if `validateDocument` rejects the data
+   * @throws QueueFullError if a configured queue limit is reached
+   * @throws DuplicateJobError if `dedupeMode: "reject"` rejects a duplicate
+   * @throws UnsupportedOptionError if the printer reports it can't print
+   * the job's quality or dpi
+   * @throws PolicyViolationError if the job breaks a policy rule
+   * @throws Error if print functionality unavailable
+   */
+  async printBytes(
+    data: Uint8Array | Buffer,
+    options: PrintJobOptions & { dryRun: true }
+  ): Promise<DryRunResult>;
+  async printBytes(
+    data: Uint8Array | Buffer,
+    options?: PrintJobOptions | Record<string, string>
+  ): Promise<number>;
+  async printBytes(
+    data: Uint8Array | Buffer,
+    options?: PrintJobOptions | Record<string, string>
+  ): Promise<number | DryRunResult> {
+    const nativeModule = await getNativeModule();
+    await checkContentPolicy({ printerName: this._native.name, data, options });
+    if (isDryRun(options)) {
+      if (!nativeModule.dryRunPrintBytes) {
+        throw new Error("Dry run functionality not available");
+      }
+      try {
+        return await nativeModule.dryRunPrintBytes(
+          this._native.name,
+          data,
+          printJobOptionsToRaw(options)
+        );
+      } catch (error) {
+        throw toPrintError(error);
+      }
+    }
+    if (nativeModule.printBytes) {
+      const { rawOptions, waitForCompletion } = convertPrintOptions(options);
+      try {
+        return await nativeModule.printBytes(
+          this._native.name,
+          data,
+          rawOptions,
+          waitForCompletion
+        );
+      } catch (error) {
+        throw toPrintError(error);
+      }
+    }
+    throw new Error("Print bytes functionality not available");
+  }
+
+  /**
+   * Print a diagnostic page generated natively: the printer's name, driver,
+   * location and the time over a one-inch alignment grid, for verifying a
+   * new installation without shipping a sample file.
+   * @param options - Typed print options or raw properties
+   * @returns Promise resolving to the job ID
+   * @throws QueueFullError if a configured queue limit is reached
+   * @throws Error if print functionality unavailable
+   */
+  async printTestPage(
+    options?: PrintJobOptions | Record<string, string>
+  ): Promise<number> {
+    const nativeModule = await getNativeModule();
+    if (nativeModule.printTestPage) {
+      const { rawOptions, waitForCompletion } = convertPrintOptions(options);
+      try {
+        return await nativeModule.printTestPage(
+          this._native.name,
+          rawOptions,
+          waitForCompletion
+        );
+      } catch (error) {
+        throw toPrintError(error);
+      }
+    }
+    throw new Error("Test page functionality not available");
+  }
+
+  /**
+   * Internal function to get the native Printer instance the job tracking
+   * methods go through, created on first use
+   */
+  private async jobs(): Promise<NativePrinterJobs> {
+    if (!this._jobs) {
+      const nativeModule = await getNativeModule();
+      this._jobs = new nativeModule.Printer(this._native.name);
+    }
+    return this._jobs;
+  }
+
+  /**
+   * Get active print jobs for this printer.
+   * @returns Promise resolving to array of active PrinterJob objects
+   */
+  async getActiveJobs(): Promise<PrinterJob[]> {
+    try {
+      return (await this.jobs()).getActiveJobs();
+    } catch (error) {
+      console.error(
+        `Failed to get active jobs for ${this._native.name}:`,
+        error
+      );
+      return [];
+    }
+  }
+
+  /**
+   * Get job history for this printer.
+   * @param limit - Maximum number of jobs to return (optional)
+   * @returns Promise resolving to array of finished PrinterJob objects
+   */
+  async getJobHistory(limit?: number): Promise<PrinterJob[]> {
+    try {
+      return (await this.jobs()).getJobHistory(limit);
+    } catch (error) {
+      console.error(
+        `Failed to get job history for ${this._native.name}:`,
+        error
+      );
+      return [];
+    }
+  }
+
+  /**
+   * Get a specific job by ID if it belongs to this printer.
+   * @param jobId - Job ID to look up
+   * @returns Promise resolving to PrinterJob object if found, null otherwise
+   */
+  async getJob(jobId: number): Promise<PrinterJob | null> {
+    try {
+      return (await this.jobs()).getJob(jobId);
+    } catch (error) {
+      console.error(
+        `Failed to get job ${jobId} for ${this._native.name}:`,
+        error
+      );
+      return null;
+    }
+  }
+
+  /**
+   * Get all jobs (active and completed) for this printer.
+   * @returns Promise resolving to array of all PrinterJob objects
+   */
+  async getAllJobs(): Promise<PrinterJob[]> {
+    try {
+      return (await this.jobs()).getAllJobs();
+    } catch (error) {
+      console.error(`Failed to get all jobs for ${this._native.name}:`, error);
+      return [];
+    }
+  }
+
+  /**
+   * Clean up old completed/cancelled jobs for this printer.
+   * @param maxAgeSeconds - Maximum age in seconds for jobs to keep
    * @returns Promise resolving to number of jobs cleaned up
    */
   async cleanupOldJobs(maxAgeSeconds: number): Promise<number> {
     try {
-      const nativeModule = await getNativeModule();
-      return nativeModule.cleanupOldJobsForPrinter
-        ? nativeModule.cleanupOldJobsForPrinter(
-            this._native.name,
-            maxAgeSeconds
-          )
-        : 0;
+      return (await this.jobs()).cleanupOldJobs(maxAgeSeconds);
     } catch (error) {
       console.error(
         `Failed to cleanup old jobs for ${this._native.name}:`,
@@ -1198,6 +3626,39 @@ export async function getAllPrinters(): Promise<Printer[]> {
   }
 }
 
+/**
+ * Get the printers whose location contains the given text, ignoring case,
+ * e.g. "3rd floor" for a printer located at "Building 2, 3rd Floor".
+ * @param location - Text to look for in printer locations
+ * @returns Promise resolving to array of Printer objects
+ */
+export async function getPrintersByLocation(
+  location: string
+): Promise<Printer[]> {
+  const nativeModule = await getNativeModule();
+  const nativePrinters = nativeModule.getPrintersByLocation
+    ? nativeModule.getPrintersByLocation(location)
+    : [];
+  return nativePrinters.map(
+    nativePrinter => new PrinterWrapperImpl(nativePrinter) as unknown as Printer
+  );
+}
+
+/**
+ * Get the available printers with a tag (see tagPrinter), ignoring case.
+ * @param tag - Tag to look for
+ * @returns Promise resolving to array of Printer objects
+ */
+export async function getPrintersByTag(tag: string): Promise<Printer[]> {
+  const nativeModule = await getNativeModule();
+  const nativePrinters = nativeModule.getPrintersByTag
+    ? nativeModule.getPrintersByTag(tag)
+    : [];
+  return nativePrinters.map(
+    nativePrinter => new PrinterWrapperImpl(nativePrinter) as unknown as Printer
+  );
+}
+
 /**
  * Get names of all available printers.
  * @returns Promise resolving to array of printer names
@@ -1215,121 +3676,1741 @@ export async function getAllPrinterNames(): Promise<string[]> {
 }
 
 /**
- * Get a specific printer by name.
- * @param name - Printer name to search for
- * @returns Promise resolving to Printer object if found, null otherwise
+ * Get a specific printer by name.
+ * @param name - Printer name to search for
+ * @returns Promise resolving to Printer object if found, null otherwise
+ */
+export async function getPrinterByName(name: string): Promise<Printer | null> {
+  try {
+    const nativeModule = await getNativeModule();
+    const nativePrinter = nativeModule.findPrinterByName
+      ? nativeModule.findPrinterByName(name)
+      : null;
+    return nativePrinter
+      ? (new PrinterWrapperImpl(nativePrinter) as unknown as Printer)
+      : null;
+  } catch (error) {
+    console.error(`Failed to get printer ${name}:`, error);
+    return null;
+  }
+}
+
+/**
+ * Get a printer's information by name without creating a Printer, for
+ * callers that only need metadata, such as stateless HTTP handlers.
+ * @param name - Printer name or alias
+ * @returns Promise resolving to the printer's information, or null if not
+ *   found
+ */
+export async function getPrinterInfo(
+  name: string
+): Promise<NativePrinter | null> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.getPrinterInfo) {
+    return nativeModule.getPrinterInfo(name);
+  }
+  throw new Error("Printer info functionality not available");
+}
+
+/**
+ * Serialize a printer's information to JSON. The library serializes
+ * printers the same way everywhere, e.g. in print server responses, so the
+ * result can be stored or sent and read back with `printerInfoFromJson()`.
+ * @param name - Printer name or alias
+ * @returns Promise resolving to the JSON, or null if the printer isn't found
+ */
+export async function printerInfoToJson(name: string): Promise<string | null> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.printerInfoToJson) {
+    return nativeModule.printerInfoToJson(name);
+  }
+  throw new Error("Printer info functionality not available");
+}
+
+/**
+ * Parse printer information serialized with `printerInfoToJson()`.
+ * @param json - Serialized printer information
+ * @returns Promise resolving to the printer's information
+ * @throws If the JSON is malformed or missing fields
+ */
+export async function printerInfoFromJson(
+  json: string
+): Promise<NativePrinter> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.printerInfoFromJson) {
+    return nativeModule.printerInfoFromJson(json);
+  }
+  throw new Error("Printer info functionality not available");
+}
+
+/**
+ * Check if a printer exists.
+ * @param name - Printer name to check
+ * @returns Promise resolving to true if printer exists, false otherwise
+ */
+export async function printerExists(name: string): Promise<boolean> {
+  try {
+    const nativeModule = await getNativeModule();
+    return nativeModule.printerExists
+      ? nativeModule.printerExists(name)
+      : false;
+  } catch (error) {
+    console.error(`Failed to check if printer ${name} exists:`, error);
+    return false;
+  }
+}
+
+/**
+ * Forget the cached list of system printers, so the next lookup sees
+ * printers added or removed since. The list is otherwise reused for
+ * `printerCacheTtlMs`.
+ */
+export async function refreshPrinters(): Promise<void> {
+  const nativeModule = await getNativeModule();
+  nativeModule.refreshPrinters?.();
+}
+
+/**
+ * Clean up resources and shutdown the printer module. New jobs are refused
+ * while shutting down; jobs still outstanding after the timeout are marked
+ * cancelled.
+ * @param options - Shutdown mode and timeout
+ * @returns Promise that resolves with what happened to outstanding jobs
+ */
+export async function shutdown(
+  options: ShutdownOptions = {}
+): Promise<ShutdownSummary> {
+  const mode = options.mode ?? "graceful";
+  if (!["graceful", "drain", "force"].includes(mode)) {
+    throw new Error(`Unknown shutdown mode: ${mode}`);
+  }
+
+  try {
+    // Only shutdown if the module was loaded
+    if (nativeModuleCache) {
+      return nativeModuleCache.shutdown({ ...options, mode });
+    }
+  } catch (error) {
+    console.error("Failed to shutdown:", error);
+  }
+  return { completed: 0, cancelled: 0, timedOut: 0 };
+}
+
+/**
+ * Make this JavaScript environment the owner of the library's process-wide
+ * state: the job tracker, configuration and state monitoring are shared by
+ * every environment that loads the module, such as Electron's main process
+ * and its Node-enabled renderers or worker threads. While attached, only
+ * this environment can call `configure()`, `initialize()` or `shutdown()`;
+ * others get an error. Ownership ends with `detach()` or when the
+ * environment exits.
+ * @throws If another environment is attached
+ */
+export async function attach(): Promise<void> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.attach) {
+    return nativeModule.attach();
+  }
+  throw new Error("Attach functionality not available");
+}
+
+/**
+ * Give up ownership taken with `attach()`.
+ * @returns Promise resolving to false if this environment wasn't attached
+ */
+export async function detach(): Promise<boolean> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.detach) {
+    return nativeModule.detach();
+  }
+  throw new Error("Attach functionality not available");
+}
+
+/**
+ * How many environments use the library and whether one of them, or this
+ * one, owns it.
+ * @returns Promise resolving to the attachment status
+ */
+export async function getAttachment(): Promise<Attachment> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.getAttachment) {
+    return nativeModule.getAttachment();
+  }
+  throw new Error("Attach functionality not available");
+}
+
+/**
+ * Static methods for creating Printer instances.
+ */
+export const PrinterConstructor: PrinterClass = {
+  fromName: (name: string) => getPrinterByName(name),
+  // @ts-expect-error: Prevent direct construction
+  new: () => {
+    throw new Error("Use PrinterConstructor.fromName() instead");
+  },
+};
+
+// Legacy exports for backward compatibility
+
+/** @deprecated Use getPrinterByName instead */
+export const findPrinter = getPrinterByName;
+
+/**
+ * Get the default printer.
+ * @returns Promise resolving to default printer if found, null otherwise
+ */
+export const getDefaultPrinter = async (): Promise<Printer | null> => {
+  const printers = await getAllPrinters();
+  return printers.find(p => p.isDefault) || null;
+};
+
+/**
+ * Print a file to a printer.
+ * @param printerName - Name of the printer
+ * @param filePath - Path to file to print
+ * @param options - Typed print options or raw properties
+ * @returns Promise<number> - Job ID, or a DryRunResult with `dryRun: true`
+ * @throws Error if printer not found
+ */
+export async function printFile(
+  printerName: string,
+  filePath: string,
+  options: PrintJobOptions & { dryRun: true }
+): Promise<DryRunResult>;
+export async function printFile(
+  printerName: string,
+  filePath: string,
+  options?: PrintJobOptions | Record<string, string>
+): Promise<number>;
+export async function printFile(
+  printerName: string,
+  filePath: string,
+  options?: PrintJobOptions | Record<string, string>
+): Promise<number | DryRunResult> {
+  const printer = await getPrinterByName(printerName);
+  if (!printer) {
+    throw new Error(`Printer not found: ${printerName}`);
+  }
+  // Narrowed separately so each call matches an overload
+  return isDryRun(options)
+    ? await printer.printFile(filePath, options)
+    : await printer.printFile(filePath, options);
+}
+
+/**
+ * Print raw bytes to a printer.
+ * @param printerName - Name of the printer
+ * @param data - Byte data to print
+ * @param options - Typed print options or raw properties
+ * @returns Promise<number> - Job ID, or a DryRunResult with `dryRun: true`
+ * @throws Error if printer not found
+ */
+export async function printBytes(
+  printerName: string,
+  data: Uint8Array | Buffer,
+  options: PrintJobOptions & { dryRun: true }
+): Promise<DryRunResult>;
+export async function printBytes(
+  printerName: string,
+  data: Uint8Array | Buffer,
+  options?: PrintJobOptions | Record<string, string>
+): Promise<number>;
+export async function printBytes(
+  printerName: string,
+  data: Uint8Array | Buffer,
+  options?: PrintJobOptions | Record<string, string>
+): Promise<number | DryRunResult> {
+  const printer = await getPrinterByName(printerName);
+  if (!printer) {
+    throw new Error(`Printer not found: ${printerName}`);
+  }
+  // Narrowed separately so each call matches an overload
+  return isDryRun(options)
+    ? await printer.printBytes(data, options)
+    : await printer.printBytes(data, options);
+}
+
+/**
+ * Extract the text of a PDF or PostScript document, in the order it is
+ * drawn, with a line break between lines and pages, e.g. to index print
+ * jobs or check their content before printing. Plain text documents are
+ * returned as they are. Text in images isn't found, and text drawn with
+ * fonts that don't say which characters they draw comes out garbled.
+ * @param file - Path of the document, or its contents
+ * @returns Promise resolving to the text
+ * @throws Error if the document isn't PDF, PostScript or text, or can't
+ * be read
+ */
+export async function extractText(
+  file: string | Uint8Array | Buffer
+): Promise<string> {
+  const nativeModule = await getNativeModule();
+  try {
+    if (typeof file === "string" && nativeModule.extractText) {
+      return await nativeModule.extractText(file);
+    }
+    if (typeof file !== "string" && nativeModule.extractTextBytes) {
+      return await nativeModule.extractTextBytes(file);
+    }
+  } catch (error) {
+    throw toPrintError(error);
+  }
+  throw new Error("Text extraction functionality not available");
+}
+
+/**
+ * Estimate the pages, sheets and color of a print job, e.g. to show its
+ * cost or ask for approval before calling printFile. The submission is
+ * checked like a dry run, so an estimate also fails where printing would.
+ * Jobs count as color unless the options, the printer's capabilities or
+ * its default color mode make them monochrome.
+ * @param printer - Printer or printer name
+ * @param file - Path to the file, or the raw bytes to print
+ * @param options - Print options the job would use
+ * @returns Promise resolving to the estimate
+ */
+export async function estimateJob(
+  printer: string | Printer,
+  file: string | Uint8Array | Buffer,
+  options?: PrintJobOptions | Record<string, string>
+): Promise<JobEstimate> {
+  const nativeModule = await getNativeModule();
+  const printerName = typeof printer === "string" ? printer : printer.name;
+  const { rawOptions } = convertPrintOptions(options);
+  try {
+    if (typeof file === "string" && nativeModule.estimateJob) {
+      return await nativeModule.estimateJob(printerName, file, rawOptions);
+    }
+    if (typeof file !== "string" && nativeModule.estimateBytesJob) {
+      return await nativeModule.estimateBytesJob(printerName, file, rawOptions);
+    }
+  } catch (error) {
+    throw toPrintError(error);
+  }
+  throw new Error("Job estimate functionality not available");
+}
+
+/**
+ * Resolve the options a job would be sent with, for debugging where an
+ * option came from. Options are merged from, in order of precedence: the
+ * options passed here, the defaults of the alias if `printer` is one, the
+ * printer's defaults (see setPrinterDefaults) and the library-wide
+ * `defaultJobOptions`. Typed options are then checked against the
+ * printer's capabilities like a dry run, which may set the raw properties
+ * they stand for.
+ * @param printer - Printer, printer name or alias
+ * @param options - Print options the job would use
+ * @returns Promise resolving to the options and where each came from
+ */
+export async function resolveJobOptions(
+  printer: string | Printer,
+  options?: PrintJobOptions | Record<string, string>
+): Promise<ResolvedJobOptions> {
+  const nativeModule = await getNativeModule();
+  const printerName = typeof printer === "string" ? printer : printer.name;
+  const { rawOptions } = convertPrintOptions(options);
+  if (nativeModule.resolveJobOptions) {
+    try {
+      return await nativeModule.resolveJobOptions(printerName, rawOptions);
+    } catch (error) {
+      throw toPrintError(error);
+    }
+  }
+  throw new Error("Option resolution functionality not available");
+}
+
+/**
+ * Print the same file to several printers. The file is read, checked and
+ * spooled once, and every printer receives the same copy.
+ * @param printers - Printers or printer names
+ * @param filePath - Path to file to print
+ * @param options - Typed print options or raw properties, used for every job
+ * @returns Promise resolving to one result per printer
+ * @throws Error if the file can't be printed at all (e.g. it doesn't exist)
+ */
+export async function printToMany(
+  printers: Array<string | Printer>,
+  filePath: string,
+  options?: PrintJobOptions | Record<string, string>
+): Promise<PrintToManyResult> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.printToMany) {
+    await checkContentPolicy({ filePath, options });
+    const { rawOptions, waitForCompletion } = convertPrintOptions(options);
+    const names = printers.map(printer =>
+      typeof printer === "string" ? printer : printer.name
+    );
+    let results: PrintDestinationResult[];
+    try {
+      results = await nativeModule.printToMany(
+        names,
+        filePath,
+        rawOptions,
+        waitForCompletion
+      );
+    } catch (error) {
+      throw toPrintError(error);
+    }
+    results = results.map(result => {
+      if (!result.error) {
+        return result;
+      }
+      const { code, message } = parseNativeMessage(result.error);
+      return code
+        ? {
+            ...result,
+            error: formatErrorMessage(code, message),
+            errorCode: code,
+          }
+        : { ...result, error: message };
+    });
+    const failed = results.filter(result => result.error).length;
+    return { results, succeeded: results.length - failed, failed };
+  }
+  throw new Error("Print functionality not available");
+}
+
+/**
+ * A group of printers that jobs are routed across. Each job goes to the
+ * first online printer ("failover") or the next online printer in turn
+ * ("round-robin"); a "failover" event is emitted whenever the printer the
+ * strategy would have used is skipped.
+ */
+export class PrinterPool {
+  readonly printers: readonly string[];
+  readonly strategy: PoolStrategy;
+  private readonly nativeModule: NativeModule;
+  private readonly poolId: number;
+  private readonly failoverListeners = new Set<
+    (event: PoolFailoverEvent) => void
+  >();
+
+  constructor(
+    nativeModule: NativeModule,
+    poolId: number,
+    printers: readonly string[],
+    strategy: PoolStrategy
+  ) {
+    this.nativeModule = nativeModule;
+    this.poolId = poolId;
+    this.printers = printers;
+    this.strategy = strategy;
+  }
+
+  /**
+   * Listen for failover events.
+   * @returns Function that removes the listener
+   */
+  on(
+    event: "failover",
+    listener: (event: PoolFailoverEvent) => void
+  ): () => void {
+    this.failoverListeners.add(listener);
+    return () => this.failoverListeners.delete(listener);
+  }
+
+  /**
+   * Print a file on the printer the strategy selects.
+   * @throws Error if no printer in the pool is available
+   */
+  async printFile(
+    filePath: string,
+    options?: PrintJobOptions | Record<string, string>
+  ): Promise<number> {
+    const nativeModule = this.nativeModule;
+    if (!nativeModule.poolPrintFile) {
+      throw new Error("Printer pool functionality not available");
+    }
+    await checkContentPolicy({ filePath, options });
+    const { rawOptions, waitForCompletion } = convertPrintOptions(options);
+    try {
+      return await nativeModule.poolPrintFile(
+        this.poolId,
+        filePath,
+        rawOptions,
+        waitForCompletion
+      );
+    } catch (error) {
+      throw toPrintError(error);
+    } finally {
+      this.emitFailovers();
+    }
+  }
+
+  /**
+   * Print raw bytes on the printer the strategy selects.
+   * @throws Error if no printer in the pool is available
+   */
+  async printBytes(
+    data: Uint8Array | Buffer,
+    options?: PrintJobOptions | Record<string, string>
+  ): Promise<number> {
+    const nativeModule = this.nativeModule;
+    if (!nativeModule.poolPrintBytes) {
+      throw new Error("Printer pool functionality not available");
+    }
+    await checkContentPolicy({ data, options });
+    const { rawOptions, waitForCompletion } = convertPrintOptions(options);
+    try {
+      return await nativeModule.poolPrintBytes(
+        this.poolId,
+        data,
+        rawOptions,
+        waitForCompletion
+      );
+    } catch (error) {
+      throw toPrintError(error);
+    } finally {
+      this.emitFailovers();
+    }
+  }
+
+  /** Release the pool; it can't be used afterwards */
+  close(): void {
+    this.nativeModule.removePrinterPool?.(this.poolId);
+    this.failoverListeners.clear();
+  }
+
+  private emitFailovers(): void {
+    const events = this.nativeModule.drainPoolEvents?.(this.poolId) ?? [];
+    for (const event of events) {
+      for (const listener of this.failoverListeners) {
+        try {
+          listener(event);
+        } catch (error) {
+          console.error("Error in pool failover listener:", error);
+        }
+      }
+    }
+  }
+}
+
+/**
+ * Create a pool of printers that jobs are routed across.
+ * @param printers - Printers or printer names, in failover order
+ * @param options - Routing strategy (default: "failover") and queue depth limit
+ * @returns Promise resolving to the pool
+ */
+export async function createPrinterPool(
+  printers: Array<string | Printer>,
+  options: PrinterPoolOptions = {}
+): Promise<PrinterPool> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.createPrinterPool) {
+    const names = printers.map(printer =>
+      typeof printer === "string" ? printer : printer.name
+    );
+    const strategy = options.strategy ?? "failover";
+    const { maxQueueDepth } = options;
+    if (
+      maxQueueDepth !== undefined &&
+      (!Number.isInteger(maxQueueDepth) || maxQueueDepth < 1)
+    ) {
+      throw new Error("maxQueueDepth must be a positive integer");
+    }
+    const poolId = nativeModule.createPrinterPool(
+      names,
+      strategy,
+      maxQueueDepth
+    );
+    return new PrinterPool(nativeModule, poolId, names, strategy);
+  }
+  throw new Error("Printer pool functionality not available");
+}
+
+/**
+ * Get the native module for synchronous APIs, which can't wait for it to load.
+ * @throws Error if no async API (e.g. initialize()) has loaded it yet
+ */
+function getLoadedNativeModule(): NativeModule {
+  if (!nativeModuleCache) {
+    throw new Error(
+      "Native module not loaded. Call initialize() or another async API " +
+        "before using synchronous print functions."
+    );
+  }
+  return nativeModuleCache;
+}
+
+/**
+ * Print a file and block until the job completes or fails. Intended for
+ * CLI tools and scripts; `waitForCompletion` is ignored.
+ * @param printerName - Name of the printer
+ * @param filePath - Path to file to print
+ * @param options - Typed print options or raw properties
+ * @param timeoutMs - Maximum time to wait for completion (default: no limit)
+ * @returns Job ID
+ * @throws TimeoutError if the timeout expires
+ * @throws Error if the printer isn't found
+ */
+export function printFileSync(
+  printerName: string,
+  filePath: string,
+  options?: PrintJobOptions | Record<string, string>,
+  timeoutMs?: number
+): number {
+  const nativeModule = getLoadedNativeModule();
+  if (nativeModule.printFileSync) {
+    const { rawOptions } = convertPrintOptions(options);
+    try {
+      return nativeModule.printFileSync(
+        printerName,
+        filePath,
+        rawOptions,
+        timeoutMs
+      );
+    } catch (error) {
+      throw toPrintError(error);
+    }
+  }
+  throw new Error("Synchronous print functionality not available");
+}
+
+/**
+ * Print raw bytes and block until the job completes or fails. Intended for
+ * CLI tools and scripts; `waitForCompletion` is ignored.
+ * @param printerName - Name of the printer
+ * @param data - Byte data to print
+ * @param options - Typed print options or raw properties
+ * @param timeoutMs - Maximum time to wait for completion (default: no limit)
+ * @returns Job ID
+ * @throws TimeoutError if the timeout expires
+ * @throws Error if the printer isn't found
+ */
+export function printBytesSync(
+  printerName: string,
+  data: Uint8Array | Buffer,
+  options?: PrintJobOptions | Record<string, string>,
+  timeoutMs?: number
+): number {
+  const nativeModule = getLoadedNativeModule();
+  if (nativeModule.printBytesSync) {
+    const { rawOptions } = convertPrintOptions(options);
+    try {
+      return nativeModule.printBytesSync(
+        printerName,
+        data,
+        rawOptions,
+        timeoutMs
+      );
+    } catch (error) {
+      throw toPrintError(error);
+    }
+  }
+  throw new Error("Synchronous print functionality not available");
+}
+
+/**
+ * Aggregate jobs, pages, sheets and bytes over completed jobs for billing
+ * and chargeback. Page counts come from spooler accounting where available,
+ * otherwise from the PDF page count taken before submission.
+ * @param query - Optional printer name and start time filters
+ * @returns Promise resolving to aggregated usage
+ */
+export async function getUsageStats(
+  query: UsageStatsQuery = {}
+): Promise<UsageStats> {
+  const since =
+    query.since instanceof Date ? query.since.getTime() / 1000 : query.since;
+
+  const nativeModule = await getNativeModule();
+  if (nativeModule.getUsageStats) {
+    return nativeModule.getUsageStats(query.printerName, since);
+  }
+  throw new Error("Usage accounting functionality not available");
+}
+
+/**
+ * Cancel every job that hasn't finished yet, e.g. after a printer jam.
+ * Jobs already accepted by the system spooler are not affected.
+ * @param printerName - Only cancel jobs for this printer
+ * @returns Promise resolving to the number of cancelled jobs
+ */
+export async function cancelAllJobs(printerName?: string): Promise<number> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.cancelAllJobs) {
+    return nativeModule.cancelAllJobs(printerName);
+  }
+  throw new Error("Job cancellation functionality not available");
+}
+
+/**
+ * Low bits of a job ID holding its sequence number within a second. Job
+ * IDs are `unixSeconds * 2 ** JOB_ID_SEQUENCE_BITS + sequence`: they
+ * increase across restarts of the process, so they don't collide with IDs
+ * stored by earlier runs, and stay below `Number.MAX_SAFE_INTEGER`.
+ */
+export const JOB_ID_SEQUENCE_BITS = 20;
+
+/**
+ * Second a job ID was issued in, decoded from the ID itself.
+ * @param jobId - Job ID
+ * @returns Date of the start of that second
+ */
+export function jobIdTime(jobId: number): Date {
+  return new Date(Math.floor(jobId / 2 ** JOB_ID_SEQUENCE_BITS) * 1000);
+}
+
+/**
+ * Remove all completed and cancelled jobs from the job tracker, e.g. to
+ * reset state between test runs.
+ * @param printerName - Only remove jobs for this printer
+ * @returns Promise resolving to the number of removed jobs
+ */
+export async function clearJobHistory(printerName?: string): Promise<number> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.clearJobHistory) {
+    return nativeModule.clearJobHistory(printerName);
+  }
+  throw new Error("Job tracking functionality not available");
+}
+
+/**
+ * Path of the payload retained for a job submitted with `keepSpoolFile`.
+ * The file is removed when the job leaves the job history or the library
+ * shuts down.
+ * @param jobId - Job ID
+ * @returns Promise resolving to the file path, or null if none is retained
+ */
+export async function getJobSpoolFile(jobId: number): Promise<string | null> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.getJobSpoolFile) {
+    return nativeModule.getJobSpoolFile(jobId);
+  }
+  throw new Error("Job tracking functionality not available");
+}
+
+/**
+ * What happened to a job: its state changes and the messages of the
+ * spooler, driver or remote printer handling it. The log is kept whatever
+ * the log level, holds the last 100 entries and leaves the job history
+ * along with the job.
+ * @param jobId - Job ID
+ * @returns Promise resolving to the entries, oldest first, or null if the
+ * job isn't tracked
+ */
+export async function getJobLog(jobId: number): Promise<JobLogEntry[] | null> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.getJobLog) {
+    return nativeModule.getJobLog(jobId);
+  }
+  throw new Error("Job tracking functionality not available");
+}
+
+/**
+ * Serialize a tracked job to JSON, in the format of webhooks, print server
+ * responses and JSONL exports. Times are Unix timestamps in seconds; of the
+ * job's options only `metadata` is included.
+ * @param jobId - Job ID
+ * @returns Promise resolving to the JSON, or null if the job isn't tracked
+ */
+export async function printerJobToJson(jobId: number): Promise<string | null> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.printerJobToJson) {
+    return nativeModule.printerJobToJson(jobId);
+  }
+  throw new Error("Job tracking functionality not available");
+}
+
+/**
+ * Find which process on this machine issued a job ID, from the configured
+ * `jobRegistry`, e.g. to tell apart jobs of several processes in a shared
+ * audit log or print queue.
+ * @param jobId - Job ID
+ * @returns Promise resolving to the process ID, or null if the registry
+ * doesn't remember the ID
+ * @throws If no job registry is configured
+ */
+export async function getJobProcess(jobId: number): Promise<number | null> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.getJobProcess) {
+    return nativeModule.getJobProcess(jobId);
+  }
+  throw new Error("Job tracking functionality not available");
+}
+
+/**
+ * Parse a job serialized with `printerJobToJson()`, or a line of a JSONL
+ * export, e.g. to read back an archived job history.
+ * @param json - Serialized job
+ * @returns Promise resolving to the job
+ * @throws If the JSON is malformed or missing fields
+ */
+export async function printerJobFromJson(json: string): Promise<PrinterJob> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.printerJobFromJson) {
+    return nativeModule.printerJobFromJson(json);
+  }
+  throw new Error("Job tracking functionality not available");
+}
+
+/**
+ * Resubmit a completed or failed job to the same printer with the same
+ * options. The job is printed from the payload retained with
+ * `keepSpoolFile`, or else from the original file if it still exists.
+ * @param jobId - ID of the finished job
+ * @returns Promise resolving to the new job's ID; its `reprintOf` is `jobId`
+ */
+export async function reprintJob(jobId: number): Promise<number> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.reprintJob) {
+    try {
+      return nativeModule.reprintJob(jobId);
+    } catch (error) {
+      throw toPrintError(error);
+    }
+  }
+  throw new Error("Reprint functionality not available");
+}
+
+/**
+ * Move the jobs queued for one printer to another, e.g. after it was removed
+ * or went offline. Jobs that haven't started printing are moved as they are;
+ * jobs that failed because `fromPrinter` was removed are reprinted on
+ * `toPrinter` like `reprintJob()`, unless they were reprinted already.
+ * @param fromPrinter - Printer the jobs were submitted to
+ * @param toPrinter - Printer to print them on instead
+ * @returns Promise resolving to the moved, requeued and unavailable jobs
+ */
+export async function migrateJobs(
+  fromPrinter: string,
+  toPrinter: string
+): Promise<JobMigration> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.migrateJobs) {
+    try {
+      return nativeModule.migrateJobs(fromPrinter, toPrinter);
+    } catch (error) {
+      throw toPrintError(error);
+    }
+  }
+  throw new Error("Job migration functionality not available");
+}
+
+/**
+ * Filter, sort and page the tracked jobs without fetching the whole history,
+ * e.g. `queryJobs({ states: ["cancelled"], limit: 20, offset: 40 })`.
+ * Jobs held for approval are included.
+ * @param query - Filters, sort order and page
+ * @returns Promise resolving to the page of jobs and the number that matched
+ */
+export async function queryJobs(query: JobQuery = {}): Promise<JobPage> {
+  const toUnix = (time?: Date | number) =>
+    time instanceof Date ? time.getTime() / 1000 : time;
+  for (const [name, value] of [
+    ["offset", query.offset],
+    ["limit", query.limit],
+  ] as const) {
+    if (value !== undefined && (!Number.isSafeInteger(value) || value < 0)) {
+      throw new Error(`Invalid ${name}: ${value}`);
+    }
+  }
+
+  const nativeModule = await getNativeModule();
+  if (nativeModule.queryJobs) {
+    return nativeModule.queryJobs({
+      ...query,
+      since: toUnix(query.since),
+      until: toUnix(query.until),
+    });
+  }
+  throw new Error("Job tracking functionality not available");
+}
+
+/** Which jobs findJobs() returns */
+export interface FindJobsQuery {
+  printerName?: string;
+  /** Values the job's metadata must have; other keys are ignored */
+  metadata?: Record<string, string>;
+}
+
+/**
+ * Find tracked jobs by the metadata they were submitted with, e.g.
+ * `findJobs({ metadata: { orderId: "A-1001" } })`.
+ * @param query - Printer and metadata values to match
+ * @returns Promise resolving to the matching jobs, oldest first
+ */
+export async function findJobs(query: FindJobsQuery): Promise<PrinterJob[]> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.findJobs) {
+    return nativeModule.findJobs(query);
+  }
+  throw new Error("Job tracking functionality not available");
+}
+
+/**
+ * Write the tracked jobs, including jobs held for approval, to a file for
+ * audit and retention, oldest first. Records have the fields of
+ * `PrinterJob` with times as Unix timestamps. The file is written natively
+ * and replaced only once the export is complete.
+ * @param path - File to write
+ * @param options - Format ("jsonl" by default) and start time
+ * @returns Promise resolving to the number of jobs written
+ */
+export async function exportJobHistory(
+  path: string,
+  options: ExportJobHistoryOptions = {}
+): Promise<number> {
+  const since =
+    options.since instanceof Date
+      ? options.since.getTime() / 1000
+      : options.since;
+
+  const nativeModule = await getNativeModule();
+  if (nativeModule.exportJobHistory) {
+    return await nativeModule.exportJobHistory(path, options.format, since);
+  }
+  throw new Error("Job history export functionality not available");
+}
+
+/**
+ * Send job lifecycle events to an HTTP endpoint from a native background
+ * thread. Each event is POSTed as JSON with the job's fields, and retried
+ * with exponential backoff on connection errors, timeouts and 408, 429 or
+ * 5xx responses. With a `secret`, the `X-Printers-Signature` header is
+ * `sha256=` and the hex HMAC-SHA256 of the body.
+ * @param config - Endpoint and delivery options, or null to stop sending
+ */
+export async function setWebhook(config: WebhookConfig | null): Promise<void> {
+  if (config) {
+    for (const [name, value] of [
+      ["maxRetries", config.maxRetries],
+      ["timeoutMs", config.timeoutMs],
+    ] as const) {
+      if (value !== undefined && (!Number.isSafeInteger(value) || value < 0)) {
+        throw new Error(`Invalid ${name}: ${value}`);
+      }
+    }
+  }
+
+  const nativeModule = await getNativeModule();
+  if (nativeModule.setWebhook) {
+    nativeModule.setWebhook(config);
+    return;
+  }
+  throw new Error("Webhook functionality not available");
+}
+
+/**
+ * Replace the print policy rules every file and byte job is checked
+ * against when it is submitted, including dry runs. A job that breaks a
+ * rule is rejected with a PolicyViolationError naming it. Color rules use
+ * the job's `requestingUser`, or the user running the process, and the
+ * color its options and printer make it print in; keyword rules use the
+ * text extractText finds in the document.
+ * @param rules - Rules, checked in order; an empty list removes them all
+ */
+export async function setPolicyRules(rules: PolicyRule[]): Promise<void> {
+  for (const rule of rules) {
+    const { maxPages } = rule;
+    if (
+      maxPages !== undefined &&
+      (!Number.isSafeInteger(maxPages) || maxPages < 0)
+    ) {
+      throw new Error(`Invalid maxPages: ${maxPages}`);
+    }
+  }
+
+  const nativeModule = await getNativeModule();
+  if (nativeModule.setPolicyRules) {
+    nativeModule.setPolicyRules(rules);
+    return;
+  }
+  throw new Error("Policy functionality not available");
+}
+
+/** Get the print policy rules set with setPolicyRules */
+export async function getPolicyRules(): Promise<PolicyRule[]> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.getPolicyRules) {
+    return nativeModule.getPolicyRules();
+  }
+  throw new Error("Policy functionality not available");
+}
+
+/**
+ * Send a job submitted with `requireApproval` to its printer. The job's
+ * `timeoutMs` starts counting from approval.
+ * @param jobId - ID of the held job
+ */
+export async function approveJob(jobId: number): Promise<void> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.approveJob) {
+    try {
+      return nativeModule.approveJob(jobId);
+    } catch (error) {
+      throw toPrintError(error);
+    }
+  }
+  throw new Error("Job approval functionality not available");
+}
+
+/**
+ * Cancel a job submitted with `requireApproval` without printing it.
+ * @param jobId - ID of the held job
+ * @param reason - Recorded as the job's error message (default: "Rejected")
+ */
+export async function rejectJob(jobId: number, reason?: string): Promise<void> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.rejectJob) {
+    try {
+      return nativeModule.rejectJob(jobId, reason);
+    } catch (error) {
+      throw toPrintError(error);
+    }
+  }
+  throw new Error("Job approval functionality not available");
+}
+
+/**
+ * Jobs waiting for approval, oldest first. Held jobs aren't included in
+ * the active jobs or the job history until they are approved or rejected.
+ * @param printerName - Only return jobs for this printer
+ */
+export async function getHeldJobs(printerName?: string): Promise<PrinterJob[]> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.getHeldJobs) {
+    return nativeModule.getHeldJobs(printerName);
+  }
+  throw new Error("Job approval functionality not available");
+}
+
+const STALL_EVENT_DRAIN_INTERVAL_MS = 250;
+
+const jobStalledListeners = new Set<JobStalledListener>();
+let stallEventDrainInterval: any = null;
+
+/**
+ * Listen for jobs stuck in processing. Jobs are only checked once a
+ * `stallThresholdMs` is configured; `stallAction` decides whether a stalled
+ * job is left running, cancelled or retried.
+ * @param listener - Called with each stalled job
+ * @returns Promise resolving to a function that removes the listener
+ */
+export async function onJobStalled(
+  listener: JobStalledListener
+): Promise<() => void> {
+  const nativeModule = await getNativeModule();
+  if (
+    !nativeModule.setJobStalledBuffering ||
+    !nativeModule.drainJobStalledEvents
+  ) {
+    throw new Error("Stalled job functionality not available");
+  }
+
+  jobStalledListeners.add(listener);
+  if (!stallEventDrainInterval) {
+    nativeModule.setJobStalledBuffering(true);
+    stallEventDrainInterval = setInterval(
+      drainJobStalledEvents,
+      STALL_EVENT_DRAIN_INTERVAL_MS
+    );
+    // Don't keep the process alive just to deliver stalled job events
+    stallEventDrainInterval.unref?.();
+  }
+
+  return () => {
+    if (!jobStalledListeners.delete(listener)) {
+      return;
+    }
+    if (jobStalledListeners.size === 0 && stallEventDrainInterval) {
+      clearInterval(stallEventDrainInterval);
+      stallEventDrainInterval = null;
+      nativeModule.setJobStalledBuffering?.(false);
+    }
+  };
+}
+
+/**
+ * Internal function to deliver buffered stalled job events to listeners
+ */
+function drainJobStalledEvents(): void {
+  const events = nativeModuleCache?.drainJobStalledEvents?.() ?? [];
+  for (const event of events) {
+    for (const listener of jobStalledListeners) {
+      try {
+        listener(event);
+      } catch (error) {
+        console.error("Error in job stalled listener:", error);
+      }
+    }
+  }
+}
+
+// ===== PRINTER ALIASES =====
+
+/**
+ * Define a logical printer name, such as "RECEIPT", for a concrete printer.
+ * The alias is accepted wherever a printer name is; jobs submitted through
+ * it go to the printer with the alias's default options, which take
+ * precedence over the library-wide defaults.
+ * @param name - Alias name
+ * @param printer - Printer or printer name the alias stands for
+ * @param defaultOptions - Options applied to jobs submitted through the alias
+ */
+export async function definePrinterAlias(
+  name: string,
+  printer: string | Printer,
+  defaultOptions?: PrintJobOptions
+): Promise<void> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.definePrinterAlias) {
+    nativeModule.definePrinterAlias(
+      name,
+      typeof printer === "string" ? printer : printer.name,
+      defaultOptions && printJobOptionsToRaw(defaultOptions)
+    );
+    return;
+  }
+  throw new Error("Printer alias functionality not available");
+}
+
+/**
+ * Remove a printer alias.
+ * @param name - Alias name
+ * @returns Promise resolving to true if the alias existed
+ */
+export async function removePrinterAlias(name: string): Promise<boolean> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.removePrinterAlias) {
+    return nativeModule.removePrinterAlias(name);
+  }
+  throw new Error("Printer alias functionality not available");
+}
+
+/**
+ * Get all printer aliases, sorted by name.
+ * @returns Promise resolving to the defined aliases
+ */
+export async function getPrinterAliases(): Promise<PrinterAlias[]> {
+  const nativeModule = await getNativeModule();
+  return nativeModule.getPrinterAliases ? nativeModule.getPrinterAliases() : [];
+}
+
+/**
+ * Set the default options of a printer's jobs. They take precedence over
+ * the library-wide defaults, and the options of an alias and of the job
+ * itself take precedence over them. Persisted to the alias file if one
+ * is set.
+ * @param printer - Printer, printer name or alias of the printer
+ * @param options - Default options, or null to remove them
+ */
+export async function setPrinterDefaults(
+  printer: string | Printer,
+  options: PrintJobOptions | null
+): Promise<void> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.setPrinterDefaults) {
+    nativeModule.setPrinterDefaults(
+      typeof printer === "string" ? printer : printer.name,
+      options && printJobOptionsToRaw(options)
+    );
+    return;
+  }
+  throw new Error("Printer defaults functionality not available");
+}
+
+/**
+ * Get the default options of a printer's jobs, as raw properties.
+ * @param printer - Printer, printer name or alias of the printer
+ * @returns Promise resolving to the defaults, empty if none are set
+ */
+export async function getPrinterDefaults(
+  printer: string | Printer
+): Promise<Record<string, string>> {
+  const nativeModule = await getNativeModule();
+  const printerName = typeof printer === "string" ? printer : printer.name;
+  return nativeModule.getPrinterDefaults
+    ? nativeModule.getPrinterDefaults(printerName)
+    : {};
+}
+
+/**
+ * Add tags to a printer, e.g. "color" or "floor-3", to find it later with
+ * getPrintersByTag. Tags are persisted to the alias file if one is set.
+ * @param printer - Printer, printer name or alias of the printer
+ * @param tags - Tags to add
+ */
+export async function tagPrinter(
+  printer: string | Printer,
+  tags: string[]
+): Promise<void> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.tagPrinter) {
+    nativeModule.tagPrinter(
+      typeof printer === "string" ? printer : printer.name,
+      tags
+    );
+    return;
+  }
+  throw new Error("Printer tags functionality not available");
+}
+
+/**
+ * Remove tags from a printer.
+ * @param printer - Printer, printer name or alias of the printer
+ * @param tags - Tags to remove; all of them if omitted
+ */
+export async function untagPrinter(
+  printer: string | Printer,
+  tags?: string[]
+): Promise<void> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.untagPrinter) {
+    nativeModule.untagPrinter(
+      typeof printer === "string" ? printer : printer.name,
+      tags ?? null
+    );
+    return;
+  }
+  throw new Error("Printer tags functionality not available");
+}
+
+/**
+ * Get a printer's tags.
+ * @param printer - Printer, printer name or alias of the printer
+ * @returns Promise resolving to the tags, sorted
+ */
+export async function getPrinterTags(
+  printer: string | Printer
+): Promise<string[]> {
+  const nativeModule = await getNativeModule();
+  const printerName = typeof printer === "string" ? printer : printer.name;
+  return nativeModule.getPrinterTags
+    ? nativeModule.getPrinterTags(printerName)
+    : [];
+}
+
+/**
+ * Persist printer aliases to a JSON file. If the file exists its aliases
+ * replace the ones defined so far; otherwise it is created from them. Every
+ * later change is written back to the file. The file may also set the
+ * library-wide `defaultJobOptions` (as raw properties).
+ * @param path - File path, or null to stop persisting
+ * @param options - Whether to re-read the file when it changes on disk
+ */
+export async function setPrinterAliasFile(
+  path: string | null,
+  options: PrinterAliasFileOptions = {}
+): Promise<void> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.setPrinterAliasFile) {
+    nativeModule.setPrinterAliasFile(path, options.watch ?? false);
+    return;
+  }
+  throw new Error("Printer alias functionality not available");
+}
+
+/**
+ * Get the file printer aliases are persisted to.
+ * @returns Promise resolving to the file path, or null if not persisting
+ */
+export async function getPrinterAliasFile(): Promise<string | null> {
+  const nativeModule = await getNativeModule();
+  return nativeModule.getPrinterAliasFile?.() ?? null;
+}
+
+/**
+ * Re-read the printer alias file set with setPrinterAliasFile, applying its
+ * aliases and default job options without restarting the process.
+ * @returns Promise resolving to true if anything changed
+ * @throws Error if no alias file is set or it can't be read
+ */
+export async function reloadConfig(): Promise<boolean> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.reloadConfig) {
+    return nativeModule.reloadConfig();
+  }
+  throw new Error("Printer alias functionality not available");
+}
+
+const CONFIG_EVENT_DRAIN_INTERVAL_MS = 250;
+
+const configChangedListeners = new Set<ConfigChangedListener>();
+let configEventDrainInterval: any = null;
+
+/**
+ * Listen for changes to the printer alias configuration, whether made
+ * through definePrinterAlias/removePrinterAlias, reloadConfig, or picked up
+ * from a watched alias file.
+ * @param listener - Called with each change
+ * @returns Promise resolving to a function that removes the listener
+ */
+export async function onConfigChanged(
+  listener: ConfigChangedListener
+): Promise<() => void> {
+  const nativeModule = await getNativeModule();
+  if (
+    !nativeModule.setConfigEventBuffering ||
+    !nativeModule.drainConfigEvents
+  ) {
+    throw new Error("Printer alias functionality not available");
+  }
+
+  configChangedListeners.add(listener);
+  if (!configEventDrainInterval) {
+    nativeModule.setConfigEventBuffering(true);
+    configEventDrainInterval = setInterval(
+      drainConfigEvents,
+      CONFIG_EVENT_DRAIN_INTERVAL_MS
+    );
+    // Don't keep the process alive just to deliver config events
+    configEventDrainInterval.unref?.();
+  }
+
+  return () => {
+    if (!configChangedListeners.delete(listener)) {
+      return;
+    }
+    if (configChangedListeners.size === 0 && configEventDrainInterval) {
+      clearInterval(configEventDrainInterval);
+      configEventDrainInterval = null;
+      nativeModule.setConfigEventBuffering?.(false);
+    }
+  };
+}
+
+/**
+ * Internal function to deliver buffered configuration changes to listeners
+ */
+function drainConfigEvents(): void {
+  const events = nativeModuleCache?.drainConfigEvents?.() ?? [];
+  for (const event of events) {
+    for (const listener of configChangedListeners) {
+      try {
+        listener(event);
+      } catch (error) {
+        console.error("Error in config changed listener:", error);
+      }
+    }
+  }
+}
+
+// ===== REMOTE PRINTERS =====
+
+/**
+ * Define a printer whose jobs are forwarded to another instance, such as a
+ * print server started with `startPrintServer` or an IPP printer URI. Jobs
+ * are tracked locally like any other; the remote job is polled and its
+ * outcome becomes the local job's.
+ * @param name - Local printer name to submit jobs to
+ * @param options - Remote URL, printer and credentials
+ */
+export async function defineRemotePrinter(
+  name: string,
+  options: RemotePrinterOptions
+): Promise<void> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.defineRemotePrinter) {
+    nativeModule.defineRemotePrinter(name, options);
+    return;
+  }
+  throw new Error("Remote printer functionality not available");
+}
+
+/**
+ * Remove a remote printer. Jobs already forwarded are still followed.
+ * @param name - Local printer name
+ * @returns Promise resolving to true if the remote printer existed
+ */
+export async function removeRemotePrinter(name: string): Promise<boolean> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.removeRemotePrinter) {
+    return nativeModule.removeRemotePrinter(name);
+  }
+  throw new Error("Remote printer functionality not available");
+}
+
+/**
+ * Get all remote printers, sorted by name. Auth tokens are not returned.
+ * @returns Promise resolving to the defined remote printers
+ */
+export async function getRemotePrinters(): Promise<RemotePrinter[]> {
+  const nativeModule = await getNativeModule();
+  return nativeModule.getRemotePrinters ? nativeModule.getRemotePrinters() : [];
+}
+
+// ===== PRINTER CREDENTIALS =====
+
+/**
+ * Set the credentials used for a protected CUPS queue or IPP printer, or
+ * the default for every printer. Credentials are only offered when the
+ * server asks for authentication.
+ * @param name - Printer, printer name or IPP printer URI, or null for the
+ *   default
+ * @param credentials - Credentials to use, or null to clear them
+ */
+export async function setPrinterCredentials(
+  name: string | Printer | null,
+  credentials: PrinterCredentials | null
+): Promise<void> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.setPrinterCredentials) {
+    nativeModule.setPrinterCredentials(
+      name === null || typeof name === "string" ? name : name.name,
+      credentials
+    );
+    return;
+  }
+  throw new Error("Printer credentials functionality not available");
+}
+
+// ===== TLS =====
+
+/**
+ * Set how the certificates of ipps:// and https:// destinations are
+ * verified. By default they must chain to the system trust store.
+ * @param destination - Host, host:port or URI, or null for the default
+ * @param options - CA bundle and verification options, or null to restore
+ *   the defaults
+ */
+export async function setTlsOptions(
+  destination: string | null,
+  options: TlsOptions | null
+): Promise<void> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.setTlsOptions) {
+    nativeModule.setTlsOptions(destination, options);
+    return;
+  }
+  throw new Error("TLS functionality not available");
+}
+
+// ===== SPOOL ENCRYPTION =====
+
+/**
+ * Encrypt the payloads of jobs held for approval on disk with AES-GCM, so
+ * sensitive documents awaiting approval aren't readable there. The key is
+ * kept in memory only: set it again after a restart before approving jobs
+ * held with it.
+ * @param key - 16 bytes for AES-128-GCM or 32 for AES-256-GCM, or null to
+ *   stop encrypting new payloads
+ */
+export async function setSpoolEncryptionKey(
+  key: Uint8Array | Buffer | null
+): Promise<void> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.setSpoolEncryptionKey) {
+    nativeModule.setSpoolEncryptionKey(key);
+    return;
+  }
+  throw new Error("Spool encryption functionality not available");
+}
+
+/**
+ * Set the key redacted job names and paths are hashed with. Without one,
+ * each process uses a random key, so the same name gets a different hash
+ * after a restart.
+ * @param key - At least 16 bytes, or null to go back to a random key
+ */
+export async function setRedactionKey(
+  key: Uint8Array | Buffer | null
+): Promise<void> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.setRedactionKey) {
+    nativeModule.setRedactionKey(key);
+    return;
+  }
+  throw new Error("Redaction functionality not available");
+}
+
+// ===== PRINTER POWER =====
+
+/**
+ * Set the MAC address used to wake a network printer with Wake-on-LAN.
+ * @param printerName - Printer or printer name
+ * @param mac - MAC address ("aa:bb:cc:dd:ee:ff"), or null to remove it
+ */
+export async function setPrinterMacAddress(
+  printerName: string | Printer,
+  mac: string | null
+): Promise<void> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.setPrinterMacAddress) {
+    nativeModule.setPrinterMacAddress(
+      typeof printerName === "string" ? printerName : printerName.name,
+      mac
+    );
+    return;
+  }
+  throw new Error("Power management functionality not available");
+}
+
+// ===== PRINTER INSTALLATION =====
+
+/**
+ * Install a print queue using an installed driver and an existing port.
+ * Only supported on Windows.
+ * @param installation - Name, port and driver of the new printer
+ */
+export async function installPrinter(
+  installation: PrinterInstallation
+): Promise<void> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.installPrinter) {
+    return await nativeModule.installPrinter(installation);
+  }
+  throw new Error("Printer installation functionality not available");
+}
+
+/**
+ * Remove a print queue. The spooler deletes it once its jobs finish.
+ * Only supported on Windows.
+ * @param printerName - Printer or printer name
+ */
+export async function removePrinter(
+  printerName: string | Printer
+): Promise<void> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.removePrinter) {
+    return await nativeModule.removePrinter(
+      typeof printerName === "string" ? printerName : printerName.name
+    );
+  }
+  throw new Error("Printer installation functionality not available");
+}
+
+/**
+ * List the installed printer drivers. Only supported on Windows.
+ * @returns Promise resolving to the drivers installPrinter can use
+ */
+export async function listDrivers(): Promise<PrinterDriver[]> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.listDrivers) {
+    return await nativeModule.listDrivers();
+  }
+  throw new Error("Printer installation functionality not available");
+}
+
+// ===== CUPS QUEUES =====
+
+/**
+ * Add a queue to the local CUPS server, or modify the queue with that name.
+ * Needs CUPS administrator credentials (see setPrinterCredentials) unless
+ * the process may already administer CUPS. Only supported on Unix.
+ * @param queue - Name, device URI and driver (PPD file or model) of the queue
+ */
+export async function addCupsQueue(queue: CupsQueue): Promise<void> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.addCupsQueue) {
+    return await nativeModule.addCupsQueue(queue);
+  }
+  throw new Error("CUPS queue functionality not available");
+}
+
+/**
+ * Delete a queue from the local CUPS server, cancelling its jobs.
+ * Only supported on Unix.
+ * @param name - Queue name
+ */
+export async function deleteCupsQueue(name: string): Promise<void> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.deleteCupsQueue) {
+    return await nativeModule.deleteCupsQueue(name);
+  }
+  throw new Error("CUPS queue functionality not available");
+}
+
+/**
+ * Enable a CUPS queue so it accepts and prints jobs, or disable it so it
+ * stops printing (jobs still queue up). Only supported on Unix.
+ * @param name - Queue name
+ * @param enabled - Whether the queue should print
+ */
+export async function setQueueEnabled(
+  name: string,
+  enabled: boolean
+): Promise<void> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.setQueueEnabled) {
+    return await nativeModule.setQueueEnabled(name, enabled);
+  }
+  throw new Error("CUPS queue functionality not available");
+}
+
+// ===== TEMPLATES =====
+
+/**
+ * Convert template variables to the strings substituted into the template
+ */
+function templateVariablesToNative(
+  variables: Record<string, string | number>
+): Record<string, string> {
+  const native: Record<string, string> = {};
+  for (const [name, value] of Object.entries(variables)) {
+    native[name] = String(value);
+  }
+  return native;
+}
+
+/**
+ * Register a named template with `{{variable}}` placeholders, replacing any
+ * template with the same name. The body is parsed once here; variables are
+ * substituted natively for each job. Values that would inject printer
+ * commands (`^` or `~` in ZPL, control characters in ESC/POS) are rejected.
+ * @param name - Template name
+ * @param body - Template body, e.g. a ZPL label
+ * @param options - Template format (default "text")
+ * @throws Error if a placeholder is unclosed or not a valid variable name
+ */
+export async function registerTemplate(
+  name: string,
+  body: string,
+  options: RegisterTemplateOptions = {}
+): Promise<void> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.registerTemplate) {
+    nativeModule.registerTemplate(name, body, options.format);
+    return;
+  }
+  throw new Error("Template functionality not available");
+}
+
+/**
+ * Remove a template.
+ * @param name - Template name
+ * @returns Promise resolving to true if the template existed
  */
-export async function getPrinterByName(name: string): Promise<Printer | null> {
-  try {
-    const nativeModule = await getNativeModule();
-    const nativePrinter = nativeModule.findPrinterByName
-      ? nativeModule.findPrinterByName(name)
-      : null;
-    return nativePrinter
-      ? (new PrinterWrapperImpl(nativePrinter) as unknown as Printer)
-      : null;
-  } catch (error) {
-    console.error(`Failed to get printer ${name}:`, error);
-    return null;
+export async function unregisterTemplate(name: string): Promise<boolean> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.unregisterTemplate) {
+    return nativeModule.unregisterTemplate(name);
   }
+  throw new Error("Template functionality not available");
 }
 
 /**
- * Check if a printer exists.
- * @param name - Printer name to check
- * @returns Promise resolving to true if printer exists, false otherwise
+ * Get all registered templates, sorted by name.
+ * @returns Promise resolving to the registered templates
  */
-export async function printerExists(name: string): Promise<boolean> {
-  try {
-    const nativeModule = await getNativeModule();
-    return nativeModule.printerExists
-      ? nativeModule.printerExists(name)
-      : false;
-  } catch (error) {
-    console.error(`Failed to check if printer ${name} exists:`, error);
-    return false;
+export async function getTemplates(): Promise<PrintTemplate[]> {
+  const nativeModule = await getNativeModule();
+  return nativeModule.getTemplates ? nativeModule.getTemplates() : [];
+}
+
+/**
+ * Render a template without printing it.
+ * @param name - Template name
+ * @param variables - Values for every variable the template uses
+ * @returns Promise resolving to the rendered document
+ * @throws Error if the template doesn't exist, a variable is missing or a
+ * value isn't allowed in the template's format
+ */
+export async function renderTemplate(
+  name: string,
+  variables: Record<string, string | number>
+): Promise<string> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.renderTemplate) {
+    return nativeModule.renderTemplate(
+      name,
+      templateVariablesToNative(variables)
+    );
   }
+  throw new Error("Template functionality not available");
 }
 
 /**
- * Clean up resources and shutdown the printer module.
- * @returns Promise that resolves when shutdown is complete
+ * Render a template and print the result. ZPL and ESC/POS templates are
+ * sent raw; the job is named after the template unless options set a name.
+ * @param printer - Printer or printer name
+ * @param name - Template name
+ * @param variables - Values for every variable the template uses
+ * @param options - Print job options
+ * @returns Promise resolving to the job ID
+ * @throws Error if the template doesn't exist, a variable is missing or a
+ * value isn't allowed in the template's format
  */
-export async function shutdown(): Promise<void> {
-  try {
-    // Only shutdown if the module was loaded
-    if (nativeModuleCache) {
-      nativeModuleCache.shutdown();
+export async function printTemplate(
+  printer: string | Printer,
+  name: string,
+  variables: Record<string, string | number>,
+  options?: PrintJobOptions | Record<string, string>
+): Promise<number> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.printTemplate) {
+    const { rawOptions, waitForCompletion } = convertPrintOptions(options);
+    try {
+      return await nativeModule.printTemplate(
+        typeof printer === "string" ? printer : printer.name,
+        name,
+        templateVariablesToNative(variables),
+        rawOptions,
+        waitForCompletion
+      );
+    } catch (error) {
+      throw toPrintError(error);
     }
-  } catch (error) {
-    console.error("Failed to shutdown:", error);
   }
+  throw new Error("Template functionality not available");
 }
 
+// ===== LIBRARY CONFIGURATION =====
+
 /**
- * Static methods for creating Printer instances.
+ * Convert LibraryConfig to the native representation
  */
-export const PrinterConstructor: PrinterClass = {
-  fromName: (name: string) => getPrinterByName(name),
-  // @ts-expect-error: Prevent direct construction
-  new: () => {
-    throw new Error("Use PrinterConstructor.fromName() instead");
-  },
-};
-
-// Legacy exports for backward compatibility
+function libraryConfigToNative(config: LibraryConfig): NativeLibraryConfig {
+  const { defaultJobOptions, ...rest } = config;
+  return defaultJobOptions === undefined
+    ? rest
+    : { ...rest, defaultJobOptions: printJobOptionsToRaw(defaultJobOptions) };
+}
 
-/** @deprecated Use getPrinterByName instead */
-export const findPrinter = getPrinterByName;
+/**
+ * Reset the library configuration to defaults, then apply the given settings.
+ * @param config - Settings to apply
+ */
+export async function initialize(config: LibraryConfig = {}): Promise<void> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.initialize) {
+    nativeModule.initialize(libraryConfigToNative(config));
+    return;
+  }
+  throw new Error("Configuration functionality not available");
+}
 
 /**
- * Get the default printer.
- * @returns Promise resolving to default printer if found, null otherwise
+ * Update individual settings; omitted settings keep their current values.
+ * @param config - Settings to change
  */
-export const getDefaultPrinter = async (): Promise<Printer | null> => {
-  const printers = await getAllPrinters();
-  return printers.find(p => p.isDefault) || null;
-};
+export async function configure(config: LibraryConfig): Promise<void> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.configure) {
+    nativeModule.configure(libraryConfigToNative(config));
+    return;
+  }
+  throw new Error("Configuration functionality not available");
+}
 
 /**
- * Print a file to a printer.
- * @param printerName - Name of the printer
- * @param filePath - Path to file to print
- * @param options - Typed print options or raw properties
- * @returns Promise<number> - Job ID
- * @throws Error if printer not found
+ * Get the effective library configuration.
+ * @returns Promise resolving to the current settings
  */
-export const printFile = async (
-  printerName: string,
-  filePath: string,
-  options?: PrintJobOptions | Record<string, string>
-): Promise<number> => {
-  const printer = await getPrinterByName(printerName);
-  if (!printer) {
-    throw new Error(`Printer not found: ${printerName}`);
+export async function getConfig(): Promise<EffectiveLibraryConfig> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.getConfig) {
+    return nativeModule.getConfig();
   }
-  return await printer.printFile(filePath, options);
-};
+  throw new Error("Configuration functionality not available");
+}
 
 /**
- * Print raw bytes to a printer.
- * @param printerName - Name of the printer
- * @param data - Byte data to print
- * @param options - Typed print options or raw properties
- * @returns Promise<number> - Job ID
- * @throws Error if printer not found
+ * Check an audit log for tampering. Each record holds a hash of itself and
+ * of the record before it, so an edited, inserted, removed or reordered
+ * record is reported as the first invalid one.
+ * @param path - Audit log to check (default: the configured auditLog)
+ * @returns Promise resolving to the verification result
  */
-export const printBytes = async (
-  printerName: string,
-  data: Uint8Array | Buffer,
-  options?: PrintJobOptions | Record<string, string>
-): Promise<number> => {
-  const printer = await getPrinterByName(printerName);
-  if (!printer) {
-    throw new Error(`Printer not found: ${printerName}`);
+export async function verifyAuditLog(
+  path?: string
+): Promise<AuditVerification> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.verifyAuditLog) {
+    return nativeModule.verifyAuditLog(path);
   }
-  return await printer.printBytes(data, options);
-};
+  throw new Error("Audit log functionality not available");
+}
 
 // ===== PRINTER STATE MONITORING FUNCTIONS =====
 
@@ -1340,7 +5421,8 @@ let monitoringInterval: any = null;
 let previousStates = new Map<string, PrinterStateSnapshot>();
 
 /**
- * Start printer state monitoring.
+ * Start printer state monitoring. Worker threads share one native monitor,
+ * which runs until every thread that started it has stopped it.
  * @param config - Optional configuration for monitoring
  * @returns Promise that resolves when monitoring starts
  */
@@ -1362,7 +5444,9 @@ export async function startPrinterStateMonitoring(
 
     // Start JavaScript-side polling for event emission
     if (!monitoringInterval) {
-      const pollInterval = (config.pollInterval || 2) * 1000; // Convert to milliseconds
+      const pollInterval = config.pollInterval
+        ? config.pollInterval * 1000
+        : nativeModule.getConfig?.().monitoringIntervalMs || 2000;
       monitoringInterval = setInterval(() => {
         pollPrinterStates();
       }, pollInterval);
@@ -1426,8 +5510,9 @@ export async function subscribeToPrinterStateChanges(
   const id = nextSubscriptionId++;
   stateSubscriptions.set(id, callback);
 
-  // Auto-start monitoring if not already active
-  if (!isPrinterStateMonitoringActive()) {
+  // Auto-start monitoring if this thread isn't polling yet; the native
+  // monitor is shared with other threads, which may have started it
+  if (monitoringInterval === null) {
     await startPrinterStateMonitoring();
   }
 
@@ -1621,3 +5706,485 @@ function emitStateChangeEvent(event: PrinterStateChangeEvent): void {
     }
   }
 }
+
+// ===== SNMP =====
+
+/**
+ * Query a networked printer over SNMP for device status, page counters,
+ * alerts and supplies from the standard Printer MIB.
+ * @param host - Printer hostname or IP address
+ * @param options - Community, port, version and timeout settings
+ * @returns Promise resolving to the printer's SNMP status
+ */
+export async function queryPrinterSnmp(
+  host: string,
+  options?: SnmpQueryOptions
+): Promise<SnmpPrinterStatus> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.queryPrinterSnmp) {
+    return await nativeModule.queryPrinterSnmp(host, options);
+  }
+  throw new Error("SNMP functionality not available");
+}
+
+// ===== GHOSTSCRIPT =====
+
+const CONVERSION_PROGRESS_DRAIN_INTERVAL_MS = 100;
+
+const conversionProgressListeners = new Map<
+  number,
+  (progress: ConversionProgress) => void
+>();
+let conversionProgressDrainInterval: any = null;
+let nextConversionId = 1;
+
+/**
+ * Convert a PDF or PostScript document to a printer format with Ghostscript,
+ * for printers that only take their own format and systems without spooler
+ * filters, like minimal Linux containers. Needs the `ghostscript` Cargo
+ * feature and Ghostscript installed (see `ghostscriptPath`).
+ * @param data - PDF or PostScript document
+ * @param options - Output format, resolution, page range and progress callback
+ * @returns Promise resolving to the converted document
+ */
+export async function rasterizeDocument(
+  data: Uint8Array | Buffer,
+  options: RasterizeOptions
+): Promise<Uint8Array> {
+  const nativeModule = await getNativeModule();
+  if (!nativeModule.rasterizeDocument) {
+    throw new Error("Ghostscript functionality not available");
+  }
+
+  const { onProgress, ...nativeOptions } = options;
+  if (!onProgress || !nativeModule.setConversionProgressBuffering) {
+    return await nativeModule.rasterizeDocument(data, nativeOptions);
+  }
+
+  const conversionId = nextConversionId++;
+  conversionProgressListeners.set(conversionId, onProgress);
+  if (!conversionProgressDrainInterval) {
+    nativeModule.setConversionProgressBuffering(true);
+    conversionProgressDrainInterval = setInterval(
+      drainConversionProgressEvents,
+      CONVERSION_PROGRESS_DRAIN_INTERVAL_MS
+    );
+    conversionProgressDrainInterval.unref?.();
+  }
+  try {
+    return await nativeModule.rasterizeDocument(data, {
+      ...nativeOptions,
+      conversionId,
+    });
+  } finally {
+    // Deliver the last pages before the promise settles
+    drainConversionProgressEvents();
+    conversionProgressListeners.delete(conversionId);
+    if (
+      conversionProgressListeners.size === 0 &&
+      conversionProgressDrainInterval
+    ) {
+      clearInterval(conversionProgressDrainInterval);
+      conversionProgressDrainInterval = null;
+      nativeModule.setConversionProgressBuffering(false);
+    }
+  }
+}
+
+/**
+ * Internal function to deliver buffered progress events to conversions
+ */
+function drainConversionProgressEvents(): void {
+  const events = nativeModuleCache?.drainConversionProgressEvents?.() ?? [];
+  for (const { conversionId, ...progress } of events) {
+    try {
+      conversionProgressListeners.get(conversionId)?.(progress);
+    } catch (error) {
+      console.error("Error in conversion progress listener:", error);
+    }
+  }
+}
+
+/**
+ * Get the version of the configured Ghostscript executable, to check it is
+ * installed before converting documents.
+ * @returns Promise resolving to the version, e.g. "10.02.1"
+ * @throws If Ghostscript isn't found at `ghostscriptPath` or on the PATH
+ */
+export async function getGhostscriptVersion(): Promise<string> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.getGhostscriptVersion) {
+    return nativeModule.getGhostscriptVersion();
+  }
+  throw new Error("Ghostscript functionality not available");
+}
+
+/**
+ * Render a page of a document as a PNG image of how it will print, after
+ * the pre-processing of its job options: layouts, scaling, margins,
+ * watermarks, headers and footers, and gray conversion. Images are shown
+ * placed on a page of the job's media. With `printer`, the options are
+ * resolved like those of a job sent to it (see resolveJobOptions). Needs
+ * Ghostscript, like rasterizeDocument.
+ * @param file - Path of the document, or its contents
+ * @param options - Page, resolution, and the job's printer and options
+ * @returns Promise resolving to the PNG image
+ */
+export async function renderPreview(
+  file: string | Uint8Array | Buffer,
+  options?: RenderPreviewOptions
+): Promise<Uint8Array> {
+  const nativeModule = await getNativeModule();
+  const { printer, options: jobOptions, ...preview } = options ?? {};
+  const nativeOptions: NativeRenderPreviewOptions = {
+    ...preview,
+    printerName: typeof printer === "object" ? printer.name : printer,
+    jobProperties: convertPrintOptions(jobOptions).rawOptions,
+  };
+  try {
+    if (typeof file === "string" && nativeModule.renderPreview) {
+      return await nativeModule.renderPreview(file, nativeOptions);
+    }
+    if (typeof file !== "string" && nativeModule.renderPreviewBytes) {
+      return await nativeModule.renderPreviewBytes(file, nativeOptions);
+    }
+  } catch (error) {
+    throw toPrintError(error);
+  }
+  throw new Error("Ghostscript functionality not available");
+}
+
+// ===== PJL =====
+
+/**
+ * Put a PJL job header in front of a document and an end-of-job trailer
+ * after it, for HP-style printers that take the job name, duplexing and
+ * input tray from PJL. Print the result with `printBytes`.
+ * @param data - Document data, without a PJL header
+ * @param options - Job name, duplex mode, tray and printer language
+ * @returns Promise resolving to the wrapped document
+ * @throws If the document already has a PJL header or a setting is invalid
+ */
+export async function wrapPjl(
+  data: Uint8Array | Buffer,
+  options?: PjlJobOptions
+): Promise<Uint8Array> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.wrapPjl) {
+    return nativeModule.wrapPjl(data, options);
+  }
+  throw new Error("PJL functionality not available");
+}
+
+/**
+ * Read a network printer's control panel status and page count with PJL
+ * `INFO` queries over its raw printing channel (TCP 9100).
+ * @param printerName - Printer name
+ * @returns Promise resolving to what the printer reported
+ */
+export async function queryPjlStatus(printerName: string): Promise<PjlStatus> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.queryPjlStatus) {
+    return await nativeModule.queryPjlStatus(printerName);
+  }
+  throw new Error("PJL functionality not available");
+}
+
+// ===== RAW IPP =====
+
+/**
+ * Send an IPP request for any operation straight to a printer or CUPS and
+ * decode the response, to use IPP features this library has no function
+ * for. The charset, language and `printer-uri` operation attributes are
+ * added unless `attributes` sets them. Credentials set with
+ * `setPrinterCredentials` are used if the printer asks for them.
+ * @param printerUri - ipp://, ipps:// or http(s):// URI of the printer
+ * @param operation - Operation name such as "Get-Jobs", or its ID
+ * @param attributes - Request attributes in their text form
+ * @param options - Document data and timeout
+ * @returns Promise resolving to the response, whatever its status
+ */
+export async function sendIppRequest(
+  printerUri: string,
+  operation: string | number,
+  attributes: IppRawAttribute[] = [],
+  options?: IppRequestOptions
+): Promise<IppRawResponse> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.sendIppRequest) {
+    return await nativeModule.sendIppRequest(
+      printerUri,
+      String(operation),
+      attributes,
+      options
+    );
+  }
+  throw new Error("IPP functionality not available");
+}
+
+// ===== IPP SERVER =====
+
+/**
+ * Start an IPP server that accepts Print-Job requests from the network and
+ * prints them on a local printer through the library's job queue, so this
+ * process can act as a print gateway. There is no authentication or TLS;
+ * only listen on trusted networks. Requires the `ipp-server` Cargo feature.
+ * @param options - Target printer and listening address
+ * @returns Promise resolving to the running server
+ */
+export async function startIppServer(
+  options: IppServerOptions
+): Promise<IppServer> {
+  const { port, maxDocumentBytes } = options;
+  if (port !== undefined && (!Number.isInteger(port) || port < 0)) {
+    throw new Error(`Invalid port: ${port}`);
+  }
+  if (
+    maxDocumentBytes !== undefined &&
+    (!Number.isSafeInteger(maxDocumentBytes) || maxDocumentBytes < 1)
+  ) {
+    throw new Error(`Invalid maxDocumentBytes: ${maxDocumentBytes}`);
+  }
+
+  const nativeModule = await getNativeModule();
+  if (nativeModule.startIppServer) {
+    return nativeModule.startIppServer(options);
+  }
+  throw new Error("IPP server functionality not available");
+}
+
+/**
+ * Stop an IPP server. Requests already being handled are completed.
+ * @param server - Server or server ID
+ * @returns Promise resolving to whether the server was running
+ */
+export async function stopIppServer(
+  server: IppServer | number
+): Promise<boolean> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.stopIppServer) {
+    return nativeModule.stopIppServer(
+      typeof server === "number" ? server : server.id
+    );
+  }
+  throw new Error("IPP server functionality not available");
+}
+
+/**
+ * Get the running IPP servers.
+ * @returns Promise resolving to the servers, oldest first
+ */
+export async function getIppServers(): Promise<IppServer[]> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.getIppServers) {
+    return nativeModule.getIppServers();
+  }
+  throw new Error("IPP server functionality not available");
+}
+
+// ===== PRINT SERVER =====
+
+/**
+ * Start an HTTP server exposing printers and jobs as a JSON REST API, so the
+ * library can run as a standalone print service. With `events`, job and
+ * printer state events are also streamed to WebSocket clients of `/events`.
+ * See docs/PrintServer.md for the endpoints. Requires the `print-server`
+ * Cargo feature.
+ * @param options - Listening address and auth token
+ * @returns Promise resolving to the running server
+ */
+export async function startPrintServer(
+  options: PrintServerOptions = {}
+): Promise<PrintServer> {
+  const { port, authToken, maxBodyBytes } = options;
+  if (port !== undefined && (!Number.isInteger(port) || port < 0)) {
+    throw new Error(`Invalid port: ${port}`);
+  }
+  if (authToken !== undefined && authToken.length === 0) {
+    throw new Error("authToken must not be empty");
+  }
+  if (
+    maxBodyBytes !== undefined &&
+    (!Number.isSafeInteger(maxBodyBytes) || maxBodyBytes < 1)
+  ) {
+    throw new Error(`Invalid maxBodyBytes: ${maxBodyBytes}`);
+  }
+
+  const nativeModule = await getNativeModule();
+  if (nativeModule.startPrintServer) {
+    return nativeModule.startPrintServer(options);
+  }
+  throw new Error("Print server functionality not available");
+}
+
+/**
+ * Stop a print server. Requests already being handled are completed.
+ * @param server - Server or server ID
+ * @returns Promise resolving to whether the server was running
+ */
+export async function stopPrintServer(
+  server: PrintServer | number
+): Promise<boolean> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.stopPrintServer) {
+    return nativeModule.stopPrintServer(
+      typeof server === "number" ? server : server.id
+    );
+  }
+  throw new Error("Print server functionality not available");
+}
+
+/**
+ * Get the running print servers.
+ * @returns Promise resolving to the servers, oldest first
+ */
+export async function getPrintServers(): Promise<PrintServer[]> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.getPrintServers) {
+    return nativeModule.getPrintServers();
+  }
+  throw new Error("Print server functionality not available");
+}
+
+// ===== METRICS =====
+
+/**
+ * Get job counters, current queue depth and the spool latency histogram
+ * (time from submission until the spooler accepted or rejected the job).
+ * @returns Promise resolving to the current metrics
+ */
+export async function getMetricsSnapshot(): Promise<MetricsSnapshot> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.getMetricsSnapshot) {
+    return nativeModule.getMetricsSnapshot();
+  }
+  throw new Error("Metrics functionality not available");
+}
+
+/**
+ * Render current metrics in the Prometheus text exposition format, ready to
+ * serve from a /metrics endpoint.
+ * @returns Promise resolving to the exposition text
+ */
+export async function getMetricsPrometheus(): Promise<string> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.getMetricsPrometheus) {
+    return nativeModule.getMetricsPrometheus();
+  }
+  throw new Error("Prometheus exporter not available");
+}
+
+/**
+ * Check library health: spooler reachability, tracked jobs by state, state
+ * monitoring, worker pool saturation and per-printer online status.
+ * `healthy` ignores printer status, since which printers matter is up to
+ * the application. Printers are probed concurrently.
+ * @param options - Printers to probe (default: all)
+ * @returns Promise resolving to the health report
+ */
+export async function healthCheck(
+  options: HealthCheckOptions = {}
+): Promise<HealthReport> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.healthCheck) {
+    const names = options.printers?.map(printer =>
+      typeof printer === "string" ? printer : printer.name
+    );
+    return await nativeModule.healthCheck(names);
+  }
+  throw new Error("Health check functionality not available");
+}
+
+/**
+ * List the native library's background tasks. Long-running tasks like the
+ * state monitor are restarted after a panic, with backoff, up to five
+ * times; a task that gives up is listed as "failed" with its last panic.
+ * @returns Promise resolving to the runtime status
+ */
+export async function getRuntimeStatus(): Promise<RuntimeStatus> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.getRuntimeStatus) {
+    return nativeModule.getRuntimeStatus();
+  }
+  throw new Error("Runtime status functionality not available");
+}
+
+// ===== LOGGING =====
+
+/** How often buffered native log events are delivered to the callback */
+const LOG_DRAIN_INTERVAL_MS = 250;
+
+let logCallback: LogCallback | null = null;
+let logDrainInterval: any = null;
+
+/**
+ * Set the minimum level of native log events. Logging is off until a level
+ * or callback is set; without a callback events are written to stderr.
+ * @param level - "off", "error", "warn", "info", "debug" or "trace"
+ */
+export async function setLogLevel(level: LogLevel): Promise<void> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.setLogLevel) {
+    nativeModule.setLogLevel(level);
+    return;
+  }
+  throw new Error("Logging functionality not available");
+}
+
+/**
+ * Get the current minimum level of native log events.
+ * @returns Promise resolving to the current log level
+ */
+export async function getLogLevel(): Promise<LogLevel> {
+  const nativeModule = await getNativeModule();
+  if (nativeModule.getLogLevel) {
+    return nativeModule.getLogLevel();
+  }
+  throw new Error("Logging functionality not available");
+}
+
+/**
+ * Route native log events to a callback instead of stderr.
+ * @param callback - Function receiving each log record, or null to restore stderr
+ */
+export async function setLogCallback(
+  callback: LogCallback | null
+): Promise<void> {
+  const nativeModule = await getNativeModule();
+  if (!nativeModule.setLogBuffering || !nativeModule.drainLogRecords) {
+    throw new Error("Logging functionality not available");
+  }
+
+  if (callback === null) {
+    if (logDrainInterval) {
+      clearInterval(logDrainInterval);
+      logDrainInterval = null;
+    }
+    drainLogRecords();
+    logCallback = null;
+    nativeModule.setLogBuffering(false);
+    return;
+  }
+
+  logCallback = callback;
+  nativeModule.setLogBuffering(true);
+  if (!logDrainInterval) {
+    logDrainInterval = setInterval(drainLogRecords, LOG_DRAIN_INTERVAL_MS);
+    // Don't keep the process alive just to deliver log events
+    logDrainInterval.unref?.();
+  }
+}
+
+/**
+ * Internal function to deliver buffered native log events to the callback
+ */
+function drainLogRecords(): void {
+  const records = nativeModuleCache?.drainLogRecords?.() ?? [];
+  for (const record of records) {
+    try {
+      logCallback?.(record);
+    } catch (error) {
+      console.error("Error in log callback:", error);
+    }
+  }
+}
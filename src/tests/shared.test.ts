@@ -55,9 +55,20 @@ const {
   getAllPrinterNames,
   getAllPrinters,
   printerExists,
+  refreshPrinters,
+  jobIdTime,
+  getPrinterInfo,
+  printerInfoToJson,
+  printerInfoFromJson,
+  printerJobToJson,
+  getJobProcess,
+  printerJobFromJson,
   getPrinterByName,
   PrinterConstructor,
   shutdown,
+  attach,
+  detach,
+  getAttachment,
   PrintError,
   isSimulationMode,
   runtimeInfo,
@@ -72,6 +83,106 @@ const {
   subscribeToPrinterStateChanges,
   getPrinterStateSnapshots,
   setPrinterStateMonitoringInterval,
+  // Job management
+  cancelAllJobs,
+  clearJobHistory,
+  getJobSpoolFile,
+  getJobLog,
+  reprintJob,
+  migrateJobs,
+  // Error messages
+  setErrorMessageFormatter,
+  ERROR_MESSAGES,
+  approveJob,
+  rejectJob,
+  getHeldJobs,
+  findJobs,
+  queryJobs,
+  exportJobHistory,
+  setWebhook,
+  onJobStalled,
+  printToMany,
+  estimateJob,
+  extractText,
+  setContentPolicy,
+  ContentRejectedError,
+  setPolicyRules,
+  getPolicyRules,
+  PolicyViolationError,
+  resolveJobOptions,
+  setPrinterDefaults,
+  getPrinterDefaults,
+  getPrintersByLocation,
+  getPrintersByTag,
+  tagPrinter,
+  untagPrinter,
+  getPrinterTags,
+  createPrinterPool,
+  definePrinterAlias,
+  getPrinterAliases,
+  removePrinterAlias,
+  setPrinterAliasFile,
+  reloadConfig,
+  defineRemotePrinter,
+  removeRemotePrinter,
+  getRemotePrinters,
+  setPrinterCredentials,
+  setTlsOptions,
+  setRedactionKey,
+  setSpoolEncryptionKey,
+  setPrinterMacAddress,
+  installPrinter,
+  removePrinter,
+  listDrivers,
+  addCupsQueue,
+  deleteCupsQueue,
+  setQueueEnabled,
+  onConfigChanged,
+  // Templates
+  registerTemplate,
+  unregisterTemplate,
+  getTemplates,
+  renderTemplate,
+  printTemplate,
+  // Usage accounting
+  getUsageStats,
+  // Synchronous printing
+  printFileSync,
+  printBytesSync,
+  TimeoutError,
+  InvalidDocumentError,
+  QueueFullError,
+  DuplicateJobError,
+  UnsupportedOptionError,
+  // SNMP
+  queryPrinterSnmp,
+  // IPP server
+  startIppServer,
+  stopIppServer,
+  getIppServers,
+  sendIppRequest,
+  rasterizeDocument,
+  renderPreview,
+  wrapPjl,
+  queryPjlStatus,
+  // Print server
+  startPrintServer,
+  stopPrintServer,
+  getPrintServers,
+  // Configuration
+  initialize,
+  configure,
+  getConfig,
+  verifyAuditLog,
+  // Metrics
+  getMetricsSnapshot,
+  getMetricsPrometheus,
+  healthCheck,
+  getRuntimeStatus,
+  // Logging
+  setLogLevel,
+  getLogLevel,
+  setLogCallback,
 } = printerAPI;
 
 console.log("Debug: Available API functions:", Object.keys(printerAPI));
@@ -88,6 +199,15 @@ const TEST_FILES = {
   DOCX: `${MEDIA_DIR}/sample-document.docx`,
 };
 
+/** The simulated printer, which every simulation test run has */
+async function requireSimulatedPrinter(): Promise<PrinterTypes.Printer> {
+  const printer = await getPrinterByName("Simulated Printer");
+  if (!printer) {
+    throw new Error("Should have a printer named 'Simulated Printer'");
+  }
+  return printer;
+}
+
 // ===== MODULE LOADING TESTS =====
 
 test(`${runtimeName}: should import module without top-level await issues`, async () => {
@@ -299,6 +419,97 @@ test(`${runtimeName}: should return false for non-existent printer in printerExi
   }
 });
 
+test(`${runtimeName}: should refresh the cached printer list`, async () => {
+  const before = await getAllPrinterNames();
+  await refreshPrinters();
+  const after = await getAllPrinterNames();
+  if (isSimulationMode && before.join() !== after.join()) {
+    throw new Error("Refreshing should not change the simulated printers");
+  }
+  if ((await getConfig()).printerCacheTtlMs !== 2000) {
+    throw new Error("printerCacheTtlMs should default to 2000");
+  }
+});
+
+test(`${runtimeName}: should get printer info without a Printer`, async () => {
+  if ((await getPrinterInfo("NonExistentPrinter12345")) !== null) {
+    throw new Error("getPrinterInfo should return null for unknown printers");
+  }
+  if (!isSimulationMode) return;
+
+  const info = await getPrinterInfo("Simulated Printer");
+  if (info?.name !== "Simulated Printer" || !info.isDefault) {
+    throw new Error("getPrinterInfo should describe 'Simulated Printer'");
+  }
+  if ("printFile" in info || typeof info.state !== "string") {
+    throw new Error("getPrinterInfo should return plain printer info");
+  }
+});
+
+test(`${runtimeName}: should serialize printers and jobs to JSON`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+
+  const json = await printerInfoToJson("Simulated Printer");
+  if (json === null) {
+    throw new Error("printerInfoToJson should serialize 'Simulated Printer'");
+  }
+  const info = await printerInfoFromJson(json);
+  const listed = await getPrinterInfo("Simulated Printer");
+  if (info.printerId !== listed?.printerId || info.name !== listed.name) {
+    throw new Error("printerInfoFromJson should read back the printer");
+  }
+  if ((await printerInfoToJson("NonExistentPrinter12345")) !== null) {
+    throw new Error("printerInfoToJson should return null for unknown names");
+  }
+
+  const printer = await requireSimulatedPrinter();
+  const jobId = await printer.printBytes(new TextEncoder().encode("%!PS"), {
+    metadata: { orderId: "A-1" },
+  });
+  const jobJson = await printerJobToJson(jobId);
+  if (jobJson === null) {
+    throw new Error("printerJobToJson should serialize a tracked job");
+  }
+  const parsed = JSON.parse(jobJson);
+  if (typeof parsed.createdAt !== "number" || "sourcePath" in parsed) {
+    throw new Error("Jobs should serialize with the PrinterJob fields");
+  }
+  const job = await printerJobFromJson(jobJson);
+  if (job.id !== jobId || job.metadata?.orderId !== "A-1") {
+    throw new Error("printerJobFromJson should read back the job");
+  }
+
+  let rejected = false;
+  try {
+    await printerJobFromJson('{"id":1}');
+  } catch {
+    rejected = true;
+  }
+  if (!rejected) {
+    throw new Error("printerJobFromJson should reject incomplete jobs");
+  }
+});
+
+test(`${runtimeName}: should refresh a printer's information`, async () => {
+  if (!isSimulationMode) return;
+
+  const printer = await getPrinterByName("Simulated Printer");
+  const listed = (await getAllPrinters()).find(
+    p => p.name === "Simulated Printer"
+  );
+  if (!printer || !listed) {
+    throw new Error("Should have a printer named 'Simulated Printer'");
+  }
+  if (!(await printer.refresh()) || !(await listed.refresh())) {
+    throw new Error("refresh() should report the printer still exists");
+  }
+  if (listed.name !== "Simulated Printer" || !listed.isDefault) {
+    throw new Error("refresh() should keep the printer's information");
+  }
+});
+
 test(`${runtimeName}: should return null for non-existent printer in getPrinterByName`, async () => {
   const printer = await getPrinterByName("NonExistentPrinter12345");
   if (printer !== null) {
@@ -333,6 +544,26 @@ test(`${runtimeName}: should have working Printer class methods`, async () => {
   }
 });
 
+test(`${runtimeName}: should identify printers across renames`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+  const printer = await getPrinterByName("Simulated Printer");
+  const info = await getPrinterInfo("Simulated Printer");
+  if (!printer || !info || !/^[0-9a-f]{16}$/.test(info.printerId)) {
+    throw new Error(`Expected a printerId, got ${info?.printerId}`);
+  }
+
+  // Serialized and renamed copies are still the same device
+  const copy = JSON.parse(JSON.stringify(info));
+  if (!printer.equals({ ...copy, name: "Front Desk" })) {
+    throw new Error("Printers with the same printerId should be equal");
+  }
+  if (printer.equals({ ...copy, printerId: "0000000000000000" })) {
+    throw new Error("Printers with different printerIds should differ");
+  }
+});
+
 test(`${runtimeName}: should handle printFile operations`, async () => {
   const printers = await getAllPrinters();
   if (printers.length === 0) {
@@ -378,6 +609,21 @@ test(`${runtimeName}: should have shutdown function available`, () => {
   // Note: We don't actually call shutdown in tests as it would terminate the library
 });
 
+test(`${runtimeName}: should reject unknown shutdown modes`, async () => {
+  let rejected = false;
+  try {
+    await shutdown({ mode: "later" as unknown as PrinterTypes.ShutdownMode });
+  } catch {
+    rejected = true;
+  }
+  if (!rejected) {
+    throw new Error("Unknown shutdown mode should be rejected");
+  }
+  if (PrintError.ShuttingDown !== 9) {
+    throw new Error("PrintError.ShuttingDown should be 9");
+  }
+});
+
 test(`${runtimeName}: should have PrintError enum available`, () => {
   if (typeof PrintError !== "object") {
     throw new Error("PrintError should be an enum object");
@@ -452,177 +698,2744 @@ test(`${runtimeName}: should have runtimeInfo with name and version`, () => {
     throw new Error("Runtime version should be available");
   }
 
-  const expectedRuntimes = ["Deno", "Bun", "Node.js", "deno", "bun", "node"];
-  if (!expectedRuntimes.includes(runtimeInfo.name)) {
-    throw new Error(
-      `Runtime should be one of ${expectedRuntimes.join(
-        ", "
-      )}, got ${runtimeInfo.name}`
-    );
+  const expectedRuntimes = ["Deno", "Bun", "Node.js", "deno", "bun", "node"];
+  if (!expectedRuntimes.includes(runtimeInfo.name)) {
+    throw new Error(
+      `Runtime should be one of ${expectedRuntimes.join(
+        ", "
+      )}, got ${runtimeInfo.name}`
+    );
+  }
+});
+
+test(`${runtimeName}: simulated printer should have correct field values`, async () => {
+  // This test only runs in simulation mode
+  if (!isSimulationMode) {
+    console.log("Skipping simulated printer test - not in simulation mode");
+    return;
+  }
+
+  const printers = await getAllPrinters();
+  if (printers.length === 0) {
+    throw new Error("Should have at least one simulated printer");
+  }
+
+  const simulatedPrinter = printers.find(p => p.name === "Simulated Printer");
+  if (!simulatedPrinter) {
+    throw new Error("Should have a printer named 'Simulated Printer'");
+  }
+
+  // Test expected field values for simulated printer
+  const validSimulatedStates = ["idle", "printing", "paused"];
+  if (!validSimulatedStates.includes(simulatedPrinter.state || "")) {
+    throw new Error(
+      `Simulated printer state should be one of ${validSimulatedStates.join(", ")}, got '${simulatedPrinter.state}'`
+    );
+  }
+
+  if (simulatedPrinter.isDefault !== true) {
+    throw new Error("Simulated printer should be marked as default");
+  }
+
+  if (simulatedPrinter.isShared !== false) {
+    throw new Error("Simulated printer should not be shared");
+  }
+
+  // Check that other fields exist and have reasonable values
+  if (
+    typeof simulatedPrinter.systemName !== "string" ||
+    !simulatedPrinter.systemName
+  ) {
+    throw new Error("Simulated printer should have a system name");
+  }
+
+  if (
+    typeof simulatedPrinter.driverName !== "string" ||
+    !simulatedPrinter.driverName
+  ) {
+    throw new Error("Simulated printer should have a driver name");
+  }
+
+  if (!Array.isArray(simulatedPrinter.normalizedStateReasons)) {
+    throw new Error(
+      "Simulated printer should have normalizedStateReasons array"
+    );
+  }
+
+  console.log("Simulated printer fields validated successfully:");
+  console.log(`  - name: ${simulatedPrinter.name}`);
+  console.log(`  - state: ${simulatedPrinter.state}`);
+  console.log(`  - isDefault: ${simulatedPrinter.isDefault}`);
+  console.log(`  - isShared: ${simulatedPrinter.isShared}`);
+  console.log(`  - systemName: ${simulatedPrinter.systemName}`);
+  console.log(`  - driverName: ${simulatedPrinter.driverName}`);
+});
+
+test(`${runtimeName}: simulated printer should report online`, async () => {
+  if (!isSimulationMode) {
+    console.log("Skipping online check test - not in simulation mode");
+    return;
+  }
+
+  const printer = await requireSimulatedPrinter();
+
+  if ((await printer.isOnline()) !== true) {
+    throw new Error("Simulated printer should be online");
+  }
+
+  if (printer.offlineSince !== undefined && printer.offlineSince !== null) {
+    throw new Error("Simulated printer should not have offlineSince set");
+  }
+});
+
+test(`${runtimeName}: simulated printer should report supply levels`, async () => {
+  if (!isSimulationMode) {
+    console.log("Skipping supplies test - not in simulation mode");
+    return;
+  }
+
+  const printer = await requireSimulatedPrinter();
+
+  const supplies = await printer.getSupplies();
+  if (!Array.isArray(supplies) || supplies.length === 0) {
+    throw new Error("Simulated printer should report at least one supply");
+  }
+
+  const toner = supplies[0];
+  if (typeof toner.name !== "string" || typeof toner.type !== "string") {
+    throw new Error("Supply should have name and type strings");
+  }
+  if (
+    typeof toner.level !== "number" ||
+    toner.level < 0 ||
+    toner.level > 100
+  ) {
+    throw new Error(`Supply level should be a percentage, got ${toner.level}`);
+  }
+  if (typeof toner.isLow !== "boolean") {
+    throw new Error("Supply should have an isLow boolean");
+  }
+});
+
+test(`${runtimeName}: should fetch printer capabilities on demand`, async () => {
+  if (!isSimulationMode) return;
+
+  const printers = await getAllPrinters();
+  const printer = printers.find(p => p.name === "Simulated Printer");
+  if (!printer) {
+    throw new Error("getAllPrinters should include 'Simulated Printer'");
+  }
+  const capabilities = await printer.getCapabilities();
+  if (!capabilities?.supported["sides"]?.includes("two-sided-long-edge")) {
+    throw new Error("Simulated printer should support two-sided printing");
+  }
+  if (capabilities.copiesMin !== 1 || capabilities.copiesMax !== 999) {
+    throw new Error("Simulated printer should support 1-999 copies");
+  }
+  if ((await printer.getCapabilities()) !== capabilities) {
+    throw new Error("Capabilities should be fetched once per printer");
+  }
+});
+
+test(`${runtimeName}: should detect printer languages`, async () => {
+  if (!isSimulationMode) return;
+
+  const printer = await requireSimulatedPrinter();
+  const formats = await printer.getSupportedFormats();
+  if (
+    formats.preferred !== "pdf" ||
+    formats.preferredMediaType !== "application/pdf" ||
+    !formats.languages.includes("postscript") ||
+    formats.source !== "simulated"
+  ) {
+    throw new Error("Simulated printer should prefer PDF over PostScript");
+  }
+});
+
+test(`${runtimeName}: should reject unsupported SNMP versions`, async () => {
+  if (typeof queryPrinterSnmp !== "function") {
+    throw new Error("queryPrinterSnmp should be exported as a function");
+  }
+
+  // SNMPv3 isn't supported; bypass the type check to exercise validation
+  const options = {
+    version: "3",
+    timeoutMs: 100,
+  } as unknown as PrinterTypes.SnmpQueryOptions;
+
+  let rejected = false;
+  try {
+    await queryPrinterSnmp("127.0.0.1", options);
+  } catch {
+    rejected = true;
+  }
+
+  if (!rejected) {
+    throw new Error("queryPrinterSnmp should reject SNMP version 3");
+  }
+});
+
+test(`${runtimeName}: should start and stop an IPP server`, async () => {
+  if (typeof startIppServer !== "function") {
+    throw new Error("startIppServer should be exported as a function");
+  }
+
+  let server: PrinterTypes.IppServer;
+  try {
+    server = await startIppServer({
+      printerName: "Simulated Printer",
+      port: 0,
+    });
+  } catch (error) {
+    // The native module is built without the ipp-server feature by default
+    if (String(error).includes("not available")) return;
+    throw error;
+  }
+
+  try {
+    if (!server.uri.startsWith("ipp://127.0.0.1:") || server.port === 0) {
+      throw new Error(`Unexpected IPP server URI: ${server.uri}`);
+    }
+    const servers = await getIppServers();
+    if (!servers.some(s => s.id === server.id)) {
+      throw new Error("getIppServers should list the running server");
+    }
+  } finally {
+    if (!(await stopIppServer(server))) {
+      throw new Error("stopIppServer should stop a running server");
+    }
+  }
+  if (await stopIppServer(server.id)) {
+    throw new Error("stopIppServer should return false once stopped");
+  }
+});
+
+test(`${runtimeName}: should only rasterize PDF and PostScript`, async () => {
+  let message = "";
+  try {
+    await rasterizeDocument(new TextEncoder().encode("plain text"), {
+      format: "pcl",
+    });
+  } catch (error) {
+    message = String(error);
+  }
+  // The native module is built without the ghostscript feature by default
+  if (message.includes("not available")) return;
+  if (!message.includes("only converts PDF and PostScript")) {
+    throw new Error(`rasterizeDocument should reject plain text: ${message}`);
+  }
+});
+
+test(`${runtimeName}: should only render previews of documents and images`, async () => {
+  let message = "";
+  try {
+    await renderPreview(new TextEncoder().encode("plain text"), {
+      page: 1,
+      dpi: 72,
+    });
+  } catch (error) {
+    message = String(error);
+  }
+  // The native module is built without the ghostscript feature by default
+  if (message.includes("not available")) return;
+  if (!message.includes("Previews can only be rendered")) {
+    throw new Error(`renderPreview should reject plain text: ${message}`);
+  }
+});
+
+test(`${runtimeName}: should wrap documents in PJL`, async () => {
+  const wrapped = await wrapPjl(new TextEncoder().encode("%PDF-1.7\n"), {
+    name: "Invoice 42",
+    duplex: "long-edge",
+    tray: "tray2",
+  });
+  const text = new TextDecoder().decode(wrapped);
+  if (
+    !text.startsWith("\x1b%-12345X@PJL JOB NAME=\"Invoice 42\"") ||
+    !text.includes("@PJL SET MEDIASOURCE=TRAY2") ||
+    !text.includes("@PJL ENTER LANGUAGE=PDF\r\n%PDF-1.7")
+  ) {
+    throw new Error("wrapPjl should add a PJL job header");
+  }
+
+  let rejected = false;
+  try {
+    await wrapPjl(wrapped);
+  } catch {
+    rejected = true;
+  }
+  if (!rejected) {
+    throw new Error("wrapPjl should reject documents that have PJL already");
+  }
+
+  if (!isSimulationMode) {
+    return;
+  }
+  const status = await queryPjlStatus("Simulated Printer");
+  if (status.code !== 10001 || status.severity !== "info") {
+    throw new Error("The simulated printer should report ready over PJL");
+  }
+});
+
+test(`${runtimeName}: should send raw IPP requests`, async () => {
+  let rejected = false;
+  try {
+    await sendIppRequest("ipp://127.0.0.1:1/ipp/print", "Get-Everything");
+  } catch {
+    rejected = true;
+  }
+  if (!rejected) {
+    throw new Error("sendIppRequest should reject unknown operations");
+  }
+
+  let server: PrinterTypes.IppServer;
+  try {
+    server = await startIppServer({
+      printerName: "Simulated Printer",
+      port: 0,
+    });
+  } catch (error) {
+    // The native module is built without the ipp-server feature by default
+    if (String(error).includes("not available")) return;
+    throw error;
+  }
+
+  try {
+    const response = await sendIppRequest(
+      server.uri,
+      "Get-Printer-Attributes",
+      [
+        {
+          name: "requested-attributes",
+          syntax: "keyword",
+          values: ["printer-state", "printer-is-accepting-jobs"],
+        },
+      ]
+    );
+    const printer = response.groups.find(group => group.group === "printer");
+    const state = printer?.attributes.find(a => a.name === "printer-state");
+    if (!response.ok || state?.syntax !== "enum") {
+      throw new Error("sendIppRequest should decode the printer attributes");
+    }
+  } finally {
+    await stopIppServer(server);
+  }
+});
+
+test(`${runtimeName}: should serve printers over the REST API`, async () => {
+  if (typeof startPrintServer !== "function") {
+    throw new Error("startPrintServer should be exported as a function");
+  }
+
+  let server: PrinterTypes.PrintServer;
+  try {
+    server = await startPrintServer({
+      port: 0,
+      authToken: "test-token",
+      events: true,
+    });
+  } catch (error) {
+    // The native module is built without the print-server feature by default
+    if (String(error).includes("not available")) return;
+    throw error;
+  }
+
+  try {
+    const unauthorized = await fetch(`${server.url}/printers`);
+    await unauthorized.body?.cancel();
+    if (unauthorized.status !== 401) {
+      throw new Error(`Expected 401, got ${unauthorized.status}`);
+    }
+
+    const response = await fetch(`${server.url}/printers`, {
+      headers: { Authorization: "Bearer test-token" },
+    });
+    const printers = (await response.json()) as { name: string }[];
+    if (!printers.some(printer => printer.name === "Simulated Printer")) {
+      throw new Error("GET /printers should list the simulated printer");
+    }
+    // The event stream only speaks WebSocket
+    const events = await fetch(`${server.url}/events?token=test-token`);
+    await events.body?.cancel();
+    if (events.status !== 426) {
+      throw new Error(`Expected 426 from /events, got ${events.status}`);
+    }
+    if (!(await getPrintServers()).some(s => s.id === server.id)) {
+      throw new Error("getPrintServers should list the running server");
+    }
+  } finally {
+    await stopPrintServer(server);
+  }
+});
+
+test(`${runtimeName}: should aggregate usage stats`, async () => {
+  if (typeof getUsageStats !== "function") {
+    throw new Error("getUsageStats should be exported as a function");
+  }
+
+  const stats = await getUsageStats();
+  for (const key of [
+    "jobs",
+    "pages",
+    "sheets",
+    "bytes",
+    "jobsWithoutPageCount",
+  ] as const) {
+    if (typeof stats[key] !== "number" || stats[key] < 0) {
+      throw new Error(`Usage stat ${key} should be a non-negative number`);
+    }
+  }
+
+  // A start time in the future excludes every job
+  const future = await getUsageStats({
+    printerName: "Simulated Printer",
+    since: new Date(Date.now() + 60_000),
+  });
+  if (future.jobs !== 0 || future.pages !== 0) {
+    throw new Error("Usage stats since a future time should be empty");
+  }
+});
+
+// CUPS Options Conversion Tests
+test(`${runtimeName}: should convert SimplePrintOptions to CUPS correctly`, () => {
+  if (typeof simpleToCUPS !== "function") {
+    throw new Error("simpleToCUPS function should be available");
+  }
+
+  const simpleOptions = {
+    copies: 3,
+    duplex: true,
+    paperSize: "A4" as const,
+    quality: "high" as const,
+    color: false,
+    pageRange: "1-5,8",
+    jobName: "Test Job",
+    pagesPerSheet: 2 as const,
+    landscape: true,
+  };
+
+  const result = simpleToCUPS(simpleOptions);
+
+  // Verify all conversions
+  if (result.copies !== "3") throw new Error("copies conversion failed");
+  if (result.sides !== "two-sided-long-edge")
+    throw new Error("duplex conversion failed");
+  if (result["media-size"] !== "A4")
+    throw new Error("paperSize conversion failed");
+  if (result["print-quality"] !== "5")
+    throw new Error("quality conversion failed");
+  if (result["print-color-mode"] !== "monochrome")
+    throw new Error("color conversion failed");
+  if (result["page-ranges"] !== "1-5,8")
+    throw new Error("pageRange conversion failed");
+  if (result["job-name"] !== "Test Job")
+    throw new Error("jobName conversion failed");
+  if (result["number-up"] !== "2")
+    throw new Error("pagesPerSheet conversion failed");
+  if (result.landscape !== "true")
+    throw new Error("landscape conversion failed");
+});
+
+test(`${runtimeName}: should convert CUPSOptions to raw properties correctly`, () => {
+  if (typeof cupsToRaw !== "function") {
+    throw new Error("cupsToRaw function should be available");
+  }
+
+  const cupsOptions = {
+    "job-name": "CUPS Test",
+    "job-priority": 50,
+    copies: 2,
+    collate: true,
+    "media-size": "Letter",
+    "fit-to-page": false,
+    "custom-option": "test-value",
+  };
+
+  const result = cupsToRaw(cupsOptions);
+
+  // Verify type conversions
+  if (result["job-name"] !== "CUPS Test")
+    throw new Error("string conversion failed");
+  if (result["job-priority"] !== "50")
+    throw new Error("number conversion failed");
+  if (result.copies !== "2") throw new Error("number to string failed");
+  if (result.collate !== "true")
+    throw new Error("boolean true conversion failed");
+  if (result["fit-to-page"] !== "false")
+    throw new Error("boolean false conversion failed");
+  if (result["custom-option"] !== "test-value")
+    throw new Error("custom option failed");
+});
+
+test(`${runtimeName}: should convert PrintJobOptions with precedence correctly`, () => {
+  if (typeof printJobOptionsToRaw !== "function") {
+    throw new Error("printJobOptionsToRaw function should be available");
+  }
+
+  const options = {
+    jobName: "Top Level Job",
+    raw: {
+      "custom-raw": "raw-value",
+      copies: "1", // This should be overridden
+    },
+    simple: {
+      copies: 2, // This should override raw
+      quality: "normal" as const,
+    },
+    cups: {
+      "job-priority": 75,
+      "job-name": "CUPS Job Name", // This should override others
+    },
+  };
+
+  const result = printJobOptionsToRaw(options);
+
+  // Test precedence: raw < simple < cups < jobName
+  if (result["custom-raw"] !== "raw-value")
+    throw new Error("raw option not preserved");
+  if (result.copies !== "2")
+    throw new Error("simple should override raw copies");
+  if (result["print-quality"] !== "4")
+    throw new Error("simple quality conversion failed");
+  if (result["job-priority"] !== "75") throw new Error("cups option not added");
+  if (result["job-name"] !== "Top Level Job")
+    throw new Error("top-level jobName should override all");
+});
+
+test(`${runtimeName}: should attribute jobs to requestingUser`, async () => {
+  const raw = printJobOptionsToRaw({ requestingUser: "alice" });
+  if (raw["requesting-user-name"] !== "alice") {
+    throw new Error("requestingUser should map to requesting-user-name");
+  }
+
+  if (!isSimulationMode) {
+    console.log("Skipping job owner test - not in simulation mode");
+    return;
+  }
+
+  const printer = await requireSimulatedPrinter();
+
+  const jobId = await printer.printFile(TEST_FILES.PDF, {
+    jobName: "Owner Test",
+    requestingUser: "alice",
+    waitForCompletion: false,
+  });
+
+  const job = await printer.getJob(jobId);
+  if (!job || job.owner !== "alice") {
+    throw new Error(`Job owner should be "alice", got "${job?.owner}"`);
+  }
+});
+
+test(`${runtimeName}: should cancel all jobs and clear history`, async () => {
+  if (!isSimulationMode) {
+    console.log("Skipping cancel all jobs test - not in simulation mode");
+    return;
+  }
+
+  const printer = await requireSimulatedPrinter();
+  const jobId = await printer.printFile(TEST_FILES.PDF, {
+    jobName: "Cancel All Test",
+    waitForCompletion: false,
+  });
+
+  const cancelled = await cancelAllJobs("Simulated Printer");
+  if (cancelled < 1) {
+    throw new Error(`Should cancel at least one job, cancelled ${cancelled}`);
+  }
+  const job = await printer.getJob(jobId);
+  if (job?.state !== "cancelled") {
+    throw new Error(`Job should be cancelled, got "${job?.state}"`);
+  }
+
+  const removed = await clearJobHistory("Simulated Printer");
+  if (removed < 1) {
+    throw new Error(`Should remove at least one job, removed ${removed}`);
+  }
+  if ((await printer.getJob(jobId)) !== null) {
+    throw new Error("Cleared job should no longer be tracked");
+  }
+});
+
+test(`${runtimeName}: should apply library configuration`, async () => {
+  let rejected = false;
+  try {
+    await configure({ tempDir: "/nonexistent/printers-js" });
+  } catch {
+    rejected = true;
+  }
+  if (!rejected) {
+    throw new Error("Missing temp directory should be rejected");
+  }
+
+  try {
+    await configure({
+      simulationDelayMs: 200,
+      defaultJobOptions: { jobName: "Configured Default" },
+    });
+    const config = await getConfig();
+    if (config.simulationDelayMs !== 200) {
+      throw new Error(
+        `simulationDelayMs should be 200, got ${config.simulationDelayMs}`
+      );
+    }
+    if (config.defaultJobOptions["job-name"] !== "Configured Default") {
+      throw new Error("Default job options should be stored as raw properties");
+    }
+
+    if (isSimulationMode) {
+      const printer = await requireSimulatedPrinter();
+      const jobId = await printer.printFile(TEST_FILES.PDF);
+      const job = await printer.getJob(jobId);
+      if (job?.name !== "Configured Default") {
+        throw new Error(`Job should use the default name, got "${job?.name}"`);
+      }
+    }
+  } finally {
+    await initialize();
+  }
+
+  const reset = await getConfig();
+  if (reset.simulationDelayMs !== 2000 || reset.workerPoolSize !== 0) {
+    throw new Error("initialize() should restore defaults");
+  }
+});
+
+test(`${runtimeName}: should issue job IDs carrying their time`, async () => {
+  if (!isSimulationMode) return;
+
+  const printer = await requireSimulatedPrinter();
+  const before = Math.floor(Date.now() / 1000) * 1000;
+  const options = { waitForCompletion: false };
+  const first = await printer.printBytes(new Uint8Array([1]), options);
+  const second = await printer.printBytes(new Uint8Array([2]), options);
+  if (second <= first || !Number.isSafeInteger(second)) {
+    throw new Error(`Job IDs should increase, got ${first} then ${second}`);
+  }
+  const issued = jobIdTime(first).getTime();
+  if (issued < before || issued > Date.now()) {
+    throw new Error(`Job ID ${first} should decode to the current time`);
+  }
+});
+
+test(`${runtimeName}: should print synchronously`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+  await initialize();
+
+  const jobId = printFileSync("Simulated Printer", TEST_FILES.PDF, {
+    jobName: "Sync File",
+  });
+  const printer = await getPrinterByName("Simulated Printer");
+  const job = await printer?.getJob(jobId);
+  if (job?.state !== "completed") {
+    throw new Error(`Sync print should complete first, got ${job?.state}`);
+  }
+
+  const bytesJobId = printBytesSync(
+    "Simulated Printer",
+    new TextEncoder().encode("sync bytes"),
+    undefined,
+    10000
+  );
+  if (bytesJobId <= 0) {
+    throw new Error("printBytesSync should return a job ID");
+  }
+
+  let timedOut = false;
+  try {
+    printFileSync("Simulated Printer", TEST_FILES.PDF, undefined, 1);
+  } catch (error) {
+    timedOut = String(error).includes("Timed out");
+  }
+  if (!timedOut) {
+    throw new Error("printFileSync should fail when the timeout expires");
+  }
+});
+
+test(`${runtimeName}: should fail jobs that exceed timeoutMs`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+  const printer = await requireSimulatedPrinter();
+
+  let rejection: unknown;
+  try {
+    await printer.printFile(TEST_FILES.PDF, {
+      jobName: "Timeout Test",
+      timeoutMs: 100,
+    });
+  } catch (error) {
+    rejection = error;
+  }
+  if (!(rejection instanceof TimeoutError)) {
+    throw new Error(`Expected a TimeoutError, got ${String(rejection)}`);
+  }
+
+  const [job] = (await printer.getJobHistory()).filter(
+    j => j.name === "Timeout Test"
+  );
+  if (job?.state !== "failed" || !job.errorMessage?.includes("timed out")) {
+    throw new Error(`Timed out job should have failed, got ${job?.state}`);
+  }
+
+  let invalid = false;
+  try {
+    await printer.printFile(TEST_FILES.PDF, { timeoutMs: -1 });
+  } catch {
+    invalid = true;
+  }
+  if (!invalid) {
+    throw new Error("Negative timeoutMs should be rejected");
+  }
+});
+
+test(`${runtimeName}: should validate PDF documents`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+  const printer = await requireSimulatedPrinter();
+
+  let rejection: unknown;
+  try {
+    await printer.printBytes(new TextEncoder().encode("%PDF-1.4\n1 0 obj"), {
+      validateDocument: true,
+    });
+  } catch (error) {
+    rejection = error;
+  }
+  if (!(rejection instanceof InvalidDocumentError)) {
+    throw new Error(
+      `Expected an InvalidDocumentError, got ${String(rejection)}`
+    );
+  }
+
+  const jobId = await printer.printFile(TEST_FILES.PDF, {
+    validateDocument: true,
+    waitForCompletion: false,
+  });
+  const job = await printer.getJob(jobId);
+  if (!job?.totalPages || job.totalPages < 1) {
+    throw new Error(
+      `Valid PDF should report totalPages, got ${job?.totalPages}`
+    );
+  }
+});
+
+test(`${runtimeName}: should stamp watermarks onto PDFs`, async () => {
+  let rejected = false;
+  try {
+    printJobOptionsToRaw({ watermark: { text: "DRAFT", opacity: 2 } });
+  } catch {
+    rejected = true;
+  }
+  if (!rejected) {
+    throw new Error("Watermark opacity above 1 should be rejected");
+  }
+
+  if (!isSimulationMode) {
+    return;
+  }
+  const printer = await requireSimulatedPrinter();
+
+  const pdf = new TextEncoder().encode(
+    "%PDF-1.4\n" +
+      "1 0 obj << /Type /Catalog /Pages 2 0 R >> endobj\n" +
+      "2 0 obj << /Type /Pages /Kids [3 0 R] /Count 1 >> endobj\n" +
+      "3 0 obj << /Type /Page /Parent 2 0 R >> endobj\n" +
+      "trailer << /Root 1 0 R >>\nstartxref\n9\n%%EOF\n"
+  );
+  const jobId = await printer.printBytes(pdf, {
+    watermark: { text: "CONFIDENTIAL", position: "top" },
+    waitForCompletion: false,
+  });
+  const job = await printer.getJob(jobId);
+  if (!job || job.sizeBytes <= pdf.byteLength || job.totalPages !== 1) {
+    throw new Error("Watermarked job should print the stamped document");
+  }
+});
+
+test(`${runtimeName}: should print headers and footers on PDFs`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+  const printer = await requireSimulatedPrinter();
+
+  const pdf = new TextEncoder().encode(
+    "%PDF-1.4\n" +
+      "1 0 obj << /Type /Catalog /Pages 2 0 R >> endobj\n" +
+      "2 0 obj << /Type /Pages /Kids [3 0 R] /Count 1 >> endobj\n" +
+      "3 0 obj << /Type /Page /Parent 2 0 R >> endobj\n" +
+      "trailer << /Root 1 0 R >>\nstartxref\n9\n%%EOF\n"
+  );
+  const jobId = await printer.printBytes(pdf, {
+    header: "Order {{orderId}} — page {{page}}/{{pages}}",
+    footer: "{{jobName}} {{date}}",
+    metadata: { orderId: "A-1042" },
+    waitForCompletion: false,
+  });
+  const job = await printer.getJob(jobId);
+  if (!job || job.sizeBytes <= pdf.byteLength) {
+    throw new Error("Job should print the document with its header");
+  }
+
+  const error = await printer
+    .printBytes(pdf, {
+      header: "Shipment {{shipmentId}}",
+      waitForCompletion: false,
+    })
+    .then(
+      () => undefined,
+      (error: PrinterTypes.CodedError) => error
+    );
+  if (error?.code !== "InvalidParams") {
+    throw new Error("Headers with unknown variables should be rejected");
+  }
+});
+
+test(`${runtimeName}: should fit pages within margins`, async () => {
+  let rejected = false;
+  try {
+    printJobOptionsToRaw({ scale: 90, fitToPage: true });
+  } catch {
+    rejected = true;
+  }
+  if (!rejected) {
+    throw new Error("scale and fitToPage should not be combined");
+  }
+
+  if (!isSimulationMode) {
+    return;
+  }
+  const printer = await requireSimulatedPrinter();
+
+  // The header of a 2 by 1 pixel JPEG image is enough to place it
+  const jpeg = new Uint8Array([
+    0xff, 0xd8, 0xff, 0xe0, 0x00, 0x04, 0x00, 0x00, 0xff, 0xc0, 0x00, 0x0b,
+    0x08, 0x00, 0x01, 0x00, 0x02, 0x03, 0x01, 0x11, 0x00, 0xff, 0xd9,
+  ]);
+  const jobId = await printer.printBytes(jpeg, {
+    fitToPage: true,
+    margins: { top: 9, right: 9, bottom: 9, left: 9 },
+    raw: { media: "oe_4x6-label_4x6in" },
+    waitForCompletion: false,
+  });
+  const job = await printer.getJob(jobId);
+  if (!job || job.mediaType !== "application/pdf") {
+    throw new Error("Images with margins should be sent as PDFs");
+  }
+});
+
+test(`${runtimeName}: should lay out pages as booklets`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+  const printer = await requireSimulatedPrinter();
+
+  const pdf = new TextEncoder().encode(
+    "%PDF-1.4\n" +
+      "1 0 obj << /Type /Catalog /Pages 2 0 R >> endobj\n" +
+      "2 0 obj << /Type /Pages /Kids [3 0 R] /Count 1 >> endobj\n" +
+      "3 0 obj << /Type /Page /Parent 2 0 R >> endobj\n" +
+      "trailer << /Root 1 0 R >>\nstartxref\n9\n%%EOF\n"
+  );
+  const result = await printer.printBytes(pdf, {
+    layout: "booklet",
+    dryRun: true,
+  });
+  if (result.options.sides !== "two-sided-short-edge") {
+    throw new Error("Booklets should be printed on both sides");
+  }
+  if (result.documentPages !== 2 || result.sheets !== 1) {
+    throw new Error("A one-page booklet should take one sheet");
+  }
+
+  const error = await printer
+    .printBytes(pdf, {
+      layout: "booklet",
+      raw: { sides: "one-sided" },
+      dryRun: true,
+    })
+    .then(
+      () => undefined,
+      (error: PrinterTypes.CodedError) => error
+    );
+  if (error?.code !== "InvalidParams") {
+    throw new Error("One-sided booklets should be rejected");
+  }
+});
+
+test(`${runtimeName}: should print in the color mode asked for`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+  const printer = await requireSimulatedPrinter();
+
+  const raw = printJobOptionsToRaw({ colorMode: "grayscale" });
+  if (raw["job-color-mode"] !== "grayscale") {
+    throw new Error("colorMode should be passed to the native module");
+  }
+  try {
+    printJobOptionsToRaw({
+      colorMode: "sepia" as unknown as PrinterTypes.JobColorMode,
+    });
+    throw new Error("Unknown color modes should be rejected");
+  } catch (error) {
+    if (!String(error).includes("Invalid colorMode")) {
+      throw error;
+    }
+  }
+
+  // The simulated printer reports monochrome, so its driver is asked
+  const pdf = new TextEncoder().encode(
+    "%PDF-1.4\n" +
+      "1 0 obj << /Type /Catalog /Pages 2 0 R >> endobj\n" +
+      "2 0 obj << /Type /Pages /Kids [3 0 R] /Count 1 >> endobj\n" +
+      "3 0 obj << /Type /Page /Parent 2 0 R >> endobj\n" +
+      "trailer << /Root 1 0 R >>\nstartxref\n9\n%%EOF\n"
+  );
+  const result = await printer.printBytes(pdf, {
+    colorMode: "grayscale",
+    dryRun: true,
+  });
+  if (result.options["print-color-mode"] !== "monochrome") {
+    throw new Error("Grayscale jobs should ask for print-color-mode");
+  }
+  if (result.convertsToGray) {
+    throw new Error("Printers reporting the mode shouldn't get gray documents");
+  }
+});
+
+test(`${runtimeName}: should check quality and dpi support`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+  const printer = await requireSimulatedPrinter();
+
+  const pdf = new TextEncoder().encode(
+    "%PDF-1.4\n" +
+      "1 0 obj << /Type /Catalog /Pages 2 0 R >> endobj\n" +
+      "2 0 obj << /Type /Pages /Kids [3 0 R] /Count 1 >> endobj\n" +
+      "3 0 obj << /Type /Page /Parent 2 0 R >> endobj\n" +
+      "trailer << /Root 1 0 R >>\nstartxref\n9\n%%EOF\n"
+  );
+  const result = await printer.printBytes(pdf, {
+    quality: "high",
+    dpi: 600,
+    dryRun: true,
+  });
+  if (
+    result.options["print-quality"] !== "5" ||
+    result.options["printer-resolution"] !== "600dpi"
+  ) {
+    throw new Error("quality and dpi should be passed to the driver");
+  }
+
+  // The simulated printer prints at 300 and 600 dpi only
+  const error = await printer.printBytes(pdf, { dpi: 1200 }).then(
+    () => undefined,
+    (error: PrinterTypes.CodedError) => error
+  );
+  if (error?.code !== "UnsupportedOption") {
+    throw new Error("Unsupported resolutions should carry their code");
+  }
+  if (!(error instanceof UnsupportedOptionError)) {
+    throw new Error(`Expected UnsupportedOptionError, got ${error}`);
+  }
+  try {
+    printJobOptionsToRaw({ dpi: -300 });
+    throw new Error("Negative dpi should be rejected");
+  } catch (error) {
+    if (!String(error).includes("Invalid dpi")) {
+      throw error;
+    }
+  }
+});
+
+test(`${runtimeName}: should ask finishers to staple and punch`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+  const printer = await requireSimulatedPrinter();
+  const capabilities = await printer.getCapabilities();
+  if (!capabilities?.finishings.includes("staple-top-left")) {
+    throw new Error("The simulated printer should report its finisher");
+  }
+
+  const pdf = new TextEncoder().encode(
+    "%PDF-1.4\n" +
+      "1 0 obj << /Type /Catalog /Pages 2 0 R >> endobj\n" +
+      "2 0 obj << /Type /Pages /Kids [3 0 R] /Count 1 >> endobj\n" +
+      "3 0 obj << /Type /Page /Parent 2 0 R >> endobj\n" +
+      "trailer << /Root 1 0 R >>\nstartxref\n9\n%%EOF\n"
+  );
+  const result = await printer.printBytes(pdf, {
+    finishing: {
+      staple: "top-left",
+      punch: "dual-left",
+      outputBin: "face-down",
+    },
+    dryRun: true,
+  });
+  if (
+    result.options.finishings !== "20,74" ||
+    result.options["output-bin"] !== "face-down"
+  ) {
+    throw new Error("finishing should become finishings and output-bin");
+  }
+
+  const error = await printer
+    .printBytes(pdf, { finishing: { fold: "z" }, dryRun: true })
+    .then(
+      () => undefined,
+      (error: PrinterTypes.CodedError) => error
+    );
+  if (error?.code !== "UnsupportedOption") {
+    throw new Error("Folds the printer doesn't report should be rejected");
+  }
+});
+
+test(`${runtimeName}: should reject unknown options in strict mode`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+  const printer = await requireSimulatedPrinter();
+
+  const capabilities = await printer.getCapabilities();
+  if (!capabilities?.supportedOptions.includes("copies")) {
+    throw new Error("supportedOptions should list copies");
+  }
+  const error = await printer
+    .printFile(TEST_FILES.PDF, {
+      strictOptions: true,
+      raw: { copeis: "2" },
+      dryRun: true,
+    })
+    .then(
+      () => undefined,
+      (error: PrinterTypes.CodedError) => error
+    );
+  if (
+    error?.code !== "UnsupportedOption" ||
+    !error.message.includes("copeis") ||
+    !error.message.includes("copies")
+  ) {
+    throw new Error("Strict jobs should name unknown and supported options");
+  }
+
+  // Without strict mode unknown options are left to the driver
+  const result = await printer.printFile(TEST_FILES.PDF, {
+    raw: { copeis: "2" },
+    dryRun: true,
+  });
+  if (result.options.copeis !== "2") {
+    throw new Error("Unknown options should be passed through");
+  }
+});
+
+test(`${runtimeName}: should honor documentFormat`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+  const printer = await requireSimulatedPrinter();
+
+  const jobId = await printer.printFile(TEST_FILES.PDF, {
+    documentFormat: "application/vnd.cups-raw",
+    waitForCompletion: false,
+  });
+  const job = await printer.getJob(jobId);
+  if (job?.mediaType !== "application/vnd.cups-raw") {
+    throw new Error(
+      `documentFormat should set mediaType, got ${job?.mediaType}`
+    );
+  }
+
+  let rejected = false;
+  try {
+    await printer.printFile(TEST_FILES.PDF, { documentFormat: "raw" });
+  } catch {
+    rejected = true;
+  }
+  if (!rejected) {
+    throw new Error("documentFormat without a subtype should be rejected");
+  }
+});
+
+test(`${runtimeName}: should report mediaTypeSource`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+  const printer = await requireSimulatedPrinter();
+
+  const byExtension = await printer.getJob(
+    await printer.printFile(TEST_FILES.PDF, { waitForCompletion: false })
+  );
+  if (byExtension?.mediaTypeSource !== "extension") {
+    throw new Error(
+      `Expected "extension" source, got ${byExtension?.mediaTypeSource}`
+    );
+  }
+
+  const byOption = await printer.getJob(
+    await printer.printFile(TEST_FILES.PDF, {
+      documentFormat: "application/pdf",
+      waitForCompletion: false,
+    })
+  );
+  if (byOption?.mediaTypeSource !== "option") {
+    throw new Error(
+      `Expected "option" source, got ${byOption?.mediaTypeSource}`
+    );
+  }
+});
+
+test(`${runtimeName}: should get and set printer preferences`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+  const printer = await requireSimulatedPrinter();
+
+  const defaults = await printer.getPreferences();
+  if (defaults.orientation !== "portrait") {
+    throw new Error(`Expected portrait, got ${defaults.orientation}`);
+  }
+
+  const updated = await printer.setPreferences({
+    orientation: "landscape",
+    duplex: "long-edge",
+  });
+  if (updated.orientation !== "landscape" || updated.duplex !== "long-edge") {
+    throw new Error("setPreferences should apply the given fields");
+  }
+  if (updated.color !== defaults.color) {
+    throw new Error("setPreferences should keep fields that aren't set");
+  }
+
+  let rejected = false;
+  try {
+    await printer.setPreferences({ quality: "draft", resolution: 300 });
+  } catch {
+    rejected = true;
+  }
+  if (!rejected) {
+    throw new Error("quality and resolution together should be rejected");
+  }
+
+  await printer.setPreferences({ orientation: "portrait", duplex: "simplex" });
+});
+
+test(`${runtimeName}: should enforce the spool quota`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+  const printer = await requireSimulatedPrinter();
+
+  try {
+    await configure({ spoolQuotaMb: 1 });
+    if ((await getConfig()).spoolQuotaMb !== 1) {
+      throw new Error("Spool quota should be stored in the config");
+    }
+
+    let rejected = false;
+    try {
+      await printer.printBytes(new Uint8Array(2 * 1024 * 1024), {
+        waitForCompletion: false,
+      });
+    } catch (error) {
+      rejected = String(error).includes("Spool quota exceeded");
+    }
+    if (!rejected) {
+      throw new Error("Payloads larger than the quota should be rejected");
+    }
+  } finally {
+    await initialize();
+  }
+});
+
+test(`${runtimeName}: should keep spool files on request`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+  const printer = await requireSimulatedPrinter();
+
+  const jobId = await printer.printBytes(new TextEncoder().encode("%!PS"), {
+    keepSpoolFile: true,
+    waitForCompletion: false,
+  });
+  if (typeof (await getJobSpoolFile(jobId)) !== "string") {
+    throw new Error("Kept spool files should have a path");
+  }
+
+  const otherId = await printer.printBytes(new TextEncoder().encode("%!PS"), {
+    waitForCompletion: false,
+  });
+  if ((await getJobSpoolFile(otherId)) !== null) {
+    throw new Error("Spool files should not be kept by default");
+  }
+});
+
+test(`${runtimeName}: should keep a log for each job`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+  const printer = await requireSimulatedPrinter();
+
+  const jobId = await printer.printBytes(new TextEncoder().encode("%!PS"));
+  const log = await getJobLog(jobId);
+  const messages = log?.map(entry => entry.message) ?? [];
+  if (
+    !messages[0]?.startsWith("Submitted to 'Simulated Printer'") ||
+    messages[messages.length - 1] !== "Completed"
+  ) {
+    throw new Error(`Job logs should record the job's progress: ${messages}`);
+  }
+  if ((await getJobLog(-1)) !== null) {
+    throw new Error("Unknown jobs should have no log");
+  }
+});
+
+test(`${runtimeName}: should run dependent jobs in order`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+  const printer = await requireSimulatedPrinter();
+
+  const first = await printer.printBytes(new TextEncoder().encode("%!PS"), {
+    waitForCompletion: false,
+  });
+  const second = await printer.printBytes(new TextEncoder().encode("%!PS"), {
+    dependsOn: [first],
+  });
+  const [firstJob, secondJob] = await Promise.all([
+    printer.getJob(first),
+    printer.getJob(second),
+  ]);
+  if (
+    firstJob?.completedAt === undefined ||
+    secondJob?.processedAt === undefined ||
+    secondJob.processedAt < firstJob.completedAt
+  ) {
+    throw new Error("Dependent jobs should start after their dependencies");
+  }
+});
+
+test(`${runtimeName}: should print to many printers`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+
+  const { results, succeeded, failed } = await printToMany(
+    ["Simulated Printer", "Missing Printer"],
+    TEST_FILES.PDF
+  );
+  if (succeeded !== 1 || failed !== 1 || results.length !== 2) {
+    throw new Error("Each destination should be reported separately");
+  }
+  if (results[0].jobId === undefined || !results[1].error) {
+    throw new Error("Missing printers should have an error and no job");
+  }
+});
+
+test(`${runtimeName}: should report library health`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+
+  const report = await healthCheck();
+  if (!report.healthy || !report.spoolerReachable || !report.acceptingJobs) {
+    throw new Error("The library should be healthy in simulation mode");
+  }
+  if (!report.printers.some(p => p.name === "Simulated Printer" && p.online)) {
+    throw new Error("Health reports should include printer status");
+  }
+
+  const missing = await healthCheck({ printers: ["Missing Printer"] });
+  if (missing.printers.length !== 1 || missing.printers[0].online) {
+    throw new Error("Only the requested printers should be probed");
+  }
+});
+
+test(`${runtimeName}: should report supervised background tasks`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+
+  const status = await getRuntimeStatus();
+  if (!Array.isArray(status.tasks) || typeof status.jobThreads !== "number") {
+    throw new Error("Runtime status should list tasks and job threads");
+  }
+  const failed = status.tasks.filter(task => task.state === "failed");
+  if (failed.length > 0) {
+    throw new Error(`Unexpected failed tasks: ${failed[0].lastPanic}`);
+  }
+});
+
+test(`${runtimeName}: should print through printer aliases`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+
+  await definePrinterAlias("RECEIPT", "Simulated Printer", { copies: 2 });
+  try {
+    const aliases = await getPrinterAliases();
+    if (!aliases.some(alias => alias.name === "RECEIPT")) {
+      throw new Error("Defined aliases should be listed");
+    }
+    const printer = await getPrinterByName("RECEIPT");
+    if (!printer) {
+      throw new Error("Aliases should be accepted as printer names");
+    }
+    const jobId = await printer.printFile(TEST_FILES.PDF, {
+      waitForCompletion: false,
+    });
+    const job = await printer.getJob(jobId);
+    if (job?.printerName !== "Simulated Printer") {
+      throw new Error("Alias jobs should go to the concrete printer");
+    }
+  } finally {
+    await removePrinterAlias("RECEIPT");
+  }
+});
+
+test(`${runtimeName}: should resolve job options through every layer`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+
+  await definePrinterAlias("RECEIPT", "Simulated Printer", { copies: 2 });
+  await setPrinterDefaults("Simulated Printer", {
+    simple: { copies: 3, paperSize: "Letter" },
+  });
+  try {
+    if ((await getPrinterDefaults("RECEIPT")).copies !== "3") {
+      throw new Error("Printer defaults should be found through aliases");
+    }
+    const { printerName, options, sources } = await resolveJobOptions(
+      "RECEIPT",
+      { quality: "draft" }
+    );
+    if (
+      printerName !== "Simulated Printer" ||
+      options.copies !== "2" ||
+      sources.copies !== "alias" ||
+      sources["media-size"] !== "printer" ||
+      sources["job-quality"] !== "call" ||
+      sources["print-quality"] !== "derived"
+    ) {
+      throw new Error(
+        `Unexpected resolved options: ${JSON.stringify({ options, sources })}`
+      );
+    }
+
+    const error = await resolveJobOptions("Missing Printer").catch(
+      (error: PrinterTypes.CodedError) => error
+    );
+    if (error?.code !== "PrinterNotFound") {
+      throw new Error("Unknown printers should be rejected");
+    }
+  } finally {
+    await setPrinterDefaults("Simulated Printer", null);
+    await removePrinterAlias("RECEIPT");
+  }
+});
+
+test("setContentPolicy checks the text of documents before printing", async () => {
+  if (!isSimulationMode) {
+    console.log("Skipping content policy test in real printing mode");
+    return;
+  }
+  const printer = await requireSimulatedPrinter();
+  const postscript = new TextEncoder().encode(
+    "%!PS\n72 700 moveto (Card 4111 1111 1111 1111) show showpage\n"
+  );
+  const text = await extractText(postscript);
+  if (text !== "Card 4111 1111 1111 1111\n") {
+    throw new Error(`Unexpected extracted text: ${JSON.stringify(text)}`);
+  }
+
+  const checked: Array<string | undefined> = [];
+  await setContentPolicy(({ printerName, text }) => {
+    checked.push(printerName);
+    return /(?:\d[ -]?){13,16}/.test(text ?? "") ? "Card number" : true;
+  });
+  try {
+    const error = await printer
+      .printBytes(postscript)
+      .catch((error: PrinterTypes.CodedError) => error);
+    if (
+      !(error instanceof ContentRejectedError) ||
+      error.code !== "ContentRejected" ||
+      error.message !== "Card number"
+    ) {
+      throw new Error(`Expected ContentRejectedError, got ${error}`);
+    }
+    await printer.printBytes(new TextEncoder().encode("Order 42"));
+    if (checked.length !== 2 || checked[0] !== "Simulated Printer") {
+      throw new Error(`Unexpected policy calls: ${JSON.stringify(checked)}`);
+    }
+  } finally {
+    await setContentPolicy(null);
+  }
+});
+
+test("setPolicyRules rejects jobs that break a rule", async () => {
+  if (!isSimulationMode) {
+    console.log("Skipping policy rule test in real printing mode");
+    return;
+  }
+  const printer = await requireSimulatedPrinter();
+
+  await setPolicyRules([
+    { id: "no-secrets", bannedKeywords: ["confidential"] },
+    { id: "documents-only", allowedFormats: ["application/*", "text/*"] },
+  ]);
+  try {
+    const rules = await getPolicyRules();
+    if (rules.length !== 2 || rules[0].id !== "no-secrets") {
+      throw new Error(`Unexpected rules: ${JSON.stringify(rules)}`);
+    }
+
+    const error = await printer
+      .printBytes(new TextEncoder().encode("CONFIDENTIAL: salaries"))
+      .catch((error: PrinterTypes.CodedError) => error);
+    if (
+      !(error instanceof PolicyViolationError) ||
+      error.code !== "PolicyViolation" ||
+      error.rule !== "no-secrets"
+    ) {
+      throw new Error(`Expected PolicyViolationError, got ${error}`);
+    }
+
+    const image = await printer
+      .printBytes(new Uint8Array([0x89, 0x50, 0x4e, 0x47]), {
+        raw: { "document-format": "image/png" },
+      })
+      .catch((error: PrinterTypes.CodedError) => error);
+    if (
+      !(image instanceof PolicyViolationError) ||
+      image.rule !== "documents-only"
+    ) {
+      throw new Error(`Expected the format rule to apply, got ${image}`);
+    }
+
+    await printer.printBytes(new TextEncoder().encode("Lunch menu"));
+  } finally {
+    await setPolicyRules([]);
+  }
+});
+
+test("getPrintersByLocation and getPrintersByTag find printers", async () => {
+  if (!isSimulationMode) {
+    console.log("Skipping printer tag test in real printing mode");
+    return;
+  }
+
+  const byLocation = await getPrintersByLocation("test loc");
+  if (!byLocation.some(printer => printer.name === "Simulated Printer")) {
+    throw new Error("Locations should match case-insensitively");
+  }
+
+  await tagPrinter("Simulated Printer", ["Floor-3", "color"]);
+  try {
+    const tags = await getPrinterTags("Simulated Printer");
+    if (JSON.stringify(tags) !== JSON.stringify(["Floor-3", "color"])) {
+      throw new Error(`Unexpected tags: ${JSON.stringify(tags)}`);
+    }
+    const byTag = await getPrintersByTag("floor-3");
+    if (byTag.length !== 1 || byTag[0].name !== "Simulated Printer") {
+      throw new Error("Tags should match case-insensitively");
+    }
+    await untagPrinter("Simulated Printer", ["color"]);
+    if ((await getPrinterTags("Simulated Printer")).includes("color")) {
+      throw new Error("Removed tags should be gone");
+    }
+  } finally {
+    await untagPrinter("Simulated Printer");
+  }
+  if ((await getPrintersByTag("floor-3")).length !== 0) {
+    throw new Error("Untagged printers should not be found by tag");
+  }
+});
+
+test(`${runtimeName}: should reload printer alias files`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+
+  const { mkdtempSync, rmSync, writeFileSync } = await import("node:fs");
+  const { tmpdir } = await import("node:os");
+  const dir = mkdtempSync(joinPath(tmpdir(), "printers-js-aliases-"));
+  const path = joinPath(dir, "aliases.json");
+  const events: PrinterTypes.ConfigChangedEvent[] = [];
+  const stop = await onConfigChanged(event => events.push(event));
+  try {
+    await setPrinterAliasFile(path);
+    writeFileSync(
+      path,
+      JSON.stringify({
+        aliases: { LABEL: { printer: "Simulated Printer" } },
+      })
+    );
+    if (!(await reloadConfig()) || (await reloadConfig())) {
+      throw new Error("Reloading should report whether anything changed");
+    }
+    if (!(await printerExists("LABEL"))) {
+      throw new Error("Reloaded aliases should take effect");
+    }
+
+    await new Promise(resolve => setTimeout(resolve, 500));
+    if (!events.some(event => event.aliases.includes("LABEL"))) {
+      throw new Error("Reloading should emit a configChanged event");
+    }
+  } finally {
+    stop();
+    await setPrinterAliasFile(null);
+    await removePrinterAlias("LABEL");
+    rmSync(dir, { recursive: true, force: true });
+  }
+});
+
+test(`${runtimeName}: should define remote printers`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+
+  await defineRemotePrinter("Warehouse", {
+    url: "http://127.0.0.1:8632",
+    printer: "Label Printer",
+    authToken: "secret",
+  });
+  try {
+    const remote = (await getRemotePrinters()).find(
+      printer => printer.name === "Warehouse"
+    );
+    if (remote?.printer !== "Label Printer" || "authToken" in remote) {
+      throw new Error("Remote printers should be listed without tokens");
+    }
+    if (!(await printerExists("Warehouse"))) {
+      throw new Error("Remote printers should be accepted as printer names");
+    }
+    const rejected = await defineRemotePrinter("Local", {
+      url: "usb://printer",
+    }).then(
+      () => false,
+      () => true
+    );
+    if (!rejected) {
+      throw new Error("Non-network remote URLs should be rejected");
+    }
+    if (remote.compression !== "none") {
+      throw new Error("Remote printers should send documents uncompressed");
+    }
+    const compressed = await defineRemotePrinter("Compressed", {
+      url: "http://127.0.0.1:8632",
+      compression: "gzip",
+    }).then(
+      () => true,
+      () => false
+    );
+    if (compressed) {
+      throw new Error("Compression should be rejected for print servers");
+    }
+    if (remote.raster !== "none") {
+      throw new Error("Remote printers should send documents unconverted");
+    }
+    const rasterized = await defineRemotePrinter("Rasterized", {
+      url: "http://127.0.0.1:8632",
+      raster: "auto",
+    }).then(
+      () => true,
+      () => false
+    );
+    if (rasterized) {
+      throw new Error("Raster conversion should be rejected for print servers");
+    }
+  } finally {
+    await removeRemotePrinter("Warehouse");
+  }
+  if (await printerExists("Warehouse")) {
+    throw new Error("Removed remote printers should no longer exist");
+  }
+});
+
+test(`${runtimeName}: should set printer credentials`, async () => {
+  await setPrinterCredentials("Simulated Printer", {
+    username: "alice",
+    password: "s3cret",
+  });
+  await setPrinterCredentials(null, { method: "negotiate" });
+
+  const missingPassword = await setPrinterCredentials("Simulated Printer", {
+    username: "alice",
+  }).then(
+    () => false,
+    () => true
+  );
+  if (!missingPassword) {
+    throw new Error("Basic credentials without a password should be rejected");
+  }
+
+  await setPrinterCredentials("Simulated Printer", null);
+  await setPrinterCredentials(null, null);
+});
+
+test(`${runtimeName}: should set TLS options per destination`, async () => {
+  try {
+    await setTlsOptions("printer.local:631", { insecureSkipVerify: true });
+  } catch (error) {
+    // The native module may be built without the tls feature
+    if (String(error).includes("not available")) return;
+    throw error;
+  }
+
+  const missingCa = await setTlsOptions(null, {
+    caFile: "/nonexistent/ca.pem",
+  }).then(
+    () => false,
+    () => true
+  );
+  if (!missingCa) {
+    throw new Error("Unreadable CA files should be rejected");
+  }
+  await setTlsOptions("printer.local:631", null);
+});
+
+test(`${runtimeName}: should wake printers with a MAC address`, async () => {
+  const printer = await requireSimulatedPrinter();
+
+  const withoutMac = await printer.wake().then(
+    () => false,
+    () => true
+  );
+  if (!withoutMac) {
+    throw new Error("Waking a printer without a MAC address should fail");
+  }
+
+  await setPrinterMacAddress(printer, "00:1b:63:84:45:e6");
+  try {
+    if (!(await printer.wake({ timeoutMs: 1000 }))) {
+      throw new Error("Simulated printer should wake");
+    }
+    const status = await printer.getPowerStatus();
+    if (!status.reachable || status.asleep !== false) {
+      throw new Error(`Unexpected power status: ${JSON.stringify(status)}`);
+    }
+  } finally {
+    await setPrinterMacAddress(printer, null);
+  }
+
+  const invalid = await setPrinterMacAddress(printer, "not-a-mac").then(
+    () => false,
+    () => true
+  );
+  if (!invalid) throw new Error("Invalid MAC addresses should be rejected");
+});
+
+test(`${runtimeName}: should send label printer commands`, async () => {
+  const printer = await requireSimulatedPrinter();
+
+  const calibrateJob = await printer.calibrateMedia({ language: "zpl" });
+  const feedJob = await printer.feed(2, { language: "escpos" });
+  if (calibrateJob <= 0 || feedJob <= 0) {
+    throw new Error("Label commands should be submitted as jobs");
+  }
+
+  // The simulated printer doesn't report a label language
+  const error = await printer.calibrateMedia().then(
+    () => null,
+    e => e
+  );
+  if (!(error instanceof UnsupportedOptionError)) {
+    throw new Error(`Expected UnsupportedOptionError, got ${error}`);
+  }
+  const tooMany = await printer.feed(101, { language: "zpl" }).then(
+    () => false,
+    () => true
+  );
+  if (!tooMany) throw new Error("Feeding over 100 labels should fail");
+
+  const status = await printer.getLabelStatus();
+  if (status.labelOut || status.stateReasons.length !== 0) {
+    throw new Error(`Unexpected label status: ${JSON.stringify(status)}`);
+  }
+});
+
+test(`${runtimeName}: should install and remove printers`, async () => {
+  const drivers = await listDrivers();
+  const driver = drivers.find(d => d.name === "Generic / Text Only");
+  if (!driver) throw new Error("Simulated drivers should be listed");
+
+  await installPrinter({
+    name: "Receiving Dock",
+    port: "FILE:",
+    driver: driver.name,
+    location: "Building 2",
+  });
+  try {
+    const printer = await getPrinterByName("Receiving Dock");
+    if (!printer || printer.portName !== "FILE:") {
+      throw new Error("Installed printer should be found with its port");
+    }
+  } finally {
+    await removePrinter("Receiving Dock");
+  }
+  if (await printerExists("Receiving Dock")) {
+    throw new Error("Removed printers should no longer exist");
+  }
+
+  const unknownDriver = await installPrinter({
+    name: "Receiving Dock",
+    port: "FILE:",
+    driver: "Missing Driver",
+  }).then(
+    () => false,
+    () => true
+  );
+  if (!unknownDriver) throw new Error("Unknown drivers should be rejected");
+});
+
+test(`${runtimeName}: should administer CUPS queues`, async () => {
+  await addCupsQueue({
+    name: "Front_Desk",
+    deviceUri: "ipp://10.0.0.5/ipp/print",
+    model: "everywhere",
+  });
+  try {
+    await setQueueEnabled("Front_Desk", false);
+    const printer = await getPrinterByName("Front_Desk");
+    if (!printer || printer.state !== "paused") {
+      throw new Error("Disabled queues should be paused");
+    }
+  } finally {
+    await deleteCupsQueue("Front_Desk");
+  }
+
+  const invalidName = await addCupsQueue({
+    name: "Front Desk",
+    deviceUri: "ipp://10.0.0.5/ipp/print",
+  }).then(
+    () => false,
+    () => true
+  );
+  if (!invalidName) throw new Error("Queue names with spaces should fail");
+});
+
+test(`${runtimeName}: should change printer access control`, async () => {
+  const printer = await requireSimulatedPrinter();
+
+  const updated = await printer.setAccessControl({
+    shared: true,
+    allowedUsers: ["alice"],
+  });
+  if (!updated.shared || updated.allowedUsers?.[0] !== "alice") {
+    throw new Error(`Unexpected access control: ${JSON.stringify(updated)}`);
+  }
+
+  const both = await printer
+    .setAccessControl({ allowedUsers: ["alice"], deniedUsers: ["bob"] })
+    .then(
+      () => false,
+      () => true
+    );
+  if (!both) throw new Error("Allow and deny lists together should fail");
+
+  const reset = await printer.setAccessControl({
+    shared: false,
+    allowedUsers: [],
+  });
+  if (reset.shared || reset.allowedUsers?.length) {
+    throw new Error("Access control should reset");
+  }
+});
+
+test(`${runtimeName}: should dry-run print submissions`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+
+  const printer = await requireSimulatedPrinter();
+  const jobsBefore = (await printer.getAllJobs()).length;
+  const result = await printer.printFile(TEST_FILES.PDF, {
+    simple: { copies: 2 },
+    raw: { sides: "booklet" },
+    dryRun: true,
+  });
+  if (result.printerName !== "Simulated Printer") {
+    throw new Error("Dry runs should resolve the target printer");
+  }
+  if (result.options.copies !== "2" || !result.capabilitiesChecked) {
+    throw new Error("Dry runs should return the resolved options");
+  }
+  if (!result.unsupportedOptions.some(option => option.startsWith("sides="))) {
+    throw new Error("Dry runs should report unsupported options");
+  }
+  if ((await printer.getAllJobs()).length !== jobsBefore) {
+    throw new Error("Dry runs should not submit a job");
+  }
+
+  let rejected = false;
+  try {
+    await printer.printBytes(new TextEncoder().encode("%PDF-1.4\n"), {
+      documentFormat: "application/pdf",
+      dryRun: true,
+    });
+  } catch (error) {
+    rejected = error instanceof InvalidDocumentError;
+  }
+  if (!rejected) {
+    throw new Error("Dry runs should validate the document");
+  }
+});
+
+test(`${runtimeName}: should estimate print jobs`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+
+  const estimate = await estimateJob("Simulated Printer", TEST_FILES.PDF, {
+    simple: { copies: 3, duplex: true, color: false },
+  });
+  if (estimate.copies !== 3 || !estimate.duplex) {
+    throw new Error("Estimates should account for copies and duplex");
+  }
+  if (estimate.color !== "monochrome") {
+    throw new Error("Monochrome options should be estimated as monochrome");
+  }
+
+  const bytes = await estimateJob(
+    "Simulated Printer",
+    new TextEncoder().encode("plain text")
+  );
+  if (bytes.color !== "color" || bytes.copies !== 1) {
+    throw new Error("Simulated jobs should default to color, single copy");
+  }
+});
+
+test(`${runtimeName}: should print a test page`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+
+  const printer = await requireSimulatedPrinter();
+  const jobId = await printer.printTestPage({ waitForCompletion: false });
+  const job = await printer.getJob(jobId);
+  if (job?.name !== "Test Page" || job.mediaType !== "application/pdf") {
+    throw new Error("Test pages should be printed as PDF jobs named Test Page");
+  }
+});
+
+test(`${runtimeName}: should render and print templates`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+
+  await registerTemplate("shipping-label", "^XA^FD{{sku}} x{{ qty }}^FS^XZ", {
+    format: "zpl",
+  });
+  try {
+    const template = (await getTemplates()).find(
+      t => t.name === "shipping-label"
+    );
+    if (template?.variables.join(",") !== "qty,sku") {
+      throw new Error("Registered templates should list their variables");
+    }
+    const rendered = await renderTemplate("shipping-label", {
+      sku: "A-1",
+      qty: 3,
+    });
+    if (rendered !== "^XA^FDA-1 x3^FS^XZ") {
+      throw new Error(`Unexpected rendered template: ${rendered}`);
+    }
+
+    let rejected = false;
+    try {
+      await renderTemplate("shipping-label", { sku: "^XZ", qty: 1 });
+    } catch {
+      rejected = true;
+    }
+    if (!rejected) {
+      throw new Error("ZPL commands in values should be rejected");
+    }
+
+    const jobId = await printTemplate(
+      "Simulated Printer",
+      "shipping-label",
+      { sku: "A-1", qty: 3 },
+      { waitForCompletion: false }
+    );
+    const printer = await getPrinterByName("Simulated Printer");
+    const job = await printer?.getJob(jobId);
+    if (job?.name !== "shipping-label") {
+      throw new Error("Template jobs should be named after the template");
+    }
+  } finally {
+    await unregisterTemplate("shipping-label");
+  }
+});
+
+test(`${runtimeName}: should fail over in printer pools`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+
+  const pool = await createPrinterPool([
+    "Missing Printer",
+    "Simulated Printer",
+  ]);
+  const events: { from: string; to?: string }[] = [];
+  pool.on("failover", event => events.push(event));
+  try {
+    const jobId = await pool.printFile(TEST_FILES.PDF, {
+      waitForCompletion: false,
+    });
+    const job = await getPrinterByName("Simulated Printer").then(printer =>
+      printer?.getJob(jobId)
+    );
+    if (job?.printerName !== "Simulated Printer") {
+      throw new Error("Jobs should go to the first online printer");
+    }
+    if (events.length !== 1 || events[0].from !== "Missing Printer") {
+      throw new Error("Skipping a printer should emit a failover event");
+    }
+  } finally {
+    pool.close();
+  }
+});
+
+test(`${runtimeName}: should route pool jobs by queue depth`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+
+  let rejected = false;
+  try {
+    await createPrinterPool(["Simulated Printer"], { maxQueueDepth: 0 });
+  } catch {
+    rejected = true;
+  }
+  if (!rejected) {
+    throw new Error("maxQueueDepth should be a positive integer");
+  }
+
+  const pool = await createPrinterPool(
+    ["Missing Printer", "Simulated Printer"],
+    { strategy: "least-busy", maxQueueDepth: 1000 }
+  );
+  try {
+    const jobId = await pool.printFile(TEST_FILES.PDF, {
+      waitForCompletion: false,
+    });
+    const job = await getPrinterByName("Simulated Printer").then(printer =>
+      printer?.getJob(jobId)
+    );
+    if (job?.printerName !== "Simulated Printer") {
+      throw new Error("Jobs should go to an available printer");
+    }
+  } finally {
+    pool.close();
+  }
+});
+
+test(`${runtimeName}: should reprint finished jobs`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+  const printer = await requireSimulatedPrinter();
+
+  const jobId = await printer.printBytes(new TextEncoder().encode("%!PS"), {
+    keepSpoolFile: true,
+  });
+  const reprintId = await reprintJob(jobId);
+  const reprint = await printer.getJob(reprintId);
+  if (reprintId === jobId || reprint?.reprintOf !== jobId) {
+    throw new Error("Reprints should be new jobs linked to the original");
+  }
+});
+
+test(`${runtimeName}: should fail and migrate jobs of removed printers`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+  const printer = await requireSimulatedPrinter();
+  await addCupsQueue({
+    name: "Loading_Dock",
+    deviceUri: "ipp://10.0.0.9/ipp/print",
+  });
+  const dock = await getPrinterByName("Loading_Dock");
+  if (!dock) {
+    throw new Error("Should have a printer named 'Loading_Dock'");
+  }
+
+  // The job stays queued until the running job completes
+  const data = new TextEncoder().encode("%!PS");
+  const busyId = await printer.printBytes(data, { waitForCompletion: false });
+  const jobId = await dock.printBytes(data, {
+    dependsOn: [busyId],
+    keepSpoolFile: true,
+    waitForCompletion: false,
+  });
+  await removePrinter("Loading_Dock");
+  const job = await dock.getJob(jobId);
+  if (job?.state !== "cancelled" || !job.printerRemoved) {
+    throw new Error("Queued jobs should fail when their printer is removed");
+  }
+
+  const migration = await migrateJobs("Loading_Dock", "Simulated Printer");
+  const requeued = migration.requeued[0];
+  if (migration.requeued.length !== 1 || requeued.jobId !== jobId) {
+    throw new Error("Failed jobs should be reprinted on the new printer");
+  }
+  const reprint = await printer.getJob(requeued.newJobId);
+  if (reprint?.reprintOf !== jobId) {
+    throw new Error("Requeued jobs should be reprints of the failed job");
+  }
+
+  const samePrinter = await migrateJobs(
+    "Simulated Printer",
+    "Simulated Printer"
+  ).then(
+    () => false,
+    () => true
+  );
+  if (!samePrinter) {
+    throw new Error("Migrating jobs to the same printer should fail");
+  }
+});
+
+test(`${runtimeName}: should format error messages by code`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+
+  const migrateError = () =>
+    migrateJobs("Simulated Printer", "Missing Printer").then(
+      () => {
+        throw new Error("Migrating jobs to a missing printer should fail");
+      },
+      (error: PrinterTypes.CodedError) => error
+    );
+
+  let error = await migrateError();
+  if (error.code !== "PrinterNotFound" || error.detail !== undefined) {
+    throw new Error(`Expected a PrinterNotFound error, got ${error.code}`);
+  }
+  const nativeMessage = error.message;
+  if (nativeMessage.startsWith("[")) {
+    throw new Error("Error codes should be stripped from messages");
+  }
+
+  try {
+    await setErrorMessageFormatter({
+      PrinterNotFound: "Drucker nicht gefunden",
+    });
+    error = await migrateError();
+    if (error.message !== "Drucker nicht gefunden") {
+      throw new Error(`Expected the mapped message, got ${error.message}`);
+    }
+    if (error.detail !== nativeMessage) {
+      throw new Error("The library's message should be kept in detail");
+    }
+
+    await setErrorMessageFormatter(({ defaultMessage }) => defaultMessage);
+    error = await migrateError();
+    if (error.message !== ERROR_MESSAGES.PrinterNotFound) {
+      throw new Error(`Expected the default message, got ${error.message}`);
+    }
+  } finally {
+    await setErrorMessageFormatter(null);
+  }
+  if ((await migrateError()).message !== nativeMessage) {
+    throw new Error("Resetting the formatter should restore the messages");
+  }
+});
+
+test(`${runtimeName}: should query jobs with paging`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+  const printer = await requireSimulatedPrinter();
+
+  const since = new Date(Date.now() - 1000);
+  const data = new TextEncoder().encode("%!PS");
+  const ids: number[] = [];
+  for (let i = 0; i < 3; i++) {
+    ids.push(await printer.printBytes(data));
+  }
+
+  const page = await queryJobs({
+    printerName: "Simulated Printer",
+    states: ["completed"],
+    since,
+    sort: "created-asc",
+    offset: 1,
+    limit: 1,
+  });
+  if (page.total < 3 || page.jobs.length !== 1) {
+    throw new Error("queryJobs should return one page and the total");
+  }
+  const newest = await queryJobs({ since, limit: 1 });
+  if (newest.jobs[0]?.id !== ids[2]) {
+    throw new Error("Jobs should be sorted newest first by default");
+  }
+  const none = await queryJobs({ states: ["completed"], until: new Date(0) });
+  if (none.total !== 0) {
+    throw new Error("until should exclude later jobs");
+  }
+});
+
+test(`${runtimeName}: should find jobs by metadata`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+  const printer = await requireSimulatedPrinter();
+
+  const orderId = `order-${Date.now()}`;
+  const data = new TextEncoder().encode("%!PS");
+  const jobId = await printer.printBytes(data, {
+    metadata: { orderId, customerId: "42" },
+  });
+  await printer.printBytes(data, { metadata: { orderId: "other" } });
+
+  const job = await printer.getJob(jobId);
+  if (job?.metadata?.customerId !== "42") {
+    throw new Error("Jobs should carry their metadata");
+  }
+  const found = await findJobs({ metadata: { orderId } });
+  if (found.length !== 1 || found[0].id !== jobId) {
+    throw new Error("findJobs should match on metadata");
+  }
+  const missing = await findJobs({ metadata: { orderId, customerId: "7" } });
+  if (missing.length !== 0) {
+    throw new Error("Every metadata value given should have to match");
+  }
+});
+
+test(`${runtimeName}: should post job events to a webhook`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+  const printer = await requireSimulatedPrinter();
+
+  const { createServer } = await import("node:http");
+  const { createHmac } = await import("node:crypto");
+  const received: { event: string; job: { id: number } }[] = [];
+  let signaturesValid = true;
+  const server = createServer((request, response) => {
+    const chunks: Uint8Array[] = [];
+    request.on("data", chunk => chunks.push(chunk));
+    request.on("end", () => {
+      const body = Buffer.concat(chunks).toString("utf8");
+      const expected =
+        "sha256=" + createHmac("sha256", "s3cret").update(body).digest("hex");
+      signaturesValid &&= request.headers["x-printers-signature"] === expected;
+      received.push(JSON.parse(body));
+      response.writeHead(204).end();
+    });
+  });
+  await new Promise<void>(resolve => server.listen(0, "127.0.0.1", resolve));
+  try {
+    const { port } = server.address() as { port: number };
+    await setWebhook({
+      url: `http://127.0.0.1:${port}/events`,
+      secret: "s3cret",
+      events: ["job.completed"],
+    });
+    const jobId = await printer.printBytes(new TextEncoder().encode("%!PS"));
+
+    const deadline = Date.now() + 5000;
+    while (!received.some(event => event.job.id === jobId)) {
+      if (Date.now() > deadline) {
+        throw new Error("The webhook should receive the job's completion");
+      }
+      await new Promise(resolve => setTimeout(resolve, 50));
+    }
+    if (!signaturesValid || received.some(e => e.event !== "job.completed")) {
+      throw new Error("Webhook events should be filtered and signed");
+    }
+  } finally {
+    await setWebhook(null);
+    server.close();
+  }
+
+  let rejected = false;
+  try {
+    await setWebhook({ url: "https://hooks.example.com/print" });
+  } catch {
+    rejected = true;
+  }
+  if (!rejected) {
+    throw new Error("https webhooks should be rejected");
+  }
+});
+
+test(`${runtimeName}: should export job history`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+  const printer = await requireSimulatedPrinter();
+
+  const { mkdtempSync, readFileSync, rmSync } = await import("node:fs");
+  const { tmpdir } = await import("node:os");
+  const dir = mkdtempSync(joinPath(tmpdir(), "printers-js-export-"));
+  try {
+    const since = new Date();
+    const data = new TextEncoder().encode("%!PS");
+    const jobId = await printer.printBytes(data, {
+      metadata: { batch: "export" },
+    });
+
+    const jsonl = joinPath(dir, "history.jsonl");
+    if ((await exportJobHistory(jsonl, { since })) !== 1) {
+      throw new Error("Only jobs created since the start time should export");
+    }
+    const record = JSON.parse(readFileSync(jsonl, "utf8").trim());
+    if (record.id !== jobId || record.metadata?.batch !== "export") {
+      throw new Error("JSONL records should have the job's fields");
+    }
+
+    const csv = joinPath(dir, "history.csv");
+    const written = await exportJobHistory(csv, { format: "csv" });
+    const rows = readFileSync(csv, "utf8").trim().split("\r\n");
+    if (
+      !rows[0].startsWith("id,name,printerName,state,") ||
+      rows.length !== written + 1
+    ) {
+      throw new Error("CSV should have a header row and one row per job");
+    }
+  } finally {
+    rmSync(dir, { recursive: true, force: true });
+  }
+});
+
+test(`${runtimeName}: should keep a tamper-evident audit log`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+  const printer = await requireSimulatedPrinter();
+
+  const { mkdtempSync, readFileSync, rmSync, writeFileSync } = await import(
+    "node:fs"
+  );
+  const { tmpdir } = await import("node:os");
+  const dir = mkdtempSync(joinPath(tmpdir(), "printers-js-audit-"));
+  const auditLog = joinPath(dir, "audit.jsonl");
+  try {
+    await configure({ auditLog });
+    if ((await getConfig()).auditLog !== auditLog) {
+      throw new Error("getConfig should report the audit log");
+    }
+    const data = new TextEncoder().encode("%!PS");
+    const jobId = await printer.printBytes(data, { jobName: "Audited" });
+
+    const verification = await verifyAuditLog();
+    if (!verification.valid || verification.records < 1) {
+      throw new Error("A freshly written audit log should verify");
+    }
+    const contents = readFileSync(auditLog, "utf8");
+    const record = JSON.parse(contents.trim().split("\n")[0]);
+    if (record.job_id !== jobId || record.job_name !== "Audited") {
+      throw new Error("Audit records should identify the job");
+    }
+
+    await configure({ auditLog: "" });
+    writeFileSync(auditLog, contents.replace("Audited", "Edited"));
+    const tampered = await verifyAuditLog(auditLog);
+    if (tampered.valid || tampered.firstInvalidLine !== 1) {
+      throw new Error("An edited record should fail verification");
+    }
+  } finally {
+    await configure({ auditLog: "" });
+    rmSync(dir, { recursive: true, force: true });
+  }
+});
+
+test(`${runtimeName}: should reserve job IDs from a job registry`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+  const printer = await requireSimulatedPrinter();
+
+  const { mkdtempSync, rmSync } = await import("node:fs");
+  const { tmpdir } = await import("node:os");
+  const { pid } = await import("node:process");
+  const dir = mkdtempSync(joinPath(tmpdir(), "printers-js-registry-"));
+  const jobRegistry = joinPath(dir, "jobs.registry");
+  try {
+    let rejected = false;
+    try {
+      await getJobProcess(1);
+    } catch {
+      rejected = true;
+    }
+    if (!rejected) {
+      throw new Error("getJobProcess should throw without a job registry");
+    }
+
+    await configure({ jobRegistry });
+    if ((await getConfig()).jobRegistry !== jobRegistry) {
+      throw new Error("getConfig should report the job registry");
+    }
+    const jobId = await printer.printBytes(new TextEncoder().encode("%!PS"));
+    if ((await getJobProcess(jobId)) !== pid) {
+      throw new Error("The registry should attribute the job to this process");
+    }
+    if ((await getJobProcess(1)) !== null) {
+      throw new Error("Unreserved IDs should have no process");
+    }
+  } finally {
+    await configure({ jobRegistry: "" });
+    rmSync(dir, { recursive: true, force: true });
+  }
+});
+
+test(`${runtimeName}: should redact job names in privacy mode`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+  const printer = await requireSimulatedPrinter();
+
+  const data = new TextEncoder().encode("%!PS");
+  try {
+    await configure({ redactJobData: true });
+    if (!(await getConfig()).redactJobData) {
+      throw new Error("getConfig should report redactJobData");
+    }
+    const redacted = await printer.printBytes(data, {
+      jobName: "Medical leave - J. Doe",
+    });
+    const kept = await printer.printBytes(data, {
+      jobName: "Lunch menu",
+      redact: false,
+    });
+    const name = (await printer.getJob(redacted))?.name ?? "";
+    if (!name.startsWith("redacted-") || name.includes("Doe")) {
+      throw new Error("Job names should be replaced with a hash");
+    }
+    if ((await printer.getJob(kept))?.name !== "Lunch menu") {
+      throw new Error("redact: false should keep the job name");
+    }
+
+    await setRedactionKey(new Uint8Array(16).fill(7));
+    const keyed = await printer.printBytes(data, {
+      jobName: "Medical leave - J. Doe",
+    });
+    const keyedName = (await printer.getJob(keyed))?.name;
+    if (keyedName === name) {
+      throw new Error("Hashes should depend on the redaction key");
+    }
+    let rejected = false;
+    await setRedactionKey(new Uint8Array(8)).catch(() => (rejected = true));
+    if (!rejected) {
+      throw new Error("setRedactionKey should reject short keys");
+    }
+  } finally {
+    await setRedactionKey(null);
+    await configure({ redactJobData: false });
+  }
+});
+
+test(`${runtimeName}: should record whether jobs were sent encrypted`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+  const printer = await requireSimulatedPrinter();
+
+  try {
+    await configure({ requireEncryptedTransport: true });
+    if (!(await getConfig()).requireEncryptedTransport) {
+      throw new Error("getConfig should report requireEncryptedTransport");
+    }
+    // The simulated printer is local, so the policy doesn't apply
+    const jobId = await printer.printBytes(new TextEncoder().encode("%!PS"), {
+      waitForCompletion: true,
+    });
+    const job = await printer.getJob(jobId);
+    if (job?.state !== "completed" || job.encryptedTransport !== undefined) {
+      throw new Error("Local jobs should print without a transport");
+    }
+  } finally {
+    await configure({ requireEncryptedTransport: false });
+  }
+});
+
+test(`${runtimeName}: should hold jobs for approval`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+  const printer = await requireSimulatedPrinter();
+
+  const data = new TextEncoder().encode("%!PS");
+  const approved = await printer.printBytes(data, { requireApproval: true });
+  const rejected = await printer.printBytes(data, { requireApproval: true });
+  const held = await getHeldJobs("Simulated Printer");
+  if (
+    held.map(job => job.id).join() !== [approved, rejected].join() ||
+    held.some(job => job.state !== "held-for-approval")
+  ) {
+    throw new Error("Jobs requiring approval should be held");
+  }
+
+  await rejectJob(rejected, "Not authorised");
+  const rejectedJob = await printer.getJob(rejected);
+  if (
+    rejectedJob?.state !== "cancelled" ||
+    rejectedJob.errorMessage !== "Not authorised"
+  ) {
+    throw new Error("Rejected jobs should be cancelled with the reason");
+  }
+  let threw = false;
+  try {
+    await approveJob(rejected);
+  } catch {
+    threw = true;
+  }
+  if (!threw) {
+    throw new Error("Only held jobs can be approved");
+  }
+
+  await approveJob(approved);
+  const approvedJob = await printer.getJob(approved);
+  if (approvedJob?.state === "held-for-approval") {
+    throw new Error("Approved jobs should be released to the printer");
+  }
+  if ((await getHeldJobs()).length !== 0) {
+    throw new Error("No jobs should be held after approval");
+  }
+});
+
+test(`${runtimeName}: should encrypt held job payloads`, async () => {
+  if (!isSimulationMode) {
+    return;
+  }
+  const printer = await requireSimulatedPrinter();
+
+  let threw = false;
+  try {
+    await setSpoolEncryptionKey(new Uint8Array(20));
+  } catch {
+    threw = true;
+  }
+  if (!threw) {
+    throw new Error("Keys other than 16 or 32 bytes should be rejected");
+  }
+
+  const key = new Uint8Array(32).fill(7);
+  const data = new TextEncoder().encode("%!PS");
+  try {
+    await setSpoolEncryptionKey(key);
+    const held = await printer.printBytes(data, { requireApproval: true });
+
+    // A different key can't decrypt the payload, so the job stays held
+    await setSpoolEncryptionKey(new Uint8Array(32));
+    threw = false;
+    try {
+      await approveJob(held);
+    } catch {
+      threw = true;
+    }
+    if (!threw || (await printer.getJob(held))?.state !== "held-for-approval") {
+      throw new Error("Jobs should stay held without the right key");
+    }
+
+    await setSpoolEncryptionKey(key);
+    await approveJob(held);
+    if ((await printer.getJob(held))?.state === "held-for-approval") {
+      throw new Error("Jobs should be released with the right key");
+    }
+  } finally {
+    await setSpoolEncryptionKey(null);
+  }
+});
+
+test(`${runtimeName}: should bound job history`, async () => {
+  try {
+    await configure({ maxJobHistory: 1, historyTtlSeconds: 3600 });
+    const config = await getConfig();
+    if (config.maxJobHistory !== 1 || config.historyTtlSeconds !== 3600) {
+      throw new Error("History limits should be stored in the config");
+    }
+
+    if (isSimulationMode) {
+      const printer = await requireSimulatedPrinter();
+      await printer.printFile(TEST_FILES.PDF, { jobName: "History 1" });
+      await printer.printFile(TEST_FILES.PDF, { jobName: "History 2" });
+
+      const history = await printer.getJobHistory();
+      if (history.length !== 1 || history[0].name !== "History 2") {
+        throw new Error(
+          `Only the latest job should be kept, got ${history.length} jobs`
+        );
+      }
+    }
+  } finally {
+    await initialize();
+  }
+});
+
+test(`${runtimeName}: should cancel stalled jobs`, async () => {
+  let rejected = false;
+  try {
+    await configure({ stallAction: "hang" as unknown as PrinterTypes.StallAction });
+  } catch {
+    rejected = true;
+  }
+  if (!rejected) {
+    throw new Error("Unknown stall actions should be rejected");
+  }
+  if (!isSimulationMode) {
+    return;
+  }
+
+  const events: PrinterTypes.JobStalledEvent[] = [];
+  const stop = await onJobStalled(event => events.push(event));
+  try {
+    await configure({
+      simulationDelayMs: 30000,
+      stallThresholdMs: 200,
+      stallAction: "cancel",
+    });
+    const config = await getConfig();
+    if (config.stallThresholdMs !== 200 || config.stallAction !== "cancel") {
+      throw new Error("Stall settings should be stored in the config");
+    }
+
+    const printer = await requireSimulatedPrinter();
+    const jobId = await printer.printBytes(new TextEncoder().encode("%!PS"), {
+      waitForCompletion: false,
+    });
+    const deadline = Date.now() + 3000;
+    while (!events.some(event => event.jobId === jobId)) {
+      if (Date.now() > deadline) {
+        throw new Error("Stalled job should be reported");
+      }
+      await new Promise(resolve => setTimeout(resolve, 100));
+    }
+
+    const job = await printer.getJob(jobId);
+    if (job?.state !== "cancelled" || !job.stalled) {
+      throw new Error(`Stalled job should be cancelled, got ${job?.state}`);
+    }
+  } finally {
+    stop();
+    await initialize();
   }
 });
 
-test(`${runtimeName}: simulated printer should have correct field values`, async () => {
-  // This test only runs in simulation mode
+test(`${runtimeName}: should refuse jobs beyond the queue limits`, async () => {
   if (!isSimulationMode) {
-    console.log("Skipping simulated printer test - not in simulation mode");
     return;
   }
 
-  const printers = await getAllPrinters();
-  if (printers.length === 0) {
-    throw new Error("Should have at least one simulated printer");
+  try {
+    await configure({ simulationDelayMs: 30000, maxQueuedJobsPerPrinter: 1 });
+    const printer = await requireSimulatedPrinter();
+    const data = new TextEncoder().encode("%!PS");
+    await printer.printBytes(data, { waitForCompletion: false });
+
+    let error: unknown;
+    try {
+      await printer.printBytes(data, { waitForCompletion: false });
+    } catch (e) {
+      error = e;
+    }
+    if (!(error instanceof QueueFullError)) {
+      throw new Error(`Expected QueueFullError, got ${error}`);
+    }
+  } finally {
+    await cancelAllJobs();
+    await initialize();
   }
+});
 
-  const simulatedPrinter = printers.find(p => p.name === "Simulated Printer");
-  if (!simulatedPrinter) {
-    throw new Error("Should have a printer named 'Simulated Printer'");
+test(`${runtimeName}: should record job state transitions`, async () => {
+  if (!isSimulationMode) {
+    return;
   }
 
-  // Test expected field values for simulated printer
-  const validSimulatedStates = ["idle", "printing", "paused"];
-  if (!validSimulatedStates.includes(simulatedPrinter.state || "")) {
-    throw new Error(
-      `Simulated printer state should be one of ${validSimulatedStates.join(", ")}, got '${simulatedPrinter.state}'`
-    );
+  try {
+    await configure({ simulationDelayMs: 100 });
+    const printer = await requireSimulatedPrinter();
+    const jobId = await printer.printBytes(new TextEncoder().encode("%!PS"));
+    const transitions = (await printer.getJob(jobId))?.transitions ?? [];
+    const path = transitions.map(({ from, to }) => `${from}->${to}`);
+    if (path.join() !== "pending->processing,processing->completed") {
+      throw new Error(`Unexpected transitions: ${path}`);
+    }
+    const [started, finished] = transitions;
+    if (finished.timestamp < started.timestamp) {
+      throw new Error("Transitions should be recorded oldest first");
+    }
+  } finally {
+    await initialize();
   }
+});
 
-  if (simulatedPrinter.isDefault !== true) {
-    throw new Error("Simulated printer should be marked as default");
+test(`${runtimeName}: should poll jobs at the configured interval`, async () => {
+  if (!isSimulationMode) {
+    return;
   }
 
-  if (simulatedPrinter.isShared !== false) {
-    throw new Error("Simulated printer should not be shared");
+  try {
+    await configure({
+      simulationDelayMs: 100,
+      jobPollIntervalMs: 200,
+      monitoringIntervalMs: 500,
+    });
+    const config = await getConfig();
+    if (config.jobPollIntervalMs !== 200) {
+      throw new Error(
+        `Expected jobPollIntervalMs 200, got ${config.jobPollIntervalMs}`
+      );
+    }
+    if (config.monitoringIntervalMs !== 500) {
+      throw new Error("monitoringIntervalMs should allow sub-second intervals");
+    }
+
+    const printer = await requireSimulatedPrinter();
+    const data = new TextEncoder().encode("%!PS");
+    const jobId = await printer.printBytes(data, { pollIntervalMs: 10 });
+    const job = await printer.getJob(jobId);
+    if (job?.state !== "completed") {
+      throw new Error(`Job should have completed, got ${job?.state}`);
+    }
+
+    const invalid = await printer
+      .printBytes(data, { pollIntervalMs: 0 })
+      .then(
+        () => false,
+        (error: Error) => error.message.includes("pollIntervalMs")
+      );
+    if (!invalid) {
+      throw new Error("A zero poll interval should be rejected");
+    }
+  } finally {
+    await initialize();
   }
+});
 
-  // Check that other fields exist and have reasonable values
+test(`${runtimeName}: should coalesce or reject duplicate jobs`, async () => {
+  const raw = printJobOptionsToRaw({
+    dedupeKey: "order-1",
+    dedupeWindowMs: 1000,
+    dedupeMode: "reject",
+  });
   if (
-    typeof simulatedPrinter.systemName !== "string" ||
-    !simulatedPrinter.systemName
+    raw["dedupe-key"] !== "order-1" ||
+    raw["dedupe-window-ms"] !== "1000" ||
+    raw["dedupe-mode"] !== "reject"
   ) {
-    throw new Error("Simulated printer should have a system name");
+    throw new Error(`Unexpected dedupe properties: ${JSON.stringify(raw)}`);
+  }
+  if (!isSimulationMode) {
+    return;
   }
 
-  if (
-    typeof simulatedPrinter.driverName !== "string" ||
-    !simulatedPrinter.driverName
-  ) {
-    throw new Error("Simulated printer should have a driver name");
+  const printer = await requireSimulatedPrinter();
+  const data = new TextEncoder().encode("%!PS");
+  const dedupeKey = `dedupe-${Date.now()}`;
+  const first = await printer.printBytes(data, {
+    dedupeKey,
+    waitForCompletion: false,
+  });
+  const second = await printer.printBytes(data, {
+    dedupeKey,
+    waitForCompletion: false,
+  });
+  if (first !== second) {
+    throw new Error("Duplicates should coalesce into the first job");
   }
 
-  console.log("Simulated printer fields validated successfully:");
-  console.log(`  - name: ${simulatedPrinter.name}`);
-  console.log(`  - state: ${simulatedPrinter.state}`);
-  console.log(`  - isDefault: ${simulatedPrinter.isDefault}`);
-  console.log(`  - isShared: ${simulatedPrinter.isShared}`);
-  console.log(`  - systemName: ${simulatedPrinter.systemName}`);
-  console.log(`  - driverName: ${simulatedPrinter.driverName}`);
+  let error: unknown;
+  try {
+    await printer.printBytes(data, {
+      dedupeKey,
+      dedupeMode: "reject",
+      waitForCompletion: false,
+    });
+  } catch (e) {
+    error = e;
+  }
+  if (!(error instanceof DuplicateJobError)) {
+    throw new Error(`Expected DuplicateJobError, got ${error}`);
+  }
+  await cancelAllJobs();
 });
 
-// CUPS Options Conversion Tests
-test(`${runtimeName}: should convert SimplePrintOptions to CUPS correctly`, () => {
-  if (typeof simpleToCUPS !== "function") {
-    throw new Error("simpleToCUPS function should be available");
+test(`${runtimeName}: should report job metrics`, async () => {
+  const before = await getMetricsSnapshot();
+  if (before.spoolLatency.buckets.length === 0) {
+    throw new Error("Spool latency histogram should have buckets");
   }
 
-  const simpleOptions = {
-    copies: 3,
-    duplex: true,
-    paperSize: "A4" as const,
-    quality: "high" as const,
-    color: false,
-    pageRange: "1-5,8",
-    jobName: "Test Job",
-    pagesPerSheet: 2 as const,
-    landscape: true,
-  };
-
-  const result = simpleToCUPS(simpleOptions);
-
-  // Verify all conversions
-  if (result.copies !== "3") throw new Error("copies conversion failed");
-  if (result.sides !== "two-sided-long-edge")
-    throw new Error("duplex conversion failed");
-  if (result["media-size"] !== "A4")
-    throw new Error("paperSize conversion failed");
-  if (result["print-quality"] !== "5")
-    throw new Error("quality conversion failed");
-  if (result["print-color-mode"] !== "monochrome")
-    throw new Error("color conversion failed");
-  if (result["page-ranges"] !== "1-5,8")
-    throw new Error("pageRange conversion failed");
-  if (result["job-name"] !== "Test Job")
-    throw new Error("jobName conversion failed");
-  if (result["number-up"] !== "2")
-    throw new Error("pagesPerSheet conversion failed");
-  if (result.landscape !== "true")
-    throw new Error("landscape conversion failed");
-});
-
-test(`${runtimeName}: should convert CUPSOptions to raw properties correctly`, () => {
-  if (typeof cupsToRaw !== "function") {
-    throw new Error("cupsToRaw function should be available");
+  const text = await getMetricsPrometheus();
+  if (!text.includes("# TYPE printers_jobs_submitted_total counter")) {
+    throw new Error("Prometheus output should declare the submitted counter");
   }
 
-  const cupsOptions = {
-    "job-name": "CUPS Test",
-    "job-priority": 50,
-    copies: 2,
-    collate: true,
-    "media-size": "Letter",
-    "fit-to-page": false,
-    "custom-option": "test-value",
-  };
+  if (!isSimulationMode) {
+    console.log("Skipping job metrics test - not in simulation mode");
+    return;
+  }
 
-  const result = cupsToRaw(cupsOptions);
+  const printer = await requireSimulatedPrinter();
+  await printer.printFile(TEST_FILES.PDF, { jobName: "Metrics Test" });
 
-  // Verify type conversions
-  if (result["job-name"] !== "CUPS Test")
-    throw new Error("string conversion failed");
-  if (result["job-priority"] !== "50")
-    throw new Error("number conversion failed");
-  if (result.copies !== "2") throw new Error("number to string failed");
-  if (result.collate !== "true")
-    throw new Error("boolean true conversion failed");
-  if (result["fit-to-page"] !== "false")
-    throw new Error("boolean false conversion failed");
-  if (result["custom-option"] !== "test-value")
-    throw new Error("custom option failed");
+  const after = await getMetricsSnapshot();
+  if (after.jobsSubmitted < before.jobsSubmitted + 1) {
+    throw new Error("jobsSubmitted should increase after printing");
+  }
+  if (after.jobsCompleted < before.jobsCompleted + 1) {
+    throw new Error("jobsCompleted should increase after completion");
+  }
+  if (after.spoolLatency.count < before.spoolLatency.count + 1) {
+    throw new Error("Spool latency should be recorded for completed jobs");
+  }
 });
 
-test(`${runtimeName}: should convert PrintJobOptions with precedence correctly`, () => {
-  if (typeof printJobOptionsToRaw !== "function") {
-    throw new Error("printJobOptionsToRaw function should be available");
+test(`${runtimeName}: should deliver native log events to callback`, async () => {
+  let rejected = false;
+  try {
+    await setLogLevel("verbose" as unknown as PrinterTypes.LogLevel);
+  } catch {
+    rejected = true;
+  }
+  if (!rejected) {
+    throw new Error("Unknown log level should be rejected");
   }
 
-  const options = {
-    jobName: "Top Level Job",
-    raw: {
-      "custom-raw": "raw-value",
-      copies: "1", // This should be overridden
-    },
-    simple: {
-      copies: 2, // This should override raw
-      quality: "normal" as const,
-    },
-    cups: {
-      "job-priority": 75,
-      "job-name": "CUPS Job Name", // This should override others
-    },
-  };
+  const records: PrinterTypes.LogRecord[] = [];
+  await setLogCallback(record => records.push(record));
+  await setLogLevel("info");
+  try {
+    if ((await getLogLevel()) !== "info") {
+      throw new Error("Log level should be info");
+    }
 
-  const result = printJobOptionsToRaw(options);
+    if (!isSimulationMode) {
+      console.log("Skipping log event test - not in simulation mode");
+      return;
+    }
 
-  // Test precedence: raw < simple < cups < jobName
-  if (result["custom-raw"] !== "raw-value")
-    throw new Error("raw option not preserved");
-  if (result.copies !== "2")
-    throw new Error("simple should override raw copies");
-  if (result["print-quality"] !== "4")
-    throw new Error("simple quality conversion failed");
-  if (result["job-priority"] !== "75") throw new Error("cups option not added");
-  if (result["job-name"] !== "Top Level Job")
-    throw new Error("top-level jobName should override all");
+    const printer = await requireSimulatedPrinter();
+    const jobId = await printer.printFile(TEST_FILES.PDF, {
+      jobName: "Logging Test",
+    });
+
+    // Buffered events are delivered on the next drain
+    await new Promise(resolve => setTimeout(resolve, 500));
+    const submitted = records.find(
+      record =>
+        record.message === "Job submitted" &&
+        record.fields.job_id === String(jobId)
+    );
+    if (!submitted || submitted.level !== "info") {
+      throw new Error("Should receive a 'Job submitted' info event");
+    }
+  } finally {
+    await setLogCallback(null);
+    await setLogLevel("off");
+  }
 });
 
 test(`${runtimeName}: should handle empty and undefined options correctly`, () => {
@@ -754,6 +3567,34 @@ test(`${runtimeName}: should return empty arrays for printer job tracking method
   }
 });
 
+test(`${runtimeName}: should track a printer's jobs through its methods`, async () => {
+  const printer = await requireSimulatedPrinter();
+
+  const jobId = await printer.printBytes(new TextEncoder().encode("Tracked"), {
+    jobName: "Tracked Job",
+  });
+  const job = await printer.getJob(jobId);
+  if (job?.id !== jobId || job.printerName !== "Simulated Printer") {
+    throw new Error(`printer.getJob should find job ${jobId}`);
+  }
+  if (!(await printer.getAllJobs()).some(j => j.id === jobId)) {
+    throw new Error("printer.getAllJobs should include the job");
+  }
+  if ((await printer.getActiveJobs()).some(j => j.id === jobId)) {
+    throw new Error("A completed job should not be active");
+  }
+  const history = await printer.getJobHistory(1);
+  if (history.length !== 1 || history[0].id !== jobId) {
+    throw new Error("printer.getJobHistory(1) should return the latest job");
+  }
+  if ((await printer.cleanupOldJobs(3600)) !== 0) {
+    throw new Error("Recent jobs should not be cleaned up");
+  }
+  if ((await printer.getJob(jobId)) === null) {
+    throw new Error("The job should be kept after cleanup");
+  }
+});
+
 test(`${runtimeName}: should create and track print jobs with new job format`, async () => {
   const printers = await getAllPrinters();
   if (printers.length === 0) {
@@ -808,6 +3649,7 @@ test(`${runtimeName}: should create and track print jobs with new job format`, a
       "processing",
       "cancelled",
       "completed",
+      "failed",
       "unknown",
     ];
     if (!validStates.includes(job.state)) {
@@ -1544,7 +4386,7 @@ test(`${runtimeName}: should track completed jobs in job history`, async () => {
       }
 
       // History jobs should be in completed states
-      const completedStates = ["completed", "cancelled"];
+      const completedStates = ["completed", "cancelled", "failed"];
       if (!completedStates.includes(job.state)) {
         throw new Error(
           `History job should be in completed state, got "${job.state}"`
@@ -1755,6 +4597,92 @@ test(`${runtimeName}: should handle raw bytes printing with correct media type`,
   }
 });
 
+test(`${runtimeName}: should attach to library-wide state`, async () => {
+  const before = await getAttachment();
+  if (before.environments < 1 || before.owned) {
+    throw new Error("No environment should own the library by default");
+  }
+
+  await attach();
+  await attach();
+  const attached = await getAttachment();
+  if (!attached.attached || !attached.owned) {
+    throw new Error("attach should make this environment the owner");
+  }
+  // The owner can still change library-wide state
+  await configure({ printerCacheTtlMs: 2000 });
+
+  if (!(await detach()) || (await detach())) {
+    throw new Error("detach should only succeed for the owner");
+  }
+  if ((await getAttachment()).owned) {
+    throw new Error("detach should release ownership");
+  }
+});
+
+test(`${runtimeName}: should guard library-wide state from worker threads`, async () => {
+  if (runtimeName !== "Node.js" || typeof process === "undefined") {
+    return;
+  }
+  const { Worker } = await import("node:worker_threads");
+  const suffix = { linux: "-gnu", win32: "-msvc" }[process.platform] ?? "";
+  const platform = `${process.platform}-${process.arch}${suffix}`;
+  const modulePath = new URL(`../../npm/${platform}/index.js`, import.meta.url);
+
+  // The worker loads the native module into its own environment and
+  // reports which calls were refused
+  const script = `
+    const { parentPort, workerData } = require("node:worker_threads");
+    import(workerData).then(native => {
+      const calls = {
+        setLogLevel: () => native.setLogLevel(native.getLogLevel()),
+        cancelAllJobs: () => native.cancelAllJobs("No Such Printer"),
+        setWebhook: () => native.setWebhook(null),
+        removeRemotePrinter: () => native.removeRemotePrinter("Missing"),
+        checkOwner: () => native.checkOwner(),
+      };
+      const refused = [];
+      for (const [name, call] of Object.entries(calls)) {
+        try {
+          call();
+        } catch {
+          refused.push(name);
+        }
+      }
+      parentPort.postMessage(refused);
+    });
+  `;
+  const runWorker = () =>
+    new Promise<string[]>((resolve, reject) => {
+      const worker = new Worker(script, {
+        eval: true,
+        workerData: modulePath.href,
+      });
+      worker.once("message", resolve);
+      worker.once("error", reject);
+    });
+
+  await attach();
+  try {
+    const refused = await runWorker();
+    const expected = [
+      "setLogLevel",
+      "cancelAllJobs",
+      "setWebhook",
+      "removeRemotePrinter",
+      "checkOwner",
+    ];
+    if (refused.join() !== expected.join()) {
+      throw new Error(`Workers should be refused, got: ${refused.join()}`);
+    }
+  } finally {
+    await detach();
+  }
+  if ((await runWorker()).length > 0) {
+    throw new Error("Workers may change state when no environment owns it");
+  }
+});
+
 // ===== setNativeModulePath / PRINTERS_JS_NATIVE_MODULE_PATH TESTS =====
 //
 // These tests verify the custom-native-module-path override
@@ -2003,3 +4931,51 @@ test(`${runtimeName}: PRINTERS_JS_NATIVE_MODULE_PATH env var loads the binary`,
   }
   console.log("✓ Env var override loaded successfully:", result.stdout.trim());
 });
+
+test(`${runtimeName}: setFallbackBackend prints when the native module is missing`, async () => {
+  const script = `
+    const printers = await import("${new URL("../index.ts", import.meta.url).href}");
+    printers.setNativeModulePath("/tmp/printers-js-does-not-exist.node");
+    const printed = [];
+    printers.setFallbackBackend({
+      printers: ["Bridge"],
+      print: job => {
+        if (job.options["job-name"] === "fail") throw new Error("bridge down");
+        printed.push(job.name + ":" + job.data.length);
+      },
+    });
+    const printer = await printers.getPrinterByName("Bridge");
+    const jobId = await printer.printBytes(new Uint8Array([1, 2, 3]), {
+      jobName: "Receipt",
+    });
+    const job = await printer.getJob(jobId);
+    let failed = false;
+    try {
+      await printer.printBytes(new Uint8Array([1]), { jobName: "fail" });
+    } catch {
+      failed = true;
+    }
+    const history = await printer.getJobHistory();
+    if (
+      printed.join() !== "Receipt:3" ||
+      job?.state !== "completed" ||
+      !failed ||
+      history[1]?.errorMessage !== "bridge down"
+    ) {
+      console.error("FAIL", printed, job, history);
+      process.exit(1);
+    }
+    console.log("OK fallback");
+  `;
+
+  const result = await runInSubprocess(script, {
+    PRINTERS_JS_SIMULATE: "true",
+  });
+
+  if (result.exitCode !== 0 || !result.stdout.includes("OK fallback")) {
+    throw new Error(
+      `Subprocess failed (exit ${result.exitCode}).\nstdout:\n${result.stdout}\nstderr:\n${result.stderr}`
+    );
+  }
+  console.log("✓ Fallback backend printed the job");
+});